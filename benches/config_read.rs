@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use rnacos::config::config_read_view::{ConfigReadValue, ConfigReadView};
+use rnacos::config::core::ConfigKey;
+use rnacos::raft::read_lease::{ReadLease, READ_LEASE_MS};
+use tokio::sync::{mpsc, oneshot};
+
+const CONFIG_COUNT: usize = 10000;
+const READ_THREADS: usize = 8;
+const READS_PER_THREAD: usize = 1000;
+const HOT_KEY_READERS: usize = 10000;
+const LATENCY_SAMPLES: usize = 10000;
+const FOLLOWER_COUNT: usize = 2;
+//模拟leader到follower一次确认的网络往返
+const FOLLOWER_RTT: Duration = Duration::from_millis(1);
+
+fn build_keys() -> Vec<ConfigKey> {
+    (0..CONFIG_COUNT)
+        .map(|i| ConfigKey::new(&format!("data_{}", i), "DEFAULT_GROUP", ""))
+        .collect()
+}
+
+fn build_value(i: usize) -> Arc<ConfigReadValue> {
+    Arc::new(ConfigReadValue {
+        content: Arc::new(format!("content_{}", i)),
+        md5: Arc::new(format!("md5_{}", i)),
+        config_type: None,
+        desc: None,
+        detected_type: None,
+        archive_uri: None,
+        last_modified: 0,
+    })
+}
+
+/// 与改造前ConfigActor串行读取近似的加锁读取
+fn bench_mutex_read(c: &mut Criterion) {
+    let keys = Arc::new(build_keys());
+    let map: HashMap<ConfigKey, Arc<ConfigReadValue>> = keys
+        .iter()
+        .enumerate()
+        .map(|(i, k)| (k.clone(), build_value(i)))
+        .collect();
+    let map = Arc::new(Mutex::new(map));
+    c.bench_function("config_read_mutex", |b| {
+        b.iter(|| {
+            let handles: Vec<_> = (0..READ_THREADS)
+                .map(|t| {
+                    let map = map.clone();
+                    let keys = keys.clone();
+                    thread::spawn(move || {
+                        for i in 0..READS_PER_THREAD {
+                            let key = &keys[(t * READS_PER_THREAD + i) % CONFIG_COUNT];
+                            let _ = map.lock().unwrap().get(key).cloned();
+                        }
+                    })
+                })
+                .collect();
+            for h in handles {
+                h.join().unwrap();
+            }
+        })
+    });
+}
+
+fn bench_read_view(c: &mut Criterion) {
+    let keys = Arc::new(build_keys());
+    let view = Arc::new(ConfigReadView::new());
+    view.store_all(
+        keys.iter()
+            .enumerate()
+            .map(|(i, k)| (k.clone(), build_value(i)))
+            .collect(),
+    );
+    c.bench_function("config_read_arc_swap", |b| {
+        b.iter(|| {
+            let handles: Vec<_> = (0..READ_THREADS)
+                .map(|t| {
+                    let view = view.clone();
+                    let keys = keys.clone();
+                    thread::spawn(move || {
+                        for i in 0..READS_PER_THREAD {
+                            let key = &keys[(t * READS_PER_THREAD + i) % CONFIG_COUNT];
+                            let _ = view.get(key);
+                        }
+                    })
+                })
+                .collect();
+            for h in handles {
+                h.join().unwrap();
+            }
+        })
+    });
+}
+
+/// 租约读取:leader在租约内只做本地租约检查,不需要多数派确认的网络往返
+fn bench_lease_read(c: &mut Criterion) {
+    let keys = Arc::new(build_keys());
+    let view = Arc::new(ConfigReadView::new());
+    view.store_all(
+        keys.iter()
+            .enumerate()
+            .map(|(i, k)| (k.clone(), build_value(i)))
+            .collect(),
+    );
+    let lease = Arc::new(ReadLease::new(Duration::from_millis(READ_LEASE_MS)));
+    c.bench_function("config_read_lease", |b| {
+        b.iter(|| {
+            lease.renew(Instant::now());
+            let handles: Vec<_> = (0..READ_THREADS)
+                .map(|t| {
+                    let view = view.clone();
+                    let keys = keys.clone();
+                    let lease = lease.clone();
+                    thread::spawn(move || {
+                        for i in 0..READS_PER_THREAD {
+                            let key = &keys[(t * READS_PER_THREAD + i) % CONFIG_COUNT];
+                            if lease.is_valid(Instant::now()) {
+                                let _ = view.get(key);
+                            }
+                        }
+                    })
+                })
+                .collect();
+            for h in handles {
+                h.join().unwrap();
+            }
+        })
+    });
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let idx = ((sorted.len() as f64 * p).ceil() as usize).clamp(1, sorted.len()) - 1;
+    sorted[idx]
+}
+
+///
+/// 模拟的多数派确认:向所有follower发送确认请求,收到多数派(含leader自身)应答即返回
+async fn confirm_by_quorum(followers: &[mpsc::Sender<oneshot::Sender<()>>]) {
+    let (ack_tx, mut ack_rx) = mpsc::channel(followers.len());
+    for follower in followers {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        if follower.send(resp_tx).await.is_ok() {
+            let ack_tx = ack_tx.clone();
+            tokio::spawn(async move {
+                if resp_rx.await.is_ok() {
+                    let _ = ack_tx.send(()).await;
+                }
+            });
+        }
+    }
+    //多数派包含leader自身
+    let nodes = followers.len() + 1;
+    let quorum = nodes / 2 + 1;
+    for _ in 1..quorum {
+        ack_rx.recv().await;
+    }
+}
+
+fn spawn_followers(rt: &tokio::runtime::Runtime) -> Vec<mpsc::Sender<oneshot::Sender<()>>> {
+    (0..FOLLOWER_COUNT)
+        .map(|_| {
+            let (tx, mut rx) = mpsc::channel::<oneshot::Sender<()>>(1024);
+            rt.spawn(async move {
+                while let Some(resp) = rx.recv().await {
+                    tokio::spawn(async move {
+                        tokio::time::sleep(FOLLOWER_RTT).await;
+                        let _ = resp.send(());
+                    });
+                }
+            });
+            tx
+        })
+        .collect()
+}
+
+///
+/// 对比quorum与lease读取单次请求的延迟分布,输出p50/p99及p99降低比例
+fn bench_lease_vs_quorum_latency(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let keys = Arc::new(build_keys());
+    let view = Arc::new(ConfigReadView::new());
+    view.store_all(
+        keys.iter()
+            .enumerate()
+            .map(|(i, k)| (k.clone(), build_value(i)))
+            .collect(),
+    );
+    let followers = spawn_followers(&rt);
+    let lease = ReadLease::new(Duration::from_millis(READ_LEASE_MS));
+
+    let quorum_read = |i: usize| {
+        rt.block_on(async {
+            confirm_by_quorum(&followers).await;
+            let _ = view.get(&keys[i % CONFIG_COUNT]);
+        })
+    };
+    let lease_read = |i: usize| {
+        rt.block_on(async {
+            if !lease.is_valid(Instant::now()) {
+                let start = Instant::now();
+                confirm_by_quorum(&followers).await;
+                lease.renew(start);
+            }
+            let _ = view.get(&keys[i % CONFIG_COUNT]);
+        })
+    };
+
+    let mut report = vec![];
+    for (name, read) in [
+        ("quorum", &quorum_read as &dyn Fn(usize)),
+        ("lease", &lease_read as &dyn Fn(usize)),
+    ]
+    .iter()
+    {
+        let mut samples: Vec<Duration> = (0..LATENCY_SAMPLES)
+            .map(|i| {
+                let start = Instant::now();
+                read(i);
+                start.elapsed()
+            })
+            .collect();
+        samples.sort();
+        let p50 = percentile(&samples, 0.5);
+        let p99 = percentile(&samples, 0.99);
+        println!("config_read_{} latency p50={:?} p99={:?}", name, p50, p99);
+        report.push(p99);
+    }
+    if report[0] > Duration::ZERO {
+        println!(
+            "lease read p99 reduction: {:.2}%",
+            (1.0 - report[1].as_secs_f64() / report[0].as_secs_f64()) * 100.0
+        );
+    }
+
+    c.bench_function("config_read_quorum_single", |b| {
+        let mut i = 0;
+        b.iter(|| {
+            i += 1;
+            quorum_read(i)
+        })
+    });
+    c.bench_function("config_read_lease_single", |b| {
+        let mut i = 0;
+        b.iter(|| {
+            i += 1;
+            lease_read(i)
+        })
+    });
+}
+
+/// 单个热点配置被大量并发读取:经过单任务邮箱串行读取(近似ConfigActor)与直接读取只读视图对比
+fn bench_hot_key_read(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let key = ConfigKey::new("hot_data", "DEFAULT_GROUP", "");
+    let view = Arc::new(ConfigReadView::new());
+    view.update(key.clone(), Some(build_value(0)));
+
+    let (tx, mut rx) = mpsc::channel::<oneshot::Sender<Option<Arc<ConfigReadValue>>>>(1024);
+    let mailbox_view = view.clone();
+    let mailbox_key = key.clone();
+    rt.spawn(async move {
+        while let Some(resp) = rx.recv().await {
+            let _ = resp.send(mailbox_view.get(&mailbox_key));
+        }
+    });
+    c.bench_function("config_hot_key_read_mailbox", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let handles: Vec<_> = (0..HOT_KEY_READERS)
+                    .map(|_| {
+                        let tx = tx.clone();
+                        tokio::spawn(async move {
+                            let (resp_tx, resp_rx) = oneshot::channel();
+                            if tx.send(resp_tx).await.is_ok() {
+                                let _ = resp_rx.await;
+                            }
+                        })
+                    })
+                    .collect();
+                for h in handles {
+                    h.await.unwrap();
+                }
+            })
+        })
+    });
+    c.bench_function("config_hot_key_read_arc_swap", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let handles: Vec<_> = (0..HOT_KEY_READERS)
+                    .map(|_| {
+                        let view = view.clone();
+                        let key = key.clone();
+                        tokio::spawn(async move {
+                            let _ = view.get(&key);
+                        })
+                    })
+                    .collect();
+                for h in handles {
+                    h.await.unwrap();
+                }
+            })
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_mutex_read,
+    bench_read_view,
+    bench_lease_read,
+    bench_lease_vs_quorum_latency,
+    bench_hot_key_read
+);
+criterion_main!(benches);
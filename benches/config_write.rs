@@ -0,0 +1,86 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use criterion::{criterion_group, criterion_main, Criterion};
+use rnacos::config::config_batch::{BatchProposer, ConfigProposer};
+use rnacos::config::core::ConfigKey;
+use rnacos::config::model::ConfigSetResult;
+use rnacos::raft::cluster::model::SetConfigReq;
+use tokio::sync::Mutex;
+
+const WRITE_COUNT: usize = 1000;
+/// 模拟单条raft日志的复制与落盘耗时
+const PROPOSE_COST: Duration = Duration::from_millis(1);
+
+/// 提案串行执行,每条日志耗时固定
+#[derive(Default)]
+struct MockRaftProposer {
+    log: Mutex<u64>,
+}
+
+#[async_trait]
+impl ConfigProposer for MockRaftProposer {
+    async fn propose(&self, _req: SetConfigReq) -> anyhow::Result<()> {
+        let mut log = self.log.lock().await;
+        tokio::time::sleep(PROPOSE_COST).await;
+        *log += 1;
+        Ok(())
+    }
+
+    async fn propose_batch(&self, reqs: Vec<SetConfigReq>) -> anyhow::Result<Vec<ConfigSetResult>> {
+        let mut log = self.log.lock().await;
+        tokio::time::sleep(PROPOSE_COST).await;
+        *log += 1;
+        Ok(vec![ConfigSetResult::Success; reqs.len()])
+    }
+}
+
+async fn concurrent_write(proposer: Arc<BatchProposer<MockRaftProposer>>) {
+    let handles: Vec<_> = (0..WRITE_COUNT)
+        .map(|i| {
+            let proposer = proposer.clone();
+            tokio::spawn(async move {
+                let req = SetConfigReq::new(
+                    ConfigKey::new(&format!("data_{}", i), "DEFAULT_GROUP", ""),
+                    Arc::new(format!("content_{}", i)),
+                );
+                proposer.set_config(req).await.unwrap();
+            })
+        })
+        .collect();
+    for h in handles {
+        h.await.unwrap();
+    }
+}
+
+fn bench_write(c: &mut Criterion, name: &str, batch_window_ms: u64) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let proposer = rt.block_on(async {
+        Arc::new(BatchProposer::new(
+            Arc::new(MockRaftProposer::default()),
+            batch_window_ms,
+        ))
+    });
+    let mut group = c.benchmark_group("config_write");
+    group.sample_size(10);
+    group.bench_function(name, |b| {
+        b.iter(|| rt.block_on(concurrent_write(proposer.clone())))
+    });
+    group.finish();
+}
+
+/// 每个写入一条raft日志
+fn bench_single_propose(c: &mut Criterion) {
+    bench_write(c, "single_propose", 0);
+}
+
+fn bench_batch_propose(c: &mut Criterion) {
+    bench_write(c, "batch_propose", 5);
+}
+
+criterion_group!(benches, bench_single_propose, bench_batch_propose);
+criterion_main!(benches);
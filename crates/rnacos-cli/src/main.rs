@@ -0,0 +1,356 @@
+//!
+//! r-nacos 命令行工具,用于在脚本中管理配置与命名空间
+
+mod output;
+
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Parser, Subcommand};
+use futures_util::StreamExt;
+use rnacos_client::{ClientError, ConfigClient, ConfigInfo, ConfigKey, NamespaceInfo};
+use serde::Serialize;
+
+use output::{print_event, print_item, print_list, OutputFormat, TableRow};
+
+const DEFAULT_NAMESPACE: &str = "public";
+
+#[derive(Parser, Debug)]
+#[command(name = "rnacos-cli", author, version, about, long_about = None)]
+struct Cli {
+    /// r-nacos server address
+    #[arg(
+        long,
+        env = "RNACOS_SERVER",
+        default_value = "http://127.0.0.1:8848",
+        global = true
+    )]
+    server: String,
+    /// login username, the token is refreshed automatically
+    #[arg(long, env = "RNACOS_USERNAME", global = true)]
+    username: Option<String>,
+    #[arg(long, env = "RNACOS_PASSWORD", global = true, hide_env_values = true)]
+    password: Option<String>,
+    /// output format, `config get` prints the raw content when not set
+    #[arg(short, long, value_enum, global = true)]
+    output: Option<OutputFormat>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// manage configs
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// manage namespaces
+    #[command(subcommand)]
+    Namespace(NamespaceCommand),
+}
+
+#[derive(Args, Debug)]
+struct ConfigKeyArgs {
+    #[arg(long, default_value = DEFAULT_NAMESPACE)]
+    ns: String,
+    #[arg(long, default_value = "DEFAULT_GROUP")]
+    group: String,
+    #[arg(long)]
+    data_id: String,
+}
+
+impl ConfigKeyArgs {
+    fn to_key(&self) -> ConfigKey {
+        ConfigKey::new(&self.data_id, &self.group, &to_tenant(&self.ns))
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// print a config
+    Get(ConfigKeyArgs),
+    /// set a config from a file or the `--content` value
+    Set {
+        #[command(flatten)]
+        key: ConfigKeyArgs,
+        #[arg(long, conflicts_with = "content", required_unless_present = "content")]
+        file: Option<PathBuf>,
+        #[arg(long)]
+        content: Option<String>,
+        /// config type, inferred from the file extension when not set
+        #[arg(long = "type")]
+        config_type: Option<String>,
+    },
+    /// delete a config
+    Delete(ConfigKeyArgs),
+    /// list configs in a namespace
+    List {
+        #[arg(long, default_value = DEFAULT_NAMESPACE)]
+        ns: String,
+    },
+    /// print the config and every change until interrupted
+    Watch(ConfigKeyArgs),
+}
+
+#[derive(Subcommand, Debug)]
+enum NamespaceCommand {
+    List,
+    Create {
+        #[arg(long)]
+        id: String,
+        /// defaults to the namespace id
+        #[arg(long)]
+        name: Option<String>,
+    },
+    Delete {
+        #[arg(long)]
+        id: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigOutput {
+    data_id: String,
+    group: String,
+    tenant: String,
+    md5: String,
+    //为None表示配置已删除
+    content: Option<String>,
+}
+
+impl TableRow for ConfigOutput {
+    fn headers() -> Vec<&'static str> {
+        vec!["DATA_ID", "GROUP", "NAMESPACE", "MD5", "CONTENT"]
+    }
+
+    fn columns(&self) -> Vec<String> {
+        vec![
+            self.data_id.clone(),
+            self.group.clone(),
+            self.tenant.clone(),
+            self.md5.clone(),
+            self.content.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ConfigListItem(ConfigInfo);
+
+impl TableRow for ConfigListItem {
+    fn headers() -> Vec<&'static str> {
+        vec!["DATA_ID", "GROUP", "NAMESPACE", "MD5"]
+    }
+
+    fn columns(&self) -> Vec<String> {
+        vec![
+            self.0.data_id.clone(),
+            self.0.group.clone(),
+            self.0.tenant.clone(),
+            self.0.md5.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct NamespaceItem(NamespaceInfo);
+
+impl TableRow for NamespaceItem {
+    fn headers() -> Vec<&'static str> {
+        vec!["NAMESPACE_ID", "NAMESPACE_NAME"]
+    }
+
+    fn columns(&self) -> Vec<String> {
+        vec![
+            self.0.namespace_id.clone().unwrap_or_default(),
+            self.0.namespace_name.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+///
+/// 默认命名空间在服务端的id为空字符串
+fn to_tenant(ns: &str) -> String {
+    if ns == DEFAULT_NAMESPACE {
+        String::new()
+    } else {
+        ns.to_owned()
+    }
+}
+
+fn infer_config_type(file: &Path) -> Option<String> {
+    let ext = file.extension()?.to_str()?.to_lowercase();
+    let config_type = match ext.as_str() {
+        "yaml" | "yml" => "yaml",
+        "json" => "json",
+        "properties" => "properties",
+        "xml" => "xml",
+        "html" | "htm" => "html",
+        "toml" => "toml",
+        "txt" => "text",
+        _ => return None,
+    };
+    Some(config_type.to_owned())
+}
+
+fn build_client(cli: &Cli) -> anyhow::Result<ConfigClient> {
+    let mut builder = ConfigClient::builder(&cli.server);
+    if let (Some(username), Some(password)) = (&cli.username, &cli.password) {
+        builder = builder.credentials(username, password);
+    }
+    Ok(builder.build()?)
+}
+
+async fn run_config(
+    client: &ConfigClient,
+    output: Option<OutputFormat>,
+    command: ConfigCommand,
+) -> anyhow::Result<()> {
+    match command {
+        ConfigCommand::Get(args) => {
+            let entry = match client.get_config(&args.to_key()).await {
+                Ok(v) => v,
+                Err(ClientError::NotFound) => {
+                    return Err(anyhow::anyhow!("config not found: {}", &args.data_id))
+                }
+                Err(err) => return Err(err.into()),
+            };
+            match output {
+                Some(format) => print_item(
+                    format,
+                    &ConfigOutput {
+                        data_id: entry.key.data_id,
+                        group: entry.key.group,
+                        tenant: entry.key.tenant,
+                        md5: entry.md5,
+                        content: Some(entry.content),
+                    },
+                )?,
+                None => print!("{}", entry.content),
+            }
+        }
+        ConfigCommand::Set {
+            key,
+            file,
+            content,
+            config_type,
+        } => {
+            let (content, config_type) = match (file, content) {
+                (Some(file), _) => (
+                    std::fs::read_to_string(&file)?,
+                    config_type.or_else(|| infer_config_type(&file)),
+                ),
+                (None, Some(content)) => (content, config_type),
+                (None, None) => return Err(anyhow::anyhow!("--file or --content is required")),
+            };
+            client
+                .set_config_with_type(&key.to_key(), &content, config_type.as_deref())
+                .await?;
+        }
+        ConfigCommand::Delete(args) => {
+            client.delete_config(&args.to_key()).await?;
+        }
+        ConfigCommand::List { ns } => {
+            let list: Vec<ConfigListItem> = client
+                .list_configs(&to_tenant(&ns))
+                .await?
+                .into_iter()
+                .map(ConfigListItem)
+                .collect();
+            print_list(output.unwrap_or(OutputFormat::Table), &list)?;
+        }
+        ConfigCommand::Watch(args) => {
+            let mut events = Box::pin(client.watch(args.to_key()));
+            while let Some(event) = events.next().await {
+                match output {
+                    Some(format) => print_event(
+                        format,
+                        &ConfigOutput {
+                            data_id: event.key.data_id,
+                            group: event.key.group,
+                            tenant: event.key.tenant,
+                            md5: event.md5,
+                            content: event.content,
+                        },
+                    )?,
+                    None => match event.content {
+                        Some(content) => println!("{}", content),
+                        None => eprintln!("config deleted: {}", &event.key.data_id),
+                    },
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn run_namespace(
+    client: &ConfigClient,
+    output: Option<OutputFormat>,
+    command: NamespaceCommand,
+) -> anyhow::Result<()> {
+    match command {
+        NamespaceCommand::List => {
+            let list: Vec<NamespaceItem> = client
+                .list_namespaces()
+                .await?
+                .into_iter()
+                .map(NamespaceItem)
+                .collect();
+            print_list(output.unwrap_or(OutputFormat::Table), &list)?;
+        }
+        NamespaceCommand::Create { id, name } => {
+            let name = name.unwrap_or_else(|| id.clone());
+            client.create_namespace(&id, &name).await?;
+        }
+        NamespaceCommand::Delete { id } => {
+            client.delete_namespace(&id).await?;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let client = build_client(&cli)?;
+    match cli.command {
+        Command::Config(command) => run_config(&client, cli.output, command).await,
+        Command::Namespace(command) => run_namespace(&client, cli.output, command).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let cli = Cli::parse_from([
+            "rnacos-cli",
+            "--server",
+            "http://10.0.0.1:8848",
+            "config",
+            "set",
+            "--ns",
+            "public",
+            "--data-id",
+            "app.yaml",
+            "--file",
+            "./app.yaml",
+            "--output",
+            "json",
+        ]);
+        assert_eq!(cli.server, "http://10.0.0.1:8848");
+        assert_eq!(cli.output, Some(OutputFormat::Json));
+        match cli.command {
+            Command::Config(ConfigCommand::Set { key, file, .. }) => {
+                assert_eq!(
+                    key.to_key(),
+                    ConfigKey::new("app.yaml", "DEFAULT_GROUP", "")
+                );
+                assert_eq!(infer_config_type(&file.unwrap()).as_deref(), Some("yaml"));
+            }
+            _ => panic!("unexpected command"),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Json,
+    Yaml,
+    Table,
+}
+
+///
+/// 表格输出的一行,按列名顺序返回各列的值
+pub trait TableRow {
+    fn headers() -> Vec<&'static str>;
+    fn columns(&self) -> Vec<String>;
+}
+
+pub fn print_list<T: Serialize + TableRow>(format: OutputFormat, list: &[T]) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(list)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(list)?),
+        OutputFormat::Table => print!("{}", render_table(list)),
+    }
+    Ok(())
+}
+
+pub fn print_item<T: Serialize + TableRow>(format: OutputFormat, item: &T) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(item)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(item)?),
+        OutputFormat::Table => print!("{}", render_table(std::slice::from_ref(item))),
+    }
+    Ok(())
+}
+
+///
+/// 持续输出的事件,json每行一个对象,yaml每个事件一个文档
+pub fn print_event<T: Serialize + TableRow>(format: OutputFormat, item: &T) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string(item)?),
+        OutputFormat::Yaml => print!("---\n{}", serde_yaml::to_string(item)?),
+        OutputFormat::Table => println!("{}", item.columns().join("\t")),
+    }
+    Ok(())
+}
+
+///
+/// 按每列最长的值对齐,列之间以两个空格分隔
+fn render_table<T: TableRow>(list: &[T]) -> String {
+    let headers = T::headers();
+    let rows: Vec<Vec<String>> = list
+        .iter()
+        .map(|v| {
+            v.columns()
+                .into_iter()
+                .map(|col| col.replace('\n', "\\n"))
+                .collect()
+        })
+        .collect();
+    let mut widths: Vec<usize> = headers.iter().map(|v| v.chars().count()).collect();
+    for row in &rows {
+        for (i, col) in row.iter().enumerate() {
+            widths[i] = widths[i].max(col.chars().count());
+        }
+    }
+    let mut output = String::new();
+    let header_row: Vec<String> = headers.iter().map(|v| v.to_string()).collect();
+    for row in std::iter::once(&header_row).chain(rows.iter()) {
+        let line: Vec<String> = row
+            .iter()
+            .enumerate()
+            .map(|(i, col)| format!("{:width$}", col, width = widths[i]))
+            .collect();
+        output.push_str(line.join("  ").trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Row(&'static str, &'static str);
+
+    impl TableRow for Row {
+        fn headers() -> Vec<&'static str> {
+            vec!["DATA_ID", "GROUP"]
+        }
+
+        fn columns(&self) -> Vec<String> {
+            vec![self.0.to_owned(), self.1.to_owned()]
+        }
+    }
+
+    #[test]
+    fn table() {
+        let table = render_table(&[Row("application.yaml", "DEFAULT_GROUP"), Row("a", "G")]);
+        assert_eq!(
+            table,
+            "DATA_ID           GROUP\napplication.yaml  DEFAULT_GROUP\na                 G\n"
+        );
+    }
+}
@@ -0,0 +1,811 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use futures_util::stream::{self, Stream};
+use reqwest::header::LOCATION;
+use reqwest::{Method, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::error::ClientError;
+use crate::model::{
+    ConfigChangeEvent, ConfigEntry, ConfigInfo, ConfigInfoPage, ConfigKey, ConsoleResult,
+    NamespaceInfo,
+};
+
+//相对路径,拼接在base_url之后,保留反向代理等场景下base_url中的路径前缀
+const CONFIG_PATH: &str = "nacos/v1/cs/configs";
+const LISTENER_PATH: &str = "nacos/v1/cs/configs/listener";
+const CONSOLE_CONFIGS_PATH: &str = "nacos/v1/console/configs";
+const CONSOLE_NAMESPACES_PATH: &str = "nacos/v1/console/namespaces";
+const LOGIN_PATH: &str = "nacos/v1/auth/login";
+const ACCESS_TOKEN_PARAM: &str = "accessToken";
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+const MAX_REDIRECTS: usize = 5;
+
+static IDEMPOTENCY_KEY_SEQ: AtomicU64 = AtomicU64::new(0);
+
+///
+/// 同一个请求的多次重试使用相同的key,不同请求的key不重复
+fn new_idempotency_key() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|v| v.as_nanos())
+        .unwrap_or_default();
+    format!(
+        "{:x}-{:x}-{:x}",
+        nanos,
+        std::process::id(),
+        IDEMPOTENCY_KEY_SEQ.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    ///
+    /// 按重试次数指数退避,不超过max_backoff
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.min(16)))
+            .min(self.max_backoff)
+    }
+}
+
+pub struct ConfigClientBuilder {
+    base_url: String,
+    auth_token: Option<String>,
+    credentials: Option<(String, String)>,
+    timeout: Duration,
+    long_poll_timeout: Duration,
+    retry_policy: RetryPolicy,
+    root_certificates: Vec<Vec<u8>>,
+    accept_invalid_certs: bool,
+}
+
+impl ConfigClientBuilder {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            auth_token: None,
+            credentials: None,
+            timeout: Duration::from_secs(5),
+            long_poll_timeout: Duration::from_secs(30),
+            retry_policy: RetryPolicy::default(),
+            root_certificates: vec![],
+            accept_invalid_certs: false,
+        }
+    }
+
+    ///
+    /// 使用固定的token访问,token失效后不会自动刷新
+    pub fn auth_token(mut self, token: &str) -> Self {
+        self.auth_token = Some(token.to_owned());
+        self
+    }
+
+    ///
+    /// 使用用户名密码登录获取token,token过期或被拒绝时自动重新登录
+    pub fn credentials(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some((username.to_owned(), password.to_owned()));
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn long_poll_timeout(mut self, timeout: Duration) -> Self {
+        self.long_poll_timeout = timeout;
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    ///
+    /// 增加信任的根证书(PEM格式),用于自签名证书的https服务
+    pub fn add_root_certificate_pem(mut self, pem: &[u8]) -> Self {
+        self.root_certificates.push(pem.to_vec());
+        self
+    }
+
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    pub fn build(self) -> Result<ConfigClient, ClientError> {
+        //以/结尾,join相对路径时不会丢弃最后一段路径
+        let base_url = Url::parse(&format!("{}/", &self.base_url))
+            .map_err(|err| ClientError::Config(err.to_string()))?;
+        //重定向需要重新附带token与表单参数,由客户端自行处理
+        let mut builder = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .redirect(reqwest::redirect::Policy::none())
+            .danger_accept_invalid_certs(self.accept_invalid_certs);
+        for pem in &self.root_certificates {
+            builder = builder.add_root_certificate(reqwest::Certificate::from_pem(pem)?);
+        }
+        let token = self.auth_token.map(|token| AccessToken {
+            token,
+            expire_at: None,
+        });
+        Ok(ConfigClient {
+            inner: Arc::new(ClientInner {
+                base_url,
+                http: builder.build()?,
+                credentials: self.credentials,
+                auth_token: Mutex::new(token),
+                long_poll_timeout: self.long_poll_timeout,
+                retry_policy: self.retry_policy,
+            }),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AccessToken {
+    token: String,
+    expire_at: Option<Instant>,
+}
+
+impl AccessToken {
+    fn is_valid(&self) -> bool {
+        self.expire_at.map(|v| Instant::now() < v).unwrap_or(true)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LoginResult {
+    access_token: Option<String>,
+    token_ttl: Option<u64>,
+}
+
+struct ClientInner {
+    base_url: Url,
+    http: reqwest::Client,
+    credentials: Option<(String, String)>,
+    auth_token: Mutex<Option<AccessToken>>,
+    long_poll_timeout: Duration,
+    retry_policy: RetryPolicy,
+}
+
+struct RequestParts {
+    method: Method,
+    path: &'static str,
+    query: Vec<(&'static str, String)>,
+    form: Option<Vec<(&'static str, String)>>,
+    headers: Vec<(&'static str, String)>,
+    timeout: Option<Duration>,
+}
+
+impl RequestParts {
+    fn new(method: Method, path: &'static str) -> Self {
+        Self {
+            method,
+            path,
+            query: vec![],
+            form: None,
+            headers: vec![],
+            timeout: None,
+        }
+    }
+
+    ///
+    /// 写请求重试时需要服务端去重;长轮询监听只读,不需要
+    fn need_idempotency_key(&self) -> bool {
+        self.method == Method::POST && self.path != LISTENER_PATH
+    }
+}
+
+///
+/// 配置中心客户端,clone后共享连接池与登录token
+#[derive(Clone)]
+pub struct ConfigClient {
+    inner: Arc<ClientInner>,
+}
+
+impl ConfigClient {
+    pub fn builder(base_url: &str) -> ConfigClientBuilder {
+        ConfigClientBuilder::new(base_url)
+    }
+
+    pub async fn get_config(&self, key: &ConfigKey) -> Result<ConfigEntry, ClientError> {
+        let mut req = RequestParts::new(Method::GET, CONFIG_PATH);
+        req.query = key.to_params();
+        let resp = self.execute(&req).await?;
+        match resp.status() {
+            StatusCode::OK => {
+                let md5 = resp
+                    .headers()
+                    .get("content-md5")
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default()
+                    .to_owned();
+                let content = resp.text().await?;
+                Ok(ConfigEntry {
+                    key: key.clone(),
+                    content,
+                    md5,
+                })
+            }
+            StatusCode::NOT_FOUND => Err(ClientError::NotFound),
+            _ => Err(Self::status_error(resp).await),
+        }
+    }
+
+    pub async fn set_config(&self, key: &ConfigKey, content: &str) -> Result<(), ClientError> {
+        self.set_config_with_type(key, content, None).await
+    }
+
+    ///
+    /// 设置配置并指定配置类型,如yaml、json、properties
+    pub async fn set_config_with_type(
+        &self,
+        key: &ConfigKey,
+        content: &str,
+        config_type: Option<&str>,
+    ) -> Result<(), ClientError> {
+        let mut req = RequestParts::new(Method::POST, CONFIG_PATH);
+        let mut form = key.to_params();
+        form.push(("content", content.to_owned()));
+        if let Some(config_type) = config_type {
+            form.push(("type", config_type.to_owned()));
+        }
+        req.form = Some(form);
+        let resp = self.execute(&req).await?;
+        if resp.status() == StatusCode::OK {
+            Ok(())
+        } else {
+            Err(Self::status_error(resp).await)
+        }
+    }
+
+    pub async fn delete_config(&self, key: &ConfigKey) -> Result<(), ClientError> {
+        let mut req = RequestParts::new(Method::DELETE, CONFIG_PATH);
+        req.query = key.to_params();
+        let resp = self.execute(&req).await?;
+        if resp.status() == StatusCode::OK {
+            Ok(())
+        } else {
+            Err(Self::status_error(resp).await)
+        }
+    }
+
+    ///
+    /// 查询命名空间下的全部配置
+    pub async fn list_configs(&self, tenant: &str) -> Result<Vec<ConfigInfo>, ClientError> {
+        let mut req = RequestParts::new(Method::GET, CONSOLE_CONFIGS_PATH);
+        req.query = vec![("tenant", tenant.to_owned())];
+        let resp = self.execute(&req).await?;
+        if resp.status() != StatusCode::OK {
+            return Err(Self::status_error(resp).await);
+        }
+        let page: ConfigInfoPage = serde_json::from_str(&resp.text().await?)
+            .map_err(|err| ClientError::Console(err.to_string()))?;
+        Ok(page.list)
+    }
+
+    pub async fn list_namespaces(&self) -> Result<Vec<NamespaceInfo>, ClientError> {
+        let req = RequestParts::new(Method::GET, CONSOLE_NAMESPACES_PATH);
+        Ok(self.console_request(&req).await?.unwrap_or_default())
+    }
+
+    pub async fn create_namespace(
+        &self,
+        namespace_id: &str,
+        namespace_name: &str,
+    ) -> Result<(), ClientError> {
+        let mut req = RequestParts::new(Method::POST, CONSOLE_NAMESPACES_PATH);
+        req.form = Some(vec![
+            ("namespaceId", namespace_id.to_owned()),
+            ("namespaceName", namespace_name.to_owned()),
+        ]);
+        self.console_request::<bool>(&req).await?;
+        Ok(())
+    }
+
+    pub async fn delete_namespace(&self, namespace_id: &str) -> Result<(), ClientError> {
+        let mut req = RequestParts::new(Method::DELETE, CONSOLE_NAMESPACES_PATH);
+        req.form = Some(vec![("namespaceId", namespace_id.to_owned())]);
+        self.console_request::<bool>(&req).await?;
+        Ok(())
+    }
+
+    ///
+    /// 调用控制台接口,返回结果中的data
+    async fn console_request<T: DeserializeOwned>(
+        &self,
+        req: &RequestParts,
+    ) -> Result<Option<T>, ClientError> {
+        let resp = self.execute(req).await?;
+        if resp.status() != StatusCode::OK {
+            return Err(Self::status_error(resp).await);
+        }
+        let result: ConsoleResult<T> = serde_json::from_str(&resp.text().await?)
+            .map_err(|err| ClientError::Console(err.to_string()))?;
+        if result.code != 200 {
+            return Err(ClientError::Console(result.message.unwrap_or_default()));
+        }
+        Ok(result.data)
+    }
+
+    ///
+    /// 长轮询监听配置变更;第一次事件为配置当前值(配置存在时),
+    /// 之后每次内容变化产生一个事件。网络异常时按重试策略退避后重新监听,流不会结束
+    pub fn watch(&self, key: ConfigKey) -> impl Stream<Item = ConfigChangeEvent> {
+        let state = WatchState {
+            client: self.clone(),
+            key,
+            md5: String::new(),
+            attempt: 0,
+        };
+        stream::unfold(state, |mut state| async move {
+            let event = state.next_event().await;
+            Some((event, state))
+        })
+    }
+
+    ///
+    /// 返回配置是否可能已变更
+    async fn listen(&self, key: &ConfigKey, md5: &str) -> Result<bool, ClientError> {
+        let timeout = self.inner.long_poll_timeout;
+        let mut req = RequestParts::new(Method::POST, LISTENER_PATH);
+        req.form = Some(vec![("Listening-Configs", key.build_listening_config(md5))]);
+        req.headers = vec![("Long-Pulling-Timeout", timeout.as_millis().to_string())];
+        req.timeout = Some(timeout + Duration::from_secs(10));
+        let resp = self.execute(&req).await?;
+        if resp.status() != StatusCode::OK {
+            return Err(Self::status_error(resp).await);
+        }
+        Ok(!resp.text().await?.trim().is_empty())
+    }
+
+    ///
+    /// 发送请求,处理token刷新与可重试错误
+    async fn execute(&self, req: &RequestParts) -> Result<reqwest::Response, ClientError> {
+        let retry_policy = &self.inner.retry_policy;
+        let mut attempt = 0;
+        let mut refreshed = false;
+        //每次重试都带上同一个Idempotency-Key,请求已在服务端执行时不会重复写入
+        let idempotency_key = if req.need_idempotency_key() {
+            Some(new_idempotency_key())
+        } else {
+            None
+        };
+        loop {
+            let result = match self.access_token().await {
+                Ok(token) => {
+                    self.send_following_redirects(req, token, idempotency_key.as_deref())
+                        .await
+                }
+                Err(err) => Err(err),
+            };
+            let err = match result {
+                Ok(resp)
+                    if resp.status() == StatusCode::FORBIDDEN
+                        && self.inner.credentials.is_some()
+                        && !refreshed =>
+                {
+                    //token可能已在服务端过期,重新登录后再试一次
+                    refreshed = true;
+                    self.clear_token();
+                    continue;
+                }
+                Ok(resp) if resp.status().is_server_error() => {
+                    let err = Self::status_error(resp).await;
+                    if !err.is_retryable() {
+                        return Err(err);
+                    }
+                    err
+                }
+                Ok(resp) => return Ok(resp),
+                Err(err) => err,
+            };
+            if !err.is_retryable() || attempt >= retry_policy.max_retries {
+                return Err(err);
+            }
+            log::warn!("rnacos client request error, retry later: {}", err);
+            tokio::time::sleep(retry_policy.backoff(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    ///
+    /// 集群节点返回307/308时按Location重新发送原请求
+    async fn send_following_redirects(
+        &self,
+        req: &RequestParts,
+        token: Option<String>,
+        idempotency_key: Option<&str>,
+    ) -> Result<reqwest::Response, ClientError> {
+        let mut url = self
+            .inner
+            .base_url
+            .join(req.path)
+            .map_err(|err| ClientError::Config(err.to_string()))?;
+        let mut with_query = true;
+        for _ in 0..=MAX_REDIRECTS {
+            let mut builder = self.inner.http.request(req.method.clone(), url.clone());
+            if with_query {
+                builder = builder.query(&req.query);
+            }
+            if let Some(token) = &token {
+                builder = builder.query(&[(ACCESS_TOKEN_PARAM, token)]);
+            }
+            if let Some(form) = &req.form {
+                builder = builder.form(form);
+            }
+            for (name, value) in &req.headers {
+                builder = builder.header(*name, value);
+            }
+            if let Some(key) = idempotency_key {
+                builder = builder.header(IDEMPOTENCY_KEY_HEADER, key);
+            }
+            if let Some(timeout) = req.timeout {
+                builder = builder.timeout(timeout);
+            }
+            let resp = builder.send().await?;
+            if !matches!(
+                resp.status(),
+                StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT
+            ) {
+                return Ok(resp);
+            }
+            let location = resp
+                .headers()
+                .get(LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| ClientError::Status {
+                    status: resp.status().as_u16(),
+                    body: "redirect without location".to_owned(),
+                })?;
+            url = url
+                .join(location)
+                .map_err(|err| ClientError::Config(err.to_string()))?;
+            //Location已带查询参数时不再重复附加
+            with_query = url.query().is_none();
+        }
+        Err(ClientError::TooManyRedirects)
+    }
+
+    async fn access_token(&self) -> Result<Option<String>, ClientError> {
+        if let Some(token) = self.inner.auth_token.lock().unwrap().as_ref() {
+            if token.is_valid() {
+                return Ok(Some(token.token.clone()));
+            }
+        }
+        let (username, password) = match &self.inner.credentials {
+            Some(v) => v,
+            None => {
+                return Ok(self
+                    .inner
+                    .auth_token
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .map(|v| v.token.clone()))
+            }
+        };
+        let mut req = RequestParts::new(Method::POST, LOGIN_PATH);
+        req.form = Some(vec![
+            ("username", username.clone()),
+            ("password", password.clone()),
+        ]);
+        let resp = self.send_following_redirects(&req, None, None).await?;
+        if resp.status() != StatusCode::OK {
+            return Err(ClientError::Auth(resp.text().await.unwrap_or_default()));
+        }
+        let result: LoginResult = serde_json::from_str(&resp.text().await?)
+            .map_err(|err| ClientError::Auth(err.to_string()))?;
+        let token = result
+            .access_token
+            .ok_or_else(|| ClientError::Auth("login result without accessToken".to_owned()))?;
+        //提前刷新,避免请求途中token过期
+        let expire_at = result
+            .token_ttl
+            .map(|ttl| Instant::now() + Duration::from_secs(ttl * 9 / 10));
+        *self.inner.auth_token.lock().unwrap() = Some(AccessToken {
+            token: token.clone(),
+            expire_at,
+        });
+        Ok(Some(token))
+    }
+
+    fn clear_token(&self) {
+        if self.inner.credentials.is_some() {
+            self.inner.auth_token.lock().unwrap().take();
+        }
+    }
+
+    async fn status_error(resp: reqwest::Response) -> ClientError {
+        let status = resp.status().as_u16();
+        let body = resp.text().await.unwrap_or_default();
+        ClientError::Status { status, body }
+    }
+}
+
+struct WatchState {
+    client: ConfigClient,
+    key: ConfigKey,
+    md5: String,
+    attempt: u32,
+}
+
+impl WatchState {
+    async fn next_event(&mut self) -> ConfigChangeEvent {
+        loop {
+            let result = match self.client.listen(&self.key, &self.md5).await {
+                Ok(true) => self.client.get_config(&self.key).await.map(Some),
+                Ok(false) => Ok(None),
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(Some(entry)) => {
+                    self.attempt = 0;
+                    if entry.md5 != self.md5 {
+                        self.md5 = entry.md5.clone();
+                        return ConfigChangeEvent {
+                            key: entry.key,
+                            content: Some(entry.content),
+                            md5: entry.md5,
+                        };
+                    }
+                }
+                Ok(None) => {
+                    self.attempt = 0;
+                }
+                Err(ClientError::NotFound) => {
+                    self.attempt = 0;
+                    if !self.md5.is_empty() {
+                        self.md5 = String::new();
+                        return ConfigChangeEvent {
+                            key: self.key.clone(),
+                            content: None,
+                            md5: String::new(),
+                        };
+                    }
+                }
+                Err(err) => {
+                    //监听连接断开后退避重连
+                    log::warn!("rnacos client watch error, reconnect later: {}", err);
+                    let backoff = self.client.inner.retry_policy.backoff(self.attempt);
+                    tokio::time::sleep(backoff).await;
+                    self.attempt = self.attempt.saturating_add(1);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    ///
+    /// 简单的http服务,按请求内容(以请求行开头)返回预设响应
+    async fn mock_server(handler: impl Fn(&str) -> String + Send + Sync + 'static) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 8192];
+                let n = socket.read(&mut buf).await.unwrap_or_default();
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let response = handler(&request);
+                socket.write_all(response.as_bytes()).await.ok();
+                socket.shutdown().await.ok();
+            }
+        });
+        addr
+    }
+
+    fn http_response(status: &str, headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n{}\r\n{}",
+            status,
+            body.len(),
+            headers,
+            body
+        )
+    }
+
+    #[test]
+    fn retry_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(3), Duration::from_millis(500));
+        assert_eq!(policy.backoff(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn listening_config() {
+        let key = ConfigKey::new("app.yaml", "", "");
+        assert_eq!(
+            key.build_listening_config("md5"),
+            "app.yaml\x02DEFAULT_GROUP\x02md5\x01"
+        );
+        let key = ConfigKey::new("app.yaml", "G", "dev");
+        assert_eq!(
+            key.build_listening_config(""),
+            "app.yaml\x02G\x02\x02dev\x01"
+        );
+    }
+
+    #[tokio::test]
+    async fn get_config_with_redirect_and_login() {
+        let addr = mock_server(|line| {
+            if line.starts_with("POST /nacos/v1/auth/login") {
+                http_response(
+                    "200 OK",
+                    "",
+                    "{\"accessToken\":\"token_1\",\"tokenTtl\":18000}",
+                )
+            } else if line.starts_with("GET /nacos/v1/cs/configs?") {
+                http_response("307 Temporary Redirect", "location: /leader/config\r\n", "")
+            } else if line.starts_with("GET /leader/config?")
+                && line.contains("dataId=app.yaml")
+                && line.contains("accessToken=token_1")
+            {
+                http_response("200 OK", "content-md5: abc\r\n", "a=1")
+            } else {
+                http_response("404 Not Found", "", "")
+            }
+        })
+        .await;
+        let client = ConfigClient::builder(&addr)
+            .credentials("nacos", "nacos")
+            .build()
+            .unwrap();
+        let entry = client
+            .get_config(&ConfigKey::new("app.yaml", "", ""))
+            .await
+            .unwrap();
+        assert_eq!(entry.content, "a=1");
+        assert_eq!(entry.md5, "abc");
+        let err = client
+            .get_config(&ConfigKey::new("other.yaml", "", ""))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ClientError::NotFound));
+    }
+
+    #[tokio::test]
+    async fn watch_config() {
+        let addr = mock_server(|line| {
+            if line.starts_with("POST /nacos/v1/cs/configs/listener") {
+                http_response("200 OK", "", "app.yaml%02DEFAULT_GROUP%01\n")
+            } else if line.starts_with("GET /nacos/v1/cs/configs?") {
+                http_response("200 OK", "content-md5: abc\r\n", "a=1")
+            } else {
+                http_response("404 Not Found", "", "")
+            }
+        })
+        .await;
+        let client = ConfigClient::builder(&addr).build().unwrap();
+        let mut stream = Box::pin(client.watch(ConfigKey::new("app.yaml", "", "")));
+        let event = stream.next().await.unwrap();
+        assert_eq!(event.content.as_deref(), Some("a=1"));
+        assert_eq!(event.md5, "abc");
+    }
+
+    #[tokio::test]
+    async fn console_namespaces() {
+        let addr = mock_server(|line| {
+            if line.starts_with("GET /nacos/v1/console/namespaces") {
+                http_response(
+                    "200 OK",
+                    "",
+                    "{\"code\":200,\"data\":[{\"namespaceId\":\"\",\"namespaceName\":\"public\",\"type\":\"0\"}]}",
+                )
+            } else if line.starts_with("POST /nacos/v1/console/namespaces") {
+                http_response(
+                    "200 OK",
+                    "",
+                    "{\"code\":500,\"message\":\"namespace is exist\"}",
+                )
+            } else {
+                http_response("404 Not Found", "", "")
+            }
+        })
+        .await;
+        let client = ConfigClient::builder(&addr).build().unwrap();
+        let namespaces = client.list_namespaces().await.unwrap();
+        assert_eq!(namespaces.len(), 1);
+        assert_eq!(namespaces[0].namespace_name.as_deref(), Some("public"));
+        let err = client.create_namespace("dev", "dev").await.unwrap_err();
+        assert!(matches!(err, ClientError::Console(message) if message == "namespace is exist"));
+    }
+
+    #[tokio::test]
+    async fn base_url_with_path_prefix() {
+        let addr = mock_server(|line| {
+            if line.starts_with("GET /proxy/nacos/v1/cs/configs?") {
+                http_response("200 OK", "content-md5: abc\r\n", "a=1")
+            } else {
+                http_response("404 Not Found", "", "")
+            }
+        })
+        .await;
+        let client = ConfigClient::builder(&format!("{}/proxy/", addr))
+            .build()
+            .unwrap();
+        let entry = client
+            .get_config(&ConfigKey::new("app.yaml", "", ""))
+            .await
+            .unwrap();
+        assert_eq!(entry.content, "a=1");
+    }
+
+    #[tokio::test]
+    async fn retry_set_config_with_idempotency_key() {
+        let keys = Arc::new(Mutex::new(vec![]));
+        let server_keys = keys.clone();
+        let addr = mock_server(move |request| {
+            if !request.starts_with("POST /nacos/v1/cs/configs ") {
+                return http_response("404 Not Found", "", "");
+            }
+            let key = request
+                .lines()
+                .find_map(|v| {
+                    v.to_lowercase()
+                        .strip_prefix("idempotency-key: ")
+                        .map(|v| v.to_owned())
+                })
+                .unwrap_or_default();
+            let mut keys = server_keys.lock().unwrap();
+            keys.push(key);
+            if keys.len() == 1 {
+                http_response("503 Service Unavailable", "", "")
+            } else {
+                http_response("200 OK", "", "true")
+            }
+        })
+        .await;
+        let client = ConfigClient::builder(&addr)
+            .retry_policy(RetryPolicy {
+                max_retries: 1,
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(1),
+            })
+            .build()
+            .unwrap();
+        let key = ConfigKey::new("app.yaml", "", "");
+        client.set_config(&key, "a=1").await.unwrap();
+        client.set_config(&key, "a=2").await.unwrap();
+        let keys = keys.lock().unwrap();
+        assert_eq!(keys.len(), 3);
+        assert!(!keys[0].is_empty());
+        assert_eq!(keys[0], keys[1]);
+        assert_ne!(keys[1], keys[2]);
+    }
+}
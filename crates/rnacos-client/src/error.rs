@@ -0,0 +1,29 @@
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("config not found")]
+    NotFound,
+    #[error("auth failed: {0}")]
+    Auth(String),
+    #[error("unexpected response, status: {status}, body: {body}")]
+    Status { status: u16, body: String },
+    #[error("console api error: {0}")]
+    Console(String),
+    #[error("too many redirects")]
+    TooManyRedirects,
+    #[error("invalid client config: {0}")]
+    Config(String),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+}
+
+impl ClientError {
+    ///
+    /// 网络错误与服务端暂时不可用时可以重试
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ClientError::Http(err) => err.is_connect() || err.is_timeout() || err.is_request(),
+            ClientError::Status { status, .. } => matches!(status, 502..=504),
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,13 @@
+//!
+//! r-nacos 配置中心客户端
+//!
+//! 通过 openapi 读写配置与长轮询监听配置变更,通过控制台接口查询配置列表与管理命名空间,
+//! 内部处理登录token刷新、失败重试与集群节点的307重定向。
+
+mod client;
+mod error;
+mod model;
+
+pub use client::{ConfigClient, ConfigClientBuilder, RetryPolicy};
+pub use error::ClientError;
+pub use model::{ConfigChangeEvent, ConfigEntry, ConfigInfo, ConfigKey, NamespaceInfo};
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_GROUP: &str = "DEFAULT_GROUP";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigKey {
+    pub data_id: String,
+    pub group: String,
+    pub tenant: String,
+}
+
+impl ConfigKey {
+    pub fn new(data_id: &str, group: &str, tenant: &str) -> Self {
+        let group = if group.is_empty() {
+            DEFAULT_GROUP
+        } else {
+            group
+        };
+        Self {
+            data_id: data_id.to_owned(),
+            group: group.to_owned(),
+            tenant: tenant.to_owned(),
+        }
+    }
+
+    ///
+    /// 长轮询监听参数,格式与服务端 ListenerItem::decode_listener_items 一致
+    pub(crate) fn build_listening_config(&self, md5: &str) -> String {
+        if self.tenant.is_empty() {
+            format!("{}\x02{}\x02{}\x01", self.data_id, self.group, md5)
+        } else {
+            format!(
+                "{}\x02{}\x02{}\x02{}\x01",
+                self.data_id, self.group, md5, self.tenant
+            )
+        }
+    }
+
+    pub(crate) fn to_params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("dataId", self.data_id.clone()),
+            ("group", self.group.clone()),
+            ("tenant", self.tenant.clone()),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigEntry {
+    pub key: ConfigKey,
+    pub content: String,
+    pub md5: String,
+}
+
+///
+/// 配置变更事件,content为None表示配置已删除
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigChangeEvent {
+    pub key: ConfigKey,
+    pub content: Option<String>,
+    pub md5: String,
+}
+
+///
+/// 控制台配置列表中的配置信息,不含配置内容
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigInfo {
+    pub data_id: String,
+    pub group: String,
+    pub tenant: String,
+    #[serde(default)]
+    pub md5: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceInfo {
+    pub namespace_id: Option<String>,
+    pub namespace_name: Option<String>,
+    pub r#type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ConfigInfoPage {
+    #[serde(default)]
+    pub list: Vec<ConfigInfo>,
+}
+
+///
+/// 控制台接口的统一返回格式,code为200时成功
+#[derive(Debug, Deserialize)]
+pub(crate) struct ConsoleResult<T> {
+    pub code: i64,
+    pub message: Option<String>,
+    pub data: Option<T>,
+}
@@ -1,28 +1,83 @@
+use crate::common::circuit_breaker::{
+    mark_config_cmd_recorded, CircuitBreaker, CONFIG_SEND_TIMEOUT_SECONDS,
+};
+use crate::common::model::UserSession;
+use crate::common::sys_config_reload::SysConfigReloader;
+use crate::common::thread_pool_stats::{HttpRequestStats, PendingCmdStats};
 use crate::common::AppSysConfig;
-use crate::config::core::ConfigActor;
+use crate::config::config_access_log::ConfigAccessLogger;
+use crate::config::config_archive::ConfigArchiveStore;
+use crate::config::config_batch::BatchProposer;
+use crate::config::config_consumer::ConsumerRegistry;
+use crate::config::config_event::ConfigEventSender;
+use crate::config::config_field_encrypt::ConfigFieldCipher;
+use crate::config::config_health::ConfigValidationStats;
+use crate::config::config_idempotency::ConfigIdempotencyCache;
+use crate::config::config_index::ConfigQueryParam;
+use crate::config::config_kafka::ConfigKafkaPublisher;
+use crate::config::config_listener_ack::ListenerAckManage;
+use crate::config::config_namespace_merge::NamespaceMergeStore;
+use crate::config::config_namespace_stats::NamespaceStatsCache;
+use crate::config::config_notify_dedup::ListenerNotifyDedup;
+use crate::config::config_ns_ref::{
+    has_ns_refs, ns_ref_keys, NamespaceRefResolver, NS_REF_MAX_DEPTH,
+};
+use crate::config::config_pending_changes::{PendingChangesActor, PendingChangesRoute};
+use crate::config::config_poll_backoff::ListenerPollBackoff;
+use crate::config::config_propagation::PropagationTracer;
+use crate::config::config_propagation_sla::PropagationSlaMonitor;
+use crate::config::config_read_cache::ConfigReadCache;
+use crate::config::config_read_limit::ConfigReadLimiter;
+use crate::config::config_read_view::ConfigReadValue;
+use crate::config::config_read_view::ConfigReadView;
+use crate::config::config_search::IndexerActor;
+use crate::config::config_stats::ConfigStats;
+use crate::config::config_watch::ConfigWatchManage;
+use crate::config::config_webhook::ConfigValidationWebhook;
+use crate::config::core::{
+    ConfigActor, ConfigCmd, ConfigHistoryInfoDto, ConfigInfoDto, ConfigKey, ConfigResult,
+    ListenerItem,
+};
+use crate::config::dal::ConfigHistoryParam;
+use crate::console::SYSCONFIG_NAMESPACE;
 use crate::grpc::bistream_manage::BiStreamManage;
 use crate::naming::cluster::node_manage::{InnerNodeManage, NodeManage};
 use crate::naming::cluster::route::NamingRoute;
-use crate::naming::core::NamingActor;
+use crate::naming::core::{NamingActor, NamingCmd, NamingResult};
+use crate::naming::model::{Instance, ServiceDetailDto, ServiceKey};
+use crate::naming::service::ServiceInfoDto;
+use crate::naming::service_index::ServiceQueryParam;
+use crate::raft::cache::model::{CacheKey, CacheType, CacheValue};
 use crate::raft::cache::route::CacheRoute;
-use crate::raft::cache::CacheManager;
+use crate::raft::cache::{CacheLimiterReq, CacheManager, CacheManagerReq, CacheManagerResult};
+use crate::raft::cluster::model::{DelConfigReq, SetConfigReq};
 use crate::raft::cluster::route::ConfigRoute;
 use crate::raft::db::route::TableRoute;
 use crate::raft::db::table::TableManager;
 use crate::raft::filestore::core::FileStore;
 use crate::raft::network::factory::RaftClusterRequestSender;
+use crate::raft::read_lease::RaftReadGuard;
+use crate::raft::split_brain::SplitBrainDetector;
 use crate::raft::NacosRaft;
-use crate::user::UserManager;
-use actix::Addr;
+use crate::user::model::UserDto;
+use crate::user::{UserManager, UserManagerReq, UserManagerResult};
+use crate::utils::get_md5;
+use actix::{Addr, MailboxError};
+use actix_web::web::Data;
+use async_trait::async_trait;
 use bean_factory::FactoryData;
 use chrono::FixedOffset;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 pub struct AppShareData {
     pub config_addr: Addr<ConfigActor>,
     pub naming_addr: Addr<NamingActor>,
     pub bi_stream_manage: Addr<BiStreamManage>,
     pub raft: Arc<NacosRaft>,
+    pub raft_read_guard: Arc<RaftReadGuard>,
+    pub split_brain_detector: Arc<SplitBrainDetector>,
     pub raft_store: Arc<FileStore>,
     pub sys_config: Arc<AppSysConfig>,
     pub config_route: Arc<ConfigRoute>,
@@ -37,4 +92,538 @@ pub struct AppShareData {
     pub user_manager: Addr<UserManager>,
     pub cache_manager: Addr<CacheManager>,
     pub timezone_offset: Arc<FixedOffset>,
+    pub config_event_bus: ConfigEventSender,
+    pub config_read_view: Arc<ConfigReadView>,
+    pub config_read_cache: Arc<ConfigReadCache>,
+    pub config_watch: Arc<ConfigWatchManage>,
+    pub config_idempotency_cache: Arc<ConfigIdempotencyCache>,
+    pub config_batch_proposer: Arc<BatchProposer>,
+    pub config_stats: Arc<ConfigStats>,
+    pub config_indexer: Addr<IndexerActor>,
+    pub namespace_stats_cache: Arc<NamespaceStatsCache>,
+    pub config_circuit_breaker: Arc<CircuitBreaker>,
+    pub config_validation_webhook: Option<Arc<ConfigValidationWebhook>>,
+    pub config_validation_stats: Arc<ConfigValidationStats>,
+    pub config_propagation_tracer: Arc<PropagationTracer>,
+    pub namespace_merge_store: Arc<NamespaceMergeStore>,
+    pub config_consumer_registry: Arc<ConsumerRegistry>,
+    pub config_propagation_sla: Arc<PropagationSlaMonitor>,
+    pub config_access_logger: Arc<ConfigAccessLogger>,
+    pub config_listener_ack: Arc<ListenerAckManage>,
+    pub config_notify_dedup: Arc<ListenerNotifyDedup>,
+    pub config_poll_backoff: Arc<ListenerPollBackoff>,
+    pub pending_changes_addr: Addr<PendingChangesActor>,
+    pub pending_changes_route: Arc<PendingChangesRoute>,
+    pub config_archive: Option<Arc<ConfigArchiveStore>>,
+    pub config_field_cipher: Arc<ConfigFieldCipher>,
+    pub config_read_limiter: Arc<ConfigReadLimiter>,
+    pub sys_config_reloader: Arc<SysConfigReloader>,
+    pub config_kafka_publisher: Option<Arc<ConfigKafkaPublisher>>,
+    pub http_request_stats: Arc<HttpRequestStats>,
+    pub config_cmd_pending: Arc<PendingCmdStats>,
+}
+
+pub type ConfigStoreData = Data<Arc<dyn ConfigStore + Send + Sync>>;
+pub type NamingStoreData = Data<Arc<dyn NamingStore + Send + Sync>>;
+pub type AuthProviderData = Data<Arc<dyn AuthProvider + Send + Sync>>;
+
+impl AppShareData {
+    ///
+    /// 按处理器依赖的trait注册web数据
+    pub fn store_data(
+        app_data: &Arc<AppShareData>,
+    ) -> (ConfigStoreData, NamingStoreData, AuthProviderData) {
+        let config_store: Arc<dyn ConfigStore + Send + Sync> = app_data.clone();
+        let naming_store: Arc<dyn NamingStore + Send + Sync> = app_data.clone();
+        let auth_provider: Arc<dyn AuthProvider + Send + Sync> = app_data.clone();
+        (
+            Data::new(config_store),
+            Data::new(naming_store),
+            Data::new(auth_provider),
+        )
+    }
+
+    ///
+    /// 带超时请求ConfigActor,结果计入熔断器
+    pub async fn send_config_cmd(&self, cmd: ConfigCmd) -> anyhow::Result<ConfigResult> {
+        let _pending_guard = self.config_cmd_pending.start();
+        let res = self
+            .config_addr
+            .send(cmd)
+            .timeout(Duration::from_secs(CONFIG_SEND_TIMEOUT_SECONDS))
+            .await;
+        let timed_out = matches!(res, Err(MailboxError::Timeout));
+        self.config_circuit_breaker
+            .record(Instant::now(), timed_out);
+        mark_config_cmd_recorded();
+        res?
+    }
+
+    ///
+    /// 已归档的配置从对象存储读取内容,未归档的原样返回
+    pub async fn load_archived(
+        &self,
+        value: Arc<ConfigReadValue>,
+    ) -> anyhow::Result<Arc<ConfigReadValue>> {
+        match &self.config_archive {
+            Some(store) => store.load_read_value(value).await,
+            None => Ok(value),
+        }
+    }
+
+    ///
+    /// 从只读视图读取配置,已归档的配置读取归档内容
+    pub async fn load_config(
+        &self,
+        key: &ConfigKey,
+    ) -> anyhow::Result<Option<Arc<ConfigReadValue>>> {
+        match self.config_read_view.get(key) {
+            Some(v) => Ok(Some(self.load_archived(v).await?)),
+            None => Ok(None),
+        }
+    }
+
+    ///
+    /// 解密命名空间开启自动加密的字段,没有需要解密的字段时原样返回
+    pub fn decrypt_config_content(
+        &self,
+        tenant: &str,
+        content: &Arc<String>,
+        config_type: Option<&str>,
+    ) -> anyhow::Result<Arc<String>> {
+        let patterns = match self
+            .config_field_cipher
+            .namespace_patterns(&self.config_read_view, tenant)
+        {
+            Some(v) => v,
+            None => return Ok(content.clone()),
+        };
+        match self
+            .config_field_cipher
+            .decrypt_content(tenant, content, config_type, &patterns)?
+        {
+            Some(v) => Ok(Arc::new(v)),
+            None => Ok(content.clone()),
+        }
+    }
+
+    ///
+    /// 替换跨命名空间引用;被引用的配置与直接读取一样加载归档内容并解密字段,
+    /// 内部系统命名空间不允许被引用
+    pub async fn resolve_ns_refs(&self, content: &str, strict: bool) -> anyhow::Result<String> {
+        let mut refs: HashMap<ConfigKey, Option<Arc<String>>> = HashMap::new();
+        let mut pending = ns_ref_keys(content);
+        for _ in 0..NS_REF_MAX_DEPTH {
+            let mut next = vec![];
+            for key in pending {
+                if key.tenant.as_str() == SYSCONFIG_NAMESPACE || refs.contains_key(&key) {
+                    continue;
+                }
+                let content = match self.load_config(&key).await? {
+                    Some(v) => Some(self.decrypt_config_content(
+                        &key.tenant,
+                        &v.content,
+                        v.config_type.as_ref().map(|v| v.as_str()),
+                    )?),
+                    None => None,
+                };
+                if let Some(content) = &content {
+                    next.extend(ns_ref_keys(content));
+                }
+                refs.insert(key, content);
+            }
+            if next.is_empty() {
+                break;
+            }
+            pending = next;
+        }
+        let resolver = NamespaceRefResolver::new(
+            |key: &ConfigKey| refs.get(key).cloned().flatten(),
+            |tenant: &str| tenant != SYSCONFIG_NAMESPACE,
+            strict,
+        );
+        resolver.resolve(content)
+    }
+
+    ///
+    /// 内容包含跨命名空间引用时,替换引用后的内容与其md5;读取接口返回该md5,监听也按该md5比较
+    pub async fn resolve_served_content(
+        &self,
+        content: Arc<String>,
+        md5: Arc<String>,
+        strict: bool,
+    ) -> anyhow::Result<(Arc<String>, Arc<String>)> {
+        if !has_ns_refs(&content) {
+            return Ok((content, md5));
+        }
+        let resolved = self.resolve_ns_refs(&content, strict).await?;
+        if resolved.as_str() == content.as_str() {
+            return Ok((content, md5));
+        }
+        let md5 = Arc::new(get_md5(&resolved));
+        Ok((Arc::new(resolved), md5))
+    }
+
+    ///
+    /// 对比保存内容md5得到的变更中,去掉包含跨命名空间引用且客户端md5与返回内容md5一致的配置
+    pub async fn filter_ns_ref_changes(
+        &self,
+        items: &[ListenerItem],
+        changes: Vec<ConfigKey>,
+    ) -> Vec<ConfigKey> {
+        let mut result = Vec::with_capacity(changes.len());
+        for key in changes {
+            let item = items.iter().find(|v| v.key == key);
+            let value = self
+                .config_read_view
+                .get_effective(&key, self.sys_config.config_group_default_enabled);
+            if let (Some(item), Some(value)) = (item, value) {
+                if self.served_md5(&key, value).await.as_ref() == Some(&item.md5) {
+                    continue;
+                }
+            }
+            result.push(key);
+        }
+        result
+    }
+
+    async fn served_md5(
+        &self,
+        key: &ConfigKey,
+        value: Arc<ConfigReadValue>,
+    ) -> Option<Arc<String>> {
+        if value.archive_uri.is_none() && !has_ns_refs(&value.content) {
+            return None;
+        }
+        let value = self.load_archived(value).await.ok()?;
+        let content = self
+            .decrypt_config_content(
+                &key.tenant,
+                &value.content,
+                value.config_type.as_ref().map(|v| v.as_str()),
+            )
+            .ok()?;
+        self.resolve_served_content(content, value.md5.clone(), false)
+            .await
+            .ok()
+            .map(|(_, md5)| md5)
+    }
+}
+
+///
+/// 配置相关处理器依赖的存储能力,便于处理器脱离actor单独测试
+#[async_trait]
+pub trait ConfigStore: Send + Sync {
+    async fn query_config_page(
+        &self,
+        param: ConfigQueryParam,
+    ) -> anyhow::Result<(usize, Vec<ConfigInfoDto>)>;
+
+    async fn query_history_page(
+        &self,
+        param: ConfigHistoryParam,
+    ) -> anyhow::Result<(usize, Vec<ConfigHistoryInfoDto>)>;
+
+    ///
+    /// 只读取元数据,已归档的配置内容为空
+    fn get_config(&self, key: &ConfigKey) -> Option<Arc<ConfigReadValue>>;
+
+    ///
+    /// 读取配置,已归档的配置读取归档内容
+    async fn load_config(&self, key: &ConfigKey) -> anyhow::Result<Option<Arc<ConfigReadValue>>>;
+
+    async fn set_config(&self, req: SetConfigReq) -> anyhow::Result<()>;
+
+    async fn del_config(&self, req: DelConfigReq) -> anyhow::Result<()>;
+}
+
+///
+/// 服务与实例相关处理器依赖的存储能力
+#[async_trait]
+pub trait NamingStore: Send + Sync {
+    async fn query_service_page(
+        &self,
+        param: ServiceQueryParam,
+    ) -> anyhow::Result<(usize, Vec<ServiceInfoDto>)>;
+
+    async fn update_service(&self, service_info: ServiceDetailDto) -> anyhow::Result<()>;
+
+    async fn remove_service(&self, key: ServiceKey) -> anyhow::Result<()>;
+
+    async fn query_all_instances(&self, key: ServiceKey) -> anyhow::Result<Vec<Arc<Instance>>>;
+
+    async fn get_instance(&self, instance: Instance) -> anyhow::Result<Option<Arc<Instance>>>;
+
+    async fn update_instance(&self, instance: Instance) -> anyhow::Result<()>;
+
+    async fn delete_instance(&self, instance: Instance) -> anyhow::Result<()>;
+}
+
+///
+/// 控制台登录依赖的验证码、限流、用户校验与会话能力
+#[async_trait]
+pub trait AuthProvider: Send + Sync {
+    async fn get_captcha(&self, captcha_token: &str) -> Option<Arc<String>>;
+
+    ///
+    /// 登录限流,返回false表示超过限制
+    async fn acquire_login_limit(&self, limit_key: Arc<String>) -> anyhow::Result<bool>;
+
+    fn clear_login_limit(&self, limit_key: Arc<String>);
+
+    async fn check_user(
+        &self,
+        name: Arc<String>,
+        password: String,
+    ) -> anyhow::Result<(bool, UserDto)>;
+
+    fn save_session(&self, token: Arc<String>, session: Arc<UserSession>);
+}
+
+#[async_trait]
+impl ConfigStore for AppShareData {
+    async fn query_config_page(
+        &self,
+        param: ConfigQueryParam,
+    ) -> anyhow::Result<(usize, Vec<ConfigInfoDto>)> {
+        match self
+            .send_config_cmd(ConfigCmd::QueryPageInfo(Box::new(param)))
+            .await?
+        {
+            ConfigResult::ConfigInfoPage(size, list) => Ok((size, list)),
+            _ => Err(anyhow::anyhow!("config result error")),
+        }
+    }
+
+    async fn query_history_page(
+        &self,
+        param: ConfigHistoryParam,
+    ) -> anyhow::Result<(usize, Vec<ConfigHistoryInfoDto>)> {
+        match self
+            .send_config_cmd(ConfigCmd::QueryHistoryPageInfo(Box::new(param)))
+            .await?
+        {
+            ConfigResult::ConfigHistoryInfoPage(size, list) => Ok((size, list)),
+            _ => Err(anyhow::anyhow!("config result error")),
+        }
+    }
+
+    fn get_config(&self, key: &ConfigKey) -> Option<Arc<ConfigReadValue>> {
+        self.config_read_view.get(key)
+    }
+
+    async fn load_config(&self, key: &ConfigKey) -> anyhow::Result<Option<Arc<ConfigReadValue>>> {
+        AppShareData::load_config(self, key).await
+    }
+
+    async fn set_config(&self, req: SetConfigReq) -> anyhow::Result<()> {
+        self.config_route.set_config(req).await
+    }
+
+    async fn del_config(&self, req: DelConfigReq) -> anyhow::Result<()> {
+        self.config_route.del_config(req).await
+    }
+}
+
+#[async_trait]
+impl NamingStore for AppShareData {
+    async fn query_service_page(
+        &self,
+        param: ServiceQueryParam,
+    ) -> anyhow::Result<(usize, Vec<ServiceInfoDto>)> {
+        match self
+            .naming_addr
+            .send(NamingCmd::QueryServiceInfoPage(param))
+            .await??
+        {
+            NamingResult::ServiceInfoPage(v) => Ok(v),
+            _ => Err(anyhow::anyhow!("naming result error")),
+        }
+    }
+
+    async fn update_service(&self, service_info: ServiceDetailDto) -> anyhow::Result<()> {
+        self.naming_addr
+            .send(NamingCmd::UpdateService(service_info))
+            .await??;
+        Ok(())
+    }
+
+    async fn remove_service(&self, key: ServiceKey) -> anyhow::Result<()> {
+        self.naming_addr
+            .send(NamingCmd::RemoveService(key))
+            .await??;
+        Ok(())
+    }
+
+    async fn query_all_instances(&self, key: ServiceKey) -> anyhow::Result<Vec<Arc<Instance>>> {
+        match self
+            .naming_addr
+            .send(NamingCmd::QueryAllInstanceList(key))
+            .await??
+        {
+            NamingResult::InstanceList(list) => Ok(list),
+            _ => Err(anyhow::anyhow!("naming result error")),
+        }
+    }
+
+    async fn get_instance(&self, instance: Instance) -> anyhow::Result<Option<Arc<Instance>>> {
+        match self.naming_addr.send(NamingCmd::Query(instance)).await?? {
+            NamingResult::Instance(v) => Ok(Some(v)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn update_instance(&self, instance: Instance) -> anyhow::Result<()> {
+        self.naming_route.update_instance(instance, None).await
+    }
+
+    async fn delete_instance(&self, instance: Instance) -> anyhow::Result<()> {
+        self.naming_route.delete_instance(instance).await
+    }
+}
+
+#[async_trait]
+impl AuthProvider for AppShareData {
+    async fn get_captcha(&self, captcha_token: &str) -> Option<Arc<String>> {
+        let cache_req = CacheManagerReq::Get(CacheKey::new(
+            CacheType::String,
+            Arc::new(format!("Captcha_{}", captcha_token)),
+        ));
+        match self.cache_manager.send(cache_req).await {
+            Ok(Ok(CacheManagerResult::Value(CacheValue::String(v)))) => Some(v),
+            _ => None,
+        }
+    }
+
+    async fn acquire_login_limit(&self, limit_key: Arc<String>) -> anyhow::Result<bool> {
+        let limit_req = CacheLimiterReq::Hour {
+            key: limit_key,
+            limit: self.sys_config.console_login_one_hour_limit as i32,
+        };
+        match self.raft_cache_route.request_limiter(limit_req).await? {
+            CacheManagerResult::Limiter(acquire_result) => Ok(acquire_result),
+            _ => Err(anyhow::anyhow!("cache result error")),
+        }
+    }
+
+    fn clear_login_limit(&self, limit_key: Arc<String>) {
+        let clear_limit_req = CacheManagerReq::Remove(CacheKey::new(CacheType::String, limit_key));
+        self.cache_manager.do_send(clear_limit_req);
+    }
+
+    async fn check_user(
+        &self,
+        name: Arc<String>,
+        password: String,
+    ) -> anyhow::Result<(bool, UserDto)> {
+        match self
+            .user_manager
+            .send(UserManagerReq::CheckUser { name, password })
+            .await??
+        {
+            UserManagerResult::CheckUserResult(valid, user) => Ok((valid, user)),
+            _ => Err(anyhow::anyhow!("user result error")),
+        }
+    }
+
+    fn save_session(&self, token: Arc<String>, session: Arc<UserSession>) {
+        let cache_req = CacheManagerReq::Set {
+            key: CacheKey::new(CacheType::UserSession, token),
+            value: CacheValue::UserSession(session),
+            ttl: self.sys_config.console_login_timeout,
+        };
+        self.cache_manager.do_send(cache_req);
+    }
+}
+
+#[cfg(test)]
+pub mod mock {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::utils::get_md5;
+
+    ///
+    /// 基于内存的ConfigStore,用于处理器单元测试
+    #[derive(Default)]
+    pub struct MockConfigStore {
+        pub configs: Mutex<HashMap<ConfigKey, Arc<ConfigReadValue>>>,
+    }
+
+    impl MockConfigStore {
+        pub fn into_data(self) -> ConfigStoreData {
+            let store: Arc<dyn ConfigStore + Send + Sync> = Arc::new(self);
+            Data::new(store)
+        }
+    }
+
+    #[async_trait]
+    impl ConfigStore for MockConfigStore {
+        async fn query_config_page(
+            &self,
+            param: ConfigQueryParam,
+        ) -> anyhow::Result<(usize, Vec<ConfigInfoDto>)> {
+            let configs = self.configs.lock().unwrap();
+            let mut list: Vec<ConfigInfoDto> = configs
+                .iter()
+                .filter(|(k, _)| param.tenant.is_none() || param.tenant.as_ref() == Some(&k.tenant))
+                .map(|(k, v)| ConfigInfoDto {
+                    tenant: k.tenant.clone(),
+                    group: k.group.clone(),
+                    data_id: k.data_id.clone(),
+                    content: Some(v.content.clone()),
+                    md5: Some(v.md5.clone()),
+                })
+                .collect();
+            list.sort_by(|a, b| a.group.cmp(&b.group).then(a.data_id.cmp(&b.data_id)));
+            let total = list.len();
+            let list = list
+                .into_iter()
+                .skip(param.offset)
+                .take(param.limit)
+                .collect();
+            Ok((total, list))
+        }
+
+        async fn query_history_page(
+            &self,
+            _param: ConfigHistoryParam,
+        ) -> anyhow::Result<(usize, Vec<ConfigHistoryInfoDto>)> {
+            Ok((0, vec![]))
+        }
+
+        fn get_config(&self, key: &ConfigKey) -> Option<Arc<ConfigReadValue>> {
+            self.configs.lock().unwrap().get(key).cloned()
+        }
+
+        async fn load_config(
+            &self,
+            key: &ConfigKey,
+        ) -> anyhow::Result<Option<Arc<ConfigReadValue>>> {
+            Ok(self.get_config(key))
+        }
+
+        async fn set_config(&self, req: SetConfigReq) -> anyhow::Result<()> {
+            let value = ConfigReadValue {
+                md5: Arc::new(get_md5(&req.value)),
+                content: req.value,
+                config_type: req.config_type,
+                desc: req.desc,
+                detected_type: None,
+                archive_uri: None,
+                last_modified: 0,
+            };
+            self.configs
+                .lock()
+                .unwrap()
+                .insert(req.config_key, Arc::new(value));
+            Ok(())
+        }
+
+        async fn del_config(&self, req: DelConfigReq) -> anyhow::Result<()> {
+            self.configs.lock().unwrap().remove(&req.config_key);
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,241 @@
+use std::cell::Cell;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+pub const CIRCUIT_WINDOW_SECONDS: u64 = 10;
+pub const CIRCUIT_RETRY_AFTER_SECONDS: u64 = 5;
+pub const CONFIG_SEND_TIMEOUT_SECONDS: u64 = 3;
+//窗口内请求数过少时不打开熔断,避免单次超时触发
+const CIRCUIT_MIN_REQUESTS: u32 = 5;
+
+tokio::task_local! {
+    //当前请求是否已通过send_config_cmd计入熔断器,由熔断中间件设置作用域
+    pub static CONFIG_CMD_RECORDED: Cell<bool>;
+}
+
+///
+/// 在熔断中间件的请求作用域内标记请求已访问ConfigActor
+pub fn mark_config_cmd_recorded() {
+    CONFIG_CMD_RECORDED.try_with(|v| v.set(true)).ok();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+struct CircuitInner {
+    state: CircuitState,
+    window_start: Instant,
+    total: u32,
+    timeouts: u32,
+    opened_at: Instant,
+    probe_at: Option<Instant>,
+}
+
+///
+/// ConfigActor请求熔断器;窗口内超过一半请求超时则打开,
+/// 打开retry_after后放行一个探测请求,探测成功则关闭
+pub struct CircuitBreaker {
+    inner: Mutex<CircuitInner>,
+    window: Duration,
+    retry_after: Duration,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(
+            Duration::from_secs(CIRCUIT_WINDOW_SECONDS),
+            Duration::from_secs(CIRCUIT_RETRY_AFTER_SECONDS),
+        )
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(window: Duration, retry_after: Duration) -> Self {
+        let now = Instant::now();
+        Self {
+            inner: Mutex::new(CircuitInner {
+                state: CircuitState::Closed,
+                window_start: now,
+                total: 0,
+                timeouts: 0,
+                opened_at: now,
+                probe_at: None,
+            }),
+            window,
+            retry_after,
+        }
+    }
+
+    pub fn retry_after(&self) -> Duration {
+        self.retry_after
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.inner
+            .lock()
+            .map(|v| v.state)
+            .unwrap_or(CircuitState::Closed)
+    }
+
+    ///
+    /// 是否放行请求;半开状态同一时间只放行一个探测请求
+    pub fn try_acquire(&self, now: Instant) -> bool {
+        let mut inner = match self.inner.lock() {
+            Ok(v) => v,
+            Err(_) => return true,
+        };
+        match inner.state {
+            CircuitState::Closed => true,
+            CircuitState::Open => {
+                if now.duration_since(inner.opened_at) >= self.retry_after {
+                    inner.state = CircuitState::HalfOpen;
+                    inner.probe_at = Some(now);
+                    true
+                } else {
+                    false
+                }
+            }
+            CircuitState::HalfOpen => {
+                //探测请求没有访问ConfigActor时,超过重试间隔后放行新的探测请求
+                let probe_expired = inner
+                    .probe_at
+                    .map(|v| now.duration_since(v) >= self.retry_after)
+                    .unwrap_or(true);
+                if probe_expired {
+                    inner.probe_at = Some(now);
+                }
+                probe_expired
+            }
+        }
+    }
+
+    ///
+    /// 半开状态下放行的探测请求没有访问ConfigActor时释放探测名额,
+    /// 下一个请求可立即探测;acquired_at为调用try_acquire时传入的时间
+    pub fn release_probe(&self, acquired_at: Instant) {
+        let mut inner = match self.inner.lock() {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        if inner.state == CircuitState::HalfOpen && inner.probe_at == Some(acquired_at) {
+            inner.probe_at = None;
+        }
+    }
+
+    pub fn record(&self, now: Instant, timed_out: bool) {
+        let mut inner = match self.inner.lock() {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        match inner.state {
+            CircuitState::HalfOpen => {
+                if timed_out {
+                    inner.state = CircuitState::Open;
+                    inner.opened_at = now;
+                } else {
+                    inner.state = CircuitState::Closed;
+                    inner.window_start = now;
+                    inner.total = 0;
+                    inner.timeouts = 0;
+                }
+                inner.probe_at = None;
+            }
+            CircuitState::Open => {}
+            CircuitState::Closed => {
+                if now.duration_since(inner.window_start) >= self.window {
+                    inner.window_start = now;
+                    inner.total = 0;
+                    inner.timeouts = 0;
+                }
+                inner.total += 1;
+                if timed_out {
+                    inner.timeouts += 1;
+                }
+                if inner.total >= CIRCUIT_MIN_REQUESTS && inner.timeouts * 2 > inner.total {
+                    log::warn!(
+                        "config circuit breaker open, timeouts {}/{}",
+                        inner.timeouts,
+                        inner.total
+                    );
+                    inner.state = CircuitState::Open;
+                    inner.opened_at = now;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circuit_breaker_state() {
+        let breaker = CircuitBreaker::new(Duration::from_secs(10), Duration::from_secs(5));
+        let start = Instant::now();
+        for _ in 0..3 {
+            breaker.record(start, false);
+        }
+        for _ in 0..3 {
+            breaker.record(start, true);
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record(start, true);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.try_acquire(start + Duration::from_secs(4)));
+
+        //探测失败重新打开
+        let probe = start + Duration::from_secs(5);
+        assert!(breaker.try_acquire(probe));
+        assert!(!breaker.try_acquire(probe));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        breaker.record(probe, true);
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        //探测成功关闭
+        let probe = probe + Duration::from_secs(5);
+        assert!(breaker.try_acquire(probe));
+        breaker.record(probe, false);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.try_acquire(probe));
+    }
+
+    #[test]
+    fn circuit_breaker_release_probe() {
+        let breaker = CircuitBreaker::new(Duration::from_secs(10), Duration::from_secs(5));
+        let start = Instant::now();
+        for _ in 0..5 {
+            breaker.record(start, true);
+        }
+        let probe = start + Duration::from_secs(5);
+        assert!(breaker.try_acquire(probe));
+        //其它请求持有探测名额时不释放
+        breaker.release_probe(probe + Duration::from_millis(1));
+        assert!(!breaker.try_acquire(probe + Duration::from_millis(2)));
+        //探测请求没有访问ConfigActor,释放后下一个请求继续探测
+        breaker.release_probe(probe);
+        let next = probe + Duration::from_millis(3);
+        assert!(breaker.try_acquire(next));
+        breaker.record(next, false);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn circuit_breaker_window() {
+        let breaker = CircuitBreaker::new(Duration::from_secs(10), Duration::from_secs(5));
+        let start = Instant::now();
+        for _ in 0..4 {
+            breaker.record(start, true);
+        }
+        //超时请求分布在不同窗口时不打开
+        breaker.record(start + Duration::from_secs(10), true);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
@@ -6,7 +6,7 @@ pub const EMPTY_STR: &str = "";
 
 pub const HTTP_METHOD_GET: &str = "GET";
 //pub const HTTP_METHOD_PUT:&str= "PUT";
-//pub const HTTP_METHOD_POST:&str= "POST";
+pub const HTTP_METHOD_POST: &str = "POST";
 //pub const HTTP_METHOD_DELETE:&str= "DELETE";
 pub const HTTP_METHOD_ALL: &str = EMPTY_STR;
 
@@ -14,11 +14,22 @@ pub const SEQ_KEY_CONFIG: &str = "SEQ_CONFIG";
 
 pub const AUTHORIZATION_HEADER: &str = "Authorization";
 pub const ACCESS_TOKEN_HEADER: &str = "accessToken";
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+pub const DEFAULT_USED_HEADER: &str = "X-Rnacos-Default-Used";
+pub const CONSISTENCY_HEADER: &str = "X-Rnacos-Consistency";
+pub const ARCHIVED_HEADER: &str = "X-Rnacos-Archived";
+pub const ALIAS_OF_HEADER: &str = "X-Rnacos-Alias-Of";
+pub const CREATED_FROM_TEMPLATE_HEADER: &str = "X-Rnacos-Created-From-Template";
+//长轮询客户端的稳定标识,用于同一客户端多个连接的变更通知去重
+pub const LISTENER_CLIENT_ID_HEADER: &str = "Client-Id";
+//长轮询返回的建议轮询间隔(秒),只作为建议
+pub const SUGGESTED_INTERVAL_HEADER: &str = "X-Rnacos-Suggested-Interval";
 
 lazy_static::lazy_static! {
     pub static ref CONFIG_TREE_NAME: Arc<String> =  Arc::new("T_CONFIG".to_string());
     pub static ref SEQUENCE_TREE_NAME: Arc<String> =  Arc::new("T_SEQUENCE".to_string());
     pub static ref USER_TREE_NAME: Arc<String> =  Arc::new("T_USER".to_string());
     pub static ref CACHE_TREE_NAME: Arc<String> =  Arc::new("T_CACHE".to_string());
+    pub static ref CONFIG_DELETE_HISTORY_TREE_NAME: Arc<String> =  Arc::new("T_CONFIG_DELETE_HISTORY".to_string());
     pub static ref EMPTY_ARC_STRING: Arc<String> = Arc::new("".to_string());
 }
@@ -50,3 +50,15 @@ pub fn decrypt_aes128(key: &str, iv: &str, cipher: &[u8]) -> anyhow::Result<Vec<
         Err(e) => Err(anyhow::anyhow!("decrypt error,{}", &e)),
     }
 }
+
+///
+/// 比较耗时只与长度有关,用于校验令牌
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
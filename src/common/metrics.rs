@@ -0,0 +1,121 @@
+use actix_web::{HttpResponse, Responder};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    TextEncoder,
+};
+
+lazy_static::lazy_static! {
+    /// 配置读缓存命中统计,result取值hit/miss
+    pub static ref CONFIG_READ_CACHE_COUNTER: IntCounterVec = {
+        let counter = IntCounterVec::new(
+            Opts::new(
+                "rnacos_config_read_cache_requests_total",
+                "config read-through cache requests",
+            ),
+            &["result"],
+        )
+        .unwrap();
+        prometheus::register(Box::new(counter.clone())).ok();
+        counter
+    };
+
+    /// 访问日志写入跟不上、缓冲区已满时丢弃的记录数
+    pub static ref CONFIG_ACCESS_LOG_DROPPED_COUNTER: IntCounter = {
+        let counter = IntCounter::new(
+            "rnacos_config_access_log_dropped_total",
+            "config access log records dropped because the write buffer is full",
+        )
+        .unwrap();
+        prometheus::register(Box::new(counter.clone())).ok();
+        counter
+    };
+
+    /// 启动时预热到读缓存的配置数
+    pub static ref CONFIG_CACHE_PREWARM_GAUGE: IntGauge = {
+        let gauge = IntGauge::new(
+            "rnacos_config_cache_prewarmed",
+            "configs pre-loaded into the read cache on startup",
+        )
+        .unwrap();
+        prometheus::register(Box::new(gauge.clone())).ok();
+        gauge
+    };
+
+    /// 相同内容的配置共享存储后节省的内存字节数
+    pub static ref CONFIG_DEDUP_SAVINGS_GAUGE: IntGauge = {
+        let gauge = IntGauge::new(
+            "rnacos_config_dedup_savings_bytes",
+            "bytes saved by sharing identical config content",
+        )
+        .unwrap();
+        prometheus::register(Box::new(gauge.clone())).ok();
+        gauge
+    };
+
+    /// 配置变更超过传播时限仍有使用方未更新的次数
+    pub static ref CONFIG_PROPAGATION_SLA_VIOLATION_COUNTER: IntCounterVec = {
+        let counter = IntCounterVec::new(
+            Opts::new(
+                "rnacos_config_propagation_sla_violations_total",
+                "config changes not picked up by all consumers within the propagation sla",
+            ),
+            &["namespace"],
+        )
+        .unwrap();
+        prometheus::register(Box::new(counter.clone())).ok();
+        counter
+    };
+
+    /// 配置变更到通知监听方的延迟(毫秒),按通知优先级区分
+    pub static ref CONFIG_NOTIFICATION_LATENCY_HISTOGRAM: HistogramVec = {
+        let histogram = HistogramVec::new(
+            HistogramOpts::new(
+                "rnacos_notification_latency_ms",
+                "latency from config change to listener notification in milliseconds",
+            )
+            .buckets(vec![1.0, 5.0, 10.0, 50.0, 100.0, 500.0, 1000.0, 5000.0, 10000.0]),
+            &["priority"],
+        )
+        .unwrap();
+        prometheus::register(Box::new(histogram.clone())).ok();
+        histogram
+    };
+
+    /// 各命名空间的配置数量
+    pub static ref NAMESPACE_CONFIG_COUNT_GAUGE: IntGaugeVec = {
+        let gauge = IntGaugeVec::new(
+            Opts::new("rnacos_namespace_config_count", "config count of namespace"),
+            &["namespace"],
+        )
+        .unwrap();
+        prometheus::register(Box::new(gauge.clone())).ok();
+        gauge
+    };
+
+    /// 各命名空间的配置内容总字节数
+    pub static ref NAMESPACE_CONTENT_BYTES_GAUGE: IntGaugeVec = {
+        let gauge = IntGaugeVec::new(
+            Opts::new(
+                "rnacos_namespace_content_bytes",
+                "total config content bytes of namespace",
+            ),
+            &["namespace"],
+        )
+        .unwrap();
+        prometheus::register(Box::new(gauge.clone())).ok();
+        gauge
+    };
+}
+
+///
+/// 以prometheus文本格式输出指标
+pub async fn metrics() -> impl Responder {
+    let encoder = TextEncoder::new();
+    let mut buf = vec![];
+    if let Err(err) = encoder.encode(&prometheus::gather(), &mut buf) {
+        return HttpResponse::InternalServerError().body(err.to_string());
+    }
+    HttpResponse::Ok()
+        .content_type(encoder.format_type())
+        .body(buf)
+}
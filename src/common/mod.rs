@@ -1,10 +1,24 @@
 use lazy_static::lazy_static;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+use crate::config::config_archive::ConfigArchiveConfig;
+use crate::config::config_federation::FederationConfig;
+use crate::config::config_feed::parse_feed_tokens;
+use crate::config::config_file_watch::FileWatchConfig;
+use crate::config::config_git_sync::GitSyncConfig;
+use crate::config::config_kafka::KafkaConfig;
+use crate::config::config_kms::KmsConfig;
+use crate::config::config_render::{load_render_envs, DEFAULT_RENDER_ENVS};
+use crate::config::config_wal::ConfigWalConfig;
+use crate::config::config_webhook::ValidationWebhook;
+use crate::raft::read_lease::ReadConsistency;
+
 pub mod actor_utils;
 pub mod appdata;
 pub mod byte_utils;
+pub mod circuit_breaker;
 pub mod constant;
 pub mod crypto_utils;
 pub mod cycle_queue;
@@ -12,12 +26,15 @@ pub mod datetime_utils;
 pub mod delay_notify;
 pub mod hash_utils;
 pub mod limiter_utils;
+pub mod metrics;
 pub mod model;
 pub mod protobuf_utils;
 pub mod rusqlite_utils;
 pub mod sequence_utils;
 pub mod sled_utils;
 pub mod string_utils;
+pub mod sys_config_reload;
+pub mod thread_pool_stats;
 pub mod web_utils;
 
 lazy_static! {
@@ -65,12 +82,56 @@ pub struct AppSysConfig {
     pub raft_auto_init: bool,
     pub raft_join_addr: String,
     pub raft_snapshot_log_size: u64,
+    pub raft_log_compaction_trigger_entries: u64,
+    pub raft_quorum_timeout_ms: u64,
     pub console_login_timeout: i32,
     pub console_login_one_hour_limit: u32,
     pub gmt_fixed_offset_hours: Option<i32>,
     pub openapi_login_timeout: i32,
     pub openapi_login_one_minute_limit: u32,
     pub openapi_enable_auth: bool,
+    pub config_import_url_one_minute_limit: u32,
+    //允许作为配置渲染上下文的环境变量,启动时读取
+    pub config_render_envs: Vec<(String, String)>,
+    pub config_read_cache_capacity: usize,
+    pub config_read_cache_ttl_seconds: u64,
+    pub grpc_ping_interval_millis: u64,
+    pub grpc_pong_timeout_millis: u64,
+    pub min_compress_bytes: usize,
+    pub config_batch_window_ms: u64,
+    pub config_max_versions_per_key: usize,
+    pub config_group_default_enabled: bool,
+    pub config_read_consistency: ReadConsistency,
+    pub config_validation_webhook: Option<ValidationWebhook>,
+    pub config_notify_debounce_ms: u64,
+    pub config_change_rate_cooldown_ms: u64,
+    pub config_cmd_pending_warn_threshold: u64,
+    pub config_consumer_heartbeat_interval_ms: u64,
+    pub config_access_log_enabled: bool,
+    pub config_access_log_path: String,
+    pub config_listener_ack_timeout_ms: u64,
+    //连续多少次长轮询没有变更后返回建议轮询间隔
+    pub config_poll_backoff_threshold: u32,
+    pub config_poll_backoff_base_secs: u64,
+    //建议轮询间隔的上限,0表示不返回建议
+    pub max_poll_interval_secs: u64,
+    pub per_key_read_rate_limit: Option<usize>,
+    pub config_cache_prewarm: bool,
+    pub config_cache_prewarm_count: usize,
+    pub config_file_watch: Option<FileWatchConfig>,
+    pub config_git_sync: Option<GitSyncConfig>,
+    pub config_archive: Option<ConfigArchiveConfig>,
+    pub config_encryption_key: String,
+    pub config_encryption_kms: KmsConfig,
+    pub config_kafka: Option<KafkaConfig>,
+    //命名空间 -> 配置变更订阅源的只读令牌,令牌只能访问所属命名空间的订阅源
+    pub config_feed_tokens: HashMap<String, String>,
+    //单机模式,配置写入不经过raft
+    pub config_wal: Option<ConfigWalConfig>,
+    //启动时加载的配置文件,运行时检查其变化
+    pub env_file: Option<String>,
+    pub namespace_retention_days: u64,
+    pub federation: Option<FederationConfig>,
 }
 
 impl AppSysConfig {
@@ -129,6 +190,15 @@ impl AppSysConfig {
             .unwrap_or("10000".to_owned())
             .parse()
             .unwrap_or(10000);
+        let raft_quorum_timeout_ms = std::env::var("RNACOS_RAFT_QUORUM_TIMEOUT_MS")
+            .unwrap_or("10000".to_owned())
+            .parse()
+            .unwrap_or(10000);
+        let raft_log_compaction_trigger_entries =
+            std::env::var("RNACOS_RAFT_LOG_COMPACTION_TRIGGER_ENTRIES")
+                .unwrap_or("10000".to_owned())
+                .parse()
+                .unwrap_or(10000);
         let enable_no_auth_console = std::env::var("RNACOS_ENABLE_NO_AUTH_CONSOLE")
             .unwrap_or("false".to_owned())
             .parse()
@@ -141,6 +211,122 @@ impl AppSysConfig {
             .unwrap_or("false".to_owned())
             .parse()
             .unwrap_or(false);
+        let config_import_url_one_minute_limit =
+            std::env::var("RNACOS_CONFIG_IMPORT_URL_ONE_MINUTE_LIMIT")
+                .unwrap_or("10".to_owned())
+                .parse()
+                .unwrap_or(10);
+        let config_render_envs = load_render_envs(
+            &std::env::var("RNACOS_CONFIG_RENDER_ENVS").unwrap_or(DEFAULT_RENDER_ENVS.to_owned()),
+        );
+        let config_read_cache_capacity = std::env::var("RNACOS_CONFIG_READ_CACHE_CAPACITY")
+            .unwrap_or("10000".to_owned())
+            .parse()
+            .unwrap_or(10000);
+        let config_read_cache_ttl_seconds = std::env::var("RNACOS_CONFIG_READ_CACHE_TTL_SECONDS")
+            .unwrap_or("60".to_owned())
+            .parse()
+            .unwrap_or(60);
+        let grpc_ping_interval_millis = std::env::var("RNACOS_GRPC_PING_INTERVAL_MILLIS")
+            .unwrap_or("15000".to_owned())
+            .parse()
+            .unwrap_or(15000);
+        let grpc_pong_timeout_millis = std::env::var("RNACOS_GRPC_PONG_TIMEOUT_MILLIS")
+            .unwrap_or("3000".to_owned())
+            .parse()
+            .unwrap_or(3000);
+        let min_compress_bytes = std::env::var("RNACOS_MIN_COMPRESS_BYTES")
+            .unwrap_or("1024".to_owned())
+            .parse()
+            .unwrap_or(1024);
+        let config_batch_window_ms = std::env::var("RNACOS_CONFIG_BATCH_WINDOW_MS")
+            .unwrap_or("5".to_owned())
+            .parse()
+            .unwrap_or(5);
+        let config_max_versions_per_key = std::env::var("RNACOS_CONFIG_MAX_VERSIONS_PER_KEY")
+            .unwrap_or("100".to_owned())
+            .parse()
+            .unwrap_or(100)
+            .max(1);
+        let config_group_default_enabled = std::env::var("RNACOS_CONFIG_GROUP_DEFAULT_ENABLED")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let config_read_consistency = std::env::var("RNACOS_CONFIG_READ_CONSISTENCY")
+            .ok()
+            .and_then(|v| ReadConsistency::new_by_value(&v))
+            .unwrap_or_default();
+        let config_validation_webhook = ValidationWebhook::from_env();
+        let config_consumer_heartbeat_interval_ms =
+            std::env::var("RNACOS_CONFIG_CONSUMER_HEARTBEAT_INTERVAL_MS")
+                .unwrap_or("30000".to_owned())
+                .parse()
+                .unwrap_or(30000);
+        let config_access_log_enabled = std::env::var("RNACOS_CONFIG_ACCESS_LOG_ENABLED")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let config_access_log_path = std::env::var("RNACOS_CONFIG_ACCESS_LOG_PATH")
+            .unwrap_or("config_access.log".to_owned());
+        let config_listener_ack_timeout_ms = std::env::var("RNACOS_CONFIG_LISTENER_ACK_TIMEOUT_MS")
+            .unwrap_or("5000".to_owned())
+            .parse()
+            .unwrap_or(5000);
+        let config_poll_backoff_threshold = std::env::var("RNACOS_CONFIG_POLL_BACKOFF_THRESHOLD")
+            .unwrap_or("3".to_owned())
+            .parse()
+            .unwrap_or(3);
+        let config_poll_backoff_base_secs = std::env::var("RNACOS_CONFIG_POLL_BACKOFF_BASE_SECS")
+            .unwrap_or("30".to_owned())
+            .parse()
+            .unwrap_or(30);
+        let max_poll_interval_secs = std::env::var("RNACOS_CONFIG_MAX_POLL_INTERVAL_SECS")
+            .unwrap_or("0".to_owned())
+            .parse()
+            .unwrap_or(0);
+        let per_key_read_rate_limit = std::env::var("RNACOS_CONFIG_PER_KEY_READ_LIMIT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0);
+        let config_cache_prewarm = std::env::var("RNACOS_CONFIG_CACHE_PREWARM")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let config_cache_prewarm_count = std::env::var("RNACOS_CONFIG_CACHE_PREWARM_COUNT")
+            .unwrap_or("100".to_owned())
+            .parse()
+            .unwrap_or(100);
+        let config_file_watch = FileWatchConfig::from_env();
+        let config_git_sync = GitSyncConfig::from_env(&config_db_dir);
+        let config_archive = ConfigArchiveConfig::from_env();
+        let config_encryption_key =
+            std::env::var("RNACOS_CONFIG_ENCRYPTION_KEY").unwrap_or_default();
+        let config_encryption_kms = KmsConfig::from_env();
+        let config_kafka = KafkaConfig::from_env();
+        let config_wal = ConfigWalConfig::from_env(&config_db_dir);
+        let config_feed_tokens =
+            parse_feed_tokens(&std::env::var("RNACOS_CONFIG_FEED_TOKENS").unwrap_or_default());
+        let env_file = std::env::var("RNACOS_ENV_FILE")
+            .ok()
+            .filter(|v| !v.is_empty());
+        let federation = FederationConfig::from_env();
+        let namespace_retention_days = std::env::var("RNACOS_NAMESPACE_RETENTION_DAYS")
+            .unwrap_or("7".to_owned())
+            .parse()
+            .unwrap_or(7);
+        let config_notify_debounce_ms = std::env::var("RNACOS_CONFIG_NOTIFY_DEBOUNCE_MS")
+            .unwrap_or("0".to_owned())
+            .parse()
+            .unwrap_or(0);
+        let config_change_rate_cooldown_ms = std::env::var("RNACOS_CONFIG_CHANGE_RATE_COOLDOWN_MS")
+            .unwrap_or("1000".to_owned())
+            .parse()
+            .unwrap_or(1000);
+        let config_cmd_pending_warn_threshold =
+            std::env::var("RNACOS_CONFIG_CMD_PENDING_WARN_THRESHOLD")
+                .unwrap_or("1000".to_owned())
+                .parse()
+                .unwrap_or(1000);
         Self {
             config_db_dir,
             config_db_file,
@@ -155,12 +341,50 @@ impl AppSysConfig {
             raft_auto_init,
             raft_join_addr,
             raft_snapshot_log_size,
+            raft_log_compaction_trigger_entries,
+            raft_quorum_timeout_ms,
             console_login_timeout,
             console_login_one_hour_limit,
             openapi_login_timeout,
             openapi_login_one_minute_limit,
             gmt_fixed_offset_hours,
             openapi_enable_auth,
+            config_import_url_one_minute_limit,
+            config_render_envs,
+            config_read_cache_capacity,
+            config_read_cache_ttl_seconds,
+            grpc_ping_interval_millis,
+            grpc_pong_timeout_millis,
+            min_compress_bytes,
+            config_batch_window_ms,
+            config_max_versions_per_key,
+            config_group_default_enabled,
+            config_read_consistency,
+            config_validation_webhook,
+            config_notify_debounce_ms,
+            config_change_rate_cooldown_ms,
+            config_cmd_pending_warn_threshold,
+            config_consumer_heartbeat_interval_ms,
+            config_access_log_enabled,
+            config_access_log_path,
+            config_listener_ack_timeout_ms,
+            config_poll_backoff_threshold,
+            config_poll_backoff_base_secs,
+            max_poll_interval_secs,
+            per_key_read_rate_limit,
+            config_cache_prewarm,
+            config_cache_prewarm_count,
+            config_file_watch,
+            config_git_sync,
+            config_archive,
+            config_encryption_key,
+            config_encryption_kms,
+            config_kafka,
+            config_feed_tokens,
+            config_wal,
+            env_file,
+            namespace_retention_days,
+            federation,
         }
     }
 
@@ -0,0 +1,173 @@
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+///
+/// http工作线程的请求统计,由中间件在请求开始与结束时记录
+#[derive(Default)]
+pub struct HttpRequestStats {
+    in_flight: AtomicI64,
+    completed: AtomicU64,
+    total_duration_us: AtomicU64,
+}
+
+impl HttpRequestStats {
+    ///
+    /// 返回的guard释放时记为完成,请求被取消时同样计入
+    pub fn start(self: &Arc<Self>) -> HttpRequestGuard {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        HttpRequestGuard {
+            stats: self.clone(),
+            start: Instant::now(),
+        }
+    }
+
+    fn finish(&self, duration: Duration) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+        self.completed.fetch_add(1, Ordering::Relaxed);
+        self.total_duration_us
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn in_flight(&self) -> u64 {
+        self.in_flight.load(Ordering::Relaxed).max(0) as u64
+    }
+
+    pub fn completed(&self) -> u64 {
+        self.completed.load(Ordering::Relaxed)
+    }
+
+    pub fn avg_duration_ms(&self) -> f64 {
+        let completed = self.completed();
+        if completed == 0 {
+            return 0f64;
+        }
+        self.total_duration_us.load(Ordering::Relaxed) as f64 / completed as f64 / 1000f64
+    }
+}
+
+pub struct HttpRequestGuard {
+    stats: Arc<HttpRequestStats>,
+    start: Instant,
+}
+
+impl Drop for HttpRequestGuard {
+    fn drop(&mut self) {
+        self.stats.finish(self.start.elapsed());
+    }
+}
+
+///
+/// 经AppShareData::send_config_cmd发送、尚未返回结果的ConfigCmd数;
+/// actix不暴露邮箱长度,其它直接发送给ConfigActor的消息不计入,不等同于邮箱长度
+pub struct PendingCmdStats {
+    pending: AtomicI64,
+    //0表示不告警
+    warn_threshold: u64,
+    warned: AtomicBool,
+}
+
+impl PendingCmdStats {
+    pub fn new(warn_threshold: u64) -> Self {
+        Self {
+            pending: AtomicI64::new(0),
+            warn_threshold,
+            warned: AtomicBool::new(false),
+        }
+    }
+
+    ///
+    /// 返回的guard释放时记为已返回
+    pub fn start(self: &Arc<Self>) -> PendingCmdGuard {
+        let pending = self.pending.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.warn_threshold > 0
+            && pending as u64 > self.warn_threshold
+            && !self.warned.swap(true, Ordering::Relaxed)
+        {
+            log::warn!(
+                "pending config cmds {} exceed config_cmd_pending_warn_threshold {}",
+                pending,
+                self.warn_threshold
+            );
+        }
+        PendingCmdGuard {
+            stats: self.clone(),
+        }
+    }
+
+    fn finish(&self) {
+        let pending = self.pending.fetch_sub(1, Ordering::Relaxed) - 1;
+        //降到阈值一半以下后允许再次告警
+        if self.warn_threshold > 0 && (pending.max(0) as u64) < self.warn_threshold / 2 {
+            self.warned.store(false, Ordering::Relaxed);
+        }
+    }
+
+    pub fn pending(&self) -> u64 {
+        self.pending.load(Ordering::Relaxed).max(0) as u64
+    }
+
+    pub fn is_over_threshold(&self) -> bool {
+        self.warn_threshold > 0 && self.pending() > self.warn_threshold
+    }
+
+    pub fn warn_threshold(&self) -> u64 {
+        self.warn_threshold
+    }
+}
+
+pub struct PendingCmdGuard {
+    stats: Arc<PendingCmdStats>,
+}
+
+impl Drop for PendingCmdGuard {
+    fn drop(&mut self) {
+        self.stats.finish();
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadPoolInfo {
+    pub worker_threads: usize,
+    //处理中的请求数,不含长轮询监听
+    pub pending_tasks: u64,
+    pub completed_tasks: u64,
+    pub avg_task_duration_ms: f64,
+    //经send_config_cmd发送未返回的ConfigCmd数,不是ConfigActor邮箱长度
+    pub config_cmd_pending: u64,
+    pub config_cmd_pending_warn_threshold: u64,
+    pub config_cmd_pending_warn: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_and_pending_cmd_stats() {
+        let stats = Arc::new(HttpRequestStats::default());
+        let first = stats.start();
+        let second = stats.start();
+        assert_eq!(stats.in_flight(), 2);
+        drop(first);
+        assert_eq!(stats.in_flight(), 1);
+        drop(second);
+        assert_eq!(stats.in_flight(), 0);
+        assert_eq!(stats.completed(), 2);
+        assert!(stats.avg_duration_ms() >= 0f64);
+
+        let cmds = Arc::new(PendingCmdStats::new(2));
+        let mut guards = vec![cmds.start(), cmds.start()];
+        assert!(!cmds.is_over_threshold());
+        guards.push(cmds.start());
+        assert!(cmds.is_over_threshold());
+        assert_eq!(cmds.pending(), 3);
+        assert!(cmds.warned.load(Ordering::Relaxed));
+        guards.clear();
+        assert_eq!(cmds.pending(), 0);
+        assert!(!cmds.warned.load(Ordering::Relaxed));
+    }
+}
@@ -1,7 +1,20 @@
-use actix_web::web;
+use actix_web::dev::Decompress;
+use actix_web::http::header::{HeaderMap, CONTENT_ENCODING};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+use std::sync::Arc;
 use tokio_stream::StreamExt;
 
+use crate::common::constant::{HTTP_METHOD_GET, HTTP_METHOD_POST};
+use crate::common::model::{TokenSession, UserSession};
+use crate::config::model::ConfigInUseError;
+use crate::user::permission::UserRole;
+
 const MAX_SIZE: usize = 10485760;
+const SUPPORT_CONTENT_ENCODINGS: [&str; 4] = ["identity", "gzip", "x-gzip", "zstd"];
+
+#[derive(Debug, thiserror::Error)]
+#[error("unsupported content-encoding: {0}")]
+pub struct UnsupportedEncodingError(pub String);
 
 pub async fn get_req_body(mut payload: web::Payload) -> anyhow::Result<Vec<u8>> {
     let mut body = web::BytesMut::new();
@@ -15,3 +28,129 @@ pub async fn get_req_body(mut payload: web::Payload) -> anyhow::Result<Vec<u8>>
     }
     Ok(body.to_vec())
 }
+
+pub fn check_content_encoding(headers: &HeaderMap) -> Result<(), UnsupportedEncodingError> {
+    if let Some(v) = headers.get(CONTENT_ENCODING) {
+        let encoding = v.to_str().unwrap_or_default().trim().to_lowercase();
+        if !SUPPORT_CONTENT_ENCODINGS.contains(&encoding.as_str()) {
+            return Err(UnsupportedEncodingError(encoding));
+        }
+    }
+    Ok(())
+}
+
+///
+/// 按Content-Encoding(gzip/zstd)解压请求体;解压后的大小同样受MAX_SIZE限制,避免压缩炸弹
+pub async fn get_decoded_req_body(
+    req: &HttpRequest,
+    payload: web::Payload,
+) -> anyhow::Result<Vec<u8>> {
+    check_content_encoding(req.headers())?;
+    let mut payload = Decompress::from_headers(payload.into_inner(), req.headers());
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if (body.len() + chunk.len()) > MAX_SIZE {
+            return Err(anyhow::anyhow!("overflow"));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body.to_vec())
+}
+
+///
+/// 不支持的压缩格式返回415,其它读取错误保持500
+pub fn req_body_error_response(err: anyhow::Error) -> HttpResponse {
+    if err.downcast_ref::<UnsupportedEncodingError>().is_some() {
+        HttpResponse::UnsupportedMediaType().body(err.to_string())
+    } else {
+        HttpResponse::InternalServerError().body(err.to_string())
+    }
+}
+
+///
+/// 删除被其它配置依赖的配置时返回409及依赖方列表
+pub fn config_in_use_response(err: &anyhow::Error) -> Option<HttpResponse> {
+    err.downcast_ref::<ConfigInUseError>().map(|e| {
+        HttpResponse::Conflict().json(serde_json::json!({
+            "error": "CONFIG_IN_USE",
+            "dependents": e.dependents,
+        }))
+    })
+}
+
+///
+/// 控制台会话或openapi令牌需有配置写权限;未开启鉴权(没有会话)时放行
+pub fn has_config_write_permission(req: &HttpRequest) -> bool {
+    let extensions = req.extensions();
+    let roles = if let Some(session) = extensions.get::<Arc<UserSession>>() {
+        &session.roles
+    } else if let Some(session) = extensions.get::<Arc<TokenSession>>() {
+        &session.roles
+    } else {
+        return true;
+    };
+    UserRole::match_url_by_roles(roles, "/rnacos/api/console/cs/configs", HTTP_METHOD_POST)
+}
+
+///
+/// 控制台会话或openapi令牌需有配置读权限;未开启鉴权(没有会话)时放行
+pub fn has_config_read_permission(req: &HttpRequest) -> bool {
+    let extensions = req.extensions();
+    let roles = if let Some(session) = extensions.get::<Arc<UserSession>>() {
+        &session.roles
+    } else if let Some(session) = extensions.get::<Arc<TokenSession>>() {
+        &session.roles
+    } else {
+        return true;
+    };
+    UserRole::match_url_by_roles(roles, "/rnacos/api/console/cs/configs", HTTP_METHOD_GET)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use actix_web::FromRequest;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    async fn decode(req: TestRequest, body: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        let (req, mut pl) = req.set_payload(body).to_http_parts();
+        let payload = web::Payload::from_request(&req, &mut pl).await.unwrap();
+        get_decoded_req_body(&req, payload).await
+    }
+
+    #[actix_web::test]
+    async fn decode_req_body() {
+        let body = b"dataId=a&group=b&content=c".to_vec();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&body).unwrap();
+        let gzip_body = encoder.finish().unwrap();
+        let req = TestRequest::post().insert_header((CONTENT_ENCODING, "gzip"));
+        assert_eq!(decode(req, gzip_body).await.unwrap(), body);
+
+        let zstd_body = zstd::encode_all(body.as_slice(), 0).unwrap();
+        let req = TestRequest::post().insert_header((CONTENT_ENCODING, "zstd"));
+        assert_eq!(decode(req, zstd_body).await.unwrap(), body);
+
+        assert_eq!(
+            decode(TestRequest::post(), body.clone()).await.unwrap(),
+            body
+        );
+        let req = TestRequest::post().insert_header((CONTENT_ENCODING, "compress"));
+        let err = decode(req, body).await.unwrap_err();
+        assert!(err.downcast_ref::<UnsupportedEncodingError>().is_some());
+    }
+
+    #[actix_web::test]
+    async fn decode_req_body_overflow() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&vec![0u8; MAX_SIZE + 1]).unwrap();
+        let gzip_body = encoder.finish().unwrap();
+        assert!(gzip_body.len() < MAX_SIZE);
+        let req = TestRequest::post().insert_header((CONTENT_ENCODING, "gzip"));
+        assert!(decode(req, gzip_body).await.is_err());
+    }
+}
@@ -0,0 +1,220 @@
+use std::borrow::Cow;
+
+use actix_web::web::Bytes;
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::mpsc;
+
+use super::config_access_log::ConfigAccessRecord;
+
+pub const AUDIT_CSV_HEADER: &str =
+    "timestamp,operation,tenant,group,dataId,user,clientIp,responseCode\n";
+
+//每次发送给响应流的块大小,避免大时间范围时整体缓存在内存中
+const AUDIT_CSV_CHUNK_SIZE: usize = 64 * 1024;
+
+///
+/// 访问审计导出的过滤条件,时间范围为[from,to)的毫秒时间戳
+#[derive(Debug, Clone, Default)]
+pub struct AuditExportFilter {
+    pub tenant: Option<String>,
+    pub from_millis: Option<i64>,
+    pub to_millis: Option<i64>,
+}
+
+impl AuditExportFilter {
+    pub fn matches(&self, record: &ConfigAccessRecord) -> bool {
+        if let Some(tenant) = &self.tenant {
+            if record.tenant.as_str() != tenant {
+                return false;
+            }
+        }
+        if self.from_millis.is_none() && self.to_millis.is_none() {
+            return true;
+        }
+        let time = match DateTime::parse_from_rfc3339(&record.time) {
+            Ok(v) => v.timestamp_millis(),
+            Err(_) => return false,
+        };
+        self.from_millis.map(|v| time >= v).unwrap_or(true)
+            && self.to_millis.map(|v| time < v).unwrap_or(true)
+    }
+}
+
+///
+/// 支持yyyy-MM-dd(按服务端本地时区当天零点)与RFC3339格式
+pub fn parse_audit_time(value: &str) -> anyhow::Result<i64> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let time = date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|v| Local.from_local_datetime(&v).earliest())
+            .ok_or_else(|| anyhow::anyhow!("invalid date: {}", value))?;
+        return Ok(time.timestamp_millis());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|v| v.timestamp_millis())
+        .map_err(|_| anyhow::anyhow!("invalid time: {}, expect yyyy-MM-dd or RFC3339", value))
+}
+
+///
+/// 以=、+、-、@开头的字段在表格软件中会被当作公式执行,加前缀'作为文本处理
+fn csv_field(value: &str) -> Cow<'_, str> {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        Cow::Owned(format!("'{}", value))
+    } else {
+        Cow::Borrowed(value)
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        value
+    }
+}
+
+pub fn to_csv_row(record: &ConfigAccessRecord) -> String {
+    format!(
+        "{},{},{},{},{},{},{},{}\n",
+        csv_field(&record.time),
+        csv_field(&record.method),
+        csv_field(&record.tenant),
+        csv_field(&record.group),
+        csv_field(&record.data_id),
+        csv_field(record.user.as_ref().map(|v| v.as_str()).unwrap_or_default()),
+        csv_field(record.client_ip.as_deref().unwrap_or_default()),
+        record.response_code
+    )
+}
+
+///
+/// 逐行读取配置访问日志文件,按条件过滤后分块发送csv内容;
+/// 接收端关闭(请求被取消)时停止读取
+pub async fn export_access_log_csv(
+    path: String,
+    filter: AuditExportFilter,
+    tx: mpsc::Sender<std::io::Result<Bytes>>,
+) {
+    let mut chunk = String::with_capacity(AUDIT_CSV_CHUNK_SIZE);
+    chunk.push_str(AUDIT_CSV_HEADER);
+    let file = match tokio::fs::File::open(&path).await {
+        Ok(v) => Some(v),
+        //尚未产生访问记录时只返回表头
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => {
+            log::error!("open config access log file {} error: {}", &path, err);
+            tx.send(Err(err)).await.ok();
+            return;
+        }
+    };
+    if let Some(file) = file {
+        let mut lines = BufReader::new(file).lines();
+        loop {
+            let line = match lines.next_line().await {
+                Ok(Some(v)) => v,
+                Ok(None) => break,
+                Err(err) => {
+                    log::error!("read config access log file {} error: {}", &path, err);
+                    tx.send(Err(err)).await.ok();
+                    return;
+                }
+            };
+            let record: ConfigAccessRecord = match serde_json::from_str(&line) {
+                Ok(v) => v,
+                //忽略写入中断产生的不完整行
+                Err(_) => continue,
+            };
+            if !filter.matches(&record) {
+                continue;
+            }
+            chunk.push_str(&to_csv_row(&record));
+            if chunk.len() >= AUDIT_CSV_CHUNK_SIZE {
+                let data =
+                    std::mem::replace(&mut chunk, String::with_capacity(AUDIT_CSV_CHUNK_SIZE));
+                if tx.send(Ok(Bytes::from(data))).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+    if !chunk.is_empty() {
+        tx.send(Ok(Bytes::from(chunk))).await.ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::core::ConfigKey;
+    use std::sync::Arc;
+
+    fn new_record(tenant: &str, time: &str, method: &str) -> ConfigAccessRecord {
+        let key = ConfigKey::new("app.yaml", "DEFAULT_GROUP", tenant);
+        let mut record = ConfigAccessRecord::new(&key, method.to_owned(), 200, 1);
+        record.time = time.to_owned();
+        record
+    }
+
+    #[test]
+    fn filter_and_csv_row() {
+        let filter = AuditExportFilter {
+            tenant: Some("prod".to_owned()),
+            from_millis: Some(parse_audit_time("2024-01-01T00:00:00+08:00").unwrap()),
+            to_millis: Some(parse_audit_time("2024-02-01T00:00:00+08:00").unwrap()),
+        };
+        assert!(filter.matches(&new_record("prod", "2024-01-15T10:00:00.000+08:00", "GET")));
+        assert!(!filter.matches(&new_record("dev", "2024-01-15T10:00:00.000+08:00", "GET")));
+        assert!(!filter.matches(&new_record("prod", "2024-02-01T00:00:00.000+08:00", "GET")));
+        assert!(!filter.matches(&new_record("prod", "2023-12-31T23:59:59.999+08:00", "GET")));
+        assert!(parse_audit_time("2024-01-01").is_ok());
+        assert!(parse_audit_time("20240101").is_err());
+
+        let mut record = new_record("prod", "2024-01-15T10:00:00.000+08:00", "POST");
+        record.user = Some(Arc::new("admin,ops".to_owned()));
+        record.client_ip = Some("10.0.0.1".to_owned());
+        assert_eq!(
+            to_csv_row(&record),
+            "2024-01-15T10:00:00.000+08:00,POST,prod,DEFAULT_GROUP,app.yaml,\"admin,ops\",10.0.0.1,200\n"
+        );
+
+        let mut record = new_record("prod", "2024-01-15T10:00:00.000+08:00", "GET");
+        record.data_id = Arc::new("=HYPERLINK(\"http://x\")".to_owned());
+        record.user = Some(Arc::new("@admin".to_owned()));
+        assert_eq!(
+            to_csv_row(&record),
+            "2024-01-15T10:00:00.000+08:00,GET,prod,DEFAULT_GROUP,\"'=HYPERLINK(\"\"http://x\"\")\",'@admin,,200\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn export_csv() {
+        let path = std::env::temp_dir().join(format!(
+            "rnacos_access_audit_test_{}.log",
+            uuid::Uuid::new_v4().simple()
+        ));
+        let lines: Vec<String> = [
+            new_record("prod", "2024-01-15T10:00:00.000+08:00", "GET"),
+            new_record("dev", "2024-01-15T10:00:00.000+08:00", "GET"),
+        ]
+        .iter()
+        .map(|v| serde_json::to_string(v).unwrap())
+        .collect();
+        std::fs::write(&path, format!("{}\n{{\"time\"", lines.join("\n"))).unwrap();
+        let filter = AuditExportFilter {
+            tenant: Some("prod".to_owned()),
+            ..Default::default()
+        };
+        let (tx, mut rx) = mpsc::channel(4);
+        export_access_log_csv(path.to_string_lossy().to_string(), filter, tx).await;
+        std::fs::remove_file(&path).ok();
+        let mut content = String::new();
+        while let Some(chunk) = rx.recv().await {
+            content.push_str(std::str::from_utf8(&chunk.unwrap()).unwrap());
+        }
+        assert_eq!(
+            content,
+            format!(
+                "{}2024-01-15T10:00:00.000+08:00,GET,prod,DEFAULT_GROUP,app.yaml,,,200\n",
+                AUDIT_CSV_HEADER
+            )
+        );
+    }
+}
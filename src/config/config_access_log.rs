@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+
+use crate::common::metrics::CONFIG_ACCESS_LOG_DROPPED_COUNTER;
+
+use super::core::ConfigKey;
+
+/// 待写入的访问日志记录上限,超过时丢弃新记录
+const ACCESS_LOG_BUFFER_SIZE: usize = 10000;
+
+///
+/// 配置访问日志记录,每条记录写为一行json
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ConfigAccessRecord {
+    pub time: String,
+    pub method: String,
+    pub tenant: Arc<String>,
+    pub group: Arc<String>,
+    #[serde(rename = "dataId")]
+    pub data_id: Arc<String>,
+    pub client_ip: Option<String>,
+    pub user: Option<Arc<String>>,
+    pub response_code: u16,
+    pub latency_ms: u64,
+}
+
+impl ConfigAccessRecord {
+    pub fn new(key: &ConfigKey, method: String, response_code: u16, latency_ms: u64) -> Self {
+        Self {
+            time: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+            method,
+            tenant: key.tenant.clone(),
+            group: key.group.clone(),
+            data_id: key.data_id.clone(),
+            response_code,
+            latency_ms,
+            ..Default::default()
+        }
+    }
+}
+
+///
+/// 配置访问日志,与应用日志分开写入独立文件;
+/// 通过有界通道交给后台任务异步写入,不增加请求耗时;写入跟不上时丢弃记录并计数
+#[derive(Default)]
+pub struct ConfigAccessLogger {
+    sender: Option<mpsc::Sender<ConfigAccessRecord>>,
+}
+
+impl ConfigAccessLogger {
+    pub fn new(enabled: bool, path: String) -> Self {
+        let sender = if enabled {
+            let (tx, rx) = mpsc::channel(ACCESS_LOG_BUFFER_SIZE);
+            tokio::spawn(Self::run(path, rx));
+            Some(tx)
+        } else {
+            None
+        };
+        Self { sender }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.sender.is_some()
+    }
+
+    pub fn log(&self, record: ConfigAccessRecord) {
+        if let Some(sender) = &self.sender {
+            if sender.try_send(record).is_err() {
+                CONFIG_ACCESS_LOG_DROPPED_COUNTER.inc();
+            }
+        }
+    }
+
+    async fn run(path: String, mut rx: mpsc::Receiver<ConfigAccessRecord>) {
+        let file = match tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+        {
+            Ok(v) => v,
+            Err(err) => {
+                log::error!("open config access log file {} error: {}", &path, err);
+                return;
+            }
+        };
+        let mut writer = BufWriter::new(file);
+        while let Some(record) = rx.recv().await {
+            Self::write_record(&mut writer, &record).await;
+            //通道中暂无记录时再刷新到文件
+            while let Ok(record) = rx.try_recv() {
+                Self::write_record(&mut writer, &record).await;
+            }
+            if let Err(err) = writer.flush().await {
+                log::error!("flush config access log error: {}", err);
+            }
+        }
+    }
+
+    async fn write_record(writer: &mut BufWriter<tokio::fs::File>, record: &ConfigAccessRecord) {
+        let mut line = match serde_json::to_vec(record) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        line.push(b'\n');
+        if let Err(err) = writer.write_all(&line).await {
+            log::error!("write config access log error: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn config_access_log() {
+        let path = std::env::temp_dir().join(format!(
+            "rnacos_access_log_test_{}.log",
+            uuid::Uuid::new_v4().simple()
+        ));
+        let logger = ConfigAccessLogger::new(true, path.to_string_lossy().to_string());
+        let key = ConfigKey::new("app.yaml", "DEFAULT_GROUP", "dev");
+        let mut record = ConfigAccessRecord::new(&key, "GET".to_owned(), 200, 3);
+        record.client_ip = Some("127.0.0.1".to_owned());
+        logger.log(record);
+        logger.log(ConfigAccessRecord::new(&key, "POST".to_owned(), 404, 1));
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|v| serde_json::from_str(v).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["dataId"], "app.yaml");
+        assert_eq!(lines[0]["client_ip"], "127.0.0.1");
+        assert_eq!(lines[1]["response_code"], 404);
+        assert!(!ConfigAccessLogger::default().is_enabled());
+    }
+
+    #[test]
+    fn drop_when_buffer_full() {
+        let (tx, _rx) = mpsc::channel(1);
+        let logger = ConfigAccessLogger { sender: Some(tx) };
+        let key = ConfigKey::new("app.yaml", "DEFAULT_GROUP", "dev");
+        let dropped = CONFIG_ACCESS_LOG_DROPPED_COUNTER.get();
+        logger.log(ConfigAccessRecord::new(&key, "GET".to_owned(), 200, 1));
+        logger.log(ConfigAccessRecord::new(&key, "GET".to_owned(), 200, 1));
+        assert_eq!(CONFIG_ACCESS_LOG_DROPPED_COUNTER.get(), dropped + 1);
+    }
+}
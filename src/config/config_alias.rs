@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::core::ConfigKey;
+
+/// 系统命名空间下保存配置别名的配置,内容为`[{"from":"tenant##group##dataId","to":"..."}]`
+pub const CONFIG_ALIAS_KEY: &str = "config_aliases";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigAlias {
+    pub from: ConfigKey,
+    pub to: ConfigKey,
+}
+
+pub fn parse_aliases(content: &str) -> anyhow::Result<Vec<ConfigAlias>> {
+    if content.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    Ok(serde_json::from_str(content)?)
+}
+
+///
+/// 添加或替换别名;别名只有一级,目标不能是别名,别名也不能是其它别名的目标
+pub fn add_alias(list: &mut Vec<ConfigAlias>, alias: ConfigAlias) -> anyhow::Result<()> {
+    if alias.from == alias.to {
+        return Err(anyhow::anyhow!("alias can not point to itself"));
+    }
+    if list.iter().any(|v| v.from == alias.to) {
+        return Err(anyhow::anyhow!("alias target {} is an alias", &alias.to));
+    }
+    if list.iter().any(|v| v.to == alias.from) {
+        return Err(anyhow::anyhow!(
+            "{} is the target of other alias",
+            &alias.from
+        ));
+    }
+    list.retain(|v| v.from != alias.from);
+    list.push(alias);
+    Ok(())
+}
+
+///
+/// 别名设置,设置内容的md5变化时重新解析
+#[derive(Default)]
+pub struct ConfigAliasSettings {
+    md5: Option<Arc<String>>,
+    aliases: HashMap<ConfigKey, ConfigKey>,
+    //目标配置到别名的反查
+    targets: HashMap<ConfigKey, Vec<ConfigKey>>,
+}
+
+impl ConfigAliasSettings {
+    ///
+    /// 返回设置是否有变化
+    pub fn update(&mut self, md5: &Arc<String>, content: &str) -> bool {
+        if self.md5.as_ref() == Some(md5) {
+            return false;
+        }
+        self.md5 = Some(md5.clone());
+        let list = match parse_aliases(content) {
+            Ok(v) => v,
+            Err(err) => {
+                log::warn!("invalid config alias settings: {}", err);
+                vec![]
+            }
+        };
+        self.aliases.clear();
+        self.targets.clear();
+        for item in list {
+            self.targets
+                .entry(item.to.clone())
+                .or_default()
+                .push(item.from.clone());
+            self.aliases.insert(item.from, item.to);
+        }
+        true
+    }
+
+    pub fn clear(&mut self) -> bool {
+        let changed = self.md5.is_some();
+        self.md5 = None;
+        self.aliases.clear();
+        self.targets.clear();
+        changed
+    }
+
+    pub fn resolve(&self, key: &ConfigKey) -> Option<&ConfigKey> {
+        self.aliases.get(key)
+    }
+
+    pub fn aliases_of(&self, key: &ConfigKey) -> &[ConfigKey] {
+        self.targets.get(key).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn alias_map(&self) -> HashMap<ConfigKey, ConfigKey> {
+        self.aliases.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_config_alias() {
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let b = ConfigKey::new("b", "DEFAULT_GROUP", "");
+        let c = ConfigKey::new("c", "DEFAULT_GROUP", "dev");
+        let mut list = vec![];
+        add_alias(
+            &mut list,
+            ConfigAlias {
+                from: a.clone(),
+                to: b.clone(),
+            },
+        )
+        .unwrap();
+        assert!(add_alias(
+            &mut list,
+            ConfigAlias {
+                from: c.clone(),
+                to: a.clone(),
+            },
+        )
+        .is_err());
+        assert!(add_alias(
+            &mut list,
+            ConfigAlias {
+                from: b.clone(),
+                to: c.clone(),
+            },
+        )
+        .is_err());
+        add_alias(
+            &mut list,
+            ConfigAlias {
+                from: a.clone(),
+                to: c.clone(),
+            },
+        )
+        .unwrap();
+        assert_eq!(list.len(), 1);
+
+        let content = serde_json::to_string(&list).unwrap();
+        let mut settings = ConfigAliasSettings::default();
+        assert!(settings.update(&Arc::new("1".to_owned()), &content));
+        assert!(!settings.update(&Arc::new("1".to_owned()), &content));
+        assert_eq!(settings.resolve(&a), Some(&c));
+        assert_eq!(settings.aliases_of(&c), std::slice::from_ref(&a));
+        assert!(settings.aliases_of(&b).is_empty());
+    }
+}
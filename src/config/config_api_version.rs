@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+use super::core::ConfigKey;
+
+pub const MEDIA_TYPE_V1: &str = "application/vnd.rnacos.v1+json";
+pub const MEDIA_TYPE_V2: &str = "application/vnd.rnacos.v2+json";
+
+///
+/// 配置接口响应格式版本,通过Accept头协商
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    ///
+    /// Accept中未指定版本时返回None,按原有格式响应
+    pub fn parse_accept(accept: &str) -> Option<Self> {
+        for item in accept.split(',') {
+            match item.split(';').next().unwrap_or_default().trim() {
+                MEDIA_TYPE_V1 => return Some(Self::V1),
+                MEDIA_TYPE_V2 => return Some(Self::V2),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            Self::V1 => MEDIA_TYPE_V1,
+            Self::V2 => MEDIA_TYPE_V2,
+        }
+    }
+
+    ///
+    /// v1为原有的配置原文,v2为json
+    pub fn to_body(&self, entry: &ConfigEntry) -> anyhow::Result<Vec<u8>> {
+        let body = match self {
+            Self::V1 => V1ConfigResponse(entry).to_body(),
+            Self::V2 => serde_json::to_vec(&V2ConfigResponse(entry))?,
+        };
+        Ok(body)
+    }
+}
+
+pub struct ConfigEntry {
+    pub key: ConfigKey,
+    pub content: Arc<String>,
+    pub md5: Arc<String>,
+    pub config_type: Option<Arc<String>>,
+}
+
+///
+/// v1格式,与未指定版本时一样返回配置原文,兼容按原文解析的客户端
+pub struct V1ConfigResponse<'a>(pub &'a ConfigEntry);
+
+impl V1ConfigResponse<'_> {
+    pub fn to_body(&self) -> Vec<u8> {
+        self.0.content.as_bytes().to_vec()
+    }
+}
+
+///
+/// v2格式,返回配置内容及元信息
+pub struct V2ConfigResponse<'a>(pub &'a ConfigEntry);
+
+impl Serialize for V2ConfigResponse<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entry = self.0;
+        let mut s = serializer.serialize_struct("V2ConfigResponse", 6)?;
+        s.serialize_field("tenant", entry.key.tenant.as_str())?;
+        s.serialize_field("group", entry.key.group.as_str())?;
+        s.serialize_field("dataId", entry.key.data_id.as_str())?;
+        s.serialize_field("content", entry.content.as_str())?;
+        s.serialize_field("md5", entry.md5.as_str())?;
+        s.serialize_field("type", &entry.config_type.as_deref())?;
+        s.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_version_response() {
+        assert_eq!(
+            ApiVersion::parse_accept("application/vnd.rnacos.v2+json, */*;q=0.8"),
+            Some(ApiVersion::V2)
+        );
+        assert_eq!(ApiVersion::parse_accept("application/json"), None);
+
+        let entry = ConfigEntry {
+            key: ConfigKey::new("app.yaml", "DEFAULT_GROUP", ""),
+            content: Arc::new("a: 1".to_owned()),
+            md5: Arc::new("md5".to_owned()),
+            config_type: Some(Arc::new("yaml".to_owned())),
+        };
+        assert_eq!(ApiVersion::V1.to_body(&entry).unwrap(), b"a: 1");
+        let v2: serde_json::Value =
+            serde_json::from_slice(&ApiVersion::V2.to_body(&entry).unwrap()).unwrap();
+        assert_eq!(v2["dataId"], "app.yaml");
+        assert_eq!(v2["content"], "a: 1");
+        assert_eq!(v2["type"], "yaml");
+    }
+}
@@ -0,0 +1,353 @@
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_raft_ext::raft::ClientWriteRequest;
+use lru::LruCache;
+use object_store::aws::AmazonS3Builder;
+use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::path::Path;
+use object_store::{ObjectStore, ObjectStoreScheme};
+use url::Url;
+
+use super::config_read_view::ConfigReadValue;
+use super::config_stats::CONFIG_TOUCH_REPORT_INTERVAL;
+use super::core::{ConfigCmd, ConfigKey, ConfigResult, ConfigValue};
+use super::model::ConfigValueDO;
+use super::DEFAULT_TENANT;
+use crate::common::appdata::AppShareData;
+use crate::now_millis_i64;
+use crate::raft::store::ClientRequest;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+/// 每轮最多归档的配置数,避免集中写入对象存储与raft
+const ARCHIVE_BATCH_SIZE: usize = 100;
+
+///
+/// 冷配置归档设置
+#[derive(Debug, Clone)]
+pub struct ConfigArchiveConfig {
+    //对象存储地址,如s3://bucket/prefix、gs://bucket/prefix
+    pub url: String,
+    pub inactive_days: u64,
+    pub interval_secs: u64,
+    //读取后在内存中缓存的归档内容数,为0时不缓存
+    pub recache_capacity: usize,
+}
+
+impl ConfigArchiveConfig {
+    pub fn from_env() -> Option<Self> {
+        let url = std::env::var("RNACOS_CONFIG_ARCHIVE_URL").unwrap_or_default();
+        if url.is_empty() {
+            return None;
+        }
+        let inactive_days = std::env::var("RNACOS_CONFIG_ARCHIVE_INACTIVE_DAYS")
+            .unwrap_or("90".to_owned())
+            .parse()
+            .unwrap_or(90);
+        let interval_secs = std::env::var("RNACOS_CONFIG_ARCHIVE_INTERVAL_SECS")
+            .unwrap_or("3600".to_owned())
+            .parse()
+            .unwrap_or(3600);
+        let recache_capacity = std::env::var("RNACOS_CONFIG_ARCHIVE_RECACHE_CAPACITY")
+            .unwrap_or("1000".to_owned())
+            .parse()
+            .unwrap_or(1000);
+        Some(Self {
+            url,
+            inactive_days,
+            interval_secs,
+            recache_capacity,
+        })
+    }
+}
+
+///
+/// 归档内容的对象存储;对象内容为归档时的ConfigValueDO(含历史版本),
+/// 对象路径带上md5,同一配置不同内容的归档互不覆盖
+pub struct ConfigArchiveStore {
+    store: Arc<dyn ObjectStore>,
+    //归档地址中对象路径之前的部分,如s3://bucket
+    base_uri: String,
+    prefix: Path,
+    recache: Option<Mutex<LruCache<Arc<String>, Arc<String>>>>,
+}
+
+impl ConfigArchiveStore {
+    pub fn new(config: &ConfigArchiveConfig) -> anyhow::Result<Self> {
+        let url = Url::parse(&config.url)?;
+        let (scheme, prefix) = ObjectStoreScheme::parse(&url)?;
+        let store: Arc<dyn ObjectStore> = match scheme {
+            ObjectStoreScheme::AmazonS3 => Arc::new(
+                AmazonS3Builder::from_env()
+                    .with_url(config.url.as_str())
+                    .build()?,
+            ),
+            ObjectStoreScheme::GoogleCloudStorage => Arc::new(
+                GoogleCloudStorageBuilder::from_env()
+                    .with_url(config.url.as_str())
+                    .build()?,
+            ),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "unsupported config archive url: {}",
+                    &config.url
+                ))
+            }
+        };
+        let base_uri = format!("{}://{}", url.scheme(), url.host_str().unwrap_or_default());
+        Ok(Self::with_store(
+            store,
+            base_uri,
+            prefix,
+            config.recache_capacity,
+        ))
+    }
+
+    pub fn with_store(
+        store: Arc<dyn ObjectStore>,
+        base_uri: String,
+        prefix: Path,
+        recache_capacity: usize,
+    ) -> Self {
+        Self {
+            store,
+            base_uri,
+            prefix,
+            recache: NonZeroUsize::new(recache_capacity).map(|v| Mutex::new(LruCache::new(v))),
+        }
+    }
+
+    fn location(&self, key: &ConfigKey, md5: &str) -> Path {
+        let tenant = if key.tenant.is_empty() {
+            DEFAULT_TENANT
+        } else {
+            key.tenant.as_str()
+        };
+        self.prefix
+            .child(tenant)
+            .child(key.group.as_str())
+            .child(key.data_id.as_str())
+            .child(md5)
+    }
+
+    fn location_from_uri(&self, archive_uri: &str) -> anyhow::Result<Path> {
+        let path = archive_uri
+            .strip_prefix(self.base_uri.as_str())
+            .and_then(|v| v.strip_prefix('/'))
+            .ok_or_else(|| anyhow::anyhow!("unknown config archive uri: {}", archive_uri))?;
+        Ok(Path::parse(path)?)
+    }
+
+    ///
+    /// 上传配置内容与历史版本,返回归档地址
+    pub async fn archive(
+        &self,
+        key: &ConfigKey,
+        value: ConfigValue,
+    ) -> anyhow::Result<Arc<String>> {
+        let location = self.location(key, &value.md5);
+        let value_do: ConfigValueDO = value.into();
+        self.store
+            .put(&location, value_do.to_bytes()?.into())
+            .await?;
+        Ok(Arc::new(format!("{}/{}", &self.base_uri, location)))
+    }
+
+    pub async fn load(&self, archive_uri: &Arc<String>) -> anyhow::Result<ConfigValue> {
+        let location = self.location_from_uri(archive_uri)?;
+        let data = self.store.get(&location).await?.bytes().await?;
+        Ok(ConfigValueDO::from_bytes(&data)?.into())
+    }
+
+    ///
+    /// 读取归档的配置内容,开启缓存时优先读缓存
+    pub async fn load_content(&self, archive_uri: &Arc<String>) -> anyhow::Result<Arc<String>> {
+        if let Some(recache) = &self.recache {
+            if let Some(v) = recache
+                .lock()
+                .ok()
+                .and_then(|mut c| c.get(archive_uri).cloned())
+            {
+                return Ok(v);
+            }
+        }
+        let content = self.load(archive_uri).await?.content;
+        if let Some(recache) = &self.recache {
+            if let Ok(mut cache) = recache.lock() {
+                cache.put(archive_uri.clone(), content.clone());
+            }
+        }
+        Ok(content)
+    }
+
+    ///
+    /// 已归档的配置读取归档内容,未归档的原样返回
+    pub async fn load_read_value(
+        &self,
+        value: Arc<ConfigReadValue>,
+    ) -> anyhow::Result<Arc<ConfigReadValue>> {
+        match &value.archive_uri {
+            Some(archive_uri) if value.content.is_empty() => {
+                let mut loaded = ConfigReadValue::clone(&value);
+                loaded.content = self.load_content(archive_uri).await?;
+                Ok(Arc::new(loaded))
+            }
+            _ => Ok(value),
+        }
+    }
+}
+
+///
+/// 定时把集群内长时间未读写的配置归档到对象存储,只在主节点执行;
+/// 上传成功后经raft写入归档地址,各节点释放内存中的内容与历史版本
+pub struct ArchivalTask {
+    app: Arc<AppShareData>,
+    store: Arc<ConfigArchiveStore>,
+    inactive_ms: i64,
+    interval: Duration,
+    started: Instant,
+}
+
+impl ArchivalTask {
+    pub fn new(app: Arc<AppShareData>, store: Arc<ConfigArchiveStore>) -> Option<Self> {
+        let config = app.sys_config.config_archive.as_ref()?;
+        Some(Self {
+            inactive_ms: (config.inactive_days as i64).saturating_mul(DAY_MS),
+            interval: Duration::from_secs(config.interval_secs.max(1)),
+            app,
+            store,
+            started: Instant::now(),
+        })
+    }
+
+    pub async fn run(self) {
+        loop {
+            tokio::time::sleep(self.interval).await;
+            //启动后先等各节点上报过读取时间,避免把其它节点上有读取的配置当作不活跃
+            if self.started.elapsed() < CONFIG_TOUCH_REPORT_INTERVAL * 2 {
+                continue;
+            }
+            if self.app.raft.current_leader().await != Some(self.app.sys_config.raft_node_id) {
+                continue;
+            }
+            match self.archive_once().await {
+                Ok(count) if count > 0 => log::info!("archived {} inactive configs", count),
+                Ok(_) => {}
+                Err(err) => log::warn!("config archive error: {}", err),
+            }
+        }
+    }
+
+    async fn archive_once(&self) -> anyhow::Result<usize> {
+        let before_ms = now_millis_i64().saturating_sub(self.inactive_ms);
+        let candidates = match self
+            .app
+            .config_addr
+            .send(ConfigCmd::QueryArchiveCandidates(
+                before_ms,
+                ARCHIVE_BATCH_SIZE,
+            ))
+            .await??
+        {
+            ConfigResult::ArchiveCandidates(list) => list,
+            _ => return Ok(0),
+        };
+        let mut count = 0;
+        for (key, value) in candidates {
+            let md5 = value.md5.clone();
+            let archive_uri = self.store.archive(&key, value).await?;
+            let req = ClientRequest::ConfigArchive {
+                key: key.build_key(),
+                md5,
+                archive_uri,
+            };
+            self.app
+                .raft
+                .client_write(ClientWriteRequest::new(req))
+                .await?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+
+    #[tokio::test]
+    async fn archive_and_load() {
+        let store = ConfigArchiveStore::with_store(
+            Arc::new(InMemory::new()),
+            "s3://bucket".to_owned(),
+            Path::from("rnacos"),
+            10,
+        );
+        let key = ConfigKey::new("app.yaml", "DEFAULT_GROUP", "");
+        let mut value = ConfigValue::init(Arc::new("a: 1".to_owned()), 1, 100, None, None);
+        value.update_value(Arc::new("a: 2".to_owned()), 2, 200, None, None, 10);
+        let archive_uri = store.archive(&key, value.clone()).await.unwrap();
+        assert_eq!(
+            archive_uri.as_str(),
+            format!(
+                "s3://bucket/rnacos/public/DEFAULT_GROUP/app.yaml/{}",
+                &value.md5
+            )
+        );
+        let archived = store.load(&archive_uri).await.unwrap();
+        assert_eq!(archived.content.as_str(), "a: 2");
+        assert_eq!(archived.histories.len(), 2);
+        assert_eq!(
+            store.load_content(&archive_uri).await.unwrap().as_str(),
+            "a: 2"
+        );
+        assert!(store
+            .load_content(&Arc::new("gs://other/a".to_owned()))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn archived_value_do() {
+        let mut value = ConfigValue::init(Arc::new("v1".to_owned()), 1, 100, None, None);
+        value.last_read_ms = 200;
+        let md5 = value.md5.clone();
+        value.archive(Arc::new("s3://bucket/a".to_owned()));
+        let value_do: ConfigValueDO = value.into();
+        assert!(value_do.content.is_none());
+        let value: ConfigValue = ConfigValueDO::from_bytes(&value_do.to_bytes().unwrap())
+            .unwrap()
+            .into();
+        assert_eq!(value.md5, md5);
+        assert_eq!(
+            value.archive_uri.as_ref().unwrap().as_str(),
+            "s3://bucket/a"
+        );
+        //归档后没有历史版本,读写时间仍然保留
+        assert_eq!(value.last_read_ms, 200);
+        assert_eq!(value.last_modified, 100);
+        assert!(value.is_inactive(201));
+        assert!(!value.is_inactive(200));
+    }
+
+    #[tokio::test]
+    async fn load_archived_read_value() {
+        let store = ConfigArchiveStore::with_store(
+            Arc::new(InMemory::new()),
+            "s3://bucket".to_owned(),
+            Path::from("rnacos"),
+            0,
+        );
+        let key = ConfigKey::new("app.yaml", "DEFAULT_GROUP", "dev");
+        let mut value = ConfigValue::new(Arc::new("a: 1".to_owned()));
+        let archive_uri = store.archive(&key, value.clone()).await.unwrap();
+        value.archive(archive_uri);
+        let read_value = Arc::new(ConfigReadValue::from(&value));
+        assert!(read_value.content.is_empty());
+        let loaded = store.load_read_value(read_value).await.unwrap();
+        assert_eq!(loaded.content.as_str(), "a: 1");
+        assert_eq!(loaded.md5, value.md5);
+    }
+}
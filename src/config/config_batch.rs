@@ -0,0 +1,184 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::raft::cluster::model::SetConfigReq;
+use crate::raft::cluster::route::ConfigRoute;
+
+use super::model::ConfigSetResult;
+
+///
+/// 单批最多合并的写入数
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+type BatchItem = (SetConfigReq, oneshot::Sender<anyhow::Result<()>>);
+
+///
+/// 配置写入提案方式,单个写入与批量写入各对应一条raft日志
+#[async_trait]
+pub trait ConfigProposer: Send + Sync + 'static {
+    async fn propose(&self, req: SetConfigReq) -> anyhow::Result<()>;
+
+    async fn propose_batch(&self, reqs: Vec<SetConfigReq>) -> anyhow::Result<Vec<ConfigSetResult>>;
+}
+
+#[async_trait]
+impl ConfigProposer for ConfigRoute {
+    async fn propose(&self, req: SetConfigReq) -> anyhow::Result<()> {
+        self.set_config(req).await
+    }
+
+    async fn propose_batch(&self, reqs: Vec<SetConfigReq>) -> anyhow::Result<Vec<ConfigSetResult>> {
+        self.batch_set_config(reqs, None).await
+    }
+}
+
+///
+/// 合并batch_window内到达的配置写入为一个raft提案,每个调用方单独等待自己的写入结果;
+/// batch_window为0时不合并,直接提案
+pub struct BatchProposer<P: ConfigProposer = ConfigRoute> {
+    proposer: Arc<P>,
+    sender: Option<mpsc::UnboundedSender<BatchItem>>,
+}
+
+impl<P: ConfigProposer> BatchProposer<P> {
+    pub fn new(proposer: Arc<P>, batch_window_ms: u64) -> Self {
+        let sender = if batch_window_ms > 0 {
+            let (tx, rx) = mpsc::unbounded_channel();
+            tokio::spawn(Self::run(
+                proposer.clone(),
+                rx,
+                Duration::from_millis(batch_window_ms),
+            ));
+            Some(tx)
+        } else {
+            None
+        };
+        Self { proposer, sender }
+    }
+
+    pub async fn set_config(&self, req: SetConfigReq) -> anyhow::Result<()> {
+        let req = match &self.sender {
+            Some(sender) => {
+                let (tx, rx) = oneshot::channel();
+                match sender.send((req, tx)) {
+                    Ok(_) => return rx.await?,
+                    //合并任务已退出时直接提案
+                    Err(err) => err.0 .0,
+                }
+            }
+            None => req,
+        };
+        self.proposer.propose(req).await
+    }
+
+    async fn run(proposer: Arc<P>, mut rx: mpsc::UnboundedReceiver<BatchItem>, window: Duration) {
+        while let Some(first) = rx.recv().await {
+            let mut batch = vec![first];
+            let deadline = tokio::time::Instant::now() + window;
+            while batch.len() < MAX_BATCH_SIZE {
+                match tokio::time::timeout_at(deadline, rx.recv()).await {
+                    Ok(Some(item)) => batch.push(item),
+                    _ => break,
+                }
+            }
+            //提案过程中继续收集下一批
+            tokio::spawn(Self::propose_batch(proposer.clone(), batch));
+        }
+    }
+
+    async fn propose_batch(proposer: Arc<P>, mut batch: Vec<BatchItem>) {
+        if batch.len() == 1 {
+            let (req, tx) = batch.pop().unwrap();
+            tx.send(proposer.propose(req).await).ok();
+            return;
+        }
+        let (reqs, senders): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+        match proposer.propose_batch(reqs).await {
+            Ok(results) => {
+                let mut results = results.into_iter();
+                for tx in senders {
+                    let result = results
+                        .next()
+                        .map(|v| v.into_result())
+                        .unwrap_or_else(|| Err(anyhow::anyhow!("config batch result is missing")));
+                    tx.send(result).ok();
+                }
+            }
+            Err(err) => {
+                let msg = err.to_string();
+                for tx in senders {
+                    tx.send(Err(anyhow::anyhow!("{}", msg))).ok();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::core::ConfigKey;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountProposer {
+        proposals: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ConfigProposer for CountProposer {
+        async fn propose(&self, _req: SetConfigReq) -> anyhow::Result<()> {
+            self.proposals.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn propose_batch(
+            &self,
+            reqs: Vec<SetConfigReq>,
+        ) -> anyhow::Result<Vec<ConfigSetResult>> {
+            self.proposals.fetch_add(1, Ordering::SeqCst);
+            Ok(reqs
+                .iter()
+                .map(|v| {
+                    if v.expected_md5.is_some() {
+                        ConfigSetResult::Md5Mismatch { current_md5: None }
+                    } else {
+                        ConfigSetResult::Success
+                    }
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn batch_propose() {
+        let proposer = Arc::new(CountProposer::default());
+        let batch_proposer = Arc::new(BatchProposer::new(proposer.clone(), 50));
+        let handles: Vec<_> = (0..10)
+            .map(|i| {
+                let batch_proposer = batch_proposer.clone();
+                tokio::spawn(async move {
+                    let mut req = SetConfigReq::new(
+                        ConfigKey::new(&format!("data_{}", i), "DEFAULT_GROUP", ""),
+                        Arc::new("v".to_owned()),
+                    );
+                    if i == 0 {
+                        req.expected_md5 = Some(Arc::new("md5".to_owned()));
+                    }
+                    batch_proposer.set_config(req).await
+                })
+            })
+            .collect();
+        let mut fail_count = 0;
+        for h in handles {
+            if h.await.unwrap().is_err() {
+                fail_count += 1;
+            }
+        }
+        assert_eq!(fail_count, 1);
+        assert_eq!(proposer.proposals.load(Ordering::SeqCst), 1);
+    }
+}
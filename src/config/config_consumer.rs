@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use super::core::ConfigKey;
+
+/// 最多记录的客户端数,超出时淘汰最早心跳的记录
+const MAX_CONSUMER_RECORDS: usize = 10000;
+
+#[derive(Debug, Clone)]
+pub struct ConsumerRecord {
+    pub client_id: Arc<String>,
+    pub client_ip: Option<Arc<String>>,
+    pub config_keys: Vec<ConfigKey>,
+    //客户端上报的正在使用的配置md5
+    pub config_md5s: HashMap<ConfigKey, Arc<String>>,
+    pub last_heartbeat: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsumerInfo {
+    pub client_id: Arc<String>,
+    pub client_ip: Option<Arc<String>>,
+    pub last_heartbeat: i64,
+}
+
+///
+/// 记录通过心跳上报的配置使用方;超过2个心跳间隔未上报的记录视为失效
+pub struct ConsumerRegistry {
+    expire_ms: i64,
+    max_records: usize,
+    records: Mutex<HashMap<Arc<String>, ConsumerRecord>>,
+}
+
+impl ConsumerRegistry {
+    pub fn new(heartbeat_interval_ms: u64) -> Self {
+        Self {
+            expire_ms: heartbeat_interval_ms as i64 * 2,
+            max_records: MAX_CONSUMER_RECORDS,
+            records: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn heartbeat(&self, record: ConsumerRecord) {
+        if let Ok(mut records) = self.records.lock() {
+            let now = record.last_heartbeat;
+            records.retain(|_, v| now - v.last_heartbeat <= self.expire_ms);
+            if records.len() >= self.max_records && !records.contains_key(&record.client_id) {
+                if let Some(oldest) = records
+                    .values()
+                    .min_by_key(|v| v.last_heartbeat)
+                    .map(|v| v.client_id.clone())
+                {
+                    records.remove(&oldest);
+                }
+            }
+            records.insert(record.client_id.clone(), record);
+        }
+    }
+
+    ///
+    /// 查询使用配置的有效客户端,按客户端id排序
+    pub fn query(&self, key: &ConfigKey, now: i64) -> Vec<ConsumerInfo> {
+        let mut list: Vec<ConsumerInfo> = match self.records.lock() {
+            Ok(records) => records
+                .values()
+                .filter(|v| now - v.last_heartbeat <= self.expire_ms && v.config_keys.contains(key))
+                .map(|v| ConsumerInfo {
+                    client_id: v.client_id.clone(),
+                    client_ip: v.client_ip.clone(),
+                    last_heartbeat: v.last_heartbeat,
+                })
+                .collect(),
+            Err(_) => vec![],
+        };
+        list.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+        list
+    }
+
+    ///
+    /// 上报的md5与指定md5不一致的有效客户端,未上报md5的客户端无法判断版本,不计入
+    pub fn stale_clients(&self, key: &ConfigKey, md5: &Arc<String>, now: i64) -> Vec<Arc<String>> {
+        let mut list: Vec<Arc<String>> = match self.records.lock() {
+            Ok(records) => records
+                .values()
+                .filter(|v| now - v.last_heartbeat <= self.expire_ms)
+                .filter(|v| matches!(v.config_md5s.get(key), Some(v) if v != md5))
+                .map(|v| v.client_id.clone())
+                .collect(),
+            Err(_) => vec![],
+        };
+        list.sort();
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(client_id: &str, keys: Vec<ConfigKey>, now: i64) -> ConsumerRecord {
+        ConsumerRecord {
+            client_id: Arc::new(client_id.to_owned()),
+            client_ip: None,
+            config_keys: keys,
+            config_md5s: HashMap::new(),
+            last_heartbeat: now,
+        }
+    }
+
+    #[test]
+    fn consumer_registry() {
+        let registry = ConsumerRegistry::new(1000);
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let b = ConfigKey::new("b", "DEFAULT_GROUP", "");
+        registry.heartbeat(record("s1", vec![a.clone(), b.clone()], 0));
+        registry.heartbeat(record("s2", vec![a.clone()], 1000));
+        let list = registry.query(&a, 1500);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].client_id.as_str(), "s1");
+        assert_eq!(registry.query(&b, 1500).len(), 1);
+
+        //超过2个心跳间隔后失效
+        assert_eq!(registry.query(&a, 2500).len(), 1);
+        registry.heartbeat(record("s1", vec![b.clone()], 2600));
+        assert_eq!(registry.query(&a, 2600).len(), 1);
+        assert_eq!(registry.query(&b, 2600).len(), 1);
+    }
+
+    #[test]
+    fn consumer_registry_limit() {
+        let registry = ConsumerRegistry {
+            max_records: 2,
+            ..ConsumerRegistry::new(1000)
+        };
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        registry.heartbeat(record("s1", vec![a.clone()], 0));
+        registry.heartbeat(record("s2", vec![a.clone()], 10));
+        //已有客户端续约不淘汰其它记录
+        registry.heartbeat(record("s1", vec![a.clone()], 20));
+        assert_eq!(registry.query(&a, 30).len(), 2);
+        registry.heartbeat(record("s3", vec![a.clone()], 30));
+        let list = registry.query(&a, 30);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].client_id.as_str(), "s1");
+        assert_eq!(list[1].client_id.as_str(), "s3");
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+///
+/// 按md5共享配置内容,内容相同的配置只保存一份
+#[derive(Default)]
+pub struct ConfigContentStore {
+    //md5 -> (共享内容,引用的配置数)
+    contents: HashMap<Arc<String>, (Arc<String>, usize)>,
+    saved_bytes: i64,
+}
+
+impl ConfigContentStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// 返回共享的内容,已存在相同md5的内容时丢弃传入的内容
+    pub fn intern(&mut self, md5: &Arc<String>, content: Arc<String>) -> Arc<String> {
+        match self.contents.get_mut(md5) {
+            Some((shared, count)) => {
+                *count += 1;
+                self.saved_bytes += shared.len() as i64;
+                shared.clone()
+            }
+            None => {
+                self.contents.insert(md5.clone(), (content.clone(), 1));
+                content
+            }
+        }
+    }
+
+    pub fn release(&mut self, md5: &Arc<String>) {
+        if let Some((shared, count)) = self.contents.get_mut(md5) {
+            if *count > 1 {
+                *count -= 1;
+                self.saved_bytes -= shared.len() as i64;
+            } else {
+                self.contents.remove(md5);
+            }
+        }
+    }
+
+    ///
+    /// 因共享内容节省的字节数
+    pub fn saved_bytes(&self) -> i64 {
+        self.saved_bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.contents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::get_md5;
+
+    #[test]
+    fn share_content() {
+        let mut store = ConfigContentStore::new();
+        let content = "<configuration/>".to_owned();
+        let md5 = Arc::new(get_md5(&content));
+        let a = store.intern(&md5, Arc::new(content.clone()));
+        let b = store.intern(&md5, Arc::new(content.clone()));
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.saved_bytes(), content.len() as i64);
+
+        let other = Arc::new("a=1".to_owned());
+        let other_md5 = Arc::new(get_md5(&other));
+        store.intern(&other_md5, other);
+        assert_eq!(store.len(), 2);
+
+        store.release(&md5);
+        assert_eq!(store.saved_bytes(), 0);
+        store.release(&md5);
+        store.release(&other_md5);
+        assert!(store.is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+/// 按最长公共子序列计算差异时允许的最大行数乘积,超过时中间部分按整体替换处理
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffTag {
+    Equal,
+    Delete,
+    Insert,
+}
+
+///
+/// 按行计算两个文本的差异,先去除相同的首尾行再计算中间部分;
+/// 每行保留行尾的换行符,应用补丁后能还原出原始内容(含\r\n与末尾换行)
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<(DiffTag, &'a str)> {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let prefix = old_lines
+        .iter()
+        .zip(new_lines.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old_lines[prefix..old_lines.len() - suffix];
+    let new_mid = &new_lines[prefix..new_lines.len() - suffix];
+
+    let mut ops: Vec<(DiffTag, &str)> = old_lines[..prefix]
+        .iter()
+        .map(|v| (DiffTag::Equal, *v))
+        .collect();
+    if old_mid.len().saturating_mul(new_mid.len()) <= MAX_LCS_CELLS {
+        lcs_diff(old_mid, new_mid, &mut ops);
+    } else {
+        ops.extend(old_mid.iter().map(|v| (DiffTag::Delete, *v)));
+        ops.extend(new_mid.iter().map(|v| (DiffTag::Insert, *v)));
+    }
+    ops.extend(
+        old_lines[old_lines.len() - suffix..]
+            .iter()
+            .map(|v| (DiffTag::Equal, *v)),
+    );
+    ops
+}
+
+fn lcs_diff<'a>(old: &[&'a str], new: &[&'a str], ops: &mut Vec<(DiffTag, &'a str)>) {
+    let (n, m) = (old.len(), new.len());
+    //lens[i][j]为old[i..]与new[j..]的最长公共子序列长度
+    let mut lens = vec![0u32; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lens[i * (m + 1) + j] = if old[i] == new[j] {
+                lens[(i + 1) * (m + 1) + j + 1] + 1
+            } else {
+                lens[(i + 1) * (m + 1) + j].max(lens[i * (m + 1) + j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            ops.push((DiffTag::Equal, old[i]));
+            i += 1;
+            j += 1;
+        } else if lens[(i + 1) * (m + 1) + j] >= lens[i * (m + 1) + j + 1] {
+            ops.push((DiffTag::Delete, old[i]));
+            i += 1;
+        } else {
+            ops.push((DiffTag::Insert, new[j]));
+            j += 1;
+        }
+    }
+    ops.extend(old[i..].iter().map(|v| (DiffTag::Delete, *v)));
+    ops.extend(new[j..].iter().map(|v| (DiffTag::Insert, *v)));
+}
+
+fn escape_html(s: &str, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+///
+/// 渲染为带行内颜色的统一差异格式html,新增行为绿色,删除行为红色
+pub fn render_diff_html(ops: &[(DiffTag, &str)]) -> String {
+    let mut html = String::new();
+    for (tag, line) in ops {
+        let (class, style, prefix) = match tag {
+            DiffTag::Equal => ("equal", "", ' '),
+            DiffTag::Delete => ("del", " style='color:red'", '-'),
+            DiffTag::Insert => ("add", " style='color:green'", '+'),
+        };
+        html.push_str("<span class='");
+        html.push_str(class);
+        html.push('\'');
+        html.push_str(style);
+        html.push('>');
+        html.push(prefix);
+        escape_html(line.trim_end_matches(&['\r', '\n'][..]), &mut html);
+        html.push_str("</span>\n");
+    }
+    html
+}
+
+///
+/// 渲染为统一差异格式的补丁,每个变更块前后保留context行相同内容;
+/// 没有换行符结尾的行与diff命令一致,追加"\\ No newline at end of file"标记
+pub fn render_unified_diff(ops: &[(DiffTag, &str)], context: usize) -> String {
+    let changes: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (tag, _))| *tag != DiffTag::Equal)
+        .map(|(i, _)| i)
+        .collect();
+    let mut out = String::new();
+    if changes.is_empty() {
+        return out;
+    }
+    //各位置之前的旧、新文本行数
+    let mut old_pos = Vec::with_capacity(ops.len() + 1);
+    let mut new_pos = Vec::with_capacity(ops.len() + 1);
+    let (mut old_line, mut new_line) = (0, 0);
+    for (tag, _) in ops {
+        old_pos.push(old_line);
+        new_pos.push(new_line);
+        match tag {
+            DiffTag::Equal => {
+                old_line += 1;
+                new_line += 1;
+            }
+            DiffTag::Delete => old_line += 1,
+            DiffTag::Insert => new_line += 1,
+        }
+    }
+    old_pos.push(old_line);
+    new_pos.push(new_line);
+    let mut i = 0;
+    while i < changes.len() {
+        let start = changes[i].saturating_sub(context);
+        let mut last = changes[i];
+        //两处变更间的相同行不超过2*context时合并为一个变更块
+        while i + 1 < changes.len() && changes[i + 1] - last - 1 <= 2 * context {
+            i += 1;
+            last = changes[i];
+        }
+        let end = (last + context + 1).min(ops.len());
+        let hunk_range = |pos: &[usize]| {
+            let count = pos[end] - pos[start];
+            let line = if count == 0 {
+                pos[start]
+            } else {
+                pos[start] + 1
+            };
+            format!("{},{}", line, count)
+        };
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(&old_pos),
+            hunk_range(&new_pos)
+        ));
+        for (tag, line) in &ops[start..end] {
+            out.push(match tag {
+                DiffTag::Equal => ' ',
+                DiffTag::Delete => '-',
+                DiffTag::Insert => '+',
+            });
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_config_lines() {
+        let ops = diff_lines("a\nb\nc\nd", "a\nc\nx\nd");
+        assert_eq!(
+            ops,
+            vec![
+                (DiffTag::Equal, "a\n"),
+                (DiffTag::Delete, "b\n"),
+                (DiffTag::Equal, "c\n"),
+                (DiffTag::Insert, "x\n"),
+                (DiffTag::Equal, "d"),
+            ]
+        );
+        //行尾换行符不同也是差异
+        assert_eq!(
+            diff_lines("a\r\nb\n", "a\r\nb"),
+            vec![
+                (DiffTag::Equal, "a\r\n"),
+                (DiffTag::Delete, "b\n"),
+                (DiffTag::Insert, "b"),
+            ]
+        );
+        assert!(diff_lines("a\nb", "a\nb")
+            .iter()
+            .all(|(tag, _)| *tag == DiffTag::Equal));
+        assert_eq!(diff_lines("", "a"), vec![(DiffTag::Insert, "a")]);
+    }
+
+    #[test]
+    fn render_diff() {
+        let html = render_diff_html(&diff_lines("k: 1\r\n", "k: <2>\r\n"));
+        assert_eq!(
+            html,
+            "<span class='del' style='color:red'>-k: 1</span>\n\
+             <span class='add' style='color:green'>+k: &lt;2&gt;</span>\n"
+        );
+    }
+
+    #[test]
+    fn unified_diff() {
+        let ops = diff_lines("a\nb\nc\nd", "a\nc\nx\nd");
+        assert_eq!(
+            render_unified_diff(&ops, 1),
+            "@@ -1,4 +1,4 @@\n a\n-b\n c\n+x\n d\n\\ No newline at end of file\n"
+        );
+        let old = "1\n2\n3\n4\n5\n6\n7\n8";
+        let new = "0\n2\n3\n4\n5\n6\n7\n9";
+        assert_eq!(
+            render_unified_diff(&diff_lines(old, new), 1),
+            "@@ -1,2 +1,2 @@\n-1\n+0\n 2\n@@ -7,2 +7,2 @@\n 7\n-8\n\\ No newline at end of file\n+9\n\\ No newline at end of file\n"
+        );
+        assert_eq!(render_unified_diff(&diff_lines("a", "a"), 3), "");
+        assert_eq!(
+            render_unified_diff(&diff_lines("a\n", "a"), 3),
+            "@@ -1,1 +1,1 @@\n-a\n+a\n\\ No newline at end of file\n"
+        );
+    }
+}
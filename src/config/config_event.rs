@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use super::core::ConfigKey;
+
+pub type ConfigEventSender = tokio::sync::broadcast::Sender<ConfigChangeEvent>;
+pub type ConfigEventReceiver = tokio::sync::broadcast::Receiver<ConfigChangeEvent>;
+pub type PropagationSlaEventSender = tokio::sync::broadcast::Sender<PropagationSlaViolationEvent>;
+pub type PropagationSlaEventReceiver =
+    tokio::sync::broadcast::Receiver<PropagationSlaViolationEvent>;
+
+/// 事件总线缓存的最大事件数,订阅方消费过慢时会丢弃最旧的事件
+const CONFIG_EVENT_BUS_CAPACITY: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigChangeType {
+    Set,
+    Remove,
+}
+
+///
+/// 配置变更事件,在配置写入内存成功后发布
+#[derive(Debug, Clone)]
+pub struct ConfigChangeEvent {
+    pub key: ConfigKey,
+    pub change_type: ConfigChangeType,
+    pub md5: Option<Arc<String>>,
+    pub op_user: Option<Arc<String>>,
+    pub op_time: i64,
+}
+
+///
+/// 配置变更超过传播时限后仍有使用方未更新到新版本
+#[derive(Debug, Clone)]
+pub struct PropagationSlaViolationEvent {
+    pub key: ConfigKey,
+    pub md5: Arc<String>,
+    pub sla_ms: u64,
+    //仍在使用旧版本的客户端
+    pub stale_clients: Vec<Arc<String>>,
+    pub detect_time: i64,
+}
+
+pub fn new_config_event_bus() -> ConfigEventSender {
+    let (sender, _) = tokio::sync::broadcast::channel(CONFIG_EVENT_BUS_CAPACITY);
+    sender
+}
+
+pub fn new_propagation_sla_event_bus() -> PropagationSlaEventSender {
+    let (sender, _) = tokio::sync::broadcast::channel(CONFIG_EVENT_BUS_CAPACITY);
+    sender
+}
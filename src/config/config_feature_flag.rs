@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::get_md5;
+
+///
+/// feature_flag类型配置的内容,以json保存
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureFlag {
+    pub enabled: bool,
+    //按客户端ip灰度开启的比例,0-100
+    #[serde(default)]
+    pub rollout_percent: Option<u8>,
+    #[serde(default)]
+    pub allowed_tenants: Vec<String>,
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeatureFlagSource {
+    //开关本身的值
+    Flag,
+    //按灰度比例计算
+    Rollout,
+    //命中允许的命名空间或ip
+    Override,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureFlagResult {
+    pub enabled: bool,
+    pub source: FeatureFlagSource,
+}
+
+impl FeatureFlagResult {
+    fn new(enabled: bool, source: FeatureFlagSource) -> Self {
+        Self { enabled, source }
+    }
+}
+
+impl FeatureFlag {
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        let flag: Self = serde_json::from_str(content)?;
+        if let Some(v) = flag.rollout_percent {
+            if v > 100 {
+                return Err(anyhow::anyhow!(
+                    "rolloutPercent must be between 0 and 100, got {}",
+                    v
+                ));
+            }
+        }
+        Ok(flag)
+    }
+
+    ///
+    /// 计算开关状态:命中允许的命名空间或ip时开启;关闭的开关不参与灰度;
+    /// 设置灰度比例时按客户端ip分桶,未提供ip时不开启
+    pub fn evaluate(
+        &self,
+        data_id: &str,
+        tenant: &str,
+        client_ip: Option<&str>,
+    ) -> FeatureFlagResult {
+        let ip_allowed = client_ip
+            .map(|ip| self.allowed_ips.iter().any(|v| v == ip))
+            .unwrap_or(false);
+        if ip_allowed || self.allowed_tenants.iter().any(|v| v == tenant) {
+            return FeatureFlagResult::new(true, FeatureFlagSource::Override);
+        }
+        if !self.enabled {
+            return FeatureFlagResult::new(false, FeatureFlagSource::Flag);
+        }
+        match (self.rollout_percent, client_ip) {
+            (None, _) | (Some(100), _) => FeatureFlagResult::new(true, FeatureFlagSource::Flag),
+            (Some(percent), Some(ip)) => FeatureFlagResult::new(
+                rollout_bucket(data_id, ip) < percent as u32,
+                FeatureFlagSource::Rollout,
+            ),
+            (Some(_), None) => FeatureFlagResult::new(false, FeatureFlagSource::Rollout),
+        }
+    }
+}
+
+///
+/// 按开关与客户端ip计算0-99的分桶;各节点计算结果一致,同一客户端的结果稳定
+fn rollout_bucket(data_id: &str, client_ip: &str) -> u32 {
+    let md5 = get_md5(&format!("{}#{}", data_id, client_ip));
+    u32::from_str_radix(&md5[..8], 16).unwrap_or_default() % 100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluate_feature_flag() {
+        let flag = FeatureFlag::parse(
+            r#"{"enabled":true,"rolloutPercent":50,"allowedTenants":["dev"],"allowedIps":["10.0.0.1"]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            flag.evaluate("a", "dev", None),
+            FeatureFlagResult::new(true, FeatureFlagSource::Override)
+        );
+        assert_eq!(
+            flag.evaluate("a", "", Some("10.0.0.1")).source,
+            FeatureFlagSource::Override
+        );
+        assert_eq!(
+            flag.evaluate("a", "", None),
+            FeatureFlagResult::new(false, FeatureFlagSource::Rollout)
+        );
+        //同一客户端多次计算结果一致
+        let r = flag.evaluate("a", "", Some("10.0.0.2"));
+        assert_eq!(r.source, FeatureFlagSource::Rollout);
+        assert_eq!(r, flag.evaluate("a", "", Some("10.0.0.2")));
+        let enabled = (0..200)
+            .filter(|i| {
+                flag.evaluate("a", "", Some(&format!("10.0.1.{}", i)))
+                    .enabled
+            })
+            .count();
+        assert!(enabled > 50 && enabled < 150);
+
+        let flag = FeatureFlag::parse(r#"{"enabled":false,"rolloutPercent":100}"#).unwrap();
+        assert_eq!(
+            flag.evaluate("a", "", Some("10.0.0.2")),
+            FeatureFlagResult::new(false, FeatureFlagSource::Flag)
+        );
+        assert!(FeatureFlag::parse(r#"{"enabled":true,"rolloutPercent":120}"#).is_err());
+        assert!(FeatureFlag::parse("enabled=true").is_err());
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::config_type::ConfigType;
+use crate::utils::get_md5;
+
+pub const FEDERATION_MD5_PATH: &str = "/nacos/v1/console/configs/md5s";
+//联邦集群间互相调用时携带的令牌
+pub const FEDERATION_TOKEN_HEADER: &str = "X-Federation-Token";
+
+///
+/// 联邦部署中的其它集群
+#[derive(Debug, Clone)]
+pub struct FederationPeer {
+    pub region: String,
+    //集群http地址,如 http://10.0.0.1:8848
+    pub url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct FederationConfig {
+    //当前集群的区域名
+    pub region: String,
+    pub peers: Vec<FederationPeer>,
+    //各集群共用的令牌,为空时对端需关闭openapi鉴权
+    pub token: Option<String>,
+}
+
+impl FederationConfig {
+    pub fn from_env() -> Option<Self> {
+        let peers =
+            Self::parse_peers(&std::env::var("RNACOS_FEDERATION_PEERS").unwrap_or_default());
+        if peers.is_empty() {
+            return None;
+        }
+        let region = std::env::var("RNACOS_FEDERATION_REGION").unwrap_or("local".to_owned());
+        let token = std::env::var("RNACOS_FEDERATION_TOKEN")
+            .ok()
+            .filter(|v| !v.is_empty());
+        Some(Self {
+            region,
+            peers,
+            token,
+        })
+    }
+
+    ///
+    /// 格式为 region1=http://host1:8848,region2=http://host2:8848
+    pub fn parse_peers(value: &str) -> Vec<FederationPeer> {
+        value
+            .split(',')
+            .filter_map(|item| item.trim().split_once('='))
+            .filter(|(region, url)| !region.trim().is_empty() && !url.trim().is_empty())
+            .map(|(region, url)| FederationPeer {
+                region: region.trim().to_owned(),
+                url: url.trim().trim_end_matches('/').to_owned(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigExportItem {
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    pub content: Arc<String>,
+    #[serde(rename = "type")]
+    pub config_type: Option<Arc<String>>,
+}
+
+impl ConfigExportItem {
+    pub fn matrix_key(&self) -> String {
+        format!("{}/{}", &self.group, &self.data_id)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FilterAction {
+    //字段值替换为null
+    Redact,
+    //包含该字段的配置不传输
+    Exclude,
+}
+
+///
+/// 跨区域复制时的字段过滤规则,字段路径格式为`$.user.email`,只支持对象字段
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterRule {
+    pub field_path: String,
+    pub action: FilterAction,
+}
+
+impl FilterRule {
+    fn segments(&self) -> Vec<&str> {
+        self.field_path
+            .trim()
+            .trim_start_matches('$')
+            .split('.')
+            .filter(|v| !v.is_empty())
+            .collect()
+    }
+}
+
+///
+/// 返回字段的父对象及字段名,字段不存在时返回None
+fn find_field<'a>(value: &'a mut Value, segments: &[&'a str]) -> Option<(&'a mut Value, &'a str)> {
+    let (last, parents) = segments.split_last()?;
+    let mut current = value;
+    for segment in parents {
+        current = current.as_object_mut()?.get_mut(*segment)?;
+    }
+    if current.as_object()?.contains_key(*last) {
+        Some((current, last))
+    } else {
+        None
+    }
+}
+
+///
+/// 按规则过滤单个配置;命中EXCLUDE规则时返回None。
+/// 只处理json/yaml配置,其它类型无法按字段定位,原样返回
+pub fn apply_replication_filter(
+    mut item: ConfigExportItem,
+    rules: &[FilterRule],
+) -> anyhow::Result<Option<ConfigExportItem>> {
+    if rules.is_empty() {
+        return Ok(Some(item));
+    }
+    let config_type = match &item.config_type {
+        Some(v) => ConfigType::new_by_value(v),
+        None => ConfigType::detect(&item.content),
+    };
+    let mut value: Value = match config_type {
+        ConfigType::Json => serde_json::from_str(&item.content)?,
+        ConfigType::Yaml => serde_yaml::from_str(&item.content)?,
+        _ => return Ok(Some(item)),
+    };
+    let mut redacted = false;
+    for rule in rules {
+        let segments = rule.segments();
+        if let Some((parent, field)) = find_field(&mut value, &segments) {
+            match rule.action {
+                FilterAction::Exclude => return Ok(None),
+                FilterAction::Redact => {
+                    parent[field] = Value::Null;
+                    redacted = true;
+                }
+            }
+        }
+    }
+    if redacted {
+        let content = match config_type {
+            ConfigType::Json => serde_json::to_string_pretty(&value)?,
+            _ => serde_yaml::to_string(&value)?,
+        };
+        item.content = Arc::new(content);
+    }
+    Ok(Some(item))
+}
+
+#[derive(Debug, Deserialize)]
+struct PeerMd5Response {
+    data: Option<HashMap<String, String>>,
+    message: Option<String>,
+}
+
+///
+/// 拉取对端集群命名空间下的配置md5,返回 配置 -> md5
+pub async fn fetch_peer_md5s(
+    client: &reqwest::Client,
+    peer: &FederationPeer,
+    namespace: &str,
+    token: Option<&str>,
+) -> anyhow::Result<HashMap<String, String>> {
+    let mut req = client
+        .get(format!("{}{}", &peer.url, FEDERATION_MD5_PATH))
+        .query(&[("namespace", namespace)]);
+    if let Some(token) = token {
+        req = req.header(FEDERATION_TOKEN_HEADER, token);
+    }
+    let resp = req.send().await?;
+    if !resp.status().is_success() {
+        return Err(anyhow::anyhow!("md5s status: {}", resp.status()));
+    }
+    let body: PeerMd5Response = resp.json().await?;
+    match body.data {
+        Some(md5s) => Ok(md5s),
+        None => Err(anyhow::anyhow!(
+            "md5s error: {}",
+            body.message.unwrap_or_default()
+        )),
+    }
+}
+
+pub fn new_peer_client() -> anyhow::Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?)
+}
+
+pub fn to_md5_map(list: &[ConfigExportItem]) -> HashMap<String, String> {
+    list.iter()
+        .map(|v| (v.matrix_key(), get_md5(&v.content)))
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ConsistencyRow {
+    //区域 -> md5,配置在该区域不存在时为空
+    #[serde(flatten)]
+    pub regions: BTreeMap<String, Option<String>>,
+    pub consistent: bool,
+}
+
+///
+/// 按配置汇总各区域的md5;拉取失败的区域不参与对比
+pub fn build_consistency_matrix(
+    regions: &[(String, HashMap<String, String>)],
+) -> BTreeMap<String, ConsistencyRow> {
+    let mut matrix: BTreeMap<String, ConsistencyRow> = BTreeMap::new();
+    for (_, md5s) in regions {
+        for key in md5s.keys() {
+            matrix.entry(key.clone()).or_default();
+        }
+    }
+    for (key, row) in matrix.iter_mut() {
+        for (region, md5s) in regions {
+            row.regions.insert(region.clone(), md5s.get(key).cloned());
+        }
+        let mut values = row.regions.values();
+        let first = values.next().cloned().flatten();
+        row.consistent = first.is_some() && values.all(|v| v.as_ref() == first.as_ref());
+    }
+    matrix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consistency_matrix() {
+        let peers = FederationConfig::parse_peers("us= http://a:8848/ ,bad, eu=http://b:8848");
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[0].url, "http://a:8848");
+
+        let md5s = |items: &[(&str, &str)]| -> HashMap<String, String> {
+            items
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        let matrix = build_consistency_matrix(&[
+            ("r1".to_owned(), md5s(&[("g/a", "1"), ("g/b", "2")])),
+            (
+                "r2".to_owned(),
+                md5s(&[("g/a", "1"), ("g/b", "3"), ("g/c", "4")]),
+            ),
+        ]);
+        assert!(matrix["g/a"].consistent);
+        assert!(!matrix["g/b"].consistent);
+        assert!(!matrix["g/c"].consistent);
+        assert_eq!(matrix["g/c"].regions["r1"], None);
+        let json = serde_json::to_value(&matrix["g/b"]).unwrap();
+        assert_eq!(json["r2"], "3");
+        assert_eq!(json["consistent"], false);
+    }
+
+    #[test]
+    fn replication_filter() {
+        let rules: Vec<FilterRule> = serde_json::from_str(
+            r#"[{"fieldPath":"$.user.email","action":"REDACT"},{"fieldPath":"$.ssn","action":"EXCLUDE"}]"#,
+        )
+        .unwrap();
+        let item = |content: &str, config_type: Option<&str>| ConfigExportItem {
+            group: Arc::new("DEFAULT_GROUP".to_owned()),
+            data_id: Arc::new("a".to_owned()),
+            content: Arc::new(content.to_owned()),
+            config_type: config_type.map(|v| Arc::new(v.to_owned())),
+        };
+        let v = apply_replication_filter(
+            item(r#"{"user":{"email":"a@b.c","name":"a"}}"#, None),
+            &rules,
+        )
+        .unwrap()
+        .unwrap();
+        let value: Value = serde_json::from_str(&v.content).unwrap();
+        assert_eq!(value["user"]["email"], Value::Null);
+        assert_eq!(value["user"]["name"], "a");
+
+        let v = apply_replication_filter(item("user:\n  email: a@b.c\n", Some("yaml")), &rules)
+            .unwrap()
+            .unwrap();
+        assert!(!v.content.contains("a@b.c"));
+
+        assert!(
+            apply_replication_filter(item(r#"{"ssn":"1","user":{}}"#, None), &rules)
+                .unwrap()
+                .is_none()
+        );
+        let text = item("user.email=a@b.c", Some("text"));
+        let v = apply_replication_filter(text, &rules).unwrap().unwrap();
+        assert_eq!(v.content.as_str(), "user.email=a@b.c");
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use atom_syndication::{Entry, Feed, FixedDateTime, Person, Text};
+use chrono::{FixedOffset, TimeZone};
+
+use super::config_event::ConfigChangeType;
+use super::core::ConfigKey;
+use super::ConfigUtils;
+use crate::utils::get_md5;
+
+/// 订阅源最多包含的变更数
+pub const CONFIG_FEED_MAX_ENTRIES: usize = 50;
+pub const CONFIG_FEED_PATH: &str = "/nacos/v1/console/configs/feed.atom";
+/// 订阅源只读令牌的请求头,不放在url中避免被访问日志与代理记录
+pub const CONFIG_FEED_TOKEN_HEADER: &str = "X-Feed-Token";
+
+///
+/// 格式为 namespace1=token1,namespace2=token2,默认命名空间可写为public
+pub fn parse_feed_tokens(value: &str) -> HashMap<String, String> {
+    value
+        .split(',')
+        .filter_map(|item| item.trim().split_once('='))
+        .filter(|(_, token)| !token.trim().is_empty())
+        .map(|(namespace, token)| {
+            (
+                ConfigUtils::default_tenant(namespace.trim().to_owned()),
+                token.trim().to_owned(),
+            )
+        })
+        .collect()
+}
+
+///
+/// 订阅源中的一次配置变更
+#[derive(Debug, Clone)]
+pub struct ConfigFeedEntry {
+    pub key: ConfigKey,
+    pub change_type: ConfigChangeType,
+    //删除记录为0
+    pub history_id: u64,
+    pub op_user: Option<Arc<String>>,
+    pub modified_time: i64,
+    //与上一版本的统一差异格式内容,删除或历史版本已裁剪时为空
+    pub diff: Option<String>,
+}
+
+impl ConfigFeedEntry {
+    ///
+    /// 条目id只由配置与变更确定,重复生成订阅源时保持不变
+    fn id(&self) -> String {
+        format!(
+            "urn:rnacos:config-change:{}:{}:{}",
+            get_md5(&self.key.build_key()),
+            self.modified_time,
+            self.history_id
+        )
+    }
+
+    fn title(&self) -> String {
+        let action = match self.change_type {
+            ConfigChangeType::Set => "changed",
+            ConfigChangeType::Remove => "deleted",
+        };
+        format!(
+            "[{}] {} {} by {}",
+            &self.key.group,
+            &self.key.data_id,
+            action,
+            self.op_user
+                .as_deref()
+                .map(|v| v.as_str())
+                .unwrap_or("unknown")
+        )
+    }
+
+    fn summary(&self) -> String {
+        match (&self.change_type, &self.diff) {
+            (ConfigChangeType::Remove, _) => "config deleted".to_owned(),
+            (_, Some(diff)) => diff.to_owned(),
+            (_, None) => "config changed".to_owned(),
+        }
+    }
+
+    fn to_entry(&self) -> Entry {
+        let mut entry = Entry::default();
+        entry.set_id(self.id());
+        entry.set_title(self.title());
+        entry.set_updated(to_feed_time(self.modified_time));
+        entry.set_summary(Some(Text::plain(self.summary())));
+        if let Some(op_user) = &self.op_user {
+            let mut author = Person::default();
+            author.set_name(op_user.as_str());
+            entry.set_authors(vec![author]);
+        }
+        entry
+    }
+}
+
+fn to_feed_time(millis: i64) -> FixedDateTime {
+    let utc = FixedOffset::east_opt(0).unwrap();
+    utc.timestamp_millis_opt(millis)
+        .single()
+        .unwrap_or_else(|| utc.timestamp_millis_opt(0).unwrap())
+}
+
+///
+/// 订阅源的更新时间为最近一次变更的时间,没有变更时为0
+pub fn feed_updated_time(entries: &[ConfigFeedEntry]) -> i64 {
+    entries
+        .iter()
+        .map(|v| v.modified_time)
+        .max()
+        .unwrap_or_default()
+}
+
+///
+/// 生成Atom 1.0订阅源,条目按变更时间倒序
+pub fn build_atom_feed(tenant: &str, group: Option<&str>, entries: &[ConfigFeedEntry]) -> String {
+    let scope = match group {
+        Some(group) => format!("{}##{}", tenant, group),
+        None => tenant.to_owned(),
+    };
+    let mut feed = Feed::default();
+    feed.set_id(format!("urn:rnacos:config-feed:{}", get_md5(&scope)));
+    feed.set_title(format!("r-nacos config changes: {}", &scope));
+    feed.set_updated(to_feed_time(feed_updated_time(entries)));
+    feed.set_entries(entries.iter().map(|v| v.to_entry()).collect::<Vec<_>>());
+    feed.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feed_tokens() {
+        let tokens = parse_feed_tokens("public=t0, dev = t1,prod=,bad");
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens.get("").unwrap(), "t0");
+        assert_eq!(tokens.get("dev").unwrap(), "t1");
+    }
+
+    #[test]
+    fn atom_feed() {
+        let entries = vec![
+            ConfigFeedEntry {
+                key: ConfigKey::new("app.yaml", "DEFAULT_GROUP", "dev"),
+                change_type: ConfigChangeType::Set,
+                history_id: 2,
+                op_user: Some(Arc::new("alice".to_owned())),
+                modified_time: 2000,
+                diff: Some("-a: 1\n+a: 2\n".to_owned()),
+            },
+            ConfigFeedEntry {
+                key: ConfigKey::new("old.yaml", "DEFAULT_GROUP", "dev"),
+                change_type: ConfigChangeType::Remove,
+                history_id: 0,
+                op_user: None,
+                modified_time: 1000,
+                diff: None,
+            },
+        ];
+        assert_eq!(feed_updated_time(&entries), 2000);
+        assert_eq!(entries[0].id(), entries[0].clone().id());
+        let xml = build_atom_feed("dev", None, &entries);
+        let feed: Feed = xml.parse().unwrap();
+        assert_eq!(feed.entries().len(), 2);
+        assert_eq!(
+            feed.entries()[0].title().as_str(),
+            "[DEFAULT_GROUP] app.yaml changed by alice"
+        );
+        assert_eq!(
+            feed.entries()[1].title().as_str(),
+            "[DEFAULT_GROUP] old.yaml deleted by unknown"
+        );
+        assert!(feed.entries()[0]
+            .summary()
+            .unwrap()
+            .as_str()
+            .contains("+a: 2"));
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use hkdf::Hkdf;
+use serde_json::Value;
+use sha2::Sha256;
+
+use super::config_read_view::ConfigReadView;
+use super::config_type::ConfigType;
+use super::core::ConfigKey;
+use crate::common::crypto_utils::{decode_base64, decrypt_aes128, encode_base64};
+use crate::console::model::NamespaceInfo;
+use crate::console::{SYSCONFIG_GROUP, SYSCONFIG_NAMESPACE, SYSCONFIG_NAMESPACE_KEY};
+use crate::utils::get_md5;
+
+const ENC_PREFIX: &str = "ENC(";
+const ENC_SUFFIX: &str = ")";
+//旧版本AES-128-CBC密文开头的iv长度
+const LEGACY_IV_LEN: usize = 16;
+//AES-256-GCM密文的版本标记,旧版本密文以16进制iv开头,不会与之冲突
+const GCM_VERSION: u8 = 1;
+const GCM_NONCE_LEN: usize = 12;
+const KEY_INFO_PREFIX: &str = "rnacos-config-field:";
+//轮换主密钥后保留的主密钥数,含当前主密钥
+const MAX_MASTER_KEYS: usize = 3;
+
+///
+/// 按`*`通配匹配字段路径,`*`可匹配包含`.`在内的任意字符
+pub fn match_pattern(pattern: &str, path: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == path;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !path.starts_with(first) || path.len() < first.len() + last.len() {
+        return false;
+    }
+    let mut rest = &path[first.len()..];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+fn is_encrypted(v: &Value) -> bool {
+    v.as_str()
+        .map(|s| s.starts_with(ENC_PREFIX) && s.ends_with(ENC_SUFFIX))
+        .unwrap_or(false)
+}
+
+///
+/// 遍历对象字段,对路径匹配的非对象字段调用f;数组元素不参与匹配
+fn visit_fields<F>(
+    value: &mut Value,
+    path: &str,
+    patterns: &[String],
+    f: &mut F,
+) -> anyhow::Result<bool>
+where
+    F: FnMut(&mut Value) -> anyhow::Result<bool>,
+{
+    let map = match value.as_object_mut() {
+        Some(v) => v,
+        None => return Ok(false),
+    };
+    let mut changed = false;
+    for (k, v) in map.iter_mut() {
+        let field_path = if path.is_empty() {
+            k.to_owned()
+        } else {
+            format!("{}.{}", path, k)
+        };
+        if v.is_object() {
+            changed |= visit_fields(v, &field_path, patterns, f)?;
+        } else if !v.is_null() && patterns.iter().any(|p| match_pattern(p, &field_path)) {
+            changed |= f(v)?;
+        }
+    }
+    Ok(changed)
+}
+
+///
+/// 命名空间列表配置的md5及解析出的各命名空间加密字段规则
+#[derive(Default)]
+struct NamespacePatterns {
+    md5: Option<Arc<String>>,
+    patterns: HashMap<String, Arc<Vec<String>>>,
+}
+
+///
+/// 配置字段自动加密;各命名空间的密钥由服务端主密钥与命名空间id派生
+pub struct ConfigFieldCipher {
+    //第一个为当前主密钥,其余为轮换前的主密钥,只用于解密
+    master_keys: RwLock<Vec<Arc<Vec<u8>>>>,
+    patterns: Mutex<NamespacePatterns>,
+}
+
+impl ConfigFieldCipher {
+    pub fn new(master_key: Vec<u8>) -> Self {
+        Self {
+            master_keys: RwLock::new(vec![Arc::new(master_key)]),
+            patterns: Mutex::new(NamespacePatterns::default()),
+        }
+    }
+
+    ///
+    /// 更换当前主密钥,保留最近的旧主密钥用于解密轮换前加密的字段;返回主密钥是否有变化
+    pub fn rotate_master_key(&self, master_key: Vec<u8>) -> bool {
+        let mut keys = self.master_keys.write().unwrap();
+        if keys.first().map(|v| v.as_slice()) == Some(master_key.as_slice()) {
+            return false;
+        }
+        keys.retain(|v| !v.is_empty() && v.as_slice() != master_key.as_slice());
+        keys.insert(0, Arc::new(master_key));
+        keys.truncate(MAX_MASTER_KEYS);
+        true
+    }
+
+    fn master_keys(&self) -> Vec<Arc<Vec<u8>>> {
+        self.master_keys.read().unwrap().clone()
+    }
+
+    ///
+    /// 命名空间开启自动加密时返回字段规则
+    pub fn namespace_patterns(
+        &self,
+        read_view: &ConfigReadView,
+        tenant: &str,
+    ) -> Option<Arc<Vec<String>>> {
+        let namespace_key = ConfigKey::new(
+            SYSCONFIG_NAMESPACE_KEY,
+            SYSCONFIG_GROUP,
+            SYSCONFIG_NAMESPACE,
+        );
+        let value = read_view.get(&namespace_key)?;
+        let mut cache = self.patterns.lock().ok()?;
+        if cache.md5.as_ref() != Some(&value.md5) {
+            let infos: Vec<NamespaceInfo> =
+                serde_json::from_str(&value.content).unwrap_or_default();
+            cache.patterns = infos
+                .into_iter()
+                .filter_map(|v| match (v.namespace_id, v.auto_encrypt_patterns) {
+                    (Some(id), Some(list)) if !list.is_empty() => Some((id, Arc::new(list))),
+                    _ => None,
+                })
+                .collect();
+            cache.md5 = Some(value.md5.clone());
+        }
+        cache.patterns.get(tenant).cloned()
+    }
+
+    ///
+    /// 用HKDF-SHA256从主密钥派生命名空间的256位密钥
+    fn namespace_cipher(master_key: &[u8], tenant: &str) -> anyhow::Result<Aes256Gcm> {
+        if master_key.is_empty() {
+            return Err(anyhow::anyhow!("config encryption key is not set"));
+        }
+        let hkdf = Hkdf::<Sha256>::new(None, master_key);
+        let mut key = [0u8; 32];
+        hkdf.expand(
+            format!("{}{}", KEY_INFO_PREFIX, tenant).as_bytes(),
+            &mut key,
+        )
+        .map_err(|_| anyhow::anyhow!("derive config encryption key error"))?;
+        Ok(Aes256Gcm::new(&key.into()))
+    }
+
+    ///
+    /// 旧版本的命名空间密钥,只用于解密升级前保存的密文
+    fn legacy_namespace_key(master_key: &[u8], tenant: &str) -> String {
+        let md5 = get_md5(&format!(
+            "{}:{}",
+            String::from_utf8_lossy(master_key),
+            tenant
+        ));
+        md5[..16].to_owned()
+    }
+
+    fn encrypt_value(cipher: &Aes256Gcm, v: &Value) -> anyhow::Result<Value> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let encrypted = cipher
+            .encrypt(&nonce, v.to_string().as_bytes())
+            .map_err(|_| anyhow::anyhow!("encrypt config field error"))?;
+        let mut data = Vec::with_capacity(1 + GCM_NONCE_LEN + encrypted.len());
+        data.push(GCM_VERSION);
+        data.extend_from_slice(nonce.as_slice());
+        data.extend(encrypted);
+        Ok(Value::String(format!(
+            "{}{}{}",
+            ENC_PREFIX,
+            encode_base64(&data),
+            ENC_SUFFIX
+        )))
+    }
+
+    ///
+    /// 依次用当前及轮换前的主密钥解密
+    fn decrypt_value(
+        tenant: &str,
+        master_keys: &[(Arc<Vec<u8>>, Aes256Gcm)],
+        v: &str,
+    ) -> anyhow::Result<Value> {
+        let data = decode_base64(&v[ENC_PREFIX.len()..v.len() - ENC_SUFFIX.len()])?;
+        let plain = if data.first() == Some(&GCM_VERSION) {
+            if data.len() <= 1 + GCM_NONCE_LEN {
+                return Err(anyhow::anyhow!("invalid encrypted value"));
+            }
+            let nonce = Nonce::from_slice(&data[1..1 + GCM_NONCE_LEN]);
+            master_keys
+                .iter()
+                .find_map(|(_, cipher)| cipher.decrypt(nonce, &data[1 + GCM_NONCE_LEN..]).ok())
+        } else {
+            if data.len() <= LEGACY_IV_LEN {
+                return Err(anyhow::anyhow!("invalid encrypted value"));
+            }
+            let iv = std::str::from_utf8(&data[..LEGACY_IV_LEN])?;
+            //旧格式没有完整性校验,用错密钥时多数情况填充校验失败,少数情况得到非json内容
+            master_keys.iter().find_map(|(master_key, _)| {
+                decrypt_aes128(
+                    &Self::legacy_namespace_key(master_key, tenant),
+                    iv,
+                    &data[LEGACY_IV_LEN..],
+                )
+                .ok()
+                .filter(|v| serde_json::from_slice::<Value>(v).is_ok())
+            })
+        };
+        match plain {
+            Some(v) => Ok(serde_json::from_slice(&v)?),
+            None => Err(anyhow::anyhow!("decrypt config field error")),
+        }
+    }
+
+    ///
+    /// 加密json/yaml配置中路径匹配的字段,字段值替换为`ENC(base64)`;没有字段需要加密时返回None
+    pub fn encrypt_content(
+        &self,
+        tenant: &str,
+        content: &str,
+        config_type: Option<&str>,
+        patterns: &[String],
+    ) -> anyhow::Result<Option<String>> {
+        let config_type = Self::content_type(content, config_type);
+        let mut value = match Self::parse(content, &config_type)? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let mut cipher = None;
+        let changed = visit_fields(&mut value, "", patterns, &mut |v| {
+            if is_encrypted(v) {
+                return Ok(false);
+            }
+            if cipher.is_none() {
+                let master_key = self.master_keys().into_iter().next().unwrap_or_default();
+                cipher = Some(Self::namespace_cipher(&master_key, tenant)?);
+            }
+            *v = Self::encrypt_value(cipher.as_ref().unwrap(), v)?;
+            Ok(true)
+        })?;
+        Self::to_content(changed, &value, &config_type)
+    }
+
+    ///
+    /// 解密路径匹配且已加密的字段;没有字段需要解密时返回None
+    pub fn decrypt_content(
+        &self,
+        tenant: &str,
+        content: &str,
+        config_type: Option<&str>,
+        patterns: &[String],
+    ) -> anyhow::Result<Option<String>> {
+        let config_type = Self::content_type(content, config_type);
+        //内容中没有加密字段时不解析
+        if !content.contains(ENC_PREFIX) {
+            return Ok(None);
+        }
+        let mut value = match Self::parse(content, &config_type)? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let mut master_keys = vec![];
+        for master_key in self.master_keys() {
+            if !master_key.is_empty() {
+                let cipher = Self::namespace_cipher(&master_key, tenant)?;
+                master_keys.push((master_key, cipher));
+            }
+        }
+        if master_keys.is_empty() {
+            return Err(anyhow::anyhow!("config encryption key is not set"));
+        }
+        let changed = visit_fields(&mut value, "", patterns, &mut |v| {
+            if !is_encrypted(v) {
+                return Ok(false);
+            }
+            *v = Self::decrypt_value(tenant, &master_keys, v.as_str().unwrap_or_default())?;
+            Ok(true)
+        })?;
+        Self::to_content(changed, &value, &config_type)
+    }
+
+    fn content_type(content: &str, config_type: Option<&str>) -> ConfigType {
+        match config_type {
+            Some(v) => ConfigType::new_by_value(v),
+            None => ConfigType::detect(content),
+        }
+    }
+
+    fn parse(content: &str, config_type: &ConfigType) -> anyhow::Result<Option<Value>> {
+        match config_type {
+            ConfigType::Json => Ok(Some(serde_json::from_str(content)?)),
+            ConfigType::Yaml => Ok(Some(serde_yaml::from_str(content)?)),
+            _ => Ok(None),
+        }
+    }
+
+    fn to_content(
+        changed: bool,
+        value: &Value,
+        config_type: &ConfigType,
+    ) -> anyhow::Result<Option<String>> {
+        if !changed {
+            return Ok(None);
+        }
+        match config_type {
+            ConfigType::Json => Ok(Some(serde_json::to_string_pretty(value)?)),
+            _ => Ok(Some(serde_yaml::to_string(value)?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::crypto_utils::encrypt_aes128;
+
+    #[test]
+    fn match_field_pattern() {
+        assert!(match_pattern("*.password", "db.password"));
+        assert!(match_pattern("*.password", "spring.datasource.password"));
+        assert!(!match_pattern("*.password", "password"));
+        assert!(!match_pattern("*.password", "db.password_hint"));
+        assert!(match_pattern("db.*.secret", "db.main.secret"));
+        assert!(match_pattern("token", "token"));
+    }
+
+    #[test]
+    fn encrypt_and_decrypt_fields() {
+        let cipher = ConfigFieldCipher::new(b"master".to_vec());
+        let patterns = vec!["*.password".to_owned(), "*.port".to_owned()];
+        let content = "db:\n  host: 127.0.0.1\n  port: 3306\n  password: abc\n";
+        let encrypted = cipher
+            .encrypt_content("dev", content, Some("yaml"), &patterns)
+            .unwrap()
+            .unwrap();
+        assert!(!encrypted.contains("abc"));
+        assert!(encrypted.contains("host: 127.0.0.1"));
+        //已加密的字段不重复加密
+        assert!(cipher
+            .encrypt_content("dev", &encrypted, Some("yaml"), &patterns)
+            .unwrap()
+            .is_none());
+        let decrypted = cipher
+            .decrypt_content("dev", &encrypted, Some("yaml"), &patterns)
+            .unwrap()
+            .unwrap();
+        let value: Value = serde_yaml::from_str(&decrypted).unwrap();
+        assert_eq!(value["db"]["password"], "abc");
+        assert_eq!(value["db"]["port"], 3306);
+        //其它命名空间的密钥无法解密
+        assert!(cipher
+            .decrypt_content("prod", &encrypted, Some("yaml"), &patterns)
+            .is_err());
+        //升级前AES-128-CBC格式的密文仍可解密
+        let legacy_key = ConfigFieldCipher::legacy_namespace_key(b"master", "dev");
+        let iv = "0123456789abcdef";
+        let mut data = iv.as_bytes().to_vec();
+        data.extend(encrypt_aes128(&legacy_key, iv, b"\"abc\"").unwrap());
+        let legacy = format!("db:\n  password: ENC({})\n", encode_base64(&data));
+        let decrypted = cipher
+            .decrypt_content("dev", &legacy, Some("yaml"), &patterns)
+            .unwrap()
+            .unwrap();
+        let value: Value = serde_yaml::from_str(&decrypted).unwrap();
+        assert_eq!(value["db"]["password"], "abc");
+        //轮换主密钥后新写入的字段使用新密钥,轮换前的字段仍可解密
+        assert!(cipher.rotate_master_key(vec![1, 2, 0xff]));
+        assert!(!cipher.rotate_master_key(vec![1, 2, 0xff]));
+        let rotated = cipher
+            .encrypt_content("dev", content, Some("yaml"), &patterns)
+            .unwrap()
+            .unwrap();
+        assert!(cipher
+            .decrypt_content("dev", &encrypted, Some("yaml"), &patterns)
+            .is_ok());
+        assert!(ConfigFieldCipher::new(b"master".to_vec())
+            .decrypt_content("dev", &rotated, Some("yaml"), &patterns)
+            .is_err());
+        //未设置主密钥时不能加密
+        assert!(ConfigFieldCipher::new(vec![])
+            .encrypt_content("dev", r#"{"a":{"password":"1"}}"#, None, &patterns)
+            .is_err());
+    }
+}
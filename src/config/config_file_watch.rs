@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use super::core::ConfigKey;
+use super::ConfigUtils;
+use crate::common::appdata::AppShareData;
+use crate::raft::cluster::model::SetConfigReq;
+use crate::utils::get_md5;
+
+///
+/// 本地目录同步配置;目录结构为root_dir/tenant/group/dataId,
+/// namespace_from_dir为false时为root_dir/group/dataId,写入默认命名空间
+#[derive(Debug, Clone)]
+pub struct FileWatchConfig {
+    pub root_dir: String,
+    pub namespace_from_dir: bool,
+    pub interval_ms: u64,
+}
+
+impl FileWatchConfig {
+    pub fn from_env() -> Option<Self> {
+        let enabled = std::env::var("RNACOS_CONFIG_FILE_WATCH_ENABLED")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        if !enabled {
+            return None;
+        }
+        let root_dir =
+            std::env::var("RNACOS_CONFIG_FILE_WATCH_ROOT_DIR").unwrap_or("./configs".to_owned());
+        let namespace_from_dir = std::env::var("RNACOS_CONFIG_FILE_WATCH_NAMESPACE_FROM_DIR")
+            .unwrap_or("true".to_owned())
+            .parse()
+            .unwrap_or(true);
+        let interval_ms = std::env::var("RNACOS_CONFIG_FILE_WATCH_INTERVAL_MS")
+            .unwrap_or("1000".to_owned())
+            .parse()
+            .unwrap_or(1000);
+        Some(Self {
+            root_dir,
+            namespace_from_dir,
+            interval_ms,
+        })
+    }
+}
+
+///
+/// 定时扫描目录,按文件修改时间与大小发现变化的配置文件
+pub struct ConfigFileWatcher {
+    config: FileWatchConfig,
+    //文件 -> (修改时间,大小)
+    files: HashMap<PathBuf, (SystemTime, u64)>,
+}
+
+impl ConfigFileWatcher {
+    pub fn new(config: FileWatchConfig) -> Self {
+        Self {
+            config,
+            files: HashMap::new(),
+        }
+    }
+
+    pub fn path_to_key(&self, relative: &Path) -> Option<ConfigKey> {
+        config_key_from_path(relative, self.config.namespace_from_dir)
+    }
+
+    ///
+    /// 返回新增或修改的配置文件;已删除的文件不删除配置
+    pub fn scan(&mut self) -> Vec<(ConfigKey, PathBuf)> {
+        let root = PathBuf::from(&self.config.root_dir);
+        let mut current = HashMap::new();
+        let depth = if self.config.namespace_from_dir { 3 } else { 2 };
+        collect_files(&root, depth, &mut current);
+        let mut changes = vec![];
+        for (path, meta) in &current {
+            if self.files.get(path) == Some(meta) {
+                continue;
+            }
+            let key = path
+                .strip_prefix(&root)
+                .ok()
+                .and_then(|v| self.path_to_key(v));
+            if let Some(key) = key {
+                changes.push((key, path.clone()));
+            }
+        }
+        self.files = current;
+        changes
+    }
+
+    ///
+    /// 写入失败时清除记录,下次扫描重试
+    pub fn forget(&mut self, path: &Path) {
+        self.files.remove(path);
+    }
+
+    pub async fn run(mut self, app: Arc<AppShareData>) {
+        log::info!("watch config files in {}", &self.config.root_dir);
+        let interval = Duration::from_millis(self.config.interval_ms.max(100));
+        loop {
+            for (key, path) in self.scan() {
+                let content = match std::fs::read_to_string(&path) {
+                    Ok(v) => v,
+                    Err(err) => {
+                        log::warn!("read config file {:?} error: {}", &path, err);
+                        self.forget(&path);
+                        continue;
+                    }
+                };
+                //内容未变化时不写入,避免重启后重复产生历史记录
+                let md5 = get_md5(&content);
+                if app
+                    .config_read_view
+                    .get(&key)
+                    .map(|v| v.md5.as_str() == md5)
+                    .unwrap_or(false)
+                {
+                    continue;
+                }
+                let req = SetConfigReq::new(key.clone(), Arc::new(content));
+                match app.config_route.set_config(req).await {
+                    Ok(_) => log::info!("config file changed, update config {}", &key),
+                    Err(err) => {
+                        log::warn!("update config {} from file error: {}", &key, err);
+                        self.forget(&path);
+                    }
+                }
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+///
+/// 相对路径转为配置key;层级不匹配或以.开头、~结尾的临时文件返回None
+pub fn config_key_from_path(relative: &Path, namespace_from_dir: bool) -> Option<ConfigKey> {
+    let parts: Vec<&str> = relative
+        .components()
+        .map(|v| v.as_os_str().to_str())
+        .collect::<Option<_>>()?;
+    if parts
+        .iter()
+        .any(|v| v.is_empty() || v.starts_with('.') || v.ends_with('~'))
+    {
+        return None;
+    }
+    let key = match (namespace_from_dir, parts.as_slice()) {
+        (true, [tenant, group, data_id]) => ConfigKey::new(
+            data_id,
+            group,
+            &ConfigUtils::default_tenant(tenant.to_string()),
+        ),
+        (false, [group, data_id]) => ConfigKey::new(data_id, group, ""),
+        _ => return None,
+    };
+    key.is_valid().ok().map(|_| key)
+}
+
+fn collect_files(dir: &Path, depth: usize, files: &mut HashMap<PathBuf, (SystemTime, u64)>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let meta = match entry.metadata() {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if meta.is_dir() && depth > 1 {
+            collect_files(&entry.path(), depth - 1, files);
+        } else if meta.is_file() && depth == 1 {
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.insert(entry.path(), (modified, meta.len()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_file_watch() {
+        let root = std::env::temp_dir().join(format!(
+            "rnacos_file_watch_test_{}",
+            uuid::Uuid::new_v4().simple()
+        ));
+        std::fs::create_dir_all(root.join("public/DEFAULT_GROUP")).unwrap();
+        std::fs::write(root.join("public/DEFAULT_GROUP/app.yaml"), "a: 1").unwrap();
+        std::fs::write(root.join("public/DEFAULT_GROUP/.app.yaml.swp"), "").unwrap();
+        std::fs::write(root.join("public/ignored"), "").unwrap();
+        let mut watcher = ConfigFileWatcher::new(FileWatchConfig {
+            root_dir: root.to_string_lossy().to_string(),
+            namespace_from_dir: true,
+            interval_ms: 1000,
+        });
+
+        let changes = watcher.scan();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].0,
+            ConfigKey::new("app.yaml", "DEFAULT_GROUP", "")
+        );
+        //未修改时不重复返回
+        assert!(watcher.scan().is_empty());
+        std::fs::write(root.join("public/DEFAULT_GROUP/app.yaml"), "a: 12").unwrap();
+        assert_eq!(watcher.scan().len(), 1);
+        watcher.forget(&root.join("public/DEFAULT_GROUP/app.yaml"));
+        assert_eq!(watcher.scan().len(), 1);
+        std::fs::remove_dir_all(&root).ok();
+
+        let watcher = ConfigFileWatcher::new(FileWatchConfig {
+            root_dir: "".to_owned(),
+            namespace_from_dir: false,
+            interval_ms: 1000,
+        });
+        assert_eq!(
+            watcher.path_to_key(Path::new("dev/app.json")),
+            Some(ConfigKey::new("app.json", "dev", ""))
+        );
+        assert!(watcher.path_to_key(Path::new("a/dev/app.json")).is_none());
+    }
+}
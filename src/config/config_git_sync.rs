@@ -0,0 +1,332 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix::prelude::*;
+use tokio::process::Command;
+
+use super::config_file_watch::config_key_from_path;
+use super::core::ConfigKey;
+use super::model::ConfigInUseError;
+use crate::common::appdata::AppShareData;
+use crate::console::{SYSCONFIG_GROUP, SYSCONFIG_NAMESPACE};
+use crate::raft::cluster::model::{DelConfigReq, SetConfigReq};
+use crate::utils::get_md5;
+
+/// 保存最近一次同步的提交,写入配置经raft复制,重启后从该提交继续同步
+pub const GIT_SYNC_COMMIT_KEY: &str = "git_sync_commit";
+
+///
+/// 从git仓库同步配置;文件路径与本地目录同步的约定相同
+#[derive(Debug, Clone)]
+pub struct GitSyncConfig {
+    pub repo_url: String,
+    pub branch: String,
+    pub interval_secs: u64,
+    pub namespace_from_dir: bool,
+    //本地仓库目录
+    pub work_dir: PathBuf,
+}
+
+impl GitSyncConfig {
+    pub fn from_env(config_db_dir: &str) -> Option<Self> {
+        let enabled = std::env::var("RNACOS_CONFIG_GIT_SYNC_ENABLED")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let repo_url = std::env::var("RNACOS_CONFIG_GIT_SYNC_REPO_URL").unwrap_or_default();
+        if !enabled || repo_url.is_empty() {
+            return None;
+        }
+        let branch = std::env::var("RNACOS_CONFIG_GIT_SYNC_BRANCH").unwrap_or("main".to_owned());
+        let interval_secs = std::env::var("RNACOS_CONFIG_GIT_SYNC_INTERVAL_SECS")
+            .unwrap_or("60".to_owned())
+            .parse()
+            .unwrap_or(60);
+        let namespace_from_dir = std::env::var("RNACOS_CONFIG_GIT_SYNC_NAMESPACE_FROM_DIR")
+            .unwrap_or("true".to_owned())
+            .parse()
+            .unwrap_or(true);
+        Some(Self {
+            repo_url,
+            branch,
+            interval_secs,
+            namespace_from_dir,
+            work_dir: Path::new(config_db_dir).join("git_sync"),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GitFileChange {
+    Update(String),
+    Delete(String),
+}
+
+///
+/// 解析`git diff --name-status -z`的输出
+pub fn parse_name_status(output: &str) -> Vec<GitFileChange> {
+    let mut changes = vec![];
+    let mut parts = output.split('\0').filter(|v| !v.is_empty());
+    while let (Some(status), Some(path)) = (parts.next(), parts.next()) {
+        let path = path.to_owned();
+        if status.starts_with('D') {
+            changes.push(GitFileChange::Delete(path));
+        } else {
+            changes.push(GitFileChange::Update(path));
+        }
+    }
+    changes
+}
+
+///
+/// 同步记录的提交来自配置内容,作为git参数前需校验,避免被当作选项解析
+fn is_commit_id(value: &str) -> bool {
+    (4..=64).contains(&value.len()) && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+async fn git_output(dir: Option<&Path>, args: &[&str]) -> anyhow::Result<Vec<u8>> {
+    let mut cmd = Command::new("git");
+    if let Some(dir) = dir {
+        cmd.arg("-C").arg(dir);
+    }
+    let output = cmd.args(args).output().await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "git {} error: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+async fn git(dir: Option<&Path>, args: &[&str]) -> anyhow::Result<String> {
+    Ok(String::from_utf8(git_output(dir, args).await?)?)
+}
+
+///
+/// 定时拉取仓库,对比上次同步的提交与分支最新提交,写入变化的配置;
+/// 仓库使用bare clone,不检出工作区
+pub struct GitSyncActor {
+    config: Arc<GitSyncConfig>,
+    app: Arc<AppShareData>,
+    syncing: bool,
+}
+
+impl GitSyncActor {
+    pub fn new(config: GitSyncConfig, app: Arc<AppShareData>) -> Self {
+        Self {
+            config: Arc::new(config),
+            app,
+            syncing: false,
+        }
+    }
+
+    fn commit_key() -> ConfigKey {
+        ConfigKey::new(GIT_SYNC_COMMIT_KEY, SYSCONFIG_GROUP, SYSCONFIG_NAMESPACE)
+    }
+
+    fn sync(&mut self, ctx: &mut Context<Self>) {
+        if self.syncing {
+            return;
+        }
+        self.syncing = true;
+        let config = self.config.clone();
+        let app = self.app.clone();
+        async move { Self::sync_once(&config, &app).await }
+            .into_actor(self)
+            .map(|res, act, _ctx| {
+                act.syncing = false;
+                if let Err(err) = res {
+                    log::warn!("git sync error: {}", err);
+                }
+            })
+            .spawn(ctx);
+    }
+
+    async fn fetch(config: &GitSyncConfig) -> anyhow::Result<String> {
+        let dir = config.work_dir.as_path();
+        if !dir.exists() {
+            let dir_str = dir.to_string_lossy();
+            git(
+                None,
+                &[
+                    "clone",
+                    "--bare",
+                    "--single-branch",
+                    &format!("--branch={}", &config.branch),
+                    "--",
+                    &config.repo_url,
+                    &dir_str,
+                ],
+            )
+            .await?;
+        } else {
+            let refspec = format!("+refs/heads/{0}:refs/heads/{0}", &config.branch);
+            git(Some(dir), &["fetch", "--", "origin", &refspec]).await?;
+        }
+        let head = git(
+            Some(dir),
+            &[
+                "rev-parse",
+                "--verify",
+                &format!("refs/heads/{}", &config.branch),
+            ],
+        )
+        .await?;
+        let head = head.trim().to_owned();
+        if !is_commit_id(&head) {
+            return Err(anyhow::anyhow!("invalid git commit: {}", &head));
+        }
+        Ok(head)
+    }
+
+    ///
+    /// 只由主节点同步,避免多个节点重复写入
+    async fn sync_once(config: &GitSyncConfig, app: &Arc<AppShareData>) -> anyhow::Result<()> {
+        if app.raft.current_leader().await != Some(app.sys_config.raft_node_id) {
+            return Ok(());
+        }
+        let head = Self::fetch(config).await?;
+        let dir = Some(config.work_dir.as_path());
+        let last = app
+            .config_read_view
+            .get(&Self::commit_key())
+            .map(|v| v.content.trim().to_owned());
+        if last.as_deref() == Some(head.as_str()) {
+            return Ok(());
+        }
+        //上次同步的提交不存在时(首次同步或历史被改写)全量同步
+        let last = match last.filter(|v| is_commit_id(v)) {
+            Some(commit) => git(dir, &["cat-file", "-e", &format!("{}^{{commit}}", &commit)])
+                .await
+                .ok()
+                .map(|_| commit),
+            None => None,
+        };
+        let changes = match &last {
+            Some(commit) => parse_name_status(
+                &git(
+                    dir,
+                    &[
+                        "diff",
+                        "--no-renames",
+                        "--name-status",
+                        "-z",
+                        commit,
+                        &head,
+                        "--",
+                    ],
+                )
+                .await?,
+            ),
+            None => git(dir, &["ls-tree", "-r", "-z", "--name-only", &head])
+                .await?
+                .split('\0')
+                .filter(|v| !v.is_empty())
+                .map(|v| GitFileChange::Update(v.to_owned()))
+                .collect(),
+        };
+        let mut count = 0;
+        for change in changes {
+            let path = match &change {
+                GitFileChange::Update(path) | GitFileChange::Delete(path) => path,
+            };
+            let key = match config_key_from_path(Path::new(path), config.namespace_from_dir) {
+                Some(key) => key,
+                None => continue,
+            };
+            match change {
+                GitFileChange::Update(path) => {
+                    let content =
+                        git_output(dir, &["show", &format!("{}:{}", &head, &path)]).await?;
+                    let content = match String::from_utf8(content) {
+                        Ok(v) => v,
+                        Err(_) => {
+                            log::warn!("git sync skip non utf-8 file: {}", &path);
+                            continue;
+                        }
+                    };
+                    let md5 = get_md5(&content);
+                    if app
+                        .config_read_view
+                        .get(&key)
+                        .map(|v| v.md5.as_str() == md5)
+                        .unwrap_or(false)
+                    {
+                        continue;
+                    }
+                    app.config_route
+                        .set_config(SetConfigReq::new(key, Arc::new(content)))
+                        .await?;
+                }
+                GitFileChange::Delete(_) => {
+                    if app.config_read_view.get(&key).is_none() {
+                        continue;
+                    }
+                    if let Err(err) = app
+                        .config_route
+                        .del_config(DelConfigReq::new(key.clone()))
+                        .await
+                    {
+                        //仍被其它配置依赖的配置不删除,不影响其它文件同步
+                        if err.downcast_ref::<ConfigInUseError>().is_some() {
+                            log::warn!("git sync skip deleting {}: {}", &key, err);
+                            continue;
+                        }
+                        return Err(err);
+                    }
+                }
+            }
+            count += 1;
+        }
+        app.config_route
+            .set_config(SetConfigReq::new(
+                Self::commit_key(),
+                Arc::new(head.clone()),
+            ))
+            .await?;
+        log::info!("git sync to commit {}, {} configs changed", &head, count);
+        Ok(())
+    }
+}
+
+impl Actor for GitSyncActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        log::info!("GitSyncActor started");
+        self.sync(ctx);
+        ctx.run_interval(
+            Duration::from_secs(self.config.interval_secs.max(1)),
+            |act, ctx| act.sync(ctx),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_git_name_status() {
+        let changes = parse_name_status("M\0dev/DEFAULT_GROUP/a.yaml\0D\0dev/g/b\0A\0c\0");
+        assert_eq!(
+            changes,
+            vec![
+                GitFileChange::Update("dev/DEFAULT_GROUP/a.yaml".to_owned()),
+                GitFileChange::Delete("dev/g/b".to_owned()),
+                GitFileChange::Update("c".to_owned()),
+            ]
+        );
+        assert!(parse_name_status("").is_empty());
+    }
+
+    #[test]
+    fn git_commit_id() {
+        assert!(is_commit_id("3f2a9c1d0e4b5a6978877665544332211aabbccd"));
+        assert!(!is_commit_id("--upload-pack=touch /tmp/x"));
+        assert!(!is_commit_id(""));
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+use crate::grpc::PayloadUtils;
+use crate::raft::cluster::model::{RouterRequest, RouterResponse};
+
+use super::config_stats::StatEntry;
+use super::core::{ConfigCmd, ConfigKey, ConfigResult};
+use super::utils::param_utils::InvalidKeyError;
+use super::ConfigUtils;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+/// 统计校验错误率的时间窗口
+const VALIDATION_WINDOW_MS: i64 = DAY_MS;
+/// 超过该天数未读取时staleness扣分达到上限
+const STALE_MAX_DAYS: i64 = 30;
+const STALE_MAX_PENALTY: f64 = 40.0;
+const NO_LISTENER_PENALTY: f64 = 20.0;
+const VALIDATION_ERROR_MAX_PENALTY: f64 = 30.0;
+const PENDING_CHANGE_PENALTY: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ValidationCounter {
+    window_start: i64,
+    total: u64,
+    failed: u64,
+}
+
+///
+/// 按配置统计最近一天内写入校验的次数与失败次数,包括参数、内容格式、引用及外部webhook校验;
+/// 只保存在当前节点内存中
+#[derive(Default)]
+pub struct ConfigValidationStats {
+    counters: Mutex<HashMap<ConfigKey, ValidationCounter>>,
+}
+
+impl ConfigValidationStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, key: &ConfigKey, success: bool, now: i64) {
+        if let Ok(mut counters) = self.counters.lock() {
+            let counter = counters.entry(key.clone()).or_default();
+            if now - counter.window_start >= VALIDATION_WINDOW_MS {
+                *counter = ValidationCounter {
+                    window_start: now,
+                    ..Default::default()
+                };
+            }
+            counter.total += 1;
+            if !success {
+                counter.failed += 1;
+            }
+        }
+    }
+
+    ///
+    /// 记录写入参数校验失败;dataId、group非法时无法对应到配置,不计入
+    pub fn record_param_error(
+        &self,
+        data_id: &Option<String>,
+        group: &Option<String>,
+        tenant: &Option<String>,
+        err: &anyhow::Error,
+        now: i64,
+    ) {
+        if err.downcast_ref::<InvalidKeyError>().is_some() {
+            return;
+        }
+        //与写入时的tenant处理一致,public即默认命名空间
+        let key = ConfigKey::new(
+            data_id.as_deref().unwrap_or_default(),
+            group.as_deref().unwrap_or_default(),
+            &ConfigUtils::default_tenant(tenant.clone().unwrap_or_default()),
+        );
+        self.record(&key, false, now);
+    }
+
+    ///
+    /// 时间窗口内的校验失败率,没有校验记录时为0
+    pub fn error_rate(&self, key: &ConfigKey, now: i64) -> f64 {
+        let counter = match self.counters.lock() {
+            Ok(counters) => counters.get(key).cloned().unwrap_or_default(),
+            Err(_) => return 0f64,
+        };
+        if counter.total == 0 || now - counter.window_start >= VALIDATION_WINDOW_MS {
+            return 0f64;
+        }
+        counter.failed as f64 / counter.total as f64
+    }
+}
+
+///
+/// 本节点上指定命名空间内各配置的监听数,含长链接订阅与长轮询等待;只返回有监听的配置
+pub async fn local_listener_counts(
+    app: &AppShareData,
+    tenants: &[Arc<String>],
+) -> anyhow::Result<Vec<(ConfigKey, usize)>> {
+    let mut counts: HashMap<ConfigKey, usize> = HashMap::new();
+    if let ConfigResult::ClientSubscribes(subscribes) = app
+        .send_config_cmd(ConfigCmd::QueryClientSubscribes)
+        .await?
+    {
+        for key in subscribes.into_values().flatten() {
+            if tenants.contains(&key.tenant) {
+                *counts.entry(key).or_default() += 1;
+            }
+        }
+    }
+    for tenant in tenants {
+        if let ConfigResult::ConfigList(configs) = app
+            .send_config_cmd(ConfigCmd::ListAll(tenant.clone()))
+            .await?
+        {
+            for (key, _) in configs {
+                let count = app.config_watch.listener_count(&key);
+                if count > 0 {
+                    *counts.entry(key).or_default() += count;
+                }
+            }
+        }
+    }
+    Ok(counts.into_iter().collect())
+}
+
+async fn send_listener_counts_route(
+    app: &AppShareData,
+    addr: Arc<String>,
+    req: &RouterRequest,
+) -> anyhow::Result<Vec<(ConfigKey, usize)>> {
+    let request = serde_json::to_string(req)?;
+    let payload = PayloadUtils::build_payload("RaftRouteRequest", request);
+    let resp_payload = app.cluster_sender.send_request(addr, payload).await?;
+    let body_vec = resp_payload.body.unwrap_or_default().value;
+    let resp: RouterResponse = serde_json::from_slice(&body_vec)?;
+    match resp {
+        RouterResponse::ConfigListenerCountsResult { counts } => Ok(counts),
+        _ => Err(anyhow::anyhow!("response type is error!")),
+    }
+}
+
+///
+/// 汇总集群所有节点上的监听数;客户端只连接其中一个节点,单节点的监听数不能代表配置是否被使用
+pub async fn cluster_listener_counts(
+    app: &AppShareData,
+    tenants: Vec<Arc<String>>,
+) -> anyhow::Result<HashMap<ConfigKey, usize>> {
+    let mut counts: HashMap<ConfigKey, usize> = local_listener_counts(app, &tenants)
+        .await?
+        .into_iter()
+        .collect();
+    let nodes = app
+        .naming_node_manage
+        .get_other_valid_nodes()
+        .await
+        .unwrap_or_default();
+    let req = RouterRequest::ConfigListenerCounts { tenants };
+    let futures = nodes.into_iter().map(|node| {
+        let req = &req;
+        async move {
+            let result = send_listener_counts_route(app, node.addr.clone(), req).await;
+            (node.id, result)
+        }
+    });
+    for (node_id, result) in futures_util::future::join_all(futures).await {
+        match result {
+            Ok(list) => merge_listener_counts(&mut counts, list),
+            Err(err) => log::warn!("query listener counts from node {} error,{}", node_id, err),
+        }
+    }
+    Ok(counts)
+}
+
+fn merge_listener_counts(counts: &mut HashMap<ConfigKey, usize>, list: Vec<(ConfigKey, usize)>) {
+    for (key, count) in list {
+        *counts.entry(key).or_default() += count;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigHealthItem {
+    pub tenant: Arc<String>,
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    pub score: u8,
+    pub last_read_time: i64,
+    pub listener_count: usize,
+    pub validation_error_rate: f64,
+    pub pending_change: bool,
+    pub warnings: Vec<String>,
+}
+
+impl ConfigHealthItem {
+    ///
+    /// 健康分0~100,按最近读取时间、监听数、校验错误率及是否有审批中的变更扣分
+    pub fn new(
+        key: &ConfigKey,
+        stat: &StatEntry,
+        listener_count: usize,
+        validation_error_rate: f64,
+        pending_change: bool,
+        now: i64,
+    ) -> Self {
+        let mut score = 100f64;
+        let mut warnings = vec![];
+        let stale_days = (now - stat.last_read_ms).max(0) / DAY_MS;
+        if stale_days > 0 {
+            score -=
+                STALE_MAX_PENALTY * stale_days.min(STALE_MAX_DAYS) as f64 / STALE_MAX_DAYS as f64;
+            warnings.push(format!("not read for {} days", stale_days));
+        }
+        if listener_count == 0 {
+            score -= NO_LISTENER_PENALTY;
+            warnings.push("no active listeners".to_owned());
+        }
+        if validation_error_rate > 0f64 {
+            score -= VALIDATION_ERROR_MAX_PENALTY * validation_error_rate.min(1f64);
+            warnings.push(format!(
+                "validation error rate {:.0}%",
+                validation_error_rate * 100f64
+            ));
+        }
+        if pending_change {
+            score -= PENDING_CHANGE_PENALTY;
+            warnings.push("has a change in approval".to_owned());
+        }
+        Self {
+            tenant: key.tenant.clone(),
+            group: key.group.clone(),
+            data_id: key.data_id.clone(),
+            score: score.round().clamp(0f64, 100f64) as u8,
+            last_read_time: stat.last_read_ms,
+            listener_count,
+            validation_error_rate,
+            pending_change,
+            warnings,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn health_score() {
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let now = 100 * DAY_MS;
+        let stats = ConfigValidationStats::new();
+        stats.record(&key, true, now);
+        stats.record(&key, false, now);
+        assert_eq!(stats.error_rate(&key, now), 0.5);
+        //超过时间窗口后重新统计
+        assert_eq!(stats.error_rate(&key, now + DAY_MS), 0f64);
+        //参数校验失败计入对应配置,dataId非法时不计入
+        let other = ConfigKey::new("b", "DEFAULT_GROUP", "");
+        stats.record_param_error(
+            &Some("b".to_owned()),
+            &Some("DEFAULT_GROUP".to_owned()),
+            &Some("public".to_owned()),
+            &anyhow::anyhow!("content is blank"),
+            now,
+        );
+        assert_eq!(stats.error_rate(&other, now), 1f64);
+        stats.record_param_error(
+            &Some("../b".to_owned()),
+            &None,
+            &None,
+            &InvalidKeyError {
+                name: "dataId",
+                reason: "contains illegal characters",
+            }
+            .into(),
+            now,
+        );
+        assert_eq!(stats.counters.lock().unwrap().len(), 2);
+
+        let stat = StatEntry {
+            last_read_ms: now,
+            ..Default::default()
+        };
+        let item = ConfigHealthItem::new(&key, &stat, 2, 0f64, false, now);
+        assert_eq!(item.score, 100);
+        assert!(item.warnings.is_empty());
+
+        let stat = StatEntry {
+            last_read_ms: now - 15 * DAY_MS,
+            ..Default::default()
+        };
+        let item = ConfigHealthItem::new(&key, &stat, 0, 0.5, true, now);
+        assert_eq!(item.score, 100 - 20 - 20 - 15 - 10);
+        assert_eq!(item.warnings.len(), 4);
+    }
+
+    #[test]
+    fn merge_node_listener_counts() {
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let b = ConfigKey::new("b", "DEFAULT_GROUP", "");
+        let mut counts = HashMap::from([(a.clone(), 1)]);
+        merge_listener_counts(&mut counts, vec![(a.clone(), 2), (b.clone(), 1)]);
+        merge_listener_counts(&mut counts, vec![(b.clone(), 3)]);
+        assert_eq!(counts.get(&a), Some(&3));
+        assert_eq!(counts.get(&b), Some(&4));
+        //跨节点传输的格式
+        let req = RouterRequest::ConfigListenerCounts {
+            tenants: vec![Arc::new("dev".to_owned())],
+        };
+        let req: RouterRequest =
+            serde_json::from_str(&serde_json::to_string(&req).unwrap()).unwrap();
+        assert!(
+            matches!(req, RouterRequest::ConfigListenerCounts { tenants } if tenants.len() == 1)
+        );
+    }
+}
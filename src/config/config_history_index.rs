@@ -0,0 +1,286 @@
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::config_event::ConfigChangeType;
+use super::core::ConfigKey;
+
+/// 历史记录内容预览的最大字符数
+pub const HISTORY_PREVIEW_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Default)]
+pub struct ConfigHistorySearchParam {
+    pub tenant: Arc<String>,
+    pub group: Option<String>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub operator: Option<String>,
+    pub change_type: Option<ConfigChangeType>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigHistorySearchItem {
+    pub id: u64,
+    pub tenant: Arc<String>,
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    //SET或DELETE
+    pub change_type: String,
+    pub op_user: Option<Arc<String>>,
+    pub modified_time: i64,
+    pub content_preview: Option<String>,
+}
+
+///
+/// 删除记录,删除后配置及其历史版本不再保留,需随raft镜像与单机预写日志单独持久化
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigDeleteHistory {
+    pub key: ConfigKey,
+    pub op_time: i64,
+    pub op_user: Option<Arc<String>>,
+}
+
+#[derive(Debug, Clone)]
+struct HistoryIndexEntry {
+    key: ConfigKey,
+    change_type: ConfigChangeType,
+    op_user: Option<Arc<String>>,
+    preview: Option<String>,
+}
+
+///
+/// 按(命名空间,修改时间)索引的变更记录,用于按时间范围审计;
+/// 写入记录与配置历史版本同步裁剪,删除记录在加载镜像与日志时重建
+#[derive(Debug, Default)]
+pub struct ConfigHistoryIndex {
+    entries: BTreeMap<(Arc<String>, i64, u64), HistoryIndexEntry>,
+    //删除记录没有历史id,使用本地递增序号
+    delete_seq: u64,
+}
+
+pub fn content_preview(content: &str) -> String {
+    content.chars().take(HISTORY_PREVIEW_CHARS).collect()
+}
+
+impl ConfigHistoryIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert_set(
+        &mut self,
+        key: &ConfigKey,
+        id: u64,
+        modified_time: i64,
+        op_user: Option<Arc<String>>,
+        content: &str,
+    ) {
+        self.entries.insert(
+            (key.tenant.clone(), modified_time, id),
+            HistoryIndexEntry {
+                key: key.clone(),
+                change_type: ConfigChangeType::Set,
+                op_user,
+                preview: Some(content_preview(content)),
+            },
+        );
+    }
+
+    pub fn insert_delete(
+        &mut self,
+        key: &ConfigKey,
+        modified_time: i64,
+        op_user: Option<Arc<String>>,
+    ) {
+        //镜像与日志中可能包含同一条删除记录
+        let range = (
+            Bound::Included((key.tenant.clone(), modified_time, 0)),
+            Bound::Included((key.tenant.clone(), modified_time, u64::MAX)),
+        );
+        if self
+            .entries
+            .range(range)
+            .any(|(_, v)| v.change_type == ConfigChangeType::Remove && &v.key == key)
+        {
+            return;
+        }
+        self.delete_seq += 1;
+        //与历史id区分,避免同一时刻的记录相互覆盖
+        let id = u64::MAX - self.delete_seq;
+        self.entries.insert(
+            (key.tenant.clone(), modified_time, id),
+            HistoryIndexEntry {
+                key: key.clone(),
+                change_type: ConfigChangeType::Remove,
+                op_user,
+                preview: None,
+            },
+        );
+    }
+
+    pub fn delete_histories(&self) -> Vec<ConfigDeleteHistory> {
+        self.entries
+            .iter()
+            .filter(|(_, v)| v.change_type == ConfigChangeType::Remove)
+            .map(|((_, modified_time, _), v)| ConfigDeleteHistory {
+                key: v.key.clone(),
+                op_time: *modified_time,
+                op_user: v.op_user.clone(),
+            })
+            .collect()
+    }
+
+    ///
+    /// 移除已被裁剪的历史版本
+    pub fn remove(&mut self, tenant: &Arc<String>, modified_time: i64, id: u64) {
+        self.entries.remove(&(tenant.clone(), modified_time, id));
+    }
+
+    ///
+    /// 移除配置的全部写入记录,重新加载配置时使用
+    pub fn remove_key_sets(&mut self, key: &ConfigKey) {
+        let range = (
+            Bound::Included((key.tenant.clone(), i64::MIN, 0)),
+            Bound::Included((key.tenant.clone(), i64::MAX, u64::MAX)),
+        );
+        let ids: Vec<(Arc<String>, i64, u64)> = self
+            .entries
+            .range(range)
+            .filter(|(_, v)| v.change_type == ConfigChangeType::Set && &v.key == key)
+            .map(|(k, _)| k.clone())
+            .collect();
+        for id in ids {
+            self.entries.remove(&id);
+        }
+    }
+
+    ///
+    /// 按修改时间倒序分页查询
+    pub fn search(
+        &self,
+        param: &ConfigHistorySearchParam,
+    ) -> (usize, Vec<ConfigHistorySearchItem>) {
+        let range = (
+            Bound::Included((param.tenant.clone(), param.from.unwrap_or(i64::MIN), 0)),
+            Bound::Included((param.tenant.clone(), param.to.unwrap_or(i64::MAX), u64::MAX)),
+        );
+        let mut total = 0;
+        let mut list = Vec::new();
+        for ((_, modified_time, id), entry) in self.entries.range(range).rev() {
+            if let Some(change_type) = &param.change_type {
+                if change_type != &entry.change_type {
+                    continue;
+                }
+            }
+            if let Some(group) = &param.group {
+                if entry.key.group.as_str() != group.as_str() {
+                    continue;
+                }
+            }
+            if let Some(operator) = &param.operator {
+                if entry.op_user.as_deref().map(|v| v.as_str()) != Some(operator.as_str()) {
+                    continue;
+                }
+            }
+            if total >= param.offset && list.len() < param.limit {
+                list.push(ConfigHistorySearchItem {
+                    id: if entry.change_type == ConfigChangeType::Set {
+                        *id
+                    } else {
+                        0
+                    },
+                    tenant: entry.key.tenant.clone(),
+                    group: entry.key.group.clone(),
+                    data_id: entry.key.data_id.clone(),
+                    change_type: match entry.change_type {
+                        ConfigChangeType::Set => "SET".to_owned(),
+                        ConfigChangeType::Remove => "DELETE".to_owned(),
+                    },
+                    op_user: entry.op_user.clone(),
+                    modified_time: *modified_time,
+                    content_preview: entry.preview.clone(),
+                });
+            }
+            total += 1;
+        }
+        (total, list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_history_index() {
+        let mut index = ConfigHistoryIndex::new();
+        let alice = Some(Arc::new("alice".to_owned()));
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "prod");
+        let b = ConfigKey::new("b", "DEFAULT_GROUP", "prod");
+        index.insert_set(&a, 1, 100, alice.clone(), &"x".repeat(300));
+        index.insert_set(&b, 2, 200, None, "b");
+        index.insert_set(
+            &ConfigKey::new("a", "DEFAULT_GROUP", "dev"),
+            3,
+            300,
+            None,
+            "c",
+        );
+        index.insert_delete(&a, 400, alice.clone());
+
+        let param = ConfigHistorySearchParam {
+            tenant: Arc::new("prod".to_owned()),
+            limit: 10,
+            ..Default::default()
+        };
+        let (total, list) = index.search(&param);
+        assert_eq!(total, 3);
+        assert_eq!(list[0].change_type, "DELETE");
+        assert_eq!(
+            list[2].content_preview.as_ref().unwrap().len(),
+            HISTORY_PREVIEW_CHARS
+        );
+
+        let (total, list) = index.search(&ConfigHistorySearchParam {
+            from: Some(150),
+            to: Some(400),
+            change_type: Some(ConfigChangeType::Set),
+            ..param.clone()
+        });
+        assert_eq!(total, 1);
+        assert_eq!(list[0].data_id.as_str(), "b");
+
+        let (total, _) = index.search(&ConfigHistorySearchParam {
+            group: Some("OTHER_GROUP".to_owned()),
+            ..param.clone()
+        });
+        assert_eq!(total, 0);
+
+        let (total, list) = index.search(&ConfigHistorySearchParam {
+            operator: Some("alice".to_owned()),
+            offset: 1,
+            ..param.clone()
+        });
+        assert_eq!(total, 2);
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].modified_time, 100);
+
+        index.remove_key_sets(&a);
+        assert_eq!(index.search(&param).0, 2);
+        index.remove(&b.tenant, 200, 2);
+        assert_eq!(index.search(&param).0, 1);
+
+        //加载镜像与日志时重复的删除记录只保留一条
+        let deletes = index.delete_histories();
+        assert_eq!(deletes.len(), 1);
+        assert_eq!(deletes[0].op_user, alice);
+        index.insert_delete(&a, 400, alice);
+        assert_eq!(index.search(&param).0, 1);
+    }
+}
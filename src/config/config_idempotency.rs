@@ -0,0 +1,230 @@
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crypto::digest::Digest;
+use lru::LruCache;
+
+use super::core::ConfigKey;
+
+pub const IDEMPOTENCY_CACHE_CAPACITY: usize = 10000;
+pub const IDEMPOTENCY_TTL_SECONDS: u64 = 60;
+
+#[derive(Debug, Clone)]
+pub struct IdempotentResponse {
+    pub status: u16,
+    pub content_type: String,
+    pub body: String,
+    pub created_at: Instant,
+}
+
+impl IdempotentResponse {
+    pub fn new(status: u16, content_type: &str, body: String) -> Self {
+        Self {
+            status,
+            content_type: content_type.to_owned(),
+            body,
+            created_at: Instant::now(),
+        }
+    }
+}
+
+enum IdempotentEntry {
+    //请求执行中
+    Pending(Instant),
+    Done(IdempotentResponse),
+}
+
+impl IdempotentEntry {
+    fn created_at(&self) -> Instant {
+        match self {
+            IdempotentEntry::Pending(v) => *v,
+            IdempotentEntry::Done(v) => v.created_at,
+        }
+    }
+}
+
+pub enum IdempotencyState<'a> {
+    //已执行过,直接返回原响应
+    Cached(IdempotentResponse),
+    //相同请求正在执行
+    Pending,
+    //已占用,由调用方执行请求
+    Reserved(IdempotencyReservation<'a>),
+}
+
+///
+/// 占用的缓存项;complete后记录响应,未complete就释放时移除占用,允许重试
+pub struct IdempotencyReservation<'a> {
+    cache: &'a ConfigIdempotencyCache,
+    key: Option<String>,
+}
+
+impl<'a> IdempotencyReservation<'a> {
+    pub fn complete(mut self, response: IdempotentResponse) {
+        if let Some(key) = self.key.take() {
+            self.cache.put(key, response);
+        }
+    }
+}
+
+impl<'a> Drop for IdempotencyReservation<'a> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.cache.remove(&key);
+        }
+    }
+}
+
+///
+/// 记录带Idempotency-Key的写请求响应,重试请求在有效期内直接返回原响应,不再发起raft提案;
+/// 缓存按 Idempotency-Key、配置key与请求内容 区分,执行前先占用,并发的重复请求不会重复执行
+pub struct ConfigIdempotencyCache {
+    cache: Mutex<LruCache<String, IdempotentEntry>>,
+    ttl: Duration,
+}
+
+impl Default for ConfigIdempotencyCache {
+    fn default() -> Self {
+        Self::new(IDEMPOTENCY_CACHE_CAPACITY, IDEMPOTENCY_TTL_SECONDS)
+    }
+}
+
+impl ConfigIdempotencyCache {
+    pub fn new(capacity: usize, ttl_seconds: u64) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::new(1).unwrap());
+        Self {
+            cache: Mutex::new(LruCache::new(capacity)),
+            ttl: Duration::from_secs(ttl_seconds),
+        }
+    }
+
+    ///
+    /// 请求内容的摘要,包含query参数与请求体
+    pub fn request_digest(query: &str, body: &[u8]) -> String {
+        let mut m = crypto::md5::Md5::new();
+        m.input(query.as_bytes());
+        m.input(b"\n");
+        m.input(body);
+        m.result_str()
+    }
+
+    pub fn build_key(
+        idempotency_key: &str,
+        config_key: &ConfigKey,
+        request_digest: &str,
+    ) -> String {
+        format!(
+            "{}\n{}\n{}",
+            idempotency_key,
+            config_key.build_key(),
+            request_digest
+        )
+    }
+
+    pub fn reserve(&self, key: String) -> IdempotencyState<'_> {
+        let mut cache = match self.cache.lock() {
+            Ok(v) => v,
+            Err(_) => {
+                return IdempotencyState::Reserved(IdempotencyReservation {
+                    cache: self,
+                    key: None,
+                })
+            }
+        };
+        match cache.get(&key) {
+            Some(v) if v.created_at().elapsed() <= self.ttl => {
+                return match v {
+                    IdempotentEntry::Pending(_) => IdempotencyState::Pending,
+                    IdempotentEntry::Done(v) => IdempotencyState::Cached(v.clone()),
+                };
+            }
+            _ => {}
+        }
+        cache.put(key.clone(), IdempotentEntry::Pending(Instant::now()));
+        IdempotencyState::Reserved(IdempotencyReservation {
+            cache: self,
+            key: Some(key),
+        })
+    }
+
+    fn put(&self, key: String, response: IdempotentResponse) {
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.put(key, IdempotentEntry::Done(response));
+        }
+    }
+
+    fn remove(&self, key: &str) {
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.pop(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reserve(cache: &ConfigIdempotencyCache, key: &str) -> Option<IdempotentResponse> {
+        match cache.reserve(key.to_owned()) {
+            IdempotencyState::Cached(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn idempotent_response() {
+        let cache = ConfigIdempotencyCache::new(10, 60);
+        match cache.reserve("k1".to_owned()) {
+            IdempotencyState::Reserved(reservation) => {
+                //执行中的重复请求不会再次执行
+                assert!(matches!(
+                    cache.reserve("k1".to_owned()),
+                    IdempotencyState::Pending
+                ));
+                reservation.complete(IdempotentResponse::new(
+                    200,
+                    "text/html; charset=utf-8",
+                    "true".to_owned(),
+                ));
+            }
+            _ => panic!("k1 should be reserved"),
+        }
+        let v = reserve(&cache, "k1").unwrap();
+        assert_eq!(v.status, 200);
+        assert_eq!(v.body, "true");
+
+        //未完成就释放时允许重试
+        match cache.reserve("k2".to_owned()) {
+            IdempotencyState::Reserved(reservation) => drop(reservation),
+            _ => panic!("k2 should be reserved"),
+        }
+        assert!(matches!(
+            cache.reserve("k2".to_owned()),
+            IdempotencyState::Reserved(_)
+        ));
+
+        let cache = ConfigIdempotencyCache::new(10, 0);
+        let mut v = IdempotentResponse::new(200, "text/html; charset=utf-8", "true".to_owned());
+        v.created_at -= Duration::from_millis(10);
+        cache.put("k1".to_owned(), v);
+        assert!(reserve(&cache, "k1").is_none());
+    }
+
+    #[test]
+    fn idempotency_key_scope() {
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let b = ConfigKey::new("b", "DEFAULT_GROUP", "");
+        let digest = ConfigIdempotencyCache::request_digest("", b"content=1");
+        let k1 = ConfigIdempotencyCache::build_key("id1", &a, &digest);
+        assert_eq!(k1, ConfigIdempotencyCache::build_key("id1", &a, &digest));
+        assert_ne!(k1, ConfigIdempotencyCache::build_key("id1", &b, &digest));
+        assert_ne!(k1, ConfigIdempotencyCache::build_key("id2", &a, &digest));
+        let digest2 = ConfigIdempotencyCache::request_digest("", b"content=2");
+        assert_ne!(digest, digest2);
+        assert_ne!(
+            digest,
+            ConfigIdempotencyCache::request_digest("type=json", b"content=1")
+        );
+    }
+}
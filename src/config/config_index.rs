@@ -116,6 +116,8 @@ impl ConfigIndex {
 pub struct TenantIndex {
     pub tenant_group: BTreeMap<Arc<String>, ConfigIndex>,
     pub size: usize,
+    //(tenant,小写dataId)到配置,用于按dataId前缀查询
+    data_id_prefix: BTreeMap<(Arc<String>, String), Vec<ConfigKey>>,
 }
 
 impl TenantIndex {
@@ -138,6 +140,7 @@ impl TenantIndex {
         data_id: Arc<String>,
     ) -> bool {
         let mut result = false;
+        let key = ConfigKey::new_by_arc(data_id.clone(), group.clone(), tenant.clone());
         if let Some(config_index) = self.tenant_group.get_mut(&tenant) {
             if config_index.insert_config(group, data_id) {
                 self.size += 1;
@@ -151,6 +154,12 @@ impl TenantIndex {
             }
             self.tenant_group.insert(tenant, config_index);
         }
+        if result {
+            self.data_id_prefix
+                .entry((key.tenant.clone(), key.data_id.to_lowercase()))
+                .or_default()
+                .push(key);
+        }
         result
     }
 
@@ -171,9 +180,29 @@ impl TenantIndex {
                 self.tenant_group.remove(tenant);
             }
         }
+        if result {
+            let prefix_key = (tenant.clone(), data_id.to_lowercase());
+            if let Some(keys) = self.data_id_prefix.get_mut(&prefix_key) {
+                keys.retain(|v| &v.group != group || &v.data_id != data_id);
+                if keys.is_empty() {
+                    self.data_id_prefix.remove(&prefix_key);
+                }
+            }
+        }
         result
     }
 
+    ///
+    /// 查询命名空间下dataId以prefix开头的配置,不区分大小写
+    pub fn query_by_data_id_prefix(&self, tenant: &Arc<String>, prefix: &str) -> Vec<ConfigKey> {
+        let prefix = prefix.to_lowercase();
+        self.data_id_prefix
+            .range((tenant.clone(), prefix.clone())..)
+            .take_while(|((t, data_id), _)| t == tenant && data_id.starts_with(&prefix))
+            .flat_map(|(_, keys)| keys.iter().cloned())
+            .collect()
+    }
+
     pub fn query_config_page(&self, param: &ConfigQueryParam) -> (usize, Vec<ConfigKey>) {
         let mut rlist = vec![];
         let mut size = 0;
@@ -260,3 +289,22 @@ fn query_service() {
     assert!(size == 0);
     assert!(list.is_empty());
 }
+
+#[test]
+fn query_by_data_id_prefix() {
+    let mut index = TenantIndex::new();
+    let tenant = Arc::new("dev".to_owned());
+    index.insert_config(ConfigKey::new("App.yaml", "1", "dev"));
+    index.insert_config(ConfigKey::new("app.yaml", "2", "dev"));
+    index.insert_config(ConfigKey::new("application.properties", "1", "dev"));
+    index.insert_config(ConfigKey::new("apb.yaml", "1", "dev"));
+    index.insert_config(ConfigKey::new("app.yaml", "1", "test"));
+    assert_eq!(index.query_by_data_id_prefix(&tenant, "APP").len(), 3);
+    assert_eq!(index.query_by_data_id_prefix(&tenant, "app.").len(), 2);
+    index.remove_config(&ConfigKey::new("App.yaml", "1", "dev"));
+    let list = index.query_by_data_id_prefix(&tenant, "app.");
+    assert_eq!(list, vec![ConfigKey::new("app.yaml", "2", "dev")]);
+    assert!(index
+        .query_by_data_id_prefix(&Arc::new("".to_owned()), "app")
+        .is_empty());
+}
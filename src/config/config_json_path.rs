@@ -0,0 +1,48 @@
+use serde_json::Value;
+
+///
+/// 读取json配置时按jsonPath只返回匹配的部分;
+/// 匹配单个节点时返回该节点,匹配多个节点时返回数组,未匹配时返回None
+pub fn select_json_path(content: &str, json_path: &str) -> anyhow::Result<Option<String>> {
+    let json: Value = serde_json::from_str(content)
+        .map_err(|err| anyhow::anyhow!("config content is not json: {}", err))?;
+    let mut nodes = jsonpath_lib::select(&json, json_path)?;
+    let value = match nodes.len() {
+        0 => return Ok(None),
+        1 => nodes.remove(0).clone(),
+        _ => Value::Array(nodes.into_iter().cloned().collect()),
+    };
+    Ok(Some(serde_json::to_string(&value)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select_sub_tree() {
+        let content =
+            r#"{"database":{"host":"10.0.0.1","port":3306},"servers":[{"name":"a"},{"name":"b"}]}"#;
+        assert_eq!(
+            select_json_path(content, "$.database").unwrap().unwrap(),
+            r#"{"host":"10.0.0.1","port":3306}"#
+        );
+        assert_eq!(
+            select_json_path(content, "$.servers[*].name")
+                .unwrap()
+                .unwrap(),
+            r#"["a","b"]"#
+        );
+        assert!(select_json_path(content, "$.cache").unwrap().is_none());
+        assert!(select_json_path("a=1", "$.a").is_err());
+    }
+
+    #[test]
+    fn select_keeps_key_order() {
+        let content = r#"{"database":{"port":3306,"host":"10.0.0.1","auth":{"user":"u"}}}"#;
+        assert_eq!(
+            select_json_path(content, "$.database").unwrap().unwrap(),
+            r#"{"port":3306,"host":"10.0.0.1","auth":{"user":"u"}}"#
+        );
+    }
+}
@@ -0,0 +1,280 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "kafka")]
+use rdkafka::config::ClientConfig;
+#[cfg(feature = "kafka")]
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Semaphore;
+
+use super::config_event::ConfigChangeEvent;
+use crate::common::appdata::AppShareData;
+use crate::now_millis_i64;
+
+const RETRY_BASE_DELAY_MS: u64 = 100;
+const RETRY_MAX_DELAY_MS: u64 = 10_000;
+#[cfg(feature = "kafka")]
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+//同时推送中的消息数上限
+const MAX_IN_FLIGHT: usize = 64;
+//缓存主节点检查结果的时间,避免每个事件都查询raft状态
+const LEADER_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const LAGGED_MESSAGE_KEY: &str = "__lagged__";
+
+///
+/// 配置变更推送到kafka的设置
+#[derive(Debug, Clone)]
+pub struct KafkaConfig {
+    pub brokers: String,
+    pub topic: String,
+    pub max_retries: u32,
+    //超过重试次数后写入的主题
+    pub dead_letter_topic: String,
+}
+
+impl KafkaConfig {
+    pub fn from_env() -> Option<Self> {
+        let enabled = std::env::var("RNACOS_CONFIG_KAFKA_ENABLED")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let brokers = std::env::var("RNACOS_CONFIG_KAFKA_BROKERS").unwrap_or_default();
+        if !enabled || brokers.is_empty() {
+            return None;
+        }
+        let topic = std::env::var("RNACOS_CONFIG_KAFKA_TOPIC")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .unwrap_or("rnacos.config.changes".to_owned());
+        let max_retries = std::env::var("RNACOS_CONFIG_KAFKA_MAX_RETRIES")
+            .unwrap_or("5".to_owned())
+            .parse()
+            .unwrap_or(5);
+        let dead_letter_topic = std::env::var("RNACOS_CONFIG_KAFKA_DEAD_LETTER_TOPIC")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .unwrap_or(format!("{}.dlq", &topic));
+        Some(Self {
+            brokers,
+            topic,
+            max_retries,
+            dead_letter_topic,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChangeMessage {
+    pub data_id: Arc<String>,
+    pub group: Arc<String>,
+    pub tenant: Arc<String>,
+    //删除配置时为空
+    pub md5: Option<Arc<String>>,
+    pub change_time: i64,
+    pub operator: Option<Arc<String>>,
+}
+
+impl From<&ConfigChangeEvent> for ConfigChangeMessage {
+    fn from(event: &ConfigChangeEvent) -> Self {
+        Self {
+            data_id: event.key.data_id.clone(),
+            group: event.key.group.clone(),
+            tenant: event.key.tenant.clone(),
+            md5: event.md5.clone(),
+            change_time: event.op_time,
+            operator: event.op_user.clone(),
+        }
+    }
+}
+
+///
+/// 事件总线积压导致丢弃事件时写入死信主题的告警,消费方需按全量配置重新同步
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChangeLaggedMessage {
+    pub dropped: u64,
+    pub time: i64,
+}
+
+///
+/// 第n次重试前的等待时间,按指数增长且不超过上限
+fn retry_delay(retry: u32) -> Duration {
+    let delay = RETRY_BASE_DELAY_MS.saturating_mul(1u64 << retry.min(16));
+    Duration::from_millis(delay.min(RETRY_MAX_DELAY_MS))
+}
+
+///
+/// 订阅配置变更事件并写入kafka;集群中只由主节点推送,避免重复消息。
+/// 消息并发推送,同一配置的消息不保证顺序,消费方按changeTime判断新旧
+pub struct ConfigKafkaPublisher {
+    config: KafkaConfig,
+    #[cfg(feature = "kafka")]
+    producer: FutureProducer,
+}
+
+impl ConfigKafkaPublisher {
+    #[cfg(feature = "kafka")]
+    pub fn new(config: KafkaConfig) -> anyhow::Result<Self> {
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("message.timeout.ms", "5000")
+            .create()?;
+        Ok(Self { config, producer })
+    }
+
+    #[cfg(not(feature = "kafka"))]
+    pub fn new(_config: KafkaConfig) -> anyhow::Result<Self> {
+        Err(anyhow::anyhow!(
+            "RNACOS_CONFIG_KAFKA_ENABLED requires rnacos built with the kafka feature"
+        ))
+    }
+
+    #[cfg(feature = "kafka")]
+    async fn send(&self, topic: &str, key: &str, payload: &str) -> anyhow::Result<()> {
+        let record = FutureRecord::to(topic).key(key).payload(payload);
+        match self.producer.send(record, SEND_TIMEOUT).await {
+            Ok(_) => Ok(()),
+            Err((err, _)) => Err(anyhow::anyhow!("{}", err)),
+        }
+    }
+
+    #[cfg(not(feature = "kafka"))]
+    async fn send(&self, _topic: &str, _key: &str, _payload: &str) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("kafka feature is not enabled"))
+    }
+
+    ///
+    /// 按指数退避重试,超过重试次数后写入死信主题
+    async fn publish(&self, event: &ConfigChangeEvent) {
+        let key = event.key.build_key();
+        let payload = match serde_json::to_string(&ConfigChangeMessage::from(event)) {
+            Ok(v) => v,
+            Err(err) => {
+                log::error!("serialize config change message error: {}", err);
+                return;
+            }
+        };
+        let mut retry = 0;
+        loop {
+            match self.send(&self.config.topic, &key, &payload).await {
+                Ok(_) => return,
+                Err(err) if retry < self.config.max_retries => {
+                    log::warn!("publish config change {} to kafka error: {}", &key, err);
+                    tokio::time::sleep(retry_delay(retry)).await;
+                    retry += 1;
+                }
+                Err(err) => {
+                    log::error!(
+                        "publish config change {} to kafka failed after {} retries: {}",
+                        &key,
+                        retry,
+                        err
+                    );
+                    break;
+                }
+            }
+        }
+        self.send_dead_letter(&key, &payload).await;
+    }
+
+    async fn send_dead_letter(&self, key: &str, payload: &str) {
+        if let Err(err) = self
+            .send(&self.config.dead_letter_topic, key, payload)
+            .await
+        {
+            log::error!(
+                "publish config change {} to kafka dead letter topic error: {}",
+                key,
+                err
+            );
+        }
+    }
+
+    ///
+    /// 丢弃的事件无法恢复具体内容,写入一条告警到死信主题
+    async fn publish_lagged(&self, dropped: u64) {
+        let message = ConfigChangeLaggedMessage {
+            dropped,
+            time: now_millis_i64(),
+        };
+        if let Ok(payload) = serde_json::to_string(&message) {
+            self.send_dead_letter(LAGGED_MESSAGE_KEY, &payload).await;
+        }
+    }
+
+    pub async fn run(self: Arc<Self>, app: Arc<AppShareData>) {
+        let mut receiver = app.config_event_bus.subscribe();
+        let in_flight = Arc::new(Semaphore::new(MAX_IN_FLIGHT));
+        let mut is_leader = false;
+        let mut leader_checked_at: Option<Instant> = None;
+        loop {
+            let event = receiver.recv().await;
+            let now = Instant::now();
+            if leader_checked_at
+                .map(|v| now.duration_since(v) >= LEADER_CHECK_INTERVAL)
+                .unwrap_or(true)
+            {
+                is_leader = app.raft.current_leader().await == Some(app.sys_config.raft_node_id);
+                leader_checked_at = Some(now);
+            }
+            let event = match event {
+                Ok(v) => v,
+                Err(RecvError::Lagged(n)) => {
+                    if is_leader {
+                        log::error!("config kafka publisher lagged, {} events dropped", n);
+                        let publisher = self.clone();
+                        tokio::spawn(async move { publisher.publish_lagged(n).await });
+                    }
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+            if !is_leader {
+                continue;
+            }
+            let permit = match in_flight.clone().acquire_owned().await {
+                Ok(v) => v,
+                Err(_) => return,
+            };
+            let publisher = self.clone();
+            tokio::spawn(async move {
+                publisher.publish(&event).await;
+                drop(permit);
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config_event::ConfigChangeType;
+    use crate::config::core::ConfigKey;
+
+    #[test]
+    fn change_message() {
+        let event = ConfigChangeEvent {
+            key: ConfigKey::new("a", "DEFAULT_GROUP", "dev"),
+            change_type: ConfigChangeType::Set,
+            md5: Some(Arc::new("m1".to_owned())),
+            op_user: Some(Arc::new("admin".to_owned())),
+            op_time: 1,
+        };
+        let value = serde_json::to_value(ConfigChangeMessage::from(&event)).unwrap();
+        assert_eq!(value["dataId"], "a");
+        assert_eq!(value["tenant"], "dev");
+        assert_eq!(value["md5"], "m1");
+        assert_eq!(value["changeTime"], 1);
+        assert_eq!(value["operator"], "admin");
+    }
+
+    #[test]
+    fn exponential_retry_delay() {
+        assert_eq!(retry_delay(0), Duration::from_millis(100));
+        assert_eq!(retry_delay(3), Duration::from_millis(800));
+        assert_eq!(retry_delay(20), Duration::from_millis(RETRY_MAX_DELAY_MS));
+    }
+}
@@ -0,0 +1,309 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use aws_sdk_kms::primitives::Blob;
+use serde::Deserialize;
+
+use super::config_field_encrypt::ConfigFieldCipher;
+use crate::common::crypto_utils::decode_base64;
+
+const VAULT_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_KEY_REFRESH_SECONDS: u64 = 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KmsProviderType {
+    Env,
+    Aws,
+    Vault,
+}
+
+impl KmsProviderType {
+    pub fn new_by_value(v: &str) -> Option<Self> {
+        match v.to_lowercase().as_str() {
+            "env" => Some(Self::Env),
+            "aws" => Some(Self::Aws),
+            "vault" => Some(Self::Vault),
+            _ => None,
+        }
+    }
+}
+
+///
+/// 配置加密主密钥的来源;使用外部KMS时RNACOS_CONFIG_ENCRYPTION_KEY保存的是被KMS加密后的主密钥
+#[derive(Debug, Clone, Default)]
+pub struct KmsConfig {
+    //env|aws|vault
+    pub provider: String,
+    //aws kms的密钥id或arn,解密时可为空
+    pub aws_key_id: Option<String>,
+    pub vault_addr: String,
+    pub vault_token: String,
+    //vault transit引擎中的密钥名
+    pub vault_key_name: String,
+    //保存被加密主密钥的文件,设置后优先于RNACOS_CONFIG_ENCRYPTION_KEY
+    pub key_file: Option<String>,
+    //重新解出主密钥的间隔,0表示只在启动时解出
+    pub refresh_seconds: u64,
+}
+
+impl KmsConfig {
+    pub fn from_env() -> Self {
+        let provider = std::env::var("RNACOS_CONFIG_ENCRYPTION_KMS_PROVIDER")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .unwrap_or("env".to_owned());
+        let aws_key_id = std::env::var("RNACOS_CONFIG_ENCRYPTION_AWS_KEY_ID")
+            .ok()
+            .filter(|v| !v.is_empty());
+        let vault_addr = std::env::var("RNACOS_CONFIG_ENCRYPTION_VAULT_ADDR").unwrap_or_default();
+        let vault_token = std::env::var("RNACOS_CONFIG_ENCRYPTION_VAULT_TOKEN").unwrap_or_default();
+        let vault_key_name =
+            std::env::var("RNACOS_CONFIG_ENCRYPTION_VAULT_KEY").unwrap_or("rnacos".to_owned());
+        let key_file = std::env::var("RNACOS_CONFIG_ENCRYPTION_KEY_FILE")
+            .ok()
+            .filter(|v| !v.is_empty());
+        let refresh_seconds = std::env::var("RNACOS_CONFIG_ENCRYPTION_KEY_REFRESH_SECONDS")
+            .unwrap_or_default()
+            .parse()
+            .unwrap_or(DEFAULT_KEY_REFRESH_SECONDS);
+        Self {
+            provider,
+            aws_key_id,
+            vault_addr,
+            vault_token,
+            vault_key_name,
+            key_file,
+            refresh_seconds,
+        }
+    }
+
+    pub fn provider_type(&self) -> anyhow::Result<KmsProviderType> {
+        KmsProviderType::new_by_value(&self.provider).ok_or_else(|| {
+            anyhow::anyhow!("unknown config encryption kms provider: {}", &self.provider)
+        })
+    }
+}
+
+///
+/// 主密钥的加解密服务
+#[async_trait]
+pub trait KeyProvider: Send + Sync {
+    async fn decrypt(&self, ciphertext: &[u8]) -> anyhow::Result<Vec<u8>>;
+}
+
+///
+/// 主密钥以明文保存在环境变量中,解密不做变换
+pub struct EnvKeyProvider;
+
+#[async_trait]
+impl KeyProvider for EnvKeyProvider {
+    async fn decrypt(&self, ciphertext: &[u8]) -> anyhow::Result<Vec<u8>> {
+        Ok(ciphertext.to_vec())
+    }
+}
+
+///
+/// AWS KMS,凭证与区域按aws sdk默认方式从环境中加载
+pub struct AwsKmsKeyProvider {
+    client: aws_sdk_kms::Client,
+    key_id: Option<String>,
+}
+
+impl AwsKmsKeyProvider {
+    pub async fn new(key_id: Option<String>) -> Self {
+        let sdk_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        Self {
+            client: aws_sdk_kms::Client::new(&sdk_config),
+            key_id,
+        }
+    }
+}
+
+#[async_trait]
+impl KeyProvider for AwsKmsKeyProvider {
+    async fn decrypt(&self, ciphertext: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut req = self.client.decrypt().ciphertext_blob(Blob::new(ciphertext));
+        if let Some(key_id) = &self.key_id {
+            req = req.key_id(key_id);
+        }
+        let output = req.send().await?;
+        output
+            .plaintext
+            .map(|v| v.into_inner())
+            .ok_or_else(|| anyhow::anyhow!("aws kms decrypt returned empty plaintext"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct VaultResponse {
+    data: VaultResponseData,
+}
+
+#[derive(Debug, Deserialize)]
+struct VaultResponseData {
+    plaintext: Option<String>,
+}
+
+///
+/// HashiCorp Vault transit引擎;密文为`vault:v1:...`格式的字符串,解出的主密钥为原始字节
+pub struct VaultKeyProvider {
+    addr: String,
+    token: String,
+    key_name: String,
+    client: reqwest::Client,
+}
+
+impl VaultKeyProvider {
+    pub fn new(addr: String, token: String, key_name: String) -> anyhow::Result<Self> {
+        if addr.is_empty() {
+            return Err(anyhow::anyhow!(
+                "RNACOS_CONFIG_ENCRYPTION_VAULT_ADDR is required for vault kms provider"
+            ));
+        }
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(VAULT_TIMEOUT_MS))
+            .build()?;
+        Ok(Self {
+            addr: addr.trim_end_matches('/').to_owned(),
+            token,
+            key_name,
+            client,
+        })
+    }
+
+    async fn transit(
+        &self,
+        op: &str,
+        body: serde_json::Value,
+    ) -> anyhow::Result<VaultResponseData> {
+        let url = format!("{}/v1/transit/{}/{}", &self.addr, op, &self.key_name);
+        let res = self
+            .client
+            .post(url)
+            .header("X-Vault-Token", &self.token)
+            .json(&body)
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "vault transit {} error, status: {}",
+                op,
+                res.status()
+            ));
+        }
+        Ok(res.json::<VaultResponse>().await?.data)
+    }
+}
+
+#[async_trait]
+impl KeyProvider for VaultKeyProvider {
+    async fn decrypt(&self, ciphertext: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let ciphertext = std::str::from_utf8(ciphertext)?;
+        let data = self
+            .transit("decrypt", serde_json::json!({ "ciphertext": ciphertext }))
+            .await?;
+        match data.plaintext {
+            Some(v) => decode_base64(&v),
+            None => Err(anyhow::anyhow!("vault decrypt returned empty plaintext")),
+        }
+    }
+}
+
+pub async fn build_key_provider(config: &KmsConfig) -> anyhow::Result<Box<dyn KeyProvider>> {
+    let provider: Box<dyn KeyProvider> = match config.provider_type()? {
+        KmsProviderType::Env => Box::new(EnvKeyProvider),
+        KmsProviderType::Aws => Box::new(AwsKmsKeyProvider::new(config.aws_key_id.clone()).await),
+        KmsProviderType::Vault => Box::new(VaultKeyProvider::new(
+            config.vault_addr.clone(),
+            config.vault_token.clone(),
+            config.vault_key_name.clone(),
+        )?),
+    };
+    Ok(provider)
+}
+
+///
+/// 解出配置加密主密钥;aws的密文为base64(与`aws kms encrypt`的输出一致),vault的密文为transit返回的字符串。
+/// 主密钥按原始字节返回,由HKDF派生字段密钥,不要求是utf8
+pub async fn load_master_key(config: &KmsConfig, encrypted_key: &str) -> anyhow::Result<Vec<u8>> {
+    if encrypted_key.is_empty() {
+        return Ok(vec![]);
+    }
+    let ciphertext = match config.provider_type()? {
+        KmsProviderType::Env => encrypted_key.as_bytes().to_vec(),
+        KmsProviderType::Aws => decode_base64(encrypted_key.trim())?,
+        KmsProviderType::Vault => encrypted_key.trim().as_bytes().to_vec(),
+    };
+    let provider = build_key_provider(config).await?;
+    provider.decrypt(&ciphertext).await
+}
+
+///
+/// 被加密的主密钥;设置了密钥文件时每次从文件读取,替换文件内容即可轮换主密钥
+pub async fn read_encrypted_key(config: &KmsConfig, default_key: &str) -> anyhow::Result<String> {
+    match &config.key_file {
+        Some(path) => Ok(tokio::fs::read_to_string(path).await?.trim().to_owned()),
+        None => Ok(default_key.to_owned()),
+    }
+}
+
+///
+/// 定时重新解出主密钥:KMS中的密钥被禁用或轮换、密钥文件被替换时不需要重启;
+/// 解密失败时继续使用当前主密钥
+pub async fn refresh_master_key(
+    config: KmsConfig,
+    default_key: String,
+    cipher: Arc<ConfigFieldCipher>,
+) {
+    let interval = Duration::from_secs(config.refresh_seconds);
+    loop {
+        tokio::time::sleep(interval).await;
+        let result = match read_encrypted_key(&config, &default_key).await {
+            Ok(encrypted_key) => load_master_key(&config, &encrypted_key).await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(master_key) if !master_key.is_empty() => {
+                if cipher.rotate_master_key(master_key) {
+                    log::info!("config encryption master key rotated");
+                }
+            }
+            Ok(_) => log::warn!("config encryption master key is empty, keep current key"),
+            Err(err) => log::error!("refresh config encryption master key error: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_type() {
+        assert_eq!(
+            KmsProviderType::new_by_value("AWS"),
+            Some(KmsProviderType::Aws)
+        );
+        assert_eq!(
+            KmsProviderType::new_by_value("vault"),
+            Some(KmsProviderType::Vault)
+        );
+        assert_eq!(KmsProviderType::new_by_value("gcp"), None);
+    }
+
+    #[tokio::test]
+    async fn env_master_key() {
+        let config = KmsConfig {
+            provider: "env".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(load_master_key(&config, "abc").await.unwrap(), b"abc");
+        assert!(load_master_key(&config, "").await.unwrap().is_empty());
+        let config = KmsConfig {
+            provider: "gcp".to_owned(),
+            ..Default::default()
+        };
+        assert!(load_master_key(&config, "abc").await.is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::core::{ConfigKey, ListenerItem};
+
+/// 未确认通知的最长保留时长,超过后视为客户端已下线
+const MIN_PENDING_TTL_MS: i64 = 10 * 60 * 1000;
+
+///
+/// 长轮询变更通知的确认;返回变更后客户端需要在ack_timeout_ms内带新md5重新监听,
+/// 否则视为响应丢失,在该客户端下一次长轮询时重新返回变更;
+/// 客户端以Client-Id请求头区分,未带该请求头的长轮询不跟踪确认
+#[derive(Default)]
+pub struct ListenerAckManage {
+    ack_timeout_ms: i64,
+    //Client-Id -> (配置 -> 通知时间)
+    pending: Mutex<HashMap<Arc<String>, HashMap<ConfigKey, i64>>>,
+}
+
+impl ListenerAckManage {
+    ///
+    /// ack_timeout_ms为0时不跟踪确认
+    pub fn new(ack_timeout_ms: u64) -> Self {
+        Self {
+            ack_timeout_ms: ack_timeout_ms as i64,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.ack_timeout_ms > 0
+    }
+
+    ///
+    /// 客户端重新监听时确认之前的通知,返回需要重新通知的配置;
+    /// 精确监听的配置由md5对比决定是否返回变更,通配监听的配置超时未重新监听时重新通知
+    pub fn check(&self, client: &Arc<String>, items: &[ListenerItem], now: i64) -> Vec<ConfigKey> {
+        if !self.is_enabled() {
+            return vec![];
+        }
+        let mut pending = match self.pending.lock() {
+            Ok(v) => v,
+            Err(_) => return vec![],
+        };
+        let keys = match pending.remove(client) {
+            Some(v) => v,
+            None => return vec![],
+        };
+        let wildcards: Vec<_> = items.iter().filter_map(|v| v.to_wildcard()).collect();
+        let mut resend = vec![];
+        for (key, notify_time) in keys {
+            if items.iter().any(|v| v.key == key) {
+                continue;
+            }
+            if now - notify_time > self.ack_timeout_ms && wildcards.iter().any(|v| v.is_match(&key))
+            {
+                resend.push(key);
+            }
+        }
+        resend
+    }
+
+    ///
+    /// 记录返回给客户端的变更,等待确认
+    pub fn notified(&self, client: &Arc<String>, keys: &[ConfigKey], now: i64) {
+        if !self.is_enabled() || keys.is_empty() {
+            return;
+        }
+        if let Ok(mut pending) = self.pending.lock() {
+            let ttl = MIN_PENDING_TTL_MS.max(self.ack_timeout_ms * 10);
+            pending.retain(|_, v| {
+                v.retain(|_, notify_time| now - *notify_time < ttl);
+                !v.is_empty()
+            });
+            let client_pending = pending.entry(client.clone()).or_default();
+            for key in keys {
+                client_pending.insert(key.clone(), now);
+            }
+        }
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending
+            .lock()
+            .map(|v| v.values().map(|keys| keys.len()).sum())
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listener_ack() {
+        let manage = ListenerAckManage::new(1000);
+        let client = Arc::new("client-a".to_owned());
+        let a = ConfigKey::new("a", "backend", "");
+        let b = ConfigKey::new("b", "backend", "");
+        let wildcard = || ListenerItem::new(ConfigKey::new("*", "backend", ""), Default::default());
+        let exact = ListenerItem::new(a.clone(), Arc::new("new".to_owned()));
+
+        //超时内重新监听视为已确认
+        manage.notified(&client, std::slice::from_ref(&b), 0);
+        assert!(manage.check(&client, &[wildcard()], 500).is_empty());
+        assert_eq!(manage.pending_count(), 0);
+
+        //超时未重新监听时重新通知
+        manage.notified(&client, &[a.clone(), b.clone()], 0);
+        let resend = manage.check(&client, &[exact, wildcard()], 2000);
+        assert_eq!(resend, vec![b.clone()]);
+
+        //未启用时不跟踪
+        let manage = ListenerAckManage::new(0);
+        manage.notified(&client, std::slice::from_ref(&b), 0);
+        assert!(manage.check(&client, &[wildcard()], 2000).is_empty());
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use super::config_read_view::ConfigReadValue;
+use super::core::ConfigKey;
+use crate::raft::cluster::model::SetConfigReq;
+
+/// 等待手动解决冲突的合并保留时长
+const PENDING_MERGE_TTL_MS: i64 = 30 * 60 * 1000;
+
+///
+/// 合并时源与目标命名空间存在内容不同的同名配置的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamespaceMergePolicy {
+    SrcWins,
+    DstWins,
+    //返回冲突列表,由调用方逐个选择
+    Manual,
+}
+
+impl FromStr for NamespaceMergePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "SRC_WINS" => Ok(Self::SrcWins),
+            "DST_WINS" => Ok(Self::DstWins),
+            "MANUAL" => Ok(Self::Manual),
+            _ => Err(anyhow::anyhow!("unknown conflict policy: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceMergeConflict {
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    pub src_md5: Arc<String>,
+    pub dst_md5: Arc<String>,
+}
+
+struct ConflictItem {
+    key: ConfigKey,
+    value: ConfigReadValue,
+    dst_md5: Arc<String>,
+}
+
+///
+/// 合并计划;目标命名空间中不存在的配置直接复制,内容相同的配置忽略
+pub struct NamespaceMergePlan {
+    pub src: Arc<String>,
+    pub dst: Arc<String>,
+    copies: Vec<(ConfigKey, ConfigReadValue)>,
+    conflicts: Vec<ConflictItem>,
+}
+
+impl NamespaceMergePlan {
+    pub fn build(
+        src: Arc<String>,
+        dst: Arc<String>,
+        src_list: Vec<(ConfigKey, ConfigReadValue)>,
+        dst_list: Vec<(ConfigKey, ConfigReadValue)>,
+    ) -> Self {
+        let dst_md5: HashMap<(Arc<String>, Arc<String>), Arc<String>> = dst_list
+            .into_iter()
+            .map(|(k, v)| ((k.group, k.data_id), v.md5))
+            .collect();
+        let mut copies = vec![];
+        let mut conflicts = vec![];
+        for (key, value) in src_list {
+            match dst_md5.get(&(key.group.clone(), key.data_id.clone())) {
+                None => copies.push((key, value)),
+                Some(md5) if md5 == &value.md5 => {}
+                Some(md5) => conflicts.push(ConflictItem {
+                    key,
+                    value,
+                    dst_md5: md5.clone(),
+                }),
+            }
+        }
+        Self {
+            src,
+            dst,
+            copies,
+            conflicts,
+        }
+    }
+
+    pub fn copy_count(&self) -> usize {
+        self.copies.len()
+    }
+
+    pub fn conflicts(&self) -> Vec<NamespaceMergeConflict> {
+        self.conflicts
+            .iter()
+            .map(|v| NamespaceMergeConflict {
+                group: v.key.group.clone(),
+                data_id: v.key.data_id.clone(),
+                src_md5: v.value.md5.clone(),
+                dst_md5: v.dst_md5.clone(),
+            })
+            .collect()
+    }
+
+    fn to_req(&self, key: &ConfigKey, value: &ConfigReadValue) -> SetConfigReq {
+        let dst_key =
+            ConfigKey::new_by_arc(key.data_id.clone(), key.group.clone(), self.dst.clone());
+        let mut req = SetConfigReq::new(dst_key, value.content.clone());
+        req.config_type = value.config_type.clone();
+        req.desc = value.desc.clone();
+        req
+    }
+
+    ///
+    /// 生成写入目标命名空间的请求;复制的配置要求目标配置仍不存在,
+    /// use_src为true的冲突配置要求目标配置的md5与合并时一致,期间目标配置被修改时该项写入失败
+    pub fn to_reqs(&self, use_src: impl Fn(&ConfigKey) -> bool) -> Vec<SetConfigReq> {
+        let not_exist_md5 = Arc::new(String::new());
+        let mut reqs: Vec<SetConfigReq> = self
+            .copies
+            .iter()
+            .map(|(key, value)| {
+                let mut req = self.to_req(key, value);
+                req.expected_md5 = Some(not_exist_md5.clone());
+                req
+            })
+            .collect();
+        for item in self.conflicts.iter().filter(|v| use_src(&v.key)) {
+            let mut req = self.to_req(&item.key, &item.value);
+            req.expected_md5 = Some(item.dst_md5.clone());
+            reqs.push(req);
+        }
+        reqs
+    }
+}
+
+//(创建时间,合并计划)
+type PendingMerge = (i64, Arc<NamespaceMergePlan>);
+
+///
+/// 等待手动解决冲突的合并,只保存在内存中
+#[derive(Default)]
+pub struct NamespaceMergeStore {
+    pending: Mutex<HashMap<Arc<String>, PendingMerge>>,
+}
+
+impl NamespaceMergeStore {
+    pub fn insert(&self, id: Arc<String>, plan: NamespaceMergePlan, now: i64) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.retain(|_, (create_time, _)| now - *create_time < PENDING_MERGE_TTL_MS);
+            pending.insert(id, (now, Arc::new(plan)));
+        }
+    }
+
+    pub fn get(&self, id: &Arc<String>, now: i64) -> Option<Arc<NamespaceMergePlan>> {
+        self.pending.lock().ok().and_then(|pending| {
+            pending
+                .get(id)
+                .filter(|(create_time, _)| now - *create_time < PENDING_MERGE_TTL_MS)
+                .map(|(_, plan)| plan.clone())
+        })
+    }
+
+    pub fn remove(&self, id: &Arc<String>) -> bool {
+        self.pending
+            .lock()
+            .map(|mut pending| pending.remove(id).is_some())
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(data_id: &str, tenant: &str, content: &str) -> (ConfigKey, ConfigReadValue) {
+        let content = Arc::new(content.to_owned());
+        (
+            ConfigKey::new(data_id, "DEFAULT_GROUP", tenant),
+            ConfigReadValue {
+                md5: Arc::new(crate::utils::get_md5(&content)),
+                content,
+                config_type: None,
+                desc: None,
+                detected_type: None,
+                archive_uri: None,
+                last_modified: 0,
+            },
+        )
+    }
+
+    #[test]
+    fn namespace_merge_plan() {
+        let plan = NamespaceMergePlan::build(
+            Arc::new("feature".to_owned()),
+            Arc::new("staging".to_owned()),
+            vec![
+                item("a", "feature", "a1"),
+                item("b", "feature", "b1"),
+                item("c", "feature", "c1"),
+            ],
+            vec![item("b", "staging", "b1"), item("c", "staging", "c2")],
+        );
+        assert_eq!(plan.copy_count(), 1);
+        let conflicts = plan.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].data_id.as_str(), "c");
+
+        let reqs = plan.to_reqs(|_| false);
+        assert_eq!(reqs.len(), 1);
+        assert_eq!(reqs[0].config_key.tenant.as_str(), "staging");
+        //目标命名空间中不存在的配置要求写入时仍不存在
+        assert_eq!(
+            reqs[0].expected_md5.as_deref().map(|v| v.as_str()),
+            Some("")
+        );
+
+        let reqs = plan.to_reqs(|_| true);
+        assert_eq!(reqs.len(), 2);
+        assert_eq!(reqs[1].value.as_str(), "c1");
+        assert_eq!(reqs[1].expected_md5, Some(conflicts[0].dst_md5.clone()));
+
+        assert_eq!(
+            NamespaceMergePolicy::from_str("src_wins").unwrap(),
+            NamespaceMergePolicy::SrcWins
+        );
+        assert!(NamespaceMergePolicy::from_str("other").is_err());
+    }
+
+    #[test]
+    fn namespace_merge_store() {
+        let store = NamespaceMergeStore::default();
+        let id = Arc::new("m1".to_owned());
+        let plan = NamespaceMergePlan::build(
+            Arc::new("a".to_owned()),
+            Arc::new("b".to_owned()),
+            vec![],
+            vec![],
+        );
+        store.insert(id.clone(), plan, 0);
+        assert!(store.get(&id, 1).is_some());
+        assert!(store.get(&id, PENDING_MERGE_TTL_MS).is_none());
+        assert!(store.remove(&id));
+        assert!(!store.remove(&id));
+    }
+}
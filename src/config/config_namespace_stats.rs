@@ -0,0 +1,114 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::metrics::{NAMESPACE_CONFIG_COUNT_GAUGE, NAMESPACE_CONTENT_BYTES_GAUGE};
+
+pub const NAMESPACE_STATS_CACHE_SECONDS: u64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceStats {
+    pub namespace_id: Arc<String>,
+    pub config_count: usize,
+    pub total_content_bytes: usize,
+    //订阅了该命名空间配置的客户端数
+    pub active_listeners: usize,
+    pub last_modified: i64,
+}
+
+///
+/// 命名空间统计结果缓存,避免监控页面频繁刷新时每次都遍历全部配置
+pub struct NamespaceStatsCache {
+    value: Mutex<Option<(Instant, Arc<Vec<NamespaceStats>>)>>,
+    ttl: Duration,
+}
+
+impl Default for NamespaceStatsCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(NAMESPACE_STATS_CACHE_SECONDS))
+    }
+}
+
+impl NamespaceStatsCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            value: Mutex::new(None),
+            ttl,
+        }
+    }
+
+    pub fn get(&self) -> Option<Arc<Vec<NamespaceStats>>> {
+        let value = self.value.lock().ok()?;
+        match value.as_ref() {
+            Some((time, list)) if time.elapsed() < self.ttl => Some(list.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn set(&self, list: Vec<NamespaceStats>) -> Arc<Vec<NamespaceStats>> {
+        let list = Arc::new(list);
+        if let Ok(mut value) = self.value.lock() {
+            *value = Some((Instant::now(), list.clone()));
+        }
+        list
+    }
+}
+
+///
+/// 更新命名空间指标;由ConfigActor定时调用,不依赖统计接口被访问
+pub fn update_namespace_gauges(list: &[NamespaceStats]) {
+    NAMESPACE_CONFIG_COUNT_GAUGE.reset();
+    NAMESPACE_CONTENT_BYTES_GAUGE.reset();
+    for item in list {
+        NAMESPACE_CONFIG_COUNT_GAUGE
+            .with_label_values(&[item.namespace_id.as_str()])
+            .set(item.config_count as i64);
+        NAMESPACE_CONTENT_BYTES_GAUGE
+            .with_label_values(&[item.namespace_id.as_str()])
+            .set(item.total_content_bytes as i64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namespace_stats_cache() {
+        let cache = NamespaceStatsCache::new(Duration::from_millis(50));
+        assert!(cache.get().is_none());
+        cache.set(vec![NamespaceStats {
+            namespace_id: Arc::new("stats_test".to_owned()),
+            config_count: 2,
+            total_content_bytes: 10,
+            ..Default::default()
+        }]);
+        assert_eq!(cache.get().unwrap()[0].config_count, 2);
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(cache.get().is_none());
+    }
+
+    #[test]
+    fn namespace_gauges() {
+        update_namespace_gauges(&[NamespaceStats {
+            namespace_id: Arc::new("gauge_test".to_owned()),
+            config_count: 2,
+            total_content_bytes: 10,
+            ..Default::default()
+        }]);
+        assert_eq!(
+            NAMESPACE_CONFIG_COUNT_GAUGE
+                .with_label_values(&["gauge_test"])
+                .get(),
+            2
+        );
+        assert_eq!(
+            NAMESPACE_CONTENT_BYTES_GAUGE
+                .with_label_values(&["gauge_test"])
+                .get(),
+            10
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use super::core::ConfigKey;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NamespaceTemplateItem {
+    namespace_id: Option<String>,
+    is_template: Option<bool>,
+    deleted_at: Option<i64>,
+}
+
+///
+/// 模板命名空间列表;模板命名空间下的配置只读,避免被误修改。
+/// 同时记录已删除(待清除)的命名空间,其下的配置不可读写
+#[derive(Debug, Default)]
+pub struct TemplateNamespaces {
+    //命名空间配置的md5,变化时重新解析
+    namespace_md5: Option<Arc<String>>,
+    ids: HashSet<String>,
+    deleted_ids: HashSet<String>,
+}
+
+impl TemplateNamespaces {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// 命名空间配置变化时重新解析,返回是否有变化
+    pub fn update_namespace_settings(&mut self, md5: &Arc<String>, content: &str) -> bool {
+        if self.namespace_md5.as_ref() == Some(md5) {
+            return false;
+        }
+        self.namespace_md5 = Some(md5.clone());
+        let items = serde_json::from_str::<Vec<NamespaceTemplateItem>>(content).unwrap_or_default();
+        self.ids = HashSet::new();
+        self.deleted_ids = HashSet::new();
+        for item in items {
+            let id = match item.namespace_id {
+                Some(id) => id,
+                None => continue,
+            };
+            if item.deleted_at.is_some() {
+                self.deleted_ids.insert(id);
+            } else if item.is_template == Some(true) {
+                self.ids.insert(id);
+            }
+        }
+        true
+    }
+
+    pub fn is_template(&self, tenant: &str) -> bool {
+        self.ids.contains(tenant)
+    }
+
+    pub fn is_deleted(&self, tenant: &str) -> bool {
+        self.deleted_ids.contains(tenant)
+    }
+
+    pub fn deleted_ids(&self) -> &HashSet<String> {
+        &self.deleted_ids
+    }
+
+    ///
+    /// 读写的配置属于已删除的命名空间时返回错误
+    pub fn check_not_deleted<'a>(
+        &self,
+        mut keys: impl Iterator<Item = &'a ConfigKey>,
+    ) -> anyhow::Result<()> {
+        match keys.find(|v| self.is_deleted(&v.tenant)) {
+            Some(key) => Err(anyhow::anyhow!("namespace {} is deleted", key.tenant)),
+            None => Ok(()),
+        }
+    }
+
+    ///
+    /// 写入的配置属于模板命名空间时返回错误
+    pub fn check_write<'a>(
+        &self,
+        mut keys: impl Iterator<Item = &'a ConfigKey>,
+    ) -> anyhow::Result<()> {
+        match keys.find(|v| self.is_template(&v.tenant)) {
+            Some(key) => Err(anyhow::anyhow!(
+                "namespace {} is a read-only template",
+                key.tenant
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_namespaces() {
+        let mut templates = TemplateNamespaces::new();
+        templates.update_namespace_settings(
+            &Arc::new("1".to_owned()),
+            r#"[{"namespaceId":"tpl","namespaceName":"tpl","isTemplate":true},
+            {"namespaceId":"dev","namespaceName":"dev","isTemplate":false}]"#,
+        );
+        assert!(templates.is_template("tpl"));
+        assert!(!templates.is_template("dev"));
+        let keys = [
+            ConfigKey::new("a", "DEFAULT_GROUP", "dev"),
+            ConfigKey::new("a", "DEFAULT_GROUP", "tpl"),
+        ];
+        assert!(templates.check_write(keys[..1].iter()).is_ok());
+        assert!(templates.check_write(keys.iter()).is_err());
+
+        //md5未变化时不重新解析
+        templates.update_namespace_settings(&Arc::new("1".to_owned()), "[]");
+        assert!(templates.is_template("tpl"));
+        templates.update_namespace_settings(&Arc::new("2".to_owned()), "[]");
+        assert!(!templates.is_template("tpl"));
+    }
+
+    #[test]
+    fn deleted_namespaces() {
+        let mut templates = TemplateNamespaces::new();
+        assert!(templates.update_namespace_settings(
+            &Arc::new("1".to_owned()),
+            r#"[{"namespaceId":"old","namespaceName":"old","deletedAt":1700000000000},
+            {"namespaceId":"dev","namespaceName":"dev"}]"#,
+        ));
+        assert!(templates.is_deleted("old"));
+        assert!(!templates.is_deleted("dev"));
+        let keys = [
+            ConfigKey::new("a", "DEFAULT_GROUP", "dev"),
+            ConfigKey::new("a", "DEFAULT_GROUP", "old"),
+        ];
+        assert!(templates.check_not_deleted(keys[..1].iter()).is_ok());
+        assert!(templates.check_not_deleted(keys.iter()).is_err());
+        assert!(!templates.update_namespace_settings(&Arc::new("1".to_owned()), "[]"));
+    }
+}
@@ -0,0 +1,519 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::core::ConfigKey;
+
+/// 高优先级配置的合并时长为命名空间设置的1/10
+const HIGH_PRIORITY_DEBOUNCE_DIVISOR: u64 = 10;
+/// 变更频率的统计窗口,频率上限小于1次/秒时按1/频率放大窗口
+const CHANGE_RATE_WINDOW_MS: u64 = 1000;
+/// 持续变更时最长等待合并时长的倍数,到期后即使仍有新变更也会通知
+const MAX_WAIT_MULTIPLE: u32 = 5;
+
+///
+/// 配置变更通知的优先级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigPriority {
+    #[default]
+    Normal,
+    //合并时长缩短为1/10
+    High,
+    //不合并,立即通知
+    Critical,
+}
+
+impl ConfigPriority {
+    pub fn new_by_value(v: &str) -> Option<Self> {
+        match v.to_lowercase().as_str() {
+            "normal" => Some(Self::Normal),
+            "high" => Some(Self::High),
+            "critical" => Some(Self::Critical),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::High => "high",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NamespaceDebounceItem {
+    namespace_id: Option<String>,
+    debounce_ms: Option<u64>,
+    max_change_rate_per_key: Option<f64>,
+}
+
+///
+/// 单个配置在当前统计窗口内的变更次数
+struct ChangeRate {
+    window_start: Instant,
+    count: u64,
+}
+
+///
+/// 超过变更频率上限的配置,只保留最近一次变更待冷却后通知
+struct ThrottledNotify {
+    changed_at: Instant,
+    deadline: Instant,
+    //待通知的首次变更的写入时间(毫秒)
+    write_ms: i64,
+}
+
+struct PendingNotify {
+    first_at: Instant,
+    deadline: Instant,
+    //变更的配置及其首次变更的写入时间(毫秒)
+    keys: HashMap<ConfigKey, i64>,
+}
+
+///
+/// 每次变更后的截止时间,最迟不超过首次变更后合并时长的MAX_WAIT_MULTIPLE倍
+fn next_deadline(first_at: Instant, now: Instant, ms: u64) -> Instant {
+    let delay = Duration::from_millis(ms);
+    (now + delay).min(first_at + delay * MAX_WAIT_MULTIPLE)
+}
+
+///
+/// 需要延迟通知的一组变更,计时结束后按命名空间及优先级取出
+#[derive(Debug, Clone)]
+pub struct PendingFlush {
+    pub tenant: Arc<String>,
+    pub priority: ConfigPriority,
+    pub delay_ms: u64,
+    //超过变更频率上限的配置单独计时
+    pub throttled_key: Option<ConfigKey>,
+}
+
+///
+/// 记录变更后的处理方式
+#[derive(Debug, Clone)]
+pub enum DebounceAction {
+    //立即通知
+    Notify,
+    //新的变更组,需要启动计时
+    Schedule(PendingFlush),
+    //合并到已在计时的变更组
+    Merged,
+}
+
+///
+/// 计时结束时取出的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlushState<T> {
+    Ready(T),
+    //期间有新变更,需要再等待的毫秒数
+    Wait(u64),
+    Empty,
+}
+
+///
+/// 按命名空间合并变更通知;窗口内每次变更都会推迟截止时间,截止后一次性通知窗口内的全部变更。
+/// 持续变更时最迟在首次变更后合并时长的MAX_WAIT_MULTIPLE倍通知,每个变更组只有一个计时。
+/// 不同优先级的变更分开计时。
+/// 单个配置的变更频率超过命名空间设置的上限时,变更照常写入,通知改为冷却时长内无新变更后只通知最近一次
+#[derive(Default)]
+pub struct NotifyDebounce {
+    default_ms: u64,
+    cooldown_ms: u64,
+    namespace_ms: HashMap<String, u64>,
+    namespace_rate: HashMap<String, f64>,
+    //命名空间配置的md5,变化时重新解析
+    namespace_md5: Option<Arc<String>>,
+    pending: HashMap<(Arc<String>, ConfigPriority), PendingNotify>,
+    change_rates: HashMap<ConfigKey, ChangeRate>,
+    throttled: HashMap<ConfigKey, ThrottledNotify>,
+}
+
+impl NotifyDebounce {
+    pub fn new(default_ms: u64, cooldown_ms: u64) -> Self {
+        Self {
+            default_ms,
+            cooldown_ms,
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// 按命名空间列表配置更新各命名空间的合并时长
+    pub fn update_namespace_settings(&mut self, md5: &Arc<String>, content: &str) {
+        if self.namespace_md5.as_ref() == Some(md5) {
+            return;
+        }
+        self.namespace_md5 = Some(md5.clone());
+        let items = serde_json::from_str::<Vec<NamespaceDebounceItem>>(content).unwrap_or_default();
+        self.namespace_ms = items
+            .iter()
+            .filter_map(|v| match (&v.namespace_id, v.debounce_ms) {
+                (Some(id), Some(ms)) => Some((id.to_owned(), ms)),
+                _ => None,
+            })
+            .collect();
+        self.namespace_rate = items
+            .into_iter()
+            .filter_map(|v| match (v.namespace_id, v.max_change_rate_per_key) {
+                (Some(id), Some(rate)) if rate > 0f64 => Some((id, rate)),
+                _ => None,
+            })
+            .collect();
+        let namespace_rate = &self.namespace_rate;
+        self.change_rates
+            .retain(|k, _| namespace_rate.contains_key(k.tenant.as_str()));
+    }
+
+    pub fn debounce_ms(&self, tenant: &str, priority: ConfigPriority) -> u64 {
+        let ms = self
+            .namespace_ms
+            .get(tenant)
+            .copied()
+            .unwrap_or(self.default_ms);
+        match priority {
+            ConfigPriority::Normal => ms,
+            ConfigPriority::High => ms / HIGH_PRIORITY_DEBOUNCE_DIVISOR,
+            ConfigPriority::Critical => 0,
+        }
+    }
+
+    ///
+    /// 记录变更;只有新的变更组需要启动计时,已在计时的变更组只推迟截止时间;
+    /// write_ms为提案节点确定的写入时间,用于统计写入到通知的延迟
+    pub fn add(
+        &mut self,
+        key: &ConfigKey,
+        priority: ConfigPriority,
+        write_ms: i64,
+    ) -> DebounceAction {
+        let now = Instant::now();
+        if self.is_rate_exceeded(key, now) {
+            return self.add_throttled(key, priority, now, write_ms);
+        }
+        let ms = self.debounce_ms(&key.tenant, priority);
+        if ms == 0 {
+            return DebounceAction::Notify;
+        }
+        let pending_key = (key.tenant.clone(), priority);
+        if let Some(pending) = self.pending.get_mut(&pending_key) {
+            pending.deadline = next_deadline(pending.first_at, now, ms);
+            pending.keys.entry(key.clone()).or_insert(write_ms);
+            return DebounceAction::Merged;
+        }
+        let mut keys = HashMap::new();
+        keys.insert(key.clone(), write_ms);
+        self.pending.insert(
+            pending_key,
+            PendingNotify {
+                first_at: now,
+                deadline: next_deadline(now, now, ms),
+                keys,
+            },
+        );
+        DebounceAction::Schedule(PendingFlush {
+            tenant: key.tenant.clone(),
+            priority,
+            delay_ms: ms,
+            throttled_key: None,
+        })
+    }
+
+    ///
+    /// 记录一次变更并判断是否超过命名空间设置的单配置变更频率上限;
+    /// 冷却中的配置在通知前一直按超限处理,避免新旧通知交错
+    fn is_rate_exceeded(&mut self, key: &ConfigKey, now: Instant) -> bool {
+        let rate = match self.namespace_rate.get(key.tenant.as_str()) {
+            Some(v) => *v,
+            None => return false,
+        };
+        let window_ms = CHANGE_RATE_WINDOW_MS.max((1000f64 / rate).ceil() as u64);
+        let window = Duration::from_millis(window_ms);
+        let change_rate = self
+            .change_rates
+            .entry(key.clone())
+            .or_insert_with(|| ChangeRate {
+                window_start: now,
+                count: 0,
+            });
+        if now.duration_since(change_rate.window_start) >= window {
+            change_rate.window_start = now;
+            change_rate.count = 0;
+        }
+        change_rate.count += 1;
+        let exceeded = change_rate.count as f64 > rate * window_ms as f64 / 1000f64;
+        if exceeded && !self.throttled.contains_key(key) {
+            log::warn!(
+                "config change rate limited, key:{}, max_change_rate_per_key:{}, notify after {}ms cooldown",
+                key.build_key(),
+                rate,
+                self.cooldown_ms
+            );
+        }
+        exceeded || self.throttled.contains_key(key)
+    }
+
+    fn add_throttled(
+        &mut self,
+        key: &ConfigKey,
+        priority: ConfigPriority,
+        now: Instant,
+        write_ms: i64,
+    ) -> DebounceAction {
+        let ms = self
+            .cooldown_ms
+            .max(self.debounce_ms(&key.tenant, ConfigPriority::Normal));
+        if let Some(throttled) = self.throttled.get_mut(key) {
+            throttled.deadline = next_deadline(throttled.changed_at, now, ms);
+            return DebounceAction::Merged;
+        }
+        self.throttled.insert(
+            key.clone(),
+            ThrottledNotify {
+                changed_at: now,
+                deadline: next_deadline(now, now, ms),
+                write_ms,
+            },
+        );
+        DebounceAction::Schedule(PendingFlush {
+            tenant: key.tenant.clone(),
+            priority,
+            delay_ms: ms,
+            throttled_key: Some(key.clone()),
+        })
+    }
+
+    ///
+    /// 冷却结束时取出超限配置首次变更的写入时间;冷却期间有新变更时返回需要再等待的时长
+    pub fn take_throttled(&mut self, key: &ConfigKey, now: Instant) -> FlushState<i64> {
+        match self.throttled.get(key) {
+            Some(throttled) if throttled.deadline > now => {
+                FlushState::Wait(remain_ms(throttled.deadline, now))
+            }
+            Some(_) => self
+                .throttled
+                .remove(key)
+                .map(|v| FlushState::Ready(v.write_ms))
+                .unwrap_or(FlushState::Empty),
+            None => FlushState::Empty,
+        }
+    }
+
+    ///
+    /// 计时结束时取出待通知的变更及其首次变更的写入时间;期间有新变更时返回需要再等待的时长
+    pub fn take(
+        &mut self,
+        tenant: &Arc<String>,
+        priority: ConfigPriority,
+        now: Instant,
+    ) -> FlushState<Vec<(ConfigKey, i64)>> {
+        let pending_key = (tenant.clone(), priority);
+        match self.pending.get(&pending_key) {
+            Some(pending) if pending.deadline > now => {
+                FlushState::Wait(remain_ms(pending.deadline, now))
+            }
+            Some(_) => self
+                .pending
+                .remove(&pending_key)
+                .map(|v| FlushState::Ready(v.keys.into_iter().collect()))
+                .unwrap_or(FlushState::Empty),
+            None => FlushState::Empty,
+        }
+    }
+}
+
+fn remain_ms(deadline: Instant, now: Instant) -> u64 {
+    //向上取整,避免提前唤醒后反复计时
+    deadline.duration_since(now).as_millis() as u64 + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(action: DebounceAction) -> PendingFlush {
+        match action {
+            DebounceAction::Schedule(v) => v,
+            v => panic!("unexpected action: {:?}", v),
+        }
+    }
+
+    #[test]
+    fn notify_debounce() {
+        let mut debounce = NotifyDebounce::new(0, 0);
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "dev");
+        assert!(matches!(
+            debounce.add(&key, ConfigPriority::Normal, 0),
+            DebounceAction::Notify
+        ));
+
+        debounce.update_namespace_settings(
+            &Arc::new("1".to_owned()),
+            r#"[{"namespaceId":"dev","namespaceName":"dev","debounceMs":100}]"#,
+        );
+        let now = Instant::now();
+        let first = schedule(debounce.add(&key, ConfigPriority::Normal, 10));
+        assert_eq!(first.delay_ms, 100);
+        //同一变更组只计时一次
+        assert!(matches!(
+            debounce.add(
+                &ConfigKey::new("b", "DEFAULT_GROUP", "dev"),
+                ConfigPriority::Normal,
+                20
+            ),
+            DebounceAction::Merged
+        ));
+        assert!(matches!(
+            debounce.add(&key, ConfigPriority::Normal, 30),
+            DebounceAction::Merged
+        ));
+        let tenant = first.tenant.clone();
+        assert!(matches!(
+            debounce.take(&tenant, ConfigPriority::Normal, now),
+            FlushState::Wait(_)
+        ));
+        let later = now + Duration::from_millis(200);
+        match debounce.take(&tenant, ConfigPriority::Normal, later) {
+            //按各配置首次变更的写入时间统计延迟
+            FlushState::Ready(mut keys) => {
+                keys.sort_by_key(|v| v.1);
+                assert_eq!(
+                    keys,
+                    vec![
+                        (key.clone(), 10),
+                        (ConfigKey::new("b", "DEFAULT_GROUP", "dev"), 20)
+                    ]
+                );
+            }
+            v => panic!("unexpected state: {:?}", v),
+        }
+        assert_eq!(
+            debounce.take(&tenant, ConfigPriority::Normal, later),
+            FlushState::Empty
+        );
+
+        assert!(matches!(
+            debounce.add(
+                &ConfigKey::new("a", "DEFAULT_GROUP", "prod"),
+                ConfigPriority::Normal,
+                0
+            ),
+            DebounceAction::Notify
+        ));
+    }
+
+    #[test]
+    fn debounce_max_wait() {
+        let first_at = Instant::now();
+        let ms = Duration::from_millis(100);
+        assert_eq!(next_deadline(first_at, first_at, 100), first_at + ms);
+        //持续变更只推迟到最长等待时长
+        let max_deadline = first_at + ms * MAX_WAIT_MULTIPLE;
+        assert_eq!(
+            next_deadline(first_at, max_deadline - ms / 2, 100),
+            max_deadline
+        );
+        assert_eq!(next_deadline(first_at, max_deadline, 100), max_deadline);
+
+        let mut debounce = NotifyDebounce::new(100, 0);
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "dev");
+        let first = schedule(debounce.add(&key, ConfigPriority::Normal, 0));
+        assert!(matches!(
+            debounce.take(&first.tenant, ConfigPriority::Normal, Instant::now()),
+            FlushState::Wait(_)
+        ));
+        assert!(matches!(
+            debounce.take(
+                &first.tenant,
+                ConfigPriority::Normal,
+                Instant::now() + ms * MAX_WAIT_MULTIPLE
+            ),
+            FlushState::Ready(_)
+        ));
+    }
+
+    #[test]
+    fn priority_debounce() {
+        let mut debounce = NotifyDebounce::new(100, 0);
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "dev");
+        assert!(matches!(
+            debounce.add(&key, ConfigPriority::Critical, 0),
+            DebounceAction::Notify
+        ));
+        let high = schedule(debounce.add(&key, ConfigPriority::High, 0));
+        assert_eq!(high.delay_ms, 10);
+        //不同优先级分开计时
+        let normal = schedule(debounce.add(&key, ConfigPriority::Normal, 0));
+        assert_eq!(normal.delay_ms, 100);
+        let later = Instant::now() + Duration::from_millis(1000);
+        match debounce.take(&high.tenant, ConfigPriority::High, later) {
+            FlushState::Ready(keys) => assert_eq!(keys.len(), 1),
+            v => panic!("unexpected state: {:?}", v),
+        }
+        assert!(matches!(
+            debounce.take(&normal.tenant, ConfigPriority::Normal, later),
+            FlushState::Ready(_)
+        ));
+        assert_eq!(
+            ConfigPriority::new_by_value("Critical"),
+            Some(ConfigPriority::Critical)
+        );
+        assert!(ConfigPriority::new_by_value("urgent").is_none());
+    }
+
+    #[test]
+    fn change_rate_throttle() {
+        let mut debounce = NotifyDebounce::new(0, 500);
+        debounce.update_namespace_settings(
+            &Arc::new("1".to_owned()),
+            r#"[{"namespaceId":"dev","maxChangeRatePerKey":2.0}]"#,
+        );
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "dev");
+        assert!(matches!(
+            debounce.add(&key, ConfigPriority::Normal, 0),
+            DebounceAction::Notify
+        ));
+        assert!(matches!(
+            debounce.add(&key, ConfigPriority::Normal, 0),
+            DebounceAction::Notify
+        ));
+        let now = Instant::now();
+        let first = schedule(debounce.add(&key, ConfigPriority::Critical, 10));
+        assert_eq!(first.delay_ms, 500);
+        assert_eq!(first.throttled_key.as_ref(), Some(&key));
+        //冷却中的配置,后续变更推迟截止时间
+        assert!(matches!(
+            debounce.add(&key, ConfigPriority::Normal, 0),
+            DebounceAction::Merged
+        ));
+        assert!(matches!(
+            debounce.take_throttled(&key, now),
+            FlushState::Wait(_)
+        ));
+        let later = now + Duration::from_millis(1000);
+        assert_eq!(debounce.take_throttled(&key, later), FlushState::Ready(10));
+        assert_eq!(debounce.take_throttled(&key, later), FlushState::Empty);
+        //其它配置不受影响
+        assert!(matches!(
+            debounce.add(
+                &ConfigKey::new("b", "DEFAULT_GROUP", "dev"),
+                ConfigPriority::Normal,
+                0
+            ),
+            DebounceAction::Notify
+        ));
+        assert!(matches!(
+            debounce.add(
+                &ConfigKey::new("a", "DEFAULT_GROUP", "prod"),
+                ConfigPriority::Normal,
+                0
+            ),
+            DebounceAction::Notify
+        ));
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::core::ConfigKey;
+
+/// 通知记录的保留时长,超过后允许再次通知相同md5;
+/// 避免通知响应丢失时客户端长时间收不到变更
+const NOTIFIED_TTL_MS: i64 = 60 * 1000;
+/// 清理过期记录的最小间隔
+const CLEAR_INTERVAL_MS: i64 = 30 * 1000;
+
+#[derive(Default)]
+struct NotifiedState {
+    //(客户端标识, 配置) -> (已通知的md5, 通知时间)
+    notified: HashMap<(Arc<String>, ConfigKey), (Arc<String>, i64)>,
+    last_clear_time: i64,
+}
+
+///
+/// 同一客户端对同一配置开启多个长轮询时,配置变更只通知其中一个连接;
+/// 客户端标识由客户端在请求头中提供,没有标识时不去重
+#[derive(Default)]
+pub struct ListenerNotifyDedup {
+    state: Mutex<NotifiedState>,
+}
+
+impl ListenerNotifyDedup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// 过滤已向该客户端通知过当前md5的配置,并记录本次通知的md5
+    pub fn filter(
+        &self,
+        client_id: &Arc<String>,
+        changes: Vec<(ConfigKey, Arc<String>)>,
+        now: i64,
+    ) -> Vec<ConfigKey> {
+        let mut state = match self.state.lock() {
+            Ok(v) => v,
+            Err(_) => return changes.into_iter().map(|(key, _)| key).collect(),
+        };
+        if now - state.last_clear_time > CLEAR_INTERVAL_MS {
+            state
+                .notified
+                .retain(|_, (_, notify_time)| now - *notify_time < NOTIFIED_TTL_MS);
+            state.last_clear_time = now;
+        }
+        let mut keys = Vec::with_capacity(changes.len());
+        for (key, md5) in changes {
+            let notified_key = (client_id.clone(), key);
+            match state.notified.get(&notified_key) {
+                Some((notified_md5, notify_time))
+                    if notified_md5 == &md5 && now - *notify_time < NOTIFIED_TTL_MS => {}
+                _ => {
+                    keys.push(notified_key.1.clone());
+                    state.notified.insert(notified_key, (md5, now));
+                }
+            }
+        }
+        keys
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().map(|v| v.notified.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dedup_notify() {
+        let dedup = ListenerNotifyDedup::new();
+        let client = Arc::new("app-1".to_owned());
+        let other = Arc::new("app-2".to_owned());
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let md5 = Arc::new("m1".to_owned());
+        let changes = || vec![(key.clone(), md5.clone())];
+        assert_eq!(dedup.filter(&client, changes(), 0), vec![key.clone()]);
+        //同一客户端的其它连接不再通知
+        assert!(dedup.filter(&client, changes(), 10).is_empty());
+        assert_eq!(dedup.filter(&other, changes(), 10), vec![key.clone()]);
+        //md5变化后重新通知
+        let new_changes = vec![(key.clone(), Arc::new("m2".to_owned()))];
+        assert_eq!(dedup.filter(&client, new_changes, 20), vec![key.clone()]);
+        //过期后清理记录
+        assert!(dedup
+            .filter(&client, vec![], NOTIFIED_TTL_MS + 30)
+            .is_empty());
+        assert!(dedup.is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use regex::{Captures, Regex};
+
+use super::core::ConfigKey;
+use super::DEFAULT_TENANT;
+
+/// 被引用配置中的引用继续解析的最大层数
+pub const NS_REF_MAX_DEPTH: usize = 3;
+
+/// 内容中包含该前缀时才需要解析引用
+const NS_REF_PREFIX: &str = "${ns:";
+
+lazy_static::lazy_static! {
+    //${ns:tenant##group##dataId}
+    static ref NS_REF_REGEX: Regex = Regex::new(r"\$\{ns:([^}]*)\}").unwrap();
+}
+
+pub fn has_ns_refs(content: &str) -> bool {
+    content.contains(NS_REF_PREFIX)
+}
+
+///
+/// 内容中直接引用的配置,忽略格式不正确的引用
+pub fn ns_ref_keys(content: &str) -> Vec<ConfigKey> {
+    if !has_ns_refs(content) {
+        return vec![];
+    }
+    NS_REF_REGEX
+        .captures_iter(content)
+        .filter_map(|caps| parse_ref_key(&caps[1]).ok())
+        .collect()
+}
+
+fn parse_ref_key(reference: &str) -> anyhow::Result<ConfigKey> {
+    let key: ConfigKey = reference.parse()?;
+    if key.tenant.as_str() == DEFAULT_TENANT {
+        return Ok(ConfigKey::new_by_arc(
+            key.data_id,
+            key.group,
+            Arc::new(String::new()),
+        ));
+    }
+    Ok(key)
+}
+
+///
+/// 读取配置时把跨命名空间引用`${ns:tenant##group##dataId}`替换为被引用配置的内容
+pub struct NamespaceRefResolver<L, A>
+where
+    L: Fn(&ConfigKey) -> Option<Arc<String>>,
+    A: Fn(&str) -> bool,
+{
+    //读取被引用配置的内容
+    lookup: L,
+    //是否允许读取该命名空间
+    allow: A,
+    //为true时无法解析的引用返回错误,否则替换为空字符串
+    strict: bool,
+}
+
+impl<L, A> NamespaceRefResolver<L, A>
+where
+    L: Fn(&ConfigKey) -> Option<Arc<String>>,
+    A: Fn(&str) -> bool,
+{
+    pub fn new(lookup: L, allow: A, strict: bool) -> Self {
+        Self {
+            lookup,
+            allow,
+            strict,
+        }
+    }
+
+    pub fn resolve(&self, content: &str) -> anyhow::Result<String> {
+        self.resolve_with_depth(content, 0)
+    }
+
+    fn resolve_with_depth(&self, content: &str, depth: usize) -> anyhow::Result<String> {
+        if !has_ns_refs(content) {
+            return Ok(content.to_owned());
+        }
+        let mut error = None;
+        let result = NS_REF_REGEX.replace_all(content, |caps: &Captures| {
+            match self.resolve_ref(&caps[1], depth) {
+                Ok(v) => v,
+                Err(err) => {
+                    if error.is_none() {
+                        error = Some(err);
+                    }
+                    String::new()
+                }
+            }
+        });
+        match error {
+            Some(err) if self.strict => Err(err),
+            _ => Ok(result.into_owned()),
+        }
+    }
+
+    fn resolve_ref(&self, reference: &str, depth: usize) -> anyhow::Result<String> {
+        if depth >= NS_REF_MAX_DEPTH {
+            return Err(anyhow::anyhow!(
+                "config reference is nested too deep: {}",
+                reference
+            ));
+        }
+        let key = parse_ref_key(reference)?;
+        if !(self.allow)(&key.tenant) {
+            return Err(anyhow::anyhow!(
+                "no permission to read namespace: {}",
+                &key.tenant
+            ));
+        }
+        let value = match (self.lookup)(&key) {
+            Some(v) => v,
+            None => {
+                return Err(anyhow::anyhow!(
+                    "referenced config not exist: {}",
+                    reference
+                ))
+            }
+        };
+        //引用一般为单个值,去掉末尾换行
+        self.resolve_with_depth(value.trim_end_matches(['\r', '\n'].as_ref()), depth + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn resolve_ns_refs() {
+        let mut configs = HashMap::new();
+        configs.insert(
+            ConfigKey::new("db.host", "DEFAULT_GROUP", "shared"),
+            Arc::new("10.0.0.1\n".to_owned()),
+        );
+        configs.insert(
+            ConfigKey::new("db.url", "DEFAULT_GROUP", "shared"),
+            Arc::new("mysql://${ns:shared##DEFAULT_GROUP##db.host}:3306".to_owned()),
+        );
+        configs.insert(
+            ConfigKey::new("loop", "DEFAULT_GROUP", "shared"),
+            Arc::new("${ns:shared##DEFAULT_GROUP##loop}".to_owned()),
+        );
+        configs.insert(
+            ConfigKey::new("token", "DEFAULT_GROUP", "secret"),
+            Arc::new("abc".to_owned()),
+        );
+        let lookup = |key: &ConfigKey| configs.get(key).cloned();
+        let allow = |tenant: &str| tenant != "secret";
+        let resolver = NamespaceRefResolver::new(lookup, allow, false);
+        assert_eq!(
+            resolver
+                .resolve("url=${ns:shared##DEFAULT_GROUP##db.url}")
+                .unwrap(),
+            "url=mysql://10.0.0.1:3306"
+        );
+        assert_eq!(
+            resolver
+                .resolve("a=${ns:secret##DEFAULT_GROUP##token},b=${ns:shared##DEFAULT_GROUP##none}")
+                .unwrap(),
+            "a=,b="
+        );
+        assert_eq!(
+            resolver
+                .resolve("${ns:shared##DEFAULT_GROUP##loop}")
+                .unwrap(),
+            ""
+        );
+        let strict = NamespaceRefResolver::new(lookup, allow, true);
+        assert!(strict
+            .resolve("a=${ns:secret##DEFAULT_GROUP##token}")
+            .is_err());
+        assert!(strict.resolve("${ns:shared##DEFAULT_GROUP##loop}").is_err());
+        assert_eq!(strict.resolve("a=${b}").unwrap(), "a=${b}");
+        assert_eq!(
+            ns_ref_keys("${ns:public##g##a},${ns:invalid},${ns:shared##g##b}"),
+            vec![
+                ConfigKey::new("a", "g", ""),
+                ConfigKey::new("b", "g", "shared")
+            ]
+        );
+    }
+}
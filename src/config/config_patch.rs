@@ -0,0 +1,92 @@
+use serde_json::Value;
+
+///
+/// 按RFC 7396 JSON Merge Patch合并
+pub fn json_merge_patch(target: &mut Value, patch: &Value) {
+    let patch_map = match patch {
+        Value::Object(v) => v,
+        _ => {
+            *target = patch.clone();
+            return;
+        }
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    if let Value::Object(target_map) = target {
+        for (key, value) in patch_map {
+            //保持其余字段的原有顺序
+            if value.is_null() {
+                target_map.shift_remove(key);
+            } else {
+                json_merge_patch(target_map.entry(key.as_str()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+///
+/// 对json配置内容应用merge patch;内容不变时返回None
+pub fn patch_json_content(content: &str, patch: &Value) -> anyhow::Result<Option<String>> {
+    let mut value: Value = serde_json::from_str(content)?;
+    let old_value = value.clone();
+    json_merge_patch(&mut value, patch);
+    if value == old_value {
+        return Ok(None);
+    }
+    //保持原内容的格式化风格
+    let new_content = if content.trim().contains('\n') {
+        serde_json::to_string_pretty(&value)?
+    } else {
+        serde_json::to_string(&value)?
+    };
+    Ok(Some(new_content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn merge_patch_rfc7396() {
+        let mut target = json!({"a": "b", "c": {"d": "e", "f": "g"}});
+        json_merge_patch(&mut target, &json!({"a": "z", "c": {"f": null}}));
+        assert_eq!(target, json!({"a": "z", "c": {"d": "e"}}));
+
+        let mut target = json!({"a": ["b"]});
+        json_merge_patch(&mut target, &json!({"a": "c"}));
+        assert_eq!(target, json!({"a": "c"}));
+
+        let mut target = json!({"a": "foo"});
+        json_merge_patch(&mut target, &json!({"b": {"c": null}}));
+        assert_eq!(target, json!({"a": "foo", "b": {}}));
+
+        let mut target = json!(["a", "b"]);
+        json_merge_patch(&mut target, &json!({"a": "b"}));
+        assert_eq!(target, json!({"a": "b"}));
+    }
+
+    #[test]
+    fn patch_content() {
+        let patch = json!({"logLevel": "DEBUG"});
+        assert_eq!(
+            patch_json_content(r#"{"logLevel":"INFO"}"#, &patch).unwrap(),
+            Some(r#"{"logLevel":"DEBUG"}"#.to_owned())
+        );
+        assert_eq!(
+            patch_json_content(r#"{"logLevel":"DEBUG"}"#, &patch).unwrap(),
+            None
+        );
+        assert!(patch_json_content("a=1", &patch).is_err());
+        //字段保持原有顺序,新增字段追加在最后
+        assert_eq!(
+            patch_json_content(
+                r#"{"z":1,"b":2,"m":3,"a":4}"#,
+                &json!({"b": null, "m": 30, "c": 5})
+            )
+            .unwrap(),
+            Some(r#"{"z":1,"m":30,"a":4,"c":5}"#.to_owned())
+        );
+    }
+}
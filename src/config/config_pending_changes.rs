@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::core::ConfigKey;
+use crate::grpc::PayloadUtils;
+use crate::now_millis_i64;
+use crate::raft::cluster::model::{RouteAddr, RouterRequest, RouterResponse, SetConfigReq};
+use crate::raft::cluster::route::RaftAddrRouter;
+use crate::raft::network::factory::RaftClusterRequestSender;
+
+///
+/// 配置变更审批状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PendingChangeStatus {
+    //已创建未提交
+    #[default]
+    Draft,
+    //已提交等待审批
+    Review,
+    //审批不通过,退回给作者修改后可重新提交
+    Rejected,
+    //审批通过等待生效
+    Approved,
+    //已写入配置
+    Active,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PendingChangeAction {
+    Submit,
+    Reject,
+    Approve,
+    Apply,
+}
+
+impl PendingChangeStatus {
+    ///
+    /// 状态流转:draft/rejected -> review -> approved -> active,review可退回为rejected
+    pub fn next(self, action: PendingChangeAction) -> anyhow::Result<Self> {
+        match (self, action) {
+            (Self::Draft | Self::Rejected, PendingChangeAction::Submit) => Ok(Self::Review),
+            (Self::Review, PendingChangeAction::Reject) => Ok(Self::Rejected),
+            (Self::Review, PendingChangeAction::Approve) => Ok(Self::Approved),
+            (Self::Approved, PendingChangeAction::Apply) => Ok(Self::Active),
+            _ => Err(anyhow::anyhow!(
+                "can not {:?} a change in status {:?}",
+                action,
+                self
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingChange {
+    pub id: u64,
+    pub tenant: Arc<String>,
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    pub content: Arc<String>,
+    pub config_type: Option<Arc<String>>,
+    pub desc: Option<Arc<String>>,
+    //创建变更时配置的md5,配置不存在时为空串;生效时配置已被修改则写入失败
+    pub base_md5: Arc<String>,
+    pub status: PendingChangeStatus,
+    pub author: Option<Arc<String>>,
+    pub reviewer: Option<Arc<String>>,
+    pub reject_reason: Option<String>,
+    pub create_time: i64,
+    pub update_time: i64,
+}
+
+impl PendingChange {
+    pub fn config_key(&self) -> ConfigKey {
+        ConfigKey::new_by_arc(
+            self.data_id.clone(),
+            self.group.clone(),
+            self.tenant.clone(),
+        )
+    }
+
+    pub fn to_req(&self, op_user: Option<Arc<String>>) -> SetConfigReq {
+        let mut req = SetConfigReq::new(self.config_key(), self.content.clone());
+        req.config_type = self.config_type.clone();
+        req.desc = self.desc.clone();
+        req.op_user = op_user;
+        req.expected_md5 = Some(self.base_md5.clone());
+        req
+    }
+}
+
+///
+/// 待审批的配置变更,只保存在主节点内存中;其它节点通过PendingChangesRoute转发到主节点
+#[derive(Default)]
+pub struct PendingChangesActor {
+    id_seed: u64,
+    changes: BTreeMap<u64, PendingChange>,
+}
+
+impl PendingChangesActor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn create(
+        &mut self,
+        req: SetConfigReq,
+        base_md5: Arc<String>,
+        author: Option<Arc<String>>,
+        now: i64,
+    ) -> PendingChange {
+        self.id_seed += 1;
+        let change = PendingChange {
+            id: self.id_seed,
+            tenant: req.config_key.tenant,
+            group: req.config_key.group,
+            data_id: req.config_key.data_id,
+            content: req.value,
+            config_type: req.config_type,
+            desc: req.desc,
+            base_md5,
+            status: PendingChangeStatus::Draft,
+            author,
+            reviewer: None,
+            reject_reason: None,
+            create_time: now,
+            update_time: now,
+        };
+        self.changes.insert(change.id, change.clone());
+        change
+    }
+
+    fn transition(
+        &mut self,
+        id: u64,
+        action: PendingChangeAction,
+        operator: Option<Arc<String>>,
+        reason: Option<String>,
+        now: i64,
+    ) -> anyhow::Result<PendingChange> {
+        let change = match self.changes.get_mut(&id) {
+            Some(v) => v,
+            None => return Err(anyhow::anyhow!("change is not exist: {}", id)),
+        };
+        change.status = change.status.next(action)?;
+        change.update_time = now;
+        match action {
+            PendingChangeAction::Submit => {
+                change.reviewer = None;
+                change.reject_reason = None;
+            }
+            PendingChangeAction::Reject => {
+                change.reviewer = operator;
+                change.reject_reason = reason;
+            }
+            PendingChangeAction::Approve => {
+                change.reviewer = operator;
+            }
+            PendingChangeAction::Apply => {}
+        }
+        Ok(change.clone())
+    }
+}
+
+impl Actor for PendingChangesActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _ctx: &mut Self::Context) {
+        log::info!("PendingChangesActor started");
+    }
+}
+
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "anyhow::Result<PendingChangesResult>")]
+pub enum PendingChangesCmd {
+    Create {
+        req: SetConfigReq,
+        base_md5: Arc<String>,
+        author: Option<Arc<String>>,
+    },
+    Transition {
+        id: u64,
+        action: PendingChangeAction,
+        operator: Option<Arc<String>>,
+        reason: Option<String>,
+    },
+    Get(u64),
+    Query(Option<PendingChangeStatus>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PendingChangesResult {
+    Change(PendingChange),
+    List(Vec<PendingChange>),
+}
+
+impl Handler<PendingChangesCmd> for PendingChangesActor {
+    type Result = anyhow::Result<PendingChangesResult>;
+
+    fn handle(&mut self, msg: PendingChangesCmd, _ctx: &mut Context<Self>) -> Self::Result {
+        let now = now_millis_i64();
+        match msg {
+            PendingChangesCmd::Create {
+                req,
+                base_md5,
+                author,
+            } => Ok(PendingChangesResult::Change(
+                self.create(req, base_md5, author, now),
+            )),
+            PendingChangesCmd::Transition {
+                id,
+                action,
+                operator,
+                reason,
+            } => self
+                .transition(id, action, operator, reason, now)
+                .map(PendingChangesResult::Change),
+            PendingChangesCmd::Get(id) => match self.changes.get(&id) {
+                Some(change) => Ok(PendingChangesResult::Change(change.clone())),
+                None => Err(anyhow::anyhow!("change is not exist: {}", id)),
+            },
+            PendingChangesCmd::Query(status) => Ok(PendingChangesResult::List(
+                self.changes
+                    .values()
+                    .filter(|v| status.is_none() || status == Some(v.status))
+                    .cloned()
+                    .collect(),
+            )),
+        }
+    }
+}
+
+///
+/// 审批流程的请求都由主节点处理,避免变更只存在于接收请求的节点上
+pub struct PendingChangesRoute {
+    pending_changes_addr: Addr<PendingChangesActor>,
+    raft_addr_route: Arc<RaftAddrRouter>,
+    cluster_sender: Arc<RaftClusterRequestSender>,
+    //单机模式不经过raft,总是由本节点处理
+    standalone: bool,
+}
+
+impl PendingChangesRoute {
+    pub fn new(
+        pending_changes_addr: Addr<PendingChangesActor>,
+        raft_addr_route: Arc<RaftAddrRouter>,
+        cluster_sender: Arc<RaftClusterRequestSender>,
+        standalone: bool,
+    ) -> Self {
+        Self {
+            pending_changes_addr,
+            raft_addr_route,
+            cluster_sender,
+            standalone,
+        }
+    }
+
+    pub async fn send(&self, cmd: PendingChangesCmd) -> anyhow::Result<PendingChangesResult> {
+        let route_addr = if self.standalone {
+            RouteAddr::Local
+        } else {
+            self.raft_addr_route.get_route_addr().await?
+        };
+        match route_addr {
+            RouteAddr::Local => self.pending_changes_addr.send(cmd).await?,
+            RouteAddr::Remote(_, addr) => {
+                let req = RouterRequest::PendingChangesReq { req: cmd };
+                let request = serde_json::to_string(&req).unwrap_or_default();
+                let payload = PayloadUtils::build_payload("RaftRouteRequest", request);
+                let resp_payload = self.cluster_sender.send_request(addr, payload).await?;
+                let body_vec = resp_payload.body.unwrap_or_default().value;
+                let resp: RouterResponse = serde_json::from_slice(&body_vec)?;
+                match resp {
+                    RouterResponse::PendingChangesResult { result } => Ok(result),
+                    _ => Err(anyhow::anyhow!("response type is error!")),
+                }
+            }
+            RouteAddr::Unknown => Err(anyhow::anyhow!("unknown the raft leader addr!")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_change_transition() {
+        let mut actor = PendingChangesActor::new();
+        let req = SetConfigReq::new(
+            ConfigKey::new("app.yaml", "DEFAULT_GROUP", "dev"),
+            Arc::new("a: 1".to_owned()),
+        );
+        let change = actor.create(req, Arc::new(String::new()), None, 0);
+        assert_eq!(
+            change
+                .to_req(None)
+                .expected_md5
+                .as_deref()
+                .map(|v| v.as_str()),
+            Some("")
+        );
+        let id = change.id;
+        let reviewer = Some(Arc::new("admin".to_owned()));
+
+        //未提交时不能审批
+        assert!(actor
+            .transition(id, PendingChangeAction::Approve, None, None, 1)
+            .is_err());
+        actor
+            .transition(id, PendingChangeAction::Submit, None, None, 1)
+            .unwrap();
+        let change = actor
+            .transition(
+                id,
+                PendingChangeAction::Reject,
+                reviewer.clone(),
+                Some("typo".to_owned()),
+                2,
+            )
+            .unwrap();
+        assert_eq!(change.status, PendingChangeStatus::Rejected);
+        assert_eq!(change.reject_reason.as_deref(), Some("typo"));
+
+        //退回后可重新提交
+        actor
+            .transition(id, PendingChangeAction::Submit, None, None, 3)
+            .unwrap();
+        actor
+            .transition(id, PendingChangeAction::Approve, reviewer, None, 4)
+            .unwrap();
+        let change = actor
+            .transition(id, PendingChangeAction::Apply, None, None, 5)
+            .unwrap();
+        assert_eq!(change.status, PendingChangeStatus::Active);
+        assert!(change.reject_reason.is_none());
+        assert!(actor
+            .transition(id, PendingChangeAction::Apply, None, None, 6)
+            .is_err());
+        assert!(actor
+            .transition(id + 1, PendingChangeAction::Submit, None, None, 6)
+            .is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::core::ConfigKey;
+use crate::utils::get_md5;
+
+/// 清理长时间未轮询的记录的最小间隔
+const CLEAR_INTERVAL_MS: i64 = 60 * 1000;
+/// 记录的最短保留时长
+const MIN_RECORD_TTL_MS: i64 = 10 * 60 * 1000;
+
+#[derive(Default)]
+struct PollState {
+    //(客户端, 监听的配置签名) -> (连续无变更的轮询次数, 最近轮询时间)
+    records: HashMap<(Arc<String>, String), (u32, i64)>,
+    last_clear_time: i64,
+}
+
+///
+/// 长轮询的建议轮询间隔;同一客户端监听的配置连续threshold次轮询都没有变更时,
+/// 建议客户端使用min(max, base * 2^连续无变更次数)秒的轮询间隔,只作为建议不影响服务端行为
+#[derive(Default)]
+pub struct ListenerPollBackoff {
+    base_secs: u64,
+    threshold: u32,
+    max_poll_interval_secs: u64,
+    state: Mutex<PollState>,
+}
+
+impl ListenerPollBackoff {
+    ///
+    /// max_poll_interval_secs为0时不给出建议
+    pub fn new(base_secs: u64, threshold: u32, max_poll_interval_secs: u64) -> Self {
+        Self {
+            base_secs,
+            threshold,
+            max_poll_interval_secs,
+            state: Mutex::new(PollState::default()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_poll_interval_secs > 0 && self.base_secs > 0
+    }
+
+    fn signature(keys: &[ConfigKey]) -> String {
+        let mut keys: Vec<String> = keys.iter().map(|v| v.build_key()).collect();
+        keys.sort();
+        get_md5(&keys.join("\x01"))
+    }
+
+    ///
+    /// 记录一次长轮询的结果,返回建议的轮询间隔(秒)
+    pub fn record(
+        &self,
+        client: &Arc<String>,
+        keys: &[ConfigKey],
+        changed: bool,
+        now: i64,
+    ) -> Option<u64> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let mut state = self.state.lock().ok()?;
+        if now - state.last_clear_time > CLEAR_INTERVAL_MS {
+            let ttl = MIN_RECORD_TTL_MS.max(self.max_poll_interval_secs as i64 * 2000);
+            state
+                .records
+                .retain(|_, (_, poll_time)| now - *poll_time < ttl);
+            state.last_clear_time = now;
+        }
+        let record = state
+            .records
+            .entry((client.clone(), Self::signature(keys)))
+            .or_insert((0, now));
+        record.0 = if changed { 0 } else { record.0 + 1 };
+        record.1 = now;
+        let no_changes = record.0;
+        if no_changes < self.threshold {
+            return None;
+        }
+        let interval = self
+            .base_secs
+            .saturating_mul(1u64.checked_shl(no_changes).unwrap_or(u64::MAX));
+        Some(interval.min(self.max_poll_interval_secs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggested_interval() {
+        let backoff = ListenerPollBackoff::new(30, 3, 600);
+        let client = Arc::new("10.0.0.1".to_owned());
+        let keys = vec![
+            ConfigKey::new("a", "DEFAULT_GROUP", ""),
+            ConfigKey::new("b", "DEFAULT_GROUP", ""),
+        ];
+        assert_eq!(backoff.record(&client, &keys, false, 1), None);
+        assert_eq!(backoff.record(&client, &keys, false, 2), None);
+        assert_eq!(backoff.record(&client, &keys, false, 3), Some(240));
+        assert_eq!(backoff.record(&client, &keys, false, 4), Some(480));
+        assert_eq!(backoff.record(&client, &keys, false, 5), Some(600));
+        //监听顺序不影响签名
+        let reversed: Vec<ConfigKey> = keys.iter().rev().cloned().collect();
+        assert_eq!(backoff.record(&client, &reversed, false, 6), Some(600));
+        //有变更时重新计数
+        assert_eq!(backoff.record(&client, &keys, true, 7), None);
+        assert_eq!(backoff.record(&client, &keys, false, 8), None);
+        assert!(!ListenerPollBackoff::new(30, 3, 0).is_enabled());
+    }
+}
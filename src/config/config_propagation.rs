@@ -0,0 +1,360 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+use crate::grpc::bistream_manage::BiStreamManageCmd;
+use crate::grpc::PayloadUtils;
+use crate::now_millis_i64;
+use crate::raft::cluster::model::{RouterRequest, RouterResponse};
+
+use super::core::{ConfigCmd, ConfigKey, ConfigResult};
+
+/// 追踪记录保留时长
+const TRACE_TTL_MS: i64 = 10 * 60 * 1000;
+const MAX_TRACE_COUNT: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PropagationSubscriber {
+    pub client_id: Arc<String>,
+    pub acked_at: Option<i64>,
+    //客户端连接所在的节点
+    #[serde(default)]
+    pub node_id: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PropagationTrace {
+    pub id: Arc<String>,
+    pub tenant: Arc<String>,
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    pub push_time: Option<i64>,
+    pub subscribers: Vec<PropagationSubscriber>,
+    pub acked_count: usize,
+    //所有订阅者都已确认
+    pub complete: bool,
+    //请求失败的节点,这些节点上的订阅者未被统计
+    #[serde(default)]
+    pub unreachable_nodes: Vec<u64>,
+}
+
+impl PropagationTrace {
+    ///
+    /// 合并其它节点上同一追踪id的记录
+    pub fn merge(&mut self, other: PropagationTrace) {
+        self.push_time = match (self.push_time, other.push_time) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.subscribers.extend(other.subscribers);
+        self.acked_count += other.acked_count;
+        self.unreachable_nodes.extend(other.unreachable_nodes);
+        self.complete =
+            self.acked_count == self.subscribers.len() && self.unreachable_nodes.is_empty();
+    }
+
+    fn set_node_id(mut self, node_id: u64) -> Self {
+        for item in self.subscribers.iter_mut() {
+            item.node_id = node_id;
+        }
+        self
+    }
+}
+
+#[derive(Default)]
+struct TracerInner {
+    traces: HashMap<Arc<String>, (i64, PropagationTrace)>,
+    //推送请求id -> 追踪id
+    requests: HashMap<String, Arc<String>>,
+}
+
+///
+/// 记录配置推送到各订阅客户端的确认情况,只保存在内存中
+#[derive(Default)]
+pub struct PropagationTracer {
+    inner: Mutex<TracerInner>,
+}
+
+impl PropagationTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn create(&self, trace_id: Arc<String>, key: &ConfigKey, now: i64) {
+        if let Ok(mut inner) = self.inner.lock() {
+            Self::clear_expired(&mut inner, now);
+            let trace = PropagationTrace {
+                id: trace_id.clone(),
+                tenant: key.tenant.clone(),
+                group: key.group.clone(),
+                data_id: key.data_id.clone(),
+                ..Default::default()
+            };
+            inner.traces.insert(trace_id, (now, trace));
+        }
+    }
+
+    ///
+    /// 推送时登记请求id及实际推送的客户端
+    pub fn start(
+        &self,
+        trace_id: &Arc<String>,
+        request_id: String,
+        client_ids: Vec<Arc<String>>,
+        now: i64,
+    ) {
+        if let Ok(mut guard) = self.inner.lock() {
+            let inner = &mut *guard;
+            if let Some((_, trace)) = inner.traces.get_mut(trace_id) {
+                trace.push_time = Some(now);
+                trace.subscribers = client_ids
+                    .into_iter()
+                    .map(|client_id| PropagationSubscriber {
+                        client_id,
+                        ..Default::default()
+                    })
+                    .collect();
+                trace.complete = trace.subscribers.is_empty();
+                inner.requests.insert(request_id, trace_id.clone());
+            }
+        }
+    }
+
+    ///
+    /// 记录客户端确认;不属于追踪中的请求时忽略
+    pub fn ack(&self, request_id: &str, client_id: &Arc<String>, now: i64) {
+        if let Ok(mut inner) = self.inner.lock() {
+            let trace_id = match inner.requests.get(request_id) {
+                Some(v) => v.clone(),
+                None => return,
+            };
+            if let Some((_, trace)) = inner.traces.get_mut(&trace_id) {
+                if let Some(item) = trace
+                    .subscribers
+                    .iter_mut()
+                    .find(|v| &v.client_id == client_id && v.acked_at.is_none())
+                {
+                    item.acked_at = Some(now);
+                    trace.acked_count += 1;
+                    trace.complete = trace.acked_count == trace.subscribers.len();
+                }
+            }
+        }
+    }
+
+    pub fn has_pending_request(&self, request_id: &str) -> bool {
+        self.inner
+            .lock()
+            .map(|v| v.requests.contains_key(request_id))
+            .unwrap_or(false)
+    }
+
+    pub fn get(&self, trace_id: &Arc<String>) -> Option<PropagationTrace> {
+        self.inner
+            .lock()
+            .ok()
+            .and_then(|inner| inner.traces.get(trace_id).map(|(_, v)| v.clone()))
+    }
+
+    fn clear_expired(inner: &mut TracerInner, now: i64) {
+        inner
+            .traces
+            .retain(|_, (create_time, _)| now - *create_time < TRACE_TTL_MS);
+        if inner.traces.len() >= MAX_TRACE_COUNT {
+            if let Some(oldest) = inner
+                .traces
+                .iter()
+                .min_by_key(|(_, (create_time, _))| *create_time)
+                .map(|(k, _)| k.clone())
+            {
+                inner.traces.remove(&oldest);
+            }
+        }
+        let trace_ids: HashSet<Arc<String>> = inner.traces.keys().cloned().collect();
+        inner.requests.retain(|_, v| trace_ids.contains(v));
+    }
+}
+
+///
+/// 向本节点订阅该配置的grpc客户端重新推送,并创建追踪记录
+pub async fn start_local_trace(
+    app: &AppShareData,
+    key: ConfigKey,
+    trace_id: Arc<String>,
+) -> anyhow::Result<Option<PropagationTrace>> {
+    let clients = match app
+        .send_config_cmd(ConfigCmd::QueryKeySubscribeClients(key.clone()))
+        .await?
+    {
+        ConfigResult::SubscribeClients(clients) => clients,
+        _ => return Err(anyhow::anyhow!("config result error")),
+    };
+    let tracer = &app.config_propagation_tracer;
+    tracer.create(trace_id.clone(), &key, now_millis_i64());
+    app.bi_stream_manage
+        .send(BiStreamManageCmd::NotifyConfigTrace(
+            key,
+            clients,
+            trace_id.clone(),
+        ))
+        .await??;
+    Ok(tracer
+        .get(&trace_id)
+        .map(|v| v.set_node_id(app.sys_config.raft_node_id)))
+}
+
+async fn send_trace_route(
+    app: &AppShareData,
+    addr: Arc<String>,
+    req: &RouterRequest,
+) -> anyhow::Result<Option<PropagationTrace>> {
+    let request = serde_json::to_string(req)?;
+    let payload = PayloadUtils::build_payload("RaftRouteRequest", request);
+    let resp_payload = app.cluster_sender.send_request(addr, payload).await?;
+    let body_vec = resp_payload.body.unwrap_or_default().value;
+    let resp: RouterResponse = serde_json::from_slice(&body_vec)?;
+    match resp {
+        RouterResponse::ConfigTraceResult { trace } => Ok(trace),
+        _ => Err(anyhow::anyhow!("response type is error!")),
+    }
+}
+
+///
+/// 在集群所有节点上发起追踪,并合并各节点的推送结果
+pub async fn start_cluster_trace(
+    app: &AppShareData,
+    key: ConfigKey,
+) -> anyhow::Result<PropagationTrace> {
+    let trace_id = Arc::new(uuid::Uuid::new_v4().to_string());
+    let mut trace = start_local_trace(app, key.clone(), trace_id.clone())
+        .await?
+        .unwrap_or_default();
+    let req = RouterRequest::ConfigTraceStart {
+        key,
+        trace_id: trace_id.clone(),
+    };
+    merge_remote_traces(app, &mut trace, &req).await;
+    Ok(trace)
+}
+
+///
+/// 查询各节点上的追踪记录并合并;本节点没有记录时返回None
+pub async fn query_cluster_trace(
+    app: &AppShareData,
+    trace_id: Arc<String>,
+) -> Option<PropagationTrace> {
+    let mut trace = app
+        .config_propagation_tracer
+        .get(&trace_id)?
+        .set_node_id(app.sys_config.raft_node_id);
+    let req = RouterRequest::ConfigTraceQuery { trace_id };
+    merge_remote_traces(app, &mut trace, &req).await;
+    Some(trace)
+}
+
+async fn merge_remote_traces(
+    app: &AppShareData,
+    trace: &mut PropagationTrace,
+    req: &RouterRequest,
+) {
+    let nodes = app
+        .naming_node_manage
+        .get_other_valid_nodes()
+        .await
+        .unwrap_or_default();
+    let futures = nodes.into_iter().map(|node| async move {
+        let result = send_trace_route(app, node.addr.clone(), req).await;
+        (node.id, result)
+    });
+    for (node_id, result) in futures_util::future::join_all(futures).await {
+        match result {
+            Ok(Some(other)) => trace.merge(other.set_node_id(node_id)),
+            Ok(None) | Err(_) => trace.merge(PropagationTrace {
+                unreachable_nodes: vec![node_id],
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn propagation_trace() {
+        let tracer = PropagationTracer::new();
+        let key = ConfigKey::new("app.yaml", "DEFAULT_GROUP", "dev");
+        let trace_id = Arc::new("t1".to_owned());
+        tracer.create(trace_id.clone(), &key, 1000);
+        let client_a = Arc::new("a".to_owned());
+        let client_b = Arc::new("b".to_owned());
+        tracer.start(
+            &trace_id,
+            "10".to_owned(),
+            vec![client_a.clone(), client_b.clone()],
+            1001,
+        );
+        assert!(tracer.has_pending_request("10"));
+        tracer.ack("10", &client_a, 1002);
+        tracer.ack("10", &client_a, 1003);
+        tracer.ack("11", &client_b, 1003);
+        let trace = tracer.get(&trace_id).unwrap();
+        assert_eq!(trace.push_time, Some(1001));
+        assert_eq!(trace.acked_count, 1);
+        assert_eq!(trace.subscribers[0].acked_at, Some(1002));
+        assert!(!trace.complete);
+
+        tracer.ack("10", &client_b, 1004);
+        assert!(tracer.get(&trace_id).unwrap().complete);
+
+        //过期后清理
+        tracer.create(Arc::new("t2".to_owned()), &key, 1000 + TRACE_TTL_MS);
+        assert!(tracer.get(&trace_id).is_none());
+        assert!(!tracer.has_pending_request("10"));
+    }
+
+    #[test]
+    fn merge_node_traces() {
+        let subscriber = |client_id: &str, acked_at: Option<i64>| PropagationSubscriber {
+            client_id: Arc::new(client_id.to_owned()),
+            acked_at,
+            ..Default::default()
+        };
+        let mut trace = PropagationTrace {
+            push_time: Some(1002),
+            subscribers: vec![subscriber("a", Some(1003))],
+            acked_count: 1,
+            complete: true,
+            ..Default::default()
+        }
+        .set_node_id(1);
+        trace.merge(
+            PropagationTrace {
+                push_time: Some(1001),
+                subscribers: vec![subscriber("b", None)],
+                ..Default::default()
+            }
+            .set_node_id(2),
+        );
+        assert_eq!(trace.push_time, Some(1001));
+        assert_eq!(trace.subscribers.len(), 2);
+        assert_eq!(trace.subscribers[1].node_id, 2);
+        assert!(!trace.complete);
+
+        let mut trace = PropagationTrace {
+            complete: true,
+            ..Default::default()
+        };
+        trace.merge(PropagationTrace {
+            unreachable_nodes: vec![3],
+            ..Default::default()
+        });
+        //节点不可达时不能认为已全部确认
+        assert!(!trace.complete);
+    }
+}
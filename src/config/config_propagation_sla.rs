@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::config_consumer::ConsumerRegistry;
+use super::config_event::{
+    new_propagation_sla_event_bus, PropagationSlaEventReceiver, PropagationSlaEventSender,
+    PropagationSlaViolationEvent,
+};
+use super::config_read_view::ConfigReadView;
+use super::core::ConfigKey;
+use crate::common::metrics::CONFIG_PROPAGATION_SLA_VIOLATION_COUNTER;
+use crate::now_millis_i64;
+
+/// 系统命名空间下保存各配置传播时限的配置,内容为`{"tenant##group##dataId": 毫秒}`
+pub const PROPAGATION_SLA_KEY: &str = "propagation_sla";
+
+///
+/// 各配置的传播时限设置,设置内容的md5变化时重新解析
+#[derive(Default)]
+pub struct PropagationSlaSettings {
+    md5: Option<Arc<String>>,
+    sla_ms: HashMap<ConfigKey, u64>,
+}
+
+impl PropagationSlaSettings {
+    pub fn update(&mut self, md5: &Arc<String>, content: &str) {
+        if self.md5.as_ref() == Some(md5) {
+            return;
+        }
+        self.md5 = Some(md5.clone());
+        self.sla_ms = match serde_json::from_str(content) {
+            Ok(v) => v,
+            Err(err) => {
+                log::warn!("invalid propagation sla settings: {}", err);
+                HashMap::new()
+            }
+        };
+    }
+
+    pub fn clear(&mut self) {
+        self.md5 = None;
+        self.sla_ms.clear();
+    }
+
+    pub fn sla_ms(&self, key: &ConfigKey) -> Option<u64> {
+        self.sla_ms.get(key).copied().filter(|v| *v > 0)
+    }
+}
+
+///
+/// 配置变更后按时限检查上报心跳的使用方是否都已使用新版本,超时未更新时发布违约事件
+pub struct PropagationSlaMonitor {
+    consumer_registry: Arc<ConsumerRegistry>,
+    event_bus: PropagationSlaEventSender,
+}
+
+impl PropagationSlaMonitor {
+    pub fn new(consumer_registry: Arc<ConsumerRegistry>) -> Self {
+        Self {
+            consumer_registry,
+            event_bus: new_propagation_sla_event_bus(),
+        }
+    }
+
+    pub fn subscribe(&self) -> PropagationSlaEventReceiver {
+        self.event_bus.subscribe()
+    }
+
+    ///
+    /// 到达时限后检查一次;期间配置再次变更时由新的检查负责
+    pub fn schedule(
+        self: &Arc<Self>,
+        key: ConfigKey,
+        md5: Arc<String>,
+        sla_ms: u64,
+        read_view: Arc<ConfigReadView>,
+    ) {
+        let monitor = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(sla_ms)).await;
+            let superseded = read_view.get(&key).map(|v| v.md5.clone()) != Some(md5.clone());
+            if superseded {
+                return;
+            }
+            if let Some(event) = monitor.check(key, md5, sla_ms, now_millis_i64()) {
+                monitor.publish(event);
+            }
+        });
+    }
+
+    pub fn check(
+        &self,
+        key: ConfigKey,
+        md5: Arc<String>,
+        sla_ms: u64,
+        now: i64,
+    ) -> Option<PropagationSlaViolationEvent> {
+        let stale_clients = self.consumer_registry.stale_clients(&key, &md5, now);
+        if stale_clients.is_empty() {
+            return None;
+        }
+        Some(PropagationSlaViolationEvent {
+            key,
+            md5,
+            sla_ms,
+            stale_clients,
+            detect_time: now,
+        })
+    }
+
+    fn publish(&self, event: PropagationSlaViolationEvent) {
+        log::warn!(
+            "config propagation sla violation, key:{}, sla_ms:{}, stale clients:{}",
+            &event.key,
+            event.sla_ms,
+            event.stale_clients.len()
+        );
+        CONFIG_PROPAGATION_SLA_VIOLATION_COUNTER
+            .with_label_values(&[event.key.tenant.as_str()])
+            .inc();
+        //没有订阅方时忽略发送失败
+        self.event_bus.send(event).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config_consumer::ConsumerRecord;
+
+    #[test]
+    fn propagation_sla_settings() {
+        let mut settings = PropagationSlaSettings::default();
+        settings.update(
+            &Arc::new("1".to_owned()),
+            r###"{"dev##DEFAULT_GROUP##app.yaml":3000,"##DEFAULT_GROUP##b":0}"###,
+        );
+        assert_eq!(
+            settings.sla_ms(&ConfigKey::new("app.yaml", "DEFAULT_GROUP", "dev")),
+            Some(3000)
+        );
+        assert_eq!(
+            settings.sla_ms(&ConfigKey::new("b", "DEFAULT_GROUP", "")),
+            None
+        );
+        settings.update(&Arc::new("2".to_owned()), "invalid");
+        assert_eq!(
+            settings.sla_ms(&ConfigKey::new("app.yaml", "DEFAULT_GROUP", "dev")),
+            None
+        );
+    }
+
+    #[test]
+    fn propagation_sla_check() {
+        let registry = Arc::new(ConsumerRegistry::new(1000));
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let new_md5 = Arc::new("new".to_owned());
+        registry.heartbeat(ConsumerRecord {
+            client_id: Arc::new("s1".to_owned()),
+            client_ip: None,
+            config_keys: vec![key.clone()],
+            config_md5s: HashMap::from([(key.clone(), Arc::new("old".to_owned()))]),
+            last_heartbeat: 0,
+        });
+        registry.heartbeat(ConsumerRecord {
+            client_id: Arc::new("s2".to_owned()),
+            client_ip: None,
+            config_keys: vec![key.clone()],
+            config_md5s: HashMap::from([(key.clone(), new_md5.clone())]),
+            last_heartbeat: 0,
+        });
+        let monitor = PropagationSlaMonitor::new(registry.clone());
+        let event = monitor
+            .check(key.clone(), new_md5.clone(), 1000, 100)
+            .unwrap();
+        assert_eq!(event.stale_clients, vec![Arc::new("s1".to_owned())]);
+
+        registry.heartbeat(ConsumerRecord {
+            client_id: Arc::new("s1".to_owned()),
+            client_ip: None,
+            config_keys: vec![key.clone()],
+            config_md5s: HashMap::from([(key.clone(), new_md5.clone())]),
+            last_heartbeat: 200,
+        });
+        assert!(monitor.check(key, new_md5, 1000, 300).is_none());
+    }
+}
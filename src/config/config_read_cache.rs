@@ -0,0 +1,89 @@
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use lru::LruCache;
+
+use crate::common::metrics::CONFIG_READ_CACHE_COUNTER;
+
+use super::core::ConfigKey;
+
+#[derive(Debug, Clone)]
+pub struct CachedEntry {
+    pub content: Arc<String>,
+    pub md5: Arc<String>,
+    pub config_type: Option<Arc<String>>,
+    pub fetched_at: Instant,
+}
+
+///
+/// 允许读取旧值的配置缓存,供对实时性不敏感的客户端使用
+pub struct ConfigReadCache {
+    cache: Mutex<LruCache<ConfigKey, CachedEntry>>,
+    ttl: Duration,
+}
+
+impl ConfigReadCache {
+    pub fn new(capacity: usize, ttl_seconds: u64) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::new(1).unwrap());
+        Self {
+            cache: Mutex::new(LruCache::new(capacity)),
+            ttl: Duration::from_secs(ttl_seconds),
+        }
+    }
+
+    ///
+    /// 取缓存时长不超过max_staleness(且不超过ttl)的缓存值
+    pub fn get(&self, key: &ConfigKey, max_staleness: Duration) -> Option<CachedEntry> {
+        let max_age = max_staleness.min(self.ttl);
+        let v = self
+            .cache
+            .lock()
+            .ok()
+            .and_then(|mut cache| cache.get(key).cloned())
+            .filter(|v| v.fetched_at.elapsed() <= max_age);
+        let result = if v.is_some() { "hit" } else { "miss" };
+        CONFIG_READ_CACHE_COUNTER.with_label_values(&[result]).inc();
+        v
+    }
+
+    pub fn put(&self, key: ConfigKey, entry: CachedEntry) {
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.put(key, entry);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_read() {
+        let cache = ConfigReadCache::new(1, 60);
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        assert!(cache.get(&key, Duration::from_secs(30)).is_none());
+        cache.put(
+            key.clone(),
+            CachedEntry {
+                content: Arc::new("v".to_owned()),
+                md5: Arc::new("md5".to_owned()),
+                config_type: None,
+                fetched_at: Instant::now(),
+            },
+        );
+        assert!(cache.get(&key, Duration::from_secs(30)).is_some());
+        assert!(cache.get(&key, Duration::ZERO).is_none());
+        //容量为1时旧值被淘汰
+        cache.put(
+            ConfigKey::new("b", "DEFAULT_GROUP", ""),
+            CachedEntry {
+                content: Arc::new("v".to_owned()),
+                md5: Arc::new("md5".to_owned()),
+                config_type: None,
+                fetched_at: Instant::now(),
+            },
+        );
+        assert!(cache.get(&key, Duration::from_secs(30)).is_none());
+    }
+}
@@ -0,0 +1,147 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::core::ConfigKey;
+
+///
+/// 限制同一配置的并发读取数,避免滚动重启时大量实例同时读取热点配置;并发数为0时不限制
+pub struct ConfigReadLimiter {
+    limit: AtomicUsize,
+    //信号量及其创建时的并发数
+    semaphores: DashMap<ConfigKey, (Arc<Semaphore>, usize)>,
+}
+
+impl ConfigReadLimiter {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit: AtomicUsize::new(limit),
+            semaphores: DashMap::new(),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.load(Ordering::Relaxed)
+    }
+
+    ///
+    /// 修改后对新建的信号量生效,正在读取的配置读取结束后按新并发数限制
+    pub fn set_limit(&self, limit: usize) {
+        self.limit.store(limit, Ordering::Relaxed);
+    }
+
+    ///
+    /// 不排队等待,超过并发数时返回None
+    pub fn try_acquire(&self, key: &ConfigKey) -> Option<ConfigReadPermit<'_>> {
+        let limit = self.limit();
+        if limit == 0 {
+            return Some(ConfigReadPermit {
+                limiter: self,
+                key: key.clone(),
+                permit: None,
+            });
+        }
+        //持有entry锁时获取许可,与释放时的检查并移除互斥,避免在即将被移除的信号量上获取许可
+        let entry = self
+            .semaphores
+            .entry(key.clone())
+            .or_insert_with(|| (Arc::new(Semaphore::new(limit)), limit));
+        let limit = entry.1;
+        let permit = entry.0.clone().try_acquire_owned().ok()?;
+        drop(entry);
+        Some(ConfigReadPermit {
+            limiter: self,
+            key: key.clone(),
+            permit: Some((permit, limit)),
+        })
+    }
+
+    pub fn key_count(&self) -> usize {
+        self.semaphores.len()
+    }
+}
+
+///
+/// 读取结束时释放;配置没有其它读取时移除对应的信号量
+pub struct ConfigReadPermit<'a> {
+    limiter: &'a ConfigReadLimiter,
+    key: ConfigKey,
+    permit: Option<(OwnedSemaphorePermit, usize)>,
+}
+
+impl Drop for ConfigReadPermit<'_> {
+    fn drop(&mut self) {
+        if let Some((permit, limit)) = self.permit.take() {
+            drop(permit);
+            //检查与移除在同一个entry锁内完成
+            self.limiter
+                .semaphores
+                .remove_if(&self.key, |_, v| v.0.available_permits() == limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limit_reads_per_key() {
+        let limiter = ConfigReadLimiter::new(2);
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let b = ConfigKey::new("b", "DEFAULT_GROUP", "");
+        let p1 = limiter.try_acquire(&a).unwrap();
+        let p2 = limiter.try_acquire(&a).unwrap();
+        assert!(limiter.try_acquire(&a).is_none());
+        assert!(limiter.try_acquire(&b).is_some());
+        drop(p1);
+        let p3 = limiter.try_acquire(&a).unwrap();
+        drop(p2);
+        drop(p3);
+        assert_eq!(limiter.key_count(), 0);
+    }
+
+    #[test]
+    fn change_limit() {
+        let limiter = ConfigReadLimiter::new(1);
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let p1 = limiter.try_acquire(&a).unwrap();
+        limiter.set_limit(0);
+        assert!(limiter.try_acquire(&a).is_some());
+        drop(p1);
+        assert_eq!(limiter.key_count(), 0);
+        limiter.set_limit(1);
+        let _p2 = limiter.try_acquire(&a).unwrap();
+        assert!(limiter.try_acquire(&a).is_none());
+    }
+
+    #[test]
+    fn concurrent_acquire_release() {
+        let limiter = Arc::new(ConfigReadLimiter::new(2));
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let active = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let limiter = limiter.clone();
+                let a = a.clone();
+                let active = active.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..2000 {
+                        if let Some(_permit) = limiter.try_acquire(&a) {
+                            let n = active.fetch_add(1, Ordering::SeqCst) + 1;
+                            //释放与获取交错时也不能超过并发数
+                            assert!(n <= 2);
+                            active.fetch_sub(1, Ordering::SeqCst);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert_eq!(limiter.key_count(), 0);
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+
+use super::core::{ConfigKey, ConfigValue};
+
+#[derive(Debug, Clone)]
+pub struct ConfigReadValue {
+    pub content: Arc<String>,
+    pub md5: Arc<String>,
+    pub config_type: Option<Arc<String>>,
+    pub desc: Option<Arc<String>>,
+    pub detected_type: Option<Arc<String>>,
+    //已归档时content为空
+    pub archive_uri: Option<Arc<String>>,
+    //最近写入时间,取自raft提案中的写入时间,各节点一致
+    pub last_modified: i64,
+}
+
+impl From<&ConfigValue> for ConfigReadValue {
+    fn from(value: &ConfigValue) -> Self {
+        Self {
+            content: value.content.clone(),
+            md5: value.md5.clone(),
+            config_type: value.config_type.clone(),
+            desc: value.desc.clone(),
+            detected_type: value.detected_type.clone(),
+            archive_uri: value.archive_uri.clone(),
+            last_modified: value.last_modified,
+        }
+    }
+}
+
+//持久化HashMap,写入时复制只涉及变更路径上的节点,不复制整个map
+type ConfigReadMap = im::HashMap<ConfigKey, Arc<ConfigReadValue>>;
+
+///
+/// 配置的只读视图,读取时无锁且不经过ConfigActor;
+/// 只由ConfigActor写入,写入时基于当前版本生成新版本后整体替换
+pub struct ConfigReadView {
+    inner: ArcSwap<ConfigReadMap>,
+    //别名到目标配置
+    aliases: ArcSwap<HashMap<ConfigKey, ConfigKey>>,
+    //已删除待清除的命名空间,其下的配置视为不存在
+    deleted_namespaces: ArcSwap<HashSet<String>>,
+}
+
+impl Default for ConfigReadView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigReadView {
+    pub fn new() -> Self {
+        Self {
+            inner: ArcSwap::from_pointee(ConfigReadMap::new()),
+            aliases: ArcSwap::from_pointee(HashMap::new()),
+            deleted_namespaces: ArcSwap::from_pointee(HashSet::new()),
+        }
+    }
+
+    pub fn get(&self, key: &ConfigKey) -> Option<Arc<ConfigReadValue>> {
+        if self.is_deleted_namespace(&key.tenant) {
+            return None;
+        }
+        self.inner.load().get(key).cloned()
+    }
+
+    fn is_deleted_namespace(&self, tenant: &str) -> bool {
+        let deleted = self.deleted_namespaces.load();
+        !deleted.is_empty() && deleted.contains(tenant)
+    }
+
+    pub fn resolve_alias(&self, key: &ConfigKey) -> Option<ConfigKey> {
+        self.aliases.load().get(key).cloned()
+    }
+
+    ///
+    /// key为别名时读取目标配置
+    pub fn get_by_alias(&self, key: &ConfigKey) -> Option<Arc<ConfigReadValue>> {
+        match self.resolve_alias(key) {
+            Some(target) => self.get(&target),
+            None => self.get(key),
+        }
+    }
+
+    ///
+    /// 监听比较使用的配置:别名读取目标配置,开启分组默认配置时不存在的配置读取分组默认配置
+    pub fn get_effective(
+        &self,
+        key: &ConfigKey,
+        group_default_enabled: bool,
+    ) -> Option<Arc<ConfigReadValue>> {
+        self.get_by_alias(key).or_else(|| {
+            key.group_default_key()
+                .filter(|_| group_default_enabled)
+                .and_then(|default_key| self.get(&default_key))
+        })
+    }
+
+    pub fn store_aliases(&self, aliases: HashMap<ConfigKey, ConfigKey>) {
+        self.aliases.store(Arc::new(aliases));
+    }
+
+    pub fn store_deleted_namespaces(&self, namespaces: HashSet<String>) {
+        self.deleted_namespaces.store(Arc::new(namespaces));
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.load().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.load().is_empty()
+    }
+
+    ///
+    /// 返回不存在的配置
+    pub fn missing_keys(&self, keys: &[ConfigKey]) -> Vec<ConfigKey> {
+        let map = self.inner.load();
+        keys.iter()
+            .filter(|key| !map.contains_key(*key) || self.is_deleted_namespace(&key.tenant))
+            .cloned()
+            .collect()
+    }
+
+    pub fn update(&self, key: ConfigKey, value: Option<Arc<ConfigReadValue>>) {
+        let mut map = ConfigReadMap::clone(&self.inner.load());
+        if let Some(value) = value {
+            map.insert(key, value);
+        } else {
+            map.remove(&key);
+        }
+        self.inner.store(Arc::new(map));
+    }
+
+    ///
+    /// 一次替换多个配置,读取方不会看到只更新了部分配置的视图
+    pub fn update_batch(&self, values: Vec<(ConfigKey, Option<Arc<ConfigReadValue>>)>) {
+        let mut map = ConfigReadMap::clone(&self.inner.load());
+        for (key, value) in values {
+            match value {
+                Some(value) => {
+                    map.insert(key, value);
+                }
+                None => {
+                    map.remove(&key);
+                }
+            }
+        }
+        self.inner.store(Arc::new(map));
+    }
+
+    pub fn store_all(&self, map: HashMap<ConfigKey, Arc<ConfigReadValue>>) {
+        self.inner.store(Arc::new(map.into_iter().collect()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(content: &str) -> Arc<ConfigReadValue> {
+        Arc::new(ConfigReadValue {
+            content: Arc::new(content.to_owned()),
+            md5: Arc::new(String::new()),
+            config_type: None,
+            desc: None,
+            detected_type: None,
+            archive_uri: None,
+            last_modified: 0,
+        })
+    }
+
+    #[test]
+    fn update_keeps_loaded_snapshot() {
+        let view = ConfigReadView::new();
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let b = ConfigKey::new("b", "DEFAULT_GROUP", "dev");
+        view.store_all(HashMap::from([(a.clone(), value("1"))]));
+        let snapshot = view.inner.load_full();
+        view.update_batch(vec![
+            (a.clone(), Some(value("2"))),
+            (b.clone(), Some(value("3"))),
+        ]);
+        //已读取的版本不受后续写入影响
+        assert_eq!(snapshot.get(&a).unwrap().content.as_str(), "1");
+        assert!(snapshot.get(&b).is_none());
+        assert_eq!(view.get(&a).unwrap().content.as_str(), "2");
+        view.update(a.clone(), None);
+        assert!(view.get(&a).is_none());
+        assert_eq!(view.len(), 1);
+
+        view.store_deleted_namespaces(HashSet::from(["dev".to_owned()]));
+        assert!(view.get(&b).is_none());
+        assert_eq!(view.missing_keys(std::slice::from_ref(&b)), vec![b]);
+    }
+}
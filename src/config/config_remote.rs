@@ -0,0 +1,219 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+pub enum RemoteFetchResult {
+    NotModified,
+    Content {
+        content: String,
+        etag: Option<String>,
+    },
+}
+
+///
+/// 从远程地址拉取配置内容,支持 http/https 与 s3://bucket/key
+pub struct RemoteConfigFetcher;
+
+impl RemoteConfigFetcher {
+    pub async fn fetch(
+        url: &str,
+        etag: Option<&str>,
+        max_content: usize,
+    ) -> anyhow::Result<RemoteFetchResult> {
+        if let Some(path) = url.strip_prefix("s3://") {
+            return Self::fetch_s3(path, etag, max_content).await;
+        }
+        let target = reqwest::Url::parse(url)?;
+        if target.scheme() != "http" && target.scheme() != "https" {
+            return Err(anyhow::anyhow!(
+                "unsupported url scheme: {}",
+                target.scheme()
+            ));
+        }
+        let (host, addr) = Self::resolve_target_host(&target).await?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            //禁止重定向,避免绕过地址校验
+            .redirect(reqwest::redirect::Policy::none())
+            //只连接已校验的地址,避免请求时重新解析域名得到内网地址
+            .resolve(&host, addr)
+            .build()?;
+        let mut req = client.get(target);
+        if let Some(etag) = etag {
+            req = req.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        let mut resp = req.send().await?;
+        if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(RemoteFetchResult::NotModified);
+        }
+        if !resp.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "fetch remote config error, status: {}",
+                resp.status()
+            ));
+        }
+        if resp.content_length().unwrap_or_default() as usize > max_content {
+            return Err(anyhow::anyhow!("invalid content, over {}", max_content));
+        }
+        let etag = resp
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_owned());
+        //没有Content-Length时按已读取的长度判断,超过限制即停止读取
+        let mut data = vec![];
+        while let Some(chunk) = resp.chunk().await? {
+            Self::append_limited(&mut data, &chunk, max_content)?;
+        }
+        let content = String::from_utf8(data)?;
+        Ok(RemoteFetchResult::Content { content, etag })
+    }
+
+    async fn fetch_s3(
+        path: &str,
+        etag: Option<&str>,
+        max_content: usize,
+    ) -> anyhow::Result<RemoteFetchResult> {
+        let (bucket, key) = match path.split_once('/') {
+            Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => (bucket, key),
+            _ => return Err(anyhow::anyhow!("invalid s3 url: s3://{}", path)),
+        };
+        let sdk_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        let client = aws_sdk_s3::Client::new(&sdk_config);
+        let resp = client.get_object().bucket(bucket).key(key).send().await?;
+        let remote_etag = resp.e_tag().map(|v| v.to_owned());
+        if etag.is_some() && remote_etag.as_deref() == etag {
+            return Ok(RemoteFetchResult::NotModified);
+        }
+        if resp.content_length().unwrap_or_default() as usize > max_content {
+            return Err(anyhow::anyhow!("invalid content, over {}", max_content));
+        }
+        let mut body = resp.body;
+        let mut data = vec![];
+        while let Some(chunk) = body.try_next().await? {
+            Self::append_limited(&mut data, &chunk, max_content)?;
+        }
+        let content = String::from_utf8(data)?;
+        Ok(RemoteFetchResult::Content {
+            content,
+            etag: remote_etag,
+        })
+    }
+
+    fn append_limited(data: &mut Vec<u8>, chunk: &[u8], max_content: usize) -> anyhow::Result<()> {
+        if data.len() + chunk.len() > max_content {
+            return Err(anyhow::anyhow!("invalid content, over {}", max_content));
+        }
+        data.extend_from_slice(chunk);
+        Ok(())
+    }
+
+    ///
+    /// 拒绝访问本机及内网地址,防止SSRF;域名的所有解析结果都需是公网地址,返回用于连接的地址
+    async fn resolve_target_host(target: &reqwest::Url) -> anyhow::Result<(String, SocketAddr)> {
+        let host = target
+            .host_str()
+            .ok_or_else(|| anyhow::anyhow!("url host is empty"))?;
+        let port = target.port_or_known_default().unwrap_or(80);
+        //ipv6地址的host带有方括号
+        let lookup_host = host.trim_start_matches('[').trim_end_matches(']');
+        let mut target_addr = None;
+        for addr in tokio::net::lookup_host((lookup_host, port)).await? {
+            if !Self::is_public_ip(&addr.ip()) {
+                return Err(anyhow::anyhow!("url host is not allowed: {}", host));
+            }
+            target_addr.get_or_insert(addr);
+        }
+        match target_addr {
+            Some(addr) => Ok((lookup_host.to_owned(), addr)),
+            None => Err(anyhow::anyhow!("url host can not be resolved: {}", host)),
+        }
+    }
+
+    fn is_public_ipv4(v: &Ipv4Addr) -> bool {
+        let [a, b, c, _] = v.octets();
+        !(v.is_loopback()
+            || v.is_private()
+            || v.is_link_local()
+            || v.is_unspecified()
+            || v.is_broadcast()
+            || v.is_multicast()
+            || v.is_documentation()
+            //0.0.0.0/8
+            || a == 0
+            //100.64.0.0/10 运营商级NAT
+            || (a == 100 && (b & 0xc0) == 64)
+            //192.0.0.0/24 协议分配
+            || (a == 192 && b == 0 && c == 0)
+            //198.18.0.0/15 基准测试
+            || (a == 198 && (b & 0xfe) == 18)
+            //240.0.0.0/4 保留地址
+            || a >= 240)
+    }
+
+    fn is_public_ip(ip: &IpAddr) -> bool {
+        match ip {
+            IpAddr::V4(v) => Self::is_public_ipv4(v),
+            IpAddr::V6(v) => {
+                let segments = v.segments();
+                //::ffff:0:0/96 ipv4映射地址与::/96 ipv4兼容地址按内嵌的ipv4地址判断
+                if let Some(v4) = v.to_ipv4() {
+                    return !v.is_loopback() && !v.is_unspecified() && Self::is_public_ipv4(&v4);
+                }
+                //64:ff9b::/96 NAT64地址
+                if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                    let [a, b] = segments[6].to_be_bytes();
+                    let [c, d] = segments[7].to_be_bytes();
+                    return Self::is_public_ipv4(&Ipv4Addr::new(a, b, c, d));
+                }
+                let first = segments[0];
+                !(v.is_loopback()
+                    || v.is_unspecified()
+                    || v.is_multicast()
+                    //fc00::/7 唯一本地地址
+                    || (first & 0xfe00) == 0xfc00
+                    //fe80::/10 链路本地地址, fec0::/10 站点本地地址
+                    || (first & 0xffc0) == 0xfe80
+                    || (first & 0xffc0) == 0xfec0
+                    //2001:db8::/32 文档地址
+                    || (first == 0x2001 && segments[1] == 0x0db8))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_public(ip: &str) -> bool {
+        RemoteConfigFetcher::is_public_ip(&ip.parse().unwrap())
+    }
+
+    #[test]
+    fn public_ip() {
+        assert!(is_public("8.8.8.8"));
+        assert!(is_public("2606:4700::1111"));
+        assert!(!is_public("127.0.0.1"));
+        assert!(!is_public("10.1.2.3"));
+        assert!(!is_public("100.64.0.1"));
+        assert!(!is_public("169.254.169.254"));
+        assert!(!is_public("198.18.0.1"));
+        assert!(!is_public("240.0.0.1"));
+        assert!(!is_public("::1"));
+        assert!(!is_public("::ffff:127.0.0.1"));
+        assert!(!is_public("::ffff:169.254.169.254"));
+        assert!(is_public("::ffff:8.8.8.8"));
+        assert!(!is_public("64:ff9b::a00:1"));
+        assert!(!is_public("fd00::1"));
+        assert!(!is_public("fe80::1"));
+    }
+
+    #[test]
+    fn append_over_limit() {
+        let mut data = vec![];
+        RemoteConfigFetcher::append_limited(&mut data, b"abc", 5).unwrap();
+        RemoteConfigFetcher::append_limited(&mut data, b"de", 5).unwrap();
+        assert!(RemoteConfigFetcher::append_limited(&mut data, b"f", 5).is_err());
+        assert_eq!(data, b"abcde");
+    }
+}
@@ -0,0 +1,131 @@
+use serde_json::{Map, Value};
+
+/// 渲染上下文配置的dataId,与被渲染配置同group、同命名空间
+pub const RENDER_CONTEXT_DATA_ID: &str = "__render_context__";
+
+/// 请求参数中作为渲染上下文的前缀,去掉前缀后作为变量名
+const QUERY_CTX_PREFIX: &str = "ctx_";
+
+/// 默认可作为渲染上下文的环境变量
+pub const DEFAULT_RENDER_ENVS: &str = "RNACOS_REGION,RNACOS_ZONE";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderEngine {
+    Jinja,
+}
+
+impl RenderEngine {
+    pub fn new_by_value(v: &str) -> Option<Self> {
+        match v {
+            "jinja" => Some(Self::Jinja),
+            _ => None,
+        }
+    }
+}
+
+///
+/// 读取允许暴露给模板的环境变量,启动时读取一次;names为逗号分隔的变量名
+pub fn load_render_envs(names: &str) -> Vec<(String, String)> {
+    names
+        .split(',')
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .filter_map(|k| std::env::var(k).ok().map(|v| (k.to_owned(), v)))
+        .collect()
+}
+
+pub struct ConfigRenderer;
+
+impl ConfigRenderer {
+    ///
+    /// 构建渲染上下文,后者覆盖前者:允许暴露的环境变量、渲染上下文配置、ctx_前缀请求参数
+    pub fn build_context<E, Q>(
+        envs: E,
+        render_context: Option<&str>,
+        query: Q,
+    ) -> anyhow::Result<Map<String, Value>>
+    where
+        E: IntoIterator<Item = (String, String)>,
+        Q: IntoIterator<Item = (String, String)>,
+    {
+        let mut context = Map::new();
+        for (k, v) in envs {
+            context.insert(k, Value::String(v));
+        }
+        if let Some(content) = render_context {
+            match serde_json::from_str(content)? {
+                Value::Object(map) => context.extend(map),
+                _ => return Err(anyhow::anyhow!("render context is not a json object")),
+            }
+        }
+        for (k, v) in query {
+            if let Some(name) = k.strip_prefix(QUERY_CTX_PREFIX) {
+                if !name.is_empty() {
+                    context.insert(name.to_owned(), Value::String(v));
+                }
+            }
+        }
+        Ok(context)
+    }
+
+    ///
+    /// 配置内容不是html,渲染时不转义变量值;模板不能加载其它模板
+    pub fn render(
+        engine: RenderEngine,
+        content: &str,
+        context: &Map<String, Value>,
+    ) -> anyhow::Result<String> {
+        match engine {
+            RenderEngine::Jinja => {
+                let mut env = minijinja::Environment::new();
+                env.set_auto_escape_callback(|_| minijinja::AutoEscape::None);
+                Ok(env.render_str(content, context)?)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_with_context_priority() {
+        let envs = vec![
+            ("RNACOS_REGION".to_owned(), "cn".to_owned()),
+            ("RNACOS_ZONE".to_owned(), "a".to_owned()),
+        ];
+        let query = vec![
+            ("ctx_RNACOS_ZONE".to_owned(), "b".to_owned()),
+            ("ctx_name".to_owned(), "a&b<c>".to_owned()),
+            ("dataId".to_owned(), "app".to_owned()),
+        ];
+        let context = ConfigRenderer::build_context(
+            envs,
+            Some(r#"{"db":{"host":"127.0.0.1"},"RNACOS_REGION":"us"}"#),
+            query,
+        )
+        .unwrap();
+        assert!(!context.contains_key("dataId"));
+        let content =
+            "region={{RNACOS_REGION}}\nzone={{RNACOS_ZONE}}\nhost={{db.host}}\nname={{name}}";
+        let v = ConfigRenderer::render(RenderEngine::Jinja, content, &context).unwrap();
+        assert_eq!(v, "region=us\nzone=b\nhost=127.0.0.1\nname=a&b<c>");
+    }
+
+    #[test]
+    fn render_envs_allowlist() {
+        std::env::set_var("RNACOS_RENDER_TEST_REGION", "cn");
+        let envs = load_render_envs(" RNACOS_RENDER_TEST_REGION,,RNACOS_RENDER_TEST_MISSING");
+        assert_eq!(
+            envs,
+            vec![("RNACOS_RENDER_TEST_REGION".to_owned(), "cn".to_owned())]
+        );
+    }
+
+    #[test]
+    fn invalid_render_context() {
+        assert!(ConfigRenderer::build_context(vec![], Some("[1]"), vec![]).is_err());
+        assert!(ConfigRenderer::build_context(vec![], Some("{"), vec![]).is_err());
+    }
+}
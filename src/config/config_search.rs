@@ -0,0 +1,415 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+use tantivy::collector::{Count, DocSetCollector, TopDocs};
+use tantivy::directory::MmapDirectory;
+use tantivy::query::{AllQuery, BooleanQuery, Occur, Query, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, Schema, Value, STORED, STRING, TEXT};
+use tantivy::{doc, Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+
+use super::core::ConfigKey;
+
+/// 索引写入缓冲,配置内容较小,单线程写入即可
+const INDEX_WRITER_MEMORY_BYTES: usize = 30_000_000;
+/// 定时提交索引写入的间隔
+const INDEX_COMMIT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSearchItem {
+    pub tenant: Arc<String>,
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    pub score: f64,
+}
+
+struct SearchFields {
+    id: Field,
+    tenant: Field,
+    group: Field,
+    data_id: Field,
+    content: Field,
+}
+
+impl SearchFields {
+    fn build_schema() -> Schema {
+        let mut builder = Schema::builder();
+        builder.add_text_field("id", STRING | STORED);
+        builder.add_text_field("tenant", STRING | STORED);
+        builder.add_text_field("group", TEXT | STORED);
+        builder.add_text_field("data_id", TEXT | STORED);
+        builder.add_text_field("content", TEXT);
+        builder.build()
+    }
+
+    fn new(schema: &Schema) -> anyhow::Result<Self> {
+        Ok(Self {
+            id: schema.get_field("id")?,
+            tenant: schema.get_field("tenant")?,
+            group: schema.get_field("group")?,
+            data_id: schema.get_field("data_id")?,
+            content: schema.get_field("content")?,
+        })
+    }
+}
+
+///
+/// 基于tantivy的配置内容倒排索引,按(tenant,group,dataId,content)分词,用于全文搜索
+pub struct ConfigSearchIndex {
+    fields: SearchFields,
+    writer: IndexWriter,
+    reader: IndexReader,
+    //有未提交的写入
+    dirty: bool,
+}
+
+impl ConfigSearchIndex {
+    ///
+    /// 打开或创建持久化在目录中的索引
+    pub fn open(path: &str) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(path)?;
+        let directory = MmapDirectory::open(path)?;
+        Self::new(Index::open_or_create(
+            directory,
+            SearchFields::build_schema(),
+        )?)
+    }
+
+    pub fn new_in_ram() -> anyhow::Result<Self> {
+        Self::new(Index::create_in_ram(SearchFields::build_schema()))
+    }
+
+    fn new(index: Index) -> anyhow::Result<Self> {
+        let fields = SearchFields::new(&index.schema())?;
+        let writer = index.writer_with_num_threads(1, INDEX_WRITER_MEMORY_BYTES)?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        Ok(Self {
+            fields,
+            writer,
+            reader,
+            dirty: false,
+        })
+    }
+
+    ///
+    /// 按非字母数字字符切分并转为小写,与tantivy默认分词一致
+    pub fn tokenize(text: &str) -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_lowercase())
+            .collect()
+    }
+
+    pub fn index(&mut self, key: &ConfigKey, content: &str) -> anyhow::Result<()> {
+        self.remove(key);
+        self.writer.add_document(doc!(
+            self.fields.id => key.build_key(),
+            self.fields.tenant => key.tenant.as_str(),
+            self.fields.group => key.group.as_str(),
+            self.fields.data_id => key.data_id.as_str(),
+            self.fields.content => content,
+        ))?;
+        Ok(())
+    }
+
+    pub fn remove(&mut self, key: &ConfigKey) {
+        self.writer
+            .delete_term(Term::from_field_text(self.fields.id, &key.build_key()));
+        self.dirty = true;
+    }
+
+    ///
+    /// 删除不在配置列表中的索引;启动加载配置后调用,清理上次运行后已删除配置的残留索引
+    pub fn retain(&mut self, keys: &HashSet<ConfigKey>) -> anyhow::Result<usize> {
+        self.commit()?;
+        let ids: HashSet<String> = keys.iter().map(|v| v.build_key()).collect();
+        let searcher = self.reader.searcher();
+        let mut removed = 0;
+        for address in searcher.search(&AllQuery, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            if let Some(id) = doc.get_first(self.fields.id).and_then(|v| v.as_str()) {
+                if !ids.contains(id) {
+                    self.writer
+                        .delete_term(Term::from_field_text(self.fields.id, id));
+                    removed += 1;
+                }
+            }
+        }
+        self.dirty = removed > 0;
+        self.commit()?;
+        Ok(removed)
+    }
+
+    pub fn commit(&mut self) -> anyhow::Result<()> {
+        if self.dirty {
+            self.writer.commit()?;
+            self.reader.reload()?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.reader.searcher().num_docs() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///
+    /// 返回包含全部查询词的配置,按BM25得分降序排列
+    pub fn search(
+        &self,
+        query: &str,
+        tenant: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> anyhow::Result<(usize, Vec<ConfigSearchItem>)> {
+        let tokens: HashSet<String> = Self::tokenize(query).into_iter().collect();
+        if tokens.is_empty() || limit == 0 {
+            return Ok((0, vec![]));
+        }
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = tokens
+            .iter()
+            .map(|token| {
+                //任一字段包含该词即可
+                let fields: Vec<(Occur, Box<dyn Query>)> =
+                    [self.fields.group, self.fields.data_id, self.fields.content]
+                        .iter()
+                        .map(|field| {
+                            let query: Box<dyn Query> = Box::new(TermQuery::new(
+                                Term::from_field_text(*field, token),
+                                IndexRecordOption::WithFreqs,
+                            ));
+                            (Occur::Should, query)
+                        })
+                        .collect();
+                let query: Box<dyn Query> = Box::new(BooleanQuery::new(fields));
+                (Occur::Must, query)
+            })
+            .collect();
+        if let Some(tenant) = tenant {
+            clauses.push((
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(self.fields.tenant, tenant),
+                    IndexRecordOption::Basic,
+                )),
+            ));
+        }
+        let searcher = self.reader.searcher();
+        let (total, top_docs) = searcher.search(
+            &BooleanQuery::new(clauses),
+            &(Count, TopDocs::with_limit(limit).and_offset(offset)),
+        )?;
+        let mut list = Vec::with_capacity(top_docs.len());
+        for (score, address) in top_docs {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let get_text = |field: Field| {
+                Arc::new(
+                    doc.get_first(field)
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_owned(),
+                )
+            };
+            list.push(ConfigSearchItem {
+                tenant: get_text(self.fields.tenant),
+                group: get_text(self.fields.group),
+                data_id: get_text(self.fields.data_id),
+                score: score as f64,
+            });
+        }
+        Ok((total, list))
+    }
+}
+
+///
+/// 在独立actor中维护搜索索引,避免分词影响ConfigActor处理写请求;
+/// 索引持久化在配置数据库目录下,写入按固定间隔批量提交
+pub struct IndexerActor {
+    index: Option<ConfigSearchIndex>,
+}
+
+impl IndexerActor {
+    pub fn new(db_dir: &str) -> Self {
+        let path = format!("{}/search_index", db_dir.trim_end_matches('/'));
+        let index = match ConfigSearchIndex::open(&path) {
+            Ok(v) => Some(v),
+            Err(err) => {
+                log::error!("open config search index {} error: {}", &path, err);
+                ConfigSearchIndex::new_in_ram().ok()
+            }
+        };
+        Self { index }
+    }
+
+    fn commit(&mut self) {
+        if let Some(index) = &mut self.index {
+            if let Err(err) = index.commit() {
+                log::error!("commit config search index error: {}", err);
+            }
+        }
+    }
+}
+
+impl Actor for IndexerActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        log::info!("IndexerActor started");
+        ctx.run_interval(INDEX_COMMIT_INTERVAL, |act, _| act.commit());
+    }
+
+    fn stopping(&mut self, _ctx: &mut Self::Context) -> Running {
+        self.commit();
+        Running::Stop
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "anyhow::Result<IndexResult>")]
+pub enum IndexCmd {
+    Index(ConfigKey, Arc<String>),
+    Remove(ConfigKey),
+    //启动加载完成后的全部配置,清理残留索引
+    Retain(HashSet<ConfigKey>),
+    Search {
+        query: String,
+        tenant: Option<String>,
+        offset: usize,
+        limit: usize,
+    },
+}
+
+pub enum IndexResult {
+    None,
+    Page(usize, Vec<ConfigSearchItem>),
+}
+
+impl Handler<IndexCmd> for IndexerActor {
+    type Result = anyhow::Result<IndexResult>;
+
+    fn handle(&mut self, msg: IndexCmd, _ctx: &mut Context<Self>) -> Self::Result {
+        let index = match &mut self.index {
+            Some(v) => v,
+            None => return Err(anyhow::anyhow!("config search index is unavailable")),
+        };
+        match msg {
+            IndexCmd::Index(key, content) => {
+                index.index(&key, &content)?;
+            }
+            IndexCmd::Remove(key) => {
+                index.remove(&key);
+            }
+            IndexCmd::Retain(keys) => {
+                let removed = index.retain(&keys)?;
+                log::info!("config search index removed {} stale docs", removed);
+            }
+            IndexCmd::Search {
+                query,
+                tenant,
+                offset,
+                limit,
+            } => {
+                //搜索前提交,保证能查到最近的写入
+                index.commit()?;
+                let (total, list) = index.search(&query, tenant.as_deref(), offset, limit)?;
+                return Ok(IndexResult::Page(total, list));
+            }
+        }
+        Ok(IndexResult::None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_config() {
+        let mut index = ConfigSearchIndex::new_in_ram().unwrap();
+        index
+            .index(
+                &ConfigKey::new("db.yaml", "DEFAULT_GROUP", ""),
+                "mysql:\n  url: jdbc:mysql://127.0.0.1/db\n  pool: 10",
+            )
+            .unwrap();
+        index
+            .index(
+                &ConfigKey::new("cache.yaml", "DEFAULT_GROUP", ""),
+                "redis:\n  url: redis://127.0.0.1",
+            )
+            .unwrap();
+        index
+            .index(
+                &ConfigKey::new("db.yaml", "DEFAULT_GROUP", "dev"),
+                "mysql:\n  url: jdbc:mysql://10.0.0.1/db",
+            )
+            .unwrap();
+        index.commit().unwrap();
+        let (total, list) = index.search("MySQL", None, 0, 10).unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(list[0].data_id.as_str(), "db.yaml");
+        let (total, _) = index.search("mysql", Some("dev"), 0, 10).unwrap();
+        assert_eq!(total, 1);
+        let (total, list) = index.search("mysql", Some(""), 0, 10).unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(list[0].tenant.as_str(), "");
+        let (total, list) = index.search("url 127", None, 0, 1).unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(list.len(), 1);
+        let (total, _) = index.search("mysql redis", None, 0, 10).unwrap();
+        assert_eq!(total, 0);
+        //分组及dataId同样参与搜索
+        let (total, _) = index.search("cache", None, 0, 10).unwrap();
+        assert_eq!(total, 1);
+
+        index.remove(&ConfigKey::new("cache.yaml", "DEFAULT_GROUP", ""));
+        index
+            .index(
+                &ConfigKey::new("db.yaml", "DEFAULT_GROUP", ""),
+                "postgres: {}",
+            )
+            .unwrap();
+        index.commit().unwrap();
+        assert_eq!(index.len(), 2);
+        let (total, _) = index.search("redis", None, 0, 10).unwrap();
+        assert_eq!(total, 0);
+        let (total, _) = index.search("mysql", None, 0, 10).unwrap();
+        assert_eq!(total, 1);
+    }
+
+    #[test]
+    fn persisted_index() {
+        let path = std::env::temp_dir().join(format!(
+            "rnacos_search_index_test_{}",
+            uuid::Uuid::new_v4().simple()
+        ));
+        let path = path.to_string_lossy().to_string();
+        let key = ConfigKey::new("db.yaml", "DEFAULT_GROUP", "dev");
+        let stale_key = ConfigKey::new("old.yaml", "DEFAULT_GROUP", "dev");
+        {
+            let mut index = ConfigSearchIndex::open(&path).unwrap();
+            index.index(&key, "mysql").unwrap();
+            index.index(&stale_key, "mysql").unwrap();
+            index.commit().unwrap();
+        }
+        let mut index = ConfigSearchIndex::open(&path).unwrap();
+        assert_eq!(index.len(), 2);
+        let removed = index.retain(&HashSet::from([key])).unwrap();
+        assert_eq!(removed, 1);
+        let (total, list) = index.search("mysql", None, 0, 10).unwrap();
+        drop(index);
+        std::fs::remove_dir_all(&path).ok();
+        assert_eq!(total, 1);
+        assert_eq!(list[0].data_id.as_str(), "db.yaml");
+    }
+}
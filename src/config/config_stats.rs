@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::common::appdata::AppShareData;
+use crate::common::hash_utils::get_hash_value;
+use crate::now_millis_i64;
+
+use super::core::{ConfigCmd, ConfigKey, ConfigResult};
+
+/// 开启缓存预热时保存读取统计的文件名,位于数据目录下
+pub const CONFIG_STATS_FILE: &str = "config_stats.json";
+pub const CONFIG_STATS_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 各节点上报配置最近读取时间的间隔
+pub const CONFIG_TOUCH_REPORT_INTERVAL: Duration = Duration::from_secs(600);
+/// 本节点读取时间比集群记录晚该值以上才上报,控制写入raft的频率
+const CONFIG_TOUCH_MIN_INTERVAL_MS: i64 = 60 * 60 * 1000;
+/// 每个raft请求最多上报的配置数
+const CONFIG_TOUCH_BATCH_SIZE: usize = 1000;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+/// 按天统计读取次数的天数
+const READ_COUNT_DAYS: usize = 7;
+/// 同一客户端在该时间窗口内读取的配置视为一起读取
+const CO_ACCESS_WINDOW_MS: i64 = 100;
+/// 每个配置最多记录的关联配置数
+const CO_ACCESS_MAX_RELATED: usize = 16;
+/// 一起读取的次数达到该值才视为关联配置
+const CO_ACCESS_MIN_COUNT: u64 = 2;
+/// 记录的客户端读取窗口超过该数量时清理已过期的窗口
+const CO_ACCESS_MAX_CLIENTS: usize = 4096;
+/// 按客户端采样统计一起读取的配置,每8个客户端统计1个
+const CO_ACCESS_SAMPLE_RATE: u64 = 8;
+/// 待统计的读取队列长度,队列满时丢弃
+const CO_ACCESS_QUEUE_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatEntry {
+    pub last_read_ms: i64,
+    pub last_write_ms: i64,
+    //最近7天每天的读取次数,(天序号,次数),按天序号取模存放
+    #[serde(default)]
+    pub daily_reads: [(i64, u64); READ_COUNT_DAYS],
+}
+
+impl StatEntry {
+    ///
+    /// 最近读取与写入时间都早于before_ms
+    pub fn is_inactive(&self, before_ms: i64) -> bool {
+        self.last_read_ms < before_ms && self.last_write_ms < before_ms
+    }
+
+    fn add_read(&mut self, now: i64) {
+        self.last_read_ms = now;
+        let day = now / DAY_MS;
+        let slot = &mut self.daily_reads[day as usize % READ_COUNT_DAYS];
+        if slot.0 != day {
+            *slot = (day, 0);
+        }
+        slot.1 += 1;
+    }
+
+    ///
+    /// 最近7天(含当天)的读取次数
+    pub fn read_count_7d(&self, now: i64) -> u64 {
+        let today = now / DAY_MS;
+        self.daily_reads
+            .iter()
+            .filter(|(day, _)| today - *day < READ_COUNT_DAYS as i64 && *day <= today)
+            .map(|(_, count)| *count)
+            .sum()
+    }
+}
+
+#[derive(Debug, Default)]
+struct CoAccessState {
+    //客户端到(窗口开始时间,窗口内读取的配置)
+    windows: HashMap<String, (i64, Vec<ConfigKey>)>,
+    //配置到与它一起读取的配置及次数
+    related: HashMap<ConfigKey, HashMap<ConfigKey, u64>>,
+}
+
+#[derive(Debug)]
+struct CoAccessRecord {
+    client: String,
+    key: ConfigKey,
+    time: i64,
+}
+
+impl CoAccessState {
+    fn add_pair(&mut self, key: &ConfigKey, other: &ConfigKey) {
+        let related = self.related.entry(key.clone()).or_default();
+        if let Some(count) = related.get_mut(other) {
+            *count += 1;
+        } else if related.len() < CO_ACCESS_MAX_RELATED {
+            related.insert(other.clone(), 1);
+        }
+    }
+}
+
+///
+/// 统计采样到的一起读取的配置
+pub async fn run_co_access_recorder(stats: Arc<ConfigStats>) {
+    let receiver = stats
+        .co_access_receiver
+        .lock()
+        .ok()
+        .and_then(|mut v| v.take());
+    let mut receiver = match receiver {
+        Some(v) => v,
+        None => return,
+    };
+    while let Some(record) = receiver.recv().await {
+        stats.record_co_access(&record.client, &record.key, record.time);
+    }
+}
+
+///
+/// 定时把本节点记录的读取时间经raft汇总为集群内的最近读取时间,
+/// 配置归档、孤立配置等按集群内的读写时间判断,不依赖单个节点的内存统计
+pub async fn run_touch_reporter(app: Arc<AppShareData>) {
+    loop {
+        tokio::time::sleep(CONFIG_TOUCH_REPORT_INTERVAL).await;
+        if let Err(err) = report_touch_items(&app).await {
+            log::warn!("report config read time error: {}", err);
+        }
+    }
+}
+
+async fn report_touch_items(app: &Arc<AppShareData>) -> anyhow::Result<()> {
+    let items = match app
+        .config_addr
+        .send(ConfigCmd::QueryTouchItems(CONFIG_TOUCH_MIN_INTERVAL_MS))
+        .await??
+    {
+        ConfigResult::TouchItems(items) => items,
+        _ => return Ok(()),
+    };
+    for chunk in items.chunks(CONFIG_TOUCH_BATCH_SIZE) {
+        app.config_route.touch_configs(chunk.to_vec()).await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StatRecord {
+    key: ConfigKey,
+    #[serde(flatten)]
+    entry: StatEntry,
+}
+
+///
+/// 记录配置最近读取(含监听)及写入时间,默认只保存在内存中,开启缓存预热时定时保存到文件;
+/// 没有读取记录时按服务启动时间计算,避免重启后所有配置都被当作长期未读取
+pub struct ConfigStats {
+    start_ms: i64,
+    entries: Mutex<HashMap<ConfigKey, StatEntry>>,
+    co_access: Mutex<CoAccessState>,
+    co_access_sender: mpsc::Sender<CoAccessRecord>,
+    co_access_receiver: Mutex<Option<mpsc::Receiver<CoAccessRecord>>>,
+}
+
+impl Default for ConfigStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigStats {
+    pub fn new() -> Self {
+        let (co_access_sender, co_access_receiver) = mpsc::channel(CO_ACCESS_QUEUE_SIZE);
+        Self {
+            start_ms: now_millis_i64(),
+            entries: Mutex::new(HashMap::new()),
+            co_access: Mutex::new(CoAccessState::default()),
+            co_access_sender,
+            co_access_receiver: Mutex::new(Some(co_access_receiver)),
+        }
+    }
+
+    pub fn record_read(&self, key: &ConfigKey) {
+        let now = now_millis_i64();
+        if let Ok(mut entries) = self.entries.lock() {
+            match entries.get_mut(key) {
+                Some(v) => v.add_read(now),
+                None => {
+                    let mut entry = StatEntry::default();
+                    entry.add_read(now);
+                    entries.insert(key.clone(), entry);
+                }
+            }
+        }
+    }
+
+    pub fn record_write(&self, key: &ConfigKey, op_time: i64) {
+        if let Ok(mut entries) = self.entries.lock() {
+            let entry = entries.entry(key.clone()).or_default();
+            entry.last_write_ms = entry.last_write_ms.max(op_time);
+        }
+    }
+
+    pub fn remove(&self, key: &ConfigKey) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(key);
+        }
+        if let Ok(mut co_access) = self.co_access.lock() {
+            co_access.related.remove(key);
+            for related in co_access.related.values_mut() {
+                related.remove(key);
+            }
+        }
+    }
+
+    ///
+    /// 读取路径上只按客户端采样并放入队列,不等待锁,由run_co_access_recorder统计
+    pub fn sample_co_access(&self, client: &str, key: &ConfigKey, now: i64) {
+        if !get_hash_value(&client).is_multiple_of(CO_ACCESS_SAMPLE_RATE) {
+            return;
+        }
+        self.co_access_sender
+            .try_send(CoAccessRecord {
+                client: client.to_owned(),
+                key: key.clone(),
+                time: now,
+            })
+            .ok();
+    }
+
+    ///
+    /// 记录客户端读取的配置,与同一窗口内该客户端读取的同一命名空间的其它配置互相关联
+    fn record_co_access(&self, client: &str, key: &ConfigKey, now: i64) {
+        let mut co_access = match self.co_access.lock() {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        if co_access.windows.len() >= CO_ACCESS_MAX_CLIENTS {
+            co_access
+                .windows
+                .retain(|_, (start, _)| now - *start <= CO_ACCESS_WINDOW_MS);
+        }
+        let window = co_access
+            .windows
+            .entry(client.to_owned())
+            .or_insert_with(|| (now, vec![]));
+        if now - window.0 > CO_ACCESS_WINDOW_MS {
+            *window = (now, vec![]);
+        }
+        if window.1.contains(key) {
+            return;
+        }
+        let others: Vec<ConfigKey> = window
+            .1
+            .iter()
+            .filter(|v| v.tenant == key.tenant)
+            .cloned()
+            .collect();
+        window.1.push(key.clone());
+        for other in &others {
+            co_access.add_pair(key, other);
+            co_access.add_pair(other, key);
+        }
+    }
+
+    ///
+    /// 经常与该配置一起读取的配置,按一起读取的次数倒序
+    pub fn co_accessed_with(&self, key: &ConfigKey, limit: usize) -> Vec<ConfigKey> {
+        let mut list: Vec<(u64, ConfigKey)> = match self.co_access.lock() {
+            Ok(co_access) => match co_access.related.get(key) {
+                Some(related) => related
+                    .iter()
+                    .filter(|(_, count)| **count >= CO_ACCESS_MIN_COUNT)
+                    .map(|(k, count)| (*count, k.clone()))
+                    .collect(),
+                None => return vec![],
+            },
+            Err(_) => return vec![],
+        };
+        list.sort_by(|(count_a, a), (count_b, b)| {
+            count_b.cmp(count_a).then_with(|| {
+                (&a.tenant, &a.group, &a.data_id).cmp(&(&b.tenant, &b.group, &b.data_id))
+            })
+        });
+        list.into_iter().take(limit).map(|(_, k)| k).collect()
+    }
+
+    pub fn get(&self, key: &ConfigKey) -> StatEntry {
+        let mut entry = self
+            .entries
+            .lock()
+            .ok()
+            .and_then(|entries| entries.get(key).cloned())
+            .unwrap_or_default();
+        entry.last_read_ms = entry.last_read_ms.max(self.start_ms);
+        entry
+    }
+
+    ///
+    /// 本节点有读取记录的配置及最近读取时间,不按启动时间修正
+    pub fn read_times(&self) -> HashMap<ConfigKey, i64> {
+        match self.entries.lock() {
+            Ok(entries) => entries
+                .iter()
+                .filter(|(_, v)| v.last_read_ms > 0)
+                .map(|(k, v)| (k.clone(), v.last_read_ms))
+                .collect(),
+            Err(_) => HashMap::new(),
+        }
+    }
+
+    ///
+    /// 按最近7天读取次数倒序取前limit个有读取的配置
+    pub fn top_read_keys(&self, limit: usize, now: i64) -> Vec<ConfigKey> {
+        let mut list: Vec<(u64, ConfigKey)> = match self.entries.lock() {
+            Ok(entries) => entries
+                .iter()
+                .map(|(k, v)| (v.read_count_7d(now), k.clone()))
+                .filter(|(count, _)| *count > 0)
+                .collect(),
+            Err(_) => return vec![],
+        };
+        list.sort_by(|(count_a, a), (count_b, b)| {
+            count_b.cmp(count_a).then_with(|| {
+                (&a.tenant, &a.group, &a.data_id).cmp(&(&b.tenant, &b.group, &b.data_id))
+            })
+        });
+        list.into_iter().take(limit).map(|(_, k)| k).collect()
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> anyhow::Result<()> {
+        let records: Vec<StatRecord> = match self.entries.lock() {
+            Ok(entries) => entries
+                .iter()
+                .map(|(k, v)| StatRecord {
+                    key: k.clone(),
+                    entry: *v,
+                })
+                .collect(),
+            Err(_) => return Err(anyhow::anyhow!("config stats lock error")),
+        };
+        //先写临时文件再改名,避免写入中断时损坏已有文件
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, serde_json::to_vec(&records)?)?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    ///
+    /// 加载保存的统计,文件不存在时忽略
+    pub fn load_from_file(&self, path: &Path) -> anyhow::Result<usize> {
+        if !path.exists() {
+            return Ok(0);
+        }
+        let records: Vec<StatRecord> = serde_json::from_slice(&std::fs::read(path)?)?;
+        let size = records.len();
+        if let Ok(mut entries) = self.entries.lock() {
+            for record in records {
+                entries.insert(record.key, record.entry);
+            }
+        }
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inactive_config() {
+        let stats = ConfigStats::new();
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let now = now_millis_i64();
+        stats.record_write(&key, 1);
+        assert_eq!(stats.get(&key).last_write_ms, 1);
+        //没有读取记录时按启动时间计算
+        assert!(stats.get(&key).is_inactive(now + 1000));
+        assert!(!stats.get(&key).is_inactive(now - 1000));
+        stats.record_write(&key, now + 2000);
+        assert!(!stats.get(&key).is_inactive(now + 1000));
+        stats.remove(&key);
+        assert_eq!(stats.get(&key).last_write_ms, 0);
+    }
+
+    #[test]
+    fn read_count_7d() {
+        let mut entry = StatEntry::default();
+        entry.add_read(0);
+        entry.add_read(DAY_MS);
+        entry.add_read(DAY_MS + 1);
+        assert_eq!(entry.read_count_7d(DAY_MS), 3);
+        assert_eq!(entry.read_count_7d(7 * DAY_MS), 2);
+        //同一位置的旧计数被新的一天覆盖
+        entry.add_read(8 * DAY_MS);
+        assert_eq!(entry.read_count_7d(8 * DAY_MS), 1);
+    }
+
+    #[test]
+    fn top_read_keys_persist() {
+        let stats = ConfigStats::new();
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let b = ConfigKey::new("b", "DEFAULT_GROUP", "");
+        stats.record_read(&a);
+        stats.record_read(&b);
+        stats.record_read(&b);
+        stats.record_write(&ConfigKey::new("c", "DEFAULT_GROUP", ""), 1);
+        let now = now_millis_i64();
+        assert_eq!(stats.top_read_keys(10, now), vec![b.clone(), a.clone()]);
+        assert_eq!(stats.top_read_keys(1, now), vec![b.clone()]);
+
+        let path = std::env::temp_dir().join(format!(
+            "rnacos_config_stats_test_{}.json",
+            uuid::Uuid::new_v4().simple()
+        ));
+        stats.save_to_file(&path).unwrap();
+        let loaded = ConfigStats::new();
+        assert_eq!(loaded.load_from_file(&path).unwrap(), 3);
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded.top_read_keys(10, now), vec![b, a]);
+    }
+
+    #[test]
+    fn co_accessed_with() {
+        let stats = ConfigStats::new();
+        let db = ConfigKey::new("database.properties", "DEFAULT_GROUP", "");
+        let pool = ConfigKey::new("connection-pool.properties", "DEFAULT_GROUP", "");
+        let cache = ConfigKey::new("cache.properties", "DEFAULT_GROUP", "");
+        stats.record_co_access("10.0.0.1", &db, 1000);
+        stats.record_co_access("10.0.0.1", &pool, 1050);
+        stats.record_co_access("10.0.0.1", &cache, 1050);
+        //只一起读取过一次,还不算关联配置
+        assert!(stats.co_accessed_with(&db, 3).is_empty());
+        stats.record_co_access("10.0.0.2", &db, 2000);
+        stats.record_co_access("10.0.0.2", &pool, 2010);
+        //超出窗口的读取不关联
+        stats.record_co_access("10.0.0.2", &cache, 2200);
+        assert_eq!(stats.co_accessed_with(&db, 3), vec![pool.clone()]);
+        assert_eq!(stats.co_accessed_with(&pool, 3), vec![db.clone()]);
+        //不同命名空间的配置不关联
+        let other_db = ConfigKey::new("database.properties", "DEFAULT_GROUP", "dev");
+        stats.record_co_access("10.0.0.3", &db, 3000);
+        stats.record_co_access("10.0.0.3", &other_db, 3010);
+        stats.record_co_access("10.0.0.4", &db, 4000);
+        stats.record_co_access("10.0.0.4", &other_db, 4010);
+        assert!(stats.co_accessed_with(&other_db, 3).is_empty());
+        stats.remove(&pool);
+        assert!(stats.co_accessed_with(&db, 3).is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+use super::config_diff::{diff_lines, render_unified_diff};
+use super::config_event::ConfigChangeType;
+use super::core::ConfigKey;
+use crate::common::appdata::AppShareData;
+
+/// 补丁中每个变更块前后保留的相同行数
+const PATCH_CONTEXT_LINES: usize = 3;
+/// 没有事件时发送注释帧的间隔,避免代理与负载均衡按空闲超时断开连接
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    Full,
+    Diff,
+}
+
+impl StreamFormat {
+    pub fn new_by_value(v: &str) -> Option<Self> {
+        match v {
+            "full" => Some(Self::Full),
+            "diff" => Some(Self::Diff),
+            _ => None,
+        }
+    }
+}
+
+///
+/// 推送给订阅方的配置事件;format为full时带全量内容,为diff时带相对base_md5的统一差异补丁,
+/// 客户端应用补丁后可用md5校验,不一致时重新订阅获取全量内容
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigStreamEvent {
+    pub format: &'static str,
+    pub md5: Option<Arc<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_md5: Option<Arc<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<Arc<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch: Option<String>,
+}
+
+impl ConfigStreamEvent {
+    fn full(md5: Arc<String>, content: Arc<String>) -> Self {
+        Self {
+            format: "full",
+            md5: Some(md5),
+            base_md5: None,
+            content: Some(content),
+            patch: None,
+        }
+    }
+
+    fn remove() -> Self {
+        Self {
+            format: "remove",
+            md5: None,
+            base_md5: None,
+            content: None,
+            patch: None,
+        }
+    }
+
+    ///
+    /// 补丁不小于全量内容时发送全量内容
+    fn diff(base: &(Arc<String>, Arc<String>), md5: Arc<String>, content: Arc<String>) -> Self {
+        let patch = render_unified_diff(&diff_lines(&base.1, &content), PATCH_CONTEXT_LINES);
+        if patch.len() >= content.len() {
+            return Self::full(md5, content);
+        }
+        Self {
+            format: "diff",
+            md5: Some(md5),
+            base_md5: Some(base.0.clone()),
+            content: None,
+            patch: Some(patch),
+        }
+    }
+}
+
+///
+/// 写入SSE连接的帧,KeepAlive为注释帧,客户端会忽略
+#[derive(Debug, Clone)]
+pub enum ConfigStreamFrame {
+    Event(ConfigStreamEvent),
+    KeepAlive,
+}
+
+impl ConfigStreamFrame {
+    pub fn to_sse(&self) -> String {
+        match self {
+            Self::Event(event) => format!(
+                "data: {}\n\n",
+                serde_json::to_string(event).unwrap_or_default()
+            ),
+            Self::KeepAlive => ": keepalive\n\n".to_owned(),
+        }
+    }
+}
+
+///
+/// 读取配置的md5与内容,归档的配置从对象存储读取,自动加密的字段解密后返回
+async fn load_content(
+    app: &AppShareData,
+    key: &ConfigKey,
+) -> anyhow::Result<Option<(Arc<String>, Arc<String>)>> {
+    let value = match app.load_config(key).await? {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let content = app.decrypt_config_content(
+        &key.tenant,
+        &value.content,
+        value.config_type.as_ref().map(|v| v.as_str()),
+    )?;
+    Ok(Some((value.md5.clone(), content)))
+}
+
+///
+/// 订阅单个配置的变更,首个事件为全量内容;diff格式时之后的事件为相对上次内容的补丁。
+/// 空闲时定时发送注释帧,订阅方断开或事件总线关闭时结束
+pub async fn run_config_stream(
+    app: Arc<AppShareData>,
+    key: ConfigKey,
+    format: StreamFormat,
+    tx: mpsc::Sender<ConfigStreamFrame>,
+) {
+    //先订阅再读取基线内容,避免漏掉期间的变更
+    let mut receiver = app.config_event_bus.subscribe();
+    let mut last: Option<(Arc<String>, Arc<String>)> = None;
+    let mut reload = true;
+    let mut keepalive = tokio::time::interval(KEEPALIVE_INTERVAL);
+    keepalive.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    keepalive.reset();
+    loop {
+        if reload {
+            reload = false;
+            let event = match load_content(&app, &key).await {
+                Ok(Some((md5, content))) => {
+                    if last.as_ref().map(|v| &v.0) == Some(&md5) {
+                        None
+                    } else {
+                        let event = match &last {
+                            Some(base) if format == StreamFormat::Diff => {
+                                ConfigStreamEvent::diff(base, md5.clone(), content.clone())
+                            }
+                            _ => ConfigStreamEvent::full(md5.clone(), content.clone()),
+                        };
+                        last = Some((md5, content));
+                        Some(event)
+                    }
+                }
+                Ok(None) => last.take().map(|_| ConfigStreamEvent::remove()),
+                Err(err) => {
+                    log::warn!("load config {} for stream error: {}", &key, err);
+                    None
+                }
+            };
+            if let Some(event) = event {
+                if tx.send(ConfigStreamFrame::Event(event)).await.is_err() {
+                    return;
+                }
+                keepalive.reset();
+            }
+        }
+        tokio::select! {
+            _ = tx.closed() => return,
+            _ = keepalive.tick() => {
+                if tx.send(ConfigStreamFrame::KeepAlive).await.is_err() {
+                    return;
+                }
+            }
+            event = receiver.recv() => match event {
+                Ok(event) if event.key == key => {
+                    if event.change_type == ConfigChangeType::Remove && last.is_none() {
+                        continue;
+                    }
+                    reload = true;
+                }
+                Ok(_) => {}
+                //丢失事件时重新读取当前内容
+                Err(RecvError::Lagged(_)) => reload = true,
+                Err(RecvError::Closed) => return,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_event() {
+        let old: String = (0..20).map(|i| format!("k{}: {}\n", i, i)).collect();
+        let new = old.replace("k10: 10", "k10: 11");
+        let base = (Arc::new("m1".to_owned()), Arc::new(old));
+        let event = ConfigStreamEvent::diff(&base, Arc::new("m2".to_owned()), Arc::new(new));
+        assert_eq!(event.format, "diff");
+        assert_eq!(event.base_md5.unwrap().as_str(), "m1");
+        assert!(event.patch.unwrap().contains("-k10: 10\n+k10: 11\n"));
+        //补丁比全量内容大时发送全量
+        let event =
+            ConfigStreamEvent::diff(&base, Arc::new("m3".to_owned()), Arc::new("a".to_owned()));
+        assert_eq!(event.format, "full");
+        assert_eq!(event.content.unwrap().as_str(), "a");
+    }
+
+    #[test]
+    fn sse_frame() {
+        assert_eq!(ConfigStreamFrame::KeepAlive.to_sse(), ": keepalive\n\n");
+        let frame = ConfigStreamFrame::Event(ConfigStreamEvent::remove());
+        assert_eq!(
+            frame.to_sse(),
+            "data: {\"format\":\"remove\",\"md5\":null}\n\n"
+        );
+    }
+}
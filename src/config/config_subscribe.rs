@@ -23,6 +23,16 @@ impl Subscriber {
         }
     }
 
+    pub fn get_key_clients(&self, key: &ConfigKey) -> HashSet<Arc<String>> {
+        self.listener.get(key).cloned().unwrap_or_default()
+    }
+
+    ///
+    /// 有客户端监听的配置
+    pub fn subscribed_keys(&self) -> impl Iterator<Item = &ConfigKey> {
+        self.listener.keys()
+    }
+
     pub fn set_conn_manage(&mut self, conn_manage: Addr<BiStreamManage>) {
         self.conn_manage = Some(conn_manage);
     }
@@ -101,6 +111,38 @@ impl Subscriber {
         }
     }
 
+    ///
+    /// 各客户端按key订阅的配置
+    pub fn get_client_keys(&self) -> HashMap<Arc<String>, Vec<ConfigKey>> {
+        self.client_keys
+            .iter()
+            .map(|(k, v)| (k.clone(), v.iter().cloned().collect()))
+            .collect()
+    }
+
+    pub fn get_keys_by_client(&self, client_id: &Arc<String>) -> Vec<ConfigKey> {
+        self.client_keys
+            .get(client_id)
+            .map(|v| v.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    ///
+    /// 各命名空间下有订阅的客户端数
+    pub fn get_tenant_client_count(&self) -> HashMap<Arc<String>, usize> {
+        let mut tenant_clients: HashMap<Arc<String>, HashSet<&Arc<String>>> = HashMap::new();
+        for (key, clients) in &self.listener {
+            tenant_clients
+                .entry(key.tenant.clone())
+                .or_default()
+                .extend(clients.iter());
+        }
+        tenant_clients
+            .into_iter()
+            .map(|(k, v)| (k, v.len()))
+            .collect()
+    }
+
     pub fn remove_config_key(&mut self, key: ConfigKey) {
         if let Some(set) = self.listener.remove(&key) {
             let mut remove_keys = vec![];
@@ -118,10 +160,15 @@ impl Subscriber {
         }
     }
 
-    pub fn notify(&self, key: ConfigKey) {
+    ///
+    /// 通知按key监听及通配监听的客户端
+    pub fn notify(&self, key: ConfigKey, mut wildcard_clients: HashSet<Arc<String>>) {
         if let Some(conn_manage) = &self.conn_manage {
             if let Some(set) = self.listener.get(&key) {
-                conn_manage.do_send(BiStreamManageCmd::NotifyConfig(key, set.clone()));
+                wildcard_clients.extend(set.iter().cloned());
+            }
+            if !wildcard_clients.is_empty() {
+                conn_manage.do_send(BiStreamManageCmd::NotifyConfig(key, wildcard_clients));
             }
         }
     }
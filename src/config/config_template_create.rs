@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::config_read_view::ConfigReadValue;
+use super::core::{ConfigCmd, ConfigKey, ConfigResult};
+use super::model::ConfigInUseError;
+use crate::common::appdata::AppShareData;
+use crate::console::{SYSCONFIG_GROUP, SYSCONFIG_NAMESPACE};
+use crate::now_millis_i64;
+use crate::raft::cluster::model::{DelConfigReq, SetConfigReq};
+
+/// 系统命名空间下保存按模板自动创建且设置了过期时长的配置
+pub const TEMPLATE_CREATED_KEY: &str = "template_created_configs";
+const EXPIRE_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+/// 并发登记时md5校验失败的重试次数
+const REGISTER_RETRY_TIMES: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateCreatedConfig {
+    pub key: ConfigKey,
+    //创建时的内容md5,配置被修改后不再自动过期
+    pub md5: Arc<String>,
+    pub ttl_seconds: u64,
+}
+
+pub fn parse_template_created(content: &str) -> anyhow::Result<Vec<TemplateCreatedConfig>> {
+    if content.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    Ok(serde_json::from_str(content)?)
+}
+
+fn registry_key() -> ConfigKey {
+    ConfigKey::new(TEMPLATE_CREATED_KEY, SYSCONFIG_GROUP, SYSCONFIG_NAMESPACE)
+}
+
+///
+/// 复制模板配置创建不存在的配置,经raft写入;配置已存在或已被其它请求创建时不覆盖,
+/// 返回(当前配置,是否由本次请求创建)
+pub async fn create_from_template(
+    app: &AppShareData,
+    key: &ConfigKey,
+    template_key: &ConfigKey,
+    ttl_seconds: Option<u64>,
+) -> anyhow::Result<Option<(Arc<ConfigReadValue>, bool)>> {
+    if let Some(v) = app.load_config(key).await? {
+        return Ok(Some((v, false)));
+    }
+    let mut template = match app.load_config(template_key).await? {
+        Some(v) => v.as_ref().clone(),
+        None => return Ok(None),
+    };
+    //复制出的配置不是归档状态
+    template.archive_uri = None;
+    let mut req = SetConfigReq::new(key.clone(), template.content.clone());
+    req.config_type = template.config_type.clone();
+    req.expected_md5 = Some(Arc::new(String::new()));
+    if let Err(err) = app.config_batch_proposer.set_config(req).await {
+        //并发请求已创建时返回已有配置
+        return match app.load_config(key).await? {
+            Some(v) => Ok(Some((v, false))),
+            None => Err(err),
+        };
+    }
+    if let Some(ttl_seconds) = ttl_seconds.filter(|v| *v > 0) {
+        let item = TemplateCreatedConfig {
+            key: key.clone(),
+            md5: template.md5.clone(),
+            ttl_seconds,
+        };
+        if let Err(err) = update_registry(app, |list| {
+            list.retain(|v| v.key != item.key);
+            list.push(item.clone());
+        })
+        .await
+        {
+            log::warn!("register template created config {} error: {}", key, err);
+        }
+    }
+    Ok(Some((Arc::new(template), true)))
+}
+
+async fn update_registry<F>(app: &AppShareData, mut f: F) -> anyhow::Result<()>
+where
+    F: FnMut(&mut Vec<TemplateCreatedConfig>),
+{
+    let key = registry_key();
+    let mut last_err = None;
+    for _ in 0..REGISTER_RETRY_TIMES {
+        let current = app.config_read_view.get(&key);
+        let mut list =
+            parse_template_created(current.as_ref().map(|v| v.content.as_str()).unwrap_or(""))?;
+        f(&mut list);
+        let mut req = SetConfigReq::new(key.clone(), Arc::new(serde_json::to_string(&list)?));
+        req.expected_md5 = Some(
+            current
+                .map(|v| v.md5.clone())
+                .unwrap_or_else(|| Arc::new(String::new())),
+        );
+        match app.config_route.set_config(req).await {
+            Ok(_) => return Ok(()),
+            Err(err) => last_err = Some(err),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| anyhow::anyhow!("update template created configs failed")))
+}
+
+///
+/// 删除超过过期时长未读写的自动创建配置;读写时间使用raft复制的集群记录,
+/// 与当前主节点本地的读取统计无关
+async fn expire_template_created(app: &AppShareData) -> anyhow::Result<usize> {
+    let list = match app.config_read_view.get(&registry_key()) {
+        Some(v) => parse_template_created(&v.content)?,
+        None => return Ok(0),
+    };
+    if list.is_empty() {
+        return Ok(0);
+    }
+    let keys = list.iter().map(|v| v.key.clone()).collect();
+    let activities: HashMap<ConfigKey, (i64, i64)> = match app
+        .send_config_cmd(ConfigCmd::QueryActivities(keys))
+        .await?
+    {
+        ConfigResult::ConfigActivities(list) => list
+            .into_iter()
+            .map(|(key, read_ms, modified)| (key, (read_ms, modified)))
+            .collect(),
+        _ => return Err(anyhow::anyhow!("config result error")),
+    };
+    let now = now_millis_i64();
+    let mut removed = vec![];
+    let mut expired_count = 0;
+    for item in &list {
+        let current = app.config_read_view.get(&item.key);
+        match (current, activities.get(&item.key)) {
+            (Some(v), Some((read_ms, modified))) if v.md5 == item.md5 => {
+                let ttl_ms = i64::try_from(item.ttl_seconds)
+                    .unwrap_or(i64::MAX)
+                    .saturating_mul(1000);
+                let before_ms = now.saturating_sub(ttl_ms);
+                if *read_ms < before_ms && *modified < before_ms {
+                    if let Err(err) = app
+                        .config_route
+                        .del_config(DelConfigReq::new(item.key.clone()))
+                        .await
+                    {
+                        //仍被其它配置依赖时保留,下次检查时再处理
+                        if err.downcast_ref::<ConfigInUseError>().is_some() {
+                            log::warn!("skip expired template config {}: {}", &item.key, err);
+                            continue;
+                        }
+                        return Err(err);
+                    }
+                    expired_count += 1;
+                    removed.push(item.key.clone());
+                }
+            }
+            //已删除或已被修改
+            _ => removed.push(item.key.clone()),
+        }
+    }
+    if !removed.is_empty() {
+        update_registry(app, |list| list.retain(|v| !removed.contains(&v.key))).await?;
+    }
+    Ok(expired_count)
+}
+
+///
+/// 定时清除过期的自动创建配置,只在主节点执行
+pub async fn run_template_expire_task(app: Arc<AppShareData>) {
+    loop {
+        tokio::time::sleep(EXPIRE_CHECK_INTERVAL).await;
+        if app.raft.current_leader().await != Some(app.sys_config.raft_node_id) {
+            continue;
+        }
+        match expire_template_created(&app).await {
+            Ok(count) if count > 0 => log::info!("expired {} template created configs", count),
+            Ok(_) => {}
+            Err(err) => log::warn!("expire template created configs error: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_created_registry() {
+        assert!(parse_template_created("").unwrap().is_empty());
+        let list = vec![TemplateCreatedConfig {
+            key: ConfigKey::new("app.yaml", "DEFAULT_GROUP", "dev"),
+            md5: Arc::new("md5".to_owned()),
+            ttl_seconds: 3600,
+        }];
+        let content = serde_json::to_string(&list).unwrap();
+        assert!(content.contains("\"ttlSeconds\":3600"));
+        assert_eq!(parse_template_created(&content).unwrap(), list);
+    }
+}
@@ -0,0 +1,495 @@
+use std::sync::{Arc, Mutex};
+
+use inner_mem_cache::MemCache;
+
+use super::config_type::ConfigType;
+use super::core::ConfigKey;
+
+/// 转码结果缓存时长(秒)
+const TRANSCODE_CACHE_SECONDS: i32 = 60;
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+struct TranscodeKey {
+    key: ConfigKey,
+    md5: Arc<String>,
+    target: ConfigType,
+    //properties按原键平铺转换,不按`.`嵌套
+    flat: bool,
+}
+
+lazy_static::lazy_static! {
+    static ref TRANSCODE_CACHE: Mutex<MemCache<TranscodeKey, Arc<String>>> =
+        Mutex::new(MemCache::new());
+}
+
+pub struct ConfigTranscoder;
+
+impl ConfigTranscoder {
+    fn parse_media_type(media_type: &str) -> Option<ConfigType> {
+        match media_type {
+            "application/json" => Some(ConfigType::Json),
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                Some(ConfigType::Yaml)
+            }
+            "application/toml" | "text/toml" => Some(ConfigType::Toml),
+            _ => None,
+        }
+    }
+
+    ///
+    /// 按本次请求的Accept头协商返回类型:只有结构化类型(json/yaml/toml)的q值高于
+    /// 原类型可接受的q值时才转码;q值相同时按原类型返回,如axios默认的
+    /// `application/json, text/plain, */*`不会触发转码
+    pub fn negotiate(accept: &str, stored: &ConfigType) -> Option<ConfigType> {
+        let essence = |v: &'static str| v.split(';').next().unwrap_or_default();
+        let stored_media_types = [
+            essence(stored.get_media_type()),
+            essence(stored.get_structured_media_type()),
+        ];
+        let mut stored_q = 0f32;
+        let mut target: Option<(ConfigType, f32)> = None;
+        for item in accept.split(',') {
+            let mut parts = item.split(';');
+            let media_type = parts.next().unwrap_or_default().trim();
+            let q = parts
+                .filter_map(|v| v.trim().strip_prefix("q="))
+                .find_map(|v| v.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if media_type.is_empty() || q <= 0.0 {
+                continue;
+            }
+            let parsed = Self::parse_media_type(media_type);
+            if media_type == "*/*"
+                || stored_media_types.contains(&media_type)
+                || parsed.as_ref() == Some(stored)
+            {
+                stored_q = stored_q.max(q);
+            } else if let Some(t) = parsed {
+                //q值相同时保持请求中的顺序
+                if target.as_ref().map(|(_, v)| q > *v).unwrap_or(true) {
+                    target = Some((t, q));
+                }
+            }
+        }
+        match target {
+            Some((t, q)) if q > stored_q => Some(t),
+            _ => None,
+        }
+    }
+
+    pub fn can_transcode(config_type: &ConfigType) -> bool {
+        matches!(
+            config_type,
+            ConfigType::Json | ConfigType::Yaml | ConfigType::Toml
+        )
+    }
+
+    pub fn transcode(content: &str, from: &ConfigType, to: &ConfigType) -> anyhow::Result<String> {
+        let value: serde_json::Value = match from {
+            ConfigType::Json => serde_json::from_str(content)?,
+            ConfigType::Yaml => serde_yaml::from_str(content)?,
+            ConfigType::Toml => toml::from_str(content)?,
+            ConfigType::Properties => properties_to_value(content)?,
+            _ => return Err(anyhow::anyhow!("unsupported source type: {:?}", from)),
+        };
+        let v = match to {
+            ConfigType::Json => serde_json::to_string_pretty(&value)?,
+            ConfigType::Yaml => serde_yaml::to_string(&value)?,
+            ConfigType::Toml => toml::to_string(&value)?,
+            ConfigType::Properties => value_to_properties(&value),
+            _ => return Err(anyhow::anyhow!("unsupported target type: {:?}", to)),
+        };
+        Ok(v)
+    }
+
+    ///
+    /// 带缓存的转码,缓存key包含md5,配置内容变更后不会命中旧缓存
+    pub fn transcode_with_cache(
+        key: &ConfigKey,
+        md5: &Arc<String>,
+        content: &str,
+        from: &ConfigType,
+        to: &ConfigType,
+    ) -> anyhow::Result<Arc<String>> {
+        let cache_key = TranscodeKey {
+            key: key.clone(),
+            md5: md5.clone(),
+            target: to.clone(),
+            flat: false,
+        };
+        Self::get_or_transcode(cache_key, || Self::transcode(content, from, to))
+    }
+
+    ///
+    /// 带缓存地把properties转换为单层json对象,键保持原样,值推断为数字、布尔或字符串
+    pub fn coerce_properties_with_cache(
+        key: &ConfigKey,
+        md5: &Arc<String>,
+        content: &str,
+    ) -> anyhow::Result<Arc<String>> {
+        let cache_key = TranscodeKey {
+            key: key.clone(),
+            md5: md5.clone(),
+            target: ConfigType::Json,
+            flat: true,
+        };
+        Self::get_or_transcode(cache_key, || {
+            Ok(serde_json::to_string_pretty(&properties_to_flat_value(
+                content,
+            ))?)
+        })
+    }
+
+    fn get_or_transcode<F>(cache_key: TranscodeKey, f: F) -> anyhow::Result<Arc<String>>
+    where
+        F: FnOnce() -> anyhow::Result<String>,
+    {
+        if let Ok(cache) = TRANSCODE_CACHE.lock() {
+            if let Ok(v) = cache.get(&cache_key) {
+                return Ok(v);
+            }
+        }
+        let v = Arc::new(f()?);
+        if let Ok(mut cache) = TRANSCODE_CACHE.lock() {
+            cache.clear_time_out();
+            cache.set(cache_key, v.clone(), TRANSCODE_CACHE_SECONDS);
+        }
+        Ok(v)
+    }
+}
+
+///
+/// 读取properties的逻辑行,合并以奇数个`\`结尾的续行
+fn properties_lines(content: &str) -> Vec<String> {
+    let mut lines = vec![];
+    let mut current: Option<String> = None;
+    for line in content.lines() {
+        let line = line.trim_start();
+        if current.is_none() && (line.is_empty() || line.starts_with('#') || line.starts_with('!'))
+        {
+            continue;
+        }
+        let backslashes = line.chars().rev().take_while(|c| *c == '\\').count();
+        let (text, continued) = if backslashes % 2 == 1 {
+            (&line[..line.len() - 1], true)
+        } else {
+            (line, false)
+        };
+        let buf = current.get_or_insert_with(String::new);
+        buf.push_str(text);
+        if !continued {
+            lines.push(current.take().unwrap_or_default());
+        }
+    }
+    if let Some(buf) = current {
+        lines.push(buf);
+    }
+    lines
+}
+
+fn unescape_properties(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('f') => out.push('\x0c'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(v) => out.push(v),
+                    None => out.push_str(&hex),
+                }
+            }
+            Some(v) => out.push(v),
+            None => {}
+        }
+    }
+    out
+}
+
+///
+/// 拆分为未转义的键与值,分隔符为第一个未转义的`=`、`:`或空白
+fn split_properties_line(line: &str) -> (String, String) {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '=' | ':' | ' ' | '\t' => {
+                let key = &line[..i];
+                let mut rest = line[i..].trim_start_matches([' ', '\t']);
+                if c.is_whitespace() {
+                    if let Some(v) = rest.strip_prefix(['=', ':']) {
+                        rest = v;
+                    }
+                } else {
+                    rest = &rest[1..];
+                }
+                return (
+                    unescape_properties(key),
+                    unescape_properties(rest.trim_start_matches([' ', '\t'])),
+                );
+            }
+            _ => {}
+        }
+    }
+    (unescape_properties(line), String::new())
+}
+
+///
+/// 推断标量类型;只在格式化后与原文一致时转为数字,避免丢失前导0等格式
+fn properties_scalar(v: String) -> serde_json::Value {
+    match v.as_str() {
+        "true" => return serde_json::Value::Bool(true),
+        "false" => return serde_json::Value::Bool(false),
+        _ => {}
+    }
+    if let Ok(n) = v.parse::<i64>() {
+        if n.to_string() == v {
+            return serde_json::Value::from(n);
+        }
+    }
+    if let Ok(n) = v.parse::<f64>() {
+        if n.is_finite() && n.to_string() == v {
+            return serde_json::Value::from(n);
+        }
+    }
+    serde_json::Value::String(v)
+}
+
+///
+/// 按`.`把键拆分为嵌套对象;同一路径既有值又有子键时返回错误
+pub fn properties_to_value(content: &str) -> anyhow::Result<serde_json::Value> {
+    let mut root = serde_json::Map::new();
+    for line in properties_lines(content) {
+        let (key, value) = split_properties_line(&line);
+        let parts: Vec<&str> = key.split('.').collect();
+        let mut node = &mut root;
+        for (i, part) in parts.iter().enumerate() {
+            if i == parts.len() - 1 {
+                if matches!(node.get(*part), Some(serde_json::Value::Object(_))) {
+                    return Err(anyhow::anyhow!("properties key conflict: {}", key));
+                }
+                node.insert(part.to_string(), properties_scalar(value));
+                break;
+            }
+            let child = node
+                .entry(part.to_string())
+                .or_insert_with(|| serde_json::Value::Object(Default::default()));
+            node = match child {
+                serde_json::Value::Object(map) => map,
+                _ => return Err(anyhow::anyhow!("properties key conflict: {}", key)),
+            };
+        }
+    }
+    Ok(serde_json::Value::Object(root))
+}
+
+///
+/// 转换为单层对象,键不拆分;重复的键以最后一次出现为准,与java Properties一致
+pub fn properties_to_flat_value(content: &str) -> serde_json::Value {
+    let mut root = serde_json::Map::new();
+    for line in properties_lines(content) {
+        let (key, value) = split_properties_line(&line);
+        root.insert(key, properties_scalar(value));
+    }
+    serde_json::Value::Object(root)
+}
+
+fn escape_properties(s: &str, is_key: bool, out: &mut String) {
+    for (i, c) in s.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '=' | ':' | '#' | '!' if is_key => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' ' if is_key || i == 0 => out.push_str("\\ "),
+            _ => out.push(c),
+        }
+    }
+}
+
+fn flatten_properties(prefix: &str, value: &serde_json::Value, lines: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (k, v) in map {
+                let key = if prefix.is_empty() {
+                    k.to_owned()
+                } else {
+                    format!("{}.{}", prefix, k)
+                };
+                flatten_properties(&key, v, lines);
+            }
+        }
+        serde_json::Value::Array(list) => {
+            for (i, v) in list.iter().enumerate() {
+                flatten_properties(&format!("{}[{}]", prefix, i), v, lines);
+            }
+        }
+        _ => {
+            let text = match value {
+                serde_json::Value::String(v) => v.to_owned(),
+                serde_json::Value::Null => String::new(),
+                v => v.to_string(),
+            };
+            let mut line = String::new();
+            escape_properties(prefix, true, &mut line);
+            line.push('=');
+            escape_properties(&text, false, &mut line);
+            lines.push(line);
+        }
+    }
+}
+
+///
+/// 嵌套对象按`.`展开,数组按`[下标]`展开
+pub fn value_to_properties(value: &serde_json::Value) -> String {
+    let mut lines = vec![];
+    flatten_properties("", value, &mut lines);
+    let mut v = lines.join("\n");
+    v.push('\n');
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transcode_json_yaml_toml() {
+        let json = r#"{"name":"rnacos","port":8848}"#;
+        let yaml = ConfigTranscoder::transcode(json, &ConfigType::Json, &ConfigType::Yaml).unwrap();
+        assert!(yaml.contains("name: rnacos"));
+        let toml =
+            ConfigTranscoder::transcode(&yaml, &ConfigType::Yaml, &ConfigType::Toml).unwrap();
+        assert!(toml.contains("port = 8848"));
+        let json2 =
+            ConfigTranscoder::transcode(&toml, &ConfigType::Toml, &ConfigType::Json).unwrap();
+        let v1: serde_json::Value = serde_json::from_str(json).unwrap();
+        let v2: serde_json::Value = serde_json::from_str(&json2).unwrap();
+        assert_eq!(v1, v2);
+    }
+
+    #[test]
+    fn transcode_properties() {
+        let props = "# comment\nserver.port=8080\nserver.host : localhost\napp.name = a\\\n  b\napp.code=007\napp.enabled=true\n";
+        let yaml =
+            ConfigTranscoder::transcode(props, &ConfigType::Properties, &ConfigType::Yaml).unwrap();
+        let v: serde_json::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(v["server"]["port"], 8080);
+        assert_eq!(v["server"]["host"], "localhost");
+        assert_eq!(v["app"]["name"], "ab");
+        assert_eq!(v["app"]["code"], "007");
+        assert_eq!(v["app"]["enabled"], true);
+
+        let props2 =
+            ConfigTranscoder::transcode(&yaml, &ConfigType::Yaml, &ConfigType::Properties).unwrap();
+        assert!(props2.contains("server.port=8080\n"));
+        assert_eq!(properties_to_value(&props2).unwrap(), v);
+        assert!(properties_to_value("a=1\na.b=2").is_err());
+        let typed = properties_to_value("ratio=1.5\nport=8080\nname=a").unwrap();
+        assert_eq!(
+            typed,
+            serde_json::json!({"ratio": 1.5, "port": 8080, "name": "a"})
+        );
+        assert_eq!(
+            value_to_properties(&serde_json::json!({"k": [" x", "y=z"]})),
+            "k[0]=\\ x\nk[1]=y=z\n"
+        );
+    }
+
+    #[test]
+    fn coerce_flat_properties() {
+        let content = "a=1\na.b=2\nport=08080\nenabled=true\nratio=1.5\nname=app\nname=app2";
+        let value = properties_to_flat_value(content);
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "a": 1,
+                "a.b": 2,
+                "port": "08080",
+                "enabled": true,
+                "ratio": 1.5,
+                "name": "app2",
+            })
+        );
+        //嵌套转换时同一路径既有值又有子键会报错,平铺转换不受影响
+        assert!(properties_to_value(content).is_err());
+        let key = ConfigKey::new("app.properties", "DEFAULT_GROUP", "");
+        let md5 = Arc::new("md5".to_owned());
+        let v = ConfigTranscoder::coerce_properties_with_cache(&key, &md5, content).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&v).unwrap(),
+            value
+        );
+        //与嵌套的json转码结果分开缓存
+        let nested = ConfigTranscoder::transcode_with_cache(
+            &key,
+            &md5,
+            "a.b=2",
+            &ConfigType::Properties,
+            &ConfigType::Json,
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&nested).unwrap(),
+            serde_json::json!({"a": {"b": 2}})
+        );
+    }
+
+    #[test]
+    fn negotiate_accept() {
+        let json = ConfigType::Json;
+        assert_eq!(
+            ConfigTranscoder::negotiate("application/yaml, */*;q=0.8", &json),
+            Some(ConfigType::Yaml)
+        );
+        assert_eq!(ConfigTranscoder::negotiate("*/*", &json), None);
+        //请求同等接受原类型时按原类型返回
+        assert_eq!(
+            ConfigTranscoder::negotiate("application/json, text/plain, */*", &ConfigType::Yaml),
+            None
+        );
+        assert_eq!(
+            ConfigTranscoder::negotiate("application/json, application/yaml", &ConfigType::Yaml),
+            None
+        );
+        assert_eq!(
+            ConfigTranscoder::negotiate(
+                "application/json, application/yaml;q=0.9",
+                &ConfigType::Yaml
+            ),
+            Some(ConfigType::Json)
+        );
+        //按q值优先
+        assert_eq!(
+            ConfigTranscoder::negotiate(
+                "application/json;q=0.5, application/toml",
+                &ConfigType::Yaml
+            ),
+            Some(ConfigType::Toml)
+        );
+        assert_eq!(
+            ConfigTranscoder::negotiate("application/yaml;q=0, application/toml;q=0.1", &json),
+            Some(ConfigType::Toml)
+        );
+        assert_eq!(
+            ConfigTranscoder::negotiate("application/yaml", &ConfigType::Yaml),
+            None
+        );
+    }
+}
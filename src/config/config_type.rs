@@ -8,6 +8,7 @@ lazy_static::lazy_static! {
     pub(crate) static ref CONFIG_TYPE_HTML: Arc<String> =  Arc::new("html".to_string());
     pub(crate) static ref CONFIG_TYPE_PROPERTIES: Arc<String> =  Arc::new("properties".to_string());
     pub(crate) static ref CONFIG_TYPE_TOML: Arc<String> =  Arc::new("toml".to_string());
+    pub(crate) static ref CONFIG_TYPE_FEATURE_FLAG: Arc<String> =  Arc::new("feature_flag".to_string());
 }
 
 //html media type
@@ -15,6 +16,8 @@ pub(crate) const MEDIA_TYPE_TEXT_PLAIN: &str = "text/plain;charset=UTF-8";
 pub(crate) const MEDIA_TYPE_TEXT_HTML: &str = "text/html;charset=UTF-8";
 pub(crate) const MEDIA_TYPE_APPLICATION_JSON: &str = "application/json;charset=UTF-8";
 pub(crate) const MEDIA_TYPE_APPLICATION_XML: &str = "application/xml;charset=UTF-8";
+pub(crate) const MEDIA_TYPE_APPLICATION_YAML: &str = "application/yaml;charset=UTF-8";
+pub(crate) const MEDIA_TYPE_APPLICATION_TOML: &str = "application/toml;charset=UTF-8";
 
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
 pub enum ConfigType {
@@ -25,6 +28,8 @@ pub enum ConfigType {
     Html,
     Properties,
     Toml,
+    //内容为json格式的功能开关,见FeatureFlag
+    FeatureFlag,
 }
 
 impl Default for ConfigType {
@@ -45,10 +50,82 @@ impl ConfigType {
             "html" => Self::Html,
             "toml" => Self::Toml,
             "properties" => Self::Properties,
+            "feature_flag" => Self::FeatureFlag,
             _ => Self::Text,
         }
     }
 
+    ///
+    /// 根据媒介类型获取类型,用于multipart上传时按内容部分的Content-Type设置类型;
+    /// 无法对应时返回None
+    pub fn new_by_media_type(media_type: &str) -> Option<Self> {
+        let essence = media_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match essence.as_str() {
+            "application/json" => Some(Self::Json),
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                Some(Self::Yaml)
+            }
+            "application/xml" | "text/xml" => Some(Self::Xml),
+            "text/html" => Some(Self::Html),
+            "application/toml" => Some(Self::Toml),
+            "text/x-java-properties" | "text/x-properties" => Some(Self::Properties),
+            v if v.ends_with("+json") => Some(Self::Json),
+            v if v.ends_with("+xml") => Some(Self::Xml),
+            _ => None,
+        }
+    }
+
+    ///
+    /// 根据内容推断类型,用于未设置类型的配置
+    pub fn detect(content: &str) -> Self {
+        let trimmed = content.trim();
+        if trimmed.is_empty() {
+            return Self::Text;
+        }
+        if (trimmed.starts_with('{') || trimmed.starts_with('['))
+            && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+        {
+            return Self::Json;
+        }
+        if trimmed.starts_with('<') && trimmed.ends_with('>') {
+            let head = trimmed
+                .chars()
+                .take(64)
+                .collect::<String>()
+                .to_ascii_lowercase();
+            if head.starts_with("<!doctype html") || head.starts_with("<html") {
+                return Self::Html;
+            }
+            return Self::Xml;
+        }
+        if matches!(
+            serde_yaml::from_str::<serde_yaml::Value>(trimmed),
+            Ok(serde_yaml::Value::Mapping(_)) | Ok(serde_yaml::Value::Sequence(_))
+        ) {
+            return Self::Yaml;
+        }
+        let lines: Vec<&str> = trimmed
+            .lines()
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty() && !v.starts_with('#') && !v.starts_with('!'))
+            .collect();
+        //toml与properties均为key=value形式,有表头或带引号的值时才认为是toml
+        if lines.iter().any(|v| v.starts_with('[') || v.contains('"'))
+            && toml::from_str::<toml::Table>(trimmed).is_ok()
+        {
+            return Self::Toml;
+        }
+        if !lines.is_empty() && lines.iter().all(|v| v.contains('=')) {
+            return Self::Properties;
+        }
+        Self::Text
+    }
+
     ///
     /// 获取类型对应值，储存到配置中心内存前统一使用此函数获取的值；
     /// 多个配置的类型值使用同一块地址，可节省内存；
@@ -61,6 +138,7 @@ impl ConfigType {
             ConfigType::Html => CONFIG_TYPE_HTML.clone(),
             ConfigType::Properties => CONFIG_TYPE_PROPERTIES.clone(),
             ConfigType::Toml => CONFIG_TYPE_TOML.clone(),
+            ConfigType::FeatureFlag => CONFIG_TYPE_FEATURE_FLAG.clone(),
         }
     }
 
@@ -76,6 +154,64 @@ impl ConfigType {
             ConfigType::Html => MEDIA_TYPE_TEXT_HTML,
             ConfigType::Properties => MEDIA_TYPE_TEXT_PLAIN,
             ConfigType::Toml => MEDIA_TYPE_TEXT_PLAIN,
+            ConfigType::FeatureFlag => MEDIA_TYPE_APPLICATION_JSON,
+        }
+    }
+
+    ///
+    /// 获取结构化类型对应的媒介类型,用于转码后的响应
+    pub fn get_structured_media_type(&self) -> &'static str {
+        match self {
+            ConfigType::Json => MEDIA_TYPE_APPLICATION_JSON,
+            ConfigType::Yaml => MEDIA_TYPE_APPLICATION_YAML,
+            ConfigType::Toml => MEDIA_TYPE_APPLICATION_TOML,
+            _ => self.get_media_type(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_config_type() {
+        assert_eq!(ConfigType::detect(""), ConfigType::Text);
+        assert_eq!(ConfigType::detect("{\"a\":1}"), ConfigType::Json);
+        assert_eq!(ConfigType::detect("<a><b>1</b></a>"), ConfigType::Xml);
+        assert_eq!(
+            ConfigType::detect("<!DOCTYPE html><html></html>"),
+            ConfigType::Html
+        );
+        assert_eq!(ConfigType::detect("a:\n  b: 1\n"), ConfigType::Yaml);
+        assert_eq!(
+            ConfigType::detect("[server]\nhost = \"127.0.0.1\"\n"),
+            ConfigType::Toml
+        );
+        assert_eq!(
+            ConfigType::detect("# comment\na.b=1\nc=hello world\n"),
+            ConfigType::Properties
+        );
+        assert_eq!(ConfigType::detect("hello world"), ConfigType::Text);
+    }
+
+    #[test]
+    fn config_type_by_media_type() {
+        assert_eq!(
+            ConfigType::new_by_media_type("application/json; charset=utf-8"),
+            Some(ConfigType::Json)
+        );
+        assert_eq!(
+            ConfigType::new_by_media_type("application/yaml"),
+            Some(ConfigType::Yaml)
+        );
+        assert_eq!(
+            ConfigType::new_by_media_type("application/vnd.api+json"),
+            Some(ConfigType::Json)
+        );
+        assert_eq!(
+            ConfigType::new_by_media_type("application/octet-stream"),
+            None
+        );
+    }
+}
@@ -0,0 +1,437 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::config_feature_flag::FeatureFlag;
+use super::config_type::ConfigType;
+use super::core::ConfigKey;
+use super::utils::param_utils;
+use crate::utils::get_md5;
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigValidateResult {
+    pub valid: bool,
+    pub md5: String,
+    pub r#type: Arc<String>,
+    pub warnings: Vec<String>,
+    pub errors: Vec<String>,
+    //内容格式错误的位置
+    pub error_positions: Vec<ConfigContentError>,
+}
+
+///
+/// 内容格式错误及其位置;line、column从1开始
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigContentError {
+    pub path: String,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigImportInvalidItem {
+    pub data_id: Arc<String>,
+    pub group: Arc<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigImportReport {
+    pub total: usize,
+    pub valid: usize,
+    pub invalid: Vec<ConfigImportInvalidItem>,
+}
+
+pub struct ConfigValidator;
+
+impl ConfigValidator {
+    ///
+    /// 校验配置参数与内容,不写入配置
+    pub fn validate(
+        data_id: &Option<String>,
+        group: &Option<String>,
+        tenant: &Option<String>,
+        content: &Option<String>,
+        config_type: &Option<String>,
+    ) -> ConfigValidateResult {
+        let mut result = ConfigValidateResult::default();
+        if let Err(err) = param_utils::check_tenant(tenant) {
+            result.errors.push(err.to_string());
+        }
+        if let Err(err) =
+            param_utils::check_param(data_id, group, &Some(String::from("datumId")), content)
+        {
+            result.errors.push(err.to_string());
+        }
+        let config_type = match config_type {
+            Some(v) if !v.is_empty() => ConfigType::new_by_value(v),
+            _ => {
+                result
+                    .warnings
+                    .push("config type is empty, use text as default".to_owned());
+                ConfigType::Text
+            }
+        };
+        result.r#type = config_type.get_value();
+        let content = content.as_ref().map(|v| v.as_str()).unwrap_or_default();
+        if let Some(err) = Self::content_error(content, &config_type) {
+            result.errors.push(format!(
+                "invalid {} content: {}",
+                config_type.get_value(),
+                &err.message
+            ));
+            result.error_positions.push(err);
+        }
+        Self::lint_content(content, &mut result.warnings);
+        result.md5 = get_md5(content);
+        result.valid = result.errors.is_empty();
+        result
+    }
+
+    ///
+    /// 按配置类型校验内容格式
+    pub fn validate_content(content: &str, config_type: &ConfigType) -> anyhow::Result<()> {
+        match config_type {
+            ConfigType::Json => {
+                serde_json::from_str::<serde_json::Value>(content)
+                    .map_err(|e| anyhow::anyhow!("invalid json content: {}", e))?;
+            }
+            ConfigType::Yaml => {
+                serde_yaml::from_str::<serde_yaml::Value>(content)
+                    .map_err(|e| anyhow::anyhow!("invalid yaml content: {}", e))?;
+            }
+            ConfigType::Toml => {
+                toml::from_str::<toml::Value>(content)
+                    .map_err(|e| anyhow::anyhow!("invalid toml content: {}", e))?;
+            }
+            ConfigType::FeatureFlag => {
+                FeatureFlag::parse(content)
+                    .map_err(|e| anyhow::anyhow!("invalid feature_flag content: {}", e))?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    ///
+    /// 按配置类型解析内容,返回第一个格式错误及其位置
+    pub fn content_error(content: &str, config_type: &ConfigType) -> Option<ConfigContentError> {
+        match config_type {
+            ConfigType::Json => {
+                let err = serde_json::from_str::<serde_json::Value>(content).err()?;
+                let (line, column) = (err.line(), err.column());
+                Some(ConfigContentError {
+                    path: json_path_at(content, line, column),
+                    message: err.to_string(),
+                    line: Some(line),
+                    column: Some(column),
+                })
+            }
+            ConfigType::Yaml => {
+                let err = serde_yaml::from_str::<serde_yaml::Value>(content).err()?;
+                let location = err.location();
+                Some(ConfigContentError {
+                    path: "$".to_owned(),
+                    message: err.to_string(),
+                    line: location.as_ref().map(|v| v.line()),
+                    column: location.as_ref().map(|v| v.column()),
+                })
+            }
+            ConfigType::Toml => {
+                let err = toml::from_str::<toml::Value>(content).err()?;
+                let position = err.span().map(|v| line_column_at(content, v.start));
+                Some(ConfigContentError {
+                    path: "$".to_owned(),
+                    message: err.message().to_owned(),
+                    line: position.map(|v| v.0),
+                    column: position.map(|v| v.1),
+                })
+            }
+            ConfigType::FeatureFlag => {
+                let err = FeatureFlag::parse(content).err()?;
+                let position = err
+                    .downcast_ref::<serde_json::Error>()
+                    .map(|v| (v.line(), v.column()));
+                Some(ConfigContentError {
+                    path: "$".to_owned(),
+                    message: err.to_string(),
+                    line: position.map(|v| v.0),
+                    column: position.map(|v| v.1),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    ///
+    /// 校验待导入的配置,不写入配置;配置类型按dataId的扩展名确定
+    pub fn validate_import(items: &[(ConfigKey, String)]) -> ConfigImportReport {
+        let mut report = ConfigImportReport {
+            total: items.len(),
+            ..Default::default()
+        };
+        for (key, content) in items {
+            let config_type = Self::import_config_type(&key.data_id);
+            let result = Self::validate(
+                &Some(key.data_id.to_string()),
+                &Some(key.group.to_string()),
+                &Some(key.tenant.to_string()),
+                &Some(content.to_owned()),
+                &config_type,
+            );
+            if result.valid {
+                report.valid += 1;
+            } else {
+                report.invalid.push(ConfigImportInvalidItem {
+                    data_id: key.data_id.clone(),
+                    group: key.group.clone(),
+                    errors: result.errors,
+                });
+            }
+        }
+        report
+    }
+
+    fn import_config_type(data_id: &str) -> Option<String> {
+        let ext = data_id
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_lowercase())?;
+        match ext.as_str() {
+            "yml" => Some("yaml".to_owned()),
+            "json" | "yaml" | "toml" | "xml" | "html" | "properties" => Some(ext),
+            _ => None,
+        }
+    }
+
+    fn lint_content(content: &str, warnings: &mut Vec<String>) {
+        if content.contains('\t') {
+            warnings.push("content contains tab characters".to_owned());
+        }
+        if content.lines().any(|line| line.ends_with(' ')) {
+            warnings.push("content contains trailing whitespace".to_owned());
+        }
+    }
+}
+
+///
+/// 字节偏移对应的行列,从1开始
+fn line_column_at(content: &str, offset: usize) -> (usize, usize) {
+    let prefix = &content[..offset.min(content.len())];
+    let line = prefix.matches('\n').count() + 1;
+    let column = prefix
+        .rsplit('\n')
+        .next()
+        .map(|v| v.chars().count())
+        .unwrap_or(0)
+        + 1;
+    (line, column)
+}
+
+enum JsonPathSegment {
+    Key(Option<String>),
+    Index(usize),
+}
+
+///
+/// 扫描到错误位置为止的json内容,返回错误所在的json path
+fn json_path_at(content: &str, line: usize, column: usize) -> String {
+    let mut stack: Vec<JsonPathSegment> = vec![];
+    let mut in_string = false;
+    let mut escape = false;
+    let mut expect_key = false;
+    let mut buf = String::new();
+    let (mut cur_line, mut cur_column) = (1, 0);
+    for c in content.chars() {
+        if c == '\n' {
+            cur_line += 1;
+            cur_column = 0;
+        } else {
+            cur_column += 1;
+        }
+        if cur_line > line || (cur_line == line && cur_column >= column) {
+            break;
+        }
+        if in_string {
+            if escape {
+                escape = false;
+                buf.push(c);
+            } else if c == '\\' {
+                escape = true;
+            } else if c == '"' {
+                in_string = false;
+                if expect_key {
+                    if let Some(JsonPathSegment::Key(key)) = stack.last_mut() {
+                        *key = Some(std::mem::take(&mut buf));
+                    }
+                }
+            } else {
+                buf.push(c);
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                buf.clear();
+            }
+            '{' => {
+                stack.push(JsonPathSegment::Key(None));
+                expect_key = true;
+            }
+            '[' => stack.push(JsonPathSegment::Index(0)),
+            '}' | ']' => {
+                stack.pop();
+                expect_key = false;
+            }
+            ':' => expect_key = false,
+            ',' => match stack.last_mut() {
+                Some(JsonPathSegment::Index(i)) => *i += 1,
+                Some(JsonPathSegment::Key(key)) => {
+                    *key = None;
+                    expect_key = true;
+                }
+                None => {}
+            },
+            _ => {}
+        }
+    }
+    let mut path = "$".to_owned();
+    for item in &stack {
+        match item {
+            JsonPathSegment::Key(Some(key)) => {
+                path.push('.');
+                path.push_str(key);
+            }
+            JsonPathSegment::Key(None) => {}
+            JsonPathSegment::Index(i) => path.push_str(&format!("[{}]", i)),
+        }
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_import_items() {
+        let items = vec![
+            (
+                ConfigKey::new("app.yml", "DEFAULT_GROUP", ""),
+                "a:\n  b: 1".to_owned(),
+            ),
+            (
+                ConfigKey::new("app.json", "DEFAULT_GROUP", ""),
+                "{\"a\":".to_owned(),
+            ),
+            (
+                ConfigKey::new("app.conf", "DEFAULT_GROUP", ""),
+                "{".to_owned(),
+            ),
+            (
+                ConfigKey::new("empty.txt", "DEFAULT_GROUP", ""),
+                "".to_owned(),
+            ),
+        ];
+        let report = ConfigValidator::validate_import(&items);
+        assert_eq!(report.total, 4);
+        assert_eq!(report.valid, 2);
+        assert_eq!(report.invalid.len(), 2);
+        assert_eq!(report.invalid[0].data_id.as_str(), "app.json");
+        assert!(report.invalid[0].errors[0].contains("invalid json"));
+        assert_eq!(report.invalid[1].data_id.as_str(), "empty.txt");
+    }
+
+    #[test]
+    fn dry_run_validate() {
+        let content = "server:\n  port: 8080\n";
+        let result = ConfigValidator::validate(
+            &Some("app.yaml".to_owned()),
+            &Some("DEFAULT_GROUP".to_owned()),
+            &Some("dev".to_owned()),
+            &Some(content.to_owned()),
+            &Some("yaml".to_owned()),
+        );
+        assert!(result.valid);
+        assert_eq!(result.md5, get_md5(content));
+        assert_eq!(result.r#type.as_str(), "yaml");
+        assert!(result.warnings.is_empty());
+        assert!(result.errors.is_empty());
+
+        //未指定类型及格式问题只作为警告
+        let content = "a:\t1 \n";
+        let result = ConfigValidator::validate(
+            &Some("app".to_owned()),
+            &Some("DEFAULT_GROUP".to_owned()),
+            &None,
+            &Some(content.to_owned()),
+            &None,
+        );
+        assert!(result.valid);
+        assert_eq!(result.r#type.as_str(), "text");
+        assert_eq!(result.warnings.len(), 3);
+
+        //参数错误与内容错误同时返回
+        let result = ConfigValidator::validate(
+            &Some("app.json".to_owned()),
+            &Some("DEFAULT_GROUP".to_owned()),
+            &Some("dev/../x".to_owned()),
+            &Some("{".to_owned()),
+            &Some("json".to_owned()),
+        );
+        assert!(!result.valid);
+        assert_eq!(result.errors.len(), 2);
+        assert_eq!(result.errors[0], "invalid tenant");
+        assert_eq!(result.md5, get_md5("{"));
+
+        let result = ConfigValidator::validate(
+            &Some("app.json".to_owned()),
+            &Some("DEFAULT_GROUP".to_owned()),
+            &None,
+            &Some("".to_owned()),
+            &Some("json".to_owned()),
+        );
+        assert!(!result.valid);
+        assert!(result.errors[0].starts_with("content is blank"));
+    }
+
+    #[test]
+    fn content_error_position() {
+        let content =
+            "{\n  \"database\": {\n    \"hosts\": [\"a\", \"b\"],\n    \"port\": 8o,\n  }\n}";
+        let err = ConfigValidator::content_error(content, &ConfigType::Json).unwrap();
+        assert_eq!(err.path, "$.database.port");
+        assert_eq!(err.line, Some(4));
+
+        let content = "{\"list\": [1, 2, {\"a\": }]}";
+        let err = ConfigValidator::content_error(content, &ConfigType::Json).unwrap();
+        assert_eq!(err.path, "$.list[2].a");
+
+        let content = "a:\n  b: 1\n c: [";
+        let err = ConfigValidator::content_error(content, &ConfigType::Yaml).unwrap();
+        assert_eq!(err.line, Some(3));
+
+        let content = "[server]\nport = \n";
+        let err = ConfigValidator::content_error(content, &ConfigType::Toml).unwrap();
+        assert_eq!(err.line, Some(2));
+
+        assert!(ConfigValidator::content_error("{}", &ConfigType::Json).is_none());
+        let result = ConfigValidator::validate(
+            &Some("app.json".to_owned()),
+            &None,
+            &None,
+            &Some("{\"a\": }".to_owned()),
+            &Some("json".to_owned()),
+        );
+        assert!(!result.valid);
+        assert_eq!(result.error_positions[0].path, "$.a");
+        assert!(result
+            .errors
+            .iter()
+            .any(|v| v.starts_with("invalid json content")));
+    }
+}
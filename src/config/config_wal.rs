@@ -0,0 +1,396 @@
+use std::convert::{TryFrom, TryInto};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::config_history_index::ConfigDeleteHistory;
+use super::core::ConfigKey;
+use super::model::{ConfigRaftCmd, ConfigValueDO};
+use crate::raft::store::ClientRequest;
+
+const WAL_FILE_NAME: &str = "config.wal";
+/// 压缩后追加的记录数达到该值后,下次定时检查时压缩日志
+const WAL_COMPACT_MIN_RECORDS: usize = 1000;
+/// 两次压缩的最小间隔
+const WAL_COMPACT_INTERVAL: Duration = Duration::from_secs(600);
+
+const KIND_REQUEST: u8 = 1;
+const KIND_VALUE: u8 = 2;
+const KIND_LAST_ID: u8 = 3;
+const KIND_SNAPSHOT: u8 = 4;
+const KIND_DELETE_HISTORY: u8 = 5;
+
+///
+/// 单机模式配置,不经过raft,配置写入先追加到本地预写日志
+#[derive(Debug, Clone)]
+pub struct ConfigWalConfig {
+    pub wal_dir: PathBuf,
+}
+
+impl ConfigWalConfig {
+    pub fn from_env(config_db_dir: &str) -> Option<Self> {
+        let mode = std::env::var("RNACOS_CLUSTER_MODE").unwrap_or("cluster".to_owned());
+        if !mode.eq_ignore_ascii_case("standalone") {
+            return None;
+        }
+        Some(Self {
+            wal_dir: Path::new(config_db_dir).join("config_wal"),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ConfigWalRecord {
+    //写入的配置请求,与raft日志内容相同,回放时按应用raft日志的方式执行
+    Request(ClientRequest),
+    //压缩时写入的完整配置,含历史版本、引用、优先级及读写时间
+    Value(ConfigKey, ConfigValueDO),
+    //压缩时的历史记录id
+    LastId(u64),
+    //压缩后的日志以该记录开头,到LastId为止的Value为全部配置
+    Snapshot,
+    //压缩时写入的删除记录
+    DeleteHistory(ConfigDeleteHistory),
+}
+
+impl ConfigWalRecord {
+    ///
+    /// 记录格式:[length: u32][kind: u8][body],length为kind与body的总字节数
+    fn encode(&self) -> anyhow::Result<Vec<u8>> {
+        let (kind, body) = match self {
+            ConfigWalRecord::Request(req) => (KIND_REQUEST, serde_json::to_vec(req)?),
+            ConfigWalRecord::Value(key, value) => {
+                let key = key.build_key();
+                let value = value.to_bytes()?;
+                let mut body = Vec::with_capacity(4 + key.len() + value.len());
+                body.extend_from_slice(&u32::try_from(key.len())?.to_be_bytes());
+                body.extend_from_slice(key.as_bytes());
+                body.extend_from_slice(&value);
+                (KIND_VALUE, body)
+            }
+            ConfigWalRecord::LastId(id) => (KIND_LAST_ID, id.to_be_bytes().to_vec()),
+            ConfigWalRecord::Snapshot => (KIND_SNAPSHOT, vec![]),
+            ConfigWalRecord::DeleteHistory(item) => {
+                (KIND_DELETE_HISTORY, serde_json::to_vec(item)?)
+            }
+        };
+        let length = u32::try_from(1 + body.len())?;
+        let mut buf = Vec::with_capacity(5 + body.len());
+        buf.extend_from_slice(&length.to_be_bytes());
+        buf.push(kind);
+        buf.extend_from_slice(&body);
+        Ok(buf)
+    }
+
+    fn decode(data: &[u8]) -> anyhow::Result<Self> {
+        let (kind, body) = match data.split_first() {
+            Some(v) => v,
+            None => return Err(anyhow::anyhow!("config wal record is empty")),
+        };
+        match *kind {
+            KIND_REQUEST => Ok(ConfigWalRecord::Request(serde_json::from_slice(body)?)),
+            KIND_VALUE => {
+                if body.len() < 4 {
+                    return Err(anyhow::anyhow!("config wal value record is broken"));
+                }
+                let mut key_len = [0u8; 4];
+                key_len.copy_from_slice(&body[..4]);
+                let key_end = 4 + u32::from_be_bytes(key_len) as usize;
+                if key_end > body.len() {
+                    return Err(anyhow::anyhow!("config wal value record is broken"));
+                }
+                let key = ConfigKey::from(std::str::from_utf8(&body[4..key_end])?);
+                let value = ConfigValueDO::from_bytes(&body[key_end..])?;
+                Ok(ConfigWalRecord::Value(key, value))
+            }
+            KIND_LAST_ID => {
+                let id: [u8; 8] = body
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("config wal last id record is broken"))?;
+                Ok(ConfigWalRecord::LastId(u64::from_be_bytes(id)))
+            }
+            KIND_SNAPSHOT => Ok(ConfigWalRecord::Snapshot),
+            KIND_DELETE_HISTORY => Ok(ConfigWalRecord::DeleteHistory(serde_json::from_slice(
+                body,
+            )?)),
+            _ => Err(anyhow::anyhow!("unknown config wal record kind: {}", kind)),
+        }
+    }
+}
+
+///
+/// 与应用raft日志相同,把配置相关的请求转为ConfigActor的命令
+pub fn to_config_raft_cmd(req: ClientRequest) -> Option<ConfigRaftCmd> {
+    match req {
+        ClientRequest::ConfigSet {
+            key,
+            value,
+            config_type,
+            desc,
+            history_id,
+            history_table_id,
+            op_time,
+            op_user,
+            expected_md5,
+            references,
+            priority,
+            max_versions,
+        } => Some(ConfigRaftCmd::ConfigAdd {
+            key,
+            value,
+            config_type,
+            desc,
+            history_id,
+            history_table_id,
+            op_time,
+            op_user,
+            expected_md5,
+            references,
+            priority,
+            max_versions,
+        }),
+        ClientRequest::ConfigRemove {
+            key,
+            op_time,
+            op_user,
+        } => Some(ConfigRaftCmd::ConfigRemove {
+            key,
+            op_time,
+            op_user,
+        }),
+        ClientRequest::ConfigBatchSet {
+            items,
+            op_time,
+            op_user,
+            atomic,
+            max_versions,
+        } => Some(ConfigRaftCmd::ConfigBatchAdd {
+            items,
+            op_time,
+            op_user,
+            atomic,
+            max_versions,
+        }),
+        ClientRequest::ConfigArchive {
+            key,
+            md5,
+            archive_uri,
+        } => Some(ConfigRaftCmd::ConfigArchive {
+            key,
+            md5,
+            archive_uri,
+        }),
+        ClientRequest::ConfigTouch { items } => Some(ConfigRaftCmd::ConfigTouch { items }),
+        ClientRequest::ConfigPurgeNamespace { tenant, op_time } => {
+            Some(ConfigRaftCmd::ConfigPurgeNamespace { tenant, op_time })
+        }
+        _ => None,
+    }
+}
+
+///
+/// 配置预写日志,追加写入且每次写入后fsync;
+/// 定时压缩为当前全部配置的快照,日志大小与配置数量相关而不随写入次数增长
+pub struct ConfigWal {
+    path: PathBuf,
+    file: File,
+    //打开时读出、等待回放的记录
+    pending: Vec<ConfigWalRecord>,
+    //上次压缩后追加的记录数
+    appended: usize,
+    last_compact: Instant,
+}
+
+impl ConfigWal {
+    ///
+    /// 打开日志并读出全部记录等待回放;崩溃时写了一半的尾部记录会被截掉
+    pub fn open(wal_dir: &Path) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(wal_dir)?;
+        let path = wal_dir.join(WAL_FILE_NAME);
+        let mut data = vec![];
+        if path.exists() {
+            File::open(&path)?.read_to_end(&mut data)?;
+        }
+        let (records, valid_len) = Self::read_records(&data);
+        let file = OpenOptions::new().append(true).create(true).open(&path)?;
+        if valid_len < data.len() {
+            log::warn!(
+                "config wal has a broken tail, drop {} bytes",
+                data.len() - valid_len
+            );
+            file.set_len(valid_len as u64)?;
+            file.sync_all()?;
+        }
+        Ok(Self {
+            path,
+            file,
+            appended: records.len(),
+            pending: records,
+            last_compact: Instant::now(),
+        })
+    }
+
+    fn read_records(data: &[u8]) -> (Vec<ConfigWalRecord>, usize) {
+        let mut records = vec![];
+        let mut offset = 0;
+        while offset + 4 <= data.len() {
+            let mut length = [0u8; 4];
+            length.copy_from_slice(&data[offset..offset + 4]);
+            let end = offset + 4 + u32::from_be_bytes(length) as usize;
+            if end > data.len() {
+                break;
+            }
+            match ConfigWalRecord::decode(&data[offset + 4..end]) {
+                Ok(record) => records.push(record),
+                Err(err) => {
+                    log::warn!("config wal record decode error:{}", err);
+                    break;
+                }
+            }
+            offset = end;
+        }
+        (records, offset)
+    }
+
+    ///
+    /// 取出打开时读到的记录,只在启动回放时调用一次
+    pub fn take_pending(&mut self) -> Vec<ConfigWalRecord> {
+        std::mem::take(&mut self.pending)
+    }
+
+    ///
+    /// 同一批记录一次写入并fsync,返回后记录已落盘
+    pub fn append(&mut self, records: &[ConfigWalRecord]) -> anyhow::Result<()> {
+        let mut buf = vec![];
+        for record in records {
+            buf.extend(record.encode()?);
+        }
+        self.file.write_all(&buf)?;
+        self.file.sync_data()?;
+        self.appended += records.len();
+        Ok(())
+    }
+
+    pub fn need_compact(&self) -> bool {
+        self.appended >= WAL_COMPACT_MIN_RECORDS
+            && self.last_compact.elapsed() >= WAL_COMPACT_INTERVAL
+    }
+
+    ///
+    /// 用当前全部配置及删除记录重写日志:先写临时文件并fsync,再替换原日志
+    pub fn compact<I>(
+        &mut self,
+        values: I,
+        deletes: Vec<ConfigDeleteHistory>,
+        last_id: u64,
+    ) -> anyhow::Result<()>
+    where
+        I: IntoIterator<Item = (ConfigKey, ConfigValueDO)>,
+    {
+        let tmp_path = self.path.with_extension("wal.tmp");
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        writer.write_all(&ConfigWalRecord::Snapshot.encode()?)?;
+        for (key, value) in values {
+            writer.write_all(&ConfigWalRecord::Value(key, value).encode()?)?;
+        }
+        for item in deletes {
+            writer.write_all(&ConfigWalRecord::DeleteHistory(item).encode()?)?;
+        }
+        writer.write_all(&ConfigWalRecord::LastId(last_id).encode()?)?;
+        let tmp_file = writer.into_inner().map_err(|err| err.into_error())?;
+        tmp_file.sync_all()?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.appended = 0;
+        self.last_compact = Instant::now();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn set_request(data_id: &str, content: &str, history_id: u64) -> ConfigWalRecord {
+        ConfigWalRecord::Request(ClientRequest::ConfigSet {
+            key: ConfigKey::new(data_id, "DEFAULT_GROUP", "dev").build_key(),
+            value: Arc::new(content.to_owned()),
+            config_type: Some(Arc::new("yaml".to_owned())),
+            desc: None,
+            history_id,
+            history_table_id: None,
+            op_time: 100,
+            op_user: Some(Arc::new("admin".to_owned())),
+            expected_md5: None,
+            references: None,
+            priority: None,
+            max_versions: None,
+        })
+    }
+
+    #[test]
+    fn wal_replay_and_compact() {
+        let dir = std::env::temp_dir().join(format!("rnacos_wal_{}", uuid::Uuid::new_v4()));
+        {
+            let mut wal = ConfigWal::open(&dir).unwrap();
+            assert!(wal.take_pending().is_empty());
+            wal.append(&[set_request("app.yaml", "a: 1", 1)]).unwrap();
+            wal.append(&[set_request("app.yaml", "a: 2", 2)]).unwrap();
+        }
+        //模拟写了一半的记录
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.join(WAL_FILE_NAME))
+            .unwrap();
+        file.write_all(&[0, 0, 1, 0, KIND_REQUEST, b'{']).unwrap();
+        drop(file);
+        let mut wal = ConfigWal::open(&dir).unwrap();
+        let records = wal.take_pending();
+        assert_eq!(records.len(), 2);
+        match &records[1] {
+            ConfigWalRecord::Request(req) => match to_config_raft_cmd(req.clone()) {
+                Some(ConfigRaftCmd::ConfigAdd { value, op_user, .. }) => {
+                    assert_eq!(value.as_str(), "a: 2");
+                    assert_eq!(op_user.unwrap().as_str(), "admin");
+                }
+                _ => panic!("unexpected cmd"),
+            },
+            _ => panic!("unexpected record"),
+        }
+
+        let key = ConfigKey::new("app.yaml", "DEFAULT_GROUP", "dev");
+        let value = ConfigValueDO {
+            content: Some("a: 2".to_owned()),
+            last_modified: Some(100),
+            ..Default::default()
+        };
+        let deleted = ConfigDeleteHistory {
+            key: ConfigKey::new("old.yaml", "DEFAULT_GROUP", "dev"),
+            op_time: 90,
+            op_user: Some(Arc::new("admin".to_owned())),
+        };
+        wal.compact(vec![(key.clone(), value.clone())], vec![deleted.clone()], 2)
+            .unwrap();
+        wal.append(&[set_request("db.yaml", "b: 1", 3)]).unwrap();
+        drop(wal);
+        let records = ConfigWal::open(&dir).unwrap().take_pending();
+        assert_eq!(records.len(), 5);
+        assert!(matches!(records[0], ConfigWalRecord::Snapshot));
+        match &records[1] {
+            ConfigWalRecord::Value(k, v) => {
+                assert_eq!(k, &key);
+                assert_eq!(v, &value);
+            }
+            _ => panic!("unexpected record"),
+        }
+        match &records[2] {
+            ConfigWalRecord::DeleteHistory(item) => assert_eq!(item, &deleted),
+            _ => panic!("unexpected record"),
+        }
+        assert!(matches!(records[3], ConfigWalRecord::LastId(2)));
+        assert!(matches!(records[4], ConfigWalRecord::Request(_)));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
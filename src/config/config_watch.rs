@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures_util::future::select_all;
+use tokio::sync::watch;
+
+use super::config_read_view::ConfigReadView;
+use super::core::{ConfigKey, ListenerItem, WildcardListenerItem};
+
+type WatchSender = watch::Sender<Option<ConfigKey>>;
+pub type WatchReceiver = watch::Receiver<Option<ConfigKey>>;
+
+///
+/// 每个配置一个watch通道,长轮询请求自行订阅等待;
+/// 配置变更时只需触发对应通道,不需要跟踪具体请求
+#[derive(Default)]
+pub struct ConfigWatchManage {
+    senders: Mutex<HashMap<ConfigKey, WatchSender>>,
+    wildcard_senders: Mutex<HashMap<WildcardListenerItem, WatchSender>>,
+}
+
+impl ConfigWatchManage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&self, key: &ConfigKey) -> WatchReceiver {
+        let mut senders = self.senders.lock().unwrap();
+        senders
+            .entry(key.clone())
+            .or_insert_with(|| watch::channel(None).0)
+            .subscribe()
+    }
+
+    ///
+    /// 按监听项订阅,dataId为`*`的通配项订阅分组通道
+    pub fn subscribe_item(&self, item: &ListenerItem) -> WatchReceiver {
+        match item.to_wildcard() {
+            Some(wildcard) => self.subscribe_wildcard(wildcard),
+            None => self.subscribe(&item.key),
+        }
+    }
+
+    pub fn subscribe_wildcard(&self, item: WildcardListenerItem) -> WatchReceiver {
+        let mut senders = self.wildcard_senders.lock().unwrap();
+        senders
+            .entry(item)
+            .or_insert_with(|| watch::channel(None).0)
+            .subscribe()
+    }
+
+    pub fn notify(&self, key: &ConfigKey) {
+        let senders = self.senders.lock().unwrap();
+        if let Some(sender) = senders.get(key) {
+            sender.send_replace(Some(key.clone()));
+        }
+        drop(senders);
+        let wildcard_senders = self.wildcard_senders.lock().unwrap();
+        for (item, sender) in wildcard_senders.iter() {
+            if item.is_match(key) {
+                sender.send_replace(Some(key.clone()));
+            }
+        }
+    }
+
+    ///
+    /// 通知所有满足条件的配置通道,每个通道发送各自的key
+    pub fn notify_where<F>(&self, filter: F)
+    where
+        F: Fn(&ConfigKey) -> bool,
+    {
+        let senders = self.senders.lock().unwrap();
+        for (key, sender) in senders.iter() {
+            if filter(key) {
+                sender.send_replace(Some(key.clone()));
+            }
+        }
+    }
+
+    ///
+    /// 清理已没有等待者的通道
+    pub fn clear_unused(&self) {
+        let mut senders = self.senders.lock().unwrap();
+        senders.retain(|_, sender| sender.receiver_count() > 0);
+        drop(senders);
+        let mut wildcard_senders = self.wildcard_senders.lock().unwrap();
+        wildcard_senders.retain(|_, sender| sender.receiver_count() > 0);
+    }
+
+    ///
+    /// 正在长轮询等待该配置的请求数,含匹配的通配监听
+    pub fn listener_count(&self, key: &ConfigKey) -> usize {
+        let count = self
+            .senders
+            .lock()
+            .unwrap()
+            .get(key)
+            .map(|v| v.receiver_count())
+            .unwrap_or(0);
+        let wildcard_senders = self.wildcard_senders.lock().unwrap();
+        count
+            + wildcard_senders
+                .iter()
+                .filter(|(item, _)| item.is_match(key))
+                .map(|(_, sender)| sender.receiver_count())
+                .sum::<usize>()
+    }
+
+    pub fn len(&self) -> usize {
+        self.senders.lock().unwrap().len() + self.wildcard_senders.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///
+    /// 对比监听项与当前配置的md5,返回有变更的配置;通配项没有md5,不参与对比;
+    /// 开启分组默认配置时,不存在的配置与分组默认配置比较
+    pub fn diff_changes(
+        read_view: &ConfigReadView,
+        items: &[ListenerItem],
+        group_default_enabled: bool,
+    ) -> Vec<ConfigKey> {
+        let mut changes = vec![];
+        for item in items {
+            if item.to_wildcard().is_some() {
+                continue;
+            }
+            if let Some(v) = read_view.get_effective(&item.key, group_default_enabled) {
+                if v.md5 != item.md5 {
+                    changes.push(item.key.clone());
+                }
+            } else if !item.md5.is_empty() {
+                changes.push(item.key.clone());
+            }
+        }
+        changes
+    }
+
+    ///
+    /// 等待任一配置变更,超时返回None
+    pub async fn wait_changed(
+        receivers: Vec<WatchReceiver>,
+        timeout: Duration,
+    ) -> Option<ConfigKey> {
+        if receivers.is_empty() {
+            return None;
+        }
+        let futs = receivers.into_iter().map(|mut rx| {
+            Box::pin(async move {
+                match rx.changed().await {
+                    Ok(_) => rx.borrow().clone(),
+                    Err(_) => None,
+                }
+            })
+        });
+        tokio::select! {
+            (v, _, _) = select_all(futs) => v,
+            _ = tokio::time::sleep(timeout) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn watch_notify() {
+        let manage = std::sync::Arc::new(ConfigWatchManage::new());
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let other = ConfigKey::new("b", "DEFAULT_GROUP", "");
+        let receivers = vec![manage.subscribe(&key), manage.subscribe(&other)];
+        let wait = tokio::spawn(ConfigWatchManage::wait_changed(
+            receivers,
+            Duration::from_secs(5),
+        ));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(manage.listener_count(&other), 1);
+        manage.notify(&other);
+        assert_eq!(wait.await.unwrap(), Some(other));
+        manage.clear_unused();
+        assert!(manage.is_empty());
+
+        let receivers = vec![manage.subscribe(&key)];
+        let v = ConfigWatchManage::wait_changed(receivers, Duration::from_millis(10)).await;
+        assert!(v.is_none());
+    }
+
+    #[tokio::test]
+    async fn watch_wildcard_notify() {
+        let manage = std::sync::Arc::new(ConfigWatchManage::new());
+        let item = ListenerItem::new(
+            ConfigKey::new("*", "backend-services", ""),
+            Default::default(),
+        );
+        let receivers = vec![manage.subscribe_item(&item)];
+        let wait = tokio::spawn(ConfigWatchManage::wait_changed(
+            receivers,
+            Duration::from_secs(5),
+        ));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        manage.notify(&ConfigKey::new("a", "DEFAULT_GROUP", ""));
+        manage.notify(&ConfigKey::new("a", "backend-services", "dev"));
+        let key = ConfigKey::new("b", "backend-services", "");
+        manage.notify(&key);
+        assert_eq!(wait.await.unwrap(), Some(key));
+        manage.clear_unused();
+        assert!(manage.is_empty());
+    }
+}
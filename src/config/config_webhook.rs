@@ -0,0 +1,226 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::core::ConfigKey;
+use super::ConfigUtils;
+
+///
+/// 配置写入前调用的外部校验服务
+#[derive(Debug, Clone, Default)]
+pub struct ValidationWebhook {
+    pub url: String,
+    pub timeout_ms: u64,
+    //为空时校验所有命名空间
+    pub namespace_filter: Vec<String>,
+    //超时或网络错误时是否拒绝写入
+    pub strict: bool,
+}
+
+impl ValidationWebhook {
+    pub fn from_env() -> Option<Self> {
+        let url = std::env::var("RNACOS_CONFIG_VALIDATION_WEBHOOK_URL").unwrap_or_default();
+        if url.is_empty() {
+            return None;
+        }
+        let timeout_ms = std::env::var("RNACOS_CONFIG_VALIDATION_WEBHOOK_TIMEOUT_MS")
+            .unwrap_or("3000".to_owned())
+            .parse()
+            .unwrap_or(3000);
+        let namespace_filter =
+            std::env::var("RNACOS_CONFIG_VALIDATION_WEBHOOK_NAMESPACES").unwrap_or_default();
+        let strict = std::env::var("RNACOS_CONFIG_VALIDATION_WEBHOOK_STRICT")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        Some(Self {
+            url,
+            timeout_ms,
+            namespace_filter: namespace_filter
+                .split(',')
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+                .map(|v| ConfigUtils::default_tenant(v.to_owned()))
+                .collect(),
+            strict,
+        })
+    }
+
+    pub fn is_match(&self, tenant: &str) -> bool {
+        self.namespace_filter.is_empty() || self.namespace_filter.iter().any(|v| v == tenant)
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WebhookRequest<'a> {
+    data_id: &'a str,
+    group: &'a str,
+    tenant: &'a str,
+    content: &'a str,
+    r#type: Option<&'a str>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookResponse {
+    valid: bool,
+    reason: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum WebhookValidateResult {
+    Pass,
+    Reject(String),
+}
+
+pub struct ConfigValidationWebhook {
+    webhook: ValidationWebhook,
+    client: reqwest::Client,
+}
+
+impl ConfigValidationWebhook {
+    pub fn new(webhook: ValidationWebhook) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(webhook.timeout_ms))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        Ok(Self { webhook, client })
+    }
+
+    pub async fn validate(
+        &self,
+        key: &ConfigKey,
+        content: &str,
+        config_type: Option<&str>,
+    ) -> WebhookValidateResult {
+        if !self.webhook.is_match(&key.tenant) {
+            return WebhookValidateResult::Pass;
+        }
+        let req = WebhookRequest {
+            data_id: &key.data_id,
+            group: &key.group,
+            tenant: &key.tenant,
+            content,
+            r#type: config_type,
+        };
+        match self.request(&req).await {
+            Ok(resp) if resp.valid => WebhookValidateResult::Pass,
+            Ok(resp) => WebhookValidateResult::Reject(resp.reason.unwrap_or_default()),
+            Err(err) => {
+                log::warn!("config validation webhook error, {}", err);
+                if self.webhook.strict {
+                    WebhookValidateResult::Reject(format!(
+                        "validation webhook unavailable: {}",
+                        err
+                    ))
+                } else {
+                    WebhookValidateResult::Pass
+                }
+            }
+        }
+    }
+
+    async fn request(&self, req: &WebhookRequest<'_>) -> anyhow::Result<WebhookResponse> {
+        let resp = self.client.post(&self.webhook.url).json(req).send().await?;
+        if !resp.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "webhook response status: {}",
+                resp.status()
+            ));
+        }
+        Ok(resp.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    async fn mock_server(body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            //请求可能分多次到达,读完请求头及content-length长度的请求体后再响应
+            let mut buf = vec![];
+            let mut chunk = [0u8; 1024];
+            while !request_complete(&buf) {
+                match socket.read(&mut chunk).await {
+                    Ok(n) if n > 0 => buf.extend_from_slice(&chunk[..n]),
+                    _ => break,
+                }
+            }
+            let resp = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(resp.as_bytes()).await.ok();
+        });
+        format!("http://{}/validate", addr)
+    }
+
+    fn request_complete(buf: &[u8]) -> bool {
+        let text = String::from_utf8_lossy(buf);
+        let header_end = match text.find("\r\n\r\n") {
+            Some(v) => v + 4,
+            None => return false,
+        };
+        let content_length = text[..header_end]
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, v)| v.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        buf.len() >= header_end + content_length
+    }
+
+    fn webhook(url: String, strict: bool) -> ConfigValidationWebhook {
+        ConfigValidationWebhook::new(ValidationWebhook {
+            url,
+            timeout_ms: 1000,
+            namespace_filter: vec!["dev".to_owned()],
+            strict,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn validate_by_webhook() {
+        let key = ConfigKey::new("app.yaml", "DEFAULT_GROUP", "dev");
+        let url = mock_server(r#"{"valid":false,"reason":"missing owner"}"#).await;
+        assert_eq!(
+            webhook(url, false).validate(&key, "a: 1", None).await,
+            WebhookValidateResult::Reject("missing owner".to_owned())
+        );
+        let url = mock_server(r#"{"valid":true}"#).await;
+        assert_eq!(
+            webhook(url, false).validate(&key, "a: 1", None).await,
+            WebhookValidateResult::Pass
+        );
+
+        let unreachable = "http://127.0.0.1:1/validate".to_owned();
+        assert_eq!(
+            webhook(unreachable.clone(), false)
+                .validate(&key, "a: 1", None)
+                .await,
+            WebhookValidateResult::Pass
+        );
+        assert!(matches!(
+            webhook(unreachable.clone(), true)
+                .validate(&key, "a: 1", None)
+                .await,
+            WebhookValidateResult::Reject(_)
+        ));
+        //不在过滤列表中的命名空间不校验
+        let other_key = ConfigKey::new("app.yaml", "DEFAULT_GROUP", "prod");
+        assert_eq!(
+            webhook(unreachable, true)
+                .validate(&other_key, "a: 1", None)
+                .await,
+            WebhookValidateResult::Pass
+        );
+    }
+}
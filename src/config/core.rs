@@ -1,29 +1,69 @@
 use async_raft_ext::raft::ClientWriteRequest;
 use bean_factory::bean;
 use bean_factory::Inject;
-use chrono::Local;
-use std::collections::BTreeMap;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
 use std::hash::Hash;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::Weak;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crate::raft::store::ClientRequest;
+use crate::raft::cluster::model::SetConfigReq;
+use crate::raft::split_brain::SplitBrainDetector;
+use crate::raft::store::{ClientRequest, ClientResponse, ConfigSetItem};
 use crate::raft::NacosRaft;
 use crate::utils::get_md5;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::common::byte_utils::id_to_bin;
-use crate::common::constant::{CONFIG_TREE_NAME, SEQUENCE_TREE_NAME, SEQ_KEY_CONFIG};
+use crate::common::constant::{
+    CONFIG_DELETE_HISTORY_TREE_NAME, CONFIG_TREE_NAME, SEQUENCE_TREE_NAME, SEQ_KEY_CONFIG,
+};
+use crate::common::metrics::{
+    CONFIG_CACHE_PREWARM_GAUGE, CONFIG_DEDUP_SAVINGS_GAUGE, CONFIG_NOTIFICATION_LATENCY_HISTOGRAM,
+};
 use crate::common::sequence_utils::SimpleSequence;
+use crate::common::AppSysConfig;
+use crate::console::{SYSCONFIG_GROUP, SYSCONFIG_NAMESPACE, SYSCONFIG_NAMESPACE_KEY};
 use actix::prelude::*;
+use futures_util::stream::{self, StreamExt};
 
+use super::config_alias::{ConfigAliasSettings, CONFIG_ALIAS_KEY};
+use super::config_archive::ConfigArchiveStore;
+use super::config_content_store::ConfigContentStore;
+use super::config_diff::{diff_lines, render_unified_diff};
+use super::config_event::{ConfigChangeEvent, ConfigChangeType, ConfigEventSender};
+use super::config_feed::ConfigFeedEntry;
+use super::config_field_encrypt::ConfigFieldCipher;
+use super::config_history_index::{
+    ConfigDeleteHistory, ConfigHistoryIndex, ConfigHistorySearchItem, ConfigHistorySearchParam,
+};
+use super::config_namespace_stats::{
+    update_namespace_gauges, NamespaceStats, NAMESPACE_STATS_CACHE_SECONDS,
+};
+use super::config_namespace_template::TemplateNamespaces;
+use super::config_notify_debounce::{
+    ConfigPriority, DebounceAction, FlushState, NotifyDebounce, PendingFlush,
+};
+use super::config_ns_ref::{ns_ref_keys, NS_REF_MAX_DEPTH};
+use super::config_propagation_sla::{
+    PropagationSlaMonitor, PropagationSlaSettings, PROPAGATION_SLA_KEY,
+};
+use super::config_read_cache::{CachedEntry, ConfigReadCache};
+use super::config_read_view::{ConfigReadValue, ConfigReadView};
+use super::config_search::{IndexCmd, IndexerActor};
+use super::config_stats::ConfigStats;
 use super::config_subscribe::Subscriber;
+use super::config_wal::{to_config_raft_cmd, ConfigWal, ConfigWalRecord};
+use super::config_watch::ConfigWatchManage;
 use super::dal::ConfigHistoryParam;
 use crate::config::config_index::{ConfigQueryParam, TenantIndex};
 use crate::config::config_type::ConfigType;
-use crate::config::model::{ConfigRaftCmd, ConfigRaftResult, ConfigValueDO, HistoryItem};
+use crate::config::model::{
+    ConfigInUseError, ConfigMd5MismatchError, ConfigRaftCmd, ConfigRaftResult, ConfigSetResult,
+    ConfigValueDO, HistoryItem,
+};
 use crate::config::utils::param_utils;
 use crate::now_millis_i64;
 use crate::raft::filestore::model::SnapshotRecordDto;
@@ -53,6 +93,19 @@ impl ConfigKey {
         }
     }
 
+    ///
+    /// 同分组下的默认配置key,本身为默认配置时返回None
+    pub fn group_default_key(&self) -> Option<ConfigKey> {
+        if self.data_id.as_str() == GROUP_DEFAULT_DATA_ID {
+            return None;
+        }
+        Some(ConfigKey::new_by_arc(
+            Arc::new(GROUP_DEFAULT_DATA_ID.to_owned()),
+            self.group.clone(),
+            self.tenant.clone(),
+        ))
+    }
+
     pub fn build_key(&self) -> String {
         if self.tenant.len() == 0 {
             return format!("{}\x02{}", self.data_id, self.group);
@@ -95,6 +148,49 @@ impl From<&str> for ConfigKey {
     }
 }
 
+impl FromStr for ConfigKey {
+    type Err = anyhow::Error;
+
+    ///
+    /// 解析 `tenant##group##data_id` 格式的紧凑key
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut list = s.splitn(3, "##");
+        match (list.next(), list.next(), list.next()) {
+            (Some(tenant), Some(group), Some(data_id)) => {
+                Ok(ConfigKey::new(data_id, group, tenant))
+            }
+            _ => Err(anyhow::anyhow!("the config key is invalid : {}", s)),
+        }
+    }
+}
+
+impl Display for ConfigKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}##{}##{}", self.tenant, self.group, self.data_id)
+    }
+}
+
+///
+/// 序列化为紧凑字符串,以支持作为json map的key
+impl Serialize for ConfigKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ConfigKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        ConfigKey::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
 // impl PartialEq for ConfigKey {
 //     fn eq(&self, o: &Self) -> bool {
 //         self.data_id == o.data_id && self.group == o.group && self.tenant == o.tenant
@@ -109,6 +205,18 @@ pub struct ConfigValue {
     pub(crate) histories: Vec<HistoryItem>,
     pub(crate) config_type: Option<Arc<String>>,
     pub(crate) desc: Option<Arc<String>>,
+    //依赖的配置
+    pub(crate) references: Vec<ConfigKey>,
+    //变更通知的优先级,None时为普通优先级
+    pub(crate) priority: Option<ConfigPriority>,
+    //未设置类型时按内容推断的类型,只保存在内存中,内容变更后失效
+    pub(crate) detected_type: Option<Arc<String>>,
+    //归档到对象存储后的地址,归档后内存中不保留内容与历史版本
+    pub(crate) archive_uri: Option<Arc<String>>,
+    //集群内最近读取时间,由各节点定时经raft上报,0表示未上报过
+    pub(crate) last_read_ms: i64,
+    //最近写入时间,归档后历史版本不在内存中时也可用
+    pub(crate) last_modified: i64,
 }
 
 impl ConfigValue {
@@ -121,6 +229,12 @@ impl ConfigValue {
             histories: vec![],
             config_type: None,
             desc: None,
+            references: vec![],
+            priority: None,
+            detected_type: None,
+            archive_uri: None,
+            last_read_ms: 0,
+            last_modified: 0,
         }
     }
 
@@ -148,6 +262,12 @@ impl ConfigValue {
             }],
             config_type: None,
             desc: None,
+            references: vec![],
+            priority: None,
+            detected_type: None,
+            archive_uri: None,
+            last_read_ms: 0,
+            last_modified: op_time,
         }
     }
 
@@ -158,6 +278,7 @@ impl ConfigValue {
         op_time: i64,
         md5: Option<Arc<String>>,
         op_user: Option<Arc<String>>,
+        max_versions: usize,
     ) {
         let md5 = if let Some(v) = md5 {
             v
@@ -167,16 +288,60 @@ impl ConfigValue {
         self.md5 = md5;
         self.content = content.clone();
         self.tmp = false;
+        self.detected_type = None;
+        self.archive_uri = None;
+        self.last_modified = op_time;
         let item = HistoryItem {
             id: history_id,
             content,
             modified_time: op_time,
             op_user,
         };
-        if self.histories.len() >= 100 {
-            self.histories.remove(0);
-        }
         self.histories.push(item);
+        self.prune_histories(max_versions);
+    }
+
+    ///
+    /// 只保留最近max_versions个历史版本
+    pub fn prune_histories(&mut self, max_versions: usize) {
+        if self.histories.len() > max_versions {
+            let remove_count = self.histories.len() - max_versions;
+            self.histories.drain(..remove_count);
+        }
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.archive_uri.is_some()
+    }
+
+    ///
+    /// 集群内最近读取与写入时间都早于before_ms
+    pub fn is_inactive(&self, before_ms: i64) -> bool {
+        self.last_read_ms < before_ms && self.last_modified < before_ms
+    }
+
+    ///
+    /// 内容与历史版本已上传到archive_uri,只保留md5等元数据
+    pub fn archive(&mut self, archive_uri: Arc<String>) {
+        self.content = Arc::new(String::new());
+        self.histories.clear();
+        self.detected_type = None;
+        self.archive_uri = Some(archive_uri);
+    }
+
+    ///
+    /// 最近一次变更的序号(历史记录id),无历史记录时为0
+    pub fn change_seq(&self) -> u64 {
+        self.histories.last().map(|v| v.id).unwrap_or_default()
+    }
+
+    ///
+    /// 返回at时刻生效的历史版本;早于保留的最旧版本时返回None
+    pub fn history_at(&self, at: i64) -> Option<&HistoryItem> {
+        self.histories
+            .iter()
+            .rev()
+            .find(|item| item.modified_time <= at)
     }
 }
 
@@ -190,6 +355,14 @@ pub struct ConfigInfoDto {
     pub md5: Option<Arc<String>>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSuggestItem {
+    pub data_id: Arc<String>,
+    pub group: Arc<String>,
+    pub last_modified: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ConfigHistoryInfoDto {
@@ -201,7 +374,7 @@ pub struct ConfigHistoryInfoDto {
     pub modified_time: Option<i64>, //给历史记录使用
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ListenerItem {
     pub key: ConfigKey,
     pub md5: Arc<String>,
@@ -212,6 +385,19 @@ impl ListenerItem {
         Self { key, md5 }
     }
 
+    ///
+    /// dataId为`*`时表示监听分组下的全部配置
+    pub fn to_wildcard(&self) -> Option<WildcardListenerItem> {
+        if self.key.data_id.as_str() == WILDCARD_DATA_ID {
+            Some(WildcardListenerItem::new(
+                self.key.group.clone(),
+                self.key.tenant.clone(),
+            ))
+        } else {
+            None
+        }
+    }
+
     pub fn decode_listener_items(configs: &str) -> Vec<Self> {
         let mut list = vec![];
         let mut start = 0;
@@ -280,106 +466,101 @@ impl ListenerItem {
     }
 }
 
-struct OnceListener {
-    version: u64,
-    //time: i64,
-    //list: Vec<ListenerItem>,
-}
+pub const WILDCARD_DATA_ID: &str = "*";
 
-pub enum ListenerResult {
-    NULL,
-    DATA(Vec<ConfigKey>),
-}
+///
+/// 开启分组默认配置后的保留dataId,分组内不存在的配置读取时返回该配置
+pub const GROUP_DEFAULT_DATA_ID: &str = "DEFAULT";
+
+///
+/// 每个配置默认保留的历史版本数
+pub const DEFAULT_MAX_VERSIONS_PER_KEY: usize = 100;
 
-type ListenerSenderType = tokio::sync::oneshot::Sender<ListenerResult>;
-//type ListenerReceiverType = tokio::sync::oneshot::Receiver<ListenerResult>;
+/// 查询结果中同时从对象存储读取的归档配置数
+const ARCHIVE_LOAD_CONCURRENCY: usize = 16;
 
-struct ConfigListener {
-    version: u64,
-    listener: HashMap<ConfigKey, Vec<u64>>,
-    time_listener: BTreeMap<i64, Vec<OnceListener>>,
-    sender_map: HashMap<u64, ListenerSenderType>,
+///
+/// 分组通配监听项,group_pattern支持精确分组名或以`*`结尾的前缀匹配
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct WildcardListenerItem {
+    pub group_pattern: Arc<String>,
+    pub tenant: Arc<String>,
 }
 
-impl ConfigListener {
-    fn new() -> Self {
+impl WildcardListenerItem {
+    pub fn new(group_pattern: Arc<String>, tenant: Arc<String>) -> Self {
         Self {
-            version: 0,
-            listener: Default::default(),
-            time_listener: Default::default(),
-            sender_map: Default::default(),
+            group_pattern,
+            tenant,
         }
     }
 
-    fn add(&mut self, items: Vec<ListenerItem>, sender: ListenerSenderType, time: i64) {
-        self.version += 1;
-        for item in &items {
-            let key = item.key.clone();
-            match self.listener.get_mut(&key) {
-                Some(list) => {
-                    list.push(self.version);
-                }
-                None => {
-                    self.listener.insert(key, vec![self.version]);
-                }
-            };
+    pub fn is_match(&self, key: &ConfigKey) -> bool {
+        if self.tenant != key.tenant {
+            return false;
         }
-        self.sender_map.insert(self.version, sender);
-        let once_listener = OnceListener {
-            version: self.version,
-            //time,
-            //list: items,
-        };
-        match self.time_listener.get_mut(&time) {
-            Some(list) => {
-                list.push(once_listener);
-            }
-            None => {
-                self.time_listener.insert(time, vec![once_listener]);
-            }
+        match self.group_pattern.strip_suffix('*') {
+            Some(prefix) => key.group.starts_with(prefix),
+            None => self.group_pattern == key.group,
         }
     }
+}
 
-    fn notify(&mut self, key: ConfigKey) {
-        if let Some(list) = self.listener.remove(&key) {
-            for v in list {
-                if let Some(sender) = self.sender_map.remove(&v) {
-                    sender.send(ListenerResult::DATA(vec![key.clone()])).ok();
-                }
-            }
-        }
-    }
+#[derive(Debug, Clone)]
+pub struct WildcardListener {
+    pub item: WildcardListenerItem,
+    pub client_id: Arc<String>,
+}
 
-    fn timeout(&mut self) {
-        let current_time = Local::now().timestamp_millis();
-        let mut keys: Vec<i64> = Vec::new();
-        for (key, list) in self.time_listener.iter().take(10000) {
-            if *key < current_time {
-                keys.push(*key);
-                for item in list {
-                    let v = item.version;
-                    if let Some(sender) = self.sender_map.remove(&v) {
-                        sender.send(ListenerResult::NULL).ok();
-                    }
-                }
-            } else {
-                break;
-            }
-        }
-        for key in keys {
-            self.time_listener.remove(&key);
-        }
-    }
+pub enum ListenerResult {
+    NULL,
+    DATA(Vec<ConfigKey>),
 }
 
 #[bean(inject)]
 pub struct ConfigActor {
     cache: HashMap<ConfigKey, ConfigValue>,
-    listener: ConfigListener,
     subscriber: Subscriber,
+    wildcard_listeners: Vec<WildcardListener>,
     tenant_index: TenantIndex,
     raft: Option<Weak<NacosRaft>>,
     sequence: SimpleSequence,
+    event_bus: Option<ConfigEventSender>,
+    read_view: Arc<ConfigReadView>,
+    read_view_dirty: bool,
+    watch: Arc<ConfigWatchManage>,
+    stats: Arc<ConfigStats>,
+    indexer: Option<Addr<IndexerActor>>,
+    //提案时写入请求,应用时按请求中的值裁剪历史版本
+    max_versions_per_key: usize,
+    //配置不存在时按分组默认配置比较及通知监听
+    group_default_enabled: bool,
+    content_store: ConfigContentStore,
+    split_brain_detector: Option<Arc<SplitBrainDetector>>,
+    field_cipher: Option<Arc<ConfigFieldCipher>>,
+    notify_debounce: NotifyDebounce,
+    self_addr: Option<Addr<ConfigActor>>,
+    history_index: ConfigHistoryIndex,
+    template_namespaces: TemplateNamespaces,
+    read_cache: Option<Arc<ConfigReadCache>>,
+    //启动时预热到读缓存的配置数,0表示不预热
+    prewarm_count: usize,
+    sla_monitor: Option<Arc<PropagationSlaMonitor>>,
+    sla_settings: PropagationSlaSettings,
+    alias_settings: ConfigAliasSettings,
+    //被依赖的配置到依赖它的配置
+    reverse_references: HashMap<ConfigKey, HashSet<ConfigKey>>,
+    //内容中跨命名空间引用的配置,及被引用配置到引用它的配置
+    ns_refs: HashMap<ConfigKey, Vec<ConfigKey>>,
+    ns_ref_dependents: HashMap<ConfigKey, HashSet<ConfigKey>>,
+    //原子批量写入期间暂存的变更,整批写入后统一更新只读视图并通知
+    deferred_changes: Option<Vec<(ConfigKey, ConfigPriority, i64)>>,
+    //单机模式的预写日志,存在时配置写入不经过raft
+    wal: Option<ConfigWal>,
+    //raft数据加载完成并回放预写日志后才接受单机模式写入
+    wal_replayed: bool,
+    //读取已归档的配置时从对象存储加载内容
+    archive_store: Option<Arc<ConfigArchiveStore>>,
 }
 
 impl Inject for ConfigActor {
@@ -393,9 +574,39 @@ impl Inject for ConfigActor {
     ) {
         let raft: Option<Arc<NacosRaft>> = factory_data.get_bean();
         self.raft = raft.map(|e| Arc::downgrade(&e));
+        self.split_brain_detector = factory_data.get_bean();
+        self.field_cipher = factory_data.get_bean();
         if let Some(conn_manage) = factory_data.get_actor() {
             self.subscriber.set_conn_manage(conn_manage);
         }
+        let event_bus: Option<Arc<ConfigEventSender>> = factory_data.get_bean();
+        self.event_bus = event_bus.map(|e| e.as_ref().clone());
+        if let Some(read_view) = factory_data.get_bean() {
+            self.read_view = read_view;
+            self.rebuild_read_view();
+        }
+        if let Some(watch) = factory_data.get_bean() {
+            self.watch = watch;
+        }
+        if let Some(stats) = factory_data.get_bean() {
+            self.stats = stats;
+        }
+        self.indexer = factory_data.get_actor();
+        self.sla_monitor = factory_data.get_bean();
+        let sys_config: Option<Arc<AppSysConfig>> = factory_data.get_bean();
+        if let Some(sys_config) = sys_config {
+            self.max_versions_per_key = sys_config.config_max_versions_per_key;
+            self.group_default_enabled = sys_config.config_group_default_enabled;
+            self.notify_debounce = NotifyDebounce::new(
+                sys_config.config_notify_debounce_ms,
+                sys_config.config_change_rate_cooldown_ms,
+            );
+            if sys_config.config_cache_prewarm {
+                self.prewarm_count = sys_config.config_cache_prewarm_count;
+            }
+        }
+        self.read_cache = factory_data.get_bean();
+        self.archive_store = factory_data.get_bean();
         log::info!("ConfigActor inject complete");
     }
 }
@@ -411,28 +622,588 @@ impl ConfigActor {
         Self {
             cache: HashMap::new(),
             subscriber: Subscriber::new(),
-            listener: ConfigListener::new(),
+            wildcard_listeners: vec![],
             tenant_index: TenantIndex::new(),
             raft: None,
             sequence: SimpleSequence::new(0, 100),
+            event_bus: None,
+            read_view: Arc::new(ConfigReadView::new()),
+            read_view_dirty: false,
+            watch: Arc::new(ConfigWatchManage::new()),
+            stats: Arc::new(ConfigStats::new()),
+            indexer: None,
+            max_versions_per_key: DEFAULT_MAX_VERSIONS_PER_KEY,
+            group_default_enabled: false,
+            content_store: ConfigContentStore::new(),
+            split_brain_detector: None,
+            field_cipher: None,
+            notify_debounce: NotifyDebounce::default(),
+            self_addr: None,
+            history_index: ConfigHistoryIndex::new(),
+            template_namespaces: TemplateNamespaces::new(),
+            read_cache: None,
+            prewarm_count: 0,
+            sla_monitor: None,
+            sla_settings: PropagationSlaSettings::default(),
+            alias_settings: ConfigAliasSettings::default(),
+            reverse_references: HashMap::new(),
+            ns_refs: HashMap::new(),
+            ns_ref_dependents: HashMap::new(),
+            deferred_changes: None,
+            wal: None,
+            wal_replayed: false,
+            archive_store: None,
+        }
+    }
+
+    ///
+    /// 单机模式下写入预写日志,raft数据加载完成后回放
+    pub fn with_wal(mut self, wal: Option<ConfigWal>) -> Self {
+        self.wal = wal;
+        self
+    }
+
+    fn update_read_view(&self, key: &ConfigKey) {
+        if self.deferred_changes.is_some() {
+            return;
+        }
+        let value = self
+            .cache
+            .get(key)
+            .map(|v| Arc::new(ConfigReadValue::from(v)));
+        self.read_view.update(key.clone(), value);
+    }
+
+    ///
+    /// 删除命名空间下的所有配置,每个配置都记录删除历史
+    fn purge_namespace_configs(&mut self, tenant: &str, op_time: i64) {
+        let keys: Vec<ConfigKey> = self
+            .cache
+            .keys()
+            .filter(|k| k.tenant.as_str() == tenant)
+            .cloned()
+            .collect();
+        for key in keys {
+            self.del_config(key, Some((op_time, None))).ok();
+        }
+        log::info!("purge namespace {} configs", tenant);
+    }
+
+    fn rebuild_read_view(&mut self) {
+        let map = self
+            .cache
+            .iter()
+            .map(|(k, v)| (k.clone(), Arc::new(ConfigReadValue::from(v))))
+            .collect();
+        self.read_view.store_all(map);
+        self.read_view_dirty = false;
+        self.refresh_namespace_settings();
+    }
+
+    ///
+    /// 命名空间列表配置变化时更新各命名空间的设置
+    fn refresh_namespace_settings(&mut self) {
+        let namespace_key = ConfigKey::new(
+            SYSCONFIG_NAMESPACE_KEY,
+            SYSCONFIG_GROUP,
+            SYSCONFIG_NAMESPACE,
+        );
+        if let Some(v) = self.cache.get(&namespace_key) {
+            self.notify_debounce
+                .update_namespace_settings(&v.md5, v.content.as_str());
+            if self
+                .template_namespaces
+                .update_namespace_settings(&v.md5, v.content.as_str())
+            {
+                self.read_view
+                    .store_deleted_namespaces(self.template_namespaces.deleted_ids().clone());
+            }
+        }
+    }
+
+    ///
+    /// 别名设置变化时同步到只读视图
+    fn refresh_alias_settings(&mut self, key: &ConfigKey) {
+        if key.data_id.as_str() != CONFIG_ALIAS_KEY
+            || key.group.as_str() != SYSCONFIG_GROUP
+            || key.tenant.as_str() != SYSCONFIG_NAMESPACE
+        {
+            return;
+        }
+        let changed = match self.cache.get(key) {
+            Some(v) => self.alias_settings.update(&v.md5, v.content.as_str()),
+            None => self.alias_settings.clear(),
+        };
+        if changed {
+            self.read_view
+                .store_aliases(self.alias_settings.alias_map());
+        }
+    }
+
+    fn resolve_alias(&self, key: &mut ConfigKey) {
+        if let Some(target) = self.alias_settings.resolve(key) {
+            *key = target.clone();
+        }
+    }
+
+    ///
+    /// 配置设置了传播时限时,到期后检查使用方是否都已更新到新版本
+    fn schedule_sla_check(&mut self, key: &ConfigKey) {
+        let monitor = match &self.sla_monitor {
+            Some(v) => v.clone(),
+            None => return,
+        };
+        let sla_key = ConfigKey::new(PROPAGATION_SLA_KEY, SYSCONFIG_GROUP, SYSCONFIG_NAMESPACE);
+        match self.cache.get(&sla_key) {
+            Some(v) => self.sla_settings.update(&v.md5, v.content.as_str()),
+            None => self.sla_settings.clear(),
+        }
+        let sla_ms = match self.sla_settings.sla_ms(key) {
+            Some(v) => v,
+            None => return,
+        };
+        if let Some(v) = self.cache.get(key) {
+            monitor.schedule(key.clone(), v.md5.clone(), sla_ms, self.read_view.clone());
+        }
+    }
+
+    ///
+    /// 按命名空间的合并时长通知变更;为0时立即通知。
+    /// write_ms为提案节点确定的写入时间,通知延迟从写入开始统计,包含复制与合并等待
+    fn notify_change(&mut self, key: &ConfigKey, priority: ConfigPriority, write_ms: i64) {
+        if let Some(changes) = &mut self.deferred_changes {
+            changes.push((key.clone(), priority, write_ms));
+            return;
+        }
+        self.refresh_namespace_settings();
+        let action = match &self.self_addr {
+            Some(_) => self.notify_debounce.add(key, priority, write_ms),
+            None => DebounceAction::Notify,
+        };
+        match action {
+            DebounceAction::Notify => {
+                self.notify_key(key);
+                Self::observe_notification_latency(priority, write_ms);
+            }
+            DebounceAction::Schedule(pending) => self.schedule_flush(pending),
+            DebounceAction::Merged => {}
+        }
+    }
+
+    ///
+    /// 通知已发出时记录从写入到通知的延迟;节点间时钟偏差可能使延迟为负,按0记录
+    fn observe_notification_latency(priority: ConfigPriority, write_ms: i64) {
+        CONFIG_NOTIFICATION_LATENCY_HISTOGRAM
+            .with_label_values(&[priority.as_str()])
+            .observe((now_millis_i64() - write_ms).max(0) as f64);
+    }
+
+    ///
+    /// 每个变更组只有一个计时,截止时间被推迟时由flush重新计时
+    fn schedule_flush(&self, pending: PendingFlush) {
+        let addr = match &self.self_addr {
+            Some(v) => v.clone(),
+            None => return,
+        };
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(pending.delay_ms)).await;
+            let cmd = match pending.throttled_key {
+                Some(key) => ConfigCmd::FlushThrottledNotify(key, pending.priority),
+                None => ConfigCmd::FlushNotify(pending.tenant, pending.priority),
+            };
+            addr.do_send(cmd);
+        });
+    }
+
+    ///
+    /// 按最近7天读取次数预热热门配置:读取使用的只读视图立即重建,
+    /// 已归档配置的内容预先加载到归档内容缓存
+    fn prewarm_cache(&mut self) {
+        if self.prewarm_count == 0 {
+            return;
+        }
+        //读取走只读视图,回放后先确保视图已包含全部配置
+        if self.read_view_dirty {
+            self.rebuild_read_view();
+        }
+        let mut count = 0;
+        let mut archive_uris = vec![];
+        for key in self
+            .stats
+            .top_read_keys(self.prewarm_count, now_millis_i64())
+        {
+            let v = match self.read_view.get(&key) {
+                Some(v) => v,
+                None => continue,
+            };
+            match &v.archive_uri {
+                //已归档的配置内容不在内存中,预先加载到归档内容缓存
+                Some(archive_uri) if v.content.is_empty() => {
+                    archive_uris.push(archive_uri.clone());
+                    continue;
+                }
+                _ => count += 1,
+            }
+            if let Some(read_cache) = &self.read_cache {
+                read_cache.put(
+                    key,
+                    CachedEntry {
+                        content: v.content.clone(),
+                        md5: v.md5.clone(),
+                        config_type: v.config_type.clone().or(v.detected_type.clone()),
+                        fetched_at: Instant::now(),
+                    },
+                );
+            }
+        }
+        let store = match self.archive_store.clone() {
+            Some(store) if !archive_uris.is_empty() => store,
+            _ => {
+                CONFIG_CACHE_PREWARM_GAUGE.set(count);
+                log::info!("cache pre-warmed with {} configs", count);
+                return;
+            }
+        };
+        actix::spawn(async move {
+            let loaded = stream::iter(archive_uris)
+                .map(|archive_uri| {
+                    let store = store.clone();
+                    async move { store.load_content(&archive_uri).await.is_ok() }
+                })
+                .buffer_unordered(ARCHIVE_LOAD_CONCURRENCY)
+                .filter(|ok| futures_util::future::ready(*ok))
+                .count()
+                .await as i64;
+            CONFIG_CACHE_PREWARM_GAUGE.set(count + loaded);
+            log::info!("cache pre-warmed with {} configs", count + loaded);
+        });
+    }
+
+    fn flush_notify(&mut self, tenant: Arc<String>, priority: ConfigPriority) {
+        match self.notify_debounce.take(&tenant, priority, Instant::now()) {
+            FlushState::Ready(keys) => {
+                for (key, write_ms) in &keys {
+                    self.notify_key(key);
+                    Self::observe_notification_latency(priority, *write_ms);
+                }
+            }
+            FlushState::Wait(delay_ms) => self.schedule_flush(PendingFlush {
+                tenant,
+                priority,
+                delay_ms,
+                throttled_key: None,
+            }),
+            FlushState::Empty => {}
+        }
+    }
+
+    ///
+    /// 超过变更频率上限的配置冷却结束,只通知一次
+    fn flush_throttled_notify(&mut self, key: ConfigKey, priority: ConfigPriority) {
+        match self.notify_debounce.take_throttled(&key, Instant::now()) {
+            FlushState::Ready(write_ms) => {
+                self.notify_key(&key);
+                Self::observe_notification_latency(priority, write_ms);
+            }
+            FlushState::Wait(delay_ms) => self.schedule_flush(PendingFlush {
+                tenant: key.tenant.clone(),
+                priority,
+                delay_ms,
+                throttled_key: Some(key),
+            }),
+            FlushState::Empty => {}
+        }
+    }
+
+    ///
+    /// 监听比较使用的配置:别名读取目标配置,配置不存在时读取分组默认配置
+    fn get_effective_value(&self, key: &ConfigKey) -> Option<&ConfigValue> {
+        let target = self.alias_settings.resolve(key).unwrap_or(key);
+        match self.cache.get(target) {
+            Some(v) => Some(v),
+            None if self.group_default_enabled => self.cache.get(&key.group_default_key()?),
+            None => None,
+        }
+    }
+
+    ///
+    /// 该配置不存在,监听时按分组默认配置比较
+    fn use_group_default(&self, key: &ConfigKey, default_key: &ConfigKey) -> bool {
+        key != default_key
+            && key.group == default_key.group
+            && key.tenant == default_key.tenant
+            && !self
+                .cache
+                .contains_key(self.alias_settings.resolve(key).unwrap_or(key))
+    }
+
+    ///
+    /// 同时通知监听该配置别名的客户端
+    fn notify_key(&self, key: &ConfigKey) {
+        self.notify_key_and_aliases(key);
+        //分组默认配置变化时,通知监听同分组下不存在配置的客户端
+        if self.group_default_enabled && key.data_id.as_str() == GROUP_DEFAULT_DATA_ID {
+            self.watch
+                .notify_where(|item| self.use_group_default(item, key));
+            let keys: Vec<ConfigKey> = self
+                .subscriber
+                .subscribed_keys()
+                .filter(|item| self.use_group_default(item, key))
+                .cloned()
+                .collect();
+            for item in &keys {
+                self.notify_subscriber(item);
+            }
+        }
+        //引用该配置的配置返回内容随之变化,也需要通知
+        for dependent in self.get_ns_ref_dependents(key) {
+            self.notify_key_and_aliases(&dependent);
+        }
+    }
+
+    fn notify_key_and_aliases(&self, key: &ConfigKey) {
+        self.watch.notify(key);
+        self.notify_subscriber(key);
+        for alias in self.alias_settings.aliases_of(key) {
+            self.watch.notify(alias);
+            self.notify_subscriber(alias);
+        }
+    }
+
+    fn notify_subscriber(&self, key: &ConfigKey) {
+        let wildcard_clients = self
+            .wildcard_listeners
+            .iter()
+            .filter(|v| v.item.is_match(key))
+            .map(|v| v.client_id.clone())
+            .collect();
+        self.subscriber.notify(key.clone(), wildcard_clients);
+    }
+
+    ///
+    /// 拆分出通配监听项,只返回按key监听的项
+    fn add_wildcard_subscribe(
+        &mut self,
+        client_id: &Arc<String>,
+        items: Vec<ListenerItem>,
+    ) -> Vec<ListenerItem> {
+        let mut key_items = Vec::with_capacity(items.len());
+        for item in items {
+            if let Some(wildcard) = item.to_wildcard() {
+                let exists = self
+                    .wildcard_listeners
+                    .iter()
+                    .any(|v| v.item == wildcard && &v.client_id == client_id);
+                if !exists {
+                    self.wildcard_listeners.push(WildcardListener {
+                        item: wildcard,
+                        client_id: client_id.clone(),
+                    });
+                }
+            } else {
+                key_items.push(item);
+            }
+        }
+        key_items
+    }
+
+    fn remove_wildcard_subscribe(
+        &mut self,
+        client_id: &Arc<String>,
+        items: Vec<ListenerItem>,
+    ) -> Vec<ListenerItem> {
+        let mut key_items = Vec::with_capacity(items.len());
+        for item in items {
+            if let Some(wildcard) = item.to_wildcard() {
+                self.wildcard_listeners
+                    .retain(|v| !(v.item == wildcard && &v.client_id == client_id));
+            } else {
+                key_items.push(item);
+            }
+        }
+        key_items
+    }
+
+    ///
+    /// 配置的依赖从old变为new时更新反向依赖索引
+    fn update_reverse_references(&mut self, key: &ConfigKey, old: &[ConfigKey], new: &[ConfigKey]) {
+        for item in old {
+            if let Some(dependents) = self.reverse_references.get_mut(item) {
+                dependents.remove(key);
+                if dependents.is_empty() {
+                    self.reverse_references.remove(item);
+                }
+            }
+        }
+        for item in new {
+            self.reverse_references
+                .entry(item.clone())
+                .or_default()
+                .insert(key.clone());
+        }
+    }
+
+    ///
+    /// 配置内容变化时更新跨命名空间引用索引,content为None表示配置已删除
+    fn update_ns_refs(&mut self, key: &ConfigKey, content: Option<&str>) {
+        let new_refs = content.map(ns_ref_keys).unwrap_or_default();
+        let old_refs = if new_refs.is_empty() {
+            self.ns_refs.remove(key).unwrap_or_default()
+        } else {
+            self.ns_refs
+                .insert(key.clone(), new_refs.clone())
+                .unwrap_or_default()
+        };
+        for item in &old_refs {
+            if let Some(dependents) = self.ns_ref_dependents.get_mut(item) {
+                dependents.remove(key);
+                if dependents.is_empty() {
+                    self.ns_ref_dependents.remove(item);
+                }
+            }
+        }
+        for item in new_refs {
+            self.ns_ref_dependents
+                .entry(item)
+                .or_default()
+                .insert(key.clone());
+        }
+    }
+
+    ///
+    /// 直接或间接引用该配置的配置,层数与读取时的解析层数一致
+    fn get_ns_ref_dependents(&self, key: &ConfigKey) -> Vec<ConfigKey> {
+        let mut visited = HashSet::new();
+        let mut result = vec![];
+        let mut pending = vec![key.clone()];
+        for _ in 0..NS_REF_MAX_DEPTH {
+            let mut next = vec![];
+            for item in &pending {
+                if let Some(dependents) = self.ns_ref_dependents.get(item) {
+                    for dependent in dependents {
+                        if dependent != key && visited.insert(dependent.clone()) {
+                            result.push(dependent.clone());
+                            next.push(dependent.clone());
+                        }
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            pending = next;
+        }
+        result
+    }
+
+    fn get_dependents(&self, key: &ConfigKey) -> Vec<ConfigKey> {
+        let mut list: Vec<ConfigKey> = self
+            .reverse_references
+            .get(key)
+            .map(|v| v.iter().cloned().collect())
+            .unwrap_or_default();
+        list.sort_by(|a, b| {
+            (&a.tenant, &a.group, &a.data_id).cmp(&(&b.tenant, &b.group, &b.data_id))
+        });
+        list
+    }
+
+    fn publish_event(&self, event: ConfigChangeEvent) {
+        if let Some(event_bus) = &self.event_bus {
+            //没有订阅方时发送会返回错误,可忽略
+            event_bus.send(event).ok();
         }
     }
 
     fn set_tmp_config(&mut self, key: ConfigKey, val: Arc<String>) {
+        let md5 = Arc::new(get_md5(&val));
+        let val = self.content_store.intern(&md5, val);
         if let Some(v) = self.cache.get_mut(&key) {
+            if !v.is_archived() {
+                self.content_store.release(&v.md5);
+            }
             v.tmp = true;
-            v.md5 = Arc::new(get_md5(&val));
+            v.md5 = md5;
             v.content = val;
+            v.detected_type = None;
+            v.archive_uri = None;
         } else {
             let mut config_val = ConfigValue::new(val);
             config_val.tmp = true;
-            self.cache.insert(key, config_val);
+            self.cache.insert(key.clone(), config_val);
         }
+        self.update_dedup_savings();
+        self.update_read_view(&key);
     }
 
-    fn inner_set_config(&mut self, key: ConfigKey, value: ConfigValue) {
+    fn update_dedup_savings(&self) {
+        CONFIG_DEDUP_SAVINGS_GAUGE.set(self.content_store.saved_bytes());
+    }
+
+    ///
+    /// 搜索索引持久化保存,加载完成后清理已不存在配置的残留索引
+    fn retain_search_index(&self) {
+        if let Some(indexer) = &self.indexer {
+            indexer.do_send(IndexCmd::Retain(self.cache.keys().cloned().collect()));
+        }
+    }
+
+    fn update_search_index(&self, key: &ConfigKey, content: Option<Arc<String>>) {
+        if let Some(indexer) = &self.indexer {
+            match content {
+                Some(content) => indexer.do_send(IndexCmd::Index(key.clone(), content)),
+                None => indexer.do_send(IndexCmd::Remove(key.clone())),
+            }
+        }
+    }
+
+    fn inner_set_config(&mut self, key: ConfigKey, mut value: ConfigValue) {
         self.tenant_index.insert_config(key.clone());
-        self.cache.insert(key, value);
+        if let Some(item) = value.histories.last() {
+            self.stats.record_write(&key, item.modified_time);
+        }
+        //已归档的配置没有内容,不参与内容去重
+        if !value.is_archived() {
+            value.content = self.content_store.intern(&value.md5, value.content);
+        }
+        if self.cache.contains_key(&key) {
+            self.history_index.remove_key_sets(&key);
+        }
+        for item in &value.histories {
+            self.history_index.insert_set(
+                &key,
+                item.id,
+                item.modified_time,
+                item.op_user.clone(),
+                &item.content,
+            );
+        }
+        if let Some(item) = value.histories.last_mut() {
+            if item.content == value.content {
+                item.content = value.content.clone();
+            }
+        }
+        let content = Some(value.content.clone()).filter(|_| !value.is_archived());
+        //已归档的配置没有内容,保留归档前的引用
+        if let Some(content) = &content {
+            self.update_ns_refs(&key, Some(content));
+        }
+        self.update_search_index(&key, content);
+        let old_references = self
+            .cache
+            .get(&key)
+            .map(|v| v.references.clone())
+            .unwrap_or_default();
+        self.update_reverse_references(&key, &old_references, &value.references);
+        if let Some(old) = self.cache.insert(key.clone(), value) {
+            if !old.is_archived() {
+                self.content_store.release(&old.md5);
+            }
+        }
+        self.refresh_alias_settings(&key);
+        self.update_dedup_savings();
+        //批量加载时延迟到定时任务中统一重建只读视图
+        self.read_view_dirty = true;
     }
 
     fn set_config(
@@ -441,14 +1212,25 @@ impl ConfigActor {
         val: Arc<String>,
         config_type: Option<Arc<String>>,
         desc: Option<Arc<String>>,
+        references: Option<Vec<ConfigKey>>,
+        priority: Option<ConfigPriority>,
         history_id: u64,
         history_table_id: Option<u64>,
         op_time: i64,
         op_user: Option<Arc<String>>,
+        max_versions: usize,
     ) -> anyhow::Result<ConfigResult> {
         if let Some(history_table_id) = history_table_id {
             self.sequence.set_valid_last_id(history_table_id);
         }
+        if let Some(references) = &references {
+            let old_references = self
+                .cache
+                .get(&key)
+                .map(|v| v.references.clone())
+                .unwrap_or_default();
+            self.update_reverse_references(&key, &old_references, references);
+        }
         if let Some(v) = self.cache.get_mut(&key) {
             let md5 = get_md5(val.as_str());
             if let Some(s) = config_type {
@@ -457,62 +1239,605 @@ impl ConfigActor {
             if let Some(s) = desc {
                 v.desc = Some(s);
             }
-            if !v.tmp && v.md5.as_str() == md5 {
+            if let Some(s) = references {
+                v.references = s;
+            }
+            if let Some(s) = priority {
+                v.priority = Some(s);
+            }
+            //写入已归档的配置时恢复为内存中的配置,内容相同也重新写入
+            if !v.tmp && !v.is_archived() && v.md5.as_str() == md5 {
+                self.update_read_view(&key);
                 return Ok(ConfigResult::NULL);
             }
             if v.histories.is_empty() {
                 self.tenant_index.insert_config(key.clone());
             }
-            v.update_value(val, history_id, op_time, Some(Arc::new(md5)), op_user);
-        } else {
-            let mut v = ConfigValue::init(val, history_id, op_time, None, op_user);
-            v.config_type = config_type;
-            v.desc = desc;
-            self.cache.insert(key.clone(), v);
-            self.tenant_index.insert_config(key.clone());
+            let md5 = Arc::new(md5);
+            if !v.is_archived() {
+                self.content_store.release(&v.md5);
+            }
+            let val = self.content_store.intern(&md5, val);
+            //更新后超出保留数量将被裁剪的历史版本
+            let expired = (v.histories.len() + 1).saturating_sub(max_versions);
+            for item in v.histories.iter().take(expired) {
+                self.history_index
+                    .remove(&key.tenant, item.modified_time, item.id);
+            }
+            v.update_value(
+                val,
+                history_id,
+                op_time,
+                Some(md5),
+                op_user.clone(),
+                max_versions,
+            );
+        } else {
+            let md5 = Arc::new(get_md5(val.as_str()));
+            let val = self.content_store.intern(&md5, val);
+            let mut v = ConfigValue::init(val, history_id, op_time, Some(md5), op_user.clone());
+            v.config_type = config_type;
+            v.desc = desc;
+            v.references = references.unwrap_or_default();
+            v.priority = priority;
+            self.cache.insert(key.clone(), v);
+            self.tenant_index.insert_config(key.clone());
+        }
+        if let Some(v) = self.cache.get(&key) {
+            self.history_index
+                .insert_set(&key, history_id, op_time, op_user.clone(), &v.content);
+        }
+        self.update_dedup_savings();
+        self.update_read_view(&key);
+        self.stats.record_write(&key, op_time);
+        let content = self.cache.get(&key).map(|v| v.content.clone());
+        self.update_ns_refs(&key, content.as_ref().map(|v| v.as_str()));
+        self.update_search_index(&key, content);
+        self.refresh_alias_settings(&key);
+        let priority = self
+            .cache
+            .get(&key)
+            .and_then(|v| v.priority)
+            .unwrap_or_default();
+        self.notify_change(&key, priority, op_time);
+        self.schedule_sla_check(&key);
+        let md5 = self.cache.get(&key).map(|v| v.md5.clone());
+        self.publish_event(ConfigChangeEvent {
+            key,
+            change_type: ConfigChangeType::Set,
+            md5,
+            op_user,
+            op_time,
+        });
+        Ok(ConfigResult::NULL)
+    }
+
+    ///
+    /// 归档后内容已变化的不处理,由下一轮归档重新判断
+    fn archive_config(&mut self, key: ConfigKey, md5: Arc<String>, archive_uri: Arc<String>) {
+        let value = match self.cache.get_mut(&key) {
+            Some(v) if !v.tmp && !v.is_archived() && v.md5 == md5 => v,
+            _ => return,
+        };
+        self.content_store.release(&value.md5);
+        value.archive(archive_uri);
+        self.history_index.remove_key_sets(&key);
+        self.update_dedup_savings();
+        self.update_search_index(&key, None);
+        self.update_read_view(&key);
+    }
+
+    ///
+    /// 按变更记录生成订阅源条目,写入记录附带与上一版本的差异
+    fn get_change_feed(&self, param: &ConfigHistorySearchParam) -> Vec<ConfigFeedEntry> {
+        let (_, list) = self.history_index.search(param);
+        list.into_iter()
+            .map(|item| {
+                let history_id = item.id;
+                let key = ConfigKey::new_by_arc(item.data_id, item.group, item.tenant);
+                let change_type = if item.change_type == "DELETE" {
+                    ConfigChangeType::Remove
+                } else {
+                    ConfigChangeType::Set
+                };
+                let diff = match (&change_type, self.cache.get(&key)) {
+                    (ConfigChangeType::Set, Some(v)) => v
+                        .histories
+                        .iter()
+                        .position(|h| h.id == history_id)
+                        .map(|i| {
+                            let old = if i > 0 {
+                                v.histories[i - 1].content.as_str()
+                            } else {
+                                ""
+                            };
+                            let new = v.histories[i].content.as_str();
+                            render_unified_diff(&diff_lines(old, new), 3)
+                        }),
+                    _ => None,
+                };
+                ConfigFeedEntry {
+                    key,
+                    change_type,
+                    history_id,
+                    op_user: item.op_user,
+                    modified_time: item.modified_time,
+                    diff,
+                }
+            })
+            .collect()
+    }
+
+    ///
+    /// 应用各节点上报的最近读取时间;只在内存与快照中保存,不更新只读视图
+    fn touch_configs(&mut self, items: Vec<(String, i64)>) {
+        for (key, read_ms) in items {
+            let config_key: ConfigKey = (&key as &str).into();
+            if let Some(v) = self.cache.get_mut(&config_key) {
+                v.last_read_ms = v.last_read_ms.max(read_ms);
+            }
+        }
+    }
+
+    ///
+    /// 本节点读取时间比集群记录晚min_interval_ms以上的配置,有监听的配置按当前时间上报
+    fn get_touch_items(&self, min_interval_ms: i64) -> Vec<(ConfigKey, i64)> {
+        let now = now_millis_i64();
+        let mut read_times = self.stats.read_times();
+        for key in self.subscriber.subscribed_keys() {
+            read_times.insert(key.clone(), now);
+        }
+        read_times
+            .into_iter()
+            .filter(|(k, read_ms)| match self.cache.get(k) {
+                Some(v) => !v.tmp && *read_ms - v.last_read_ms >= min_interval_ms,
+                None => false,
+            })
+            .collect()
+    }
+
+    ///
+    /// 集群内最近读写时间都早于before_ms的配置,不含系统配置
+    fn get_archive_candidates(
+        &self,
+        before_ms: i64,
+        limit: usize,
+    ) -> Vec<(ConfigKey, ConfigValue)> {
+        self.cache
+            .iter()
+            .filter(|(k, v)| {
+                !v.tmp
+                    && !v.is_archived()
+                    && k.tenant.as_str() != SYSCONFIG_NAMESPACE
+                    && v.is_inactive(before_ms)
+            })
+            .take(limit)
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
+    ///
+    /// deleted为删除时间与操作人,为None时不记录删除历史
+    fn del_config(
+        &mut self,
+        key: ConfigKey,
+        deleted: Option<(i64, Option<Arc<String>>)>,
+    ) -> anyhow::Result<()> {
+        let write_ms = deleted.as_ref().map_or_else(now_millis_i64, |v| v.0);
+        let mut priority = ConfigPriority::default();
+        if let Some(old) = self.cache.remove(&key) {
+            priority = old.priority.unwrap_or_default();
+            if !old.is_archived() {
+                self.content_store.release(&old.md5);
+            }
+            self.update_reverse_references(&key, &old.references, &[]);
+            self.update_ns_refs(&key, None);
+            self.update_dedup_savings();
+            if let Some((op_time, op_user)) = deleted {
+                self.history_index.insert_delete(&key, op_time, op_user);
+            }
+        }
+        //强制删除被依赖的配置后,清除依赖方中失效的引用
+        for dependent in self.reverse_references.remove(&key).unwrap_or_default() {
+            if let Some(v) = self.cache.get_mut(&dependent) {
+                v.references.retain(|item| item != &key);
+            }
+        }
+        //self.config_db.del_config(&key).ok();
+        self.tenant_index.remove_config(&key);
+        self.update_read_view(&key);
+        self.stats.remove(&key);
+        self.update_search_index(&key, None);
+        self.refresh_alias_settings(&key);
+        self.notify_change(&key, priority, write_ms);
+        self.subscriber.remove_config_key(key.clone());
+        self.publish_event(ConfigChangeEvent {
+            key,
+            change_type: ConfigChangeType::Remove,
+            md5: None,
+            op_user: None,
+            op_time: write_ms,
+        });
+        Ok(())
+    }
+
+    /*
+    fn load_config(&mut self) {
+        for item in self.config_db.query_config_list().unwrap() {
+            let key = ConfigKey::new(
+                item.data_id.as_ref(),
+                item.group.as_ref(),
+                item.tenant.as_ref(),
+            );
+            let val = ConfigValue::new(Arc::new(item.content.unwrap_or_default()));
+            self.tenant_index.insert_config(key.clone());
+            self.cache.insert(key, val);
+        }
+        self.config_db.init_seq();
+    }
+     */
+
+    async fn send_raft_request(
+        raft: &Option<Weak<NacosRaft>>,
+        req: ClientRequest,
+    ) -> anyhow::Result<ClientResponse> {
+        if let Some(weak_raft) = raft {
+            if let Some(raft) = weak_raft.upgrade() {
+                //TODO换成feature,非wait的方式
+                let resp = raft.client_write(ClientWriteRequest::new(req)).await?;
+                return Ok(resp.data);
+            }
+        }
+        Ok(ClientResponse::Success)
+    }
+
+    ///
+    /// 单机模式在raft镜像与日志加载完成后回放预写日志,预写日志中的配置覆盖raft中的数据;
+    /// 日志以压缩快照开头时,快照中没有的配置视为已删除
+    fn replay_wal(&mut self) {
+        let records = match &mut self.wal {
+            Some(wal) if !self.wal_replayed => wal.take_pending(),
+            _ => return,
+        };
+        log::info!("config wal replay {} records", records.len());
+        let mut snapshot_keys: Option<HashSet<ConfigKey>> = None;
+        for record in records {
+            match record {
+                ConfigWalRecord::Snapshot => snapshot_keys = Some(HashSet::new()),
+                ConfigWalRecord::Value(key, value) => {
+                    if let Some(keys) = &mut snapshot_keys {
+                        keys.insert(key.clone());
+                    }
+                    self.inner_set_config(key, value.into());
+                }
+                ConfigWalRecord::LastId(last_id) => {
+                    self.sequence.set_valid_last_id(last_id);
+                    if let Some(keys) = snapshot_keys.take() {
+                        let removed: Vec<ConfigKey> = self
+                            .cache
+                            .keys()
+                            .filter(|k| !keys.contains(k))
+                            .cloned()
+                            .collect();
+                        //删除记录已随压缩日志保存
+                        for key in removed {
+                            self.del_config(key, None).ok();
+                        }
+                    }
+                }
+                ConfigWalRecord::DeleteHistory(item) => {
+                    self.history_index
+                        .insert_delete(&item.key, item.op_time, item.op_user);
+                }
+                ConfigWalRecord::Request(req) => {
+                    if let Some(cmd) = to_config_raft_cmd(req) {
+                        if let Err(err) = self.apply_raft_cmd(cmd) {
+                            log::warn!("config wal replay error: {}", err);
+                        }
+                    }
+                }
+            }
+        }
+        self.wal_replayed = true;
+    }
+
+    fn compact_wal(&mut self) {
+        let last_id = self.sequence.get_end_id();
+        let wal = match &mut self.wal {
+            Some(wal) if self.wal_replayed && wal.need_compact() => wal,
+            _ => return,
+        };
+        let values = self
+            .cache
+            .iter()
+            .filter(|(_, v)| !v.tmp)
+            .map(|(k, v)| (k.clone(), ConfigValueDO::from(v.clone())));
+        match wal.compact(values, self.history_index.delete_histories(), last_id) {
+            Ok(_) => log::info!("config wal compacted"),
+            Err(err) => log::error!("config wal compact error: {}", err),
+        }
+    }
+
+    ///
+    /// 单机模式下写入请求先追加到预写日志,落盘后再按应用raft日志相同的方式写入内存;
+    /// 日志记录与raft日志内容相同,保留操作人、操作时间、引用及优先级等全部信息;
+    /// md5不一致的写入不记录到日志
+    fn standalone_write(&mut self, msg: ConfigAsyncCmd) -> anyhow::Result<ConfigResult> {
+        if !self.wal_replayed {
+            return Err(anyhow::anyhow!("config wal is not replayed yet"));
+        }
+        let op_time = now_millis_i64();
+        let req = match msg {
+            ConfigAsyncCmd::Add {
+                key,
+                value,
+                op_user,
+                config_type,
+                desc,
+                expected_md5,
+                references,
+                priority,
+            } => {
+                self.check_expected_md5(&key, &expected_md5)?;
+                let (history_id, history_table_id) = self.sequence.next_state()?;
+                ClientRequest::ConfigSet {
+                    key: key.build_key(),
+                    value,
+                    config_type,
+                    desc,
+                    history_id,
+                    history_table_id,
+                    op_time,
+                    op_user,
+                    expected_md5: None,
+                    references,
+                    priority,
+                    max_versions: Some(self.max_versions_per_key),
+                }
+            }
+            ConfigAsyncCmd::Delete { key, op_user, .. } => ClientRequest::ConfigRemove {
+                key: key.build_key(),
+                op_time,
+                op_user,
+            },
+            ConfigAsyncCmd::BatchAdd {
+                reqs,
+                op_user,
+                atomic,
+            } => {
+                let passed = reqs
+                    .iter()
+                    .filter(|req| {
+                        self.check_expected_md5(&req.config_key, &req.expected_md5)
+                            .is_ok()
+                    })
+                    .count();
+                let mut items = Vec::with_capacity(reqs.len());
+                for req in reqs {
+                    let (history_id, history_table_id) = self.sequence.next_state()?;
+                    items.push(ConfigSetItem {
+                        key: req.config_key.build_key(),
+                        value: req.value,
+                        config_type: req.config_type,
+                        desc: req.desc,
+                        history_id,
+                        history_table_id,
+                        expected_md5: req.expected_md5,
+                        references: req.references,
+                        priority: req.priority,
+                        op_user: req.op_user,
+                    });
+                }
+                let size = items.len();
+                let req = ClientRequest::ConfigBatchSet {
+                    items,
+                    op_time,
+                    op_user,
+                    atomic,
+                    max_versions: Some(self.max_versions_per_key),
+                };
+                //没有可写入的配置时不记录日志,回放时md5校验结果与此时一致
+                if passed == 0 || (atomic && passed < size) {
+                    return match self.apply_request(req)? {
+                        ConfigRaftResult::BatchResult(results) => {
+                            Ok(ConfigResult::BatchResult(results))
+                        }
+                        _ => Ok(ConfigResult::NULL),
+                    };
+                }
+                req
+            }
+            ConfigAsyncCmd::Touch(items) => ClientRequest::ConfigTouch {
+                items: items
+                    .into_iter()
+                    .map(|(key, read_ms)| (key.build_key(), read_ms))
+                    .collect(),
+            },
+            ConfigAsyncCmd::PurgeNamespace(tenant) => ClientRequest::ConfigPurgeNamespace {
+                tenant: tenant.as_ref().to_owned(),
+                op_time: now_millis_i64(),
+            },
+        };
+        if let Some(wal) = &mut self.wal {
+            wal.append(&[ConfigWalRecord::Request(req.clone())])?;
+        }
+        match self.apply_request(req)? {
+            ConfigRaftResult::BatchResult(results) => Ok(ConfigResult::BatchResult(results)),
+            _ => Ok(ConfigResult::NULL),
+        }
+    }
+
+    fn apply_request(&mut self, req: ClientRequest) -> anyhow::Result<ConfigRaftResult> {
+        match to_config_raft_cmd(req) {
+            Some(cmd) => self.apply_raft_cmd(cmd),
+            None => Ok(ConfigRaftResult::None),
+        }
+    }
+
+    ///
+    /// 原子写入一批配置:任一配置md5不一致时整批不写入;
+    /// 写入完成后一次性更新只读视图,再通知各配置的监听方
+    fn atomic_set_configs(
+        &mut self,
+        items: Vec<ConfigSetItem>,
+        op_time: i64,
+        op_user: Option<Arc<String>>,
+        max_versions: usize,
+    ) -> anyhow::Result<Vec<ConfigSetResult>> {
+        let keys: Vec<ConfigKey> = items.iter().map(|v| (&v.key as &str).into()).collect();
+        let mismatches: Vec<Option<ConfigMd5MismatchError>> = keys
+            .iter()
+            .zip(items.iter())
+            .map(|(key, item)| self.check_expected_md5(key, &item.expected_md5).err())
+            .collect();
+        if mismatches.iter().any(|v| v.is_some()) {
+            return Ok(mismatches
+                .into_iter()
+                .map(|v| match v {
+                    Some(e) => ConfigSetResult::Md5Mismatch {
+                        current_md5: e.current_md5,
+                    },
+                    None => ConfigSetResult::Aborted,
+                })
+                .collect());
+        }
+        self.deferred_changes = Some(vec![]);
+        let mut result = Ok(());
+        for (key, item) in keys.iter().cloned().zip(items) {
+            if let Err(err) = self.set_config(
+                key,
+                item.value,
+                item.config_type
+                    .map(|v| ConfigType::new_by_value(v.as_ref()).get_value()),
+                item.desc,
+                item.references,
+                item.priority,
+                item.history_id,
+                item.history_table_id,
+                op_time,
+                item.op_user.or(op_user.clone()),
+                max_versions,
+            ) {
+                result = Err(err);
+                break;
+            }
         }
-        self.listener.notify(key.clone());
-        self.subscriber.notify(key);
-        Ok(ConfigResult::NULL)
+        //出错时已写入的配置同样需要更新只读视图并通知
+        let changes = self.deferred_changes.take().unwrap_or_default();
+        let values = keys
+            .iter()
+            .map(|key| {
+                let value = self
+                    .cache
+                    .get(key)
+                    .map(|v| Arc::new(ConfigReadValue::from(v)));
+                (key.clone(), value)
+            })
+            .collect();
+        self.read_view.update_batch(values);
+        for (key, priority, write_ms) in changes {
+            self.notify_change(&key, priority, write_ms);
+        }
+        result?;
+        Ok(vec![ConfigSetResult::Success; keys.len()])
     }
 
-    fn del_config(&mut self, key: ConfigKey) -> anyhow::Result<()> {
-        self.cache.remove(&key);
-        //self.config_db.del_config(&key).ok();
-        self.tenant_index.remove_config(&key);
-        self.listener.notify(key.clone());
-        self.subscriber.notify(key.clone());
-        self.subscriber.remove_config_key(key);
+    ///
+    /// 检查当前md5是否与期望值一致,配置不存在时当前md5为空串
+    fn check_expected_md5(
+        &self,
+        key: &ConfigKey,
+        expected_md5: &Option<Arc<String>>,
+    ) -> Result<(), ConfigMd5MismatchError> {
+        if let Some(expected_md5) = expected_md5 {
+            let current_md5 = self
+                .cache
+                .get(key)
+                .filter(|v| !v.tmp)
+                .map(|v| v.md5.clone());
+            let current = current_md5.as_ref().map(|v| v.as_str()).unwrap_or("");
+            if current != expected_md5.as_str() {
+                return Err(ConfigMd5MismatchError { current_md5 });
+            }
+        }
         Ok(())
     }
 
-    /*
-    fn load_config(&mut self) {
-        for item in self.config_db.query_config_list().unwrap() {
-            let key = ConfigKey::new(
-                item.data_id.as_ref(),
-                item.group.as_ref(),
-                item.tenant.as_ref(),
-            );
-            let val = ConfigValue::new(Arc::new(item.content.unwrap_or_default()));
-            self.tenant_index.insert_config(key.clone());
-            self.cache.insert(key, val);
-        }
-        self.config_db.init_seq();
+    ///
+    /// 按dataId前缀补全配置,按最近7天读取次数倒序
+    fn suggest_configs(
+        &self,
+        tenant: &Arc<String>,
+        prefix: &str,
+        limit: usize,
+    ) -> Vec<ConfigSuggestItem> {
+        let now = now_millis_i64();
+        let mut list: Vec<(u64, ConfigKey)> = self
+            .tenant_index
+            .query_by_data_id_prefix(tenant, prefix)
+            .into_iter()
+            .map(|key| (self.stats.get(&key).read_count_7d(now), key))
+            .collect();
+        list.sort_by(|(count_a, a), (count_b, b)| {
+            count_b
+                .cmp(count_a)
+                .then_with(|| (&a.data_id, &a.group).cmp(&(&b.data_id, &b.group)))
+        });
+        list.into_iter()
+            .take(limit)
+            .map(|(_, key)| {
+                let last_modified = self
+                    .cache
+                    .get(&key)
+                    .and_then(|v| v.histories.iter().map(|e| e.modified_time).max())
+                    .unwrap_or_default();
+                ConfigSuggestItem {
+                    data_id: key.data_id,
+                    group: key.group,
+                    last_modified,
+                }
+            })
+            .collect()
     }
-     */
 
-    async fn send_raft_request(
-        raft: &Option<Weak<NacosRaft>>,
-        req: ClientRequest,
-    ) -> anyhow::Result<()> {
-        if let Some(weak_raft) = raft {
-            if let Some(raft) = weak_raft.upgrade() {
-                //TODO换成feature,非wait的方式
-                raft.client_write(ClientWriteRequest::new(req)).await?;
+    fn get_namespace_stats(&self) -> Vec<NamespaceStats> {
+        let mut stats_map: HashMap<Arc<String>, NamespaceStats> = HashMap::new();
+        for (key, value) in &self.cache {
+            let item = stats_map
+                .entry(key.tenant.clone())
+                .or_insert_with(|| NamespaceStats {
+                    namespace_id: key.tenant.clone(),
+                    ..Default::default()
+                });
+            item.config_count += 1;
+            item.total_content_bytes += value.content.len();
+            if let Some(modified_time) = value.histories.iter().map(|e| e.modified_time).max() {
+                item.last_modified = item.last_modified.max(modified_time);
             }
         }
-        Ok(())
+        for (tenant, count) in self.subscriber.get_tenant_client_count() {
+            stats_map
+                .entry(tenant.clone())
+                .or_insert_with(|| NamespaceStats {
+                    namespace_id: tenant,
+                    ..Default::default()
+                })
+                .active_listeners = count;
+        }
+        let mut list: Vec<NamespaceStats> = stats_map.into_values().collect();
+        list.sort_by(|a, b| a.namespace_id.cmp(&b.namespace_id));
+        list
+    }
+
+    ///
+    /// 配置不存在或为临时值时变更序号为0
+    fn get_change_seq(&self, key: &ConfigKey) -> u64 {
+        let key = self.alias_settings.resolve(key).unwrap_or(key);
+        self.cache
+            .get(key)
+            .filter(|v| !v.tmp)
+            .map(|v| v.change_seq())
+            .unwrap_or_default()
     }
 
     pub fn get_config_info_page(&self, param: &ConfigQueryParam) -> (usize, Vec<ConfigInfoDto>) {
@@ -610,15 +1935,40 @@ impl ConfigActor {
             op_type: 0,
         };
         writer.do_send(SnapshotWriterRequest::Record(seq_record));
+        for (i, item) in self.history_index.delete_histories().iter().enumerate() {
+            let record = SnapshotRecordDto {
+                tree: CONFIG_DELETE_HISTORY_TREE_NAME.clone(),
+                key: (i as u64).to_be_bytes().to_vec(),
+                value: serde_json::to_vec(item)?,
+                op_type: 0,
+            };
+            writer.do_send(SnapshotWriterRequest::Record(record));
+        }
         Ok(())
     }
 
     pub fn hb(&self, ctx: &mut actix::Context<Self>) {
         ctx.run_later(Duration::from_millis(500), |act, ctx| {
-            act.listener.timeout();
+            act.watch.clear_unused();
+            if act.read_view_dirty {
+                act.rebuild_read_view();
+            }
+            act.compact_wal();
             act.hb(ctx);
         });
     }
+
+    ///
+    /// 定时刷新命名空间指标
+    fn namespace_gauges_hb(&self, ctx: &mut actix::Context<Self>) {
+        ctx.run_later(
+            Duration::from_secs(NAMESPACE_STATS_CACHE_SECONDS),
+            |act, ctx| {
+                update_namespace_gauges(&act.get_namespace_stats());
+                act.namespace_gauges_hb(ctx);
+            },
+        );
+    }
 }
 
 #[derive(Message)]
@@ -629,14 +1979,54 @@ pub enum ConfigCmd {
     SetTmpValue(ConfigKey, Arc<String>),
     InnerSet(ConfigKey, ConfigValue),
     InnerSetLastId(u64),
+    InnerInsertDeleteHistory(ConfigDeleteHistory),
     GET(ConfigKey),
     QueryPageInfo(Box<ConfigQueryParam>),
     QueryHistoryPageInfo(Box<ConfigHistoryParam>),
-    LISTENER(Vec<ListenerItem>, ListenerSenderType, i64),
+    QueryHistoryCount(ConfigKey),
     Subscribe(Vec<ListenerItem>, Arc<String>),
     RemoveSubscribe(Vec<ListenerItem>, Arc<String>),
     RemoveSubscribeClient(Arc<String>),
+    QueryClientSubscribes,
+    //订阅配置的客户端,含通配订阅
+    QueryKeySubscribeClients(ConfigKey),
+    //客户端按key订阅的配置及其最新变更序号
+    QueryClientKeySeqs(Arc<String>),
+    //返回变更序号与客户端记录不一致的配置
+    QueryChangedSince(Vec<(ConfigKey, u64)>),
+    //命名空间下设置了依赖的配置
+    QueryReferences(Arc<String>),
+    //依赖该配置的配置
+    QueryDependents(ConfigKey),
+    //各命名空间的配置数量、内容大小及订阅客户端数
+    QueryNamespaceStats,
+    //按key排序返回命名空间下的全部配置
+    ListAll(Arc<String>),
+    //按历史版本还原命名空间在指定时间点的配置,不修改当前配置
+    QuerySnapshotAt(Arc<String>, i64),
+    //缓存按内容推断的类型,md5不一致时忽略
+    SetDetectedType(ConfigKey, Arc<String>, Arc<String>),
     BuildSnapshot(Addr<SnapshotWriterActor>),
+    //按命名空间、时间范围、操作人及变更类型查询变更记录
+    SearchHistory(Box<ConfigHistorySearchParam>),
+    //合并计时结束,通知命名空间下待通知的变更
+    FlushNotify(Arc<String>, ConfigPriority),
+    //超过变更频率上限的配置冷却结束,通知最近一次变更
+    FlushThrottledNotify(ConfigKey, ConfigPriority),
+    //启动时raft数据加载完成后回放单机模式的预写日志并预热读缓存
+    PrewarmCache,
+    //最近读写时间早于指定时间的待归档配置,最多返回指定数量
+    QueryArchiveCandidates(i64, usize),
+    //本节点读取时间比集群记录晚指定毫秒数以上、需要上报的配置
+    QueryTouchItems(i64),
+    //命名空间下集群内最近读写时间都早于指定时间的配置
+    QueryInactiveConfigs(Arc<String>, i64),
+    //指定配置集群内的最近读写时间,不存在的配置忽略
+    QueryActivities(Vec<ConfigKey>),
+    //按命名空间、分组生成配置变更订阅源的条目
+    QueryChangeFeed(Box<ConfigHistorySearchParam>),
+    //(tenant,dataId前缀,数量)
+    SuggestConfigs(Arc<String>, String, usize),
 }
 
 #[derive(Message)]
@@ -648,8 +2038,26 @@ pub enum ConfigAsyncCmd {
         op_user: Option<Arc<String>>,
         config_type: Option<Arc<String>>,
         desc: Option<Arc<String>>,
+        expected_md5: Option<Arc<String>>,
+        references: Option<Vec<ConfigKey>>,
+        priority: Option<ConfigPriority>,
+    },
+    Delete {
+        key: ConfigKey,
+        //为true时允许删除被其它配置依赖的配置
+        force: bool,
+        op_user: Option<Arc<String>>,
+    },
+    BatchAdd {
+        reqs: Vec<SetConfigReq>,
+        op_user: Option<Arc<String>>,
+        //为true时整批写入或都不写入
+        atomic: bool,
     },
-    Delete(ConfigKey),
+    //上报本节点记录的最近读取时间
+    Touch(Vec<(ConfigKey, i64)>),
+    //清除已删除命名空间下的所有配置
+    PurgeNamespace(Arc<String>),
 }
 
 pub enum ConfigResult {
@@ -658,11 +2066,29 @@ pub enum ConfigResult {
         md5: Arc<String>,
         config_type: Option<Arc<String>>,
         desc: Option<Arc<String>>,
+        //已归档时value为空,需从归档地址读取
+        archive_uri: Option<Arc<String>>,
     },
     NULL,
     ChangeKey(Vec<ConfigKey>),
     ConfigInfoPage(usize, Vec<ConfigInfoDto>),
     ConfigHistoryInfoPage(usize, Vec<ConfigHistoryInfoDto>),
+    ConfigList(Vec<(ConfigKey, ConfigReadValue)>),
+    BatchResult(Vec<ConfigSetResult>),
+    HistoryCount(usize),
+    ClientSubscribes(HashMap<Arc<String>, Vec<ConfigKey>>),
+    SubscribeClients(HashSet<Arc<String>>),
+    KeySeqs(Vec<(ConfigKey, u64)>),
+    References(Vec<(ConfigKey, Vec<ConfigKey>)>),
+    Dependents(Vec<ConfigKey>),
+    NamespaceStats(Vec<NamespaceStats>),
+    HistorySearchPage(usize, Vec<ConfigHistorySearchItem>),
+    ArchiveCandidates(Vec<(ConfigKey, ConfigValue)>),
+    TouchItems(Vec<(ConfigKey, i64)>),
+    //(key,最近读取时间,最近修改时间)
+    ConfigActivities(Vec<(ConfigKey, i64, i64)>),
+    ChangeFeed(Vec<ConfigFeedEntry>),
+    Suggestions(Vec<ConfigSuggestItem>),
 }
 
 impl Actor for ConfigActor {
@@ -670,7 +2096,9 @@ impl Actor for ConfigActor {
 
     fn started(&mut self, ctx: &mut Self::Context) {
         log::info!("ConfigActor started");
+        self.self_addr = Some(ctx.address());
         self.hb(ctx);
+        self.namespace_gauges_hb(ctx);
     }
 }
 
@@ -681,11 +2109,154 @@ impl Supervised for ConfigActor {
 }
 
 impl Handler<ConfigCmd> for ConfigActor {
-    type Result = anyhow::Result<ConfigResult>;
+    type Result = ResponseActFuture<Self, anyhow::Result<ConfigResult>>;
 
     fn handle(&mut self, msg: ConfigCmd, _ctx: &mut Context<Self>) -> Self::Result {
+        let store = match self.archive_store.clone() {
+            Some(store) => store,
+            None => return Box::pin(actix::fut::ready(self.handle_cmd(msg))),
+        };
+        //已归档的配置只保留元数据,内容与历史版本需从对象存储读取
+        let snapshot = match &msg {
+            ConfigCmd::QuerySnapshotAt(tenant, at) => Some((self.archived_uris(tenant), *at)),
+            _ => None,
+        };
+        let query_context = matches!(&msg, ConfigCmd::QueryPageInfo(param) if param.query_context);
+        let result = match self.handle_cmd(msg) {
+            Ok(v) => v,
+            Err(err) => return Box::pin(actix::fut::ready(Err(err))),
+        };
+        let page_uris: Vec<Option<Arc<String>>> = match &result {
+            ConfigResult::ConfigInfoPage(_, list) if query_context => list
+                .iter()
+                .map(|v| {
+                    let key =
+                        ConfigKey::new_by_arc(v.data_id.clone(), v.group.clone(), v.tenant.clone());
+                    self.cache.get(&key).and_then(|v| v.archive_uri.clone())
+                })
+                .collect(),
+            _ => vec![],
+        };
+        let need_load = match &result {
+            ConfigResult::Data { archive_uri, .. } => archive_uri.is_some(),
+            ConfigResult::ConfigList(list) => list.iter().any(|(_, v)| v.archive_uri.is_some()),
+            _ => {
+                page_uris.iter().any(|v| v.is_some())
+                    || snapshot
+                        .as_ref()
+                        .map(|v| !v.0.is_empty())
+                        .unwrap_or_default()
+            }
+        };
+        if !need_load {
+            return Box::pin(actix::fut::ready(Ok(result)));
+        }
+        let fut = async move { load_archived_result(&store, result, page_uris, snapshot).await }
+            .into_actor(self);
+        Box::pin(fut)
+    }
+}
+
+//历史快照查询时已归档的配置及归档地址,与查询的时刻
+type ArchivedSnapshot = (Vec<(ConfigKey, Arc<String>)>, i64);
+
+///
+/// 补全查询结果中已归档配置的内容
+async fn load_archived_result(
+    store: &ConfigArchiveStore,
+    result: ConfigResult,
+    page_uris: Vec<Option<Arc<String>>>,
+    snapshot: Option<ArchivedSnapshot>,
+) -> anyhow::Result<ConfigResult> {
+    match result {
+        ConfigResult::Data {
+            value: _,
+            md5,
+            config_type,
+            desc,
+            archive_uri: Some(archive_uri),
+        } => Ok(ConfigResult::Data {
+            value: store.load_content(&archive_uri).await?,
+            md5,
+            config_type,
+            desc,
+            archive_uri: Some(archive_uri),
+        }),
+        ConfigResult::ConfigList(list) => {
+            let list = stream::iter(list)
+                .map(|(key, mut value)| async move {
+                    if let Some(archive_uri) = &value.archive_uri {
+                        value.content = store.load_content(archive_uri).await?;
+                    }
+                    anyhow::Ok((key, value))
+                })
+                .buffered(ARCHIVE_LOAD_CONCURRENCY)
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(ConfigResult::ConfigList(list))
+        }
+        ConfigResult::ConfigInfoPage(size, list) => {
+            let mut list = stream::iter(
+                list.into_iter()
+                    .zip(page_uris.into_iter().chain(std::iter::repeat(None))),
+            )
+            .map(|(mut info, archive_uri)| async move {
+                if let Some(archive_uri) = &archive_uri {
+                    info.content = Some(store.load_content(archive_uri).await?);
+                }
+                anyhow::Ok(info)
+            })
+            .buffered(ARCHIVE_LOAD_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>()?;
+            let (archived, at) = match snapshot {
+                Some(v) => v,
+                None => return Ok(ConfigResult::ConfigInfoPage(size, list)),
+            };
+            //历史快照中补充已归档配置在指定时刻的版本
+            let archived = stream::iter(archived)
+                .map(|(key, archive_uri)| async move {
+                    let value = store.load(&archive_uri).await?;
+                    anyhow::Ok(value.history_at(at).map(|item| ConfigInfoDto {
+                        tenant: key.tenant,
+                        group: key.group,
+                        data_id: key.data_id,
+                        content: Some(item.content.clone()),
+                        md5: None,
+                    }))
+                })
+                .buffered(ARCHIVE_LOAD_CONCURRENCY)
+                .collect::<Vec<_>>()
+                .await;
+            for item in archived {
+                if let Some(info) = item? {
+                    list.push(info);
+                }
+            }
+            list.sort_by(|a, b| (&a.group, &a.data_id).cmp(&(&b.group, &b.data_id)));
+            Ok(ConfigResult::ConfigInfoPage(list.len(), list))
+        }
+        _ => Ok(result),
+    }
+}
+
+impl ConfigActor {
+    fn archived_uris(&self, tenant: &Arc<String>) -> Vec<(ConfigKey, Arc<String>)> {
+        self.cache
+            .iter()
+            .filter(|(k, _)| &k.tenant == tenant)
+            .filter_map(|(k, v)| v.archive_uri.clone().map(|uri| (k.clone(), uri)))
+            .collect()
+    }
+
+    fn handle_cmd(&mut self, msg: ConfigCmd) -> anyhow::Result<ConfigResult> {
         match msg {
-            ConfigCmd::SetTmpValue(key, value) => {
+            ConfigCmd::SetTmpValue(mut key, value) => {
+                self.resolve_alias(&mut key);
                 self.set_tmp_config(key, value);
             }
             ConfigCmd::InnerSet(key, value) => {
@@ -694,39 +2265,33 @@ impl Handler<ConfigCmd> for ConfigActor {
             ConfigCmd::InnerSetLastId(last_id) => {
                 self.sequence.set_last_id(last_id);
             }
-            ConfigCmd::GET(key) => {
+            ConfigCmd::InnerInsertDeleteHistory(item) => {
+                self.history_index
+                    .insert_delete(&item.key, item.op_time, item.op_user);
+            }
+            ConfigCmd::GET(mut key) => {
+                self.resolve_alias(&mut key);
+                self.refresh_namespace_settings();
+                if self.template_namespaces.is_deleted(&key.tenant) {
+                    return Ok(ConfigResult::NULL);
+                }
                 if let Some(v) = self.cache.get(&key) {
+                    self.stats.record_read(&key);
                     return Ok(ConfigResult::Data {
                         value: v.content.clone(),
                         md5: v.md5.clone(),
                         config_type: v.config_type.clone(),
                         desc: v.desc.clone(),
+                        archive_uri: v.archive_uri.clone(),
                     });
                 }
             }
-            ConfigCmd::LISTENER(items, sender, time) => {
-                let mut changes = vec![];
-                for item in &items {
-                    if let Some(v) = self.cache.get(&item.key) {
-                        if v.md5 != item.md5 {
-                            changes.push(item.key.clone());
-                        }
-                    } else if !item.md5.is_empty() {
-                        changes.push(item.key.clone());
-                    }
-                }
-                if !changes.is_empty() || time <= 0 {
-                    sender.send(ListenerResult::DATA(changes)).ok();
-                    return Ok(ConfigResult::NULL);
-                } else {
-                    self.listener.add(items, sender, time);
-                    return Ok(ConfigResult::NULL);
-                }
-            }
             ConfigCmd::Subscribe(items, client_id) => {
+                let items = self.add_wildcard_subscribe(&client_id, items);
                 let mut changes = vec![];
                 for item in &items {
-                    if let Some(v) = self.cache.get(&item.key) {
+                    self.stats.record_read(&item.key);
+                    if let Some(v) = self.get_effective_value(&item.key) {
                         if v.md5 != item.md5 {
                             changes.push(item.key.clone());
                         }
@@ -740,11 +2305,50 @@ impl Handler<ConfigCmd> for ConfigActor {
                 }
             }
             ConfigCmd::RemoveSubscribe(items, client_id) => {
+                let items = self.remove_wildcard_subscribe(&client_id, items);
                 self.subscriber.remove_subscribe(client_id, items);
             }
             ConfigCmd::RemoveSubscribeClient(client_id) => {
+                self.wildcard_listeners.retain(|v| v.client_id != client_id);
                 self.subscriber.remove_client_subscribe(client_id);
             }
+            ConfigCmd::QueryClientSubscribes => {
+                return Ok(ConfigResult::ClientSubscribes(
+                    self.subscriber.get_client_keys(),
+                ));
+            }
+            ConfigCmd::QueryKeySubscribeClients(key) => {
+                let mut clients = self.subscriber.get_key_clients(&key);
+                clients.extend(
+                    self.wildcard_listeners
+                        .iter()
+                        .filter(|v| v.item.is_match(&key))
+                        .map(|v| v.client_id.clone()),
+                );
+                return Ok(ConfigResult::SubscribeClients(clients));
+            }
+            ConfigCmd::QueryClientKeySeqs(client_id) => {
+                let list = self
+                    .subscriber
+                    .get_keys_by_client(&client_id)
+                    .into_iter()
+                    .map(|key| {
+                        let seq = self.get_change_seq(&key);
+                        (key, seq)
+                    })
+                    .collect();
+                return Ok(ConfigResult::KeySeqs(list));
+            }
+            ConfigCmd::QueryChangedSince(key_seqs) => {
+                let changes: Vec<ConfigKey> = key_seqs
+                    .into_iter()
+                    .filter(|(key, seq)| self.get_change_seq(key) != *seq)
+                    .map(|(key, _)| key)
+                    .collect();
+                if !changes.is_empty() {
+                    return Ok(ConfigResult::ChangeKey(changes));
+                }
+            }
             ConfigCmd::QueryPageInfo(config_query_param) => {
                 let (size, list) = self.get_config_info_page(config_query_param.as_ref());
                 return Ok(ConfigResult::ConfigInfoPage(size, list));
@@ -753,19 +2357,239 @@ impl Handler<ConfigCmd> for ConfigActor {
                 let (size, list) = self.get_history_info_page(query_param.as_ref());
                 return Ok(ConfigResult::ConfigHistoryInfoPage(size, list));
             }
+            ConfigCmd::QueryHistoryCount(key) => {
+                let count = self.cache.get(&key).map(|v| v.histories.len());
+                return Ok(ConfigResult::HistoryCount(count.unwrap_or_default()));
+            }
+            ConfigCmd::ListAll(tenant) => {
+                let mut list: Vec<(ConfigKey, ConfigReadValue)> = self
+                    .cache
+                    .iter()
+                    .filter(|(k, v)| k.tenant == tenant && !v.tmp)
+                    .map(|(k, v)| (k.clone(), ConfigReadValue::from(v)))
+                    .collect();
+                list.sort_by(|a, b| (&a.0.group, &a.0.data_id).cmp(&(&b.0.group, &b.0.data_id)));
+                return Ok(ConfigResult::ConfigList(list));
+            }
+            ConfigCmd::QueryReferences(tenant) => {
+                let mut list: Vec<(ConfigKey, Vec<ConfigKey>)> = self
+                    .cache
+                    .iter()
+                    .filter(|(k, v)| k.tenant == tenant && !v.references.is_empty())
+                    .map(|(k, v)| (k.clone(), v.references.clone()))
+                    .collect();
+                list.sort_by(|a, b| (&a.0.group, &a.0.data_id).cmp(&(&b.0.group, &b.0.data_id)));
+                return Ok(ConfigResult::References(list));
+            }
+            ConfigCmd::QueryDependents(key) => {
+                return Ok(ConfigResult::Dependents(self.get_dependents(&key)));
+            }
+            ConfigCmd::QueryNamespaceStats => {
+                return Ok(ConfigResult::NamespaceStats(self.get_namespace_stats()));
+            }
+            ConfigCmd::QuerySnapshotAt(tenant, at) => {
+                let mut list: Vec<ConfigInfoDto> = self
+                    .cache
+                    .iter()
+                    .filter(|(k, v)| k.tenant == tenant && !v.tmp)
+                    .filter_map(|(k, v)| {
+                        let item = v.history_at(at)?;
+                        Some(ConfigInfoDto {
+                            tenant: k.tenant.clone(),
+                            group: k.group.clone(),
+                            data_id: k.data_id.clone(),
+                            content: Some(item.content.clone()),
+                            md5: None,
+                        })
+                    })
+                    .collect();
+                list.sort_by(|a, b| (&a.group, &a.data_id).cmp(&(&b.group, &b.data_id)));
+                return Ok(ConfigResult::ConfigInfoPage(list.len(), list));
+            }
+            ConfigCmd::SetDetectedType(key, md5, detected_type) => {
+                if let Some(v) = self.cache.get_mut(&key) {
+                    if v.config_type.is_none() && v.md5 == md5 {
+                        v.detected_type = Some(detected_type);
+                        self.update_read_view(&key);
+                    }
+                }
+            }
             ConfigCmd::BuildSnapshot(writer) => {
                 self.build_snapshot(writer).ok();
             }
+            ConfigCmd::SearchHistory(param) => {
+                let (size, list) = self.history_index.search(&param);
+                return Ok(ConfigResult::HistorySearchPage(size, list));
+            }
+            ConfigCmd::FlushNotify(tenant, priority) => {
+                self.flush_notify(tenant, priority);
+            }
+            ConfigCmd::FlushThrottledNotify(key, priority) => {
+                self.flush_throttled_notify(key, priority);
+            }
+            ConfigCmd::PrewarmCache => {
+                self.replay_wal();
+                self.prewarm_cache();
+                self.retain_search_index();
+            }
+            ConfigCmd::SuggestConfigs(tenant, prefix, limit) => {
+                return Ok(ConfigResult::Suggestions(
+                    self.suggest_configs(&tenant, &prefix, limit),
+                ));
+            }
+            ConfigCmd::QueryChangeFeed(param) => {
+                return Ok(ConfigResult::ChangeFeed(self.get_change_feed(&param)));
+            }
+            ConfigCmd::QueryArchiveCandidates(before_ms, limit) => {
+                return Ok(ConfigResult::ArchiveCandidates(
+                    self.get_archive_candidates(before_ms, limit),
+                ));
+            }
+            ConfigCmd::QueryTouchItems(min_interval_ms) => {
+                return Ok(ConfigResult::TouchItems(
+                    self.get_touch_items(min_interval_ms),
+                ));
+            }
+            ConfigCmd::QueryInactiveConfigs(tenant, before_ms) => {
+                let mut list: Vec<(ConfigKey, i64, i64)> = self
+                    .cache
+                    .iter()
+                    .filter(|(k, v)| k.tenant == tenant && !v.tmp && v.is_inactive(before_ms))
+                    .map(|(k, v)| (k.clone(), v.last_read_ms, v.last_modified))
+                    .collect();
+                list.sort_by(|a, b| (&a.0.group, &a.0.data_id).cmp(&(&b.0.group, &b.0.data_id)));
+                return Ok(ConfigResult::ConfigActivities(list));
+            }
+            ConfigCmd::QueryActivities(keys) => {
+                let list = keys
+                    .into_iter()
+                    .filter_map(|k| {
+                        self.cache
+                            .get(&k)
+                            .map(|v| (v.last_read_ms, v.last_modified))
+                            .map(|(read_ms, modified)| (k, read_ms, modified))
+                    })
+                    .collect();
+                return Ok(ConfigResult::ConfigActivities(list));
+            }
         }
         Ok(ConfigResult::NULL)
     }
 }
 
+impl ConfigActor {
+    ///
+    /// 命名空间开启自动加密时,所有写入在提交前加密匹配的字段
+    fn encrypt_fields(&self, msg: &mut ConfigAsyncCmd) -> anyhow::Result<()> {
+        let cipher = match &self.field_cipher {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        let encrypt = |key: &ConfigKey,
+                       value: &mut Arc<String>,
+                       config_type: &Option<Arc<String>>|
+         -> anyhow::Result<()> {
+            let patterns = match cipher.namespace_patterns(&self.read_view, &key.tenant) {
+                Some(v) => v,
+                None => return Ok(()),
+            };
+            if let Some(content) = cipher.encrypt_content(
+                &key.tenant,
+                value,
+                config_type.as_ref().map(|v| v.as_str()),
+                &patterns,
+            )? {
+                *value = Arc::new(content);
+            }
+            Ok(())
+        };
+        match msg {
+            ConfigAsyncCmd::Add {
+                key,
+                value,
+                config_type,
+                ..
+            } => encrypt(key, value, config_type)?,
+            ConfigAsyncCmd::BatchAdd { reqs, .. } => {
+                for req in reqs.iter_mut() {
+                    encrypt(&req.config_key, &mut req.value, &req.config_type)?;
+                }
+            }
+            ConfigAsyncCmd::Delete { .. }
+            | ConfigAsyncCmd::Touch(_)
+            | ConfigAsyncCmd::PurgeNamespace(_) => {}
+        }
+        Ok(())
+    }
+}
+
 impl Handler<ConfigAsyncCmd> for ConfigActor {
     type Result = ResponseActFuture<Self, anyhow::Result<ConfigResult>>;
 
-    fn handle(&mut self, msg: ConfigAsyncCmd, _ctx: &mut Context<Self>) -> Self::Result {
+    fn handle(&mut self, mut msg: ConfigAsyncCmd, _ctx: &mut Context<Self>) -> Self::Result {
+        //写入别名时写入目标配置
+        match &mut msg {
+            ConfigAsyncCmd::Add { key, .. } | ConfigAsyncCmd::Delete { key, .. } => {
+                self.resolve_alias(key)
+            }
+            ConfigAsyncCmd::BatchAdd { reqs, .. } => {
+                for req in reqs.iter_mut() {
+                    self.resolve_alias(&mut req.config_key);
+                }
+            }
+            ConfigAsyncCmd::Touch(_) | ConfigAsyncCmd::PurgeNamespace(_) => {}
+        }
+        if let Some(detector) = &self.split_brain_detector {
+            if let Err(err) = detector.check_write() {
+                return Box::pin(actix::fut::ready(Err(err.into())));
+            }
+        }
+        self.refresh_namespace_settings();
+        let check = match &msg {
+            ConfigAsyncCmd::Add { key, .. } => self
+                .template_namespaces
+                .check_not_deleted(std::iter::once(key))
+                .and_then(|_| self.template_namespaces.check_write(std::iter::once(key))),
+            //所有删除入口都在这里检查反向依赖,不只是openapi
+            ConfigAsyncCmd::Delete { key, force, .. } => self
+                .template_namespaces
+                .check_write(std::iter::once(key))
+                .and_then(|_| {
+                    let dependents = self.get_dependents(key);
+                    if *force || dependents.is_empty() {
+                        Ok(())
+                    } else {
+                        Err(ConfigInUseError { dependents }.into())
+                    }
+                }),
+            ConfigAsyncCmd::BatchAdd { reqs, .. } => self
+                .template_namespaces
+                .check_not_deleted(reqs.iter().map(|v| &v.config_key))
+                .and_then(|_| {
+                    self.template_namespaces
+                        .check_write(reqs.iter().map(|v| &v.config_key))
+                }),
+            ConfigAsyncCmd::Touch(_) => Ok(()),
+            //只允许清除已标记删除的命名空间
+            ConfigAsyncCmd::PurgeNamespace(tenant) => {
+                if self.template_namespaces.is_deleted(tenant) {
+                    Ok(())
+                } else {
+                    Err(anyhow::anyhow!("namespace {} is not deleted", tenant))
+                }
+            }
+        };
+        if let Err(err) = check {
+            return Box::pin(actix::fut::ready(Err(err)));
+        }
+        if let Err(err) = self.encrypt_fields(&mut msg) {
+            return Box::pin(actix::fut::ready(Err(err)));
+        }
+        if self.wal.is_some() {
+            return Box::pin(actix::fut::ready(self.standalone_write(msg)));
+        }
         let raft = self.raft.clone();
+        let max_versions = Some(self.max_versions_per_key);
         let history_info = if let ConfigAsyncCmd::Add { .. } = &msg {
             match self.sequence.next_state() {
                 Ok(v) => Some(v),
@@ -774,6 +2598,14 @@ impl Handler<ConfigAsyncCmd> for ConfigActor {
         } else {
             None
         };
+        let batch_history_info: Vec<(u64, Option<u64>)> =
+            if let ConfigAsyncCmd::BatchAdd { reqs, .. } = &msg {
+                reqs.iter()
+                    .filter_map(|_| self.sequence.next_state().ok())
+                    .collect()
+            } else {
+                vec![]
+            };
         let fut = async move {
             match msg {
                 ConfigAsyncCmd::Add {
@@ -782,6 +2614,9 @@ impl Handler<ConfigAsyncCmd> for ConfigActor {
                     op_user,
                     config_type,
                     desc,
+                    expected_md5,
+                    references,
+                    priority,
                 } => {
                     if let Some((history_id, history_table_id)) = history_info {
                         let req = ClientRequest::ConfigSet {
@@ -793,16 +2628,84 @@ impl Handler<ConfigAsyncCmd> for ConfigActor {
                             history_table_id,
                             op_time: now_millis_i64(),
                             op_user,
+                            expected_md5,
+                            references,
+                            priority,
+                            max_versions,
                         };
-                        Self::send_raft_request(&raft, req).await.ok();
+                        if let Ok(ClientResponse::ConfigMd5Mismatch { current_md5 }) =
+                            Self::send_raft_request(&raft, req).await
+                        {
+                            return Err(ConfigMd5MismatchError { current_md5 }.into());
+                        }
                     }
                 }
-                ConfigAsyncCmd::Delete(key) => {
+                ConfigAsyncCmd::Delete { key, op_user, .. } => {
                     let req = ClientRequest::ConfigRemove {
                         key: key.build_key(),
+                        op_time: now_millis_i64(),
+                        op_user,
                     };
                     Self::send_raft_request(&raft, req).await.ok();
                 }
+                ConfigAsyncCmd::BatchAdd {
+                    reqs,
+                    op_user,
+                    atomic,
+                } => {
+                    if batch_history_info.len() != reqs.len() {
+                        return Err(anyhow::anyhow!("config history sequence is unavailable"));
+                    }
+                    let items = reqs
+                        .into_iter()
+                        .zip(batch_history_info)
+                        .map(|(req, (history_id, history_table_id))| ConfigSetItem {
+                            key: req.config_key.build_key(),
+                            value: req.value,
+                            config_type: req.config_type,
+                            desc: req.desc,
+                            history_id,
+                            history_table_id,
+                            expected_md5: req.expected_md5,
+                            references: req.references,
+                            priority: req.priority,
+                            op_user: req.op_user,
+                        })
+                        .collect::<Vec<_>>();
+                    let size = items.len();
+                    let req = ClientRequest::ConfigBatchSet {
+                        items,
+                        op_time: now_millis_i64(),
+                        op_user,
+                        atomic,
+                        max_versions,
+                    };
+                    return match Self::send_raft_request(&raft, req).await? {
+                        ClientResponse::ConfigBatchResult(results) => {
+                            Ok(ConfigResult::BatchResult(results))
+                        }
+                        _ => Ok(ConfigResult::BatchResult(vec![
+                            ConfigSetResult::Success;
+                            size
+                        ])),
+                    };
+                }
+                ConfigAsyncCmd::Touch(items) => {
+                    let req = ClientRequest::ConfigTouch {
+                        items: items
+                            .into_iter()
+                            .map(|(key, read_ms)| (key.build_key(), read_ms))
+                            .collect(),
+                    };
+                    Self::send_raft_request(&raft, req).await?;
+                }
+                ConfigAsyncCmd::PurgeNamespace(tenant) => {
+                    let req = ClientRequest::ConfigPurgeNamespace {
+                        tenant: tenant.as_ref().to_owned(),
+                        op_time: now_millis_i64(),
+                    };
+                    Self::send_raft_request(&raft, req).await?;
+                }
             }
             Ok(ConfigResult::NULL)
         }
@@ -816,6 +2719,14 @@ impl Handler<ConfigRaftCmd> for ConfigActor {
     type Result = anyhow::Result<ConfigRaftResult>;
 
     fn handle(&mut self, msg: ConfigRaftCmd, _ctx: &mut Self::Context) -> Self::Result {
+        self.apply_raft_cmd(msg)
+    }
+}
+
+impl ConfigActor {
+    ///
+    /// 应用raft日志中的配置命令;单机模式写入与回放预写日志时同样使用
+    fn apply_raft_cmd(&mut self, msg: ConfigRaftCmd) -> anyhow::Result<ConfigRaftResult> {
         match msg {
             ConfigRaftCmd::ConfigAdd {
                 key,
@@ -826,23 +2737,109 @@ impl Handler<ConfigRaftCmd> for ConfigActor {
                 history_table_id,
                 op_time,
                 op_user,
+                expected_md5,
+                references,
+                priority,
+                max_versions,
             } => {
                 let config_key: ConfigKey = (&key as &str).into();
+                if let Err(e) = self.check_expected_md5(&config_key, &expected_md5) {
+                    return Ok(ConfigRaftResult::Md5Mismatch {
+                        current_md5: e.current_md5,
+                    });
+                }
                 self.set_config(
                     config_key,
                     value,
                     config_type.map(|v| ConfigType::new_by_value(v.as_ref()).get_value()),
                     desc,
+                    references,
+                    priority,
                     history_id,
                     history_table_id,
                     op_time,
                     op_user,
+                    max_versions.unwrap_or(DEFAULT_MAX_VERSIONS_PER_KEY),
                 )
                 .ok();
             }
-            ConfigRaftCmd::ConfigRemove { key } => {
+            ConfigRaftCmd::ConfigRemove {
+                key,
+                op_time,
+                op_user,
+            } => {
+                let config_key: ConfigKey = (&key as &str).into();
+                //兼容未记录删除时间的旧日志
+                let op_time = if op_time > 0 {
+                    op_time
+                } else {
+                    now_millis_i64()
+                };
+                self.del_config(config_key, Some((op_time, op_user))).ok();
+            }
+            ConfigRaftCmd::ConfigBatchAdd {
+                items,
+                op_time,
+                op_user,
+                atomic,
+                max_versions,
+            } => {
+                let max_versions = max_versions.unwrap_or(DEFAULT_MAX_VERSIONS_PER_KEY);
+                if atomic {
+                    return Ok(ConfigRaftResult::BatchResult(self.atomic_set_configs(
+                        items,
+                        op_time,
+                        op_user,
+                        max_versions,
+                    )?));
+                }
+                let mut results = Vec::with_capacity(items.len());
+                for item in items {
+                    let config_key: ConfigKey = (&item.key as &str).into();
+                    //批量写入时md5不一致的配置单独跳过,不影响同批其它配置
+                    if let Err(e) = self.check_expected_md5(&config_key, &item.expected_md5) {
+                        results.push(ConfigSetResult::Md5Mismatch {
+                            current_md5: e.current_md5,
+                        });
+                        continue;
+                    }
+                    let result = self.set_config(
+                        config_key,
+                        item.value,
+                        item.config_type
+                            .map(|v| ConfigType::new_by_value(v.as_ref()).get_value()),
+                        item.desc,
+                        item.references,
+                        item.priority,
+                        item.history_id,
+                        item.history_table_id,
+                        op_time,
+                        item.op_user.or(op_user.clone()),
+                        max_versions,
+                    );
+                    match result {
+                        Ok(_) => results.push(ConfigSetResult::Success),
+                        Err(err) => {
+                            log::error!("batch set config {} error: {}", &item.key, err);
+                            results.push(ConfigSetResult::Aborted);
+                        }
+                    }
+                }
+                return Ok(ConfigRaftResult::BatchResult(results));
+            }
+            ConfigRaftCmd::ConfigArchive {
+                key,
+                md5,
+                archive_uri,
+            } => {
                 let config_key: ConfigKey = (&key as &str).into();
-                self.del_config(config_key).ok();
+                self.archive_config(config_key, md5, archive_uri);
+            }
+            ConfigRaftCmd::ConfigTouch { items } => {
+                self.touch_configs(items);
+            }
+            ConfigRaftCmd::ConfigPurgeNamespace { tenant, op_time } => {
+                self.purge_namespace_configs(&tenant, op_time);
             }
             ConfigRaftCmd::ApplySnaphot => {
                 //self.load_config();
@@ -851,3 +2848,230 @@ impl Handler<ConfigRaftCmd> for ConfigActor {
         Ok(ConfigRaftResult::None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_key_serde() {
+        let key = ConfigKey::new("app.yaml", "DEFAULT_GROUP", "dev");
+        let v = serde_json::to_string(&key).unwrap();
+        assert_eq!(v, "\"dev##DEFAULT_GROUP##app.yaml\"");
+        let key2: ConfigKey = serde_json::from_str(&v).unwrap();
+        assert_eq!(key, key2);
+
+        let mut map = HashMap::new();
+        map.insert(ConfigKey::new("app.yaml", "DEFAULT_GROUP", ""), 1);
+        let v = serde_json::to_string(&map).unwrap();
+        assert_eq!(v, "{\"##DEFAULT_GROUP##app.yaml\":1}");
+        let map2: HashMap<ConfigKey, i32> = serde_json::from_str(&v).unwrap();
+        assert_eq!(map, map2);
+        assert!(ConfigKey::from_str("invalid").is_err());
+    }
+
+    #[test]
+    fn wildcard_listener_match() {
+        let item = ListenerItem::new(
+            ConfigKey::new("*", "backend-services", "dev"),
+            Default::default(),
+        );
+        let wildcard = item.to_wildcard().unwrap();
+        assert!(wildcard.is_match(&ConfigKey::new("a", "backend-services", "dev")));
+        assert!(!wildcard.is_match(&ConfigKey::new("a", "backend-services", "")));
+        assert!(!wildcard.is_match(&ConfigKey::new("a", "DEFAULT_GROUP", "dev")));
+
+        let prefix =
+            WildcardListenerItem::new(Arc::new("backend-*".to_owned()), Default::default());
+        assert!(prefix.is_match(&ConfigKey::new("a", "backend-services", "")));
+        assert!(!prefix.is_match(&ConfigKey::new("a", "frontend", "")));
+
+        let item = ListenerItem::new(
+            ConfigKey::new("a", "backend-services", ""),
+            Default::default(),
+        );
+        assert!(item.to_wildcard().is_none());
+    }
+
+    #[test]
+    fn prune_config_histories() {
+        let mut value = ConfigValue::init(Arc::new("v0".to_owned()), 0, 0, None, None);
+        for i in 1..10u64 {
+            value.update_value(Arc::new(format!("v{}", i)), i, i as i64, None, None, 3);
+        }
+        assert_eq!(value.histories.len(), 3);
+        assert_eq!(value.histories[0].id, 7);
+        assert_eq!(value.histories[2].content.as_str(), "v9");
+        value.prune_histories(1);
+        assert_eq!(value.histories.len(), 1);
+        assert_eq!(value.histories[0].id, 9);
+    }
+
+    #[tokio::test]
+    async fn apply_replicated_max_versions() {
+        let mut actor = ConfigActor::new();
+        //本节点设置不影响应用结果
+        actor.max_versions_per_key = 1;
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        for i in 1..5u64 {
+            actor
+                .apply_raft_cmd(ConfigRaftCmd::ConfigAdd {
+                    key: key.build_key(),
+                    value: Arc::new(format!("v{}", i)),
+                    config_type: None,
+                    desc: None,
+                    history_id: i,
+                    history_table_id: None,
+                    op_time: i as i64,
+                    op_user: None,
+                    expected_md5: None,
+                    references: None,
+                    priority: None,
+                    max_versions: Some(3),
+                })
+                .unwrap();
+        }
+        assert_eq!(actor.cache.get(&key).unwrap().histories.len(), 3);
+    }
+
+    #[test]
+    fn config_history_at() {
+        let mut value = ConfigValue::init(Arc::new("v0".to_owned()), 0, 100, None, None);
+        value.update_value(Arc::new("v1".to_owned()), 1, 200, None, None, 10);
+        assert!(value.history_at(99).is_none());
+        assert_eq!(value.history_at(100).unwrap().content.as_str(), "v0");
+        assert_eq!(value.history_at(199).unwrap().content.as_str(), "v0");
+        assert_eq!(value.history_at(300).unwrap().content.as_str(), "v1");
+    }
+
+    #[test]
+    fn config_change_seq() {
+        let mut value = ConfigValue::new(Arc::new("v0".to_owned()));
+        assert_eq!(value.change_seq(), 0);
+        value.update_value(Arc::new("v1".to_owned()), 5, 100, None, None, 10);
+        value.update_value(Arc::new("v2".to_owned()), 8, 200, None, None, 10);
+        assert_eq!(value.change_seq(), 8);
+    }
+
+    #[test]
+    fn group_default_effective_value() {
+        let mut actor = ConfigActor::new();
+        let default_key = ConfigKey::new(GROUP_DEFAULT_DATA_ID, "DEFAULT_GROUP", "");
+        let missing = ConfigKey::new("missing", "DEFAULT_GROUP", "");
+        actor.cache.insert(
+            default_key.clone(),
+            ConfigValue::new(Arc::new("d".to_owned())),
+        );
+        assert!(actor.get_effective_value(&missing).is_none());
+        actor.group_default_enabled = true;
+        assert_eq!(
+            actor
+                .get_effective_value(&missing)
+                .unwrap()
+                .content
+                .as_str(),
+            "d"
+        );
+        assert!(actor.use_group_default(&missing, &default_key));
+        assert!(!actor.use_group_default(&default_key, &default_key));
+        assert!(
+            !actor.use_group_default(&ConfigKey::new("missing", "OTHER_GROUP", ""), &default_key)
+        );
+    }
+
+    #[test]
+    fn ns_ref_dependents() {
+        let mut actor = ConfigActor::new();
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "shared");
+        let b = ConfigKey::new("b", "DEFAULT_GROUP", "");
+        let c = ConfigKey::new("c", "DEFAULT_GROUP", "");
+        actor.update_ns_refs(&b, Some("x=${ns:shared##DEFAULT_GROUP##a}"));
+        actor.update_ns_refs(&c, Some("y=${ns:public##DEFAULT_GROUP##b}"));
+        assert_eq!(actor.get_ns_ref_dependents(&a), vec![b.clone(), c.clone()]);
+        actor.update_ns_refs(&b, Some("x=1"));
+        assert!(actor.get_ns_ref_dependents(&a).is_empty());
+        assert_eq!(actor.get_ns_ref_dependents(&b), vec![c.clone()]);
+        actor.update_ns_refs(&c, None);
+        assert!(actor.ns_ref_dependents.is_empty());
+    }
+
+    #[test]
+    fn reverse_references() {
+        let mut actor = ConfigActor::new();
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let b = ConfigKey::new("b", "DEFAULT_GROUP", "");
+        let c = ConfigKey::new("c", "DEFAULT_GROUP", "");
+        actor.update_reverse_references(&b, &[], std::slice::from_ref(&a));
+        actor.update_reverse_references(&c, &[], std::slice::from_ref(&a));
+        assert_eq!(actor.get_dependents(&a), vec![b.clone(), c.clone()]);
+        //依赖变更后从原依赖的反向索引中移除
+        actor.update_reverse_references(&c, std::slice::from_ref(&a), std::slice::from_ref(&b));
+        assert_eq!(actor.get_dependents(&a), vec![b.clone()]);
+        assert_eq!(actor.get_dependents(&b), vec![c]);
+        actor.update_reverse_references(&b, std::slice::from_ref(&a), &[]);
+        assert!(actor.get_dependents(&a).is_empty());
+        assert!(!actor.reverse_references.contains_key(&a));
+    }
+
+    #[test]
+    fn touch_and_archive_candidates() {
+        let mut actor = ConfigActor::new();
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        actor.cache.insert(
+            a.clone(),
+            ConfigValue::init(Arc::new("a=1".to_owned()), 1, 100, None, None),
+        );
+        assert_eq!(actor.get_archive_candidates(200, 10).len(), 1);
+        //读取时间只会推后
+        actor.touch_configs(vec![(a.build_key(), 300)]);
+        actor.touch_configs(vec![(a.build_key(), 250)]);
+        assert_eq!(actor.cache.get(&a).unwrap().last_read_ms, 300);
+        assert!(actor.get_archive_candidates(200, 10).is_empty());
+    }
+
+    #[tokio::test]
+    async fn atomic_set_configs() {
+        let mut actor = ConfigActor::new();
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let b = ConfigKey::new("b", "DEFAULT_GROUP", "");
+        let item = |key: &ConfigKey, value: &str, expected_md5: Option<&str>| ConfigSetItem {
+            key: key.build_key(),
+            value: Arc::new(value.to_owned()),
+            config_type: None,
+            desc: None,
+            history_id: 1,
+            history_table_id: None,
+            expected_md5: expected_md5.map(|v| Arc::new(v.to_owned())),
+            references: None,
+            priority: None,
+            op_user: None,
+        };
+        //任一配置md5不一致时整批不写入
+        let results = actor
+            .atomic_set_configs(
+                vec![item(&a, "a=1", None), item(&b, "b=1", Some("x"))],
+                1,
+                None,
+                DEFAULT_MAX_VERSIONS_PER_KEY,
+            )
+            .unwrap();
+        assert!(matches!(results[0], ConfigSetResult::Aborted));
+        assert!(matches!(results[1], ConfigSetResult::Md5Mismatch { .. }));
+        assert!(!actor.cache.contains_key(&a));
+
+        let results = actor
+            .atomic_set_configs(
+                vec![item(&a, "a=1", None), item(&b, "b=1", None)],
+                1,
+                None,
+                DEFAULT_MAX_VERSIONS_PER_KEY,
+            )
+            .unwrap();
+        assert!(results
+            .iter()
+            .all(|v| matches!(v, ConfigSetResult::Success)));
+        assert!(actor.deferred_changes.is_none());
+        assert_eq!(actor.read_view.get(&a).unwrap().content.as_str(), "a=1");
+        assert_eq!(actor.read_view.get(&b).unwrap().content.as_str(), "b=1");
+    }
+}
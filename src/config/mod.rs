@@ -1,8 +1,56 @@
+pub mod config_access_audit;
+pub mod config_access_log;
+pub mod config_alias;
+pub mod config_api_version;
+pub mod config_archive;
+pub mod config_batch;
+pub mod config_consumer;
+pub mod config_content_store;
 pub mod config_db;
+pub mod config_diff;
+pub mod config_event;
+pub mod config_feature_flag;
+pub mod config_federation;
+pub mod config_feed;
+pub mod config_field_encrypt;
+pub mod config_file_watch;
+pub mod config_git_sync;
+pub mod config_health;
+pub mod config_history_index;
+pub mod config_idempotency;
 pub mod config_index;
+pub mod config_json_path;
+pub mod config_kafka;
+pub mod config_kms;
+pub mod config_listener_ack;
+pub mod config_namespace_merge;
+pub mod config_namespace_stats;
+pub mod config_namespace_template;
+pub mod config_notify_debounce;
+pub mod config_notify_dedup;
+pub mod config_ns_ref;
+pub mod config_patch;
+pub mod config_pending_changes;
+pub mod config_poll_backoff;
+pub mod config_propagation;
+pub mod config_propagation_sla;
+pub mod config_read_cache;
+pub mod config_read_limit;
+pub mod config_read_view;
+pub mod config_remote;
+pub mod config_render;
+pub mod config_search;
 pub mod config_sled;
+pub mod config_stats;
+pub mod config_stream;
 pub mod config_subscribe;
+pub mod config_template_create;
+pub mod config_transcode;
 pub mod config_type;
+pub mod config_validate;
+pub mod config_wal;
+pub mod config_watch;
+pub mod config_webhook;
 pub mod core;
 pub mod dal;
 pub mod model;
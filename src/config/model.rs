@@ -1,5 +1,7 @@
+use crate::config::config_notify_debounce::ConfigPriority;
 use crate::config::config_type::ConfigType;
 use crate::config::core::{ConfigHistoryInfoDto, ConfigKey, ConfigValue};
+use crate::raft::store::ConfigSetItem;
 use crate::utils::get_md5;
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -17,9 +19,34 @@ pub enum ConfigRaftCmd {
         history_table_id: Option<u64>,
         op_time: i64,
         op_user: Option<Arc<String>>,
+        expected_md5: Option<Arc<String>>,
+        references: Option<Vec<ConfigKey>>,
+        priority: Option<ConfigPriority>,
+        max_versions: Option<usize>,
     },
     ConfigRemove {
         key: String,
+        op_time: i64,
+        op_user: Option<Arc<String>>,
+    },
+    ConfigBatchAdd {
+        items: Vec<ConfigSetItem>,
+        op_time: i64,
+        op_user: Option<Arc<String>>,
+        atomic: bool,
+        max_versions: Option<usize>,
+    },
+    ConfigArchive {
+        key: String,
+        md5: Arc<String>,
+        archive_uri: Arc<String>,
+    },
+    ConfigTouch {
+        items: Vec<(String, i64)>,
+    },
+    ConfigPurgeNamespace {
+        tenant: String,
+        op_time: i64,
     },
     ApplySnaphot,
 }
@@ -29,9 +56,51 @@ pub enum ConfigRaftResult {
         data: Vec<(ConfigKey, Arc<String>)>,
         history_table_id: u64,
     },
+    Md5Mismatch {
+        current_md5: Option<Arc<String>>,
+    },
+    BatchResult(Vec<ConfigSetResult>),
     None,
 }
 
+///
+/// 批量写入时单个配置的写入结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ConfigSetResult {
+    Success,
+    Md5Mismatch { current_md5: Option<Arc<String>> },
+    //原子写入时因同批其它配置写入失败而未写入
+    Aborted,
+}
+
+impl ConfigSetResult {
+    pub fn into_result(self) -> anyhow::Result<()> {
+        match self {
+            ConfigSetResult::Success => Ok(()),
+            ConfigSetResult::Md5Mismatch { current_md5 } => {
+                Err(ConfigMd5MismatchError { current_md5 }.into())
+            }
+            ConfigSetResult::Aborted => Err(anyhow::anyhow!("config batch write aborted")),
+        }
+    }
+}
+
+///
+/// 配置写入时md5与期望值不一致
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("config md5 mismatch, current md5: {current_md5:?}")]
+pub struct ConfigMd5MismatchError {
+    pub current_md5: Option<Arc<String>>,
+}
+
+///
+/// 删除被其它配置依赖的配置且未指定强制删除
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("config is referenced by {} configs", dependents.len())]
+pub struct ConfigInUseError {
+    pub dependents: Vec<ConfigKey>,
+}
+
 #[derive(Clone)]
 pub struct HistoryItem {
     pub id: u64,
@@ -96,6 +165,19 @@ pub struct ConfigValueDO {
     pub config_type: Option<String>,
     #[prost(string, optional, tag = "4")]
     pub desc: Option<String>,
+    #[prost(string, repeated, tag = "5")]
+    pub references: Vec<String>,
+    //已归档时content为空,md5保存归档内容的md5
+    #[prost(string, optional, tag = "6")]
+    pub archive_uri: Option<String>,
+    #[prost(string, optional, tag = "7")]
+    pub md5: Option<String>,
+    #[prost(string, optional, tag = "8")]
+    pub priority: Option<String>,
+    #[prost(int64, optional, tag = "9")]
+    pub last_read_time: Option<i64>,
+    #[prost(int64, optional, tag = "10")]
+    pub last_modified: Option<i64>,
 }
 
 impl ConfigValueDO {
@@ -115,19 +197,42 @@ impl ConfigValueDO {
 
 impl From<ConfigValue> for ConfigValueDO {
     fn from(value: ConfigValue) -> Self {
+        let archived = value.is_archived();
         Self {
-            content: Some(value.content.as_ref().to_owned()),
+            content: if archived {
+                None
+            } else {
+                Some(value.content.as_ref().to_owned())
+            },
             histories: value.histories.into_iter().map(|e| e.into()).collect(),
             config_type: value.config_type.map(|e| e.as_ref().to_owned()),
             desc: value.desc.map(|e| e.as_ref().to_owned()),
+            references: value.references.iter().map(|e| e.to_string()).collect(),
+            md5: if archived {
+                Some(value.md5.as_ref().to_owned())
+            } else {
+                None
+            },
+            archive_uri: value.archive_uri.map(|e| e.as_ref().to_owned()),
+            priority: value.priority.map(|e| e.as_str().to_owned()),
+            last_read_time: Some(value.last_read_ms).filter(|v| *v > 0),
+            last_modified: Some(value.last_modified).filter(|v| *v > 0),
         }
     }
 }
 
 impl From<ConfigValueDO> for ConfigValue {
     fn from(value: ConfigValueDO) -> Self {
+        //旧版本数据没有写入时间,取最近的历史版本时间
+        let last_modified = value
+            .last_modified
+            .or_else(|| value.histories.last().and_then(|v| v.last_time))
+            .unwrap_or_default();
         let content = value.content.unwrap_or_default();
-        let md5 = Arc::new(get_md5(&content));
+        let md5 = match value.md5 {
+            Some(md5) if value.archive_uri.is_some() => Arc::new(md5),
+            _ => Arc::new(get_md5(&content)),
+        };
         Self {
             content: Arc::new(content),
             md5,
@@ -137,6 +242,18 @@ impl From<ConfigValueDO> for ConfigValue {
                 .config_type
                 .map(|v| ConfigType::new_by_value(&v).get_value()),
             desc: value.desc.map(Arc::new),
+            references: value
+                .references
+                .iter()
+                .filter_map(|e| e.parse().ok())
+                .collect(),
+            priority: value
+                .priority
+                .and_then(|v| ConfigPriority::new_by_value(&v)),
+            detected_type: None,
+            archive_uri: value.archive_uri.map(Arc::new),
+            last_read_ms: value.last_read_time.unwrap_or_default(),
+            last_modified,
         }
     }
 }
@@ -1,10 +1,55 @@
 pub mod param_utils {
+    use actix_web::HttpResponse;
     use anyhow::Ok;
+    use regex::Regex;
 
     use super::property_util;
 
     const VALID_CHARS: [char; 4] = ['_', '-', '.', ':'];
     const TENANT_MAX_LEN: usize = 128;
+    const KEY_MAX_LEN: usize = 256;
+
+    ///
+    /// dataId、group中不允许出现的路径穿越及控制字符
+    pub const CHECK_DATA_ID_REGEX: &str = r"[/\\%\x00]|\.\.";
+
+    lazy_static::lazy_static! {
+        static ref ILLEGAL_KEY_REGEX: Regex = Regex::new(CHECK_DATA_ID_REGEX).unwrap();
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("invalid {name} : {reason}")]
+    pub struct InvalidKeyError {
+        pub name: &'static str,
+        pub reason: &'static str,
+    }
+
+    ///
+    /// 校验dataId、group;非法时返回InvalidKeyError
+    pub fn check_key(name: &'static str, value: &str) -> Result<(), InvalidKeyError> {
+        let reason = if value.trim().is_empty() {
+            "is empty"
+        } else if value.len() > KEY_MAX_LEN {
+            "is longer than 256"
+        } else if ILLEGAL_KEY_REGEX.is_match(value) || !is_valid(value.trim()) {
+            "contains illegal characters"
+        } else {
+            return Result::Ok(());
+        };
+        Err(InvalidKeyError { name, reason })
+    }
+
+    ///
+    /// dataId、group非法返回400,其它参数错误保持500
+    pub fn check_param_error_response(err: anyhow::Error) -> HttpResponse {
+        match err.downcast_ref::<InvalidKeyError>() {
+            Some(e) => HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "INVALID_DATA_ID",
+                "reason": e.reason,
+            })),
+            None => HttpResponse::InternalServerError().body(err.to_string()),
+        }
+    }
 
     pub fn check_tenant(tenant: &Option<String>) -> anyhow::Result<()> {
         if let Some(t) = tenant {
@@ -25,22 +70,8 @@ pub mod param_utils {
         datum_id: &Option<String>,
         content: &Option<String>,
     ) -> anyhow::Result<()> {
-        match data_id {
-            Some(data_id) => {
-                if data_id.is_empty() || !is_valid(data_id.trim()) {
-                    return Err(anyhow::anyhow!("invalid dataId : {}", data_id));
-                }
-            }
-            None => return Err(anyhow::anyhow!("invalid dataId : ")),
-        }
-        match group {
-            Some(group) => {
-                if group.is_empty() || !is_valid(group) {
-                    return Err(anyhow::anyhow!("invalid group : {}", group));
-                }
-            }
-            None => return Err(anyhow::anyhow!("invalid group : ")),
-        }
+        check_key("dataId", data_id.as_deref().unwrap_or_default())?;
+        check_key("group", group.as_deref().unwrap_or_default())?;
         match datum_id {
             Some(datum_id) => {
                 if datum_id.is_empty() || !is_valid(datum_id) {
@@ -81,6 +112,53 @@ pub mod param_utils {
         }
         true
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const SAMPLE_CHARS: [char; 14] = [
+            'a', 'Z', '0', '_', '-', '.', ':', '/', '\\', '%', '\0', ' ', '中', '\n',
+        ];
+
+        fn next_rand(seed: &mut u64) -> u64 {
+            *seed ^= *seed << 13;
+            *seed ^= *seed >> 7;
+            *seed ^= *seed << 17;
+            *seed
+        }
+
+        #[test]
+        fn check_key_reject_illegal() {
+            assert!(check_key("dataId", "app.yaml").is_ok());
+            assert!(check_key("dataId", "a:b_c-d").is_ok());
+            for v in ["../etc", "a/b", "a\\b", "a%2e", "a\0", "", "a..b"] {
+                let err = check_key("dataId", v).unwrap_err();
+                assert_eq!(err.name, "dataId");
+            }
+            assert!(check_key("dataId", &"a".repeat(256)).is_ok());
+            assert!(check_key("dataId", &"a".repeat(257)).is_err());
+        }
+
+        #[test]
+        fn check_key_property() {
+            let mut seed = 0x9e3779b97f4a7c15u64;
+            for _ in 0..20000 {
+                let len = (next_rand(&mut seed) % 300) as usize;
+                let v: String = (0..len)
+                    .map(|_| {
+                        SAMPLE_CHARS[(next_rand(&mut seed) % SAMPLE_CHARS.len() as u64) as usize]
+                    })
+                    .collect();
+                if check_key("dataId", &v).is_ok() {
+                    assert!(!v.trim().is_empty());
+                    assert!(v.len() <= KEY_MAX_LEN);
+                    assert!(!v.contains(['/', '\\', '%', '\0']));
+                    assert!(!v.contains(".."));
+                }
+            }
+        }
+    }
 }
 pub mod property_util {
     use crate::common::AppSysConfig;
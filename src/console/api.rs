@@ -1,7 +1,8 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::Arc;
 
 use actix::prelude::*;
-use actix_web::{http::header, web, HttpResponse, Responder};
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Responder};
 
 use crate::common::appdata::AppShareData;
 use crate::config::core::ConfigActor;
@@ -14,9 +15,23 @@ use crate::openapi::naming::service::{query_service, remove_service, update_serv
 use super::cluster_api::query_cluster_info;
 use super::config_api::query_config_list;
 use super::{
-    config_api::{download_config, import_config, query_history_config_page},
+    config_api::{
+        add_config_alias, apply_config_change, approve_config_change, bulk_patch_config,
+        config_change_feed, create_config_change, delete_orphan_configs, detect_config_types,
+        download_config, download_config_snapshot, export_namespace_configs,
+        federation_consistency_check, get_config_change, import_config, import_config_from_url,
+        migrate_config_format, preview_config_diff, query_config_changes, query_config_consumers,
+        query_config_health, query_config_propagation_trace, query_config_references,
+        query_history_config_count, query_history_config_page, query_namespace_config_md5s,
+        query_orphan_configs, reject_config_change, search_config, search_history_config,
+        submit_config_change, suggest_config, trace_config_propagation,
+    },
     connection_api::query_grpc_connection,
-    model::{ConsoleResult, NamespaceInfo},
+    model::{parse_label, ConsoleResult, NamespaceInfo, NamespaceQueryParam},
+    namespace_api::{
+        copy_namespace, export_namespace, import_namespace, merge_namespace, query_namespace_stats,
+        resolve_namespace_merge,
+    },
     naming_api::{query_grpc_client_instance_count, query_ops_instances_list},
     NamespaceUtils,
 };
@@ -24,9 +39,36 @@ use super::{login_api, user_api};
 
 use super::v2;
 
-pub async fn query_namespace_list(config_addr: web::Data<Addr<ConfigActor>>) -> impl Responder {
+pub async fn query_namespace_list(
+    req: HttpRequest,
+    param: web::Query<NamespaceQueryParam>,
+    config_addr: web::Data<Addr<ConfigActor>>,
+) -> impl Responder {
     //HttpResponse::InternalServerError().body("system error")
-    let namespaces = NamespaceUtils::get_namespaces(&config_addr).await;
+    let mut namespaces = if param.include_deleted.unwrap_or(false) {
+        NamespaceUtils::get_namespaces_with_deleted(&config_addr).await
+    } else {
+        NamespaceUtils::get_namespaces(&config_addr).await
+    };
+    if let Some(is_template) = param.is_template {
+        namespaces.retain(|v| v.is_template.unwrap_or(false) == is_template);
+    }
+    if param.managed_by.is_some() || param.managed_by_ref.is_some() {
+        namespaces.retain(|v| {
+            v.match_managed(param.managed_by.as_deref(), param.managed_by_ref.as_deref())
+        });
+    }
+    //多个label参数需同时满足
+    let labels: Vec<(String, String)> =
+        serde_urlencoded::from_str::<Vec<(String, String)>>(req.query_string())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(k, _)| k == "label")
+            .filter_map(|(_, v)| parse_label(&v))
+            .collect();
+    if !labels.is_empty() {
+        namespaces.retain(|v| v.match_labels(&labels));
+    }
     let result = ConsoleResult::success(namespaces);
     let v = serde_json::to_string(&result).unwrap();
     HttpResponse::Ok()
@@ -34,6 +76,22 @@ pub async fn query_namespace_list(config_addr: web::Data<Addr<ConfigActor>>) ->
         .body(v)
 }
 
+///
+/// 返回所有标签名及其取值,用于输入提示
+pub async fn query_namespace_labels(config_addr: web::Data<Addr<ConfigActor>>) -> impl Responder {
+    let mut labels: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for info in NamespaceUtils::get_namespaces(&config_addr).await {
+        for (k, v) in info.labels.iter().flatten() {
+            labels.entry(k.clone()).or_default().insert(v.clone());
+        }
+    }
+    let result = ConsoleResult::success(labels);
+    let v = serde_json::to_string(&result).unwrap();
+    HttpResponse::Ok()
+        .insert_header(header::ContentType(mime::APPLICATION_JSON))
+        .body(v)
+}
+
 pub async fn add_namespace(
     param: web::Form<NamespaceInfo>,
     app_data: web::Data<Arc<AppShareData>>,
@@ -78,11 +136,38 @@ pub async fn update_namespace(
     }
 }
 
-pub async fn remove_namespace(
+pub async fn restore_namespace(
     param: web::Form<NamespaceInfo>,
     app_data: web::Data<Arc<AppShareData>>,
 ) -> impl Responder {
-    match NamespaceUtils::remove_namespace(&app_data, param.0.namespace_id).await {
+    match NamespaceUtils::restore_namespace(&app_data, param.0.namespace_id).await {
+        Ok(_) => HttpResponse::Ok().json(ConsoleResult::success(true)),
+        Err(e) => HttpResponse::Ok().json(ConsoleResult::<()>::error(e.to_string())),
+    }
+}
+
+///
+/// 带`managedBy`查询参数时删除该工具管理的命名空间,否则按表单中的命名空间id删除
+pub async fn remove_namespace(
+    query: web::Query<NamespaceQueryParam>,
+    param: Option<web::Form<NamespaceInfo>>,
+    app_data: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    if let Some(managed_by) = &query.managed_by {
+        let managed_by_ref = query.managed_by_ref.as_deref().unwrap_or_default();
+        return match NamespaceUtils::remove_managed_namespaces(
+            &app_data,
+            managed_by,
+            managed_by_ref,
+        )
+        .await
+        {
+            Ok(count) => HttpResponse::Ok().json(ConsoleResult::success(count)),
+            Err(e) => HttpResponse::Ok().json(ConsoleResult::<()>::error(e.to_string())),
+        };
+    }
+    let namespace_id = param.and_then(|v| v.0.namespace_id);
+    match NamespaceUtils::remove_namespace(&app_data, namespace_id).await {
         Ok(_) => {
             let result = ConsoleResult::success(true);
             let v = serde_json::to_string(&result).unwrap();
@@ -110,7 +195,93 @@ pub fn console_api_config(config: &mut web::ServiceConfig) {
                     .route(web::put().to(update_namespace))
                     .route(web::delete().to(remove_namespace)),
             )
+            .service(web::resource("/namespaces/restore").route(web::post().to(restore_namespace)))
+            .service(
+                web::resource("/namespaces/labels").route(web::get().to(query_namespace_labels)),
+            )
+            .service(web::resource("/namespaces/export").route(web::post().to(export_namespace)))
+            .service(web::resource("/namespaces/import").route(web::post().to(import_namespace)))
+            .service(web::resource("/namespaces/copy").route(web::post().to(copy_namespace)))
+            .service(web::resource("/namespaces/merge").route(web::post().to(merge_namespace)))
+            .service(
+                web::resource("/namespaces/merge/{id}/resolve")
+                    .route(web::post().to(resolve_namespace_merge)),
+            )
+            .service(web::resource("/namespaces/stats").route(web::get().to(query_namespace_stats)))
             .service(web::resource("/configs").route(web::get().to(query_config_list)))
+            .service(
+                web::resource("/configs/detect-types").route(web::get().to(detect_config_types)),
+            )
+            .service(web::resource("/configs/search").route(web::get().to(search_config)))
+            .service(web::resource("/configs/suggest").route(web::get().to(suggest_config)))
+            .service(web::resource("/configs/aliases").route(web::post().to(add_config_alias)))
+            .service(
+                web::resource("/configs/preview-diff").route(web::post().to(preview_config_diff)),
+            )
+            .service(web::resource("/configs/bulk-patch").route(web::patch().to(bulk_patch_config)))
+            .service(
+                web::resource("/configs/migrate-format")
+                    .route(web::post().to(migrate_config_format)),
+            )
+            .service(web::resource("/configs/orphans").route(web::get().to(query_orphan_configs)))
+            .service(
+                web::resource("/configs/orphans/delete")
+                    .route(web::post().to(delete_orphan_configs)),
+            )
+            .service(web::resource("/configs/health").route(web::get().to(query_config_health)))
+            .service(web::resource("/configs/feed.atom").route(web::get().to(config_change_feed)))
+            .service(
+                web::resource("/configs/export").route(web::get().to(export_namespace_configs)),
+            )
+            .service(
+                web::resource("/configs/md5s").route(web::get().to(query_namespace_config_md5s)),
+            )
+            .service(
+                web::resource("/federation/consistency-check")
+                    .route(web::get().to(federation_consistency_check)),
+            )
+            .service(
+                web::resource("/changes")
+                    .route(web::get().to(query_config_changes))
+                    .route(web::post().to(create_config_change)),
+            )
+            .service(web::resource("/changes/{id}").route(web::get().to(get_config_change)))
+            .service(
+                web::resource("/changes/{id}/submit").route(web::put().to(submit_config_change)),
+            )
+            .service(
+                web::resource("/changes/{id}/reject").route(web::put().to(reject_config_change)),
+            )
+            .service(
+                web::resource("/changes/{id}/approve").route(web::put().to(approve_config_change)),
+            )
+            .service(web::resource("/changes/{id}/apply").route(web::put().to(apply_config_change)))
+            .service(web::resource("/configs/history").route(web::get().to(search_history_config)))
+            .service(
+                web::resource("/configs/consumers").route(web::get().to(query_config_consumers)),
+            )
+            .service(
+                web::resource("/configs/trace-propagation")
+                    .route(web::post().to(trace_config_propagation)),
+            )
+            .service(
+                web::resource("/configs/trace-propagation/{id}")
+                    .route(web::get().to(query_config_propagation_trace)),
+            )
+            .service(
+                web::resource("/configs/history/count")
+                    .route(web::get().to(query_history_config_count)),
+            )
+            .service(
+                web::resource("/configs/references").route(web::get().to(query_config_references)),
+            )
+            .service(
+                web::resource("/configs/history/snapshot")
+                    .route(web::get().to(download_config_snapshot)),
+            )
+            .service(
+                web::resource("/configs/import-url").route(web::post().to(import_config_from_url)),
+            )
             .service(web::resource("/config/import").route(web::post().to(import_config)))
             .service(web::resource("/config/download").route(web::get().to(download_config)))
             .service(
@@ -177,7 +348,15 @@ pub fn console_api_config_new(config: &mut web::ServiceConfig) {
                     .route(web::put().to(update_namespace))
                     .route(web::delete().to(remove_namespace)),
             )
+            .service(web::resource("/namespaces/restore").route(web::post().to(restore_namespace)))
+            .service(
+                web::resource("/namespaces/labels").route(web::get().to(query_namespace_labels)),
+            )
             .service(web::resource("/configs").route(web::get().to(query_config_list)))
+            .service(web::resource("/configs/suggest").route(web::get().to(suggest_config)))
+            .service(
+                web::resource("/configs/preview-diff").route(web::post().to(preview_config_diff)),
+            )
             .service(web::resource("/config/import").route(web::post().to(import_config)))
             .service(web::resource("/config/download").route(web::get().to(download_config)))
             .service(
@@ -255,10 +434,22 @@ pub fn console_api_config_v2(config: &mut web::ServiceConfig) {
                 web::resource("/cluster/cluster_node_list")
                     .route(web::get().to(v2::cluster_api::query_cluster_info)),
             )
+            .service(
+                web::resource("/admin/config")
+                    .route(web::get().to(v2::cluster_api::query_server_config)),
+            )
+            .service(
+                web::resource("/audit/export")
+                    .route(web::get().to(v2::config_api::export_config_access_audit)),
+            )
             .service(
                 web::resource("/config/import")
                     .route(web::post().to(v2::config_api::import_config)),
             )
+            .service(
+                web::resource("/config/import-url")
+                    .route(web::post().to(v2::config_api::import_config_from_url)),
+            )
             .service(
                 web::resource("/config/download")
                     .route(web::get().to(v2::config_api::download_config)),
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use actix_web::{http::header, web, HttpResponse, Responder};
 
 use crate::common::appdata::AppShareData;
+use crate::common::thread_pool_stats::ThreadPoolInfo;
 
 use super::model::{cluster_model::ClusterNodeInfo, ConsoleResult};
 
@@ -28,3 +29,25 @@ pub async fn query_cluster_info(app: web::Data<Arc<AppShareData>>) -> impl Respo
         .insert_header(header::ContentType(mime::APPLICATION_JSON))
         .body(v)
 }
+
+///
+/// http工作线程的负载及ConfigActor待处理消息数
+pub async fn query_thread_pool(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    //与actix-web未设置工作线程数时的默认值一致
+    let worker_threads = app.sys_config.http_workers.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|v| v.get())
+            .unwrap_or(1)
+    });
+    let stats = &app.http_request_stats;
+    let cmds = &app.config_cmd_pending;
+    HttpResponse::Ok().json(ThreadPoolInfo {
+        worker_threads,
+        pending_tasks: stats.in_flight(),
+        completed_tasks: stats.completed(),
+        avg_task_duration_ms: stats.avg_duration_ms(),
+        config_cmd_pending: cmds.pending(),
+        config_cmd_pending_warn_threshold: cmds.warn_threshold(),
+        config_cmd_pending_warn: cmds.is_over_threshold(),
+    })
+}
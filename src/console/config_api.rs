@@ -1,5 +1,7 @@
 #![allow(unused_imports)]
 
+use std::collections::HashSet;
+use std::convert::TryFrom;
 use std::fs::File;
 use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::sync::Arc;
@@ -8,27 +10,82 @@ use actix_multipart::form::tempfile::TempFile;
 use actix_multipart::form::text::Text;
 use actix_multipart::form::MultipartForm;
 use actix_multipart::Multipart;
-use actix_web::{http::header, web, Error, HttpRequest, HttpResponse, Responder};
+use actix_web::{http::header, web, Error, HttpMessage, HttpRequest, HttpResponse, Responder};
 use zip::write::FileOptions;
 
 use crate::common::appdata::AppShareData;
+use crate::common::constant::HTTP_METHOD_GET;
+use crate::common::crypto_utils::constant_time_eq;
+use crate::common::model::UserSession;
+use crate::common::web_utils::{has_config_read_permission, has_config_write_permission};
+use crate::config::config_access_audit::{
+    export_access_log_csv, parse_audit_time, AuditExportFilter,
+};
+use crate::config::config_alias::{add_alias, parse_aliases, ConfigAlias, CONFIG_ALIAS_KEY};
+use crate::config::config_diff::{diff_lines, render_diff_html};
+use crate::config::config_federation::{
+    apply_replication_filter, build_consistency_matrix, fetch_peer_md5s, new_peer_client,
+    to_md5_map, ConfigExportItem, FEDERATION_TOKEN_HEADER,
+};
+use crate::config::config_feed::{
+    build_atom_feed, feed_updated_time, CONFIG_FEED_MAX_ENTRIES, CONFIG_FEED_TOKEN_HEADER,
+};
+use crate::config::config_health::{cluster_listener_counts, ConfigHealthItem};
+use crate::config::config_history_index::ConfigHistorySearchParam;
+use crate::config::config_patch::patch_json_content;
+use crate::config::config_pending_changes::{
+    PendingChangeAction, PendingChangeStatus, PendingChangesCmd, PendingChangesResult,
+};
+use crate::config::config_propagation::{query_cluster_trace, start_cluster_trace};
+use crate::config::config_read_view::ConfigReadValue;
+use crate::config::config_remote::{RemoteConfigFetcher, RemoteFetchResult};
+use crate::config::config_search::{IndexCmd, IndexResult};
+use crate::config::config_transcode::ConfigTranscoder;
+use crate::config::config_type::ConfigType;
+use crate::config::config_validate::ConfigValidator;
 use crate::config::core::{
     ConfigActor, ConfigAsyncCmd, ConfigCmd, ConfigInfoDto, ConfigKey, ConfigResult,
+    ConfigSuggestItem,
 };
+use crate::config::model::{ConfigInUseError, ConfigMd5MismatchError};
 use crate::config::ConfigUtils;
 use crate::console::model::config_model::{
-    OpsConfigOptQueryListResponse, OpsConfigQueryListRequest,
+    ConfigAliasParam, ConfigAuditExportParam, ConfigBulkPatchFailedItem, ConfigBulkPatchParam,
+    ConfigBulkPatchResult, ConfigChangeCreateParam, ConfigChangeQueryParam,
+    ConfigChangeRejectParam, ConfigDetectTypeItem, ConfigDetectTypesParam, ConfigDetectTypesResult,
+    ConfigFeedParam, ConfigHealthParam, ConfigHistoryCountParam, ConfigHistorySearchQuery,
+    ConfigImportParam, ConfigImportUrlParam, ConfigKeyParam, ConfigMigrateFormatItem,
+    ConfigMigrateFormatParam, ConfigMigrateFormatResult, ConfigNamespaceParam, ConfigOrphanItem,
+    ConfigOrphansDeleteParam, ConfigOrphansDeleteResult, ConfigOrphansParam, ConfigOrphansResult,
+    ConfigPreviewDiffParam, ConfigPreviewDiffResult, ConfigReferenceEdge, ConfigReferenceGraph,
+    ConfigReferencesParam, ConfigSearchParam, ConfigSnapshotParam, ConfigSuggestParam,
+    FederationConsistencyResult, OpsConfigOptQueryListResponse, OpsConfigQueryListRequest,
 };
-use crate::now_millis;
-use crate::raft::cluster::model::SetConfigReq;
+use crate::console::model::ConsoleResult;
+use crate::console::{NamespaceUtils, SYSCONFIG_GROUP, SYSCONFIG_NAMESPACE};
+use crate::raft::cache::CacheLimiterReq;
+use crate::raft::cache::CacheManagerResult;
+use crate::raft::cluster::model::{DelConfigReq, SetConfigReq};
+use crate::user::permission::UserRole;
+use crate::{now_millis, now_millis_i64};
 use actix::prelude::Addr;
+use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::StreamExt;
-use uuid::Uuid;
 use zip::{ZipArchive, ZipWriter};
 
 use super::model::config_model::OpsConfigImportInfo;
 use super::model::PageResult;
 
+const DETECT_TYPES_BATCH_SIZE: usize = 100;
+const BULK_REWRITE_BATCH_SIZE: usize = 100;
+const DEFAULT_ORPHAN_INACTIVE_DAYS: u64 = 30;
+/// 配置补全最多返回的配置数
+const CONFIG_SUGGEST_LIMIT: usize = 20;
+/// 健康检查返回的健康分最低的配置数
+const HEALTH_RESULT_LIMIT: usize = 10;
+/// 历史快照最多回溯的天数,避免扫描过多历史版本
+const MAX_SNAPSHOT_WINDOW_DAYS: i64 = 30;
+
 pub async fn query_config_list(
     request: web::Query<OpsConfigQueryListRequest>,
     config_addr: web::Data<Addr<ConfigActor>>,
@@ -87,6 +144,210 @@ pub async fn query_history_config_page(
     }
 }
 
+///
+/// 只返回配置的历史版本数,不传输历史内容
+pub async fn query_history_config_count(
+    web::Query(param): web::Query<ConfigHistoryCountParam>,
+    config_addr: web::Data<Addr<ConfigActor>>,
+) -> impl Responder {
+    let key = match param.to_key() {
+        Ok(key) => key,
+        Err(err) => {
+            return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    match config_addr.send(ConfigCmd::QueryHistoryCount(key)).await {
+        Ok(Ok(ConfigResult::HistoryCount(count))) => {
+            HttpResponse::Ok().json(ConsoleResult::success(count))
+        }
+        Ok(Err(err)) => {
+            HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(err.to_string()))
+        }
+        Err(err) => {
+            HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(err.to_string()))
+        }
+        _ => HttpResponse::InternalServerError()
+            .json(ConsoleResult::<()>::error("config result error".to_owned())),
+    }
+}
+
+///
+/// 按命名空间、时间范围、操作人及变更类型查询变更记录,按修改时间倒序分页
+pub async fn search_history_config(
+    web::Query(param): web::Query<ConfigHistorySearchQuery>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = match param.to_param() {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    match app
+        .send_config_cmd(ConfigCmd::SearchHistory(Box::new(param)))
+        .await
+    {
+        Ok(ConfigResult::HistorySearchPage(count, list)) => {
+            HttpResponse::Ok().json(ConsoleResult::success(PageResult {
+                count: count as u64,
+                list,
+            }))
+        }
+        Ok(_) => HttpResponse::InternalServerError()
+            .json(ConsoleResult::<()>::error("config result error".to_owned())),
+        Err(err) => {
+            HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(err.to_string()))
+        }
+    }
+}
+
+///
+/// 写入前预览新内容与当前内容的差异,不修改任何状态
+pub async fn preview_config_diff(
+    req: HttpRequest,
+    web::Json(param): web::Json<ConfigPreviewDiffParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    //与读取配置接口使用相同的权限
+    if let Some(session) = req.extensions().get::<Arc<UserSession>>() {
+        if !UserRole::match_url_by_roles(
+            &session.roles,
+            "/rnacos/api/console/cs/configs",
+            HTTP_METHOD_GET,
+        ) {
+            return HttpResponse::Forbidden()
+                .json(ConsoleResult::<()>::error("NO_PERMISSION".to_owned()));
+        }
+    }
+    let key = match param.key.to_key() {
+        Ok(key) => key,
+        Err(err) => {
+            return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    let current_content = match app.load_config(&key).await {
+        Ok(v) => v.map(|v| v.content.clone()),
+        Err(err) => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    let diff_html = render_diff_html(&diff_lines(
+        current_content
+            .as_ref()
+            .map(|v| v.as_str())
+            .unwrap_or_default(),
+        &param.new_content,
+    ));
+    HttpResponse::Ok().json(ConsoleResult::success(ConfigPreviewDiffResult {
+        current_content,
+        new_content: param.new_content,
+        diff_html,
+    }))
+}
+
+///
+/// 添加配置别名,读写别名时作用于目标配置;别名设置保存在系统配置中经raft复制
+pub async fn add_config_alias(
+    web::Json(param): web::Json<ConfigAliasParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let (from, to) = match (param.from.to_key(), param.to.to_key()) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(err), _) | (_, Err(err)) => {
+            return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    if let Err(err) = from.is_valid().and(to.is_valid()) {
+        return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(err.to_string()));
+    }
+    if app.config_read_view.get(&from).is_some() {
+        return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(format!(
+            "config {} already exists",
+            &from
+        )));
+    }
+    let alias_key = ConfigKey::new(CONFIG_ALIAS_KEY, SYSCONFIG_GROUP, SYSCONFIG_NAMESPACE);
+    let current = app.config_read_view.get(&alias_key);
+    let mut list = match parse_aliases(current.as_ref().map(|v| v.content.as_str()).unwrap_or("")) {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    if let Err(err) = add_alias(&mut list, ConfigAlias { from, to }) {
+        return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(err.to_string()));
+    }
+    let content = match serde_json::to_string(&list) {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    let mut req = SetConfigReq::new(alias_key, Arc::new(content));
+    //并发修改别名时以md5校验,避免覆盖
+    req.expected_md5 = Some(
+        current
+            .map(|v| v.md5.clone())
+            .unwrap_or_else(|| Arc::new(String::new())),
+    );
+    match app.config_route.set_config(req).await {
+        Ok(_) => HttpResponse::Ok().json(ConsoleResult::success(true)),
+        Err(err) => {
+            HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(err.to_string()))
+        }
+    }
+}
+
+///
+/// 向集群各节点上订阅配置的客户端重新推送变更通知,并创建追踪记录收集各客户端的确认
+pub async fn trace_config_propagation(
+    web::Json(param): web::Json<ConfigKeyParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let key = match param.to_key() {
+        Ok(key) => key,
+        Err(err) => {
+            return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    match start_cluster_trace(&app, key).await {
+        Ok(trace) => HttpResponse::Ok().json(ConsoleResult::success(trace)),
+        Err(err) => {
+            HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(err.to_string()))
+        }
+    }
+}
+
+///
+/// 查询心跳有效期内正在使用配置的客户端
+pub async fn query_config_consumers(
+    web::Query(param): web::Query<ConfigKeyParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let key = match param.to_key() {
+        Ok(key) => key,
+        Err(err) => {
+            return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    let list = app.config_consumer_registry.query(&key, now_millis_i64());
+    HttpResponse::Ok().json(ConsoleResult::success(list))
+}
+
+pub async fn query_config_propagation_trace(
+    path: web::Path<String>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    match query_cluster_trace(&app, Arc::new(path.into_inner())).await {
+        Some(trace) => HttpResponse::Ok().json(ConsoleResult::success(trace)),
+        None => HttpResponse::NotFound()
+            .json(ConsoleResult::<()>::error("trace is not exist".to_owned())),
+    }
+}
+
 #[derive(Debug, MultipartForm)]
 pub struct UploadForm {
     #[multipart(rename = "tenant")]
@@ -97,6 +358,7 @@ pub struct UploadForm {
 
 pub async fn import_config(
     req: HttpRequest,
+    web::Query(param): web::Query<ConfigImportParam>,
     MultipartForm(form): MultipartForm<UploadForm>,
     app: web::Data<Arc<AppShareData>>,
 ) -> Result<impl Responder, Error> {
@@ -107,44 +369,55 @@ pub async fn import_config(
         },
     ));
     //let tenant = Arc::new(ConfigUtils::default_tenant(config_info.0.tenant.unwrap_or_default()));
+    let mut items = vec![];
     for f in form.files {
-        match zip::ZipArchive::new(f.file) {
-            Ok(mut archive) => {
-                for i in 0..archive.len() {
-                    let mut file = archive.by_index(i).unwrap();
-                    /*
-                    let filepath = match file.enclosed_name() {
-                        Some(path) => path,
-                        None => continue,
-                    };
-                    */
-                    let filename = file.name();
-                    if !(*filename).ends_with('/') {
-                        let parts = filename.split('/').collect::<Vec<_>>();
-                        if parts.len() != 2 {
-                            continue;
-                        }
-                        assert!(parts.len() == 2);
-                        let config_key = ConfigKey::new_by_arc(
-                            Arc::new(parts[1].to_owned()),
-                            Arc::new(parts[0].to_owned()),
-                            tenant.clone(),
-                        );
-                        let value = match io::read_to_string(&mut file) {
-                            Ok(v) => v,
-                            Err(_) => continue,
-                        };
-                        //println!("update load, {:?}:{}",&config_key,&value);
-                        //config_addr.do_send(ConfigAsyncCmd::Add(config_key, Arc::new(value)));
-                        let req = SetConfigReq::new(config_key, Arc::new(value));
-                        app.config_route.set_config(req).await.ok();
-                    }
-                }
+        match read_import_items(f.file.into_file(), &tenant) {
+            Ok(mut list) => items.append(&mut list),
+            Err(err) => {
+                return Ok(
+                    HttpResponse::BadRequest().json(ConsoleResult::<()>::error(err.to_string()))
+                );
             }
-            Err(_) => todo!(),
         }
     }
-    Ok(HttpResponse::Ok())
+    if param.dry_run.unwrap_or(false) {
+        let report = ConfigValidator::validate_import(&items);
+        return Ok(HttpResponse::Ok().json(ConsoleResult::success(report)));
+    }
+    for (config_key, value) in items {
+        //println!("update load, {:?}:{}",&config_key,&value);
+        //config_addr.do_send(ConfigAsyncCmd::Add(config_key, Arc::new(value)));
+        let req = SetConfigReq::new(config_key, Arc::new(value));
+        app.config_route.set_config(req).await.ok();
+    }
+    Ok(HttpResponse::Ok().finish())
+}
+
+///
+/// 读取导入压缩包中group/dataId格式的配置文件,忽略其它路径与无法读取的文件
+fn read_import_items(file: File, tenant: &Arc<String>) -> anyhow::Result<Vec<(ConfigKey, String)>> {
+    let mut archive = zip::ZipArchive::new(file)?;
+    let mut items = vec![];
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let filename = file.name();
+        if filename.ends_with('/') {
+            continue;
+        }
+        let parts = filename.split('/').collect::<Vec<_>>();
+        if parts.len() != 2 {
+            continue;
+        }
+        let config_key = ConfigKey::new_by_arc(
+            Arc::new(parts[1].to_owned()),
+            Arc::new(parts[0].to_owned()),
+            tenant.clone(),
+        );
+        if let Ok(value) = io::read_to_string(&mut file) {
+            items.push((config_key, value));
+        }
+    }
+    Ok(items)
 }
 
 fn zip_file(mut zip: ZipWriter<&mut File>, list: Vec<ConfigInfoDto>) -> anyhow::Result<()> {
@@ -209,3 +482,1101 @@ pub async fn download_config(
         Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
     }
 }
+
+///
+/// 返回命名空间下配置的依赖关系
+pub async fn query_config_references(
+    web::Query(param): web::Query<ConfigReferencesParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let tenant = Arc::new(ConfigUtils::default_tenant(
+        param.tenant.unwrap_or_default(),
+    ));
+    let list = match app
+        .send_config_cmd(ConfigCmd::QueryReferences(tenant))
+        .await
+    {
+        Ok(ConfigResult::References(list)) => list,
+        Err(err) => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()));
+        }
+        _ => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error("config result error".to_owned()));
+        }
+    };
+    let mut graph = ConfigReferenceGraph::default();
+    let mut nodes = HashSet::new();
+    for (from, references) in list {
+        for to in references {
+            let exists = app.config_read_view.get(&to).is_some();
+            if nodes.insert(to.clone()) {
+                graph.nodes.push(to.clone());
+            }
+            graph.edges.push(ConfigReferenceEdge {
+                from: from.clone(),
+                to,
+                exists,
+            });
+        }
+        if nodes.insert(from.clone()) {
+            graph.nodes.push(from);
+        }
+    }
+    HttpResponse::Ok().json(ConsoleResult::success(graph))
+}
+
+///
+/// 按历史版本还原命名空间在指定时间点的配置,以导出压缩包的格式返回;
+/// 已删除配置的历史不再保留,不会出现在快照中
+pub async fn download_config_snapshot(
+    web::Query(param): web::Query<ConfigSnapshotParam>,
+    config_addr: web::Data<Addr<ConfigActor>>,
+) -> impl Responder {
+    let at = match param.parse_at() {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    if now_millis_i64() - at > MAX_SNAPSHOT_WINDOW_DAYS * 24 * 3600 * 1000 {
+        return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(format!(
+            "at is out of the snapshot window, max {} days",
+            MAX_SNAPSHOT_WINDOW_DAYS
+        )));
+    }
+    let tenant = Arc::new(ConfigUtils::default_tenant(
+        param.tenant.unwrap_or_default(),
+    ));
+    match config_addr
+        .send(ConfigCmd::QuerySnapshotAt(tenant, at))
+        .await
+    {
+        Ok(Ok(ConfigResult::ConfigInfoPage(_, list))) => {
+            let mut tmpfile: File = tempfile::tempfile().unwrap();
+            {
+                let write = std::io::Write::by_ref(&mut tmpfile);
+                let zip = ZipWriter::new(write);
+                zip_file(zip, list).ok();
+            }
+            tmpfile.seek(SeekFrom::Start(0)).unwrap();
+            let mut buf = vec![];
+            tmpfile.read_to_end(&mut buf).unwrap();
+            let filename = format!("rnacos_config_snapshot_{}.zip", at);
+            HttpResponse::Ok()
+                .insert_header(header::ContentType::octet_stream())
+                .insert_header(header::ContentDisposition::attachment(filename))
+                .body(buf)
+        }
+        Ok(Err(err)) => {
+            HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(err.to_string()))
+        }
+        Err(err) => {
+            HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(err.to_string()))
+        }
+        _ => HttpResponse::InternalServerError()
+            .json(ConsoleResult::<()>::error("config result error".to_owned())),
+    }
+}
+
+///
+/// 推断命名空间下未设置类型的配置类型并批量更新,不修改内容与md5
+pub async fn detect_config_types(
+    web::Query(param): web::Query<ConfigDetectTypesParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let tenant = ConfigUtils::default_tenant(param.tenant.unwrap_or_default());
+    let list = match app
+        .send_config_cmd(ConfigCmd::ListAll(Arc::new(tenant)))
+        .await
+    {
+        Ok(ConfigResult::ConfigList(list)) => list,
+        Err(err) => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()));
+        }
+        _ => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error("config result error".to_owned()));
+        }
+    };
+    let mut result = ConfigDetectTypesResult {
+        total: list.len(),
+        ..Default::default()
+    };
+    let reqs: Vec<SetConfigReq> = list
+        .into_iter()
+        .filter(|(_, v)| v.config_type.is_none())
+        .map(|(key, v)| {
+            let config_type = ConfigType::detect(&v.content).get_value();
+            result.list.push(ConfigDetectTypeItem {
+                group: key.group.clone(),
+                data_id: key.data_id.clone(),
+                config_type: config_type.clone(),
+            });
+            let mut req = SetConfigReq::new(key, v.content);
+            req.config_type = Some(config_type);
+            req.desc = v.desc;
+            //内容在推断后被修改时跳过
+            req.expected_md5 = Some(v.md5);
+            req
+        })
+        .collect();
+    result.updated = reqs.len();
+    for batch in reqs.chunks(DETECT_TYPES_BATCH_SIZE) {
+        if let Err(err) = app
+            .config_route
+            .batch_set_config(batch.to_vec(), None)
+            .await
+        {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    }
+    HttpResponse::Ok().json(ConsoleResult::success(result))
+}
+
+///
+/// 批量改写时单个配置的改写方式
+enum ConfigRewrite {
+    Skip,
+    //新内容及写入的配置类型
+    Rewrite(String, Option<Arc<String>>),
+    Failed(String),
+}
+
+enum ConfigRewriteOutcome {
+    Skipped,
+    Preview(String),
+    Written,
+    Failed(String),
+}
+
+///
+/// 批量改写分组下的配置:按rewrite生成新内容后分批写入;
+/// 写入时带上读取时的md5,读取后被修改的配置不覆盖;dryRun时只返回新内容
+async fn bulk_rewrite_configs<F>(
+    app: &AppShareData,
+    tenant: Option<String>,
+    group: &str,
+    dry_run: bool,
+    rewrite: F,
+) -> anyhow::Result<Vec<(ConfigKey, ConfigRewriteOutcome)>>
+where
+    F: Fn(&ConfigKey, &ConfigReadValue) -> ConfigRewrite,
+{
+    let tenant = ConfigUtils::default_tenant(tenant.unwrap_or_default());
+    let list = match app
+        .send_config_cmd(ConfigCmd::ListAll(Arc::new(tenant)))
+        .await?
+    {
+        ConfigResult::ConfigList(list) => list,
+        _ => return Err(anyhow::anyhow!("config result error")),
+    };
+    let mut outcomes = vec![];
+    let mut reqs = vec![];
+    for (key, v) in list {
+        if key.group.as_str() != group {
+            continue;
+        }
+        match rewrite(&key, &v) {
+            ConfigRewrite::Skip => outcomes.push((key, ConfigRewriteOutcome::Skipped)),
+            ConfigRewrite::Failed(reason) => {
+                outcomes.push((key, ConfigRewriteOutcome::Failed(reason)))
+            }
+            ConfigRewrite::Rewrite(content, _) if dry_run => {
+                outcomes.push((key, ConfigRewriteOutcome::Preview(content)))
+            }
+            ConfigRewrite::Rewrite(content, config_type) => {
+                let mut req = SetConfigReq::new(key, Arc::new(content));
+                req.config_type = config_type;
+                req.desc = v.desc.clone();
+                //读取后被修改的配置不覆盖
+                req.expected_md5 = Some(v.md5.clone());
+                reqs.push(req);
+            }
+        }
+    }
+    for batch in reqs.chunks(BULK_REWRITE_BATCH_SIZE) {
+        let results: Vec<anyhow::Result<()>> = match app
+            .config_route
+            .batch_set_config(batch.to_vec(), None)
+            .await
+        {
+            Ok(v) => v.into_iter().map(|v| v.into_result()).collect(),
+            Err(err) => batch
+                .iter()
+                .map(|_| Err(anyhow::anyhow!(err.to_string())))
+                .collect(),
+        };
+        for (req, set_result) in batch.iter().zip(results) {
+            let outcome = match set_result {
+                Ok(_) => ConfigRewriteOutcome::Written,
+                Err(err) => ConfigRewriteOutcome::Failed(err.to_string()),
+            };
+            outcomes.push((req.config_key.clone(), outcome));
+        }
+    }
+    Ok(outcomes)
+}
+
+///
+/// 对分组下所有json配置应用JSON Merge Patch,非json配置跳过
+pub async fn bulk_patch_config(
+    web::Json(param): web::Json<ConfigBulkPatchParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    if param.group.is_empty() {
+        return HttpResponse::BadRequest()
+            .json(ConsoleResult::<()>::error("group is empty".to_owned()));
+    }
+    let outcomes = bulk_rewrite_configs(&app, param.tenant.clone(), &param.group, false, |_, v| {
+        let config_type = match &v.config_type {
+            Some(t) => ConfigType::new_by_value(t),
+            None => ConfigType::detect(&v.content),
+        };
+        if config_type != ConfigType::Json {
+            return ConfigRewrite::Skip;
+        }
+        match patch_json_content(&v.content, &param.content_patch) {
+            Ok(Some(content)) => ConfigRewrite::Rewrite(content, v.config_type.clone()),
+            Ok(None) => ConfigRewrite::Skip,
+            Err(err) => ConfigRewrite::Failed(err.to_string()),
+        }
+    })
+    .await;
+    let outcomes = match outcomes {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    let mut result = ConfigBulkPatchResult::default();
+    for (key, outcome) in outcomes {
+        match outcome {
+            ConfigRewriteOutcome::Written => result.patched += 1,
+            ConfigRewriteOutcome::Skipped | ConfigRewriteOutcome::Preview(_) => result.skipped += 1,
+            ConfigRewriteOutcome::Failed(reason) => result.failed.push(ConfigBulkPatchFailedItem {
+                group: key.group,
+                data_id: key.data_id,
+                reason,
+            }),
+        }
+    }
+    HttpResponse::Ok().json(ConsoleResult::success(result))
+}
+
+///
+/// 把分组下fromType类型的配置转换为toType类型后写回,dryRun时只返回转换结果
+pub async fn migrate_config_format(
+    web::Json(param): web::Json<ConfigMigrateFormatParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    if param.group.is_empty() {
+        return HttpResponse::BadRequest()
+            .json(ConsoleResult::<()>::error("group is empty".to_owned()));
+    }
+    let from_type = ConfigType::new_by_value(&param.from_type);
+    let to_type = ConfigType::new_by_value(&param.to_type);
+    let supported = |v: &ConfigType| {
+        matches!(
+            v,
+            ConfigType::Json | ConfigType::Yaml | ConfigType::Toml | ConfigType::Properties
+        )
+    };
+    if !supported(&from_type) || !supported(&to_type) || from_type == to_type {
+        return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(format!(
+            "unsupported migration: {} -> {}",
+            &param.from_type, &param.to_type
+        )));
+    }
+    let outcomes = bulk_rewrite_configs(
+        &app,
+        param.tenant,
+        &param.group,
+        param.dry_run.unwrap_or(false),
+        |_, v| {
+            if v.config_type.as_ref().map(|t| ConfigType::new_by_value(t))
+                != Some(from_type.clone())
+            {
+                return ConfigRewrite::Skip;
+            }
+            match ConfigTranscoder::transcode(&v.content, &from_type, &to_type) {
+                Ok(content) => ConfigRewrite::Rewrite(content, Some(to_type.get_value())),
+                Err(err) => ConfigRewrite::Failed(err.to_string()),
+            }
+        },
+    )
+    .await;
+    let outcomes = match outcomes {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    let mut result = ConfigMigrateFormatResult::default();
+    for (key, outcome) in outcomes {
+        let mut item = ConfigMigrateFormatItem {
+            group: key.group,
+            data_id: key.data_id,
+            ..Default::default()
+        };
+        match outcome {
+            ConfigRewriteOutcome::Skipped => continue,
+            ConfigRewriteOutcome::Preview(content) => {
+                item.status = "PREVIEW".to_owned();
+                item.new_content = Some(content);
+            }
+            ConfigRewriteOutcome::Written => {
+                item.status = "SUCCESS".to_owned();
+                result.migrated += 1;
+            }
+            ConfigRewriteOutcome::Failed(reason) => {
+                item.status = "FAILED".to_owned();
+                item.reason = Some(reason);
+                result.failed += 1;
+            }
+        }
+        result.items.push(item);
+    }
+    HttpResponse::Ok().json(ConsoleResult::success(result))
+}
+
+///
+/// inactiveDays天前的毫秒时间戳,天数过大时返回None
+fn inactive_before_ms(inactive_days: Option<u64>) -> Option<i64> {
+    let inactive_days = inactive_days.unwrap_or(DEFAULT_ORPHAN_INACTIVE_DAYS);
+    let inactive_ms = i64::try_from(inactive_days)
+        .ok()
+        .and_then(|v| v.checked_mul(24 * 3600 * 1000))?;
+    now_millis_i64().checked_sub(inactive_ms)
+}
+
+async fn query_inactive_configs(
+    app: &AppShareData,
+    tenant: Arc<String>,
+    before_ms: i64,
+) -> anyhow::Result<Vec<(ConfigKey, i64, i64)>> {
+    match app
+        .send_config_cmd(ConfigCmd::QueryInactiveConfigs(tenant, before_ms))
+        .await?
+    {
+        ConfigResult::ConfigActivities(list) => Ok(list),
+        _ => Err(anyhow::anyhow!("config result error")),
+    }
+}
+
+///
+/// 列出集群内最近inactiveDays天没有读取、监听及写入的配置,只读不删除;
+/// 读取时间由各节点定期通过raft上报
+pub async fn query_orphan_configs(
+    web::Query(param): web::Query<ConfigOrphansParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let before_ms = match inactive_before_ms(param.inactive_days) {
+        Some(v) => v,
+        None => {
+            return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(
+                "inactiveDays is too large".to_owned(),
+            ));
+        }
+    };
+    let tenant = ConfigUtils::default_tenant(param.tenant.unwrap_or_default());
+    let list = match query_inactive_configs(&app, Arc::new(tenant), before_ms).await {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    let result = ConfigOrphansResult {
+        total: list.len(),
+        list: list
+            .into_iter()
+            .map(|(key, last_read_time, last_write_time)| ConfigOrphanItem {
+                group: key.group,
+                data_id: key.data_id,
+                last_read_time,
+                last_write_time,
+            })
+            .collect(),
+    };
+    HttpResponse::Ok().json(ConsoleResult::success(result))
+}
+
+///
+/// 删除查询接口列出的孤立配置;删除前按集群记录的读写时间重新校验,
+/// 期间被读取或修改过的配置会跳过
+pub async fn delete_orphan_configs(
+    req: HttpRequest,
+    web::Json(param): web::Json<ConfigOrphansDeleteParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    if !has_config_write_permission(&req) {
+        return HttpResponse::Forbidden()
+            .json(ConsoleResult::<()>::error("NO_PERMISSION".to_owned()));
+    }
+    let before_ms = match inactive_before_ms(param.inactive_days) {
+        Some(v) => v,
+        None => {
+            return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(
+                "inactiveDays is too large".to_owned(),
+            ));
+        }
+    };
+    if param.keys.is_empty() {
+        return HttpResponse::BadRequest()
+            .json(ConsoleResult::<()>::error("keys is empty".to_owned()));
+    }
+    let tenant = ConfigUtils::default_tenant(param.tenant.unwrap_or_default());
+    let inactive_keys: HashSet<ConfigKey> =
+        match query_inactive_configs(&app, Arc::new(tenant.clone()), before_ms).await {
+            Ok(list) => list.into_iter().map(|(key, _, _)| key).collect(),
+            Err(err) => {
+                return HttpResponse::InternalServerError()
+                    .json(ConsoleResult::<()>::error(err.to_string()));
+            }
+        };
+    let mut result = ConfigOrphansDeleteResult::default();
+    for item in param.keys {
+        let key = ConfigKey::new(&item.data_id, &item.group, &tenant);
+        if !inactive_keys.contains(&key) {
+            result.skipped.push(item);
+            continue;
+        }
+        let mut del_req = DelConfigReq::new(key);
+        del_req.op_user = session_username(&req);
+        if let Err(err) = app.config_route.del_config(del_req).await {
+            //仍被其它配置依赖的配置不删除
+            if err.downcast_ref::<ConfigInUseError>().is_some() {
+                result.skipped.push(item);
+                continue;
+            }
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()));
+        }
+        result.deleted.push(item);
+    }
+    HttpResponse::Ok().json(ConsoleResult::success(result))
+}
+
+///
+/// 按最近读取时间、集群监听数、校验错误率及审批中的变更计算健康分,返回健康分最低的配置;
+/// 未指定命名空间时检查所有命名空间
+pub async fn query_config_health(
+    web::Query(param): web::Query<ConfigHealthParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let tenants: Vec<Arc<String>> = match param.tenant {
+        Some(tenant) => vec![Arc::new(ConfigUtils::default_tenant(tenant))],
+        None => NamespaceUtils::get_namespaces(&app.config_addr)
+            .await
+            .iter()
+            .filter_map(|v| v.namespace_id.clone().map(Arc::new))
+            .collect(),
+    };
+    //客户端分散连接在各节点上,监听数按集群汇总
+    let listener_counts = match cluster_listener_counts(&app, tenants.clone()).await {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    let pending_keys: HashSet<ConfigKey> =
+        match send_pending_changes_cmd(&app, PendingChangesCmd::Query(None)).await {
+            Ok(PendingChangesResult::List(list)) => list
+                .into_iter()
+                .filter(|v| {
+                    v.status == PendingChangeStatus::Review
+                        || v.status == PendingChangeStatus::Approved
+                })
+                .map(|v| v.config_key())
+                .collect(),
+            _ => HashSet::new(),
+        };
+    let now = now_millis_i64();
+    let mut list = vec![];
+    for tenant in tenants {
+        let configs = match app.send_config_cmd(ConfigCmd::ListAll(tenant)).await {
+            Ok(ConfigResult::ConfigList(configs)) => configs,
+            Ok(_) => continue,
+            Err(err) => {
+                return HttpResponse::InternalServerError()
+                    .json(ConsoleResult::<()>::error(err.to_string()));
+            }
+        };
+        for (key, _) in configs {
+            let listener_count = listener_counts.get(&key).cloned().unwrap_or_default();
+            list.push(ConfigHealthItem::new(
+                &key,
+                &app.config_stats.get(&key),
+                listener_count,
+                app.config_validation_stats.error_rate(&key, now),
+                pending_keys.contains(&key),
+                now,
+            ));
+        }
+    }
+    list.sort_by(|a, b| {
+        a.score
+            .cmp(&b.score)
+            .then_with(|| (&a.tenant, &a.group, &a.data_id).cmp(&(&b.tenant, &b.group, &b.data_id)))
+    });
+    list.truncate(HEALTH_RESULT_LIMIT);
+    HttpResponse::Ok().json(ConsoleResult::success(list))
+}
+
+///
+/// 通过倒排索引全文搜索配置,按相关度排序
+pub async fn search_config(
+    web::Query(param): web::Query<ConfigSearchParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let query = param.q.unwrap_or_default();
+    if query.trim().is_empty() {
+        return HttpResponse::BadRequest()
+            .json(ConsoleResult::<()>::error("q is empty".to_owned()));
+    }
+    let limit = param.page_size.unwrap_or(20);
+    let offset = (param.page_no.unwrap_or(1).max(1) - 1) * limit;
+    let cmd = IndexCmd::Search {
+        query,
+        tenant: param.tenant.map(ConfigUtils::default_tenant),
+        offset,
+        limit,
+    };
+    match app.config_indexer.send(cmd).await {
+        Ok(Ok(IndexResult::Page(count, list))) => {
+            HttpResponse::Ok().json(ConsoleResult::success(PageResult {
+                count: count as u64,
+                list,
+            }))
+        }
+        Ok(Err(err)) => {
+            HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(err.to_string()))
+        }
+        Err(err) => {
+            HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(err.to_string()))
+        }
+        _ => HttpResponse::InternalServerError()
+            .json(ConsoleResult::<()>::error("index result error".to_owned())),
+    }
+}
+
+///
+/// 从远程地址(http/https/s3)拉取内容并导入为配置
+pub async fn import_config_from_url(
+    web::Json(param): web::Json<ConfigImportUrlParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let limit_req = CacheLimiterReq::Minutes {
+        key: Arc::new("CONFIG_IMPORT_URL_L".to_owned()),
+        limit: app.sys_config.config_import_url_one_minute_limit as i32,
+    };
+    match app.raft_cache_route.request_limiter(limit_req).await {
+        Ok(CacheManagerResult::Limiter(true)) => {}
+        Ok(_) => {
+            return HttpResponse::TooManyRequests().json(ConsoleResult::<()>::error(
+                "Frequent import, please try again later".to_owned(),
+            ));
+        }
+        Err(err) => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    }
+    let config_key = param.to_key();
+    if let Err(err) = config_key.is_valid() {
+        return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(err.to_string()));
+    }
+    let fetch_result = RemoteConfigFetcher::fetch(
+        &param.url,
+        param.etag.as_deref(),
+        app.sys_config.config_max_content,
+    )
+    .await;
+    let (content, etag) = match fetch_result {
+        Ok(RemoteFetchResult::Content { content, etag }) => (content, etag),
+        Ok(RemoteFetchResult::NotModified) => {
+            return HttpResponse::NotModified()
+                .insert_header(("X-Rnacos-Etag", param.etag.unwrap_or_default()))
+                .finish();
+        }
+        Err(err) => {
+            return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    let config_type = param
+        .r#type
+        .as_ref()
+        .map(|v| ConfigType::new_by_value(v))
+        .unwrap_or_default();
+    let validate_result = ConfigValidator::validate_content(&content, &config_type);
+    app.config_validation_stats
+        .record(&config_key, validate_result.is_ok(), now_millis_i64());
+    if let Err(err) = validate_result {
+        return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(err.to_string()));
+    }
+    let mut req = SetConfigReq::new(config_key, Arc::new(content));
+    req.config_type = Some(config_type.get_value());
+    match app.config_route.set_config(req).await {
+        Ok(_) => HttpResponse::Ok()
+            .insert_header(("X-Rnacos-Etag", etag.unwrap_or_default()))
+            .json(ConsoleResult::success(true)),
+        Err(err) => {
+            HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(err.to_string()))
+        }
+    }
+}
+
+fn session_username(req: &HttpRequest) -> Option<Arc<String>> {
+    req.extensions()
+        .get::<Arc<UserSession>>()
+        .map(|v| v.username.clone())
+}
+
+async fn send_pending_changes_cmd(
+    app: &AppShareData,
+    cmd: PendingChangesCmd,
+) -> anyhow::Result<PendingChangesResult> {
+    app.pending_changes_route.send(cmd).await
+}
+
+fn pending_changes_response(result: anyhow::Result<PendingChangesResult>) -> HttpResponse {
+    match result {
+        Ok(PendingChangesResult::Change(change)) => {
+            HttpResponse::Ok().json(ConsoleResult::success(change))
+        }
+        Ok(PendingChangesResult::List(list)) => {
+            HttpResponse::Ok().json(ConsoleResult::success(list))
+        }
+        Err(err) => HttpResponse::Ok().json(ConsoleResult::<()>::error(err.to_string())),
+    }
+}
+
+///
+/// 创建待审批的配置变更,状态为draft;记录当前配置的md5,生效时配置已被修改则写入失败
+pub async fn create_config_change(
+    req: HttpRequest,
+    web::Json(param): web::Json<ConfigChangeCreateParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let config_key = match param.key.to_key().and_then(|v| v.is_valid().map(|_| v)) {
+        Ok(key) => key,
+        Err(err) => {
+            return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    let base_md5 = app
+        .config_read_view
+        .get(&config_key)
+        .map(|v| v.md5.clone())
+        .unwrap_or_default();
+    let mut set_req = SetConfigReq::new(config_key, Arc::new(param.content.unwrap_or_default()));
+    set_req.config_type = param.config_type.map(Arc::new);
+    set_req.desc = param.desc.map(Arc::new);
+    let cmd = PendingChangesCmd::Create {
+        req: set_req,
+        base_md5,
+        author: session_username(&req),
+    };
+    pending_changes_response(send_pending_changes_cmd(&app, cmd).await)
+}
+
+pub async fn query_config_changes(
+    web::Query(param): web::Query<ConfigChangeQueryParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let cmd = PendingChangesCmd::Query(param.status);
+    pending_changes_response(send_pending_changes_cmd(&app, cmd).await)
+}
+
+pub async fn get_config_change(
+    path: web::Path<u64>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let cmd = PendingChangesCmd::Get(path.into_inner());
+    pending_changes_response(send_pending_changes_cmd(&app, cmd).await)
+}
+
+async fn transition_config_change(
+    req: &HttpRequest,
+    app: &AppShareData,
+    id: u64,
+    action: PendingChangeAction,
+    reason: Option<String>,
+) -> HttpResponse {
+    let cmd = PendingChangesCmd::Transition {
+        id,
+        action,
+        operator: session_username(req),
+        reason,
+    };
+    pending_changes_response(send_pending_changes_cmd(app, cmd).await)
+}
+
+///
+/// draft/rejected -> review
+pub async fn submit_config_change(
+    req: HttpRequest,
+    path: web::Path<u64>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    transition_config_change(
+        &req,
+        &app,
+        path.into_inner(),
+        PendingChangeAction::Submit,
+        None,
+    )
+    .await
+}
+
+///
+/// review -> rejected
+pub async fn reject_config_change(
+    req: HttpRequest,
+    path: web::Path<u64>,
+    web::Json(param): web::Json<ConfigChangeRejectParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    if param.reason.as_ref().map(|v| v.is_empty()).unwrap_or(true) {
+        return HttpResponse::BadRequest()
+            .json(ConsoleResult::<()>::error("reason is empty".to_owned()));
+    }
+    transition_config_change(
+        &req,
+        &app,
+        path.into_inner(),
+        PendingChangeAction::Reject,
+        param.reason,
+    )
+    .await
+}
+
+///
+/// review -> approved
+pub async fn approve_config_change(
+    req: HttpRequest,
+    path: web::Path<u64>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    transition_config_change(
+        &req,
+        &app,
+        path.into_inner(),
+        PendingChangeAction::Approve,
+        None,
+    )
+    .await
+}
+
+///
+/// approved -> active,写入配置成功后才变更状态
+pub async fn apply_config_change(
+    req: HttpRequest,
+    path: web::Path<u64>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let change = match send_pending_changes_cmd(&app, PendingChangesCmd::Get(id)).await {
+        Ok(PendingChangesResult::Change(change)) => change,
+        Ok(_) => {
+            return HttpResponse::Ok()
+                .json(ConsoleResult::<()>::error("change result error".to_owned()));
+        }
+        Err(err) => return HttpResponse::Ok().json(ConsoleResult::<()>::error(err.to_string())),
+    };
+    if let Err(err) = change.status.next(PendingChangeAction::Apply) {
+        return HttpResponse::Ok().json(ConsoleResult::<()>::error(err.to_string()));
+    }
+    let op_user = session_username(&req);
+    if let Err(err) = app.config_route.set_config(change.to_req(op_user)).await {
+        if err.downcast_ref::<ConfigMd5MismatchError>().is_some() {
+            return HttpResponse::Conflict().json(ConsoleResult::<()>::error(
+                "config was modified after the change was created".to_owned(),
+            ));
+        }
+        return HttpResponse::InternalServerError()
+            .json(ConsoleResult::<()>::error(err.to_string()));
+    }
+    transition_config_change(&req, &app, id, PendingChangeAction::Apply, None).await
+}
+
+///
+/// 列出命名空间下的配置,并按命名空间的复制过滤规则处理,保证敏感字段不离开当前区域
+async fn list_namespace_configs(
+    app: &AppShareData,
+    namespace: Option<String>,
+) -> anyhow::Result<Vec<ConfigExportItem>> {
+    let tenant = ConfigUtils::default_tenant(namespace.unwrap_or_default());
+    let rules = NamespaceUtils::get_namespaces(&app.config_addr)
+        .await
+        .into_iter()
+        .find(|v| v.namespace_id.as_deref() == Some(tenant.as_str()))
+        .and_then(|v| v.replication_filter.clone())
+        .unwrap_or_default();
+    let list = match app
+        .send_config_cmd(ConfigCmd::ListAll(Arc::new(tenant)))
+        .await?
+    {
+        ConfigResult::ConfigList(list) => list,
+        _ => return Err(anyhow::anyhow!("config result error")),
+    };
+    let mut items = Vec::with_capacity(list.len());
+    for (key, v) in list {
+        let item = ConfigExportItem {
+            group: key.group.clone(),
+            data_id: key.data_id.clone(),
+            content: v.content,
+            config_type: v.config_type,
+        };
+        match apply_replication_filter(item, &rules) {
+            Ok(Some(item)) => items.push(item),
+            Ok(None) => {}
+            //无法解析时不确定是否包含敏感字段,不传输
+            Err(err) => log::warn!("replication filter error, key:{}, {}", &key, err),
+        }
+    }
+    Ok(items)
+}
+
+///
+/// 导出命名空间下的所有配置,供其它集群对比
+pub async fn export_namespace_configs(
+    web::Query(param): web::Query<ConfigNamespaceParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    match list_namespace_configs(&app, param.namespace).await {
+        Ok(list) => HttpResponse::Ok().json(ConsoleResult::success(list)),
+        Err(err) => {
+            HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(err.to_string()))
+        }
+    }
+}
+
+///
+/// 命名空间下各配置的md5,供联邦中其它集群对比一致性;
+/// 带X-Federation-Token请求头时按联邦令牌校验,否则需有配置读权限
+pub async fn query_namespace_config_md5s(
+    req: HttpRequest,
+    web::Query(param): web::Query<ConfigNamespaceParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let pass = match req.headers().get(FEDERATION_TOKEN_HEADER) {
+        Some(v) => app
+            .sys_config
+            .federation
+            .as_ref()
+            .and_then(|federation| federation.token.as_ref())
+            .map(|token| constant_time_eq(token.as_bytes(), v.as_bytes()))
+            .unwrap_or(false),
+        None => has_config_read_permission(&req),
+    };
+    if !pass {
+        return HttpResponse::Forbidden()
+            .json(ConsoleResult::<()>::error("no permission".to_owned()));
+    }
+    match list_namespace_configs(&app, param.namespace).await {
+        Ok(list) => HttpResponse::Ok().json(ConsoleResult::success(to_md5_map(&list))),
+        Err(err) => {
+            HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(err.to_string()))
+        }
+    }
+}
+
+///
+/// 对比当前集群与联邦中其它集群的配置md5,只读
+pub async fn federation_consistency_check(
+    web::Query(param): web::Query<ConfigNamespaceParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let federation = match &app.sys_config.federation {
+        Some(v) => v,
+        None => {
+            return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(
+                "federation peers not configured".to_owned(),
+            ));
+        }
+    };
+    let local = match list_namespace_configs(&app, param.namespace.clone()).await {
+        Ok(list) => to_md5_map(&list),
+        Err(err) => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    let client = match new_peer_client() {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    let namespace = param.namespace.unwrap_or_default();
+    let peer_results = futures_util::future::join_all(
+        federation
+            .peers
+            .iter()
+            .map(|peer| fetch_peer_md5s(&client, peer, &namespace, federation.token.as_deref())),
+    )
+    .await;
+    let mut result = FederationConsistencyResult::default();
+    let mut regions = vec![(federation.region.clone(), local)];
+    for (peer, peer_result) in federation.peers.iter().zip(peer_results) {
+        match peer_result {
+            Ok(md5s) => regions.push((peer.region.clone(), md5s)),
+            Err(err) => {
+                result.errors.insert(peer.region.clone(), err.to_string());
+            }
+        }
+    }
+    result.regions = regions.iter().map(|(region, _)| region.clone()).collect();
+    result.configs = build_consistency_matrix(&regions);
+    result.inconsistent = result
+        .configs
+        .iter()
+        .filter(|(_, row)| !row.consistent)
+        .map(|(key, _)| key.clone())
+        .collect();
+    HttpResponse::Ok().json(ConsoleResult::success(result))
+}
+
+///
+/// 配置变更的Atom订阅源;X-Feed-Token请求头中的只读令牌只能访问所属命名空间,
+/// 未带该请求头时使用openapi令牌鉴权,需有配置读权限
+pub async fn config_change_feed(
+    req: HttpRequest,
+    web::Query(param): web::Query<ConfigFeedParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let tenant = ConfigUtils::default_tenant(param.tenant.unwrap_or_default());
+    let pass = match req.headers().get(CONFIG_FEED_TOKEN_HEADER) {
+        Some(v) => app
+            .sys_config
+            .config_feed_tokens
+            .get(&tenant)
+            .map(|token| constant_time_eq(token.as_bytes(), v.as_bytes()))
+            .unwrap_or(false),
+        None => has_config_read_permission(&req),
+    };
+    if !pass {
+        return HttpResponse::Forbidden().body("no permission to read the feed");
+    }
+    let group = param.group.filter(|v| !v.is_empty());
+    let search_param = ConfigHistorySearchParam {
+        tenant: Arc::new(tenant.clone()),
+        group: group.clone(),
+        limit: CONFIG_FEED_MAX_ENTRIES,
+        ..Default::default()
+    };
+    let entries = match app
+        .send_config_cmd(ConfigCmd::QueryChangeFeed(Box::new(search_param)))
+        .await
+    {
+        Ok(ConfigResult::ChangeFeed(entries)) => entries,
+        Ok(_) => vec![],
+        Err(err) => {
+            return HttpResponse::InternalServerError().body(err.to_string());
+        }
+    };
+    //Last-Modified只精确到秒
+    let updated_secs = (feed_updated_time(&entries) / 1000).max(0) as u64;
+    let last_modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(updated_secs);
+    if let Some(header::IfModifiedSince(since)) = req.get_header::<header::IfModifiedSince>() {
+        let since: std::time::SystemTime = since.into();
+        if last_modified <= since {
+            return HttpResponse::NotModified().finish();
+        }
+    }
+    let body = build_atom_feed(&tenant, group.as_deref(), &entries);
+    HttpResponse::Ok()
+        .insert_header(header::LastModified(last_modified.into()))
+        .content_type("application/atom+xml; charset=utf-8")
+        .body(body)
+}
+
+///
+/// 按命名空间与时间范围导出配置访问审计记录,数据来自配置访问日志文件;
+/// 以csv分块流式返回,不在内存中缓存全部结果。
+/// 只包含当前节点正在写入的日志文件,不包含其它节点、已轮转的日志文件及gRPC读取记录
+pub async fn export_config_access_audit(
+    web::Query(param): web::Query<ConfigAuditExportParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    if !app.config_access_logger.is_enabled() {
+        return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(
+            "config access log is not enabled".to_owned(),
+        ));
+    }
+    if let Some(format) = param.format.as_deref() {
+        if !format.eq_ignore_ascii_case("csv") {
+            return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(format!(
+                "unsupported format: {}",
+                format
+            )));
+        }
+    }
+    let parse_time = |v: Option<&str>| {
+        v.filter(|v| !v.is_empty())
+            .map(parse_audit_time)
+            .transpose()
+    };
+    let (from_millis, to_millis) = match (
+        parse_time(param.from.as_deref()),
+        parse_time(param.to.as_deref()),
+    ) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(err), _) | (_, Err(err)) => {
+            return HttpResponse::BadRequest().json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    let filter = AuditExportFilter {
+        tenant: param.tenant.map(ConfigUtils::default_tenant),
+        from_millis,
+        to_millis,
+    };
+    let (tx, rx) = tokio::sync::mpsc::channel(4);
+    actix_web::rt::spawn(export_access_log_csv(
+        app.sys_config.config_access_log_path.clone(),
+        filter,
+        tx,
+    ));
+    HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header(header::ContentDisposition::attachment(
+            "config_access_audit.csv",
+        ))
+        .streaming(ReceiverStream::new(rx))
+}
+
+///
+/// 按dataId前缀补全配置,供控制台搜索框使用
+pub async fn suggest_config(
+    web::Query(param): web::Query<ConfigSuggestParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let prefix = param.q.unwrap_or_default();
+    if prefix.is_empty() {
+        return HttpResponse::Ok().json(ConsoleResult::success(Vec::<ConfigSuggestItem>::new()));
+    }
+    let tenant = Arc::new(ConfigUtils::default_tenant(
+        param.tenant.unwrap_or_default(),
+    ));
+    let cmd = ConfigCmd::SuggestConfigs(tenant, prefix, CONFIG_SUGGEST_LIMIT);
+    match app.send_config_cmd(cmd).await {
+        Ok(ConfigResult::Suggestions(list)) => {
+            HttpResponse::Ok().json(ConsoleResult::success(list))
+        }
+        Ok(_) => HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(
+            "unexpected config result".to_owned(),
+        )),
+        Err(err) => {
+            HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(err.to_string()))
+        }
+    }
+}
@@ -1,10 +1,15 @@
+use std::sync::Arc;
+
 use actix_web::{http::header, web, HttpResponse, Responder};
 
 use actix::prelude::Addr;
 
+use crate::common::appdata::AppShareData;
+use crate::config::core::{ConfigCmd, ConfigResult};
 use crate::grpc::bistream_manage::{BiStreamManage, BiStreamManageCmd, BiStreamManageResult};
 
-use super::model::PageResult;
+use super::model::connection_model::ConnectionInfo;
+use super::model::{ConsoleResult, PageResult};
 
 pub async fn query_grpc_connection(
     conn_manager_addr: web::Data<Addr<BiStreamManage>>,
@@ -32,3 +37,74 @@ pub async fn query_grpc_connection(
         Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
     }
 }
+
+///
+/// 返回当前节点的grpc连接及连接上按key订阅的配置
+pub async fn query_grpc_connection_detail(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    let conn_list = match app
+        .bi_stream_manage
+        .send(BiStreamManageCmd::QueryConnInfoList)
+        .await
+    {
+        Ok(Ok(BiStreamManageResult::ConnInfoList(list))) => list,
+        Ok(Err(err)) => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()))
+        }
+        Err(err) => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error(err.to_string()))
+        }
+        _ => {
+            return HttpResponse::InternalServerError()
+                .json(ConsoleResult::<()>::error("error result".to_owned()))
+        }
+    };
+    let mut subscribes = match app.send_config_cmd(ConfigCmd::QueryClientSubscribes).await {
+        Ok(ConfigResult::ClientSubscribes(v)) => v,
+        _ => Default::default(),
+    };
+    let list: Vec<ConnectionInfo> = conn_list
+        .into_iter()
+        .map(|conn| ConnectionInfo {
+            subscriptions: subscribes.remove(&conn.client_id).unwrap_or_default(),
+            remote_addr: conn.client_id.clone(),
+            id: conn.client_id,
+            created_at: conn.create_time,
+            last_active_time: conn.last_active_time,
+        })
+        .collect();
+    HttpResponse::Ok().json(ConsoleResult::success(PageResult {
+        count: list.len() as u64,
+        list,
+    }))
+}
+
+///
+/// 主动关闭grpc连接,客户端会重新建立连接
+pub async fn close_grpc_connection(
+    path: web::Path<String>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let client_id = Arc::new(path.into_inner());
+    match app
+        .bi_stream_manage
+        .send(BiStreamManageCmd::CloseConn(client_id))
+        .await
+    {
+        Ok(Ok(BiStreamManageResult::CloseResult(true))) => {
+            HttpResponse::Ok().json(ConsoleResult::success(true))
+        }
+        Ok(Ok(BiStreamManageResult::CloseResult(false))) => HttpResponse::NotFound().json(
+            ConsoleResult::<()>::error("connection not found".to_owned()),
+        ),
+        Ok(Err(err)) => {
+            HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(err.to_string()))
+        }
+        Err(err) => {
+            HttpResponse::InternalServerError().json(ConsoleResult::<()>::error(err.to_string()))
+        }
+        _ => HttpResponse::InternalServerError()
+            .json(ConsoleResult::<()>::error("error result".to_owned())),
+    }
+}
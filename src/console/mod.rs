@@ -4,6 +4,7 @@ pub mod config_api;
 pub mod connection_api;
 pub mod login_api;
 pub mod model;
+pub mod namespace_api;
 pub mod naming_api;
 pub mod user_api;
 
@@ -11,15 +12,18 @@ pub mod middle;
 pub mod v2;
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::{
     common::appdata::AppShareData,
     config::core::{ConfigActor, ConfigCmd, ConfigKey, ConfigResult},
+    now_millis_i64,
     raft::cluster::model::SetConfigReq,
 };
 use actix::prelude::*;
 
 use self::model::NamespaceInfo;
+use crate::config::config_federation::FilterRule;
 
 pub struct NamespaceUtils;
 
@@ -27,17 +31,39 @@ pub const DEFAULT_NAMESPACE: &str = "public";
 pub const SYSCONFIG_NAMESPACE: &str = "__INNER_SYSTEM__";
 pub const SYSCONFIG_GROUP: &str = "sys";
 pub const SYSCONFIG_NAMESPACE_KEY: &str = "namespaces";
+//清除超过保留期的已删除命名空间的检查间隔
+pub const NAMESPACE_PURGE_INTERVAL: Duration = Duration::from_secs(600);
 
 lazy_static::lazy_static! {
     static ref DEFAULT_NAMESPACE_INFO:Arc<NamespaceInfo> = Arc::new(NamespaceInfo {
             namespace_id: Some("".to_owned()),
             namespace_name: Some(DEFAULT_NAMESPACE.to_owned()),
             r#type: Some("0".to_owned()),
+            debounce_ms: None,
+            max_change_rate_per_key: None,
+            is_template: None,
+            template_namespace_id: None,
+            deleted_at: None,
+            replication_filter: None,
+            auto_encrypt_patterns: None,
+            labels: None,
+            managed_by: None,
+            managed_by_ref: None,
     });
 }
 
 impl NamespaceUtils {
     pub async fn get_namespaces(config_addr: &Addr<ConfigActor>) -> Vec<Arc<NamespaceInfo>> {
+        let mut list = Self::get_namespaces_with_deleted(config_addr).await;
+        list.retain(|v| v.deleted_at.is_none());
+        list
+    }
+
+    ///
+    /// 包含已删除、保留期内的命名空间
+    pub async fn get_namespaces_with_deleted(
+        config_addr: &Addr<ConfigActor>,
+    ) -> Vec<Arc<NamespaceInfo>> {
         let cmd = ConfigCmd::GET(ConfigKey::new(
             SYSCONFIG_NAMESPACE_KEY,
             SYSCONFIG_GROUP,
@@ -59,7 +85,18 @@ impl NamespaceUtils {
         if let Ok(mut namespaces) = serde_json::from_str::<Vec<Arc<NamespaceInfo>>>(&namespace_str)
         {
             let mut list = Vec::with_capacity(namespaces.len() + 1);
-            list.push(DEFAULT_NAMESPACE_INFO.clone());
+            //公共命名空间保存的设置合并到默认信息中
+            let mut default_info = DEFAULT_NAMESPACE_INFO.clone();
+            if let Some(index) = namespaces
+                .iter()
+                .position(|v| v.namespace_id.as_deref() == Some(""))
+            {
+                let stored = namespaces.remove(index);
+                let mut info = NamespaceInfo::clone(&default_info);
+                info.replication_filter = stored.replication_filter.clone();
+                default_info = Arc::new(info);
+            }
+            list.push(default_info);
             list.append(&mut namespaces);
             list
         } else {
@@ -127,18 +164,96 @@ impl NamespaceUtils {
                     return Err(anyhow::anyhow!("namespace is exist"));
                 }
             }
+            let template_namespace_id = info.template_namespace_id.filter(|v| !v.is_empty());
+            if let Some(template_namespace_id) = &template_namespace_id {
+                if !infos.iter().any(|v| {
+                    v.namespace_id.as_ref() == Some(template_namespace_id)
+                        && v.is_template == Some(true)
+                }) {
+                    return Err(anyhow::anyhow!(
+                        "template namespace not exist: {}",
+                        template_namespace_id
+                    ));
+                }
+            }
             let new_info = NamespaceInfo {
-                namespace_id: Some(namespace_id),
+                namespace_id: Some(namespace_id.clone()),
                 namespace_name: Some(namespace_name),
                 r#type: Some("2".to_owned()),
+                debounce_ms: info.debounce_ms,
+                max_change_rate_per_key: info.max_change_rate_per_key,
+                is_template: info.is_template,
+                template_namespace_id: None,
+                deleted_at: None,
+                replication_filter: info.replication_filter,
+                auto_encrypt_patterns: info.auto_encrypt_patterns,
+                labels: info.labels,
+                managed_by: info.managed_by,
+                managed_by_ref: info.managed_by_ref,
             };
             infos.push(new_info);
-            Self::save_namespace(app_data, &infos).await
+            match template_namespace_id {
+                Some(template_namespace_id) => {
+                    Self::save_namespace_with_template(
+                        app_data,
+                        &infos,
+                        template_namespace_id,
+                        namespace_id,
+                    )
+                    .await
+                }
+                None => Self::save_namespace(app_data, &infos).await,
+            }
         } else {
             Err(anyhow::anyhow!("params is empty"))
         }
     }
 
+    ///
+    /// 命名空间列表与模板命名空间下的全部配置在同一个raft提案中原子写入,
+    /// 任一配置写入失败时命名空间也不会被创建
+    async fn save_namespace_with_template(
+        app_data: &Arc<AppShareData>,
+        value: &Vec<NamespaceInfo>,
+        template_namespace_id: String,
+        namespace_id: String,
+    ) -> anyhow::Result<()> {
+        let list = match app_data
+            .send_config_cmd(ConfigCmd::ListAll(Arc::new(template_namespace_id)))
+            .await?
+        {
+            ConfigResult::ConfigList(list) => list,
+            _ => return Err(anyhow::anyhow!("config result error")),
+        };
+        let tenant = Arc::new(namespace_id);
+        let mut reqs: Vec<SetConfigReq> = list
+            .into_iter()
+            .map(|(key, value)| {
+                let key = ConfigKey::new_by_arc(key.data_id, key.group, tenant.clone());
+                let mut req = SetConfigReq::new(key, value.content);
+                req.config_type = value.config_type;
+                req.desc = value.desc;
+                //新命名空间下不能已有同名配置
+                req.expected_md5 = Some(Arc::new(String::new()));
+                req
+            })
+            .collect();
+        //命名空间列表最后写入,避免新命名空间标记为模板后拒绝同批配置写入
+        reqs.push(SetConfigReq::new(
+            ConfigKey::new(
+                SYSCONFIG_NAMESPACE_KEY,
+                SYSCONFIG_GROUP,
+                SYSCONFIG_NAMESPACE,
+            ),
+            Arc::new(serde_json::to_string(value)?),
+        ));
+        let results = app_data.config_route.multi_set_config(reqs, None).await?;
+        for result in results {
+            result.into_result()?;
+        }
+        Ok(())
+    }
+
     pub async fn update_namespace(
         app_data: &Arc<AppShareData>,
         info: NamespaceInfo,
@@ -146,14 +261,40 @@ impl NamespaceUtils {
         if let (Some(namespace_id), Some(namespace_name)) = (info.namespace_id, info.namespace_name)
         {
             if namespace_id.is_empty() || namespace_id.eq(DEFAULT_NAMESPACE) {
-                return Err(anyhow::anyhow!("namespace can't update"));
+                return Self::update_default_namespace(app_data, info.replication_filter).await;
             }
             let infos = Self::load_namespace_from_config(&app_data.config_addr).await;
             let mut new_infos = Vec::with_capacity(infos.len());
             let mut update_mark = false;
             for mut item in infos {
-                if namespace_id.eq(item.namespace_id.as_ref().unwrap() as &str) {
+                if item.deleted_at.is_none()
+                    && namespace_id.eq(item.namespace_id.as_ref().unwrap() as &str)
+                {
                     item.namespace_name = Some(namespace_name.clone());
+                    if info.debounce_ms.is_some() {
+                        item.debounce_ms = info.debounce_ms;
+                    }
+                    if info.max_change_rate_per_key.is_some() {
+                        item.max_change_rate_per_key = info.max_change_rate_per_key;
+                    }
+                    if info.is_template.is_some() {
+                        item.is_template = info.is_template;
+                    }
+                    if info.replication_filter.is_some() {
+                        item.replication_filter = info.replication_filter.clone();
+                    }
+                    if info.auto_encrypt_patterns.is_some() {
+                        item.auto_encrypt_patterns = info.auto_encrypt_patterns.clone();
+                    }
+                    if info.labels.is_some() {
+                        item.labels = info.labels.clone();
+                    }
+                    if info.managed_by.is_some() {
+                        item.managed_by = info.managed_by.clone();
+                    }
+                    if info.managed_by_ref.is_some() {
+                        item.managed_by_ref = info.managed_by_ref.clone();
+                    }
                     update_mark = true;
                 }
                 new_infos.push(item);
@@ -167,6 +308,32 @@ impl NamespaceUtils {
         }
     }
 
+    ///
+    /// 公共命名空间只能设置复制过滤规则,保存为命名空间id为空的记录
+    async fn update_default_namespace(
+        app_data: &Arc<AppShareData>,
+        replication_filter: Option<Vec<FilterRule>>,
+    ) -> anyhow::Result<()> {
+        if replication_filter.is_none() {
+            return Err(anyhow::anyhow!("namespace can't update"));
+        }
+        let mut infos = Self::load_namespace_from_config(&app_data.config_addr).await;
+        match infos
+            .iter_mut()
+            .find(|v| v.namespace_id.as_deref() == Some(""))
+        {
+            Some(item) => item.replication_filter = replication_filter,
+            None => infos.push(NamespaceInfo {
+                namespace_id: Some("".to_owned()),
+                replication_filter,
+                ..Default::default()
+            }),
+        }
+        Self::save_namespace(app_data, &infos).await
+    }
+
+    ///
+    /// 标记删除,保留期内可恢复,过期后由后台任务清除
     pub async fn remove_namespace(
         app_data: &Arc<AppShareData>,
         namespace_id: Option<String>,
@@ -175,21 +342,87 @@ impl NamespaceUtils {
             if namespace_id.is_empty() || namespace_id.eq(DEFAULT_NAMESPACE) {
                 return Err(anyhow::anyhow!("namespace can't delete"));
             }
-            let infos = Self::load_namespace_from_config(&app_data.config_addr).await;
-            let infos_len = infos.len();
-            let mut new_infos = Vec::with_capacity(infos.len());
-            for item in infos {
-                if namespace_id.eq(item.namespace_id.as_ref().unwrap() as &str) {
-                    continue;
-                }
-                new_infos.push(item);
-            }
-            if new_infos.len() == infos_len {
-                return Err(anyhow::anyhow!("namespace is not exist"));
+            let mut infos = Self::load_namespace_from_config(&app_data.config_addr).await;
+            match infos.iter_mut().find(|v| {
+                v.deleted_at.is_none() && v.namespace_id.as_deref() == Some(namespace_id.as_str())
+            }) {
+                Some(item) => item.deleted_at = Some(now_millis_i64()),
+                None => return Err(anyhow::anyhow!("namespace is not exist")),
             }
-            Self::save_namespace(app_data, &new_infos).await
+            Self::save_namespace(app_data, &infos).await
         } else {
             Err(anyhow::anyhow!("params is empty"))
         }
     }
+
+    ///
+    /// 标记删除由指定工具及资源管理的全部命名空间,在一次命名空间列表写入中完成,返回删除的数量
+    pub async fn remove_managed_namespaces(
+        app_data: &Arc<AppShareData>,
+        managed_by: &str,
+        managed_by_ref: &str,
+    ) -> anyhow::Result<usize> {
+        if managed_by.is_empty() || managed_by_ref.is_empty() {
+            return Err(anyhow::anyhow!("managedBy and managedByRef is required"));
+        }
+        let mut infos = Self::load_namespace_from_config(&app_data.config_addr).await;
+        let now = now_millis_i64();
+        let mut count = 0;
+        for item in infos.iter_mut().filter(|v| {
+            v.deleted_at.is_none() && v.match_managed(Some(managed_by), Some(managed_by_ref))
+        }) {
+            item.deleted_at = Some(now);
+            count += 1;
+        }
+        if count > 0 {
+            Self::save_namespace(app_data, &infos).await?;
+        }
+        Ok(count)
+    }
+
+    pub async fn restore_namespace(
+        app_data: &Arc<AppShareData>,
+        namespace_id: Option<String>,
+    ) -> anyhow::Result<()> {
+        let namespace_id = match namespace_id {
+            Some(v) if !v.is_empty() => v,
+            _ => return Err(anyhow::anyhow!("params is empty")),
+        };
+        let mut infos = Self::load_namespace_from_config(&app_data.config_addr).await;
+        match infos.iter_mut().find(|v| {
+            v.deleted_at.is_some() && v.namespace_id.as_deref() == Some(namespace_id.as_str())
+        }) {
+            Some(item) => item.deleted_at = None,
+            None => return Err(anyhow::anyhow!("deleted namespace is not exist")),
+        }
+        Self::save_namespace(app_data, &infos).await
+    }
+
+    ///
+    /// 删除超过保留期的命名空间下的所有配置,再移除命名空间信息
+    pub async fn purge_deleted_namespaces(
+        app_data: &Arc<AppShareData>,
+        retention_ms: i64,
+    ) -> anyhow::Result<()> {
+        let now = now_millis_i64();
+        let infos = Self::load_namespace_from_config(&app_data.config_addr).await;
+        let (expired, retained): (Vec<_>, Vec<_>) = infos.into_iter().partition(|v| {
+            v.deleted_at
+                .map(|deleted_at| now - deleted_at >= retention_ms)
+                .unwrap_or(false)
+        });
+        if expired.is_empty() {
+            return Ok(());
+        }
+        for item in &expired {
+            let tenant = Arc::new(item.namespace_id.clone().unwrap_or_default());
+            //命名空间整体清除,不保留被依赖的配置;只写入一条raft日志
+            app_data
+                .config_route
+                .purge_namespace(tenant.clone())
+                .await?;
+            log::info!("purge deleted namespace {}", &tenant);
+        }
+        Self::save_namespace(app_data, &retained).await
+    }
 }
@@ -1,8 +1,13 @@
+use crate::config::config_event::ConfigChangeType;
+use crate::config::config_federation::ConsistencyRow;
+use crate::config::config_history_index::ConfigHistorySearchParam;
 use crate::config::config_index::ConfigQueryParam;
+use crate::config::config_pending_changes::PendingChangeStatus;
 use crate::config::core::{ConfigInfoDto, ConfigKey};
 use crate::config::dal::ConfigHistoryParam;
 use crate::config::ConfigUtils;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -86,6 +91,7 @@ pub struct ConfigParams {
     pub content: Option<Arc<String>>,
     pub config_type: Option<Arc<String>>,
     pub desc: Option<Arc<String>>,
+    pub references: Option<Vec<ConfigKey>>,
 }
 
 impl ConfigParams {
@@ -107,3 +113,407 @@ pub struct ConfigInfo {
     pub config_type: Option<Arc<String>>,
     pub desc: Option<Arc<String>>,
 }
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigImportParam {
+    /// 为true时只校验压缩包内的配置,不写入
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigImportUrlParam {
+    pub url: String,
+    pub data_id: Arc<String>,
+    pub group: Option<Arc<String>>,
+    pub tenant: Option<String>,
+    pub r#type: Option<String>,
+    /// 上次拉取返回的etag,上游内容未变更时不会重复写入
+    pub etag: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigDetectTypesParam {
+    pub tenant: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigDetectTypeItem {
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    pub config_type: Arc<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigDetectTypesResult {
+    pub total: usize,
+    pub updated: usize,
+    pub list: Vec<ConfigDetectTypeItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigBulkPatchParam {
+    pub tenant: Option<String>,
+    pub group: String,
+    pub content_patch: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigBulkPatchFailedItem {
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigBulkPatchResult {
+    pub patched: usize,
+    pub skipped: usize,
+    pub failed: Vec<ConfigBulkPatchFailedItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigMigrateFormatParam {
+    pub tenant: Option<String>,
+    pub group: String,
+    pub from_type: String,
+    pub to_type: String,
+    /// 为true时只返回转换后的内容,不写入
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigMigrateFormatItem {
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    //SUCCESS、FAILED或PREVIEW
+    pub status: String,
+    pub new_content: Option<String>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigMigrateFormatResult {
+    pub migrated: usize,
+    pub failed: usize,
+    pub items: Vec<ConfigMigrateFormatItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigOrphansParam {
+    pub tenant: Option<String>,
+    pub inactive_days: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigOrphanKey {
+    pub group: String,
+    pub data_id: String,
+}
+
+///
+/// 删除孤立配置,只删除keys中提交且仍满足不活跃条件的配置
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigOrphansDeleteParam {
+    pub tenant: Option<String>,
+    pub inactive_days: Option<u64>,
+    pub keys: Vec<ConfigOrphanKey>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigHealthParam {
+    pub tenant: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSuggestParam {
+    pub tenant: Option<String>,
+    //dataId前缀
+    pub q: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigFeedParam {
+    pub tenant: Option<String>,
+    pub group: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigAuditExportParam {
+    pub tenant: Option<String>,
+    //yyyy-MM-dd或RFC3339,包含from不包含to
+    pub from: Option<String>,
+    pub to: Option<String>,
+    //目前只支持csv
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigReferencesParam {
+    pub tenant: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigReferenceEdge {
+    pub from: ConfigKey,
+    pub to: ConfigKey,
+    /// 被依赖的配置是否存在
+    pub exists: bool,
+}
+
+///
+/// 配置依赖关系图,nodes包含依赖方与被依赖方
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigReferenceGraph {
+    pub nodes: Vec<ConfigKey>,
+    pub edges: Vec<ConfigReferenceEdge>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSnapshotParam {
+    pub tenant: Option<String>,
+    /// ISO8601时间或毫秒时间戳
+    pub at: Option<String>,
+}
+
+impl ConfigSnapshotParam {
+    pub fn parse_at(&self) -> anyhow::Result<i64> {
+        let at = self.at.as_deref().unwrap_or_default().trim();
+        if at.is_empty() {
+            return Err(anyhow::anyhow!("at is empty"));
+        }
+        if let Ok(v) = at.parse::<i64>() {
+            return Ok(v);
+        }
+        chrono::DateTime::parse_from_rfc3339(at)
+            .map(|v| v.timestamp_millis())
+            .map_err(|err| anyhow::anyhow!("invalid at: {}, {}", at, err))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigOrphanItem {
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    pub last_read_time: i64,
+    pub last_write_time: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigOrphansResult {
+    pub total: usize,
+    pub list: Vec<ConfigOrphanItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigOrphansDeleteResult {
+    pub deleted: Vec<ConfigOrphanKey>,
+    //已不存在或期间有读写的配置
+    pub skipped: Vec<ConfigOrphanKey>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigHistoryCountParam {
+    pub tenant: Option<String>,
+    pub group: Option<String>,
+    pub data_id: Option<String>,
+}
+
+impl ConfigHistoryCountParam {
+    pub fn to_key(&self) -> anyhow::Result<ConfigKey> {
+        let data_id = self.data_id.clone().unwrap_or_default();
+        if data_id.is_empty() {
+            return Err(anyhow::anyhow!("dataId is empty"));
+        }
+        let group = match &self.group {
+            Some(v) if !v.is_empty() => v.to_owned(),
+            _ => "DEFAULT_GROUP".to_owned(),
+        };
+        let tenant = ConfigUtils::default_tenant(self.tenant.clone().unwrap_or_default());
+        Ok(ConfigKey::new(&data_id, &group, &tenant))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigKeyParam {
+    pub tenant: Option<String>,
+    pub group: Option<String>,
+    pub data_id: Option<String>,
+}
+
+impl ConfigKeyParam {
+    pub fn to_key(&self) -> anyhow::Result<ConfigKey> {
+        let data_id = self.data_id.clone().unwrap_or_default();
+        if data_id.is_empty() {
+            return Err(anyhow::anyhow!("dataId is empty"));
+        }
+        let group = match &self.group {
+            Some(v) if !v.is_empty() => v.to_owned(),
+            _ => "DEFAULT_GROUP".to_owned(),
+        };
+        let tenant = ConfigUtils::default_tenant(self.tenant.clone().unwrap_or_default());
+        Ok(ConfigKey::new(&data_id, &group, &tenant))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigAliasParam {
+    pub from: ConfigKeyParam,
+    pub to: ConfigKeyParam,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigPreviewDiffParam {
+    #[serde(flatten)]
+    pub key: ConfigKeyParam,
+    pub new_content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigPreviewDiffResult {
+    //配置不存在时为空
+    pub current_content: Option<Arc<String>>,
+    pub new_content: String,
+    pub diff_html: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChangeCreateParam {
+    #[serde(flatten)]
+    pub key: ConfigKeyParam,
+    pub content: Option<String>,
+    #[serde(rename = "type")]
+    pub config_type: Option<String>,
+    pub desc: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChangeQueryParam {
+    pub status: Option<PendingChangeStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChangeRejectParam {
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigNamespaceParam {
+    pub namespace: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FederationConsistencyResult {
+    pub regions: Vec<String>,
+    pub configs: BTreeMap<String, ConsistencyRow>,
+    //不一致的配置
+    pub inconsistent: Vec<String>,
+    //拉取失败的区域 -> 错误信息
+    pub errors: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigHistorySearchQuery {
+    pub tenant: Option<String>,
+    /// ISO8601时间或毫秒时间戳
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub operator: Option<String>,
+    /// SET或DELETE
+    pub change_type: Option<String>,
+    pub page_no: Option<usize>,
+    pub page_size: Option<usize>,
+}
+
+impl ConfigHistorySearchQuery {
+    fn parse_time(name: &str, value: &Option<String>) -> anyhow::Result<Option<i64>> {
+        let value = value.as_deref().unwrap_or_default().trim();
+        if value.is_empty() {
+            return Ok(None);
+        }
+        if let Ok(v) = value.parse::<i64>() {
+            return Ok(Some(v));
+        }
+        chrono::DateTime::parse_from_rfc3339(value)
+            .map(|v| Some(v.timestamp_millis()))
+            .map_err(|err| anyhow::anyhow!("invalid {}: {}, {}", name, value, err))
+    }
+
+    pub fn to_param(&self) -> anyhow::Result<ConfigHistorySearchParam> {
+        let change_type = match self.change_type.as_deref().map(|v| v.to_uppercase()) {
+            None => None,
+            Some(v) if v.is_empty() => None,
+            Some(v) if v == "SET" => Some(ConfigChangeType::Set),
+            Some(v) if v == "DELETE" => Some(ConfigChangeType::Remove),
+            Some(v) => return Err(anyhow::anyhow!("invalid changeType: {}", v)),
+        };
+        let limit = self.page_size.unwrap_or(20);
+        Ok(ConfigHistorySearchParam {
+            tenant: Arc::new(ConfigUtils::default_tenant(
+                self.tenant.clone().unwrap_or_default(),
+            )),
+            group: None,
+            from: Self::parse_time("from", &self.from)?,
+            to: Self::parse_time("to", &self.to)?,
+            operator: self.operator.clone().filter(|v| !v.is_empty()),
+            change_type,
+            offset: (self.page_no.unwrap_or(1).max(1) - 1) * limit,
+            limit,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSearchParam {
+    pub q: Option<String>,
+    pub tenant: Option<String>,
+    pub page_no: Option<usize>,
+    pub page_size: Option<usize>,
+}
+
+impl ConfigImportUrlParam {
+    pub fn to_key(&self) -> ConfigKey {
+        let group = self
+            .group
+            .clone()
+            .unwrap_or(Arc::new("DEFAULT_GROUP".to_owned()));
+        let tenant = ConfigUtils::default_tenant(self.tenant.clone().unwrap_or_default());
+        ConfigKey::new_by_arc(self.data_id.clone(), group, Arc::new(tenant))
+    }
+}
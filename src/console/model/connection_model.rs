@@ -0,0 +1,16 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::core::ConfigKey;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionInfo {
+    /// 连接id,即客户端地址
+    pub id: Arc<String>,
+    pub remote_addr: Arc<String>,
+    pub created_at: u64,
+    pub last_active_time: u64,
+    pub subscriptions: Vec<ConfigKey>,
+}
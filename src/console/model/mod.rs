@@ -1,11 +1,16 @@
 pub mod cluster_model;
 pub mod config_model;
+pub mod connection_model;
 pub mod login_model;
 pub mod naming_model;
 pub mod raft_model;
 pub mod user_model;
 
-use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::config::config_federation::FilterRule;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -13,6 +18,109 @@ pub struct NamespaceInfo {
     pub namespace_id: Option<String>,
     pub namespace_name: Option<String>,
     pub r#type: Option<String>,
+    //配置变更通知的合并时长,为空时使用全局默认值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debounce_ms: Option<u64>,
+    //单个配置每秒最多触发的变更通知次数,超过后冷却期内只通知最近一次变更
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_change_rate_per_key: Option<f64>,
+    //模板命名空间下的配置只读
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_template: Option<bool>,
+    //创建命名空间时复制该模板命名空间下的配置,不保存
+    #[serde(default, skip_serializing)]
+    pub template_namespace_id: Option<String>,
+    //删除时间(毫秒),保留期内可恢复,过期后清除命名空间及其配置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<i64>,
+    //向联邦中其它区域传输配置前应用的字段过滤规则
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replication_filter: Option<Vec<FilterRule>>,
+    //写入json/yaml配置时自动加密的字段路径规则,如`*.password`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_encrypt_patterns: Option<Vec<String>>,
+    //用于筛选、归类命名空间的标签,如`env=prod`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_labels"
+    )]
+    pub labels: Option<HashMap<String, String>>,
+    //管理该命名空间的自动化工具,如`terraform`,为空时为手动创建
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub managed_by: Option<String>,
+    //管理工具中对应的资源标识,如terraform的资源地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub managed_by_ref: Option<String>,
+}
+
+impl NamespaceInfo {
+    ///
+    /// 未指定的条件不参与匹配
+    pub fn match_managed(&self, managed_by: Option<&str>, managed_by_ref: Option<&str>) -> bool {
+        managed_by
+            .map(|v| self.managed_by.as_deref() == Some(v))
+            .unwrap_or(true)
+            && managed_by_ref
+                .map(|v| self.managed_by_ref.as_deref() == Some(v))
+                .unwrap_or(true)
+    }
+
+    ///
+    /// 包含所有指定标签时返回true
+    pub fn match_labels(&self, labels: &[(String, String)]) -> bool {
+        labels.iter().all(|(k, v)| {
+            self.labels
+                .as_ref()
+                .and_then(|m| m.get(k))
+                .map(|item| item == v)
+                .unwrap_or(false)
+        })
+    }
+}
+
+///
+/// 解析`key=value`格式的标签
+pub fn parse_label(v: &str) -> Option<(String, String)> {
+    let (key, value) = v.split_once('=')?;
+    let key = key.trim();
+    if key.is_empty() {
+        return None;
+    }
+    Some((key.to_owned(), value.trim().to_owned()))
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LabelsValue {
+    Map(HashMap<String, String>),
+    Text(String),
+}
+
+///
+/// 标签支持json对象,表单中按`key=value,key2=value2`格式传入
+fn deserialize_labels<'de, D>(deserializer: D) -> Result<Option<HashMap<String, String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(
+        Option::<LabelsValue>::deserialize(deserializer)?.map(|v| match v {
+            LabelsValue::Map(map) => map,
+            LabelsValue::Text(text) => text.split(',').filter_map(parse_label).collect(),
+        }),
+    )
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceQueryParam {
+    //为true时只返回模板命名空间
+    pub is_template: Option<bool>,
+    //为true时同时返回已删除、保留期内的命名空间
+    pub include_deleted: Option<bool>,
+    //只返回由该工具管理的命名空间
+    pub managed_by: Option<String>,
+    pub managed_by_ref: Option<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Default)]
@@ -55,3 +163,38 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namespace_labels() {
+        let info: NamespaceInfo =
+            serde_urlencoded::from_str("namespaceId=dev&labels=env%3Dprod%2Cteam%3Dpay").unwrap();
+        let labels = info.labels.as_ref().unwrap();
+        assert_eq!(labels.get("env").unwrap(), "prod");
+        assert_eq!(labels.get("team").unwrap(), "pay");
+        let info: NamespaceInfo =
+            serde_json::from_str(r#"{"namespaceId":"dev","labels":{"env":"prod"}}"#).unwrap();
+        assert!(info.match_labels(&[parse_label("env=prod").unwrap()]));
+        assert!(!info.match_labels(&[
+            parse_label("env=prod").unwrap(),
+            parse_label("team=pay").unwrap()
+        ]));
+        assert!(parse_label("=prod").is_none());
+    }
+
+    #[test]
+    fn namespace_managed_by() {
+        let info: NamespaceInfo = serde_urlencoded::from_str(
+            "namespaceId=dev&managedBy=terraform&managedByRef=module.rnacos",
+        )
+        .unwrap();
+        assert!(info.match_managed(Some("terraform"), None));
+        assert!(info.match_managed(Some("terraform"), Some("module.rnacos")));
+        assert!(!info.match_managed(Some("terraform"), Some("module.other")));
+        assert!(!NamespaceInfo::default().match_managed(Some("terraform"), None));
+        assert!(NamespaceInfo::default().match_managed(None, None));
+    }
+}
@@ -0,0 +1,1014 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use actix_multipart::form::tempfile::TempFile;
+use actix_multipart::form::MultipartForm;
+use actix_web::{http::header, web, HttpResponse, Responder};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::Sender;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+
+use crate::common::appdata::AppShareData;
+use crate::config::config_index::ConfigQueryParam;
+use crate::config::config_namespace_merge::{
+    NamespaceMergeConflict, NamespaceMergePlan, NamespaceMergePolicy,
+};
+use crate::config::config_namespace_stats::NamespaceStats;
+use crate::config::config_read_view::ConfigReadValue;
+use crate::config::core::{ConfigCmd, ConfigKey, ConfigResult};
+use crate::config::model::ConfigSetResult;
+use crate::config::ConfigUtils;
+use crate::console::model::{ConsoleResult, NamespaceInfo};
+use crate::console::{NamespaceUtils, SYSCONFIG_NAMESPACE};
+use crate::raft::cluster::model::SetConfigReq;
+use crate::utils::get_md5;
+use crate::{now_millis, now_millis_i64};
+use uuid::Uuid;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const CONFIG_META_FILE: &str = "configs.json";
+const CONFIG_DIR: &str = "configs/";
+const BACKUP_VERSION: u32 = 1;
+const SECTION_NAMESPACE: &str = "namespace";
+const SECTION_CONFIG: &str = "config";
+const COPY_BATCH_SIZE: usize = 100;
+/// 导入归档解压后的总大小上限,避免压缩炸弹耗尽内存
+const IMPORT_MAX_ARCHIVE_BYTES: u64 = 512 * 1024 * 1024;
+
+///
+/// 导入时命名空间已存在的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamespaceImportPolicy {
+    //只导入不存在的配置
+    Merge,
+    //覆盖命名空间信息及所有配置
+    Overwrite,
+    //命名空间已存在时跳过
+    Skip,
+}
+
+impl FromStr for NamespaceImportPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "MERGE" => Ok(Self::Merge),
+            "OVERWRITE" => Ok(Self::Overwrite),
+            "SKIP" => Ok(Self::Skip),
+            _ => Err(anyhow::anyhow!("unknown import policy: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceBackupManifest {
+    pub version: u32,
+    pub export_time: i64,
+    pub namespace: NamespaceInfo,
+    //归档中包含的数据类型
+    pub sections: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceBackupConfigItem {
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    pub md5: Option<Arc<String>>,
+    pub config_type: Option<Arc<String>>,
+    pub desc: Option<Arc<String>>,
+}
+
+impl NamespaceBackupConfigItem {
+    fn content_path(&self) -> String {
+        format!("{}{}/{}", CONFIG_DIR, &self.group, &self.data_id)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct NamespaceBackupArchive {
+    pub manifest: NamespaceBackupManifest,
+    pub configs: Vec<(NamespaceBackupConfigItem, Arc<String>)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceImportResult {
+    pub namespace_id: String,
+    pub namespace_skipped: bool,
+    pub success_count: usize,
+    pub skip_count: usize,
+    pub fail_count: usize,
+}
+
+///
+/// 预览导入时归档中配置与已有配置的对比结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum NamespaceImportStatus {
+    New,
+    //已有配置在导出后未修改,只有归档中的内容不同
+    Update,
+    Unchanged,
+    //已有配置在导出后也被修改,两边内容都不同
+    Conflict,
+    //配置key不合法,无法导入
+    Invalid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum NamespaceImportAction {
+    Create,
+    Overwrite,
+    Skip,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceImportPreviewItem {
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    pub status: NamespaceImportStatus,
+    pub action: NamespaceImportAction,
+    //冲突时返回已有内容与导入内容用于对比
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stored_content: Option<Arc<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub incoming_content: Option<Arc<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceImportPreview {
+    pub namespace_id: String,
+    pub namespace_exists: bool,
+    pub namespace_skipped: bool,
+    pub create_count: usize,
+    pub overwrite_count: usize,
+    pub skip_count: usize,
+    pub items: Vec<NamespaceImportPreviewItem>,
+}
+
+impl NamespaceImportStatus {
+    ///
+    /// stored为已有配置的(md5,最近写入时间);写入时间为raft复制的写入时间,重启或在其它节点预览时结果一致;
+    /// 已有配置在导出后写入过且内容不同时视为冲突
+    fn classify(stored: Option<(&str, i64)>, incoming_md5: &str, export_time: i64) -> Self {
+        match stored {
+            None => Self::New,
+            Some((md5, _)) if md5 == incoming_md5 => Self::Unchanged,
+            Some((_, last_write_ms)) if last_write_ms > export_time => Self::Conflict,
+            Some(_) => Self::Update,
+        }
+    }
+}
+
+impl NamespaceImportPolicy {
+    ///
+    /// 与import的处理保持一致:Merge只导入不存在的配置,Skip在命名空间已存在时整体跳过
+    fn action(
+        &self,
+        namespace_exists: bool,
+        status: NamespaceImportStatus,
+    ) -> NamespaceImportAction {
+        match (self, status) {
+            (_, NamespaceImportStatus::Invalid) => NamespaceImportAction::Skip,
+            (NamespaceImportPolicy::Skip, _) if namespace_exists => NamespaceImportAction::Skip,
+            (_, NamespaceImportStatus::New) => NamespaceImportAction::Create,
+            (NamespaceImportPolicy::Merge, _) => NamespaceImportAction::Skip,
+            (_, NamespaceImportStatus::Unchanged) => NamespaceImportAction::Skip,
+            _ => NamespaceImportAction::Overwrite,
+        }
+    }
+}
+
+pub struct NamespaceBackup;
+
+impl NamespaceBackup {
+    ///
+    /// 导出命名空间信息及其下所有配置为tar.gz
+    pub async fn export(app: &Arc<AppShareData>, namespace_id: &str) -> anyhow::Result<Vec<u8>> {
+        let tenant = ConfigUtils::default_tenant(namespace_id.to_owned());
+        let namespace = NamespaceUtils::get_namespaces(&app.config_addr)
+            .await
+            .into_iter()
+            .find(|e| e.namespace_id.as_deref() == Some(tenant.as_str()))
+            .ok_or_else(|| anyhow::anyhow!("namespace not exist"))?;
+        let param = ConfigQueryParam {
+            tenant: Some(Arc::new(tenant)),
+            query_context: true,
+            limit: 0xffff_ffff,
+            ..Default::default()
+        };
+        let list = match app
+            .send_config_cmd(ConfigCmd::QueryPageInfo(Box::new(param)))
+            .await?
+        {
+            ConfigResult::ConfigInfoPage(_, list) => list,
+            _ => return Err(anyhow::anyhow!("config result error")),
+        };
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        let mut items = Vec::with_capacity(list.len());
+        for config in list {
+            let key = ConfigKey::new_by_arc(
+                config.data_id.clone(),
+                config.group.clone(),
+                config.tenant.clone(),
+            );
+            let read_value = app.config_read_view.get(&key);
+            let item = NamespaceBackupConfigItem {
+                group: config.group,
+                data_id: config.data_id,
+                md5: config.md5,
+                config_type: read_value.as_ref().and_then(|v| v.config_type.clone()),
+                desc: read_value.as_ref().and_then(|v| v.desc.clone()),
+            };
+            let content = config.content.unwrap_or_default();
+            Self::append_file(&mut builder, &item.content_path(), content.as_bytes())?;
+            items.push(item);
+        }
+        let manifest = NamespaceBackupManifest {
+            version: BACKUP_VERSION,
+            export_time: now_millis_i64(),
+            namespace: namespace.as_ref().clone(),
+            sections: vec![SECTION_NAMESPACE.to_owned(), SECTION_CONFIG.to_owned()],
+        };
+        Self::append_file(
+            &mut builder,
+            CONFIG_META_FILE,
+            serde_json::to_string(&items)?.as_bytes(),
+        )?;
+        Self::append_file(
+            &mut builder,
+            MANIFEST_FILE,
+            serde_json::to_string(&manifest)?.as_bytes(),
+        )?;
+        let encoder = builder.into_inner()?;
+        Ok(encoder.finish()?)
+    }
+
+    fn append_file<W: Write>(
+        builder: &mut tar::Builder<W>,
+        path: &str,
+        data: &[u8],
+    ) -> anyhow::Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(now_millis() / 1000);
+        header.set_cksum();
+        builder.append_data(&mut header, path, data)?;
+        Ok(())
+    }
+
+    ///
+    /// 解析导出的归档,单个文件超过max_content或解压后总大小超过max_total时报错
+    pub fn read_archive<R: Read>(
+        reader: R,
+        max_content: usize,
+        max_total: u64,
+    ) -> anyhow::Result<NamespaceBackupArchive> {
+        let mut archive = tar::Archive::new(GzDecoder::new(reader));
+        let mut manifest: Option<NamespaceBackupManifest> = None;
+        let mut items: Vec<NamespaceBackupConfigItem> = vec![];
+        let mut contents = HashMap::new();
+        let mut total = 0u64;
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if entry.size() > max_content as u64 {
+                return Err(anyhow::anyhow!("archive file is too large"));
+            }
+            let path = entry.path()?.to_string_lossy().to_string();
+            if path != MANIFEST_FILE && path != CONFIG_META_FILE && !path.starts_with(CONFIG_DIR) {
+                continue;
+            }
+            total += entry.size();
+            if total > max_total {
+                return Err(anyhow::anyhow!(
+                    "archive is too large, limit {} bytes",
+                    max_total
+                ));
+            }
+            let mut buf = String::new();
+            entry.read_to_string(&mut buf)?;
+            if path == MANIFEST_FILE {
+                manifest = Some(serde_json::from_str(&buf)?);
+            } else if path == CONFIG_META_FILE {
+                items = serde_json::from_str(&buf)?;
+            } else if path.starts_with(CONFIG_DIR) {
+                contents.insert(path, Arc::new(buf));
+            }
+        }
+        let manifest = manifest.ok_or_else(|| anyhow::anyhow!("manifest.json not found"))?;
+        if manifest.version > BACKUP_VERSION {
+            return Err(anyhow::anyhow!(
+                "unsupported archive version: {}",
+                manifest.version
+            ));
+        }
+        let mut configs = Vec::with_capacity(items.len());
+        for item in items {
+            if let Some(content) = contents.remove(&item.content_path()) {
+                configs.push((item, content));
+            }
+        }
+        Ok(NamespaceBackupArchive { manifest, configs })
+    }
+
+    ///
+    /// 不写入数据,返回按策略导入时各配置的对比结果及处理方式
+    pub async fn preview(
+        app: &Arc<AppShareData>,
+        archive: NamespaceBackupArchive,
+        policy: NamespaceImportPolicy,
+    ) -> anyhow::Result<NamespaceImportPreview> {
+        let namespace = archive.manifest.namespace;
+        let tenant = ConfigUtils::default_tenant(namespace.namespace_id.unwrap_or_default());
+        let namespace_exists = tenant.is_empty()
+            || NamespaceUtils::load_namespace_from_config(&app.config_addr)
+                .await
+                .iter()
+                .any(|e| e.namespace_id.as_deref() == Some(tenant.as_str()));
+        let mut preview = NamespaceImportPreview {
+            namespace_id: tenant.clone(),
+            namespace_exists,
+            namespace_skipped: namespace_exists && policy == NamespaceImportPolicy::Skip,
+            ..Default::default()
+        };
+        let export_time = archive.manifest.export_time;
+        let tenant = Arc::new(tenant);
+        for (item, content) in archive.configs {
+            let key =
+                ConfigKey::new_by_arc(item.data_id.clone(), item.group.clone(), tenant.clone());
+            let valid = key.is_valid().is_ok();
+            let stored = if valid {
+                app.config_read_view.get(&key)
+            } else {
+                None
+            };
+            let status = if !valid {
+                NamespaceImportStatus::Invalid
+            } else {
+                NamespaceImportStatus::classify(
+                    stored.as_ref().map(|v| (v.md5.as_str(), v.last_modified)),
+                    &get_md5(&content),
+                    export_time,
+                )
+            };
+            let action = policy.action(namespace_exists, status);
+            match action {
+                NamespaceImportAction::Create => preview.create_count += 1,
+                NamespaceImportAction::Overwrite => preview.overwrite_count += 1,
+                NamespaceImportAction::Skip => preview.skip_count += 1,
+            }
+            let conflict = status == NamespaceImportStatus::Conflict;
+            let stored_content = match stored.filter(|_| conflict) {
+                Some(v) => Some(app.load_archived(v).await?.content.clone()),
+                None => None,
+            };
+            preview.items.push(NamespaceImportPreviewItem {
+                group: item.group,
+                data_id: item.data_id,
+                status,
+                action,
+                stored_content,
+                incoming_content: if conflict { Some(content) } else { None },
+            });
+        }
+        Ok(preview)
+    }
+
+    ///
+    /// 按策略恢复命名空间及配置
+    pub async fn import(
+        app: &Arc<AppShareData>,
+        archive: NamespaceBackupArchive,
+        policy: NamespaceImportPolicy,
+    ) -> anyhow::Result<NamespaceImportResult> {
+        let namespace = archive.manifest.namespace;
+        let tenant = ConfigUtils::default_tenant(namespace.namespace_id.unwrap_or_default());
+        let mut result = NamespaceImportResult {
+            namespace_id: tenant.clone(),
+            ..Default::default()
+        };
+        let mut infos = NamespaceUtils::load_namespace_from_config(&app.config_addr).await;
+        let exist_index = infos
+            .iter()
+            .position(|e| e.namespace_id.as_deref() == Some(tenant.as_str()));
+        let exist = tenant.is_empty() || exist_index.is_some();
+        if exist && policy == NamespaceImportPolicy::Skip {
+            result.namespace_skipped = true;
+            return Ok(result);
+        }
+        match exist_index {
+            Some(index) => {
+                if policy == NamespaceImportPolicy::Overwrite && namespace.namespace_name.is_some()
+                {
+                    infos[index].namespace_name = namespace.namespace_name;
+                    NamespaceUtils::save_namespace(app, &infos).await?;
+                }
+            }
+            None => {
+                if !tenant.is_empty() {
+                    infos.push(NamespaceInfo {
+                        namespace_id: Some(tenant.clone()),
+                        namespace_name: namespace.namespace_name.or(Some(tenant.clone())),
+                        r#type: Some("2".to_owned()),
+                        debounce_ms: namespace.debounce_ms,
+                        max_change_rate_per_key: namespace.max_change_rate_per_key,
+                        //导入后再按需标记为模板,否则配置无法写入
+                        is_template: None,
+                        template_namespace_id: None,
+                        deleted_at: None,
+                        replication_filter: namespace.replication_filter,
+                        auto_encrypt_patterns: namespace.auto_encrypt_patterns,
+                        labels: namespace.labels,
+                        managed_by: namespace.managed_by,
+                        managed_by_ref: namespace.managed_by_ref,
+                    });
+                    NamespaceUtils::save_namespace(app, &infos).await?;
+                }
+            }
+        }
+        let tenant = Arc::new(tenant);
+        for (item, content) in archive.configs {
+            let key = ConfigKey::new_by_arc(item.data_id, item.group, tenant.clone());
+            if key.is_valid().is_err() {
+                result.fail_count += 1;
+                continue;
+            }
+            if policy == NamespaceImportPolicy::Merge && app.config_read_view.get(&key).is_some() {
+                result.skip_count += 1;
+                continue;
+            }
+            let mut req = SetConfigReq::new(key, content);
+            req.config_type = item.config_type;
+            req.desc = item.desc;
+            match app.config_route.set_config(req).await {
+                Ok(_) => result.success_count += 1,
+                Err(err) => {
+                    log::warn!("namespace import config error,{}", err);
+                    result.fail_count += 1;
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceCopyParam {
+    pub src_namespace: Option<String>,
+    pub dst_namespace: Option<String>,
+    pub overwrite: Option<bool>,
+    //上次复制进度中返回的游标,用于中断后继续复制
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceCopyProgress {
+    //已处理的源配置数(含未覆盖而跳过的)
+    pub copied: usize,
+    pub skipped: usize,
+    pub total: usize,
+    pub cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub struct NamespaceCopy;
+
+impl NamespaceCopy {
+    ///
+    /// 游标为最后一个已复制的源配置key,返回下一个待复制配置的位置
+    fn cursor_position(list: &[(ConfigKey, ConfigReadValue)], cursor: &Option<String>) -> usize {
+        match cursor.as_deref().map(ConfigKey::from_str) {
+            Some(Ok(cursor)) => list.partition_point(|(k, _)| {
+                (&k.group, &k.data_id) <= (&cursor.group, &cursor.data_id)
+            }),
+            _ => 0,
+        }
+    }
+
+    ///
+    /// 校验源与目标命名空间存在且不相同
+    pub(crate) async fn check_namespaces(
+        app: &Arc<AppShareData>,
+        src: Option<String>,
+        dst: Option<String>,
+    ) -> anyhow::Result<(String, String)> {
+        let src = ConfigUtils::default_tenant(src.unwrap_or_default());
+        let dst = ConfigUtils::default_tenant(dst.unwrap_or_default());
+        if src == dst {
+            return Err(anyhow::anyhow!(
+                "srcNamespace and dstNamespace are the same"
+            ));
+        }
+        let namespaces = NamespaceUtils::get_namespaces(&app.config_addr).await;
+        for tenant in [&src, &dst] {
+            if !namespaces
+                .iter()
+                .any(|e| e.namespace_id.as_deref() == Some(tenant.as_str()))
+            {
+                return Err(anyhow::anyhow!("namespace not exist: {}", tenant));
+            }
+        }
+        Ok((src, dst))
+    }
+
+    ///
+    /// 按批复制源命名空间下的配置到目标命名空间,每批一个raft提案,每批完成后发送一次进度
+    pub async fn copy(
+        app: &Arc<AppShareData>,
+        param: NamespaceCopyParam,
+        sender: &Sender<NamespaceCopyProgress>,
+    ) -> anyhow::Result<()> {
+        let (src, dst) =
+            Self::check_namespaces(app, param.src_namespace, param.dst_namespace).await?;
+        let list = match app
+            .send_config_cmd(ConfigCmd::ListAll(Arc::new(src)))
+            .await?
+        {
+            ConfigResult::ConfigList(list) => list,
+            _ => return Err(anyhow::anyhow!("config result error")),
+        };
+        let overwrite = param.overwrite.unwrap_or(true);
+        let start = Self::cursor_position(&list, &param.cursor);
+        let mut progress = NamespaceCopyProgress {
+            copied: start,
+            total: list.len(),
+            cursor: param.cursor,
+            ..Default::default()
+        };
+        let dst = Arc::new(dst);
+        for batch in list[start..].chunks(COPY_BATCH_SIZE) {
+            let mut reqs = Vec::with_capacity(batch.len());
+            for (key, value) in batch {
+                let dst_key =
+                    ConfigKey::new_by_arc(key.data_id.clone(), key.group.clone(), dst.clone());
+                if !overwrite && app.config_read_view.get(&dst_key).is_some() {
+                    progress.skipped += 1;
+                    continue;
+                }
+                let mut req = SetConfigReq::new(dst_key, value.content.clone());
+                req.config_type = value.config_type.clone();
+                req.desc = value.desc.clone();
+                reqs.push(req);
+            }
+            if let Err(err) = app.config_route.batch_set_config(reqs, None).await {
+                log::warn!("namespace copy config error,{}", err);
+                progress.error = Some(err.to_string());
+                sender.send(progress).await.ok();
+                return Ok(());
+            }
+            progress.copied += batch.len();
+            progress.cursor = batch.last().map(|(k, _)| k.to_string());
+            if sender.send(progress.clone()).await.is_err() {
+                //客户端已断开,可凭游标继续
+                break;
+            }
+        }
+        if list.len() == start {
+            sender.send(progress).await.ok();
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceMergeParam {
+    pub src_namespace: Option<String>,
+    pub dst_namespace: Option<String>,
+    /// SRC_WINS|DST_WINS|MANUAL
+    pub conflict_policy: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceMergeResolution {
+    pub group: Option<String>,
+    pub data_id: Option<String>,
+    /// SRC或DST
+    pub resolution: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceMergeResolveParam {
+    pub resolutions: Vec<NamespaceMergeResolution>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceMergeResult {
+    //等待手动解决冲突时返回
+    pub id: Option<Arc<String>>,
+    //PENDING、COMPLETED或ABORTED
+    pub status: String,
+    pub written: usize,
+    pub conflicts: Vec<NamespaceMergeConflict>,
+    //合并期间目标配置被修改的配置,此时整批不写入
+    pub failed: Vec<ConfigKey>,
+}
+
+pub struct NamespaceMerge;
+
+impl NamespaceMerge {
+    pub async fn merge(
+        app: &Arc<AppShareData>,
+        param: NamespaceMergeParam,
+    ) -> anyhow::Result<NamespaceMergeResult> {
+        let policy =
+            NamespaceMergePolicy::from_str(param.conflict_policy.as_deref().unwrap_or_default())?;
+        let (src, dst) =
+            NamespaceCopy::check_namespaces(app, param.src_namespace, param.dst_namespace).await?;
+        let src_list = Self::list_all(app, src).await?;
+        let dst_list = Self::list_all(app, dst).await?;
+        let plan = NamespaceMergePlan::build(src_list.0, dst_list.0, src_list.1, dst_list.1);
+        let conflicts = plan.conflicts();
+        if policy == NamespaceMergePolicy::Manual && !conflicts.is_empty() {
+            let id = Arc::new(Uuid::new_v4().to_string());
+            app.namespace_merge_store
+                .insert(id.clone(), plan, now_millis_i64());
+            return Ok(NamespaceMergeResult {
+                id: Some(id),
+                status: "PENDING".to_owned(),
+                conflicts,
+                ..Default::default()
+            });
+        }
+        let reqs = plan.to_reqs(|_| policy == NamespaceMergePolicy::SrcWins);
+        Self::apply(app, reqs).await
+    }
+
+    pub async fn resolve(
+        app: &Arc<AppShareData>,
+        id: Arc<String>,
+        param: NamespaceMergeResolveParam,
+    ) -> anyhow::Result<NamespaceMergeResult> {
+        let plan = match app.namespace_merge_store.get(&id, now_millis_i64()) {
+            Some(v) => v,
+            None => return Err(anyhow::anyhow!("merge is not exist or expired: {}", &id)),
+        };
+        let mut use_src = HashMap::new();
+        for item in param.resolutions {
+            let group = match item.group {
+                Some(v) if !v.is_empty() => v,
+                _ => "DEFAULT_GROUP".to_owned(),
+            };
+            let resolution = item.resolution.unwrap_or_default().to_uppercase();
+            let v = match resolution.as_str() {
+                "SRC" => true,
+                "DST" => false,
+                _ => return Err(anyhow::anyhow!("unknown resolution: {}", resolution)),
+            };
+            use_src.insert((group, item.data_id.unwrap_or_default()), v);
+        }
+        let unresolved: Vec<String> = plan
+            .conflicts()
+            .into_iter()
+            .filter(|v| !use_src.contains_key(&(v.group.to_string(), v.data_id.to_string())))
+            .map(|v| format!("{}/{}", v.group, v.data_id))
+            .collect();
+        if !unresolved.is_empty() {
+            return Err(anyhow::anyhow!(
+                "unresolved conflicts: {}",
+                unresolved.join(",")
+            ));
+        }
+        //只允许解决一次
+        if !app.namespace_merge_store.remove(&id) {
+            return Err(anyhow::anyhow!("merge is not exist or expired: {}", &id));
+        }
+        let reqs = plan.to_reqs(|key| {
+            use_src
+                .get(&(key.group.to_string(), key.data_id.to_string()))
+                .copied()
+                .unwrap_or(false)
+        });
+        Self::apply(app, reqs).await
+    }
+
+    async fn list_all(
+        app: &Arc<AppShareData>,
+        tenant: String,
+    ) -> anyhow::Result<(Arc<String>, Vec<(ConfigKey, ConfigReadValue)>)> {
+        let tenant = Arc::new(tenant);
+        match app
+            .send_config_cmd(ConfigCmd::ListAll(tenant.clone()))
+            .await?
+        {
+            ConfigResult::ConfigList(list) => Ok((tenant, list)),
+            _ => Err(anyhow::anyhow!("config result error")),
+        }
+    }
+
+    ///
+    /// 所有写入在同一个raft提案中按md5原子提交,任一目标配置在合并期间被修改时整批不写入
+    async fn apply(
+        app: &Arc<AppShareData>,
+        reqs: Vec<SetConfigReq>,
+    ) -> anyhow::Result<NamespaceMergeResult> {
+        let keys: Vec<ConfigKey> = reqs.iter().map(|v| v.config_key.clone()).collect();
+        let results = app.config_route.multi_set_config(reqs, None).await?;
+        let mut result = NamespaceMergeResult {
+            status: "COMPLETED".to_owned(),
+            ..Default::default()
+        };
+        for (key, item) in keys.into_iter().zip(results) {
+            match item {
+                ConfigSetResult::Success => result.written += 1,
+                ConfigSetResult::Md5Mismatch { .. } => result.failed.push(key),
+                ConfigSetResult::Aborted => {}
+            }
+        }
+        if !result.failed.is_empty() {
+            result.status = "ABORTED".to_owned();
+            result.written = 0;
+        }
+        Ok(result)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceBackupParam {
+    pub namespace_id: Option<String>,
+    pub policy: Option<String>,
+    //只返回导入预览,不写入
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, MultipartForm)]
+pub struct NamespaceImportForm {
+    #[multipart(rename = "file")]
+    pub files: Vec<TempFile>,
+}
+
+///
+/// 导出命名空间完整备份
+pub async fn export_namespace(
+    web::Query(param): web::Query<NamespaceBackupParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let namespace_id = param.namespace_id.unwrap_or_default();
+    match NamespaceBackup::export(&app, &namespace_id).await {
+        Ok(buf) => {
+            let filename = format!(
+                "rnacos_namespace_export_{}_{}.tar.gz",
+                if namespace_id.is_empty() {
+                    "public"
+                } else {
+                    &namespace_id
+                },
+                now_millis()
+            );
+            HttpResponse::Ok()
+                .insert_header(header::ContentType::octet_stream())
+                .insert_header(header::ContentDisposition::attachment(filename))
+                .body(buf)
+        }
+        Err(err) => HttpResponse::Ok().json(ConsoleResult::<()>::error(err.to_string())),
+    }
+}
+
+///
+/// 从完整备份恢复命名空间
+pub async fn import_namespace(
+    web::Query(param): web::Query<NamespaceBackupParam>,
+    MultipartForm(form): MultipartForm<NamespaceImportForm>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let policy = match param
+        .policy
+        .as_deref()
+        .map(NamespaceImportPolicy::from_str)
+        .unwrap_or(Ok(NamespaceImportPolicy::Merge))
+    {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::Ok().json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    let file = match form.files.into_iter().next() {
+        Some(v) => v,
+        None => {
+            return HttpResponse::Ok().json(ConsoleResult::<()>::error("file is empty".to_owned()));
+        }
+    };
+    let archive = match NamespaceBackup::read_archive(
+        file.file,
+        app.sys_config.config_max_content,
+        IMPORT_MAX_ARCHIVE_BYTES,
+    ) {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::Ok().json(ConsoleResult::<()>::error(err.to_string()));
+        }
+    };
+    if param.dry_run.unwrap_or(false) {
+        return match NamespaceBackup::preview(&app, archive, policy).await {
+            Ok(preview) => HttpResponse::Ok().json(ConsoleResult::success(preview)),
+            Err(err) => HttpResponse::Ok().json(ConsoleResult::<()>::error(err.to_string())),
+        };
+    }
+    match NamespaceBackup::import(&app, archive, policy).await {
+        Ok(result) => HttpResponse::Ok().json(ConsoleResult::success(result)),
+        Err(err) => HttpResponse::Ok().json(ConsoleResult::<()>::error(err.to_string())),
+    }
+}
+
+///
+/// 复制命名空间下的配置,以SSE返回复制进度
+pub async fn copy_namespace(
+    web::Json(param): web::Json<NamespaceCopyParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let (tx, rx) = tokio::sync::mpsc::channel(16);
+    let app = app.get_ref().clone();
+    actix_web::rt::spawn(async move {
+        if let Err(err) = NamespaceCopy::copy(&app, param, &tx).await {
+            let progress = NamespaceCopyProgress {
+                error: Some(err.to_string()),
+                ..Default::default()
+            };
+            tx.send(progress).await.ok();
+        }
+    });
+    let stream = ReceiverStream::new(rx).map(|progress| {
+        let data = serde_json::to_string(&progress).unwrap_or_default();
+        Ok::<_, actix_web::Error>(web::Bytes::from(format!("data: {}\n\n", data)))
+    });
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        //避免被压缩中间件缓冲
+        .insert_header((header::CONTENT_ENCODING, "identity"))
+        .streaming(stream)
+}
+
+///
+/// 合并源命名空间的配置到目标命名空间;MANUAL策略存在冲突时返回冲突列表等待解决
+pub async fn merge_namespace(
+    web::Json(param): web::Json<NamespaceMergeParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    match NamespaceMerge::merge(&app, param).await {
+        Ok(result) => HttpResponse::Ok().json(ConsoleResult::success(result)),
+        Err(err) => HttpResponse::Ok().json(ConsoleResult::<()>::error(err.to_string())),
+    }
+}
+
+pub async fn resolve_namespace_merge(
+    path: web::Path<String>,
+    web::Json(param): web::Json<NamespaceMergeResolveParam>,
+    app: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let id = Arc::new(path.into_inner());
+    match NamespaceMerge::resolve(&app, id, param).await {
+        Ok(result) => HttpResponse::Ok().json(ConsoleResult::success(result)),
+        Err(err) => HttpResponse::Ok().json(ConsoleResult::<()>::error(err.to_string())),
+    }
+}
+
+///
+/// 查询各命名空间的配置统计,结果缓存30秒
+pub async fn query_namespace_stats(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    if let Some(list) = app.namespace_stats_cache.get() {
+        return HttpResponse::Ok().json(ConsoleResult::success(list));
+    }
+    let mut list = match app.send_config_cmd(ConfigCmd::QueryNamespaceStats).await {
+        Ok(ConfigResult::NamespaceStats(list)) => list,
+        Err(err) => {
+            return HttpResponse::Ok().json(ConsoleResult::<()>::error(err.to_string()));
+        }
+        _ => {
+            return HttpResponse::Ok()
+                .json(ConsoleResult::<()>::error("config result error".to_owned()));
+        }
+    };
+    list.retain(|e| e.namespace_id.as_str() != SYSCONFIG_NAMESPACE);
+    //没有配置的命名空间也返回统计
+    for namespace in NamespaceUtils::get_namespaces(&app.config_addr).await {
+        let namespace_id = namespace.namespace_id.clone().unwrap_or_default();
+        if !list.iter().any(|e| e.namespace_id.as_str() == namespace_id) {
+            list.push(NamespaceStats {
+                namespace_id: Arc::new(namespace_id),
+                ..Default::default()
+            });
+        }
+    }
+    list.sort_by(|a, b| a.namespace_id.cmp(&b.namespace_id));
+    let list = app.namespace_stats_cache.set(list);
+    HttpResponse::Ok().json(ConsoleResult::success(list))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_exported_archive() {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        let item = NamespaceBackupConfigItem {
+            group: Arc::new("DEFAULT_GROUP".to_owned()),
+            data_id: Arc::new("app.yaml".to_owned()),
+            ..Default::default()
+        };
+        NamespaceBackup::append_file(&mut builder, &item.content_path(), b"a: 1").unwrap();
+        NamespaceBackup::append_file(
+            &mut builder,
+            CONFIG_META_FILE,
+            serde_json::to_string(&vec![item]).unwrap().as_bytes(),
+        )
+        .unwrap();
+        let manifest = NamespaceBackupManifest {
+            version: BACKUP_VERSION,
+            namespace: NamespaceInfo {
+                namespace_id: Some("dev".to_owned()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        NamespaceBackup::append_file(
+            &mut builder,
+            MANIFEST_FILE,
+            serde_json::to_string(&manifest).unwrap().as_bytes(),
+        )
+        .unwrap();
+        let data = builder.into_inner().unwrap().finish().unwrap();
+        let archive = NamespaceBackup::read_archive(data.as_slice(), 1024, 4096).unwrap();
+        assert_eq!(archive.manifest.namespace.namespace_id.unwrap(), "dev");
+        assert_eq!(archive.configs.len(), 1);
+        assert_eq!(archive.configs[0].1.as_str(), "a: 1");
+        assert!(NamespaceBackup::read_archive(data.as_slice(), 2, 4096).is_err());
+        //单个文件未超限但总大小超限
+        assert!(NamespaceBackup::read_archive(data.as_slice(), 1024, 16).is_err());
+        assert_eq!(
+            NamespaceImportPolicy::from_str("overwrite").unwrap(),
+            NamespaceImportPolicy::Overwrite
+        );
+    }
+
+    #[test]
+    fn import_preview_status() {
+        use NamespaceImportAction::*;
+        use NamespaceImportStatus::*;
+        assert_eq!(NamespaceImportStatus::classify(None, "a", 100), New);
+        assert_eq!(
+            NamespaceImportStatus::classify(Some(("a", 200)), "a", 100),
+            Unchanged
+        );
+        assert_eq!(
+            NamespaceImportStatus::classify(Some(("b", 50)), "a", 100),
+            Update
+        );
+        assert_eq!(
+            NamespaceImportStatus::classify(Some(("b", 200)), "a", 100),
+            Conflict
+        );
+        let merge = NamespaceImportPolicy::Merge;
+        assert_eq!(merge.action(true, New), Create);
+        assert_eq!(merge.action(true, Conflict), Skip);
+        let overwrite = NamespaceImportPolicy::Overwrite;
+        assert_eq!(overwrite.action(true, Conflict), Overwrite);
+        assert_eq!(overwrite.action(true, Unchanged), Skip);
+        assert_eq!(overwrite.action(true, Invalid), Skip);
+        let skip = NamespaceImportPolicy::Skip;
+        assert_eq!(skip.action(true, New), Skip);
+        assert_eq!(skip.action(false, New), Create);
+    }
+
+    #[test]
+    fn copy_cursor_position() {
+        let value = ConfigReadValue {
+            content: Default::default(),
+            md5: Default::default(),
+            config_type: None,
+            desc: None,
+            detected_type: None,
+            archive_uri: None,
+            last_modified: 0,
+        };
+        let list: Vec<_> = ["a", "b", "c"]
+            .iter()
+            .map(|v| (ConfigKey::new(v, "DEFAULT_GROUP", "dev"), value.clone()))
+            .collect();
+        assert_eq!(NamespaceCopy::cursor_position(&list, &None), 0);
+        let cursor = Some(list[1].0.to_string());
+        assert_eq!(NamespaceCopy::cursor_position(&list, &cursor), 2);
+        let cursor = Some("invalid".to_owned());
+        assert_eq!(NamespaceCopy::cursor_position(&list, &cursor), 0);
+    }
+}
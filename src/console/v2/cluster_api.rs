@@ -22,3 +22,9 @@ pub async fn query_cluster_info(app: web::Data<Arc<AppShareData>>) -> impl Respo
     }
     HttpResponse::Ok().json(ApiResult::success(Some(list)))
 }
+
+///
+/// 当前生效的服务设置,以及配置文件中已修改但需要重启才生效的设置;只允许管理员查看
+pub async fn query_server_config(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    HttpResponse::Ok().json(ApiResult::success(Some(app.sys_config_reloader.view())))
+}
@@ -1,32 +1,32 @@
-use crate::common::appdata::AppShareData;
+use crate::common::appdata::ConfigStore;
 use crate::common::model::{ApiResult, PageResult};
-use crate::config::core::{ConfigActor, ConfigCmd, ConfigResult};
 use crate::console::model::config_model::{ConfigInfo, ConfigParams, OpsConfigQueryListRequest};
-use actix::Addr;
 use actix_web::web::Data;
 use actix_web::{web, HttpResponse, Responder};
 use std::sync::Arc;
 
-pub use crate::console::config_api::{download_config, import_config};
+pub use crate::console::config_api::{
+    download_config, export_config_access_audit, import_config, import_config_from_url,
+};
 use crate::console::v2::ERROR_CODE_SYSTEM_ERROR;
 use crate::raft::cluster::model::{DelConfigReq, SetConfigReq};
 
 pub async fn query_config_list(
     request: web::Query<OpsConfigQueryListRequest>,
-    config_addr: web::Data<Addr<ConfigActor>>,
+    store: Data<Arc<dyn ConfigStore + Send + Sync>>,
 ) -> impl Responder {
-    let cmd = ConfigCmd::QueryPageInfo(Box::new(request.0.to_param().unwrap()));
-    match config_addr.send(cmd).await {
-        Ok(res) => {
-            let r: ConfigResult = res.unwrap();
-            match r {
-                ConfigResult::ConfigInfoPage(total_count, list) => HttpResponse::Ok()
-                    .json(ApiResult::success(Some(PageResult { total_count, list }))),
-                _ => HttpResponse::Ok().json(ApiResult::<()>::error(
-                    ERROR_CODE_SYSTEM_ERROR.to_string(),
-                    None,
-                )),
-            }
+    let param = match request.0.to_param() {
+        Ok(param) => param,
+        Err(err) => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                Some(err.to_string()),
+            ));
+        }
+    };
+    match store.query_config_page(param).await {
+        Ok((total_count, list)) => {
+            HttpResponse::Ok().json(ApiResult::success(Some(PageResult { total_count, list })))
         }
         Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
             ERROR_CODE_SYSTEM_ERROR.to_string(),
@@ -37,7 +37,7 @@ pub async fn query_config_list(
 
 pub async fn query_history_config_page(
     request: web::Query<OpsConfigQueryListRequest>,
-    config_addr: web::Data<Addr<ConfigActor>>,
+    store: Data<Arc<dyn ConfigStore + Send + Sync>>,
 ) -> impl Responder {
     let param = match request.0.to_history_param() {
         Ok(param) => param,
@@ -48,18 +48,9 @@ pub async fn query_history_config_page(
             ));
         }
     };
-    let cmd = ConfigCmd::QueryHistoryPageInfo(Box::new(param));
-    match config_addr.send(cmd).await {
-        Ok(res) => {
-            let r: ConfigResult = res.unwrap();
-            match r {
-                ConfigResult::ConfigHistoryInfoPage(total_count, list) => HttpResponse::Ok()
-                    .json(ApiResult::success(Some(PageResult { total_count, list }))),
-                _ => HttpResponse::Ok().json(ApiResult::<()>::error(
-                    ERROR_CODE_SYSTEM_ERROR.to_string(),
-                    None,
-                )),
-            }
+    match store.query_history_page(param).await {
+        Ok((total_count, list)) => {
+            HttpResponse::Ok().json(ApiResult::success(Some(PageResult { total_count, list })))
         }
         Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
             ERROR_CODE_SYSTEM_ERROR.to_string(),
@@ -70,33 +61,29 @@ pub async fn query_history_config_page(
 
 pub(crate) async fn get_config(
     web::Query(param): web::Query<ConfigParams>,
-    appdata: Data<Arc<AppShareData>>,
+    store: Data<Arc<dyn ConfigStore + Send + Sync>>,
 ) -> impl Responder {
     let config_key = param.to_key();
-    let cmd = ConfigCmd::GET(config_key);
-    if let Ok(Ok(ConfigResult::Data {
-        value: v,
-        md5,
-        config_type,
-        desc,
-    })) = appdata.config_addr.send(cmd).await
-    {
-        HttpResponse::Ok().json(ApiResult::success(Some(ConfigInfo {
-            value: Some(v),
-            md5: Some(md5),
-            config_type,
-            desc,
-        })))
-    } else {
-        HttpResponse::Ok().json(ApiResult::<()>::error(
+    match store.load_config(&config_key).await {
+        Ok(Some(v)) => HttpResponse::Ok().json(ApiResult::success(Some(ConfigInfo {
+            value: Some(v.content.clone()),
+            md5: Some(v.md5.clone()),
+            config_type: v.config_type.clone(),
+            desc: v.desc.clone(),
+        }))),
+        Ok(None) => HttpResponse::Ok().json(ApiResult::<()>::error(
             ERROR_CODE_SYSTEM_ERROR.to_string(),
             None,
-        ))
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
     }
 }
 
 pub async fn add_config(
-    appdata: Data<Arc<AppShareData>>,
+    store: Data<Arc<dyn ConfigStore + Send + Sync>>,
     web::Json(param): web::Json<ConfigParams>,
 ) -> impl Responder {
     let content = param.content.clone().unwrap_or_default();
@@ -107,10 +94,31 @@ pub async fn add_config(
             Some(e.to_string()),
         ));
     }
+    if let Some(references) = &param.references {
+        let mut missing = vec![];
+        for key in references {
+            if let Err(e) = key.is_valid() {
+                return HttpResponse::BadRequest().json(ApiResult::<()>::error(
+                    ERROR_CODE_SYSTEM_ERROR.to_string(),
+                    Some(e.to_string()),
+                ));
+            }
+            if store.get_config(key).is_none() {
+                missing.push(key.to_string());
+            }
+        }
+        if !missing.is_empty() {
+            return HttpResponse::BadRequest().json(ApiResult::<()>::error(
+                "REFERENCED_CONFIG_NOT_FOUND".to_string(),
+                Some(missing.join(",")),
+            ));
+        }
+    }
     let mut req = SetConfigReq::new(config_key, content);
     req.config_type = param.config_type;
     req.desc = param.desc;
-    if let Ok(_) = appdata.config_route.set_config(req).await {
+    req.references = param.references;
+    if store.set_config(req).await.is_ok() {
         HttpResponse::Ok().json(ApiResult::success(Some(true)))
     } else {
         HttpResponse::Ok().json(ApiResult::<()>::error(
@@ -121,17 +129,119 @@ pub async fn add_config(
 }
 
 pub async fn remove_config(
-    appdata: Data<Arc<AppShareData>>,
+    store: Data<Arc<dyn ConfigStore + Send + Sync>>,
     web::Json(param): web::Json<ConfigParams>,
 ) -> impl Responder {
     let config_key = param.to_key();
     let req = DelConfigReq::new(config_key);
-    if appdata.config_route.del_config(req).await.is_ok() {
-        HttpResponse::Ok().json(ApiResult::success(Some(true)))
-    } else {
-        HttpResponse::Ok().json(ApiResult::<()>::error(
+    match store.del_config(req).await {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
             ERROR_CODE_SYSTEM_ERROR.to_string(),
-            None,
-        ))
+            Some(err.to_string()),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::appdata::mock::MockConfigStore;
+    use actix_web::{test, App};
+
+    #[actix_web::test]
+    async fn config_handlers_with_mock_store() {
+        let app = test::init_service(
+            App::new()
+                .app_data(MockConfigStore::default().into_data())
+                .route("/config/add", web::post().to(add_config))
+                .route("/config/info", web::get().to(get_config))
+                .route("/config/list", web::get().to(query_config_list))
+                .route("/config/remove", web::post().to(remove_config)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/config/add")
+            .set_json(serde_json::json!({"dataId":"app.yaml","content":"a: 1"}))
+            .to_request();
+        let res: ApiResult<bool> = test::call_and_read_body_json(&app, req).await;
+        assert!(res.success);
+
+        let req = test::TestRequest::get()
+            .uri("/config/info?dataId=app.yaml")
+            .to_request();
+        let res: ApiResult<ConfigInfo> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(res.data.unwrap().value.unwrap().as_str(), "a: 1");
+
+        let req = test::TestRequest::get()
+            .uri("/config/list?pageNo=1&pageSize=10")
+            .to_request();
+        let res: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(res["data"]["totalCount"], 1);
+
+        let req = test::TestRequest::post()
+            .uri("/config/remove")
+            .set_json(serde_json::json!({"dataId":"app.yaml"}))
+            .to_request();
+        let res: ApiResult<bool> = test::call_and_read_body_json(&app, req).await;
+        assert!(res.success);
+        let req = test::TestRequest::get()
+            .uri("/config/info?dataId=app.yaml")
+            .to_request();
+        let res: ApiResult<ConfigInfo> = test::call_and_read_body_json(&app, req).await;
+        assert!(!res.success);
+    }
+
+    #[actix_web::test]
+    async fn add_config_with_references() {
+        let app = test::init_service(
+            App::new()
+                .app_data(MockConfigStore::default().into_data())
+                .route("/config/add", web::post().to(add_config)),
+        )
+        .await;
+        let body = serde_json::json!({
+            "dataId": "datasource.properties",
+            "content": "url=jdbc",
+            "references": ["##DEFAULT_GROUP##encryption-keys.properties"],
+        });
+        let req = test::TestRequest::post()
+            .uri("/config/add")
+            .set_json(&body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let res: ApiResult<bool> = test::read_body_json(resp).await;
+        assert!(!res.success);
+        assert_eq!(res.code.unwrap(), "REFERENCED_CONFIG_NOT_FOUND");
+        assert_eq!(
+            res.message.unwrap(),
+            "##DEFAULT_GROUP##encryption-keys.properties"
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/config/add")
+            .set_json(serde_json::json!({"dataId":"encryption-keys.properties","content":"k=v"}))
+            .to_request();
+        let res: ApiResult<bool> = test::call_and_read_body_json(&app, req).await;
+        assert!(res.success);
+        let req = test::TestRequest::post()
+            .uri("/config/add")
+            .set_json(&body)
+            .to_request();
+        let res: ApiResult<bool> = test::call_and_read_body_json(&app, req).await;
+        assert!(res.success);
+
+        //引用的配置key不合法
+        let req = test::TestRequest::post()
+            .uri("/config/add")
+            .set_json(serde_json::json!({
+                "dataId": "datasource.properties",
+                "content": "url=jdbc",
+                "references": ["####encryption-keys.properties"],
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
     }
 }
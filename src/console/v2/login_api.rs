@@ -1,11 +1,8 @@
-use crate::common::appdata::AppShareData;
+use crate::common::appdata::AuthProvider;
 use crate::common::crypto_utils;
 use crate::common::model::{ApiResult, UserSession};
 pub use crate::console::login_api::{gen_captcha, logout};
 use crate::console::model::login_model::LoginParam;
-use crate::raft::cache::model::{CacheKey, CacheType, CacheValue};
-use crate::raft::cache::{CacheLimiterReq, CacheManagerReq, CacheManagerResult};
-use crate::user::{UserManagerReq, UserManagerResult};
 use actix_web::cookie::Cookie;
 use actix_web::web::Data;
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
@@ -13,7 +10,7 @@ use std::sync::Arc;
 
 pub async fn login(
     request: HttpRequest,
-    app: Data<Arc<AppShareData>>,
+    auth: Data<Arc<dyn AuthProvider + Send + Sync>>,
     web::Json(param): web::Json<LoginParam>,
 ) -> actix_web::Result<impl Responder> {
     //校验验证码
@@ -26,16 +23,9 @@ pub async fn login(
         )));
     };
     let captcha_code = param.captcha.to_uppercase();
-    let cache_req = CacheManagerReq::Get(CacheKey::new(
-        CacheType::String,
-        Arc::new(format!("Captcha_{}", &captcha_token)),
-    ));
-    let captcha_check_result = if let Ok(Ok(CacheManagerResult::Value(CacheValue::String(v)))) =
-        app.cache_manager.send(cache_req).await
-    {
-        &captcha_code == v.as_ref()
-    } else {
-        false
+    let captcha_check_result = match auth.get_captcha(&captcha_token).await {
+        Some(v) => &captcha_code == v.as_ref(),
+        None => false,
     };
     if !captcha_check_result {
         return Ok(HttpResponse::Ok()
@@ -51,14 +41,8 @@ pub async fn login(
             )));
     }
     let limit_key = Arc::new(format!("USER_L#{}", &param.username));
-    let limit_req = CacheLimiterReq::Hour {
-        key: limit_key.clone(),
-        limit: app.sys_config.console_login_one_hour_limit as i32,
-    };
     //登录前先判断是否登陆准入
-    if let Ok(CacheManagerResult::Limiter(acquire_result)) =
-        app.raft_cache_route.request_limiter(limit_req).await
-    {
+    if let Ok(acquire_result) = auth.acquire_login_limit(limit_key.clone()).await {
         if !acquire_result {
             return Ok(HttpResponse::Ok().json(ApiResult::<()>::error(
                 "LOGIN_LIMITE_ERROR".to_owned(),
@@ -78,13 +62,7 @@ pub async fn login(
             )));
         }
     };
-    let msg = UserManagerReq::CheckUser {
-        name: param.username,
-        password,
-    };
-    if let Ok(Ok(UserManagerResult::CheckUserResult(valid, user))) =
-        app.user_manager.send(msg).await
-    {
+    if let Ok((valid, user)) = auth.check_user(param.username, password).await {
         if valid {
             //增加长度避免遍历
             let token = Arc::new(
@@ -97,16 +75,9 @@ pub async fn login(
                 roles: user.roles.unwrap_or_default(),
                 extend_infos: user.extend_info.unwrap_or_default(),
             });
-            let cache_req = CacheManagerReq::Set {
-                key: CacheKey::new(CacheType::UserSession, token.clone()),
-                value: CacheValue::UserSession(session),
-                ttl: app.sys_config.console_login_timeout,
-            };
-            app.cache_manager.do_send(cache_req);
+            auth.save_session(token.clone(), session);
             //登录成功后清除登陆限流计数
-            let clear_limit_req =
-                CacheManagerReq::Remove(CacheKey::new(CacheType::String, limit_key));
-            app.cache_manager.do_send(clear_limit_req);
+            auth.clear_login_limit(limit_key);
             return Ok(HttpResponse::Ok()
                 .cookie(
                     Cookie::build("token", token.as_str())
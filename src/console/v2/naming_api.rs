@@ -1,41 +1,36 @@
-use crate::common::appdata::AppShareData;
+use crate::common::appdata::NamingStore;
 use crate::common::model::{ApiResult, PageResult};
 use crate::console::model::naming_model::{
     InstanceParams, ServiceDto, ServiceParam, ServiceQueryListRequest,
 };
 use crate::console::v2::ERROR_CODE_SYSTEM_ERROR;
 use crate::naming::api_model::InstanceVO;
-use crate::naming::core::{NamingActor, NamingCmd, NamingResult};
 use crate::naming::model::ServiceDetailDto;
-use actix::Addr;
 use actix_web::web::Data;
 use actix_web::{web, HttpResponse, Responder};
 use std::sync::Arc;
 
 pub async fn query_service_list(
     param: web::Query<ServiceQueryListRequest>,
-    naming_addr: web::Data<Addr<NamingActor>>,
+    store: Data<Arc<dyn NamingStore + Send + Sync>>,
 ) -> impl Responder {
-    let service_param = param.0.to_param().unwrap();
-    match naming_addr
-        .send(NamingCmd::QueryServiceInfoPage(service_param))
-        .await
-    {
-        Ok(res) => {
-            let result: NamingResult = res.unwrap();
-            if let NamingResult::ServiceInfoPage((total_count, list)) = result {
-                let service_list: Vec<ServiceDto> =
-                    list.into_iter().map(ServiceDto::from).collect::<_>();
-                HttpResponse::Ok().json(ApiResult::success(Some(PageResult {
-                    total_count,
-                    list: service_list,
-                })))
-            } else {
-                HttpResponse::Ok().json(ApiResult::<()>::error(
-                    ERROR_CODE_SYSTEM_ERROR.to_string(),
-                    None,
-                ))
-            }
+    let service_param = match param.0.to_param() {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                Some(err.to_string()),
+            ));
+        }
+    };
+    match store.query_service_page(service_param).await {
+        Ok((total_count, list)) => {
+            let service_list: Vec<ServiceDto> =
+                list.into_iter().map(ServiceDto::from).collect::<_>();
+            HttpResponse::Ok().json(ApiResult::success(Some(PageResult {
+                total_count,
+                list: service_list,
+            })))
         }
         Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
             ERROR_CODE_SYSTEM_ERROR.to_string(),
@@ -45,7 +40,7 @@ pub async fn query_service_list(
 }
 
 pub async fn add_service(
-    appdata: Data<Arc<AppShareData>>,
+    store: Data<Arc<dyn NamingStore + Send + Sync>>,
     web::Json(param): web::Json<ServiceParam>,
 ) -> impl Responder {
     let service_key = param.to_key();
@@ -56,79 +51,39 @@ pub async fn add_service(
         metadata: param.metadata,
         protect_threshold: param.protect_threshold,
     };
-    if let Ok(res) = appdata
-        .naming_addr
-        .send(NamingCmd::UpdateService(service_info))
-        .await
-    {
-        match res {
-            Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
-            Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
-                ERROR_CODE_SYSTEM_ERROR.to_string(),
-                Some(err.to_string()),
-            )),
-        }
-    } else {
-        HttpResponse::Ok().json(ApiResult::<()>::error(
+    match store.update_service(service_info).await {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
             ERROR_CODE_SYSTEM_ERROR.to_string(),
-            None,
-        ))
+            Some(err.to_string()),
+        )),
     }
 }
 
 pub async fn remove_service(
-    appdata: Data<Arc<AppShareData>>,
+    store: Data<Arc<dyn NamingStore + Send + Sync>>,
     web::Json(param): web::Json<ServiceParam>,
 ) -> impl Responder {
     let service_key = param.to_key();
-    if let Ok(res) = appdata
-        .naming_addr
-        .send(NamingCmd::RemoveService(service_key))
-        .await
-    {
-        match res {
-            Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
-            Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
-                ERROR_CODE_SYSTEM_ERROR.to_string(),
-                Some(err.to_string()),
-            )),
-        }
-    } else {
-        HttpResponse::Ok().json(ApiResult::<()>::error(
+    match store.remove_service(service_key).await {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
             ERROR_CODE_SYSTEM_ERROR.to_string(),
-            None,
-        ))
+            Some(err.to_string()),
+        )),
     }
 }
 
 pub async fn query_instances_list(
     param: web::Query<ServiceParam>,
-    appdata: Data<Arc<AppShareData>>,
+    store: Data<Arc<dyn NamingStore + Send + Sync>>,
 ) -> impl Responder {
     let service_key = param.to_key();
-    match appdata
-        .naming_addr
-        .send(NamingCmd::QueryAllInstanceList(service_key))
-        .await
-    {
-        Ok(res) => match res as anyhow::Result<NamingResult> {
-            Ok(result) => match result {
-                NamingResult::InstanceList(list) => {
-                    HttpResponse::Ok().json(ApiResult::success(Some(PageResult {
-                        total_count: list.len(),
-                        list,
-                    })))
-                }
-                _ => HttpResponse::Ok().json(ApiResult::<()>::error(
-                    ERROR_CODE_SYSTEM_ERROR.to_string(),
-                    None,
-                )),
-            },
-            Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
-                ERROR_CODE_SYSTEM_ERROR.to_string(),
-                Some(err.to_string()),
-            )),
-        },
+    match store.query_all_instances(service_key).await {
+        Ok(list) => HttpResponse::Ok().json(ApiResult::success(Some(PageResult {
+            total_count: list.len(),
+            list,
+        }))),
         Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
             ERROR_CODE_SYSTEM_ERROR.to_string(),
             Some(err.to_string()),
@@ -137,24 +92,19 @@ pub async fn query_instances_list(
 }
 
 pub async fn get_instance(
-    appdata: Data<Arc<AppShareData>>,
+    store: Data<Arc<dyn NamingStore + Send + Sync>>,
     web::Query(param): web::Query<InstanceParams>,
 ) -> impl Responder {
     match param.to_instance() {
-        Ok(instance) => match appdata.naming_addr.send(NamingCmd::Query(instance)).await {
-            Ok(res) => {
-                let result: NamingResult = res.unwrap();
-                match result {
-                    NamingResult::Instance(v) => {
-                        let vo = InstanceVO::from_instance(&v);
-                        HttpResponse::Ok().json(ApiResult::success(Some(vo)))
-                    }
-                    _ => HttpResponse::Ok().json(ApiResult::<()>::error(
-                        ERROR_CODE_SYSTEM_ERROR.to_string(),
-                        None,
-                    )),
-                }
+        Ok(instance) => match store.get_instance(instance).await {
+            Ok(Some(v)) => {
+                let vo = InstanceVO::from_instance(&v);
+                HttpResponse::Ok().json(ApiResult::success(Some(vo)))
             }
+            Ok(None) => HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                None,
+            )),
             Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
                 ERROR_CODE_SYSTEM_ERROR.to_string(),
                 Some(err.to_string()),
@@ -166,8 +116,9 @@ pub async fn get_instance(
         )),
     }
 }
+
 pub async fn add_instance(
-    appdata: Data<Arc<AppShareData>>,
+    store: Data<Arc<dyn NamingStore + Send + Sync>>,
     web::Json(param): web::Json<InstanceParams>,
 ) -> impl Responder {
     match param.to_instance() {
@@ -178,7 +129,7 @@ pub async fn add_instance(
                     Some("instance check is invalid".to_string()),
                 ))
             } else {
-                match appdata.naming_route.update_instance(instance, None).await {
+                match store.update_instance(instance).await {
                     Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
                     Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
                         ERROR_CODE_SYSTEM_ERROR.to_string(),
@@ -195,7 +146,7 @@ pub async fn add_instance(
 }
 
 pub async fn remove_instance(
-    appdata: Data<Arc<AppShareData>>,
+    store: Data<Arc<dyn NamingStore + Send + Sync>>,
     web::Json(param): web::Json<InstanceParams>,
 ) -> impl Responder {
     match param.to_instance() {
@@ -206,7 +157,7 @@ pub async fn remove_instance(
                     Some("instance check is invalid".to_string()),
                 ))
             } else {
-                match appdata.naming_route.delete_instance(instance).await {
+                match store.delete_instance(instance).await {
                     Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
                     Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
                         ERROR_CODE_SYSTEM_ERROR.to_string(),
@@ -100,6 +100,18 @@ pub struct ConfigQueryRequest {
     pub group: String,
     pub tenant: String,
     pub tag: Option<String>,
+    //重连时带上断开前各订阅配置的变更序号,服务端据此补推断连期间的变更
+    #[serde(default)]
+    pub key_seqs: Vec<ConfigKeySeq>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigKeySeq {
+    pub data_id: Arc<String>,
+    pub group: Arc<String>,
+    pub tenant: Arc<String>,
+    pub seq: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -184,6 +196,18 @@ pub struct ConfigChangeNotifyRequest {
     pub tenant: Arc<String>,
 }
 
+///
+/// 服务端主动断开连接前推送,携带各订阅配置的最新变更序号供客户端重连时对账
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigReconnectRequest {
+    pub module: Option<String>,
+    pub request_id: Option<String>,
+    pub headers: HashMap<String, String>,
+
+    pub key_seqs: Vec<ConfigKeySeq>,
+}
+
 // ----- naming model -----
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -56,6 +56,18 @@ impl BiStreamConn {
         }
     }
 
+    fn build_detection_payload(request_id: String) -> Payload {
+        let request = ClientDetectionRequest {
+            module: Some("internal".to_owned()),
+            request_id: Some(request_id),
+            ..Default::default()
+        };
+        PayloadUtils::build_payload(
+            "ClientDetectionRequest",
+            serde_json::to_string(&request).unwrap(),
+        )
+    }
+
     fn send_payload(&mut self, ctx: &mut Context<Self>, payload: Payload) {
         let sender = self.sender.clone();
         //debug
@@ -70,8 +82,10 @@ impl BiStreamConn {
 impl Actor for BiStreamConn {
     type Context = Context<Self>;
 
-    fn started(&mut self, _ctx: &mut Self::Context) {
+    fn started(&mut self, ctx: &mut Self::Context) {
         //log::info!("BiStreamConn started");
+        //客户端的响应用于刷新活跃时间,保活检测由BiStreamManage统一处理
+        self.receive(ctx);
     }
 }
 
@@ -87,6 +101,8 @@ pub enum BiStreamSenderCmd {
     Detection(String),
     Reset(String, Option<String>, Option<String>),
     Send(Arc<Payload>),
+    //发送完成后关闭连接
+    SendAndClose(Arc<Payload>),
     Close,
 }
 
@@ -100,15 +116,7 @@ impl Handler<BiStreamSenderCmd> for BiStreamConn {
     fn handle(&mut self, msg: BiStreamSenderCmd, ctx: &mut Context<Self>) -> Self::Result {
         match msg {
             BiStreamSenderCmd::Detection(request_id) => {
-                let request = ClientDetectionRequest {
-                    module: Some("internal".to_owned()),
-                    request_id: Some(request_id),
-                    ..Default::default()
-                };
-                let payload = PayloadUtils::build_payload(
-                    "ClientDetectionRequest",
-                    serde_json::to_string(&request).unwrap(),
-                );
+                let payload = Self::build_detection_payload(request_id);
                 self.send_payload(ctx, payload);
             }
             BiStreamSenderCmd::Reset(request_id, ip, port) => {
@@ -128,6 +136,16 @@ impl Handler<BiStreamSenderCmd> for BiStreamConn {
             BiStreamSenderCmd::Send(payload) => {
                 self.send_payload(ctx, payload.as_ref().to_owned());
             }
+            BiStreamSenderCmd::SendAndClose(payload) => {
+                let sender = self.sender.clone();
+                let payload = payload.as_ref().to_owned();
+                async move { sender.send(Ok(payload)).await }
+                    .into_actor(self)
+                    .map(|_, _, ctx| {
+                        ctx.stop();
+                    })
+                    .spawn(ctx);
+            }
             BiStreamSenderCmd::Close => {
                 ctx.stop();
             }
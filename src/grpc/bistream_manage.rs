@@ -5,16 +5,22 @@ use std::{
 };
 
 use crate::{
-    config::core::{ConfigActor, ConfigCmd, ConfigKey},
+    config::{
+        config_propagation::PropagationTracer,
+        core::{ConfigActor, ConfigCmd, ConfigKey, ConfigResult},
+    },
     naming::{
         core::{NamingActor, NamingCmd},
         model::{ServiceInfo, ServiceKey},
     },
-    now_millis,
+    now_millis, now_millis_i64,
 };
 
 use super::{
-    api_model::{ConfigChangeNotifyRequest, NotifySubscriberRequest, CONFIG_MODEL, NAMING_MODEL},
+    api_model::{
+        ConfigChangeNotifyRequest, ConfigKeySeq, ConfigReconnectRequest, NotifySubscriberRequest,
+        CONFIG_MODEL, NAMING_MODEL,
+    },
     bistream_conn::{BiStreamConn, BiStreamSenderCmd},
     handler::converter::ModelConverter,
     nacos_proto::Payload,
@@ -24,7 +30,17 @@ use actix::prelude::*;
 use bean_factory::{bean, Inject};
 use inner_mem_cache::TimeoutSet;
 
+//客户端处理配置变更通知后的确认消息
+const CONFIG_ACK_TYPES: [&str; 2] = ["ConfigChangeNotifyResponse", "ConfigAck"];
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigAckBody {
+    request_id: Option<String>,
+}
+
 struct ConnCacheItem {
+    create_time: u64,
     last_active_time: u64,
     conn: Addr<BiStreamConn>,
 }
@@ -32,12 +48,20 @@ struct ConnCacheItem {
 impl ConnCacheItem {
     fn new(last_active_time: u64, conn: Addr<BiStreamConn>) -> Self {
         Self {
+            create_time: last_active_time,
             last_active_time,
             conn,
         }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct ConnInfo {
+    pub client_id: Arc<String>,
+    pub create_time: u64,
+    pub last_active_time: u64,
+}
+
 #[bean(inject)]
 #[derive(Default)]
 pub struct BiStreamManage {
@@ -49,17 +73,34 @@ pub struct BiStreamManage {
     request_id: u64,
     config_addr: Option<Addr<ConfigActor>>,
     naming_addr: Option<Addr<NamingActor>>,
+    propagation_tracer: Option<Arc<PropagationTracer>>,
 }
 
 impl BiStreamManage {
     pub fn new() -> Self {
+        Self::new_with_keepalive(15000, 3000)
+    }
+
+    ///
+    /// detection_time_out: 连接空闲多久后发送探测请求;response_time_out: 探测后等待响应的时长
+    pub fn new_with_keepalive(detection_time_out: u64, response_time_out: u64) -> Self {
         Self {
-            detection_time_out: 15000,
-            response_time_out: 3000,
+            detection_time_out,
+            response_time_out,
             ..Default::default()
         }
     }
 
+    fn need_detection(&self, last_active_time: u64, now: u64) -> bool {
+        last_active_time + self.detection_time_out <= now
+    }
+
+    ///
+    /// 发送探测后超时仍没有收到客户端的任何消息
+    fn is_response_timeout(&self, last_active_time: u64, now: u64) -> bool {
+        last_active_time + self.detection_time_out + self.response_time_out <= now
+    }
+
     pub fn add_conn(&mut self, client_id: Arc<String>, sender: Addr<BiStreamConn>) {
         log::info!("add_conn client_id:{}", &client_id);
         let now = now_millis();
@@ -84,6 +125,91 @@ impl BiStreamManage {
         }
     }
 
+    ///
+    /// 主动关闭连接并清理连接上的配置与服务订阅
+    fn close_conn(&mut self, client_id: &Arc<String>, ctx: &mut Context<Self>) -> bool {
+        match self.conn_cache.remove(client_id) {
+            Some(item) => {
+                log::info!("close conn client_id:{}", client_id);
+                self.reconnect_close(client_id.clone(), item.conn, ctx);
+                if let Some(naming_addr) = &self.naming_addr {
+                    naming_addr.do_send(NamingCmd::RemoveClient(client_id.clone()));
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// 服务端主动断开前推送RECONNECT事件,带上连接订阅配置的最新变更序号;
+    /// 推送完成后关闭连接,并在查询序号后再清理配置订阅
+    fn reconnect_close(
+        &mut self,
+        client_id: Arc<String>,
+        conn: Addr<BiStreamConn>,
+        ctx: &mut Context<Self>,
+    ) {
+        let config_addr = match &self.config_addr {
+            Some(v) => v.clone(),
+            None => {
+                conn.do_send(BiStreamSenderCmd::Close);
+                return;
+            }
+        };
+        let request_id = self.next_request_id();
+        async move {
+            let key_seqs = match config_addr
+                .send(ConfigCmd::QueryClientKeySeqs(client_id.clone()))
+                .await
+            {
+                Ok(Ok(ConfigResult::KeySeqs(list))) => list,
+                _ => vec![],
+            };
+            config_addr.do_send(ConfigCmd::RemoveSubscribeClient(client_id));
+            let request = ConfigReconnectRequest {
+                key_seqs: key_seqs
+                    .into_iter()
+                    .map(|(key, seq)| ConfigKeySeq {
+                        data_id: key.data_id,
+                        group: key.group,
+                        tenant: key.tenant,
+                        seq,
+                    })
+                    .collect(),
+                request_id: Some(request_id),
+                module: Some(CONFIG_MODEL.to_string()),
+                ..Default::default()
+            };
+            let payload = Arc::new(PayloadUtils::build_payload(
+                "ConfigReconnectRequest",
+                serde_json::to_string(&request).unwrap(),
+            ));
+            conn.do_send(BiStreamSenderCmd::SendAndClose(payload));
+        }
+        .into_actor(self)
+        .map(|_, _, _| {})
+        .spawn(ctx);
+    }
+
+    fn record_config_ack(&self, client_id: &Arc<String>, payload: &Payload) {
+        let tracer = match &self.propagation_tracer {
+            Some(v) => v,
+            None => return,
+        };
+        let body = payload
+            .body
+            .as_ref()
+            .map(|v| v.value.as_slice())
+            .unwrap_or_default();
+        if let Ok(ConfigAckBody {
+            request_id: Some(request_id),
+        }) = serde_json::from_slice::<ConfigAckBody>(body)
+        {
+            tracer.ack(&request_id, client_id, now_millis_i64());
+        }
+    }
+
     fn next_request_id(&mut self) -> String {
         if self.request_id >= 0x7fff_ffff_ffff_ffff {
             self.request_id = 0;
@@ -99,7 +225,7 @@ impl BiStreamManage {
         for key in keys {
             if let Some(item) = self.conn_cache.get(&key) {
                 let next_time = item.last_active_time + self.detection_time_out;
-                if item.last_active_time + self.detection_time_out <= now {
+                if self.need_detection(item.last_active_time, now) {
                     check_keys.push((key, self.next_request_id()));
                 } else {
                     self.active_time_set.add(next_time, key.clone());
@@ -118,12 +244,12 @@ impl BiStreamManage {
         }
     }
 
-    fn check_response_time_set(&mut self, now: u64) {
+    fn check_response_time_set(&mut self, now: u64, ctx: &mut Context<Self>) {
         let keys = self.response_time_set.timeout(now);
         let mut del_keys = vec![];
         for key in keys {
             if let Some(item) = self.conn_cache.get(&key) {
-                if item.last_active_time + self.detection_time_out + self.response_time_out <= now {
+                if self.is_response_timeout(item.last_active_time, now) {
                     del_keys.push(key);
                 } else {
                     self.active_time_set
@@ -137,12 +263,7 @@ impl BiStreamManage {
         for key in &del_keys {
             if let Some(item) = self.conn_cache.remove(key) {
                 //item.conn.do_send(BiStreamSenderCmd::Reset(self.next_request_id(),None,None));
-                item.conn.do_send(BiStreamSenderCmd::Close);
-            }
-        }
-        if let Some(config_addr) = &self.config_addr {
-            for key in &del_keys {
-                config_addr.do_send(ConfigCmd::RemoveSubscribeClient(key.clone()));
+                self.reconnect_close(key.clone(), item.conn, ctx);
             }
         }
         if let Some(naming_addr) = &self.naming_addr {
@@ -156,7 +277,7 @@ impl BiStreamManage {
         ctx.run_later(Duration::new(2, 0), |act, ctx| {
             let now = now_millis();
             act.check_active_time_set(now);
-            act.check_response_time_set(now);
+            act.check_response_time_set(now, ctx);
             act.time_out_heartbeat(ctx);
         });
     }
@@ -182,6 +303,7 @@ impl Inject for BiStreamManage {
     ) {
         self.config_addr = factory_data.get_actor();
         self.naming_addr = factory_data.get_actor();
+        self.propagation_tracer = factory_data.get_bean();
         log::info!("BiStreamManage inject complete");
     }
 }
@@ -200,23 +322,32 @@ pub enum BiStreamManageCmd {
     AddConn(Arc<String>, BiStreamConn),
     ActiveClinet(Arc<String>),
     NotifyConfig(ConfigKey, HashSet<Arc<String>>),
+    //推送配置变更并按追踪id记录各客户端的确认
+    NotifyConfigTrace(ConfigKey, HashSet<Arc<String>>, Arc<String>),
     NotifyNaming(ServiceKey, HashSet<Arc<String>>, ServiceInfo),
     QueryConnList,
+    QueryConnInfoList,
+    CloseConn(Arc<String>),
 }
 
 pub enum BiStreamManageResult {
     ConnList(Vec<Arc<String>>),
+    ConnInfoList(Vec<ConnInfo>),
+    CloseResult(bool),
     None,
 }
 
 impl Handler<BiStreamManageCmd> for BiStreamManage {
     type Result = anyhow::Result<BiStreamManageResult>;
 
-    fn handle(&mut self, msg: BiStreamManageCmd, _ctx: &mut Context<Self>) -> Self::Result {
+    fn handle(&mut self, msg: BiStreamManageCmd, ctx: &mut Context<Self>) -> Self::Result {
         match msg {
             BiStreamManageCmd::Response(client_id, payload) => {
                 //println!("BiStreamManageCmd payload:{},client_id:{}",PayloadUtils::get_payload_string(&payload),&client_id);
-                if let Some(_t) = PayloadUtils::get_payload_type(&payload) {
+                if let Some(t) = PayloadUtils::get_payload_type(&payload) {
+                    if CONFIG_ACK_TYPES.contains(&t.as_str()) {
+                        self.record_config_ack(&client_id, &payload);
+                    }
                     self.active_client(client_id).ok();
                     //if "ClientDetectionResponse"== t {
                     //}
@@ -256,6 +387,35 @@ impl Handler<BiStreamManageCmd> for BiStreamManage {
                     }
                 }
             }
+            BiStreamManageCmd::NotifyConfigTrace(config_key, client_id_set, trace_id) => {
+                let request_id = self.next_request_id();
+                let request = ConfigChangeNotifyRequest {
+                    group: config_key.group,
+                    data_id: config_key.data_id,
+                    tenant: config_key.tenant,
+                    request_id: Some(request_id.clone()),
+                    module: Some(CONFIG_MODEL.to_string()),
+                    ..Default::default()
+                };
+                let payload = Arc::new(PayloadUtils::build_payload(
+                    "ConfigChangeNotifyRequest",
+                    serde_json::to_string(&request).unwrap(),
+                ));
+                let mut client_ids = Vec::with_capacity(client_id_set.len());
+                for client_id in client_id_set {
+                    if self.conn_cache.contains_key(&client_id) {
+                        client_ids.push(client_id);
+                    }
+                }
+                if let Some(tracer) = &self.propagation_tracer {
+                    tracer.start(&trace_id, request_id, client_ids.clone(), now_millis_i64());
+                }
+                for client_id in &client_ids {
+                    if let Some(item) = self.conn_cache.get(client_id) {
+                        item.conn.do_send(BiStreamSenderCmd::Send(payload.clone()));
+                    }
+                }
+            }
             BiStreamManageCmd::NotifyNaming(service_key, client_id_set, service_info) => {
                 let service_info = ModelConverter::to_api_service_info(service_info);
                 let request = NotifySubscriberRequest {
@@ -284,7 +444,41 @@ impl Handler<BiStreamManageCmd> for BiStreamManage {
                 }
                 return Ok(BiStreamManageResult::ConnList(list));
             }
+            BiStreamManageCmd::QueryConnInfoList => {
+                let mut list: Vec<ConnInfo> = self
+                    .conn_cache
+                    .iter()
+                    .map(|(k, v)| ConnInfo {
+                        client_id: k.clone(),
+                        create_time: v.create_time,
+                        last_active_time: v.last_active_time,
+                    })
+                    .collect();
+                list.sort_by_key(|v| v.create_time);
+                return Ok(BiStreamManageResult::ConnInfoList(list));
+            }
+            BiStreamManageCmd::CloseConn(client_id) => {
+                return Ok(BiStreamManageResult::CloseResult(
+                    self.close_conn(&client_id, ctx),
+                ));
+            }
         }
         Ok(BiStreamManageResult::None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keepalive_timeout() {
+        let manage = BiStreamManage::new_with_keepalive(5000, 3000);
+        assert!(!manage.need_detection(1000, 5999));
+        assert!(manage.need_detection(1000, 6000));
+        assert!(!manage.is_response_timeout(1000, 8999));
+        assert!(manage.is_response_timeout(1000, 9000));
+        //探测后收到响应刷新了活跃时间,不再超时
+        assert!(!manage.is_response_timeout(7000, 9000));
+    }
+}
@@ -43,6 +43,11 @@ impl PayloadHandler for ConfigChangeBatchListenRequestHandler {
             let key = ConfigKey::new(&item.data_id, &item.group, &item.tenant);
             listener_items.push(ListenerItem::new(key, item.md5));
         }
+        let listen_items = if request.listen {
+            listener_items.clone()
+        } else {
+            vec![]
+        };
         let cmd = if request.listen {
             ConfigCmd::Subscribe(listener_items, request_meta.connection_id)
         } else {
@@ -57,6 +62,11 @@ impl PayloadHandler for ConfigChangeBatchListenRequestHandler {
                 let r: ConfigResult = res.unwrap();
                 match r {
                     ConfigResult::ChangeKey(keys) => {
+                        //包含跨命名空间引用的配置按返回内容的md5比较
+                        let keys = self
+                            .app_data
+                            .filter_ns_ref_changes(&listen_items, keys)
+                            .await;
                         response.result_code = SUCCESS_CODE;
                         for key in keys {
                             let obj = ConfigContext {
@@ -39,7 +39,7 @@ impl PayloadHandler for ConfigPublishRequestHandler {
             ConfigKey::new(&request.data_id, &request.group, &request.tenant),
             request.content,
         );
-        match self.app_data.config_route.set_config(req).await {
+        match self.app_data.config_batch_proposer.set_config(req).await {
             Ok(_res) => {
                 //let res:ConfigResult = res.unwrap();
                 let mut response = BaseResponse::build_success_response();
@@ -1,10 +1,15 @@
 #![allow(unused_imports)]
 
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Instant;
 
+use crate::config::config_access_log::ConfigAccessRecord;
+use crate::config::config_read_view::ConfigReadValue;
 use crate::config::config_type::ConfigType;
-use crate::grpc::api_model::NOT_FOUND;
-use crate::grpc::HandlerResult;
+use crate::grpc::api_model::{ConfigKeySeq, NOT_FOUND};
+use crate::grpc::bistream_manage::BiStreamManageCmd;
+use crate::grpc::{HandlerResult, RequestMeta};
 use crate::{
     common::appdata::AppShareData,
     config::core::{ConfigActor, ConfigCmd, ConfigKey, ConfigResult},
@@ -28,22 +33,71 @@ impl ConfigQueryRequestHandler {
     pub fn new(app_data: Arc<AppShareData>) -> Self {
         Self { app_data }
     }
+
+    ///
+    /// 重连后按客户端带上的变更序号对账,立即向当前连接补推断连期间发生变更的配置
+    async fn reconcile_key_seqs(&self, key_seqs: Vec<ConfigKeySeq>, connection_id: Arc<String>) {
+        let key_seqs = key_seqs
+            .into_iter()
+            .map(|v| (ConfigKey::new_by_arc(v.data_id, v.group, v.tenant), v.seq))
+            .collect();
+        if let Ok(Ok(ConfigResult::ChangeKey(keys))) = self
+            .app_data
+            .config_addr
+            .send(ConfigCmd::QueryChangedSince(key_seqs))
+            .await
+        {
+            log::info!(
+                "reconnect reconcile, client_id:{}, changed size:{}",
+                &connection_id,
+                keys.len()
+            );
+            let clients: HashSet<Arc<String>> = HashSet::from([connection_id]);
+            for key in keys {
+                self.app_data
+                    .bi_stream_manage
+                    .do_send(BiStreamManageCmd::NotifyConfig(key, clients.clone()));
+            }
+        }
+    }
 }
 
-#[async_trait]
-impl PayloadHandler for ConfigQueryRequestHandler {
-    async fn handle(
+impl ConfigQueryRequestHandler {
+    ///
+    /// 与http读取一致,配置不存在时读取同分组下的默认配置
+    async fn get_group_default(&self, key: &ConfigKey) -> Option<ConfigResult> {
+        if !self.app_data.sys_config.config_group_default_enabled {
+            return None;
+        }
+        let cmd = ConfigCmd::GET(key.group_default_key()?);
+        match self.app_data.config_addr.send(cmd).await {
+            Ok(Ok(r @ ConfigResult::Data { .. })) => Some(r),
+            _ => None,
+        }
+    }
+
+    async fn load_served_content(
         &self,
-        request_payload: crate::grpc::nacos_proto::Payload,
-        _request_meta: crate::grpc::RequestMeta,
-    ) -> anyhow::Result<HandlerResult> {
-        let body_vec = request_payload.body.unwrap_or_default().value;
-        let request: ConfigQueryRequest = serde_json::from_slice(&body_vec)?;
-        let cmd = ConfigCmd::GET(ConfigKey::new(
-            &request.data_id,
-            &request.group,
-            &request.tenant,
-        ));
+        key: &ConfigKey,
+        value: ConfigReadValue,
+    ) -> anyhow::Result<(Arc<String>, Arc<String>)> {
+        let value = self.app_data.load_archived(Arc::new(value)).await?;
+        let content = self.app_data.decrypt_config_content(
+            &key.tenant,
+            &value.content,
+            value.config_type.as_ref().map(|v| v.as_str()),
+        )?;
+        self.app_data
+            .resolve_served_content(content, value.md5.clone(), false)
+            .await
+    }
+
+    async fn query(
+        &self,
+        config_key: &ConfigKey,
+        request: ConfigQueryRequest,
+    ) -> ConfigQueryResponse {
+        let cmd = ConfigCmd::GET(config_key.clone());
         let mut response = ConfigQueryResponse {
             request_id: request.request_id,
             ..Default::default()
@@ -52,14 +106,38 @@ impl PayloadHandler for ConfigQueryRequestHandler {
             Ok(res) => {
                 //let res:ConfigResult = res.unwrap();
                 let r: ConfigResult = res.unwrap();
+                let r = match r {
+                    ConfigResult::Data { .. } => r,
+                    _ => self.get_group_default(config_key).await.unwrap_or(r),
+                };
                 match r {
                     ConfigResult::Data {
                         value: content,
                         md5,
                         config_type,
-                        ..
+                        desc,
+                        archive_uri,
                     } => {
-                        //v.to_owned()
+                        let value = ConfigReadValue {
+                            content,
+                            md5,
+                            config_type: config_type.clone(),
+                            desc,
+                            detected_type: None,
+                            archive_uri,
+                            last_modified: 0,
+                        };
+                        //与http读取一致:加载归档内容、解密字段并替换跨命名空间引用
+                        let (content, md5) = match self.load_served_content(config_key, value).await
+                        {
+                            Ok(v) => v,
+                            Err(err) => {
+                                response.result_code = ERROR_CODE;
+                                response.error_code = ERROR_CODE;
+                                response.message = Some(err.to_string());
+                                return response;
+                            }
+                        };
                         response.result_code = SUCCESS_CODE;
                         response.content = content;
                         response.content_type =
@@ -80,20 +158,76 @@ impl PayloadHandler for ConfigQueryRequestHandler {
                         response.message = Some("config data not exist".to_owned());
                     }
                 }
-                Ok(HandlerResult::success(PayloadUtils::build_payload(
-                    "ConfigQueryResponse",
-                    serde_json::to_string(&response)?,
-                )))
             }
             Err(err) => {
                 response.result_code = ERROR_CODE;
                 response.error_code = ERROR_CODE;
                 response.message = Some(err.to_string());
-                Ok(HandlerResult::success(PayloadUtils::build_payload(
-                    "ErrorResponse",
-                    serde_json::to_string(&response)?,
-                )))
             }
         }
+        response
+    }
+
+    ///
+    /// gRPC读取与http读取写入同一访问日志,状态码按http语义记录
+    fn log_access(
+        &self,
+        key: &ConfigKey,
+        request_meta: &RequestMeta,
+        response: &ConfigQueryResponse,
+        start: Instant,
+    ) {
+        let response_code = if response.result_code == SUCCESS_CODE {
+            200
+        } else if response.error_code == NOT_FOUND {
+            404
+        } else {
+            500
+        };
+        let mut record = ConfigAccessRecord::new(
+            key,
+            "GRPC".to_owned(),
+            response_code,
+            start.elapsed().as_millis() as u64,
+        );
+        record.client_ip = Some(request_meta.client_ip.clone());
+        record.user = request_meta
+            .token_session
+            .as_ref()
+            .map(|v| v.username.clone());
+        self.app_data.config_access_logger.log(record);
+    }
+}
+
+#[async_trait]
+impl PayloadHandler for ConfigQueryRequestHandler {
+    async fn handle(
+        &self,
+        request_payload: crate::grpc::nacos_proto::Payload,
+        request_meta: crate::grpc::RequestMeta,
+    ) -> anyhow::Result<HandlerResult> {
+        let start = Instant::now();
+        let body_vec = request_payload.body.unwrap_or_default().value;
+        let mut request: ConfigQueryRequest = serde_json::from_slice(&body_vec)?;
+        if !request.key_seqs.is_empty() {
+            let key_seqs = std::mem::take(&mut request.key_seqs);
+            self.reconcile_key_seqs(key_seqs, request_meta.connection_id.clone())
+                .await;
+        }
+        let config_key = ConfigKey::new(&request.data_id, &request.group, &request.tenant);
+        let response = self.query(&config_key, request).await;
+        if self.app_data.config_access_logger.is_enabled() {
+            self.log_access(&config_key, &request_meta, &response, start);
+        }
+        let payload_type =
+            if response.result_code == SUCCESS_CODE || response.error_code == NOT_FOUND {
+                "ConfigQueryResponse"
+            } else {
+                "ErrorResponse"
+            };
+        Ok(HandlerResult::success(PayloadUtils::build_payload(
+            payload_type,
+            serde_json::to_string(&response)?,
+        )))
     }
 }
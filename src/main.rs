@@ -36,6 +36,8 @@ use env_logger_timezone_fmt::{TimeZoneFormat, TimeZoneFormatEnv};
 use rnacos::common::appdata::AppShareData;
 use rnacos::common::constant::APP_VERSION;
 use rnacos::openapi::middle::auth_middle::ApiCheckAuth;
+use rnacos::openapi::middle::circuit_breaker_middle::ConfigCircuitBreakerCheck;
+use rnacos::openapi::middle::request_stats_middle::HttpRequestStatsRecord;
 use rnacos::raft::NacosRaft;
 use rnacos::web_config::{app_config, console_config};
 
@@ -110,11 +112,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
         let bistream_manage_http_addr = app_data.bi_stream_manage.clone();
         let source_app_data = app_data.clone();
         let app_config_shard = app_data.sys_config.deref().clone();
+        let (config_store, naming_store, auth_provider) = AppShareData::store_data(&app_data);
         App::new()
+            .app_data(config_store)
+            .app_data(naming_store)
+            .app_data(auth_provider)
             .app_data(Data::new(app_data))
             .app_data(Data::new(config_addr))
             .app_data(Data::new(naming_addr))
             .app_data(Data::new(bistream_manage_http_addr))
+            .wrap(ConfigCircuitBreakerCheck::new(source_app_data.clone()))
+            .wrap(HttpRequestStatsRecord::new(source_app_data.clone()))
             .wrap(ApiCheckAuth::new(source_app_data))
             .wrap(middleware::Logger::default())
             .configure(app_config(app_config_shard))
@@ -131,10 +139,16 @@ fn init_env() {
     let app_opt = AppOpt::parse();
     let env_path = app_opt.env_file;
     //let env_path = std::env::var("RNACOS_ENV_FILE").unwrap_or_default();
-    if env_path.is_empty() {
-        dotenv::dotenv().ok();
+    let loaded_path = if env_path.is_empty() {
+        dotenv::dotenv().ok()
     } else {
-        dotenv::from_path(env_path).ok();
+        dotenv::from_path(&env_path)
+            .ok()
+            .map(|_| std::path::PathBuf::from(env_path))
+    };
+    //记录加载的配置文件,运行时检查其变化
+    if let Some(path) = loaded_path {
+        std::env::set_var("RNACOS_ENV_FILE", path);
     }
 }
 
@@ -147,12 +161,17 @@ async fn run_console_web(source_app_data: Arc<AppShareData>) {
         let config_addr = app_data.config_addr.clone();
         let naming_addr = app_data.naming_addr.clone();
         let bistream_manage_http_addr = app_data.bi_stream_manage.clone();
+        let (config_store, naming_store, auth_provider) = AppShareData::store_data(&app_data);
         let app_data = app_data.clone();
         App::new()
+            .app_data(config_store)
+            .app_data(naming_store)
+            .app_data(auth_provider)
             .app_data(app_data)
             .app_data(Data::new(config_addr))
             .app_data(Data::new(naming_addr))
             .app_data(Data::new(bistream_manage_http_addr))
+            .wrap(ConfigCircuitBreakerCheck::new(source_app_data.clone()))
             .wrap(CheckLogin::new(source_app_data))
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
@@ -1,10 +1,11 @@
 use crate::now_millis_i64;
 
 use super::model::{Instance, ServiceDetailDto, ServiceKey};
+use super::naming_subscriber::NamingListenerItem;
 use super::NamingUtils;
 use chrono::Local;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -185,3 +186,91 @@ impl ServiceInfoParam {
         }
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceSubscribeParam {
+    pub namespace_id: Option<String>,
+    pub group_name: Option<String>,
+    pub service_name: Option<String>,
+    pub clusters: Option<String>,
+    /// 接收推送的grpc长链接id
+    pub connection_id: Option<String>,
+}
+
+impl ServiceSubscribeParam {
+    pub(crate) fn build_listener_item(self) -> anyhow::Result<(NamingListenerItem, Arc<String>)> {
+        let service_name = self.service_name.unwrap_or_default();
+        if service_name.is_empty() {
+            return Err(anyhow::anyhow!("serviceName is empty"));
+        }
+        let connection_id = self.connection_id.unwrap_or_default();
+        if connection_id.is_empty() {
+            return Err(anyhow::anyhow!("connectionId is empty"));
+        }
+        let service_key = ServiceKey::new(
+            &NamingUtils::default_namespace(self.namespace_id.unwrap_or_default()),
+            &NamingUtils::default_group(self.group_name.unwrap_or_default()),
+            &service_name,
+        );
+        let clusters = self
+            .clusters
+            .map(|v| NamingUtils::split_filters(&v))
+            .filter(|v| !v.is_empty())
+            .map(|v| v.into_iter().collect::<HashSet<_>>());
+        Ok((
+            NamingListenerItem {
+                service_key,
+                clusters,
+            },
+            Arc::new(connection_id),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscribe_param_listener_item() {
+        let param = ServiceSubscribeParam {
+            service_name: Some("foo".to_owned()),
+            clusters: Some("a,,b".to_owned()),
+            connection_id: Some("conn_1".to_owned()),
+            ..Default::default()
+        };
+        let (item, connection_id) = param.build_listener_item().unwrap();
+        assert_eq!(connection_id.as_str(), "conn_1");
+        assert_eq!(
+            item.service_key,
+            ServiceKey::new("public", "DEFAULT_GROUP", "foo")
+        );
+        assert_eq!(
+            item.clusters,
+            Some(HashSet::from(["a".to_owned(), "b".to_owned()]))
+        );
+        //未指定集群时订阅全部集群
+        let param = ServiceSubscribeParam {
+            namespace_id: Some("dev".to_owned()),
+            group_name: Some("g1".to_owned()),
+            service_name: Some("foo".to_owned()),
+            clusters: Some(",".to_owned()),
+            connection_id: Some("conn_1".to_owned()),
+        };
+        let (item, _) = param.build_listener_item().unwrap();
+        assert_eq!(item.service_key, ServiceKey::new("dev", "g1", "foo"));
+        assert!(item.clusters.is_none());
+        //缺少服务名或推送链接时报错
+        let param = ServiceSubscribeParam {
+            connection_id: Some("conn_1".to_owned()),
+            ..Default::default()
+        };
+        assert!(param.build_listener_item().is_err());
+        let param = ServiceSubscribeParam {
+            service_name: Some("foo".to_owned()),
+            ..Default::default()
+        };
+        assert!(param.build_listener_item().is_err());
+    }
+}
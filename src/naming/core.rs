@@ -15,6 +15,7 @@ use super::cluster::model::{
 };
 use super::cluster::node_manage::{InnerNodeManage, NodeManageRequest};
 use super::filter::InstanceFilterUtils;
+use super::instance_weight::InstanceWeightInfo;
 use super::listener::{InnerNamingListener, ListenerItem, NamingListenerCmd};
 use super::model::Instance;
 use super::model::InstanceKey;
@@ -194,6 +195,7 @@ impl NamingActor {
                 }
                 if let Some(metadata) = service_info.metadata {
                     service.metadata = metadata;
+                    service.update_auto_weight();
                 }
             }
             None => {
@@ -212,6 +214,7 @@ impl NamingActor {
                 }
                 if let Some(metadata) = service_info.metadata {
                     service.metadata = metadata;
+                    service.update_auto_weight();
                 }
                 service.recalculate_checksum();
                 self.namespace_index.insert_service(key.clone());
@@ -360,6 +363,33 @@ impl NamingActor {
         tag
     }
 
+    ///
+    /// 外部健康检查更新实例健康状态,变更后推送给订阅者
+    pub(crate) fn update_instance_healthy(
+        &mut self,
+        key: &ServiceKey,
+        instance_key: &InstanceShortKey,
+        healthy: bool,
+    ) -> anyhow::Result<Option<Arc<Instance>>> {
+        let service = match self.service_map.get_mut(key) {
+            Some(service) => service,
+            None => return Err(anyhow::anyhow!("service not exist")),
+        };
+        if service.get_instance(instance_key).is_none() {
+            return Err(anyhow::anyhow!("instance not exist"));
+        }
+        let instance = service.update_instance_healthy_by_admin(instance_key, healthy);
+        if let Some(instance) = &instance {
+            let notify_instance = Some(instance.clone()).filter(|e| !e.is_from_cluster());
+            self.do_notify(
+                &UpdateInstanceType::UpdateValue,
+                key.clone(),
+                notify_instance,
+            );
+        }
+        Ok(instance)
+    }
+
     pub(crate) fn remove_client_instance(&mut self, client_id: &Arc<String>) {
         if let Some(keys) = self.client_instance_set.remove(client_id) {
             for instance_key in keys {
@@ -721,6 +751,9 @@ pub enum NamingCmd {
     Delete(Instance),
     DeleteBatch(Vec<Instance>),
     Query(Instance),
+    //外部健康检查结果及本次检查的响应时间(毫秒)
+    UpdateHealthy(ServiceKey, InstanceShortKey, bool, Option<u64>),
+    QueryInstanceWeights(ServiceKey),
     QueryList(ServiceKey, String, bool, Option<SocketAddr>),
     QueryAllInstanceList(ServiceKey),
     QueryListString(ServiceKey, String, bool, Option<SocketAddr>),
@@ -749,6 +782,7 @@ pub enum NamingResult {
     Instance(Arc<Instance>),
     InstanceList(Vec<Arc<Instance>>),
     InstanceListString(String),
+    InstanceWeights(Vec<InstanceWeightInfo>),
     ServiceInfo(ServiceInfo),
     ServicePage((usize, Vec<Arc<String>>)),
     ServiceInfoPage((usize, Vec<ServiceInfoDto>)),
@@ -808,6 +842,19 @@ impl Handler<NamingCmd> for NamingActor {
                 }
                 Ok(NamingResult::NULL)
             }
+            NamingCmd::UpdateHealthy(service_key, instance_key, healthy, rt_millis) => {
+                if let (Some(rt_millis), Some(service)) =
+                    (rt_millis, self.service_map.get_mut(&service_key))
+                {
+                    service.add_response_time(&instance_key, rt_millis);
+                }
+                if let Some(i) =
+                    self.update_instance_healthy(&service_key, &instance_key, healthy)?
+                {
+                    return Ok(NamingResult::Instance(i));
+                }
+                Ok(NamingResult::NULL)
+            }
             NamingCmd::QueryList(service_key, cluster_str, only_healthy, addr) => {
                 let cluster_names = NamingUtils::split_filters(&cluster_str);
                 if let Some(addr) = addr {
@@ -902,6 +949,14 @@ impl Handler<NamingCmd> for NamingActor {
                 self.remove_empty_service(service_key)?;
                 Ok(NamingResult::NULL)
             }
+            NamingCmd::QueryInstanceWeights(key) => {
+                let list = self
+                    .service_map
+                    .get(&key)
+                    .map(|service| service.get_instance_weights())
+                    .unwrap_or_default();
+                Ok(NamingResult::InstanceWeights(list))
+            }
             NamingCmd::QueryAllInstanceList(key) => {
                 if let Some(service) = self.service_map.get(&key) {
                     Ok(NamingResult::InstanceList(service.get_instance_list(
@@ -1031,3 +1086,109 @@ fn test_remove_has_instance_service() {
     assert!(naming.remove_empty_service(service_key.clone()).is_ok());
     assert!(naming.namespace_index.service_size == 0);
 }
+
+#[test]
+fn test_admin_set_instance_unhealthy() {
+    use super::*;
+    let mut naming = NamingActor::new();
+    let service_key = ServiceKey::new("public", "DEFUALT", "foo");
+    for port in [8080, 8081] {
+        let mut instance = Instance::new("127.0.0.1".to_owned(), port);
+        instance.namespace_id = service_key.namespace_id.clone();
+        instance.service_name = service_key.service_name.clone();
+        instance.group_name = service_key.group_name.clone();
+        instance.init();
+        naming.update_instance(&service_key, instance, None);
+    }
+    naming.update_service(ServiceDetailDto {
+        namespace_id: service_key.namespace_id.clone(),
+        service_name: service_key.service_name.clone(),
+        group_name: service_key.group_name.clone(),
+        metadata: Default::default(),
+        protect_threshold: Some(0.9),
+    });
+    let short_key = InstanceShortKey::new(Arc::new("127.0.0.1".to_owned()), 8080);
+    assert!(naming
+        .update_instance_healthy(&service_key, &short_key, false)
+        .unwrap()
+        .is_some());
+    //外部设置的不健康实例不会被保护阈值恢复
+    let items = naming.get_instance_list(&service_key, "", true);
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].port, 8081);
+
+    //心跳不会覆盖外部设置的状态
+    let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+    instance.namespace_id = service_key.namespace_id.clone();
+    instance.service_name = service_key.service_name.clone();
+    instance.group_name = service_key.group_name.clone();
+    naming.update_instance(&service_key, instance, None);
+    assert!(
+        !naming
+            .get_instance(&service_key, &short_key)
+            .unwrap()
+            .healthy
+    );
+
+    naming
+        .update_instance_healthy(&service_key, &short_key, true)
+        .unwrap();
+    let items = naming.get_instance_list(&service_key, "", true);
+    assert_eq!(items.len(), 2);
+}
+
+#[test]
+fn test_auto_weight_by_response_time() {
+    use super::*;
+    use crate::naming::instance_weight::AUTO_WEIGHT_METADATA_KEY;
+    let mut naming = NamingActor::new();
+    let service_key = ServiceKey::new("public", "DEFUALT", "foo");
+    for port in [8080, 8081] {
+        let mut instance = Instance::new("127.0.0.1".to_owned(), port);
+        instance.namespace_id = service_key.namespace_id.clone();
+        instance.service_name = service_key.service_name.clone();
+        instance.group_name = service_key.group_name.clone();
+        instance.init();
+        naming.update_instance(&service_key, instance, None);
+    }
+    let fast_key = InstanceShortKey::new(Arc::new("127.0.0.1".to_owned()), 8080);
+    let slow_key = InstanceShortKey::new(Arc::new("127.0.0.1".to_owned()), 8081);
+    //未开启时忽略响应时间
+    naming
+        .get_service(&service_key)
+        .unwrap()
+        .add_response_time(&slow_key, 100);
+    assert!(naming
+        .get_service(&service_key)
+        .unwrap()
+        .rt_metrics
+        .is_empty());
+
+    let mut metadata = HashMap::new();
+    metadata.insert(AUTO_WEIGHT_METADATA_KEY.to_owned(), "true".to_owned());
+    naming.update_service(ServiceDetailDto {
+        namespace_id: service_key.namespace_id.clone(),
+        service_name: service_key.service_name.clone(),
+        group_name: service_key.group_name.clone(),
+        metadata: Some(Arc::new(metadata)),
+        protect_threshold: None,
+    });
+    let service = naming.get_service(&service_key).unwrap();
+    service.add_response_time(&fast_key, 10);
+    service.add_response_time(&slow_key, 50);
+    let items = naming.get_instance_list(&service_key, "", true);
+    let slow = items.iter().find(|i| i.port == 8081).unwrap();
+    let fast = items.iter().find(|i| i.port == 8080).unwrap();
+    assert!(slow.weight < fast.weight);
+    //原始权重不变
+    assert_eq!(
+        naming.get_instance(&service_key, &slow_key).unwrap().weight,
+        fast.weight
+    );
+    let weights = naming
+        .get_service(&service_key)
+        .unwrap()
+        .get_instance_weights();
+    assert_eq!(weights.len(), 2);
+    assert_eq!(weights[1].p95_millis, Some(50));
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use super::{
+    instance_weight::InstanceWeightUtils,
     model::{Instance, ServiceInfo},
     service::ServiceMetadata,
 };
@@ -16,34 +17,16 @@ impl InstanceFilterUtils {
         metadata: Option<ServiceMetadata>,
         filter_headlthy: bool,
     ) -> Vec<Arc<Instance>> {
+        let all_instances = match metadata.as_ref() {
+            Some(metadata) => InstanceWeightUtils::apply_effective_weights(
+                all_instances,
+                &metadata.effective_weights,
+            ),
+            None => all_instances,
+        };
         if let Some(metadata) = metadata {
-            let original_total = all_instances.len();
-            // all_instances = from metadata select
-            let mut healthy_count = 0;
-            for item in &all_instances {
-                if item.healthy {
-                    healthy_count += 1;
-                }
-            }
-            let threshold = if metadata.protect_threshold <= 0f32 {
-                0f32
-            } else {
-                metadata.protect_threshold
-            };
-            if (healthy_count as f32) / original_total as f32 <= threshold {
-                let instances = all_instances
-                    .iter()
-                    .map(|i| {
-                        if !i.healthy {
-                            let mut raw = i.as_ref().clone();
-                            raw.healthy = true;
-                            Arc::new(raw)
-                        } else {
-                            i.clone()
-                        }
-                    })
-                    .collect();
-                return instances;
+            if Self::reach_protection_threshold(&all_instances, &metadata) {
+                return Self::protect_instances(&all_instances, &metadata);
             }
         };
         if filter_headlthy {
@@ -58,35 +41,15 @@ impl InstanceFilterUtils {
         metadata: Option<ServiceMetadata>,
         filter_headlthy: bool,
     ) -> ServiceInfo {
+        if let Some(metadata) = metadata.as_ref() {
+            service_info.hosts = service_info.hosts.map(|hosts| {
+                InstanceWeightUtils::apply_effective_weights(hosts, &metadata.effective_weights)
+            });
+        }
         if let (Some(all_instances), Some(metadata)) = (service_info.hosts.as_ref(), metadata) {
-            let original_total = all_instances.len();
-            // all_instances = from metadata select
-            let mut healthy_count = 0;
-            for item in all_instances {
-                if item.healthy {
-                    healthy_count += 1;
-                }
-            }
-            let threshold = if metadata.protect_threshold <= 0f32 {
-                0f32
-            } else {
-                metadata.protect_threshold
-            };
-            if (healthy_count as f32) / original_total as f32 <= threshold {
+            if Self::reach_protection_threshold(all_instances, &metadata) {
                 service_info.reach_protection_threshold = true;
-                let instances = all_instances
-                    .iter()
-                    .map(|i| {
-                        if !i.healthy {
-                            let mut raw = i.as_ref().clone();
-                            raw.healthy = true;
-                            Arc::new(raw)
-                        } else {
-                            i.clone()
-                        }
-                    })
-                    .collect();
-                service_info.hosts = Some(instances);
+                service_info.hosts = Some(Self::protect_instances(all_instances, &metadata));
                 return service_info;
             }
         }
@@ -95,4 +58,53 @@ impl InstanceFilterUtils {
         }
         service_info
     }
+
+    ///
+    /// 外部健康检查设置为不健康的实例不计入保护阈值的统计
+    fn reach_protection_threshold(
+        all_instances: &[Arc<Instance>],
+        metadata: &ServiceMetadata,
+    ) -> bool {
+        let mut total = 0;
+        let mut healthy_count = 0;
+        for item in all_instances {
+            if metadata.admin_unhealthy.contains(&item.get_short_key()) {
+                continue;
+            }
+            total += 1;
+            if item.healthy {
+                healthy_count += 1;
+            }
+        }
+        if total == 0 {
+            return false;
+        }
+        let threshold = if metadata.protect_threshold <= 0f32 {
+            0f32
+        } else {
+            metadata.protect_threshold
+        };
+        (healthy_count as f32) / total as f32 <= threshold
+    }
+
+    ///
+    /// 触发保护阈值时只恢复心跳超时的实例,外部健康检查设置为不健康的实例仍然过滤
+    fn protect_instances(
+        all_instances: &[Arc<Instance>],
+        metadata: &ServiceMetadata,
+    ) -> Vec<Arc<Instance>> {
+        all_instances
+            .iter()
+            .filter(|i| !metadata.admin_unhealthy.contains(&i.get_short_key()))
+            .map(|i| {
+                if !i.healthy {
+                    let mut raw = i.as_ref().clone();
+                    raw.healthy = true;
+                    Arc::new(raw)
+                } else {
+                    i.clone()
+                }
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,162 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::model::{Instance, InstanceShortKey};
+
+///
+/// 服务元数据中设置为true时,按健康检查响应时间自动调整实例权重
+pub const AUTO_WEIGHT_METADATA_KEY: &str = "preserved.auto.weight";
+
+///
+/// 每个实例保留的响应时间样本数
+pub const RESPONSE_TIME_SAMPLE_SIZE: usize = 100;
+
+///
+/// p95平滑系数,越小权重变化越平缓
+pub const SMOOTHING_ALPHA: f64 = 0.3;
+
+///
+/// 权重最低缩放比例,避免高延迟实例完全没有流量
+pub const MIN_WEIGHT_FACTOR: f64 = 0.1;
+
+#[derive(Debug, Clone, Default)]
+pub struct ResponseTimeMetrics {
+    samples: VecDeque<u64>,
+    pub p95: u64,
+    pub smoothed_p95: f64,
+}
+
+impl ResponseTimeMetrics {
+    pub fn add_sample(&mut self, rt_millis: u64) {
+        if self.samples.len() >= RESPONSE_TIME_SAMPLE_SIZE {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(rt_millis);
+        let mut sorted: Vec<u64> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        let index = ((sorted.len() as f64 * 0.95).ceil() as usize).max(1) - 1;
+        self.p95 = sorted[index];
+        self.smoothed_p95 = if self.samples.len() == 1 {
+            self.p95 as f64
+        } else {
+            SMOOTHING_ALPHA * self.p95 as f64 + (1f64 - SMOOTHING_ALPHA) * self.smoothed_p95
+        };
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceWeightInfo {
+    pub ip: Arc<String>,
+    pub port: u32,
+    pub weight: f32,
+    pub effective_weight: f32,
+    pub sample_count: usize,
+    pub p95_millis: Option<u64>,
+    pub smoothed_p95_millis: Option<f64>,
+}
+
+pub struct InstanceWeightUtils;
+
+impl InstanceWeightUtils {
+    pub fn is_auto_weight(metadata: &HashMap<String, String>) -> bool {
+        metadata
+            .get(AUTO_WEIGHT_METADATA_KEY)
+            .map(|v| v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false)
+    }
+
+    ///
+    /// 有效权重与平滑后的p95成反比,响应最快的实例保持原权重;没有样本的实例保持原权重
+    pub fn calculate_effective_weights<'a>(
+        instances: impl Iterator<Item = &'a Arc<Instance>>,
+        metrics: &HashMap<InstanceShortKey, ResponseTimeMetrics>,
+    ) -> HashMap<InstanceShortKey, f32> {
+        let min_p95 = metrics
+            .values()
+            .map(|v| v.smoothed_p95)
+            .fold(f64::MAX, f64::min)
+            .max(1f64);
+        instances
+            .filter_map(|instance| {
+                let key = instance.get_short_key();
+                let metric = metrics.get(&key)?;
+                let factor =
+                    (min_p95 / metric.smoothed_p95.max(1f64)).clamp(MIN_WEIGHT_FACTOR, 1f64);
+                Some((key, (instance.weight as f64 * factor) as f32))
+            })
+            .collect()
+    }
+
+    pub fn apply_effective_weights(
+        instances: Vec<Arc<Instance>>,
+        weights: &HashMap<InstanceShortKey, f32>,
+    ) -> Vec<Arc<Instance>> {
+        if weights.is_empty() {
+            return instances;
+        }
+        instances
+            .into_iter()
+            .map(|i| match weights.get(&i.get_short_key()) {
+                Some(weight) if *weight != i.weight => {
+                    let mut raw = i.as_ref().clone();
+                    raw.weight = *weight;
+                    Arc::new(raw)
+                }
+                _ => i,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smoothed_p95() {
+        let mut metrics = ResponseTimeMetrics::default();
+        for i in 1..=100 {
+            metrics.add_sample(i);
+        }
+        assert_eq!(metrics.p95, 95);
+        assert_eq!(metrics.sample_count(), 100);
+        let before = metrics.smoothed_p95;
+        //单次突增的样本只会部分影响平滑后的值
+        metrics.add_sample(10000);
+        assert_eq!(metrics.sample_count(), 100);
+        assert!(metrics.smoothed_p95 > before);
+        assert!(metrics.smoothed_p95 < 100f64);
+    }
+
+    #[test]
+    fn effective_weights() {
+        let mut fast = Instance::new("127.0.0.1".to_owned(), 8080);
+        fast.weight = 1f32;
+        let mut slow = fast.clone();
+        slow.port = 8081;
+        let mut unknown = fast.clone();
+        unknown.port = 8082;
+        let instances = vec![Arc::new(fast), Arc::new(slow), Arc::new(unknown)];
+        let mut metrics = HashMap::new();
+        let mut m = ResponseTimeMetrics::default();
+        m.add_sample(10);
+        metrics.insert(instances[0].get_short_key(), m);
+        let mut m = ResponseTimeMetrics::default();
+        m.add_sample(40);
+        metrics.insert(instances[1].get_short_key(), m);
+        let weights = InstanceWeightUtils::calculate_effective_weights(instances.iter(), &metrics);
+        assert_eq!(weights.len(), 2);
+        assert_eq!(weights[&instances[0].get_short_key()], 1f32);
+        assert_eq!(weights[&instances[1].get_short_key()], 0.25f32);
+        let list = InstanceWeightUtils::apply_effective_weights(instances, &weights);
+        assert_eq!(list[1].weight, 0.25f32);
+        assert_eq!(list[2].weight, 1f32);
+    }
+}
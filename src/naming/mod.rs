@@ -1,6 +1,7 @@
 pub mod api_model;
 pub mod core;
 pub(crate) mod filter;
+pub mod instance_weight;
 pub mod listener;
 pub mod model;
 pub mod naming_delay_nofity;
@@ -162,3 +162,55 @@ impl Subscriber {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(service_name: &str, clusters: Option<&str>) -> NamingListenerItem {
+        NamingListenerItem {
+            service_key: ServiceKey::new("public", "DEFAULT_GROUP", service_name),
+            clusters: clusters.map(|v| HashSet::from([v.to_owned()])),
+        }
+    }
+
+    #[test]
+    fn subscribe_and_remove() {
+        let mut subscriber = Subscriber::new();
+        let client_1 = Arc::new("conn_1".to_owned());
+        let client_2 = Arc::new("conn_2".to_owned());
+        subscriber.add_subscribe(
+            client_1.clone(),
+            vec![item("foo", Some("a")), item("bar", None)],
+        );
+        subscriber.add_subscribe(client_2.clone(), vec![item("foo", None)]);
+        let foo = item("foo", None).service_key;
+        let bar = item("bar", None).service_key;
+        let listeners = subscriber.listener.get(&foo).unwrap();
+        assert_eq!(listeners.len(), 2);
+        assert_eq!(
+            listeners.get(&client_1).unwrap(),
+            &Some(HashSet::from(["a".to_owned()]))
+        );
+        assert_eq!(listeners.get(&client_2).unwrap(), &None);
+
+        //取消订阅只移除对应服务
+        subscriber.remove_subscribe(client_1.clone(), vec![item("foo", None)]);
+        assert_eq!(subscriber.listener.get(&foo).unwrap().len(), 1);
+        assert!(subscriber
+            .listener
+            .get(&bar)
+            .unwrap()
+            .contains_key(&client_1));
+        assert_eq!(subscriber.client_keys.get(&client_1).unwrap().len(), 1);
+
+        //链接断开时移除其全部订阅
+        subscriber.remove_client_subscribe(client_1.clone());
+        assert!(!subscriber.listener.contains_key(&bar));
+        assert!(!subscriber.client_keys.contains_key(&client_1));
+
+        subscriber.remove_key(foo.clone());
+        assert!(subscriber.listener.is_empty());
+        assert!(subscriber.client_keys.is_empty());
+    }
+}
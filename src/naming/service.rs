@@ -1,7 +1,7 @@
 #![allow(unused_assignments, unused_imports)]
 
 use std::{
-    collections::{HashMap, LinkedList},
+    collections::{HashMap, HashSet, LinkedList},
     hash::Hash,
     sync::{atomic::Ordering, Arc},
 };
@@ -12,6 +12,7 @@ use crate::now_millis;
 
 use super::{
     api_model::QueryListResult,
+    instance_weight::{InstanceWeightInfo, InstanceWeightUtils, ResponseTimeMetrics},
     model::{
         Instance, InstanceShortKey, InstanceTimeInfo, InstanceUpdateTag, ServiceDetailDto,
         ServiceKey, UpdateInstanceType,
@@ -21,6 +22,10 @@ use super::{
 #[derive(Debug, Clone, Default)]
 pub struct ServiceMetadata {
     pub protect_threshold: f32,
+    //由外部健康检查设置为不健康的实例,不参与保护阈值计算
+    pub admin_unhealthy: Arc<HashSet<InstanceShortKey>>,
+    //开启自动权重时按响应时间计算的有效权重
+    pub effective_weights: Arc<HashMap<InstanceShortKey, f32>>,
 }
 
 type InstanceMetaData = Arc<HashMap<String, String>>;
@@ -44,6 +49,11 @@ pub struct Service {
     pub(crate) instances: HashMap<InstanceShortKey, Arc<Instance>>,
     pub(crate) timeinfos: LinkedList<InstanceTimeInfo>,
     pub(crate) instance_metadata_map: HashMap<InstanceShortKey, InstanceMetaData>,
+    //由外部健康检查设置为不健康的实例
+    pub(crate) admin_unhealthy_set: Arc<HashSet<InstanceShortKey>>,
+    pub(crate) auto_weight: bool,
+    pub(crate) rt_metrics: HashMap<InstanceShortKey, ResponseTimeMetrics>,
+    pub(crate) effective_weights: Arc<HashMap<InstanceShortKey, f32>>,
 }
 
 impl Service {
@@ -79,6 +89,10 @@ impl Service {
         //let mut update_mark = true;
         let mut rtype = UpdateInstanceType::None;
         let short_key = instance.get_short_key();
+        if self.admin_unhealthy_set.contains(&key) {
+            //外部健康检查设置的状态优先于心跳
+            instance.healthy = false;
+        }
         let old_instance = self.instances.get(&key);
         if let Some(old_instance) = old_instance {
             if !instance.from_grpc {
@@ -144,7 +158,9 @@ impl Service {
                 instance.metadata = priority_metadata.clone();
             }
             self.instance_size += 1;
-            self.healthy_instance_size += 1;
+            if instance.healthy {
+                self.healthy_instance_size += 1;
+            }
             rtype = UpdateInstanceType::New;
         }
         let new_instance = Arc::new(instance);
@@ -153,7 +169,10 @@ impl Service {
             let time_info = new_instance.get_time_info();
             self.update_timeinfos(time_info);
         }
-        self.instances.insert(key, new_instance);
+        self.instances.insert(key.clone(), new_instance);
+        if self.rt_metrics.contains_key(&key) {
+            self.recalculate_effective_weights();
+        }
         /*
         if update_mark {
             self.update_timeinfos(time_info);
@@ -222,6 +241,12 @@ impl Service {
             }
         }
         if let Some(old) = self.instances.remove(instance_key) {
+            if self.admin_unhealthy_set.contains(instance_key) {
+                Arc::make_mut(&mut self.admin_unhealthy_set).remove(instance_key);
+            }
+            if self.rt_metrics.remove(instance_key).is_some() {
+                self.recalculate_effective_weights();
+            }
             self.instance_size -= 1;
             if self.instance_size == 0 {
                 self.last_empty_times = now_millis();
@@ -246,6 +271,93 @@ impl Service {
         }
     }
 
+    ///
+    /// 由外部健康检查设置实例健康状态,与心跳超时的不健康区分开
+    /// 返回状态有变更的实例
+    pub(crate) fn update_instance_healthy_by_admin(
+        &mut self,
+        instance_key: &InstanceShortKey,
+        healthy: bool,
+    ) -> Option<Arc<Instance>> {
+        let old = self.instances.get(instance_key)?.clone();
+        let admin_unhealthy_set = Arc::make_mut(&mut self.admin_unhealthy_set);
+        if healthy {
+            admin_unhealthy_set.remove(instance_key);
+        } else {
+            admin_unhealthy_set.insert(instance_key.clone());
+        }
+        if old.healthy == healthy {
+            return None;
+        }
+        if healthy {
+            self.healthy_instance_size += 1;
+        } else {
+            self.healthy_instance_size -= 1;
+        }
+        let mut instance = old.as_ref().clone();
+        instance.healthy = healthy;
+        let instance = Arc::new(instance);
+        self.instances
+            .insert(instance_key.clone(), instance.clone());
+        Some(instance)
+    }
+
+    ///
+    /// 按服务元数据更新自动权重开关,关闭时清空已收集的响应时间
+    pub(crate) fn update_auto_weight(&mut self) {
+        self.auto_weight = InstanceWeightUtils::is_auto_weight(&self.metadata);
+        if !self.auto_weight {
+            self.rt_metrics.clear();
+            self.effective_weights = Default::default();
+        }
+    }
+
+    ///
+    /// 记录健康检查的响应时间,未开启自动权重时忽略
+    pub(crate) fn add_response_time(&mut self, instance_key: &InstanceShortKey, rt_millis: u64) {
+        if !self.auto_weight || !self.instances.contains_key(instance_key) {
+            return;
+        }
+        self.rt_metrics
+            .entry(instance_key.clone())
+            .or_default()
+            .add_sample(rt_millis);
+        self.recalculate_effective_weights();
+    }
+
+    fn recalculate_effective_weights(&mut self) {
+        self.effective_weights = Arc::new(InstanceWeightUtils::calculate_effective_weights(
+            self.instances.values(),
+            &self.rt_metrics,
+        ));
+    }
+
+    pub(crate) fn get_instance_weights(&self) -> Vec<InstanceWeightInfo> {
+        let mut list: Vec<InstanceWeightInfo> = self
+            .instances
+            .values()
+            .map(|i| {
+                let key = i.get_short_key();
+                let metric = self.rt_metrics.get(&key);
+                InstanceWeightInfo {
+                    ip: i.ip.clone(),
+                    port: i.port,
+                    weight: i.weight,
+                    effective_weight: self
+                        .effective_weights
+                        .get(&key)
+                        .copied()
+                        .unwrap_or(i.weight),
+                    sample_count: metric.map(|m| m.sample_count()).unwrap_or_default(),
+                    p95_millis: metric.map(|m| m.p95),
+                    smoothed_p95_millis: metric.map(|m| m.smoothed_p95),
+                }
+            })
+            .collect();
+        list.sort_by(|a, b| a.ip.cmp(&b.ip).then(a.port.cmp(&b.port)));
+        list
+    }
+
     pub(crate) fn get_instance(&self, instance_key: &InstanceShortKey) -> Option<Arc<Instance>> {
         self.instances.get(instance_key).cloned()
     }
@@ -296,6 +408,8 @@ impl Service {
     pub fn get_metadata(&self) -> ServiceMetadata {
         ServiceMetadata {
             protect_threshold: self.protect_threshold,
+            admin_unhealthy: self.admin_unhealthy_set.clone(),
+            effective_weights: self.effective_weights.clone(),
         }
     }
 
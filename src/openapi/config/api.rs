@@ -1,22 +1,56 @@
-use std::cmp::{max, min};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use actix::Addr;
-use actix_web::{web, HttpRequest, HttpResponse, Responder, Scope};
-use chrono::Local;
+use actix_multipart::Multipart;
+use actix_web::{
+    http::header, http::header::HeaderValue, http::StatusCode, http::Version, web, HttpMessage,
+    HttpRequest, HttpResponse, HttpResponseBuilder, Responder, Scope,
+};
+use futures_util::TryStreamExt;
 use serde::{Deserialize, Serialize};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
 
 use crate::common::appdata::AppShareData;
-use crate::common::web_utils::get_req_body;
-use crate::config::config_type::ConfigType;
-use crate::config::core::{
-    ConfigActor, ConfigCmd, ConfigKey, ConfigResult, ListenerItem, ListenerResult,
+use crate::common::constant::{
+    ALIAS_OF_HEADER, ARCHIVED_HEADER, CONSISTENCY_HEADER, CREATED_FROM_TEMPLATE_HEADER,
+    DEFAULT_USED_HEADER, IDEMPOTENCY_KEY_HEADER, LISTENER_CLIENT_ID_HEADER,
+    SUGGESTED_INTERVAL_HEADER,
+};
+use crate::common::model::TokenSession;
+use crate::common::web_utils::{
+    config_in_use_response, get_decoded_req_body, has_config_read_permission,
+    has_config_write_permission, req_body_error_response,
 };
+use crate::config::config_access_log::ConfigAccessRecord;
+use crate::config::config_api_version::{ApiVersion, ConfigEntry};
+use crate::config::config_consumer::ConsumerRecord;
+use crate::config::config_feature_flag::FeatureFlag;
+use crate::config::config_idempotency::{
+    ConfigIdempotencyCache, IdempotencyState, IdempotentResponse,
+};
+use crate::config::config_json_path::select_json_path;
+use crate::config::config_notify_debounce::ConfigPriority;
+use crate::config::config_read_cache::CachedEntry;
+use crate::config::config_read_view::ConfigReadValue;
+use crate::config::config_render::{ConfigRenderer, RenderEngine, RENDER_CONTEXT_DATA_ID};
+use crate::config::config_stream::{run_config_stream, StreamFormat};
+use crate::config::config_template_create::create_from_template;
+use crate::config::config_transcode::ConfigTranscoder;
+use crate::config::config_type::ConfigType;
+use crate::config::config_validate::ConfigValidator;
+use crate::config::config_watch::ConfigWatchManage;
+use crate::config::config_webhook::WebhookValidateResult;
+use crate::config::core::{ConfigCmd, ConfigKey, ListenerItem, ListenerResult};
+use crate::config::model::{ConfigMd5MismatchError, ConfigSetResult};
 use crate::config::utils::param_utils;
+use crate::config::ConfigUtils;
+use crate::now_millis_i64;
 use crate::openapi::constant::EMPTY;
 use crate::raft::cluster::model::{DelConfigReq, SetConfigReq};
-use crate::utils::select_option_by_clone;
+use crate::raft::read_lease::{ReadConsistency, ReadConsistencyError};
+use crate::utils::{gz_encode, select_option_by_clone};
 
 pub(super) fn service() -> Scope {
     web::scope("/configs")
@@ -28,6 +62,135 @@ pub(super) fn service() -> Scope {
                 .route(web::delete().to(del_config)),
         )
         .service(web::resource("/listener").route(web::post().to(listener_config)))
+        .service(web::resource("/batch-listener").route(web::post().to(batch_listener_config)))
+        .service(web::resource("/listener-json").route(web::post().to(json_listener_config)))
+        .service(web::resource("/consumer-heartbeat").route(web::post().to(consumer_heartbeat)))
+        .service(web::resource("/subscribe").route(web::get().to(subscribe_config)))
+        .service(web::resource("/batch").route(web::post().to(multi_set_config)))
+        .service(web::resource("/template").route(web::post().to(create_config_from_template)))
+}
+
+pub(super) fn feature_flag_service() -> Scope {
+    web::scope("/feature-flags")
+        .service(web::resource("/{data_id}").route(web::get().to(get_feature_flag)))
+}
+
+/// 读取配置时最多返回的关联配置数
+const CO_ACCESS_PUSH_LIMIT: usize = 3;
+/// 单个客户端心跳可上报的最大配置数
+const CONSUMER_MAX_CONFIG_KEYS: usize = 1000;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigKeyItem {
+    pub data_id: Option<String>,
+    pub group: Option<String>,
+    pub tenant: Option<String>,
+}
+
+impl ConfigKeyItem {
+    fn to_key(&self) -> Result<ConfigKey, &'static str> {
+        let data_id = self.data_id.clone().unwrap_or_default();
+        if data_id.is_empty() {
+            return Err("dataId is empty");
+        }
+        let group = match &self.group {
+            Some(v) if !v.is_empty() => v.to_owned(),
+            _ => "DEFAULT_GROUP".to_owned(),
+        };
+        let tenant = ConfigUtils::default_tenant(self.tenant.clone().unwrap_or_default());
+        Ok(ConfigKey::new(&data_id, &group, &tenant))
+    }
+}
+
+/// 批量长轮询单次可监听的最大配置数
+const BATCH_LISTENER_MAX_CONFIGS: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchListenerConfigItem {
+    #[serde(flatten)]
+    pub key: ConfigKeyItem,
+    pub md5: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchListenerParams {
+    #[serde(default)]
+    pub configs: Vec<BatchListenerConfigItem>,
+    //毫秒,与Long-Pulling-Timeout请求头使用相同的取值范围
+    pub timeout: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ListenerChangedItem {
+    #[serde(flatten)]
+    pub key: ConfigKeyItem,
+    //变更后的md5,配置已删除时为空
+    pub new_md5: String,
+}
+
+/// 原子批量写入单次可写入的最大配置数
+const MULTI_SET_MAX_CONFIGS: usize = 100;
+
+///
+/// 逗号分隔的依赖配置,每项格式同ConfigKey的字符串格式
+fn parse_config_references(value: Option<&str>) -> anyhow::Result<Option<Vec<ConfigKey>>> {
+    match value {
+        Some(v) => v
+            .split(',')
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                let key = v.parse::<ConfigKey>()?;
+                key.is_valid()?;
+                Ok(key)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map(Some),
+        None => Ok(None),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiSetConfigItem {
+    #[serde(flatten)]
+    pub key: ConfigKeyItem,
+    pub content: Option<String>,
+    pub r#type: Option<String>,
+    pub expected_md5: Option<String>,
+    //依赖的配置,格式同单个写入的references参数
+    pub references: Option<String>,
+}
+
+impl MultiSetConfigItem {
+    pub fn parse_references(&self) -> anyhow::Result<Option<Vec<ConfigKey>>> {
+        parse_config_references(self.references.as_deref())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateCreateParams {
+    #[serde(flatten)]
+    pub key: ConfigKeyItem,
+    //模板配置的dataId,模板默认与配置同分组
+    pub template: Option<String>,
+    pub template_group: Option<String>,
+    //自动创建的配置超过该时长(秒)未读写时删除
+    pub ttl: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsumerHeartbeatParams {
+    pub client_id: Option<String>,
+    //md5为客户端正在使用的版本,用于检查配置传播时限
+    #[serde(default)]
+    pub config_keys: Vec<BatchListenerConfigItem>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -37,6 +200,27 @@ pub struct ConfigWebParams {
     pub group: Option<String>,
     pub tenant: Option<String>,
     pub content: Option<String>,
+    pub r#type: Option<String>,
+    pub dry_run: Option<bool>,
+    pub expected_md5: Option<String>,
+    //可接受的缓存时长(秒),设置后优先读取缓存
+    pub max_staleness: Option<u64>,
+    //依赖的配置,多个以逗号分隔,格式为tenant##group##dataId
+    pub references: Option<String>,
+    //服务端渲染模板,目前支持jinja
+    pub render: Option<String>,
+    //SSE订阅的事件格式,full或diff
+    pub format: Option<String>,
+    //删除被其它配置依赖的配置
+    pub force: Option<bool>,
+    //properties配置以json返回,数字与布尔值转为原生类型
+    pub coerce: Option<bool>,
+    //变更通知的优先级:normal、high或critical
+    pub priority: Option<String>,
+    //为true时无法解析的跨命名空间引用返回错误,否则替换为空字符串
+    pub strict_refs: Option<bool>,
+    //json配置只返回匹配该路径的部分,如`$.database`
+    pub json_path: Option<String>,
 }
 
 impl ConfigWebParams {
@@ -46,9 +230,25 @@ impl ConfigWebParams {
             group: select_option_by_clone(&self.group, &o.group),
             tenant: select_option_by_clone(&self.tenant, &o.tenant),
             content: select_option_by_clone(&self.content, &o.content),
+            r#type: select_option_by_clone(&self.r#type, &o.r#type),
+            dry_run: select_option_by_clone(&self.dry_run, &o.dry_run),
+            expected_md5: select_option_by_clone(&self.expected_md5, &o.expected_md5),
+            max_staleness: select_option_by_clone(&self.max_staleness, &o.max_staleness),
+            references: select_option_by_clone(&self.references, &o.references),
+            render: select_option_by_clone(&self.render, &o.render),
+            format: select_option_by_clone(&self.format, &o.format),
+            force: select_option_by_clone(&self.force, &o.force),
+            coerce: select_option_by_clone(&self.coerce, &o.coerce),
+            priority: select_option_by_clone(&self.priority, &o.priority),
+            strict_refs: select_option_by_clone(&self.strict_refs, &o.strict_refs),
+            json_path: select_option_by_clone(&self.json_path, &o.json_path),
         }
     }
 
+    pub fn parse_references(&self) -> anyhow::Result<Option<Vec<ConfigKey>>> {
+        parse_config_references(self.references.as_deref())
+    }
+
     pub fn to_confirmed_param(&self) -> Result<ConfigWebConfirmedParam, String> {
         let mut param = ConfigWebConfirmedParam::default();
         if let Some(v) = self.data_id.as_ref() {
@@ -84,24 +284,222 @@ pub struct ConfigWebConfirmedParam {
     pub content: String,
 }
 
+fn to_http_response(v: IdempotentResponse) -> HttpResponse {
+    HttpResponse::build(StatusCode::from_u16(v.status).unwrap_or(StatusCode::OK))
+        .content_type(v.content_type)
+        .body(v.body)
+}
+
+///
+/// If-Match头:`*`要求配置已存在;否则为ETag列表,按强校验比较,弱校验`W/`的ETag不会匹配
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum IfMatch {
+    Any,
+    Tags(Vec<String>),
+}
+
+impl IfMatch {
+    fn parse(v: &str) -> Option<Self> {
+        let v = v.trim();
+        if v.is_empty() {
+            return None;
+        }
+        if v == "*" {
+            return Some(Self::Any);
+        }
+        let tags = v
+            .split(',')
+            .map(|v| v.trim())
+            .filter(|v| !v.starts_with("W/"))
+            .map(|v| v.trim_matches('"'))
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_owned())
+            .collect();
+        Some(Self::Tags(tags))
+    }
+
+    ///
+    /// 按配置当前的md5得到写入时要求的md5,由ConfigActor在写入时再次比较;
+    /// 配置不存在或没有匹配的ETag时返回None
+    fn expected_md5(&self, current_md5: Option<&Arc<String>>) -> Option<Arc<String>> {
+        let current_md5 = current_md5?;
+        match self {
+            Self::Any => Some(current_md5.clone()),
+            Self::Tags(tags) => tags
+                .iter()
+                .any(|v| v.as_str() == current_md5.as_str())
+                .then(|| current_md5.clone()),
+        }
+    }
+}
+
+fn precondition_failed_response(current_md5: &str) -> IdempotentResponse {
+    IdempotentResponse::new(
+        StatusCode::PRECONDITION_FAILED.as_u16(),
+        "application/json",
+        serde_json::json!({
+            "currentMd5": current_md5,
+        })
+        .to_string(),
+    )
+}
+
+fn is_multipart(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_ascii_lowercase().starts_with("multipart/form-data"))
+        .unwrap_or(false)
+}
+
+///
+/// 解析multipart/form-data表单参数;content部分的Content-Type能对应配置类型且未指定type参数时,按其设置配置类型
+async fn parse_multipart_params(
+    req: &HttpRequest,
+    body: Vec<u8>,
+) -> anyhow::Result<ConfigWebParams> {
+    let stream = futures_util::stream::once(async move {
+        Ok::<_, actix_web::error::PayloadError>(web::Bytes::from(body))
+    });
+    let mut multipart = Multipart::new(req.headers(), stream);
+    let mut fields: Vec<(String, String)> = vec![];
+    let mut content_type = None;
+    while let Some(mut field) = TryStreamExt::try_next(&mut multipart)
+        .await
+        .map_err(|err| anyhow::anyhow!("parse multipart error, {}", err))?
+    {
+        let name = match field.content_disposition().get_name() {
+            Some(v) => v.to_owned(),
+            None => continue,
+        };
+        if name == "content" {
+            content_type = field
+                .content_type()
+                .and_then(|v| ConfigType::new_by_media_type(v.as_ref()));
+        }
+        let mut data = vec![];
+        while let Some(chunk) = TryStreamExt::try_next(&mut field)
+            .await
+            .map_err(|err| anyhow::anyhow!("parse multipart error, {}", err))?
+        {
+            data.extend_from_slice(&chunk);
+        }
+        fields.push((name, String::from_utf8(data)?));
+    }
+    let mut params: ConfigWebParams =
+        serde_urlencoded::from_str(&serde_urlencoded::to_string(&fields)?)?;
+    if params.r#type.is_none() {
+        params.r#type = content_type.map(|v| v.get_value().as_ref().to_owned());
+    }
+    Ok(params)
+}
+
+///
+/// 单个与批量写入共用的写入前校验:功能开关内容格式、引用的配置存在、外部校验webhook;
+/// 校验结果计入配置的校验错误率;字段自动加密与别名在ConfigActor中统一处理
+async fn validate_set_config(
+    appdata: &AppShareData,
+    req: &SetConfigReq,
+) -> Result<(), HttpResponse> {
+    let result = check_set_config(appdata, req).await;
+    appdata
+        .config_validation_stats
+        .record(&req.config_key, result.is_ok(), now_millis_i64());
+    result
+}
+
+async fn check_set_config(appdata: &AppShareData, req: &SetConfigReq) -> Result<(), HttpResponse> {
+    let config_type = req
+        .config_type
+        .as_ref()
+        .map(|v| ConfigType::new_by_value(v));
+    if let Some(ConfigType::FeatureFlag) = config_type {
+        if let Err(err) = FeatureFlag::parse(&req.value) {
+            return Err(
+                HttpResponse::BadRequest().body(format!("invalid feature_flag content: {}", err))
+            );
+        }
+    }
+    if let Some(references) = &req.references {
+        let missing = appdata.config_read_view.missing_keys(references);
+        if !missing.is_empty() {
+            return Err(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "REFERENCED_CONFIG_NOT_FOUND",
+                "missing": missing,
+            })));
+        }
+    }
+    if let Some(webhook) = &appdata.config_validation_webhook {
+        let result = webhook
+            .validate(
+                &req.config_key,
+                &req.value,
+                req.config_type.as_ref().map(|v| v.as_str()),
+            )
+            .await;
+        if let WebhookValidateResult::Reject(reason) = result {
+            return Err(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "CONFIG_VALIDATION_REJECTED",
+                "key": req.config_key.build_key(),
+                "reason": reason,
+            })));
+        }
+    }
+    Ok(())
+}
+
 pub(crate) async fn add_config(
+    req: HttpRequest,
     a: web::Query<ConfigWebParams>,
     payload: web::Payload,
     appdata: web::Data<Arc<AppShareData>>,
 ) -> impl Responder {
-    let body = match get_req_body(payload).await {
+    let idempotency_key = req
+        .headers()
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_owned());
+    let if_match = req
+        .headers()
+        .get(header::IF_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(IfMatch::parse);
+    let body = match get_decoded_req_body(&req, payload).await {
         Ok(v) => v,
         Err(err) => {
-            return HttpResponse::InternalServerError().body(err.to_string());
+            return req_body_error_response(err);
         }
     };
-    let b = match serde_urlencoded::from_bytes(&body) {
-        Ok(v) => v,
-        Err(err) => {
-            return HttpResponse::InternalServerError().body(err.to_string());
+    let request_digest = idempotency_key
+        .as_ref()
+        .map(|_| ConfigIdempotencyCache::request_digest(req.query_string(), &body));
+    let b = if is_multipart(&req) {
+        match parse_multipart_params(&req, body).await {
+            Ok(v) => v,
+            Err(err) => {
+                return HttpResponse::BadRequest().body(err.to_string());
+            }
+        }
+    } else {
+        match serde_urlencoded::from_bytes(&body) {
+            Ok(v) => v,
+            Err(err) => {
+                return HttpResponse::InternalServerError().body(err.to_string());
+            }
         }
     };
     let selected_param = a.select_option(&b);
+    if selected_param.dry_run.unwrap_or(false) {
+        let result = ConfigValidator::validate(
+            &selected_param.data_id,
+            &selected_param.group,
+            &selected_param.tenant,
+            &selected_param.content,
+            &selected_param.r#type,
+        );
+        return HttpResponse::Ok().json(result);
+    }
     match param_utils::check_tenant(&selected_param.tenant) {
         Ok(v) => v,
         Err(err) => {
@@ -117,37 +515,130 @@ pub(crate) async fn add_config(
     ) {
         Ok(v) => v,
         Err(err) => {
-            return HttpResponse::InternalServerError().body(err.to_string());
+            appdata.config_validation_stats.record_param_error(
+                &selected_param.data_id,
+                &selected_param.group,
+                &selected_param.tenant,
+                &err,
+                now_millis_i64(),
+            );
+            return param_utils::check_param_error_response(err);
         }
     }
 
     let param = selected_param.to_confirmed_param();
     match param {
         Ok(p) => {
-            let req = SetConfigReq::new(
+            let mut req = SetConfigReq::new(
                 ConfigKey::new(&p.data_id, &p.group, &p.tenant),
                 Arc::new(p.content.to_owned()),
             );
-            match appdata.config_route.set_config(req).await {
-                Ok(_) => HttpResponse::Ok()
-                    .content_type("text/html; charset=utf-8")
-                    .body("true"),
-                Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+            req.config_type = selected_param
+                .r#type
+                .as_ref()
+                .map(|v| ConfigType::new_by_value(v).get_value());
+            //If-Match优先于expectedMd5参数;Err为前置条件不成立时配置当前的md5
+            let if_match_md5 = if_match.as_ref().map(|if_match| {
+                let current_md5 = appdata
+                    .config_read_view
+                    .get(&req.config_key)
+                    .map(|v| v.md5.clone());
+                if_match
+                    .expected_md5(current_md5.as_ref())
+                    .ok_or(current_md5)
+            });
+            req.expected_md5 = match &if_match_md5 {
+                Some(Ok(md5)) => Some(md5.clone()),
+                Some(Err(_)) => None,
+                None => selected_param.expected_md5.clone().map(Arc::new),
+            };
+            match selected_param.parse_references() {
+                Ok(references) => req.references = references,
+                Err(err) => {
+                    return HttpResponse::BadRequest().body(err.to_string());
+                }
+            }
+            if let Some(v) = &selected_param.priority {
+                match ConfigPriority::new_by_value(v) {
+                    Some(priority) => req.priority = Some(priority),
+                    None => {
+                        return HttpResponse::BadRequest()
+                            .body(format!("unknown config priority: {}", v));
+                    }
+                }
+            }
+            let reservation = match (&idempotency_key, &request_digest) {
+                (Some(key), Some(digest)) => {
+                    let cache_key = ConfigIdempotencyCache::build_key(key, &req.config_key, digest);
+                    match appdata.config_idempotency_cache.reserve(cache_key) {
+                        IdempotencyState::Cached(v) => return to_http_response(v),
+                        IdempotencyState::Pending => {
+                            return HttpResponse::Conflict().json(serde_json::json!({
+                                "error": "IDEMPOTENT_REQUEST_IN_PROGRESS",
+                            }));
+                        }
+                        IdempotencyState::Reserved(v) => Some(v),
+                    }
+                }
+                _ => None,
+            };
+            if let Some(Err(current_md5)) = &if_match_md5 {
+                let response =
+                    precondition_failed_response(current_md5.as_ref().map_or("", |v| v.as_str()));
+                if let Some(reservation) = reservation {
+                    reservation.complete(response.clone());
+                }
+                return to_http_response(response);
             }
+            if let Err(response) = validate_set_config(&appdata, &req).await {
+                return response;
+            }
+            let response = match appdata.config_batch_proposer.set_config(req).await {
+                Ok(_) => IdempotentResponse::new(
+                    StatusCode::OK.as_u16(),
+                    "text/html; charset=utf-8",
+                    "true".to_owned(),
+                ),
+                Err(err) => {
+                    if let Some(e) = err.downcast_ref::<ConfigMd5MismatchError>() {
+                        let current_md5 = e.current_md5.as_ref().map(|v| v.as_str()).unwrap_or("");
+                        if if_match_md5.is_some() {
+                            precondition_failed_response(current_md5)
+                        } else {
+                            let body = serde_json::json!({
+                                "error": "MD5_MISMATCH",
+                                "currentMd5": current_md5,
+                            });
+                            IdempotentResponse::new(
+                                StatusCode::CONFLICT.as_u16(),
+                                "application/json",
+                                body.to_string(),
+                            )
+                        }
+                    } else {
+                        return HttpResponse::InternalServerError().body(err.to_string());
+                    }
+                }
+            };
+            if let Some(reservation) = reservation {
+                reservation.complete(response.clone());
+            }
+            to_http_response(response)
         }
         Err(e) => HttpResponse::InternalServerError().body(e),
     }
 }
 
 pub(crate) async fn del_config(
+    req: HttpRequest,
     a: web::Query<ConfigWebParams>,
     payload: web::Payload,
     appdata: web::Data<Arc<AppShareData>>,
 ) -> impl Responder {
-    let body = match get_req_body(payload).await {
+    let body = match get_decoded_req_body(&req, payload).await {
         Ok(v) => v,
         Err(err) => {
-            return HttpResponse::InternalServerError().body(err.to_string());
+            return req_body_error_response(err);
         }
     };
     let b = match serde_urlencoded::from_bytes(&body) {
@@ -173,61 +664,603 @@ pub(crate) async fn del_config(
     ) {
         Ok(v) => v,
         Err(err) => {
-            return HttpResponse::InternalServerError().body(err.to_string());
+            return param_utils::check_param_error_response(err);
         }
     }
 
     let param = selected_param.to_confirmed_param();
     match param {
         Ok(p) => {
-            let req = DelConfigReq::new(ConfigKey::new(&p.data_id, &p.group, &p.tenant));
-            match appdata.config_route.del_config(req).await {
+            let mut del_req = DelConfigReq::new(ConfigKey::new(&p.data_id, &p.group, &p.tenant));
+            del_req.force = selected_param.force.unwrap_or(false);
+            del_req.op_user = req
+                .extensions()
+                .get::<Arc<TokenSession>>()
+                .map(|v| v.username.clone());
+            match appdata.config_route.del_config(del_req).await {
                 Ok(_) => HttpResponse::Ok()
                     .content_type("text/html; charset=utf-8")
                     .body("true"),
-                Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+                Err(err) => config_in_use_response(&err)
+                    .unwrap_or_else(|| HttpResponse::InternalServerError().body(err.to_string())),
             }
         }
         Err(e) => HttpResponse::InternalServerError().body(e),
     }
 }
 
+fn accept_gzip(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| {
+            v.split(',').any(|item| {
+                let mut parts = item.split(';');
+                let encoding = parts.next().unwrap_or_default().trim();
+                let disabled = parts.any(|p| {
+                    p.trim()
+                        .strip_prefix("q=")
+                        .and_then(|q| q.parse::<f32>().ok())
+                        .map(|q| q <= 0f32)
+                        .unwrap_or(false)
+                });
+                encoding.eq_ignore_ascii_case("gzip") && !disabled
+            })
+        })
+        .unwrap_or(false)
+}
+
+///
+/// 客户端支持gzip且内容不小于min_compress_bytes时压缩响应体;
+/// content-md5与ETag始终为未压缩内容的md5;响应随Accept协商,缓存需按Accept区分
+fn build_config_body(
+    req: &HttpRequest,
+    mut builder: HttpResponseBuilder,
+    md5: &str,
+    body: &[u8],
+    min_compress_bytes: usize,
+) -> HttpResponse {
+    builder
+        .insert_header(("content-md5", md5.to_owned()))
+        .insert_header((header::ETAG, format!("\"{}\"", md5)))
+        .insert_header((header::VARY, "Accept, Accept-Encoding"));
+    if body.len() >= min_compress_bytes && accept_gzip(req) {
+        builder
+            .insert_header((header::CONTENT_ENCODING, "gzip"))
+            .body(gz_encode(body, 0))
+    } else {
+        builder.body(body.to_vec())
+    }
+}
+
+fn read_consistency_error_response(err: ReadConsistencyError) -> HttpResponse {
+    let body = match err {
+        ReadConsistencyError::NotLeader(leader_id) => serde_json::json!({
+            "error": "NOT_LEADER",
+            "leaderId": leader_id,
+        }),
+        ReadConsistencyError::Raft(msg) => serde_json::json!({
+            "error": "READ_CONSISTENCY_ERROR",
+            "message": msg,
+        }),
+    };
+    HttpResponse::ServiceUnavailable().json(body)
+}
+
+///
+/// 超过单个配置的并发读取数时不排队;客户端已有当前内容时返回304,否则返回429
+fn read_limited_response(
+    req: &HttpRequest,
+    appdata: &AppShareData,
+    config_key: &ConfigKey,
+) -> HttpResponse {
+    let md5 = appdata
+        .config_read_view
+        .get(config_key)
+        .map(|v| v.md5.clone());
+    let if_none_match = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim_matches('"'));
+    match (md5, if_none_match) {
+        (Some(md5), Some(etag)) if md5.as_str() == etag => HttpResponse::NotModified()
+            .insert_header((header::ETAG, format!("\"{}\"", md5)))
+            .finish(),
+        _ => HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, "1"))
+            .body("too many concurrent reads of config"),
+    }
+}
+
+///
+/// 配置不存在时读取同分组下的默认配置,只继承一层
+fn get_group_default_config(
+    appdata: &AppShareData,
+    key: &ConfigKey,
+) -> Option<(ConfigKey, Arc<ConfigReadValue>)> {
+    if !appdata.sys_config.config_group_default_enabled {
+        return None;
+    }
+    let default_key = key.group_default_key()?;
+    let value = appdata.config_read_view.get(&default_key)?;
+    Some((default_key, value))
+}
+
+///
+/// 开启访问日志时记录每次读取的客户端、用户、响应码与耗时
+fn log_config_access(
+    req: &HttpRequest,
+    appdata: &AppShareData,
+    keys: &[ConfigKey],
+    response_code: u16,
+    start: Instant,
+) {
+    let latency_ms = start.elapsed().as_millis() as u64;
+    let client_ip = req
+        .connection_info()
+        .realip_remote_addr()
+        .map(|v| v.to_owned());
+    let user = req
+        .extensions()
+        .get::<Arc<TokenSession>>()
+        .map(|v| v.username.clone());
+    for key in keys {
+        let mut record =
+            ConfigAccessRecord::new(key, req.method().to_string(), response_code, latency_ms);
+        record.client_ip = client_ip.clone();
+        record.user = user.clone();
+        appdata.config_access_logger.log(record);
+    }
+}
+
 pub(crate) async fn get_config(
+    req: HttpRequest,
+    a: web::Query<ConfigWebParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let start = Instant::now();
+    let key = a
+        .to_confirmed_param()
+        .ok()
+        .map(|p| ConfigKey::new(&p.data_id, &p.group, &p.tenant));
+    let mut res = do_get_config(&req, a, &appdata).await;
+    if let Some(key) = key {
+        if appdata.config_access_logger.is_enabled() {
+            log_config_access(
+                &req,
+                &appdata,
+                std::slice::from_ref(&key),
+                res.status().as_u16(),
+                start,
+            );
+        }
+        if res.status() == StatusCode::OK {
+            add_related_config_links(&req, &appdata, &key, &mut res);
+        }
+    }
+    res
+}
+
+///
+/// 采样记录一起读取的配置;HTTP/2请求时以preload链接返回同一命名空间经常一起读取的配置,
+/// 没有配置读权限时不返回。
+/// actix-web不支持发送PUSH_PROMISE,由支持的代理或客户端按链接提前获取
+fn add_related_config_links(
+    req: &HttpRequest,
+    appdata: &AppShareData,
+    key: &ConfigKey,
+    res: &mut HttpResponse,
+) {
+    if let Some(client_ip) = req.connection_info().realip_remote_addr() {
+        appdata
+            .config_stats
+            .sample_co_access(client_ip, key, now_millis_i64());
+    }
+    if req.version() != Version::HTTP_2 || !has_config_read_permission(req) {
+        return;
+    }
+    for related in appdata
+        .config_stats
+        .co_accessed_with(key, CO_ACCESS_PUSH_LIMIT)
+    {
+        let mut query = vec![
+            ("dataId", related.data_id.as_str()),
+            ("group", related.group.as_str()),
+        ];
+        if !related.tenant.is_empty() {
+            query.push(("tenant", related.tenant.as_str()));
+        }
+        let link = format!(
+            "<{}?{}>; rel=preload; as=fetch",
+            req.path(),
+            serde_urlencoded::to_string(&query).unwrap_or_default()
+        );
+        if let Ok(value) = HeaderValue::from_str(&link) {
+            res.headers_mut().append(header::LINK, value);
+        }
+    }
+}
+
+///
+/// 以SSE推送单个配置的变更;format=diff时首个事件为全量内容,之后为统一差异格式的补丁;
+/// 空闲时定时发送注释帧保持连接
+pub(crate) async fn subscribe_config(
     a: web::Query<ConfigWebParams>,
-    config_addr: web::Data<Addr<ConfigActor>>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let format = match a.format.as_deref() {
+        Some(v) => match StreamFormat::new_by_value(v) {
+            Some(format) => format,
+            None => return HttpResponse::BadRequest().body(format!("unsupported format: {}", v)),
+        },
+        None => StreamFormat::Full,
+    };
+    let p = match a.to_confirmed_param() {
+        Ok(p) => p,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+    let config_key = ConfigKey::new(&p.data_id, &p.group, &p.tenant);
+    if let Err(err) = config_key.is_valid() {
+        return HttpResponse::BadRequest().body(err.to_string());
+    }
+    let config_key = appdata
+        .config_read_view
+        .resolve_alias(&config_key)
+        .unwrap_or(config_key);
+    let (tx, rx) = tokio::sync::mpsc::channel(16);
+    actix_web::rt::spawn(run_config_stream(
+        appdata.get_ref().clone(),
+        config_key,
+        format,
+        tx,
+    ));
+    let stream = ReceiverStream::new(rx)
+        .map(|frame| Ok::<_, actix_web::Error>(web::Bytes::from(frame.to_sse())));
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        //避免被压缩中间件缓冲
+        .insert_header((header::CONTENT_ENCODING, "identity"))
+        .streaming(stream)
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureFlagParams {
+    pub group: Option<String>,
+    pub tenant: Option<String>,
+    //用于灰度分桶与ip白名单,未设置时使用请求来源ip
+    pub client_ip: Option<String>,
+}
+
+///
+/// 在服务端计算feature_flag类型配置的开关状态
+pub(crate) async fn get_feature_flag(
+    req: HttpRequest,
+    path: web::Path<String>,
+    a: web::Query<FeatureFlagParams>,
+    appdata: web::Data<Arc<AppShareData>>,
 ) -> impl Responder {
+    let group = a
+        .group
+        .as_deref()
+        .filter(|v| !v.is_empty())
+        .unwrap_or("DEFAULT_GROUP");
+    let tenant = ConfigUtils::default_tenant(a.tenant.clone().unwrap_or_default());
+    let config_key = ConfigKey::new(&path, group, &tenant);
+    if let Err(err) = config_key.is_valid() {
+        return HttpResponse::BadRequest().body(err.to_string());
+    }
+    let value = match appdata.load_config(&config_key).await {
+        Ok(Some(v)) => v,
+        Ok(None) => return HttpResponse::NotFound().body("config data not exist"),
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+    if value.config_type.as_deref().map(|v| v.as_str()) != Some("feature_flag") {
+        return HttpResponse::BadRequest().body("config type is not feature_flag");
+    }
+    let flag = match FeatureFlag::parse(&value.content) {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::InternalServerError()
+                .body(format!("invalid feature_flag content: {}", err));
+        }
+    };
+    let client_ip = match &a.client_ip {
+        Some(v) if !v.is_empty() => Some(v.to_owned()),
+        _ => req
+            .connection_info()
+            .realip_remote_addr()
+            .map(|v| v.to_owned()),
+    };
+    HttpResponse::Ok().json(flag.evaluate(&config_key.data_id, &tenant, client_ip.as_deref()))
+}
+
+fn config_ok_builder(
+    default_used: bool,
+    archived: bool,
+    alias_of: &Option<String>,
+) -> HttpResponseBuilder {
+    let mut builder = HttpResponse::Ok();
+    if default_used {
+        builder.insert_header((DEFAULT_USED_HEADER, "true"));
+    }
+    if let Some(alias_of) = alias_of {
+        builder.insert_header((ALIAS_OF_HEADER, alias_of.as_str()));
+    }
+    if archived {
+        builder.insert_header((ARCHIVED_HEADER, "true"));
+    }
+    builder
+}
+
+async fn do_get_config(
+    req: &HttpRequest,
+    a: web::Query<ConfigWebParams>,
+    appdata: &Arc<AppShareData>,
+) -> HttpResponse {
     let param = a.to_confirmed_param();
     match param {
         Ok(p) => {
-            let cmd = ConfigCmd::GET(ConfigKey::new(&p.data_id, &p.group, &p.tenant));
-            match config_addr.send(cmd).await {
-                Ok(res) => {
-                    let r: ConfigResult = res.unwrap();
-                    match r {
-                        ConfigResult::Data {
-                            value: v,
-                            md5,
-                            config_type,
-                            ..
-                        } => HttpResponse::Ok()
-                            .content_type(
-                                config_type
-                                    .map(|v| ConfigType::new_by_value(&v))
-                                    .unwrap_or_default()
-                                    .get_media_type(),
-                            )
-                            .insert_header(("content-md5", md5.as_ref().to_string()))
-                            .body(v.as_ref().as_bytes().to_vec()),
-                        _ => HttpResponse::NotFound().body("config data not exist"),
+            let consistency = match req.headers().get(CONSISTENCY_HEADER) {
+                Some(v) => match v.to_str().ok().and_then(ReadConsistency::new_by_value) {
+                    Some(v) => v,
+                    None => {
+                        return HttpResponse::BadRequest()
+                            .body(format!("invalid {} header", CONSISTENCY_HEADER));
+                    }
+                },
+                None => appdata.sys_config.config_read_consistency,
+            };
+            if let Err(err) = appdata.raft_read_guard.ensure(consistency).await {
+                return read_consistency_error_response(err);
+            }
+            let mut config_key = ConfigKey::new(&p.data_id, &p.group, &p.tenant);
+            //别名按目标配置读取
+            let alias_of = match appdata.config_read_view.resolve_alias(&config_key) {
+                Some(target) => {
+                    let alias_of =
+                        format!("{}/{}/{}", &target.data_id, &target.group, &target.tenant);
+                    config_key = target;
+                    Some(alias_of)
+                }
+                None => None,
+            };
+            let _read_permit = match appdata.config_read_limiter.try_acquire(&config_key) {
+                Some(permit) => permit,
+                None => return read_limited_response(req, appdata, &config_key),
+            };
+            //要求一致性读取时不使用读缓存
+            let max_staleness = a
+                .max_staleness
+                .filter(|_| consistency == ReadConsistency::Local)
+                .map(Duration::from_secs);
+            let cached = max_staleness.and_then(|d| appdata.config_read_cache.get(&config_key, d));
+            let mut default_used = false;
+            let mut archived = false;
+            let value = match cached {
+                Some(v) => v,
+                None => {
+                    //直接读取只读视图,不经过ConfigActor
+                    let value = match appdata.config_read_view.get(&config_key) {
+                        Some(v) => v,
+                        None => match get_group_default_config(appdata, &config_key) {
+                            Some((default_key, v)) => {
+                                config_key = default_key;
+                                default_used = true;
+                                v
+                            }
+                            None => return HttpResponse::NotFound().body("config data not exist"),
+                        },
+                    };
+                    let content = match (&value.archive_uri, &appdata.config_archive) {
+                        (Some(archive_uri), Some(store)) => {
+                            archived = true;
+                            match store.load_content(archive_uri).await {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    return HttpResponse::InternalServerError()
+                                        .body(format!("load archived config error: {}", err));
+                                }
+                            }
+                        }
+                        _ => value.content.clone(),
+                    };
+                    let config_type =
+                        match value.config_type.as_ref().or(value.detected_type.as_ref()) {
+                            Some(v) => v.clone(),
+                            None => {
+                                //未设置类型时按内容推断,并缓存到配置中避免每次读取都推断
+                                let detected_type = ConfigType::detect(&content).get_value();
+                                appdata.config_addr.do_send(ConfigCmd::SetDetectedType(
+                                    config_key.clone(),
+                                    value.md5.clone(),
+                                    detected_type.clone(),
+                                ));
+                                detected_type
+                            }
+                        };
+                    let entry = CachedEntry {
+                        content,
+                        md5: value.md5.clone(),
+                        config_type: Some(config_type),
+                        fetched_at: Instant::now(),
+                    };
+                    //默认配置按原key缓存会丢失响应头,不缓存
+                    if max_staleness.is_some() && !default_used {
+                        appdata
+                            .config_read_cache
+                            .put(config_key.clone(), entry.clone());
+                    }
+                    entry
+                }
+            };
+            appdata.config_stats.record_read(&config_key);
+            let content = match appdata.decrypt_config_content(
+                &config_key.tenant,
+                &value.content,
+                value.config_type.as_ref().map(|v| v.as_str()),
+            ) {
+                Ok(v) => v,
+                Err(err) => {
+                    return HttpResponse::InternalServerError()
+                        .body(format!("decrypt config error: {}", err));
+                }
+            };
+            //包含跨命名空间引用时返回替换后内容的md5,监听时按同样方式比较
+            let (content, md5) = match appdata
+                .resolve_served_content(content, value.md5.clone(), a.strict_refs.unwrap_or(false))
+                .await
+            {
+                Ok(v) => v,
+                Err(err) => return HttpResponse::UnprocessableEntity().body(err.to_string()),
+            };
+            let v = &content;
+            let md5 = &md5;
+            let min_compress_bytes = appdata.sys_config.min_compress_bytes;
+            let config_type = value
+                .config_type
+                .as_ref()
+                .map(|v| ConfigType::new_by_value(v))
+                .unwrap_or_default();
+            let rendered = match &a.render {
+                Some(render) => {
+                    let engine = match RenderEngine::new_by_value(render) {
+                        Some(v) => v,
+                        None => {
+                            return HttpResponse::BadRequest()
+                                .body(format!("unsupported render: {}", render));
+                        }
+                    };
+                    //渲染结果与请求参数相关,content-md5仍为配置的md5,与监听时的比较保持一致
+                    match render_config(req, appdata, &config_key, engine, v).await {
+                        Ok(content) => Some(content),
+                        Err(err) => {
+                            return HttpResponse::InternalServerError().body(err.to_string())
+                        }
+                    }
+                }
+                None => None,
+            };
+            //同时指定render时在渲染结果上选取;content-md5仍为完整配置的md5,监听按完整配置进行
+            if let Some(json_path) = a.json_path.as_deref().filter(|v| !v.is_empty()) {
+                return match select_json_path(rendered.as_deref().unwrap_or(v), json_path) {
+                    Ok(Some(content)) => {
+                        let mut builder = config_ok_builder(default_used, archived, &alias_of);
+                        builder.content_type(ConfigType::Json.get_media_type());
+                        build_config_body(req, builder, md5, content.as_bytes(), min_compress_bytes)
+                    }
+                    Ok(None) => HttpResponse::NotFound().body("json path not match"),
+                    Err(err) => HttpResponse::UnprocessableEntity().body(err.to_string()),
+                };
+            }
+            if let Some(content) = rendered {
+                let mut builder = config_ok_builder(default_used, archived, &alias_of);
+                builder.content_type(config_type.get_media_type());
+                return build_config_body(
+                    req,
+                    builder,
+                    md5,
+                    content.as_bytes(),
+                    min_compress_bytes,
+                );
+            }
+            if a.coerce.unwrap_or(false) && config_type == ConfigType::Properties {
+                return match ConfigTranscoder::coerce_properties_with_cache(&config_key, md5, v) {
+                    Ok(content) => {
+                        let mut builder = config_ok_builder(default_used, archived, &alias_of);
+                        builder.content_type(ConfigType::Json.get_media_type());
+                        build_config_body(req, builder, md5, content.as_bytes(), min_compress_bytes)
                     }
+                    Err(err) => HttpResponse::UnprocessableEntity().body(err.to_string()),
+                };
+            }
+            let accept = req
+                .headers()
+                .get(header::ACCEPT)
+                .and_then(|v| v.to_str().ok());
+            if let Some(version) = accept.and_then(ApiVersion::parse_accept) {
+                let entry = ConfigEntry {
+                    key: config_key,
+                    content: v.clone(),
+                    md5: md5.clone(),
+                    config_type: value.config_type.clone(),
+                };
+                return match version.to_body(&entry) {
+                    Ok(body) => {
+                        let mut builder = config_ok_builder(default_used, archived, &alias_of);
+                        match version {
+                            ApiVersion::V1 => builder.content_type(config_type.get_media_type()),
+                            ApiVersion::V2 => builder.content_type(version.media_type()),
+                        };
+                        build_config_body(req, builder, md5, &body, min_compress_bytes)
+                    }
+                    Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+                };
+            }
+            let accept_type = accept.and_then(|v| ConfigTranscoder::negotiate(v, &config_type));
+            if let Some(accept_type) = accept_type {
+                if ConfigTranscoder::can_transcode(&config_type) {
+                    return match ConfigTranscoder::transcode_with_cache(
+                        &config_key,
+                        md5,
+                        v,
+                        &config_type,
+                        &accept_type,
+                    ) {
+                        Ok(content) => {
+                            let mut builder = config_ok_builder(default_used, archived, &alias_of);
+                            builder
+                                .content_type(accept_type.get_structured_media_type())
+                                .insert_header((
+                                    "X-Rnacos-Original-Type",
+                                    config_type.get_structured_media_type(),
+                                ));
+                            build_config_body(
+                                req,
+                                builder,
+                                md5,
+                                content.as_bytes(),
+                                min_compress_bytes,
+                            )
+                        }
+                        Err(err) => HttpResponse::NotAcceptable().body(err.to_string()),
+                    };
                 }
-                Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
             }
+            let mut builder = config_ok_builder(default_used, archived, &alias_of);
+            builder.content_type(config_type.get_media_type());
+            build_config_body(req, builder, md5, v.as_bytes(), min_compress_bytes)
         }
         Err(e) => HttpResponse::InternalServerError().body(e),
     }
 }
 
+///
+/// 以允许暴露的环境变量、同group下的渲染上下文配置及ctx_前缀请求参数作为上下文渲染配置
+async fn render_config(
+    req: &HttpRequest,
+    appdata: &Arc<AppShareData>,
+    config_key: &ConfigKey,
+    engine: RenderEngine,
+    content: &str,
+) -> anyhow::Result<String> {
+    let context_key = ConfigKey::new_by_arc(
+        Arc::new(RENDER_CONTEXT_DATA_ID.to_owned()),
+        config_key.group.clone(),
+        config_key.tenant.clone(),
+    );
+    let render_context = appdata.load_config(&context_key).await?;
+    let query: Vec<(String, String)> = serde_urlencoded::from_str(req.query_string())?;
+    let context = ConfigRenderer::build_context(
+        appdata.sys_config.config_render_envs.clone(),
+        render_context.as_ref().map(|v| v.content.as_str()),
+        query,
+    )?;
+    ConfigRenderer::render(engine, content, &context)
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ListenerParams {
     #[serde(rename(serialize = "Listening-Configs", deserialize = "Listening-Configs"))]
@@ -247,16 +1280,238 @@ impl ListenerParams {
     }
 }
 
+///
+/// 配置不存在时复制模板配置创建,需要配置写权限;配置已存在时不修改
+pub(super) async fn create_config_from_template(
+    req: HttpRequest,
+    web::Json(params): web::Json<TemplateCreateParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    if !has_config_write_permission(&req) {
+        return HttpResponse::Forbidden().body("no config write permission");
+    }
+    let config_key = match params.key.to_key() {
+        Ok(v) => v,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+    if let Err(err) = config_key.is_valid() {
+        return HttpResponse::BadRequest().body(err.to_string());
+    }
+    let template = match params.template.as_deref() {
+        Some(v) if !v.is_empty() => v,
+        _ => return HttpResponse::BadRequest().body("template is empty"),
+    };
+    let template_key = ConfigKey::new(
+        template,
+        params
+            .template_group
+            .as_deref()
+            .filter(|v| !v.is_empty())
+            .unwrap_or(config_key.group.as_str()),
+        &config_key.tenant,
+    );
+    match create_from_template(&appdata, &config_key, &template_key, params.ttl).await {
+        Ok(Some((value, created))) => {
+            let mut builder = HttpResponse::Ok();
+            if created {
+                builder.insert_header((CREATED_FROM_TEMPLATE_HEADER, "true"));
+            }
+            builder.json(serde_json::json!({
+                "created": created,
+                "md5": value.md5.as_str(),
+            }))
+        }
+        Ok(None) => HttpResponse::NotFound().body("template config data not exist"),
+        Err(err) => HttpResponse::InternalServerError()
+            .body(format!("create config from template error: {}", err)),
+    }
+}
+
+///
+/// 客户端定时上报正在使用的配置
+pub(super) async fn consumer_heartbeat(
+    req: HttpRequest,
+    web::Json(params): web::Json<ConsumerHeartbeatParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let client_id = params.client_id.unwrap_or_default();
+    if client_id.is_empty() {
+        return HttpResponse::BadRequest().body("clientId is empty");
+    }
+    if params.config_keys.len() > CONSUMER_MAX_CONFIG_KEYS {
+        return HttpResponse::BadRequest().body(format!(
+            "configKeys exceeds the limit {}",
+            CONSUMER_MAX_CONFIG_KEYS
+        ));
+    }
+    let mut config_keys = Vec::with_capacity(params.config_keys.len());
+    let mut config_md5s = HashMap::new();
+    for item in &params.config_keys {
+        match item.key.to_key() {
+            Ok(key) => {
+                if let Some(md5) = item.md5.as_ref().filter(|v| !v.is_empty()) {
+                    config_md5s.insert(key.clone(), Arc::new(md5.to_owned()));
+                }
+                config_keys.push(key);
+            }
+            Err(err) => return HttpResponse::BadRequest().body(err),
+        }
+    }
+    let client_ip = req
+        .connection_info()
+        .realip_remote_addr()
+        .map(|v| Arc::new(v.to_owned()));
+    appdata.config_consumer_registry.heartbeat(ConsumerRecord {
+        client_id: Arc::new(client_id),
+        client_ip,
+        config_keys,
+        config_md5s,
+        last_heartbeat: now_millis_i64(),
+    });
+    HttpResponse::Ok().json(serde_json::json!({
+        "heartbeatIntervalMs": appdata.sys_config.config_consumer_heartbeat_interval_ms,
+    }))
+}
+
 pub(super) async fn listener_config(
-    _req: HttpRequest,
+    req: HttpRequest,
     a: web::Query<ListenerParams>,
     payload: web::Payload,
-    config_addr: web::Data<Addr<ConfigActor>>,
+    appdata: web::Data<Arc<AppShareData>>,
 ) -> impl Responder {
-    let body = match get_req_body(payload).await {
+    if !appdata.config_access_logger.is_enabled() {
+        return do_listener_config(&req, a, payload, &appdata, &mut vec![]).await;
+    }
+    let start = Instant::now();
+    let mut keys = vec![];
+    let res = do_listener_config(&req, a, payload, &appdata, &mut keys).await;
+    log_config_access(&req, &appdata, &keys, res.status().as_u16(), start);
+    res
+}
+
+fn clamp_listener_timeout(timeout: i64) -> i64 {
+    timeout.clamp(10000, 120000) - 500
+}
+
+///
+/// 请求头中提供了客户端标识时,过滤已通过该客户端其它连接通知过的变更
+fn dedup_listener_changes(
+    appdata: &AppShareData,
+    client_id: Option<&Arc<String>>,
+    changes: Vec<ConfigKey>,
+) -> Vec<ConfigKey> {
+    let client_id = match client_id {
+        Some(v) if !changes.is_empty() => v,
+        _ => return changes,
+    };
+    let changes = changes
+        .into_iter()
+        .map(|key| {
+            let md5 = appdata
+                .config_read_view
+                .get_by_alias(&key)
+                .map(|v| v.md5.clone())
+                .unwrap_or_default();
+            (key, md5)
+        })
+        .collect();
+    appdata
+        .config_notify_dedup
+        .filter(client_id, changes, now_millis_i64())
+}
+
+fn listener_client_id(req: &HttpRequest) -> Option<Arc<String>> {
+    req.headers()
+        .get(LISTENER_CLIENT_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(|v| Arc::new(v.to_owned()))
+}
+
+///
+/// 有变更时立即返回,否则等待任一监听项变更或超时
+async fn wait_listener_changes(
+    appdata: &AppShareData,
+    client_id: Option<&Arc<String>>,
+    list: &[ListenerItem],
+    time_out: i64,
+) -> ListenerResult {
+    //先订阅再对比md5,避免对比后到订阅前的变更被遗漏
+    let mut receivers: Vec<_> = list
+        .iter()
+        .map(|item| appdata.config_watch.subscribe_item(item))
+        .collect();
+    for item in list {
+        if item.to_wildcard().is_none() {
+            appdata.config_stats.record_read(&item.key);
+        }
+    }
+    let changes = appdata
+        .filter_ns_ref_changes(
+            list,
+            ConfigWatchManage::diff_changes(
+                &appdata.config_read_view,
+                list,
+                appdata.sys_config.config_group_default_enabled,
+            ),
+        )
+        .await;
+    let mut changes = dedup_listener_changes(appdata, client_id, changes);
+    //同一出口IP后可能有多个客户端,只按Client-Id跟踪确认
+    if let Some(client_id) = client_id {
+        for key in appdata
+            .config_listener_ack
+            .check(client_id, list, now_millis_i64())
+        {
+            if !changes.contains(&key) {
+                changes.push(key);
+            }
+        }
+    }
+    if !changes.is_empty() || time_out <= 0 {
+        return ListenerResult::DATA(changes);
+    }
+    let deadline = Instant::now() + Duration::from_millis(time_out as u64);
+    loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let key = match ConfigWatchManage::wait_changed(receivers, timeout).await {
+            Some(key) => key,
+            None => return ListenerResult::NULL,
+        };
+        receivers = list
+            .iter()
+            .map(|item| appdata.config_watch.subscribe_item(item))
+            .collect();
+        let changes = ConfigWatchManage::diff_changes(
+            &appdata.config_read_view,
+            list,
+            appdata.sys_config.config_group_default_enabled,
+        );
+        let mut changes = appdata.filter_ns_ref_changes(list, changes).await;
+        if changes.is_empty() {
+            changes.push(key);
+        }
+        //变更已通过同一客户端的其它连接通知时继续等待
+        let changes = dedup_listener_changes(appdata, client_id, changes);
+        if !changes.is_empty() {
+            return ListenerResult::DATA(changes);
+        }
+    }
+}
+
+///
+/// keys返回监听的配置,用于记录访问日志
+async fn do_listener_config(
+    req: &HttpRequest,
+    a: web::Query<ListenerParams>,
+    payload: web::Payload,
+    appdata: &Arc<AppShareData>,
+    keys: &mut Vec<ConfigKey>,
+) -> HttpResponse {
+    let body = match get_decoded_req_body(req, payload).await {
         Ok(v) => v,
         Err(err) => {
-            return HttpResponse::InternalServerError().body(err.to_string());
+            return req_body_error_response(err);
         }
     };
     let b = match serde_urlencoded::from_bytes(&body) {
@@ -266,31 +1521,48 @@ pub(super) async fn listener_config(
         }
     };
     let list = a.select_option(&b).to_items();
+    keys.extend(list.iter().map(|v| v.key.clone()));
     if list.is_empty() {
         //println!("listener_config error: listener item len == 0");
         return HttpResponse::NoContent()
             .content_type("text/html; charset=utf-8")
             .body("error:listener empty");
     }
-    let (tx, rx) = tokio::sync::oneshot::channel();
-    let current_time = Local::now().timestamp_millis();
     let mut time_out = 0;
-    if let Some(_timeout) = _req.headers().get("Long-Pulling-Timeout") {
+    if let Some(_timeout) = req.headers().get("Long-Pulling-Timeout") {
         match _timeout.to_str().unwrap().parse::<i64>() {
             Ok(v) => {
-                time_out = current_time + min(max(10000, v), 120000) - 500;
+                time_out = clamp_listener_timeout(v);
             }
             Err(_) => {
                 time_out = 0;
             }
         }
     }
-    //println!("timeout header:{:?},time_out:{}",_req.headers().get("Long-Pulling-Timeout") ,time_out);
-    let cmd = ConfigCmd::LISTENER(list, tx, time_out);
-    let _ = config_addr.send(cmd).await;
-    let res = rx.await.unwrap();
+    let client = Arc::new(
+        req.connection_info()
+            .realip_remote_addr()
+            .unwrap_or_default()
+            .to_owned(),
+    );
+    let client_id = listener_client_id(req);
+    let res = wait_listener_changes(appdata, client_id.as_ref(), &list, time_out).await;
+    let suggested_interval = if time_out > 0 {
+        appdata.config_poll_backoff.record(
+            client_id.as_ref().unwrap_or(&client),
+            keys,
+            matches!(res, ListenerResult::DATA(_)),
+            now_millis_i64(),
+        )
+    } else {
+        None
+    };
+    let ack = &appdata.config_listener_ack;
     let v = match res {
         ListenerResult::DATA(list) => {
+            if let Some(client_id) = &client_id {
+                ack.notified(client_id, &list, now_millis_i64());
+            }
             let mut data = "".to_string();
             for item in list {
                 data += &item.build_key();
@@ -303,7 +1575,273 @@ pub(super) async fn listener_config(
         }
         ListenerResult::NULL => "".to_owned(),
     };
-    HttpResponse::Ok()
-        .content_type("text/html; charset=utf-8")
-        .body(v)
+    let mut builder = HttpResponse::Ok();
+    if let Some(interval) = suggested_interval {
+        builder.insert_header((SUGGESTED_INTERVAL_HEADER, interval.to_string()));
+    }
+    builder.content_type("text/html; charset=utf-8").body(v)
+}
+
+///
+/// json请求体的长轮询,返回有变更的配置;未设置timeout时立即返回
+async fn do_batch_listener(
+    req: &HttpRequest,
+    params: BatchListenerParams,
+    appdata: &AppShareData,
+) -> Result<Vec<ConfigKey>, HttpResponse> {
+    if params.configs.is_empty() {
+        return Err(HttpResponse::BadRequest().body("configs is empty"));
+    }
+    if params.configs.len() > BATCH_LISTENER_MAX_CONFIGS {
+        return Err(HttpResponse::BadRequest().body(format!(
+            "configs exceeds the limit {}",
+            BATCH_LISTENER_MAX_CONFIGS
+        )));
+    }
+    let mut list = Vec::with_capacity(params.configs.len());
+    for item in &params.configs {
+        match item.key.to_key() {
+            Ok(key) => list.push(ListenerItem::new(
+                key,
+                Arc::new(item.md5.clone().unwrap_or_default()),
+            )),
+            Err(err) => return Err(HttpResponse::BadRequest().body(err)),
+        }
+    }
+    let start = Instant::now();
+    let time_out = params.timeout.map(clamp_listener_timeout).unwrap_or(0);
+    let client_id = listener_client_id(req);
+    let changes = match wait_listener_changes(appdata, client_id.as_ref(), &list, time_out).await {
+        ListenerResult::DATA(changes) => changes,
+        ListenerResult::NULL => vec![],
+    };
+    if let Some(client_id) = &client_id {
+        appdata
+            .config_listener_ack
+            .notified(client_id, &changes, now_millis_i64());
+    }
+    if appdata.config_access_logger.is_enabled() {
+        let keys: Vec<ConfigKey> = list.into_iter().map(|v| v.key).collect();
+        log_config_access(req, appdata, &keys, StatusCode::OK.as_u16(), start);
+    }
+    Ok(changes)
+}
+
+fn to_key_item(key: &ConfigKey) -> ConfigKeyItem {
+    ConfigKeyItem {
+        data_id: Some(key.data_id.as_ref().to_owned()),
+        group: Some(key.group.as_ref().to_owned()),
+        tenant: Some(key.tenant.as_ref().to_owned()),
+    }
+}
+
+///
+/// 使用json请求体的长轮询,监听项数量不受url长度限制;未设置timeout时立即返回
+pub(super) async fn batch_listener_config(
+    req: HttpRequest,
+    web::Json(params): web::Json<BatchListenerParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let changes = match do_batch_listener(&req, params, &appdata).await {
+        Ok(v) => v,
+        Err(res) => return res,
+    };
+    let changes: Vec<ConfigKeyItem> = changes.iter().map(to_key_item).collect();
+    HttpResponse::Ok().json(serde_json::json!({ "changes": changes }))
+}
+
+///
+/// 与batch-listener相同的json长轮询,直接返回变更配置及其新md5的数组
+pub(super) async fn json_listener_config(
+    req: HttpRequest,
+    web::Json(params): web::Json<BatchListenerParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let changes = match do_batch_listener(&req, params, &appdata).await {
+        Ok(v) => v,
+        Err(res) => return res,
+    };
+    let changes: Vec<ListenerChangedItem> = changes
+        .iter()
+        .map(|key| ListenerChangedItem {
+            key: to_key_item(key),
+            new_md5: appdata
+                .config_read_view
+                .get_by_alias(key)
+                .map(|v| v.md5.as_ref().to_owned())
+                .unwrap_or_default(),
+        })
+        .collect();
+    HttpResponse::Ok().json(changes)
+}
+
+///
+/// 原子写入多个配置,所有配置在同一个raft提案中提交;
+/// 任一配置md5不一致时整批不写入,写入后再统一通知各配置的监听方
+pub(super) async fn multi_set_config(
+    req: HttpRequest,
+    web::Json(items): web::Json<Vec<MultiSetConfigItem>>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    if items.is_empty() {
+        return HttpResponse::BadRequest().body("configs is empty");
+    }
+    if items.len() > MULTI_SET_MAX_CONFIGS {
+        return HttpResponse::BadRequest().body(format!(
+            "too many configs, max size is {}",
+            MULTI_SET_MAX_CONFIGS
+        ));
+    }
+    let mut reqs = Vec::with_capacity(items.len());
+    for item in items {
+        let key = match item.key.to_key() {
+            Ok(v) => v,
+            Err(err) => return HttpResponse::BadRequest().body(err),
+        };
+        if let Err(err) = key.is_valid() {
+            return HttpResponse::BadRequest().body(err.to_string());
+        }
+        let references = match item.parse_references() {
+            Ok(v) => v,
+            Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+        };
+        let content = match item.content {
+            Some(v) if !v.is_empty() => v,
+            _ => {
+                return HttpResponse::BadRequest()
+                    .body(format!("content is empty: {}", key.build_key()))
+            }
+        };
+        let mut set_req = SetConfigReq::new(key, Arc::new(content));
+        set_req.config_type = item
+            .r#type
+            .as_ref()
+            .map(|v| ConfigType::new_by_value(v).get_value());
+        set_req.references = references;
+        set_req.expected_md5 = item.expected_md5.map(Arc::new);
+        if let Err(response) = validate_set_config(&appdata, &set_req).await {
+            return response;
+        }
+        reqs.push(set_req);
+    }
+    let keys: Vec<ConfigKey> = reqs.iter().map(|v| v.config_key.clone()).collect();
+    let op_user = req
+        .extensions()
+        .get::<Arc<TokenSession>>()
+        .map(|v| v.username.clone());
+    let results = match appdata.config_route.multi_set_config(reqs, op_user).await {
+        Ok(v) => v,
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+    if results
+        .iter()
+        .all(|v| matches!(v, ConfigSetResult::Success))
+    {
+        return HttpResponse::Ok().json(serde_json::json!({ "success": true }));
+    }
+    let results: Vec<serde_json::Value> = keys
+        .iter()
+        .zip(results)
+        .map(|(key, result)| {
+            let (status, current_md5) = match result {
+                ConfigSetResult::Success => ("success", None),
+                ConfigSetResult::Md5Mismatch { current_md5 } => ("md5Mismatch", current_md5),
+                ConfigSetResult::Aborted => ("aborted", None),
+            };
+            serde_json::json!({
+                "dataId": key.data_id.as_str(),
+                "group": key.group.as_str(),
+                "tenant": key.tenant.as_str(),
+                "status": status,
+                "currentMd5": current_md5,
+            })
+        })
+        .collect();
+    HttpResponse::Conflict().json(serde_json::json!({
+        "error": "MD5_MISMATCH",
+        "results": results,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn gzip_config_body() {
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT_ENCODING, "deflate, gzip;q=0.8"))
+            .to_http_request();
+        assert!(accept_gzip(&req));
+        let disabled = TestRequest::default()
+            .insert_header((header::ACCEPT_ENCODING, "gzip;q=0"))
+            .to_http_request();
+        assert!(!accept_gzip(&disabled));
+
+        let body = "a: 1\n".repeat(300);
+        let res = build_config_body(&req, HttpResponse::Ok(), "md5", body.as_bytes(), 1024);
+        assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+        assert_eq!(res.headers().get("content-md5").unwrap(), "md5");
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"md5\"");
+
+        let res = build_config_body(&req, HttpResponse::Ok(), "md5", b"a: 1", 1024);
+        assert!(res.headers().get(header::CONTENT_ENCODING).is_none());
+    }
+
+    #[test]
+    fn parse_references() {
+        let list = parse_config_references(Some("dev##DEFAULT_GROUP##a.yaml, ##g##b.yaml"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(list[0], ConfigKey::new("a.yaml", "DEFAULT_GROUP", "dev"));
+        assert_eq!(list[1], ConfigKey::new("b.yaml", "g", ""));
+        assert!(parse_config_references(Some("a.yaml")).is_err());
+        //group为空的引用不合法
+        assert!(parse_config_references(Some("####a.yaml")).is_err());
+        assert!(parse_config_references(None).unwrap().is_none());
+    }
+
+    #[test]
+    fn listener_changed_item_json() {
+        let item = ListenerChangedItem {
+            key: to_key_item(&ConfigKey::new("app.yaml", "DEFAULT_GROUP", "")),
+            new_md5: "md5".to_owned(),
+        };
+        assert_eq!(
+            serde_json::to_value(&item).unwrap(),
+            serde_json::json!({
+                "dataId": "app.yaml",
+                "group": "DEFAULT_GROUP",
+                "tenant": "",
+                "newMd5": "md5",
+            })
+        );
+    }
+
+    #[test]
+    fn if_match_md5() {
+        let current = Arc::new("def".to_owned());
+        let if_match = IfMatch::parse("\"abc\", \"def\"").unwrap();
+        assert_eq!(
+            if_match,
+            IfMatch::Tags(vec!["abc".to_owned(), "def".to_owned()])
+        );
+        //列表中任一ETag匹配即可
+        assert_eq!(if_match.expected_md5(Some(&current)), Some(current.clone()));
+        assert_eq!(if_match.expected_md5(None), None);
+        //弱校验ETag不匹配
+        let if_match = IfMatch::parse("W/\"def\"").unwrap();
+        assert_eq!(if_match, IfMatch::Tags(vec![]));
+        assert_eq!(if_match.expected_md5(Some(&current)), None);
+        //`*`要求配置已存在
+        let if_match = IfMatch::parse("*").unwrap();
+        assert_eq!(if_match.expected_md5(Some(&current)), Some(current.clone()));
+        assert_eq!(if_match.expected_md5(None), None);
+        assert!(IfMatch::parse(" ").is_none());
+
+        let response = precondition_failed_response("def");
+        assert_eq!(response.status, 412);
+        assert_eq!(response.body, "{\"currentMd5\":\"def\"}");
+    }
 }
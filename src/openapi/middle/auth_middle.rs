@@ -2,6 +2,8 @@ use crate::common::appdata::AppShareData;
 use crate::common::constant::{AUTHORIZATION_HEADER, EMPTY_ARC_STRING};
 use crate::common::datetime_utils;
 use crate::common::model::TokenSession;
+use crate::config::config_federation::{FEDERATION_MD5_PATH, FEDERATION_TOKEN_HEADER};
+use crate::config::config_feed::{CONFIG_FEED_PATH, CONFIG_FEED_TOKEN_HEADER};
 use crate::raft::cache::model::{CacheKey, CacheType, CacheValue};
 use crate::raft::cache::{CacheManager, CacheManagerReq, CacheManagerResult};
 use actix::Addr;
@@ -77,7 +79,12 @@ where
         let enable_auth = self.app_share_data.sys_config.openapi_enable_auth;
         let path = request.path();
         let is_check_path = if enable_auth {
-            API_PATH.is_match(path) && !IGNORE_PATH.contains(&path)
+            //带订阅源只读令牌时由订阅源接口按命名空间校验,带联邦令牌时由配置md5接口校验
+            let checked_by_api = (path == CONFIG_FEED_PATH
+                && request.headers().contains_key(CONFIG_FEED_TOKEN_HEADER))
+                || (path == FEDERATION_MD5_PATH
+                    && request.headers().contains_key(FEDERATION_TOKEN_HEADER));
+            API_PATH.is_match(path) && !IGNORE_PATH.contains(&path) && !checked_by_api
         } else {
             true
         };
@@ -0,0 +1,117 @@
+use crate::common::appdata::AppShareData;
+use crate::common::circuit_breaker::CONFIG_CMD_RECORDED;
+use actix_http::body::EitherBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{dev, http::header, Error, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+use regex::Regex;
+use std::cell::Cell;
+use std::future::{ready, Ready};
+use std::sync::Arc;
+use std::time::Instant;
+
+lazy_static::lazy_static! {
+    //会访问ConfigActor的配置接口
+    pub static ref CONFIG_API_PATH: Regex =
+        Regex::new(r"(?i)^/r?nacos/.*/(cs|configs?)(/|$)").unwrap();
+}
+
+///
+/// ConfigActor熔断打开时,配置接口直接返回503
+#[derive(Clone)]
+pub struct ConfigCircuitBreakerCheck {
+    app_share_data: Arc<AppShareData>,
+}
+
+impl ConfigCircuitBreakerCheck {
+    pub fn new(app_share_data: Arc<AppShareData>) -> Self {
+        Self { app_share_data }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ConfigCircuitBreakerCheck
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ConfigCircuitBreakerCheckMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ConfigCircuitBreakerCheckMiddleware {
+            service: Arc::new(service),
+            app_share_data: self.app_share_data.clone(),
+        }))
+    }
+}
+
+#[derive(Clone)]
+pub struct ConfigCircuitBreakerCheckMiddleware<S> {
+    service: Arc<S>,
+    app_share_data: Arc<AppShareData>,
+}
+
+impl<S, B> Service<ServiceRequest> for ConfigCircuitBreakerCheckMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let breaker = &self.app_share_data.config_circuit_breaker;
+        if !CONFIG_API_PATH.is_match(req.path()) {
+            let res = self.service.call(req);
+            return Box::pin(async move { res.await.map(ServiceResponse::map_into_left_body) });
+        }
+        let now = Instant::now();
+        if breaker.try_acquire(now) {
+            let app_share_data = self.app_share_data.clone();
+            let res = self.service.call(req);
+            //匹配路径的请求不一定访问ConfigActor(如从只读视图读取配置),
+            //没有计入熔断器的请求不能占用半开状态的探测名额
+            return Box::pin(CONFIG_CMD_RECORDED.scope(Cell::new(false), async move {
+                let res = res.await;
+                if !CONFIG_CMD_RECORDED.with(|v| v.get()) {
+                    app_share_data.config_circuit_breaker.release_probe(now);
+                }
+                res.map(ServiceResponse::map_into_left_body)
+            }));
+        }
+        let retry_after = breaker.retry_after().as_secs();
+        Box::pin(async move {
+            let response = HttpResponse::ServiceUnavailable()
+                .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+                .json(serde_json::json!({
+                    "error": "CONFIG_CIRCUIT_OPEN",
+                    "message": "config service is overloaded, retry later",
+                }))
+                .map_into_right_body();
+            let (http_request, _pl) = req.into_parts();
+            Ok(ServiceResponse::new(http_request, response))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_api_path() {
+        assert!(CONFIG_API_PATH.is_match("/nacos/v1/cs/configs"));
+        assert!(CONFIG_API_PATH.is_match("/nacos/v1/console/configs/search"));
+        assert!(CONFIG_API_PATH.is_match("/rnacos/api/console/v2/config/list"));
+        assert!(!CONFIG_API_PATH.is_match("/nacos/v1/ns/instance"));
+        assert!(!CONFIG_API_PATH.is_match("/nacos/actuator/health"));
+    }
+}
@@ -1 +1,3 @@
 pub mod auth_middle;
+pub mod circuit_breaker_middle;
+pub mod request_stats_middle;
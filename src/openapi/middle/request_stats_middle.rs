@@ -0,0 +1,91 @@
+use crate::common::appdata::AppShareData;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{dev, Error};
+use futures_util::future::LocalBoxFuture;
+use regex::Regex;
+use std::future::{ready, Ready};
+use std::sync::Arc;
+
+lazy_static::lazy_static! {
+    //长轮询与订阅请求大部分时间在等待变更,不计入工作线程统计
+    pub static ref LONG_POLL_PATH: Regex =
+        Regex::new(r"(?i)/(listener|batch-listener|listener-json|subscribe)$").unwrap();
+}
+
+///
+/// 统计处理中的请求数、已完成请求数及处理耗时
+#[derive(Clone)]
+pub struct HttpRequestStatsRecord {
+    app_share_data: Arc<AppShareData>,
+}
+
+impl HttpRequestStatsRecord {
+    pub fn new(app_share_data: Arc<AppShareData>) -> Self {
+        Self { app_share_data }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for HttpRequestStatsRecord
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = HttpRequestStatsRecordMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(HttpRequestStatsRecordMiddleware {
+            service: Arc::new(service),
+            app_share_data: self.app_share_data.clone(),
+        }))
+    }
+}
+
+#[derive(Clone)]
+pub struct HttpRequestStatsRecordMiddleware<S> {
+    service: Arc<S>,
+    app_share_data: Arc<AppShareData>,
+}
+
+impl<S, B> Service<ServiceRequest> for HttpRequestStatsRecordMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if LONG_POLL_PATH.is_match(req.path()) {
+            return Box::pin(self.service.call(req));
+        }
+        let guard = self.app_share_data.http_request_stats.start();
+        let res = self.service.call(req);
+        Box::pin(async move {
+            let res = res.await;
+            drop(guard);
+            res
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_poll_path() {
+        assert!(LONG_POLL_PATH.is_match("/nacos/v1/cs/configs/listener"));
+        assert!(LONG_POLL_PATH.is_match("/nacos/v1/cs/configs/batch-listener"));
+        assert!(LONG_POLL_PATH.is_match("/nacos/v1/cs/configs/subscribe"));
+        assert!(!LONG_POLL_PATH.is_match("/nacos/v1/cs/configs"));
+    }
+}
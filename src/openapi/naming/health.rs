@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpResponse, Responder, Scope};
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+use crate::common::web_utils::get_req_body;
+use crate::naming::core::NamingCmd;
+use crate::naming::model::{InstanceShortKey, ServiceKey};
+use crate::naming::NamingUtils;
+use crate::utils::{get_bool_from_string, select_option_by_clone};
+
+pub(super) fn service() -> Scope {
+    web::scope("/health")
+        .service(web::resource("/instance").route(web::put().to(update_instance_health)))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceHealthWebParams {
+    pub namespace_id: Option<String>,
+    pub service_name: Option<String>,
+    pub group_name: Option<String>,
+    pub ip: Option<String>,
+    pub port: Option<u32>,
+    pub healthy: Option<String>,
+    /// 本次健康检查的响应时间(毫秒),服务开启自动权重时用于调整实例权重
+    pub rt: Option<u64>,
+}
+
+impl InstanceHealthWebParams {
+    fn select_option(&self, o: &Self) -> Self {
+        Self {
+            namespace_id: select_option_by_clone(&self.namespace_id, &o.namespace_id),
+            service_name: select_option_by_clone(&self.service_name, &o.service_name),
+            group_name: select_option_by_clone(&self.group_name, &o.group_name),
+            ip: select_option_by_clone(&self.ip, &o.ip),
+            port: select_option_by_clone(&self.port, &o.port),
+            healthy: select_option_by_clone(&self.healthy, &o.healthy),
+            rt: select_option_by_clone(&self.rt, &o.rt),
+        }
+    }
+
+    fn into_cmd(self) -> Result<NamingCmd, String> {
+        let grouped_name = self.service_name.unwrap_or_default();
+        let (mut group_name, service_name) =
+            match NamingUtils::split_group_and_serivce_name(&grouped_name) {
+                Some(v) => v,
+                None => return Err("serivceName is unvaild!".to_owned()),
+            };
+        if let Some(v) = self.group_name {
+            if !v.is_empty() {
+                group_name = v;
+            }
+        }
+        let ip = match self.ip {
+            Some(ip) if !ip.is_empty() => ip,
+            _ => return Err("ip is empty".to_owned()),
+        };
+        let port = match self.port {
+            Some(port) if port > 0 => port,
+            _ => return Err("port is unvaild".to_owned()),
+        };
+        if self.healthy.is_none() {
+            return Err("healthy is empty".to_owned());
+        }
+        let service_key = ServiceKey::new(
+            &NamingUtils::default_namespace(self.namespace_id.unwrap_or_default()),
+            &group_name,
+            &service_name,
+        );
+        Ok(NamingCmd::UpdateHealthy(
+            service_key,
+            InstanceShortKey::new(Arc::new(ip), port),
+            get_bool_from_string(&self.healthy, true),
+            self.rt,
+        ))
+    }
+}
+
+///
+/// 外部健康检查更新实例健康状态,与实例自身心跳区分
+pub async fn update_instance_health(
+    a: web::Query<InstanceHealthWebParams>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let body = match get_req_body(payload).await {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::InternalServerError().body(err.to_string());
+        }
+    };
+    let b = match serde_urlencoded::from_bytes(&body) {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::InternalServerError().body(err.to_string());
+        }
+    };
+    let param = a.select_option(&b);
+    let cmd = match param.into_cmd() {
+        Ok(cmd) => cmd,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    match appdata.naming_addr.send(cmd).await {
+        Ok(Ok(_)) => HttpResponse::Ok().body("ok"),
+        Ok(Err(e)) => HttpResponse::NotFound().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
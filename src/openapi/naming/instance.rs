@@ -29,6 +29,7 @@ pub(super) fn service() -> Scope {
         )
         .service(beat_instance)
         .service(get_instance_list)
+        .service(get_instance_weights)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -553,3 +554,24 @@ pub async fn get_instance_list(
         Err(err) => HttpResponse::InternalServerError().body(err),
     }
 }
+
+///
+/// 查看实例当前有效权重及计算权重使用的响应时间指标
+#[get("/weights")]
+pub async fn get_instance_weights(
+    param: web::Query<InstanceWebQueryListParams>,
+    naming_addr: web::Data<Addr<NamingActor>>,
+) -> impl Responder {
+    if param.service_name.is_none() {
+        return HttpResponse::BadRequest().body("serviceName is empty");
+    }
+    match param.to_clusters_key() {
+        Ok((key, _)) => match naming_addr.send(NamingCmd::QueryInstanceWeights(key)).await {
+            Ok(Ok(NamingResult::InstanceWeights(list))) => HttpResponse::Ok().json(list),
+            Ok(Err(err)) => HttpResponse::InternalServerError().body(err.to_string()),
+            Ok(_) => HttpResponse::InternalServerError().body("error"),
+            Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+        },
+        Err(err) => HttpResponse::BadRequest().body(err),
+    }
+}
@@ -4,6 +4,7 @@ use crate::openapi::constant::NAMING_V1_BASE_PATH;
 use crate::openapi::RouteConf;
 
 mod catalog;
+mod health;
 pub(crate) mod instance;
 mod operator;
 pub(crate) mod service;
@@ -19,4 +20,5 @@ pub fn openapi_v1_route(_conf: RouteConf) -> Scope {
         .service(service::service())
         .service(operator::service())
         .service(catalog::service())
+        .service(health::service())
 }
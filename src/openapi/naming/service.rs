@@ -2,7 +2,7 @@ use actix::Addr;
 use actix_web::{web, HttpResponse, Responder, Scope};
 
 use crate::common::web_utils::get_req_body;
-use crate::naming::api_model::ServiceInfoParam;
+use crate::naming::api_model::{ServiceInfoParam, ServiceSubscribeParam};
 use crate::naming::core::{NamingActor, NamingCmd, NamingResult};
 use crate::naming::model::ServiceKey;
 use crate::naming::NamingUtils;
@@ -19,6 +19,11 @@ pub(super) fn service() -> Scope {
                 .route(web::get().to(query_service)),
         )
         .service(web::resource("/list").route(web::get().to(query_service_list)))
+        .service(
+            web::resource("/subscribe")
+                .route(web::post().to(subscribe_service))
+                .route(web::delete().to(unsubscribe_service)),
+        )
 }
 
 pub async fn query_service(
@@ -131,3 +136,41 @@ pub async fn query_service_list(
         Err(_) => HttpResponse::InternalServerError().body("error"),
     }
 }
+
+///
+/// 订阅服务变更,服务实例变更时通过对应grpc长链接推送
+pub async fn subscribe_service(
+    param: web::Json<ServiceSubscribeParam>,
+    naming_addr: web::Data<Addr<NamingActor>>,
+) -> impl Responder {
+    match param.0.build_listener_item() {
+        Ok((item, connection_id)) => {
+            match naming_addr
+                .send(NamingCmd::Subscribe(vec![item], connection_id))
+                .await
+            {
+                Ok(_) => HttpResponse::Ok().body("ok"),
+                Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+            }
+        }
+        Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+    }
+}
+
+pub async fn unsubscribe_service(
+    param: web::Json<ServiceSubscribeParam>,
+    naming_addr: web::Data<Addr<NamingActor>>,
+) -> impl Responder {
+    match param.0.build_listener_item() {
+        Ok((item, connection_id)) => {
+            match naming_addr
+                .send(NamingCmd::RemoveSubscribe(vec![item], connection_id))
+                .await
+            {
+                Ok(_) => HttpResponse::Ok().body("ok"),
+                Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+            }
+        }
+        Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+    }
+}
@@ -4,7 +4,12 @@ use async_raft_ext::raft::ClientWriteRequest;
 
 use crate::{
     common::appdata::AppShareData,
-    config::core::{ConfigAsyncCmd, ConfigKey},
+    config::{
+        config_health::local_listener_counts,
+        config_propagation::start_local_trace,
+        core::{ConfigAsyncCmd, ConfigKey, ConfigResult},
+        model::{ConfigInUseError, ConfigMd5MismatchError},
+    },
 };
 
 use self::model::{RouterRequest, RouterResponse};
@@ -26,26 +31,82 @@ pub async fn handle_route(
             op_user,
             config_type,
             desc,
+            expected_md5,
+            references,
+            priority,
             extend_info: _,
         } => {
             let config_key: ConfigKey = (&key as &str).into();
-            app.config_addr
+            if let Err(err) = app
+                .config_addr
                 .send(ConfigAsyncCmd::Add {
                     key: config_key,
                     value,
                     op_user,
                     config_type,
                     desc,
+                    expected_md5,
+                    references,
+                    priority,
                 })
-                .await??;
+                .await?
+            {
+                if let Some(e) = err.downcast_ref::<ConfigMd5MismatchError>() {
+                    return Ok(RouterResponse::ConfigMd5Mismatch {
+                        current_md5: e.current_md5.clone(),
+                    });
+                }
+                return Err(err);
+            }
         }
         RouterRequest::ConfigDel {
             key,
+            force,
+            op_user,
             extend_info: _,
         } => {
             let config_key: ConfigKey = (&key as &str).into();
+            if let Err(err) = app
+                .config_addr
+                .send(ConfigAsyncCmd::Delete {
+                    key: config_key,
+                    force,
+                    op_user,
+                })
+                .await?
+            {
+                if let Some(e) = err.downcast_ref::<ConfigInUseError>() {
+                    return Ok(RouterResponse::ConfigInUse {
+                        dependents: e.dependents.clone(),
+                    });
+                }
+                return Err(err);
+            }
+        }
+        RouterRequest::ConfigBatchSet {
+            reqs,
+            op_user,
+            atomic,
+            extend_info: _,
+        } => {
+            if let ConfigResult::BatchResult(results) = app
+                .config_addr
+                .send(ConfigAsyncCmd::BatchAdd {
+                    reqs,
+                    op_user,
+                    atomic,
+                })
+                .await??
+            {
+                return Ok(RouterResponse::ConfigBatchResult { results });
+            }
+        }
+        RouterRequest::ConfigTouch { items } => {
+            app.config_addr.send(ConfigAsyncCmd::Touch(items)).await??;
+        }
+        RouterRequest::ConfigPurgeNamespace { tenant } => {
             app.config_addr
-                .send(ConfigAsyncCmd::Delete(config_key))
+                .send(ConfigAsyncCmd::PurgeNamespace(tenant))
                 .await??;
         }
         RouterRequest::JoinNode {
@@ -76,6 +137,22 @@ pub async fn handle_route(
             let result = app.cache_manager.send(req).await??;
             return Ok(RouterResponse::CacheManagerResult { result });
         }
+        RouterRequest::PendingChangesReq { req } => {
+            let result = app.pending_changes_addr.send(req).await??;
+            return Ok(RouterResponse::PendingChangesResult { result });
+        }
+        RouterRequest::ConfigTraceStart { key, trace_id } => {
+            let trace = start_local_trace(app, key, trace_id).await?;
+            return Ok(RouterResponse::ConfigTraceResult { trace });
+        }
+        RouterRequest::ConfigTraceQuery { trace_id } => {
+            let trace = app.config_propagation_tracer.get(&trace_id);
+            return Ok(RouterResponse::ConfigTraceResult { trace });
+        }
+        RouterRequest::ConfigListenerCounts { tenants } => {
+            let counts = local_listener_counts(app, &tenants).await?;
+            return Ok(RouterResponse::ConfigListenerCountsResult { counts });
+        }
     };
     Ok(RouterResponse::None)
 }
@@ -3,7 +3,13 @@ use std::{collections::HashMap, sync::Arc};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    config::core::ConfigKey,
+    config::{
+        config_notify_debounce::ConfigPriority,
+        config_pending_changes::{PendingChangesCmd, PendingChangesResult},
+        config_propagation::PropagationTrace,
+        core::ConfigKey,
+        model::ConfigSetResult,
+    },
     raft::{
         cache::{CacheLimiterReq, CacheManagerResult},
         db::table::{TableManagerQueryReq, TableManagerReq, TableManagerResult},
@@ -16,13 +22,21 @@ pub enum RouteAddr {
     Unknown,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SetConfigReq {
     pub config_key: ConfigKey,
     pub value: Arc<String>,
     pub op_user: Option<Arc<String>>,
     pub config_type: Option<Arc<String>>,
     pub desc: Option<Arc<String>>,
+    //设置后只有当前md5与之相同时才写入
+    pub expected_md5: Option<Arc<String>>,
+    //依赖的配置,写入前校验是否存在;None时保留原有依赖
+    #[serde(default)]
+    pub references: Option<Vec<ConfigKey>>,
+    //变更通知的优先级;None时保留原有优先级
+    #[serde(default)]
+    pub priority: Option<ConfigPriority>,
     //pub can_route_to_remote: bool,
     //pub extend_info: Option<HashMap<String,String>>,
 }
@@ -35,6 +49,9 @@ impl SetConfigReq {
             op_user: None,
             config_type: None,
             desc: None,
+            expected_md5: None,
+            references: None,
+            priority: None,
         }
     }
 
@@ -49,6 +66,9 @@ impl SetConfigReq {
             op_user: Some(op_user),
             config_type: None,
             desc: None,
+            expected_md5: None,
+            references: None,
+            priority: None,
         }
     }
 }
@@ -56,13 +76,20 @@ impl SetConfigReq {
 #[derive(Clone, Debug)]
 pub struct DelConfigReq {
     pub config_key: ConfigKey,
+    //为true时允许删除被其它配置依赖的配置
+    pub force: bool,
+    pub op_user: Option<Arc<String>>,
     //pub can_route_to_remote: bool,
     //pub extend_info: Option<HashMap<String,String>>,
 }
 
 impl DelConfigReq {
     pub fn new(config_key: ConfigKey) -> Self {
-        Self { config_key }
+        Self {
+            config_key,
+            force: false,
+            op_user: None,
+        }
     }
 }
 
@@ -74,12 +101,37 @@ pub enum RouterRequest {
         op_user: Option<Arc<String>>,
         config_type: Option<Arc<String>>,
         desc: Option<Arc<String>>,
+        #[serde(default)]
+        expected_md5: Option<Arc<String>>,
+        #[serde(default)]
+        references: Option<Vec<ConfigKey>>,
+        #[serde(default)]
+        priority: Option<ConfigPriority>,
         extend_info: HashMap<String, String>,
     },
     ConfigDel {
         key: String,
+        #[serde(default)]
+        force: bool,
+        #[serde(default)]
+        op_user: Option<Arc<String>>,
         extend_info: HashMap<String, String>,
     },
+    ConfigBatchSet {
+        reqs: Vec<SetConfigReq>,
+        op_user: Option<Arc<String>>,
+        #[serde(default)]
+        atomic: bool,
+        extend_info: HashMap<String, String>,
+    },
+    //上报配置最近读取时间
+    ConfigTouch {
+        items: Vec<(ConfigKey, i64)>,
+    },
+    //清除已删除命名空间下的所有配置
+    ConfigPurgeNamespace {
+        tenant: Arc<String>,
+    },
     JoinNode {
         node_id: u64,
         node_addr: Arc<String>,
@@ -93,6 +145,21 @@ pub enum RouterRequest {
     CacheLimiterReq {
         req: CacheLimiterReq,
     },
+    PendingChangesReq {
+        req: PendingChangesCmd,
+    },
+    //在本节点发起配置推送追踪
+    ConfigTraceStart {
+        key: ConfigKey,
+        trace_id: Arc<String>,
+    },
+    ConfigTraceQuery {
+        trace_id: Arc<String>,
+    },
+    //查询本节点指定命名空间内配置的监听数
+    ConfigListenerCounts {
+        tenants: Vec<Arc<String>>,
+    },
 }
 
 impl From<SetConfigReq> for RouterRequest {
@@ -103,6 +170,9 @@ impl From<SetConfigReq> for RouterRequest {
             op_user: req.op_user,
             config_type: req.config_type,
             desc: req.desc,
+            expected_md5: req.expected_md5,
+            references: req.references,
+            priority: req.priority,
             extend_info: Default::default(),
         }
     }
@@ -112,6 +182,8 @@ impl From<DelConfigReq> for RouterRequest {
     fn from(req: DelConfigReq) -> Self {
         Self::ConfigDel {
             key: req.config_key.build_key(),
+            force: req.force,
+            op_user: req.op_user,
             extend_info: Default::default(),
         }
     }
@@ -128,4 +200,10 @@ pub enum RouterResponse {
     None,
     TableManagerResult { result: TableManagerResult },
     CacheManagerResult { result: CacheManagerResult },
+    ConfigMd5Mismatch { current_md5: Option<Arc<String>> },
+    ConfigInUse { dependents: Vec<ConfigKey> },
+    ConfigBatchResult { results: Vec<ConfigSetResult> },
+    PendingChangesResult { result: PendingChangesResult },
+    ConfigTraceResult { trace: Option<PropagationTrace> },
+    ConfigListenerCountsResult { counts: Vec<(ConfigKey, usize)> },
 }
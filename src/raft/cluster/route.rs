@@ -2,9 +2,10 @@ use std::{fmt::Debug, sync::Arc};
 
 use actix::prelude::*;
 
+use crate::config::model::{ConfigInUseError, ConfigMd5MismatchError, ConfigSetResult};
 use crate::raft::filestore::core::FileStore;
 use crate::{
-    config::core::{ConfigActor, ConfigAsyncCmd, ConfigCmd},
+    config::core::{ConfigActor, ConfigAsyncCmd, ConfigCmd, ConfigKey, ConfigResult},
     grpc::PayloadUtils,
     raft::{network::factory::RaftClusterRequestSender, NacosRaft},
 };
@@ -55,6 +56,8 @@ pub struct ConfigRoute {
     config_addr: Addr<ConfigActor>,
     raft_addr_route: Arc<RaftAddrRouter>,
     cluster_sender: Arc<RaftClusterRequestSender>,
+    //单机模式配置写入不经过raft,总是由本节点处理
+    standalone: bool,
 }
 
 impl ConfigRoute {
@@ -62,20 +65,29 @@ impl ConfigRoute {
         config_addr: Addr<ConfigActor>,
         raft_addr_route: Arc<RaftAddrRouter>,
         cluster_sender: Arc<RaftClusterRequestSender>,
+        standalone: bool,
     ) -> Self {
         Self {
             config_addr,
             raft_addr_route,
             cluster_sender,
+            standalone,
         }
     }
 
+    async fn get_route_addr(&self) -> anyhow::Result<RouteAddr> {
+        if self.standalone {
+            return Ok(RouteAddr::Local);
+        }
+        self.raft_addr_route.get_route_addr().await
+    }
+
     fn unknown_err(&self) -> anyhow::Error {
         anyhow::anyhow!("unknown the raft leader addr!")
     }
 
     pub async fn set_config(&self, req: SetConfigReq) -> anyhow::Result<()> {
-        match self.raft_addr_route.get_route_addr().await? {
+        match self.get_route_addr().await? {
             RouteAddr::Local => {
                 let cmd = ConfigAsyncCmd::Add {
                     key: req.config_key,
@@ -83,8 +95,11 @@ impl ConfigRoute {
                     op_user: req.op_user,
                     config_type: req.config_type,
                     desc: req.desc,
+                    expected_md5: req.expected_md5,
+                    references: req.references,
+                    priority: req.priority,
                 };
-                self.config_addr.send(cmd).await?.ok();
+                self.config_addr.send(cmd).await??;
             }
             RouteAddr::Remote(_, addr) => {
                 let source_req = req.clone();
@@ -93,7 +108,10 @@ impl ConfigRoute {
                 let payload = PayloadUtils::build_payload("RaftRouteRequest", request);
                 let resp_payload = self.cluster_sender.send_request(addr, payload).await?;
                 let body_vec = resp_payload.body.unwrap_or_default().value;
-                let _: RouterResponse = serde_json::from_slice(&body_vec)?;
+                let resp: RouterResponse = serde_json::from_slice(&body_vec)?;
+                if let RouterResponse::ConfigMd5Mismatch { current_md5 } = resp {
+                    return Err(ConfigMd5MismatchError { current_md5 }.into());
+                }
                 self.config_addr.do_send(ConfigCmd::SetTmpValue(
                     source_req.config_key,
                     source_req.value,
@@ -106,11 +124,142 @@ impl ConfigRoute {
         Ok(())
     }
 
+    ///
+    /// 多个配置合并为一个raft提案写入
+    pub async fn batch_set_config(
+        &self,
+        reqs: Vec<SetConfigReq>,
+        op_user: Option<Arc<String>>,
+    ) -> anyhow::Result<Vec<ConfigSetResult>> {
+        self.do_batch_set_config(reqs, op_user, false).await
+    }
+
+    ///
+    /// 多个配置原子写入:任一配置md5不一致时整批不写入
+    pub async fn multi_set_config(
+        &self,
+        reqs: Vec<SetConfigReq>,
+        op_user: Option<Arc<String>>,
+    ) -> anyhow::Result<Vec<ConfigSetResult>> {
+        self.do_batch_set_config(reqs, op_user, true).await
+    }
+
+    async fn do_batch_set_config(
+        &self,
+        reqs: Vec<SetConfigReq>,
+        op_user: Option<Arc<String>>,
+        atomic: bool,
+    ) -> anyhow::Result<Vec<ConfigSetResult>> {
+        if reqs.is_empty() {
+            return Ok(vec![]);
+        }
+        let size = reqs.len();
+        let results = match self.get_route_addr().await? {
+            RouteAddr::Local => {
+                let cmd = ConfigAsyncCmd::BatchAdd {
+                    reqs,
+                    op_user,
+                    atomic,
+                };
+                match self.config_addr.send(cmd).await?? {
+                    ConfigResult::BatchResult(results) => results,
+                    _ => vec![ConfigSetResult::Success; size],
+                }
+            }
+            RouteAddr::Remote(_, addr) => {
+                let tmp_values: Vec<_> = reqs
+                    .iter()
+                    .map(|v| (v.config_key.clone(), v.value.clone()))
+                    .collect();
+                let req = RouterRequest::ConfigBatchSet {
+                    reqs,
+                    op_user,
+                    atomic,
+                    extend_info: Default::default(),
+                };
+                let request = serde_json::to_string(&req).unwrap_or_default();
+                let payload = PayloadUtils::build_payload("RaftRouteRequest", request);
+                let resp_payload = self.cluster_sender.send_request(addr, payload).await?;
+                let body_vec = resp_payload.body.unwrap_or_default().value;
+                let resp: RouterResponse = serde_json::from_slice(&body_vec)?;
+                let results = match resp {
+                    RouterResponse::ConfigBatchResult { results } => results,
+                    _ => vec![ConfigSetResult::Success; size],
+                };
+                for ((key, value), result) in tmp_values.into_iter().zip(results.iter()) {
+                    if let ConfigSetResult::Success = result {
+                        self.config_addr.do_send(ConfigCmd::SetTmpValue(key, value));
+                    }
+                }
+                results
+            }
+            RouteAddr::Unknown => {
+                return Err(self.unknown_err());
+            }
+        };
+        Ok(results)
+    }
+
+    ///
+    /// 上报本节点记录的配置最近读取时间,由主节点写入raft
+    pub async fn touch_configs(&self, items: Vec<(ConfigKey, i64)>) -> anyhow::Result<()> {
+        if items.is_empty() {
+            return Ok(());
+        }
+        match self.get_route_addr().await? {
+            RouteAddr::Local => {
+                self.config_addr
+                    .send(ConfigAsyncCmd::Touch(items))
+                    .await??;
+            }
+            RouteAddr::Remote(_, addr) => {
+                let req = RouterRequest::ConfigTouch { items };
+                let request = serde_json::to_string(&req).unwrap_or_default();
+                let payload = PayloadUtils::build_payload("RaftRouteRequest", request);
+                let resp_payload = self.cluster_sender.send_request(addr, payload).await?;
+                let body_vec = resp_payload.body.unwrap_or_default().value;
+                let _: RouterResponse = serde_json::from_slice(&body_vec)?;
+            }
+            RouteAddr::Unknown => {
+                return Err(self.unknown_err());
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// 清除已删除命名空间下的所有配置,由主节点写入一条raft日志
+    pub async fn purge_namespace(&self, tenant: Arc<String>) -> anyhow::Result<()> {
+        match self.get_route_addr().await? {
+            RouteAddr::Local => {
+                self.config_addr
+                    .send(ConfigAsyncCmd::PurgeNamespace(tenant))
+                    .await??;
+            }
+            RouteAddr::Remote(_, addr) => {
+                let req = RouterRequest::ConfigPurgeNamespace { tenant };
+                let request = serde_json::to_string(&req).unwrap_or_default();
+                let payload = PayloadUtils::build_payload("RaftRouteRequest", request);
+                let resp_payload = self.cluster_sender.send_request(addr, payload).await?;
+                let body_vec = resp_payload.body.unwrap_or_default().value;
+                let _: RouterResponse = serde_json::from_slice(&body_vec)?;
+            }
+            RouteAddr::Unknown => {
+                return Err(self.unknown_err());
+            }
+        }
+        Ok(())
+    }
+
     pub async fn del_config(&self, req: DelConfigReq) -> anyhow::Result<()> {
-        match self.raft_addr_route.get_route_addr().await? {
+        match self.get_route_addr().await? {
             RouteAddr::Local => {
-                let cmd = ConfigAsyncCmd::Delete(req.config_key);
-                self.config_addr.send(cmd).await?.ok();
+                let cmd = ConfigAsyncCmd::Delete {
+                    key: req.config_key,
+                    force: req.force,
+                    op_user: req.op_user,
+                };
+                self.config_addr.send(cmd).await??;
             }
             RouteAddr::Remote(_, addr) => {
                 let req: RouterRequest = req.into();
@@ -118,7 +267,10 @@ impl ConfigRoute {
                 let payload = PayloadUtils::build_payload("RaftRouteRequest", request);
                 let resp_payload = self.cluster_sender.send_request(addr, payload).await?;
                 let body_vec = resp_payload.body.unwrap_or_default().value;
-                let _: RouterResponse = serde_json::from_slice(&body_vec)?;
+                let resp: RouterResponse = serde_json::from_slice(&body_vec)?;
+                if let RouterResponse::ConfigInUse { dependents } = resp {
+                    return Err(ConfigInUseError { dependents }.into());
+                }
             }
             RouteAddr::Unknown => {
                 return Err(self.unknown_err());
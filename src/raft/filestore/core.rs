@@ -56,6 +56,27 @@ impl FileStore {
         }
     }
 
+    ///
+    /// 获取最近一次快照对应的日志下标
+    pub async fn get_last_snapshot_index(&self) -> anyhow::Result<u64> {
+        if let RaftSnapshotResponse::LastSnapshot(_, Some(header)) = self
+            .snapshot_manager
+            .send(RaftSnapshotRequest::GetLastSnapshot)
+            .await??
+        {
+            Ok(header.last_index)
+        } else {
+            Ok(0)
+        }
+    }
+
+    ///
+    /// 立即生成快照并压缩日志,返回快照对应的日志下标
+    pub async fn compact_log(&self) -> anyhow::Result<u64> {
+        let snapshot = self.do_log_compaction().await?;
+        Ok(snapshot.index)
+    }
+
     async fn get_last_log_index(&self) -> anyhow::Result<LogIndexInfo> {
         match self
             .log_manager
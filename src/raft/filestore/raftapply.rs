@@ -3,10 +3,12 @@ use std::sync::Arc;
 
 use crate::common::byte_utils::bin_to_id;
 use crate::common::constant::{
-    CACHE_TREE_NAME, CONFIG_TREE_NAME, SEQUENCE_TREE_NAME, SEQ_KEY_CONFIG, USER_TREE_NAME,
+    CACHE_TREE_NAME, CONFIG_DELETE_HISTORY_TREE_NAME, CONFIG_TREE_NAME, SEQUENCE_TREE_NAME,
+    SEQ_KEY_CONFIG, USER_TREE_NAME,
 };
+use crate::config::config_history_index::ConfigDeleteHistory;
 use crate::config::core::{ConfigCmd, ConfigKey};
-use crate::config::model::{ConfigRaftCmd, ConfigValueDO};
+use crate::config::model::{ConfigRaftCmd, ConfigRaftResult, ConfigValueDO};
 use crate::raft::db::table::{TableManagerInnerReq, TableManagerReq};
 use crate::raft::filestore::raftdata::RaftDataWrap;
 use crate::raft::store::{ClientRequest, ClientResponse};
@@ -65,6 +67,10 @@ impl LogRecordLoader for LogRecordLoaderInstance {
                     history_table_id,
                     op_time,
                     op_user,
+                    expected_md5,
+                    references,
+                    priority,
+                    max_versions,
                 } => {
                     let cmd = ConfigRaftCmd::ConfigAdd {
                         key,
@@ -75,11 +81,59 @@ impl LogRecordLoader for LogRecordLoaderInstance {
                         history_table_id,
                         op_time,
                         op_user,
+                        expected_md5,
+                        references,
+                        priority,
+                        max_versions,
                     };
                     self.data_wrap.config.do_send(cmd);
                 }
-                ClientRequest::ConfigRemove { key } => {
-                    let cmd = ConfigRaftCmd::ConfigRemove { key };
+                ClientRequest::ConfigRemove {
+                    key,
+                    op_time,
+                    op_user,
+                } => {
+                    let cmd = ConfigRaftCmd::ConfigRemove {
+                        key,
+                        op_time,
+                        op_user,
+                    };
+                    self.data_wrap.config.do_send(cmd);
+                }
+                ClientRequest::ConfigBatchSet {
+                    items,
+                    op_time,
+                    op_user,
+                    atomic,
+                    max_versions,
+                } => {
+                    let cmd = ConfigRaftCmd::ConfigBatchAdd {
+                        items,
+                        op_time,
+                        op_user,
+                        atomic,
+                        max_versions,
+                    };
+                    self.data_wrap.config.do_send(cmd);
+                }
+                ClientRequest::ConfigArchive {
+                    key,
+                    md5,
+                    archive_uri,
+                } => {
+                    let cmd = ConfigRaftCmd::ConfigArchive {
+                        key,
+                        md5,
+                        archive_uri,
+                    };
+                    self.data_wrap.config.do_send(cmd);
+                }
+                ClientRequest::ConfigTouch { items } => {
+                    let cmd = ConfigRaftCmd::ConfigTouch { items };
+                    self.data_wrap.config.do_send(cmd);
+                }
+                ClientRequest::ConfigPurgeNamespace { tenant, op_time } => {
+                    let cmd = ConfigRaftCmd::ConfigPurgeNamespace { tenant, op_time };
                     self.data_wrap.config.do_send(cmd);
                 }
                 ClientRequest::TableManagerReq(req) => {
@@ -234,6 +288,12 @@ impl StateApplyManager {
                         .send(ConfigCmd::InnerSetLastId(last_id))
                         .await??;
                 };
+            } else if record.tree.as_str() == CONFIG_DELETE_HISTORY_TREE_NAME.as_str() {
+                let item: ConfigDeleteHistory = serde_json::from_slice(&record.value)?;
+                data_wrap
+                    .config
+                    .send(ConfigCmd::InnerInsertDeleteHistory(item))
+                    .await??;
             } else if record.tree.as_str() == USER_TREE_NAME.as_str() {
                 let key = record.key;
                 let value = record.value;
@@ -261,6 +321,7 @@ impl StateApplyManager {
 
     fn load_log(&mut self, ctx: &mut Context<Self>) {
         if self.last_applied_log == 0 || self.log_manager.is_none() || self.data_wrap.is_none() {
+            self.load_complete();
             return;
         }
         let start_index = self.snapshot_next_index;
@@ -281,10 +342,20 @@ impl StateApplyManager {
             Ok(())
         }
         .into_actor(self)
-        .map(|_r: anyhow::Result<()>, _act, _ctx| {})
+        .map(|_r: anyhow::Result<()>, act, _ctx| {
+            act.load_complete();
+        })
         .wait(ctx);
     }
 
+    ///
+    /// 启动时镜像与日志都已加载到状态机
+    fn load_complete(&self) {
+        if let Some(data_wrap) = &self.data_wrap {
+            data_wrap.config.do_send(ConfigCmd::PrewarmCache);
+        }
+    }
+
     fn apply_request_to_state_machine(&mut self, request: ApplyRequestDto) -> anyhow::Result<()> {
         //self.last_applied_log = request.index;
         //todo
@@ -312,6 +383,10 @@ impl StateApplyManager {
                 history_table_id,
                 op_time,
                 op_user,
+                expected_md5,
+                references,
+                priority,
+                max_versions,
             } => {
                 if let Some(raft_data_wrap) = &self.data_wrap {
                     let cmd = ConfigRaftCmd::ConfigAdd {
@@ -323,13 +398,69 @@ impl StateApplyManager {
                         history_table_id,
                         op_time,
                         op_user,
+                        expected_md5,
+                        references,
+                        priority,
+                        max_versions,
+                    };
+                    raft_data_wrap.config.do_send(cmd);
+                }
+            }
+            ClientRequest::ConfigRemove {
+                key,
+                op_time,
+                op_user,
+            } => {
+                if let Some(raft_data_wrap) = &self.data_wrap {
+                    let cmd = ConfigRaftCmd::ConfigRemove {
+                        key,
+                        op_time,
+                        op_user,
+                    };
+                    raft_data_wrap.config.do_send(cmd);
+                }
+            }
+            ClientRequest::ConfigBatchSet {
+                items,
+                op_time,
+                op_user,
+                atomic,
+                max_versions,
+            } => {
+                if let Some(raft_data_wrap) = &self.data_wrap {
+                    let cmd = ConfigRaftCmd::ConfigBatchAdd {
+                        items,
+                        op_time,
+                        op_user,
+                        atomic,
+                        max_versions,
+                    };
+                    raft_data_wrap.config.do_send(cmd);
+                }
+            }
+            ClientRequest::ConfigArchive {
+                key,
+                md5,
+                archive_uri,
+            } => {
+                if let Some(raft_data_wrap) = &self.data_wrap {
+                    let cmd = ConfigRaftCmd::ConfigArchive {
+                        key,
+                        md5,
+                        archive_uri,
                     };
                     raft_data_wrap.config.do_send(cmd);
                 }
             }
-            ClientRequest::ConfigRemove { key } => {
+            ClientRequest::ConfigTouch { items } => {
+                if let Some(raft_data_wrap) = &self.data_wrap {
+                    let cmd = ConfigRaftCmd::ConfigTouch { items };
+                    raft_data_wrap.config.do_send(cmd);
+                }
+            }
+            ClientRequest::ConfigPurgeNamespace { tenant, op_time } => {
                 if let Some(raft_data_wrap) = &self.data_wrap {
-                    let cmd = ConfigRaftCmd::ConfigRemove { key };
+                    let cmd = ConfigRaftCmd::ConfigPurgeNamespace { tenant, op_time };
                     raft_data_wrap.config.do_send(cmd);
                 }
             }
@@ -370,6 +501,10 @@ impl StateApplyManager {
                 history_table_id,
                 op_time,
                 op_user,
+                expected_md5,
+                references,
+                priority,
+                max_versions,
             } => {
                 let cmd = ConfigRaftCmd::ConfigAdd {
                     key,
@@ -380,12 +515,72 @@ impl StateApplyManager {
                     history_table_id,
                     op_time,
                     op_user,
+                    expected_md5,
+                    references,
+                    priority,
+                    max_versions,
+                };
+                match raft_data_wrap.config.send(cmd).await?? {
+                    ConfigRaftResult::Md5Mismatch { current_md5 } => {
+                        Ok(ClientResponse::ConfigMd5Mismatch { current_md5 })
+                    }
+                    _ => Ok(ClientResponse::Success),
+                }
+            }
+            ClientRequest::ConfigRemove {
+                key,
+                op_time,
+                op_user,
+            } => {
+                let cmd = ConfigRaftCmd::ConfigRemove {
+                    key,
+                    op_time,
+                    op_user,
+                };
+                raft_data_wrap.config.send(cmd).await??;
+                Ok(ClientResponse::Success)
+            }
+            ClientRequest::ConfigBatchSet {
+                items,
+                op_time,
+                op_user,
+                atomic,
+                max_versions,
+            } => {
+                let cmd = ConfigRaftCmd::ConfigBatchAdd {
+                    items,
+                    op_time,
+                    op_user,
+                    atomic,
+                    max_versions,
+                };
+                match raft_data_wrap.config.send(cmd).await?? {
+                    ConfigRaftResult::BatchResult(results) => {
+                        Ok(ClientResponse::ConfigBatchResult(results))
+                    }
+                    _ => Ok(ClientResponse::Success),
+                }
+            }
+            ClientRequest::ConfigArchive {
+                key,
+                md5,
+                archive_uri,
+            } => {
+                let cmd = ConfigRaftCmd::ConfigArchive {
+                    key,
+                    md5,
+                    archive_uri,
                 };
                 raft_data_wrap.config.send(cmd).await??;
                 Ok(ClientResponse::Success)
             }
-            ClientRequest::ConfigRemove { key } => {
-                let cmd = ConfigRaftCmd::ConfigRemove { key };
+            ClientRequest::ConfigTouch { items } => {
+                let cmd = ConfigRaftCmd::ConfigTouch { items };
+                raft_data_wrap.config.send(cmd).await??;
+                Ok(ClientResponse::Success)
+            }
+            ClientRequest::ConfigPurgeNamespace { tenant, op_time } => {
+                let cmd = ConfigRaftCmd::ConfigPurgeNamespace { tenant, op_time };
                 raft_data_wrap.config.send(cmd).await??;
                 Ok(ClientResponse::Success)
             }
@@ -1,6 +1,10 @@
+use std::sync::Arc;
+use std::time::Duration;
+
 use crate::raft::filestore::core::FileStore;
 use async_raft_ext::raft::ClientWriteRequest;
 use async_raft_ext::{Raft, RaftStorage};
+use serde::{Deserialize, Serialize};
 
 use self::network::core::RaftRouter;
 use self::store::{ClientRequest, ClientResponse};
@@ -10,6 +14,8 @@ pub mod cluster;
 pub mod db;
 pub mod filestore;
 pub mod network;
+pub mod read_lease;
+pub mod split_brain;
 pub mod store;
 
 pub type NacosRaft = Raft<ClientRequest, ClientResponse, RaftRouter, FileStore>;
@@ -35,3 +41,92 @@ pub async fn join_node(
     }
     Ok(())
 }
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RaftLogSizeInfo {
+    pub last_applied: u64,
+    pub last_snapshot: u64,
+    /// 已应用但还未被快照压缩的日志数
+    pub uncommitted_entries: u64,
+}
+
+impl RaftLogSizeInfo {
+    pub fn new(last_applied: u64, last_snapshot: u64) -> Self {
+        Self {
+            last_applied,
+            last_snapshot,
+            uncommitted_entries: last_applied.saturating_sub(last_snapshot),
+        }
+    }
+
+    ///
+    /// 未压缩的日志数超过阈值时需要压缩;阈值为0时不按日志数触发
+    pub fn need_compaction(&self, trigger_entries: u64) -> bool {
+        trigger_entries > 0 && self.uncommitted_entries > trigger_entries
+    }
+}
+
+pub async fn get_log_size_info(
+    raft: &NacosRaft,
+    raft_store: &FileStore,
+) -> anyhow::Result<RaftLogSizeInfo> {
+    let last_applied = raft.metrics().borrow().last_applied;
+    let last_snapshot = raft_store.get_last_snapshot_index().await?;
+    Ok(RaftLogSizeInfo::new(last_applied, last_snapshot))
+}
+
+///
+/// 定时检查未压缩的日志数,超过阈值时立即触发快照与日志压缩,不等待raft内部的快照策略
+pub async fn watch_log_compaction(
+    raft: Arc<NacosRaft>,
+    raft_store: Arc<FileStore>,
+    trigger_entries: u64,
+) {
+    if trigger_entries == 0 {
+        return;
+    }
+    let mut interval = tokio::time::interval(Duration::from_secs(10));
+    loop {
+        interval.tick().await;
+        let info = match get_log_size_info(&raft, &raft_store).await {
+            Ok(v) => v,
+            Err(err) => {
+                log::warn!("watch_log_compaction get log size error,{}", err);
+                continue;
+            }
+        };
+        if info.need_compaction(trigger_entries) {
+            log::info!(
+                "raft log compaction triggered,last_applied:{},last_snapshot:{}",
+                info.last_applied,
+                info.last_snapshot
+            );
+            if let Err(err) = raft_store.compact_log().await {
+                log::error!("raft log compaction error,{}", err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_compaction_trigger() {
+        let info = RaftLogSizeInfo::new(25000, 10000);
+        assert_eq!(info.uncommitted_entries, 15000);
+        assert!(info.need_compaction(10000));
+        assert!(!info.need_compaction(15000));
+        assert!(!info.need_compaction(0));
+        //快照下标可能领先于本地已应用的下标
+        let info = RaftLogSizeInfo::new(100, 200);
+        assert_eq!(info.uncommitted_entries, 0);
+        assert!(!info.need_compaction(1));
+        assert_eq!(
+            serde_json::to_value(&info).unwrap(),
+            serde_json::json!({"lastApplied": 100, "lastSnapshot": 200, "uncommittedEntries": 0})
+        );
+    }
+}
@@ -3,13 +3,13 @@ use std::sync::Arc;
 
 use actix_web::web::Data;
 use actix_web::web::Json;
-use actix_web::Responder;
+use actix_web::{HttpResponse, Responder};
 use async_raft_ext::raft::ClientWriteRequest;
 
 use crate::common::appdata::AppShareData;
-use crate::raft::join_node;
 use crate::raft::store::ClientRequest;
 use crate::raft::store::NodeId;
+use crate::raft::{get_log_size_info, join_node};
 
 // --- Cluster management
 
@@ -89,3 +89,30 @@ pub async fn metrics(app: Data<Arc<AppShareData>>) -> actix_web::Result<impl Res
     let metrics = app.raft.metrics().borrow().clone();
     Ok(Json(metrics))
 }
+
+/// Whether writes are fenced because the leader lost the quorum
+//#[get("/fenced")]
+pub async fn fenced(app: Data<Arc<AppShareData>>) -> impl Responder {
+    HttpResponse::Ok().json(app.split_brain_detector.status())
+}
+
+/// Get the raft log size since the last snapshot
+//#[get("/log-size")]
+pub async fn log_size(app: Data<Arc<AppShareData>>) -> impl Responder {
+    match get_log_size_info(&app.raft, &app.raft_store).await {
+        Ok(info) => HttpResponse::Ok().json(info),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+/// Build a snapshot and compact the raft log on demand
+//#[post("/admin/raft/compact")]
+pub async fn compact(app: Data<Arc<AppShareData>>) -> impl Responder {
+    match app.raft_store.compact_log().await {
+        Ok(_) => match get_log_size_info(&app.raft, &app.raft_store).await {
+            Ok(info) => HttpResponse::Ok().json(info),
+            Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+        },
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
@@ -1,6 +1,10 @@
 use actix_web::web;
 
-use crate::{raft::cluster::routeapi, user};
+use crate::{
+    console::{cluster_api, connection_api},
+    raft::cluster::routeapi,
+    user,
+};
 
 use super::{cache, db::kvapi};
 
@@ -23,6 +27,8 @@ pub fn raft_config(config: &mut web::ServiceConfig) {
                     .route(web::post().to(management::change_membership)),
             )
             .service(web::resource("/metrics").route(web::get().to(management::metrics)))
+            .service(web::resource("/log-size").route(web::get().to(management::log_size)))
+            .service(web::resource("/fenced").route(web::get().to(management::fenced)))
             .service(web::resource("/route").route(web::post().to(routeapi::route_request)))
             .service(web::resource("/table/set").route(web::post().to(kvapi::set)))
             .service(web::resource("/table/get").route(web::get().to(kvapi::get)))
@@ -38,4 +44,19 @@ pub fn raft_config(config: &mut web::ServiceConfig) {
             .service(web::resource("/cache/remove").route(web::post().to(cache::api::remove_cache)))
             .service(web::resource("/cache/get").route(web::get().to(cache::api::get_cache))),
     );
+    config.service(
+        web::scope("/nacos/v1/admin")
+            .service(web::resource("/raft/compact").route(web::post().to(management::compact)))
+            .service(
+                web::resource("/connections")
+                    .route(web::get().to(connection_api::query_grpc_connection_detail)),
+            )
+            .service(
+                web::resource("/connections/{id}")
+                    .route(web::delete().to(connection_api::close_grpc_connection)),
+            )
+            .service(
+                web::resource("/thread-pool").route(web::get().to(cluster_api::query_thread_pool)),
+            ),
+    );
 }
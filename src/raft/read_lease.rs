@@ -0,0 +1,176 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_raft_ext::error::ClientReadError;
+use async_raft_ext::State;
+
+use super::NacosRaft;
+
+pub const RAFT_HEARTBEAT_INTERVAL_MS: u64 = 1000;
+pub const RAFT_ELECTION_TIMEOUT_MIN_MS: u64 = 2500;
+pub const RAFT_ELECTION_TIMEOUT_MAX_MS: u64 = 5000;
+
+///
+/// 租约时长,小于最小选举超时并预留时钟漂移
+pub const READ_LEASE_MS: u64 = RAFT_ELECTION_TIMEOUT_MIN_MS * 4 / 5;
+const _: () = assert!(READ_LEASE_MS < RAFT_ELECTION_TIMEOUT_MIN_MS);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReadConsistency {
+    ///
+    /// 直接读取本节点数据
+    #[default]
+    Local,
+    ///
+    /// leader在租约内直接读取,租约过期时确认leader身份并续约
+    Lease,
+    ///
+    /// 每次读取前通过多数派确认leader身份
+    Quorum,
+}
+
+impl ReadConsistency {
+    pub fn new_by_value(v: &str) -> Option<Self> {
+        match v.trim().to_lowercase().as_str() {
+            "local" => Some(Self::Local),
+            "lease" => Some(Self::Lease),
+            "quorum" => Some(Self::Quorum),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReadConsistencyError {
+    #[error("the current node is not the raft leader")]
+    NotLeader(Option<u64>),
+    #[error("{0}")]
+    Raft(String),
+}
+
+///
+/// leader读租约;多数派确认leader身份后的lease时长内,不会有新的leader产生
+pub struct ReadLease {
+    duration: Duration,
+    expire_at: Mutex<Option<Instant>>,
+}
+
+impl ReadLease {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            expire_at: Mutex::new(None),
+        }
+    }
+
+    pub fn is_valid(&self, now: Instant) -> bool {
+        self.expire_at
+            .lock()
+            .ok()
+            .and_then(|v| *v)
+            .map(|v| now < v)
+            .unwrap_or(false)
+    }
+
+    ///
+    /// 以发起确认的时间计算租约,避免确认耗时延长租约
+    pub fn renew(&self, confirm_start: Instant) {
+        if let Ok(mut v) = self.expire_at.lock() {
+            let expire_at = confirm_start + self.duration;
+            if v.map(|old| old < expire_at).unwrap_or(true) {
+                *v = Some(expire_at);
+            }
+        }
+    }
+
+    pub fn invalidate(&self) {
+        if let Ok(mut v) = self.expire_at.lock() {
+            *v = None;
+        }
+    }
+}
+
+pub struct RaftReadGuard {
+    raft: Arc<NacosRaft>,
+    lease: ReadLease,
+}
+
+impl RaftReadGuard {
+    pub fn new(raft: Arc<NacosRaft>) -> Self {
+        Self {
+            raft,
+            lease: ReadLease::new(Duration::from_millis(READ_LEASE_MS)),
+        }
+    }
+
+    ///
+    /// 按一致性级别确认当前节点可以直接返回本地数据
+    pub async fn ensure(&self, consistency: ReadConsistency) -> Result<(), ReadConsistencyError> {
+        match consistency {
+            ReadConsistency::Local => Ok(()),
+            ReadConsistency::Lease => {
+                let metrics = self.raft.metrics().borrow().clone();
+                if metrics.state != State::Leader {
+                    self.lease.invalidate();
+                    return Err(ReadConsistencyError::NotLeader(metrics.current_leader));
+                }
+                if self.lease.is_valid(Instant::now()) {
+                    return Ok(());
+                }
+                self.confirm_leader().await
+            }
+            ReadConsistency::Quorum => self.confirm_leader().await,
+        }
+    }
+
+    async fn confirm_leader(&self) -> Result<(), ReadConsistencyError> {
+        let start = Instant::now();
+        match self.raft.client_read().await {
+            Ok(_) => {
+                self.lease.renew(start);
+                Ok(())
+            }
+            Err(ClientReadError::ForwardToLeader(leader)) => {
+                self.lease.invalidate();
+                Err(ReadConsistencyError::NotLeader(leader))
+            }
+            Err(err) => {
+                self.lease.invalidate();
+                Err(ReadConsistencyError::Raft(err.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_consistency_value() {
+        assert_eq!(
+            ReadConsistency::new_by_value("Lease"),
+            Some(ReadConsistency::Lease)
+        );
+        assert_eq!(
+            ReadConsistency::new_by_value("quorum"),
+            Some(ReadConsistency::Quorum)
+        );
+        assert_eq!(ReadConsistency::new_by_value("strong"), None);
+    }
+
+    #[test]
+    fn read_lease() {
+        let lease = ReadLease::new(Duration::from_millis(100));
+        let start = Instant::now();
+        assert!(!lease.is_valid(start));
+        lease.renew(start);
+        assert!(lease.is_valid(start + Duration::from_millis(99)));
+        assert!(!lease.is_valid(start + Duration::from_millis(100)));
+        //较早发起的确认不会缩短租约
+        lease.renew(start - Duration::from_millis(50));
+        assert!(lease.is_valid(start + Duration::from_millis(99)));
+        lease.invalidate();
+        assert!(!lease.is_valid(start));
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_raft_ext::State;
+use serde::{Deserialize, Serialize};
+
+use super::NacosRaft;
+
+const CHECK_INTERVAL_MS: u64 = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FencedStatus {
+    pub fenced: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("the raft node is fenced, reject write: {0}")]
+pub struct RaftFencedError(pub String);
+
+#[derive(Default)]
+struct DetectorInner {
+    status: FencedStatus,
+    //(停止推进时的last_applied,开始停止推进的时间)
+    stall: Option<(u64, Instant)>,
+}
+
+///
+/// 脑裂检测;节点自认为是leader且存在未提交日志,
+/// 但已应用日志在quorum_timeout内没有推进时,认为已失去多数派,进入只读的fenced状态
+pub struct SplitBrainDetector {
+    quorum_timeout: Duration,
+    inner: Mutex<DetectorInner>,
+}
+
+impl SplitBrainDetector {
+    pub fn new(quorum_timeout: Duration) -> Self {
+        Self {
+            quorum_timeout,
+            inner: Mutex::new(DetectorInner::default()),
+        }
+    }
+
+    pub fn observe(&self, is_leader: bool, last_log_index: u64, last_applied: u64, now: Instant) {
+        let mut inner = match self.inner.lock() {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        if !is_leader || last_log_index <= last_applied {
+            inner.stall = None;
+            Self::unfence(&mut inner);
+            return;
+        }
+        match inner.stall {
+            Some((applied, since)) if applied == last_applied => {
+                if !inner.status.fenced && now.duration_since(since) >= self.quorum_timeout {
+                    let reason = format!(
+                        "leader commit stalled at {} for {}ms with last log index {}",
+                        last_applied,
+                        now.duration_since(since).as_millis(),
+                        last_log_index
+                    );
+                    log::error!("raft split brain suspected, fence writes: {}", &reason);
+                    inner.status = FencedStatus {
+                        fenced: true,
+                        reason: Some(reason),
+                    };
+                }
+            }
+            _ => {
+                inner.stall = Some((last_applied, now));
+                Self::unfence(&mut inner);
+            }
+        }
+    }
+
+    fn unfence(inner: &mut DetectorInner) {
+        if inner.status.fenced {
+            log::warn!("raft commit progress recovered, unfence writes");
+            inner.status = FencedStatus::default();
+        }
+    }
+
+    pub fn status(&self) -> FencedStatus {
+        self.inner
+            .lock()
+            .map(|v| v.status.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn check_write(&self) -> Result<(), RaftFencedError> {
+        let status = self.status();
+        if status.fenced {
+            Err(RaftFencedError(status.reason.unwrap_or_default()))
+        } else {
+            Ok(())
+        }
+    }
+
+    ///
+    /// 定时按raft metrics检测
+    pub fn start_monitor(self: &Arc<Self>, raft: Arc<NacosRaft>) {
+        let detector = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(CHECK_INTERVAL_MS));
+            loop {
+                interval.tick().await;
+                let metrics = raft.metrics().borrow().clone();
+                detector.observe(
+                    metrics.state == State::Leader,
+                    metrics.last_log_index,
+                    metrics.last_applied,
+                    Instant::now(),
+                );
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_brain_fence() {
+        let detector = SplitBrainDetector::new(Duration::from_secs(5));
+        let start = Instant::now();
+        //空闲的leader不会被隔离
+        detector.observe(true, 10, 10, start);
+        detector.observe(true, 10, 10, start + Duration::from_secs(10));
+        assert!(!detector.status().fenced);
+
+        detector.observe(true, 11, 10, start);
+        detector.observe(true, 12, 10, start + Duration::from_secs(4));
+        assert!(detector.check_write().is_ok());
+        detector.observe(true, 12, 10, start + Duration::from_secs(5));
+        assert!(detector.status().fenced);
+        assert!(detector.check_write().is_err());
+
+        //提交推进后恢复写入
+        detector.observe(true, 12, 11, start + Duration::from_secs(6));
+        assert!(!detector.status().fenced);
+
+        detector.observe(true, 13, 11, start + Duration::from_secs(12));
+        assert!(detector.status().fenced);
+        //不再是leader时解除
+        detector.observe(false, 13, 11, start + Duration::from_secs(13));
+        assert_eq!(detector.status(), FencedStatus::default());
+    }
+}
@@ -409,8 +409,16 @@ impl InnerStore {
                             self.do_send_to_config(cmd);
                             //self.wait_send_config_raft_cmd(cmd,ctx).ok();
                         }
-                        ClientRequest::ConfigRemove { key } => {
-                            let cmd = ConfigRaftCmd::ConfigRemove { key };
+                        ClientRequest::ConfigRemove {
+                            key,
+                            op_time,
+                            op_user,
+                        } => {
+                            let cmd = ConfigRaftCmd::ConfigRemove {
+                                key,
+                                op_time,
+                                op_user,
+                            };
                             self.do_send_to_config(cmd);
                             //self.wait_send_config_raft_cmd(cmd,ctx).ok();
                         }
@@ -10,6 +10,9 @@ use serde::Serialize;
 use thiserror::Error;
 
 use super::db::table::TableManagerReq;
+use crate::config::config_notify_debounce::ConfigPriority;
+use crate::config::core::ConfigKey;
+use crate::config::model::ConfigSetResult;
 
 pub type NodeId = u64;
 
@@ -29,19 +32,80 @@ pub enum ClientRequest {
         history_table_id: Option<u64>,
         op_time: i64,
         op_user: Option<Arc<String>>,
+        #[serde(default)]
+        expected_md5: Option<Arc<String>>,
+        #[serde(default)]
+        references: Option<Vec<ConfigKey>>,
+        #[serde(default)]
+        priority: Option<ConfigPriority>,
+        //历史版本保留数量由提案节点确定,各节点裁剪结果一致;旧日志未记录时使用默认值
+        #[serde(default)]
+        max_versions: Option<usize>,
     },
     ConfigRemove {
         key: String,
+        //删除时间与操作人由提案节点确定,各节点的删除记录保持一致
+        #[serde(default)]
+        op_time: i64,
+        #[serde(default)]
+        op_user: Option<Arc<String>>,
+    },
+    //多个配置写入合并为一个提案,一起生效
+    ConfigBatchSet {
+        items: Vec<ConfigSetItem>,
+        op_time: i64,
+        op_user: Option<Arc<String>>,
+        //为true时任一配置md5不一致则整批不写入,写入后统一通知
+        #[serde(default)]
+        atomic: bool,
+        #[serde(default)]
+        max_versions: Option<usize>,
+    },
+    //配置内容已归档到对象存储,md5未变化时释放内存中的内容
+    ConfigArchive {
+        key: String,
+        md5: Arc<String>,
+        archive_uri: Arc<String>,
+    },
+    //各节点上报的配置最近读取时间,只会推后已记录的时间
+    ConfigTouch {
+        items: Vec<(String, i64)>,
+    },
+    //清除已删除命名空间下的所有配置,整个命名空间只写一条日志
+    ConfigPurgeNamespace {
+        tenant: String,
+        op_time: i64,
     },
     TableManagerReq(TableManagerReq),
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConfigSetItem {
+    pub key: String,
+    pub value: Arc<String>,
+    pub config_type: Option<Arc<String>>,
+    pub desc: Option<Arc<String>>,
+    pub history_id: u64,
+    pub history_table_id: Option<u64>,
+    #[serde(default)]
+    pub expected_md5: Option<Arc<String>>,
+    #[serde(default)]
+    pub references: Option<Vec<ConfigKey>>,
+    #[serde(default)]
+    pub priority: Option<ConfigPriority>,
+    //未设置时使用批次的op_user
+    #[serde(default)]
+    pub op_user: Option<Arc<String>>,
+}
+
 impl AppData for ClientRequest {}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ClientResponse {
     Success,
     Fail,
+    ConfigMd5Mismatch { current_md5: Option<Arc<String>> },
+    ConfigBatchResult(Vec<ConfigSetResult>),
 }
 
 impl Default for ClientResponse {
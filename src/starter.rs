@@ -1,15 +1,57 @@
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::{collections::HashSet, path::Path, sync::Arc, time::Duration};
 
 use crate::common::actor_utils::{create_actor_at_thread, create_actor_at_thread2};
+use crate::common::circuit_breaker::CircuitBreaker;
+use crate::common::thread_pool_stats::{HttpRequestStats, PendingCmdStats};
 use crate::raft::filestore::core::FileStore;
 use crate::raft::filestore::raftapply::StateApplyManager;
 use crate::raft::filestore::raftdata::RaftDataWrap;
 use crate::raft::filestore::raftindex::RaftIndexManager;
 use crate::raft::filestore::raftlog::RaftLogManager;
 use crate::raft::filestore::raftsnapshot::RaftSnapshotManager;
+use crate::raft::read_lease::{
+    RaftReadGuard, RAFT_ELECTION_TIMEOUT_MAX_MS, RAFT_ELECTION_TIMEOUT_MIN_MS,
+    RAFT_HEARTBEAT_INTERVAL_MS,
+};
+use crate::raft::split_brain::SplitBrainDetector;
 use crate::{
-    common::{appdata::AppShareData, AppSysConfig},
-    config::core::ConfigActor,
+    common::{appdata::AppShareData, sys_config_reload::SysConfigReloader, AppSysConfig},
+    config::{
+        config_access_log::ConfigAccessLogger,
+        config_archive::{ArchivalTask, ConfigArchiveStore},
+        config_batch::BatchProposer,
+        config_consumer::ConsumerRegistry,
+        config_event::{new_config_event_bus, ConfigEventSender},
+        config_field_encrypt::ConfigFieldCipher,
+        config_file_watch::ConfigFileWatcher,
+        config_git_sync::GitSyncActor,
+        config_health::ConfigValidationStats,
+        config_idempotency::ConfigIdempotencyCache,
+        config_kafka::ConfigKafkaPublisher,
+        config_kms::{load_master_key, read_encrypted_key, refresh_master_key, KmsProviderType},
+        config_listener_ack::ListenerAckManage,
+        config_namespace_merge::NamespaceMergeStore,
+        config_namespace_stats::NamespaceStatsCache,
+        config_notify_dedup::ListenerNotifyDedup,
+        config_pending_changes::{PendingChangesActor, PendingChangesRoute},
+        config_poll_backoff::ListenerPollBackoff,
+        config_propagation::PropagationTracer,
+        config_propagation_sla::PropagationSlaMonitor,
+        config_read_cache::ConfigReadCache,
+        config_read_limit::ConfigReadLimiter,
+        config_read_view::ConfigReadView,
+        config_search::IndexerActor,
+        config_stats::{
+            run_co_access_recorder, run_touch_reporter, ConfigStats, CONFIG_STATS_FILE,
+            CONFIG_STATS_SAVE_INTERVAL,
+        },
+        config_template_create::run_template_expire_task,
+        config_wal::ConfigWal,
+        config_watch::ConfigWatchManage,
+        config_webhook::ConfigValidationWebhook,
+        core::ConfigActor,
+    },
+    console::{NamespaceUtils, NAMESPACE_PURGE_INTERVAL},
     grpc::{bistream_manage::BiStreamManage, PayloadUtils},
     naming::{
         cluster::{
@@ -60,12 +102,75 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
     let base_path = Arc::new(sys_config.config_db_dir.clone());
     let factory = BeanFactory::new();
     factory.register(BeanDefinition::from_obj(sys_config.clone()));
+    factory.register(BeanDefinition::from_obj(Arc::new(new_config_event_bus())));
+    factory.register(BeanDefinition::from_obj(Arc::new(ConfigReadView::new())));
+    factory.register(BeanDefinition::from_obj(Arc::new(ConfigWatchManage::new())));
+    let config_stats = Arc::new(ConfigStats::new());
+    if sys_config.config_cache_prewarm {
+        let path = Path::new(sys_config.config_db_dir.as_str()).join(CONFIG_STATS_FILE);
+        if let Err(err) = config_stats.load_from_file(&path) {
+            log::warn!("load config stats error: {}", err);
+        }
+    }
+    factory.register(BeanDefinition::from_obj(config_stats));
+    factory.register(BeanDefinition::from_obj(Arc::new(ConfigReadCache::new(
+        sys_config.config_read_cache_capacity,
+        sys_config.config_read_cache_ttl_seconds,
+    ))));
+    factory.register(BeanDefinition::from_obj(Arc::new(PropagationTracer::new())));
+    if let Some(archive) = sys_config.config_archive.as_ref() {
+        factory.register(BeanDefinition::from_obj(Arc::new(ConfigArchiveStore::new(
+            archive,
+        )?)));
+    }
+    //使用外部KMS时启动时解出主密钥,之后按间隔重新解出,加解密字段不访问KMS
+    let kms_config = &sys_config.config_encryption_kms;
+    let encrypted_key = read_encrypted_key(kms_config, &sys_config.config_encryption_key).await?;
+    let master_key = load_master_key(kms_config, &encrypted_key).await?;
+    let config_field_cipher = Arc::new(ConfigFieldCipher::new(master_key));
+    if kms_config.refresh_seconds > 0
+        && (kms_config.key_file.is_some() || kms_config.provider_type()? != KmsProviderType::Env)
+    {
+        tokio::spawn(refresh_master_key(
+            kms_config.clone(),
+            sys_config.config_encryption_key.clone(),
+            config_field_cipher.clone(),
+        ));
+    }
+    factory.register(BeanDefinition::from_obj(config_field_cipher));
+    let config_consumer_registry = Arc::new(ConsumerRegistry::new(
+        sys_config.config_consumer_heartbeat_interval_ms,
+    ));
+    factory.register(BeanDefinition::from_obj(config_consumer_registry.clone()));
+    factory.register(BeanDefinition::from_obj(Arc::new(
+        PropagationSlaMonitor::new(config_consumer_registry),
+    )));
 
+    //单机模式的预写日志打开失败时配置写入仍经过raft
+    let config_wal =
+        sys_config
+            .config_wal
+            .as_ref()
+            .and_then(|v| match ConfigWal::open(&v.wal_dir) {
+                Ok(wal) => Some(wal),
+                Err(err) => {
+                    log::error!("open config wal error, config write use raft: {}", err);
+                    None
+                }
+            });
+    let config_standalone = config_wal.is_some();
     let index_manager = RaftIndexManager::new(base_path.clone());
-    let (index_manager, config_addr) = create_actor_at_thread2(index_manager, ConfigActor::new());
+    let (index_manager, config_addr) =
+        create_actor_at_thread2(index_manager, ConfigActor::new().with_wal(config_wal));
     factory.register(BeanDefinition::actor_with_inject_from_obj::<ConfigActor>(
         config_addr.clone(),
     ));
+    //搜索索引的提交会写磁盘,放在独立线程避免阻塞其它actor
+    factory.register(BeanDefinition::actor_from_obj(create_actor_at_thread(
+        IndexerActor::new(&sys_config.config_db_dir),
+    )));
+    let pending_changes_addr = PendingChangesActor::new().start();
+    factory.register(BeanDefinition::actor_from_obj(pending_changes_addr.clone()));
     let naming_addr = NamingActor::create_at_new_system();
     factory.register(BeanDefinition::actor_with_inject_from_obj(
         naming_addr.clone(),
@@ -110,6 +215,11 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
 
     let raft = build_raft(&sys_config, store.clone(), cluster_sender.clone())?;
     factory.register(BeanDefinition::from_obj(raft.clone()));
+    let split_brain_detector = Arc::new(SplitBrainDetector::new(Duration::from_millis(
+        sys_config.raft_quorum_timeout_ms,
+    )));
+    split_brain_detector.start_monitor(raft.clone());
+    factory.register(BeanDefinition::from_obj(split_brain_detector));
     let table_manage = TableManager::new().start();
     factory.register(BeanDefinition::actor_with_inject_from_obj(
         table_manage.clone(),
@@ -131,8 +241,16 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
         config_addr.clone(),
         raft_addr_router.clone(),
         cluster_sender.clone(),
+        config_standalone,
     ));
     factory.register(BeanDefinition::from_obj(config_route.clone()));
+    let pending_changes_route = Arc::new(PendingChangesRoute::new(
+        pending_changes_addr,
+        raft_addr_router.clone(),
+        cluster_sender.clone(),
+        config_standalone,
+    ));
+    factory.register(BeanDefinition::from_obj(pending_changes_route));
 
     let naming_inner_node_manage_addr =
         InnerNodeManage::new(sys_config.raft_node_id.to_owned()).start();
@@ -152,7 +270,11 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
         naming_cluster_delay_notify_addr.clone(),
     ));
 
-    let bistream_manage_addr = BiStreamManage::new().start();
+    let bistream_manage_addr = BiStreamManage::new_with_keepalive(
+        sys_config.grpc_ping_interval_millis,
+        sys_config.grpc_pong_timeout_millis,
+    )
+    .start();
     factory.register(BeanDefinition::actor_with_inject_from_obj(
         bistream_manage_addr.clone(),
     ));
@@ -187,14 +309,75 @@ pub fn build_share_data(factory_data: FactoryData) -> anyhow::Result<Arc<AppShar
         } else {
             Local::now().offset().fix()
         };
+    let config_event_bus: Arc<ConfigEventSender> = factory_data.get_bean().unwrap();
+    let config_read_view: Arc<ConfigReadView> = factory_data.get_bean().unwrap();
+    let config_watch: Arc<ConfigWatchManage> = factory_data.get_bean().unwrap();
+    let config_stats: Arc<ConfigStats> = factory_data.get_bean().unwrap();
+    let config_propagation_tracer: Arc<PropagationTracer> = factory_data.get_bean().unwrap();
+    let config_indexer: Addr<IndexerActor> = factory_data.get_actor().unwrap();
+    let pending_changes_addr: Addr<PendingChangesActor> = factory_data.get_actor().unwrap();
+    let pending_changes_route: Arc<PendingChangesRoute> = factory_data.get_bean().unwrap();
+    let config_read_cache: Arc<ConfigReadCache> = factory_data.get_bean().unwrap();
+    if sys_config.config_cache_prewarm {
+        let stats = config_stats.clone();
+        let path = Path::new(sys_config.config_db_dir.as_str()).join(CONFIG_STATS_FILE);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(CONFIG_STATS_SAVE_INTERVAL).await;
+                if let Err(err) = stats.save_to_file(&path) {
+                    log::warn!("save config stats error: {}", err);
+                }
+            }
+        });
+    }
+    let config_route: Arc<ConfigRoute> = factory_data.get_bean().unwrap();
+    let raft: Arc<NacosRaft> = factory_data.get_bean().unwrap();
+    let raft_read_guard = Arc::new(RaftReadGuard::new(raft.clone()));
+    let config_batch_proposer = Arc::new(BatchProposer::new(
+        config_route.clone(),
+        sys_config.config_batch_window_ms,
+    ));
+    let config_consumer_registry: Arc<ConsumerRegistry> = factory_data.get_bean().unwrap();
+    let config_propagation_sla: Arc<PropagationSlaMonitor> = factory_data.get_bean().unwrap();
+    let config_access_logger = Arc::new(ConfigAccessLogger::new(
+        sys_config.config_access_log_enabled,
+        sys_config.config_access_log_path.clone(),
+    ));
+    let config_listener_ack = Arc::new(ListenerAckManage::new(
+        sys_config.config_listener_ack_timeout_ms,
+    ));
+    let config_validation_webhook = match sys_config.config_validation_webhook.clone() {
+        Some(webhook) => Some(Arc::new(ConfigValidationWebhook::new(webhook)?)),
+        None => None,
+    };
+    let config_archive: Option<Arc<ConfigArchiveStore>> = factory_data.get_bean();
+    let config_field_cipher: Arc<ConfigFieldCipher> = factory_data.get_bean().unwrap();
+    let config_read_limiter = Arc::new(ConfigReadLimiter::new(
+        sys_config.per_key_read_rate_limit.unwrap_or_default(),
+    ));
+    let sys_config_reloader = Arc::new(SysConfigReloader::new(sys_config.env_file.clone()));
+    let config_kafka_publisher = match sys_config.config_kafka.clone() {
+        Some(kafka) => Some(Arc::new(ConfigKafkaPublisher::new(kafka)?)),
+        None => None,
+    };
+    let config_poll_backoff = Arc::new(ListenerPollBackoff::new(
+        sys_config.config_poll_backoff_base_secs,
+        sys_config.config_poll_backoff_threshold,
+        sys_config.max_poll_interval_secs,
+    ));
+    let config_cmd_pending = Arc::new(PendingCmdStats::new(
+        sys_config.config_cmd_pending_warn_threshold,
+    ));
     let app_data = Arc::new(AppShareData {
         config_addr: factory_data.get_actor().unwrap(),
         naming_addr: factory_data.get_actor().unwrap(),
         bi_stream_manage: factory_data.get_actor().unwrap(),
-        raft: factory_data.get_bean().unwrap(),
+        raft,
+        raft_read_guard,
+        split_brain_detector: factory_data.get_bean().unwrap(),
         raft_store: factory_data.get_bean().unwrap(),
         sys_config,
-        config_route: factory_data.get_bean().unwrap(),
+        config_route,
         cluster_sender: factory_data.get_bean().unwrap(),
         naming_route: factory_data.get_bean().unwrap(),
         naming_inner_node_manage: factory_data.get_actor().unwrap(),
@@ -206,19 +389,82 @@ pub fn build_share_data(factory_data: FactoryData) -> anyhow::Result<Arc<AppShar
         cache_manager: factory_data.get_actor().unwrap(),
         factory_data,
         timezone_offset: Arc::new(timezone_offset),
+        config_event_bus: config_event_bus.as_ref().clone(),
+        config_read_view,
+        config_read_cache,
+        config_watch,
+        config_idempotency_cache: Arc::new(ConfigIdempotencyCache::default()),
+        config_batch_proposer,
+        config_stats,
+        config_indexer,
+        namespace_stats_cache: Arc::new(NamespaceStatsCache::default()),
+        config_circuit_breaker: Arc::new(CircuitBreaker::default()),
+        config_validation_webhook,
+        config_validation_stats: Arc::new(ConfigValidationStats::new()),
+        config_propagation_tracer,
+        namespace_merge_store: Arc::new(NamespaceMergeStore::default()),
+        config_consumer_registry,
+        config_propagation_sla,
+        config_access_logger,
+        config_listener_ack,
+        config_notify_dedup: Arc::new(ListenerNotifyDedup::new()),
+        config_poll_backoff,
+        pending_changes_addr,
+        pending_changes_route,
+        config_archive,
+        config_field_cipher,
+        config_read_limiter,
+        sys_config_reloader,
+        config_kafka_publisher,
+        http_request_stats: Arc::new(HttpRequestStats::default()),
+        config_cmd_pending,
     });
+    if let Some(file_watch) = app_data.sys_config.config_file_watch.clone() {
+        tokio::spawn(ConfigFileWatcher::new(file_watch).run(app_data.clone()));
+    }
+    tokio::spawn(purge_deleted_namespaces(app_data.clone()));
+    tokio::spawn(run_template_expire_task(app_data.clone()));
+    tokio::spawn(SysConfigReloader::run(app_data.clone()));
+    if let Some(git_sync) = app_data.sys_config.config_git_sync.clone() {
+        GitSyncActor::new(git_sync, app_data.clone()).start();
+    }
+    if let Some(publisher) = app_data.config_kafka_publisher.clone() {
+        tokio::spawn(publisher.run(app_data.clone()));
+    }
+    tokio::spawn(run_touch_reporter(app_data.clone()));
+    tokio::spawn(run_co_access_recorder(app_data.config_stats.clone()));
+    if let Some(store) = app_data.config_archive.clone() {
+        if let Some(task) = ArchivalTask::new(app_data.clone(), store) {
+            tokio::spawn(task.run());
+        }
+    }
     Ok(app_data)
 }
 
+///
+/// 定时清除超过保留期的已删除命名空间,只在主节点执行
+async fn purge_deleted_namespaces(app_data: Arc<AppShareData>) {
+    let retention_ms = app_data.sys_config.namespace_retention_days as i64 * 24 * 3600 * 1000;
+    loop {
+        tokio::time::sleep(NAMESPACE_PURGE_INTERVAL).await;
+        if app_data.raft.current_leader().await != Some(app_data.sys_config.raft_node_id) {
+            continue;
+        }
+        if let Err(err) = NamespaceUtils::purge_deleted_namespaces(&app_data, retention_ms).await {
+            log::warn!("purge deleted namespaces error: {}", err);
+        }
+    }
+}
+
 fn build_raft(
     sys_config: &Arc<AppSysConfig>,
     store: Arc<FileStore>,
     cluster_sender: Arc<RaftClusterRequestSender>,
 ) -> anyhow::Result<Arc<NacosRaft>> {
     let config = Config::build("rnacos raft".to_owned())
-        .heartbeat_interval(1000)
-        .election_timeout_min(2500)
-        .election_timeout_max(5000)
+        .heartbeat_interval(RAFT_HEARTBEAT_INTERVAL_MS)
+        .election_timeout_min(RAFT_ELECTION_TIMEOUT_MIN_MS)
+        .election_timeout_max(RAFT_ELECTION_TIMEOUT_MAX_MS)
         .snapshot_policy(async_raft_ext::SnapshotPolicy::LogsSinceLast(
             sys_config.raft_snapshot_log_size,
         ))
@@ -233,6 +479,11 @@ fn build_raft(
         network,
         store.clone(),
     ));
+    tokio::spawn(crate::raft::watch_log_compaction(
+        raft.clone(),
+        store.clone(),
+        sys_config.raft_log_compaction_trigger_entries,
+    ));
     if sys_config.raft_auto_init {
         tokio::spawn(auto_init_raft(store, raft.clone(), sys_config.clone()));
     } else if !sys_config.raft_join_addr.is_empty() {
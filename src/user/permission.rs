@@ -181,6 +181,7 @@ lazy_static::lazy_static! {
         //path
         R::Path("/rnacos/manage/namespace",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/namespaces",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/namespaces/restore",HTTP_METHOD_ALL),
 
         R::Path("/rnacos/api/console/v2/namespaces/add",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/namespaces/update",HTTP_METHOD_ALL),
@@ -207,6 +208,12 @@ lazy_static::lazy_static! {
         R::Path("/rnacos/api/console/v2/user/remove",HTTP_METHOD_ALL),
     ]);
 
+    static ref M_SYSTEM_MANAGE: ModuleResource = ModuleResource::new(vec![
+        //path
+        R::Path("/rnacos/api/console/v2/admin/config",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/audit/export",HTTP_METHOD_GET),
+    ]);
+
     static ref M_CONFIG_VISITOR: ModuleResource = ModuleResource::new(vec![
         //WebResource
         R::WebResource("/manage/configs"),
@@ -220,6 +227,8 @@ lazy_static::lazy_static! {
         R::Path("/rnacos/api/console/configs",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/download",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/cs/configs",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/configs/suggest",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/configs/preview-diff",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/config/history",HTTP_METHOD_GET),
 
         R::Path("/rnacos/api/console/v2/config/list",HTTP_METHOD_GET),
@@ -243,6 +252,8 @@ lazy_static::lazy_static! {
         R::Path("/rnacos/api/console/config/download",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/config/import",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/cs/configs",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/configs/suggest",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/configs/preview-diff",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/config/history",HTTP_METHOD_GET),
 
         R::Path("/rnacos/api/console/v2/config/list",HTTP_METHOD_GET),
@@ -250,6 +261,7 @@ lazy_static::lazy_static! {
         R::Path("/rnacos/api/console/v2/config/info",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/config/history",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/config/import",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/import-url",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/config/add",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/config/update",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/config/remove",HTTP_METHOD_ALL),
@@ -325,6 +337,7 @@ lazy_static::lazy_static! {
         &M_CONFIG_MANAGE,
         &M_NAMING_MANAGE,
         &M_USER_MANAGE,
+        &M_SYSTEM_MANAGE,
     ]));
 
 }
@@ -3,6 +3,11 @@ use actix_web::{web, HttpResponse, Responder};
 use mime_guess::from_path;
 use rnacos_web_dist_wrap::get_embedded_file;
 
+use std::sync::Arc;
+
+use crate::common::appdata::AppShareData;
+use crate::common::circuit_breaker::CircuitState;
+use crate::common::metrics::metrics;
 use crate::common::AppSysConfig;
 use crate::console::api::{console_api_config, console_api_config_new, console_api_config_v2};
 use crate::openapi::auth::{login_config, mock_token};
@@ -78,6 +83,31 @@ async fn disable_no_auth_console_index() -> impl Responder {
     HttpResponse::Ok().content_type("text/html").body(body)
 }
 
+///
+/// 服务健康状态,配置服务熔断打开时返回DOWN
+async fn actuator_health(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    let circuit_state = app.config_circuit_breaker.state();
+    let status = if circuit_state == CircuitState::Open {
+        "DOWN"
+    } else {
+        "UP"
+    };
+    let body = serde_json::json!({
+        "status": status,
+        "components": {
+            "configCircuitBreaker": {
+                "status": status,
+                "details": { "state": circuit_state },
+            },
+        },
+    });
+    if circuit_state == CircuitState::Open {
+        HttpResponse::ServiceUnavailable().json(body)
+    } else {
+        HttpResponse::Ok().json(body)
+    }
+}
+
 ///
 /// 面向SDK的http服务接口
 pub fn app_config(conf_data: AppSysConfig) -> impl FnOnce(&mut ServiceConfig) {
@@ -100,6 +130,9 @@ pub fn app_config(conf_data: AppSysConfig) -> impl FnOnce(&mut ServiceConfig) {
         } else {
             config
         };
+        config.service(web::resource("/nacos/metrics").route(web::get().to(metrics)));
+        config
+            .service(web::resource("/nacos/actuator/health").route(web::get().to(actuator_health)));
         login_config(config);
         raft_config(config);
         console_api_config(config);